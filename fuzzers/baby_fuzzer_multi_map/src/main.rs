@@ -0,0 +1,142 @@
+use std::path::PathBuf;
+
+#[cfg(windows)]
+use std::ptr::write_volatile;
+
+use libafl::{
+    bolts::{current_nanos, rands::StdRand, tuples::tuple_list, AsSlice},
+    corpus::{InMemoryCorpus, OnDiskCorpus, QueueCorpusScheduler},
+    events::SimpleEventManager,
+    executors::{inprocess::InProcessExecutor, ExitKind},
+    feedback_or,
+    feedbacks::{CrashFeedback, MapFeedbackState, MaxMapFeedback},
+    fuzzer::{Fuzzer, StdFuzzer},
+    generators::RandPrintablesGenerator,
+    inputs::{BytesInput, HasTargetBytes},
+    monitors::SimpleMonitor,
+    mutators::scheduled::{havoc_mutations, StdScheduledMutator},
+    observers::StdMapObserver,
+    stages::mutational::StdMutationalStage,
+    state::StdState,
+};
+
+/// Coverage map of the first (simulated) component
+static mut SIGNALS_A: [u8; 16] = [0; 16];
+
+/// Coverage map of the second (simulated) component
+static mut SIGNALS_B: [u8; 16] = [0; 16];
+
+/// Assign a signal to the map of the first component
+fn signals_a_set(idx: usize) {
+    unsafe { SIGNALS_A[idx] = 1 };
+}
+
+/// Assign a signal to the map of the second component
+fn signals_b_set(idx: usize) {
+    unsafe { SIGNALS_B[idx] = 1 };
+}
+
+#[allow(clippy::similar_names)]
+pub fn main() {
+    // The closure that we want to fuzz, calling into two components
+    let mut harness = |input: &BytesInput| {
+        let target = input.target_bytes();
+        let buf = target.as_slice();
+        // The first component parses the header
+        signals_a_set(0);
+        if !buf.is_empty() && buf[0] == b'a' {
+            signals_a_set(1);
+            if buf.len() > 1 && buf[1] == b'b' {
+                signals_a_set(2);
+                // The second component parses the body
+                signals_b_set(0);
+                if buf.len() > 2 && buf[2] == b'c' {
+                    signals_b_set(1);
+                    if buf.len() > 3 && buf[3] == b'd' {
+                        #[cfg(unix)]
+                        panic!("=(");
+
+                        // panic!() raises a STATUS_STACK_BUFFER_OVERRUN exception which cannot be caught by the exception handler.
+                        // Here we make it raise STATUS_ACCESS_VIOLATION instead.
+                        #[cfg(windows)]
+                        unsafe {
+                            write_volatile(0 as *mut u32, 0);
+                        }
+                    }
+                }
+            }
+        }
+        ExitKind::Ok
+    };
+
+    // Create an observation channel for each of the maps
+    let observer_a = StdMapObserver::new("signals_a", unsafe { &mut SIGNALS_A });
+    let observer_b = StdMapObserver::new("signals_b", unsafe { &mut SIGNALS_B });
+
+    // The states of the feedbacks, one for each map, matched by the name of the observer
+    let feedback_state_a = MapFeedbackState::with_observer(&observer_a);
+    let feedback_state_b = MapFeedbackState::with_observer(&observer_b);
+
+    // Feedback to rate the interestingness of an input.
+    // New coverage in any of the maps is interesting.
+    // Use the eager `feedback_or!` so that both history maps get updated for every input.
+    let feedback = feedback_or!(
+        MaxMapFeedback::new(&feedback_state_a, &observer_a),
+        MaxMapFeedback::new(&feedback_state_b, &observer_b)
+    );
+
+    // A feedback to choose if an input is a solution or not
+    let objective = CrashFeedback::new();
+
+    // create a State from scratch
+    let mut state = StdState::new(
+        // RNG
+        StdRand::with_seed(current_nanos()),
+        // Corpus that will be evolved, we keep it in memory for performance
+        InMemoryCorpus::new(),
+        // Corpus in which we store solutions (crashes in this example),
+        // on disk so the user can get them after stopping the fuzzer
+        OnDiskCorpus::new(PathBuf::from("./crashes")).unwrap(),
+        // States of the feedbacks, one for each map.
+        tuple_list!(feedback_state_a, feedback_state_b),
+    );
+
+    // The Monitor trait define how the fuzzer stats are displayed to the user
+    let mon = SimpleMonitor::new(|s| println!("{}", s));
+
+    // The event manager handle the various events generated during the fuzzing loop
+    // such as the notification of the addition of a new item to the corpus
+    let mut mgr = SimpleEventManager::new(mon);
+
+    // A queue policy to get testcasess from the corpus
+    let scheduler = QueueCorpusScheduler::new();
+
+    // A fuzzer with feedbacks and a corpus scheduler
+    let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+
+    // Create the executor for an in-process function with both observers
+    let mut executor = InProcessExecutor::new(
+        &mut harness,
+        tuple_list!(observer_a, observer_b),
+        &mut fuzzer,
+        &mut state,
+        &mut mgr,
+    )
+    .expect("Failed to create the Executor");
+
+    // Generator of printable bytearrays of max size 32
+    let mut generator = RandPrintablesGenerator::new(32);
+
+    // Generate 8 initial inputs
+    state
+        .generate_initial_inputs(&mut fuzzer, &mut executor, &mut generator, &mut mgr, 8)
+        .expect("Failed to generate the initial corpus");
+
+    // Setup a mutational stage with a basic bytes mutator
+    let mutator = StdScheduledMutator::new(havoc_mutations());
+    let mut stages = tuple_list!(StdMutationalStage::new(mutator));
+
+    fuzzer
+        .fuzz_loop(&mut stages, &mut executor, &mut state, &mut mgr)
+        .expect("Error in the fuzzing loop");
+}
@@ -14,7 +14,6 @@ use std::{
 
 use libafl::{
     bolts::{
-        current_nanos,
         launcher::Launcher,
         os::Cores,
         rands::StdRand,
@@ -27,18 +26,19 @@ use libafl::{
         IndexesLenTimeMinimizerCorpusScheduler, OnDiskCorpus, QueueCorpusScheduler,
     },
     events::{llmp::LlmpRestartingEventManager, EventConfig},
-    executors::{inprocess::InProcessExecutor, ExitKind, ShadowExecutor},
+    executors::{inprocess::InProcessExecutor, ExitKind, HasObservers, ShadowExecutor},
     feedback_or, feedback_or_fast,
     feedbacks::{CrashFeedback, MapFeedbackState, MaxMapFeedback, TimeFeedback, TimeoutFeedback},
     fuzzer::{Fuzzer, StdFuzzer},
+    generators::RandBytesGenerator,
     inputs::{BytesInput, HasTargetBytes},
     monitors::MultiMonitor,
     mutators::{
         scheduled::{havoc_mutations, tokens_mutations, StdScheduledMutator},
-        token_mutations::I2SRandReplace,
         token_mutations::Tokens,
+        token_mutations::{I2SRandReplace, I2STokenReplace},
     },
-    observers::{HitcountsMapObserver, StdMapObserver, TimeObserver},
+    observers::{HitcountsMapObserver, ObserversTuple, StdMapObserver, TimeObserver},
     stages::{ShadowTracingStage, StdMutationalStage},
     state::{HasCorpus, HasMetadata, StdState},
     Error,
@@ -139,7 +139,7 @@ struct Opt {
 pub fn main() {
     // Registry the metadata types used in this fuzzer
     // Needed only on no_std
-    //RegistryBuilder::register::<Tokens>();
+    //libafl::register_metadata_types!(Tokens);
 
     let opt = Opt::parse();
     color_backtrace::install();
@@ -169,6 +169,15 @@ pub fn main() {
     }
 }
 
+/// Prints the observers attached to the executor
+fn print_observers<E, I, OT, S>(executor: &E)
+where
+    E: HasObservers<I, OT, S>,
+    OT: ObserversTuple<I, S>,
+{
+    println!("Observers: {}", executor.observers().names().join(", "));
+}
+
 /// The actual fuzzer
 #[allow(clippy::too_many_lines, clippy::too_many_arguments)]
 unsafe fn fuzz(
@@ -190,7 +199,8 @@ unsafe fn fuzz(
 
     let mut run_client = |state: Option<StdState<_, _, _, _, _>>,
                           mut mgr: LlmpRestartingEventManager<_, _, _, _>,
-                          _core_id| {
+                          _core_id,
+                          seed| {
         // The restarting state will spawn the same process again as child, then restarted it each time it crashes.
 
         // println!("{:?}", mgr.mgr_id());
@@ -255,7 +265,7 @@ unsafe fn fuzz(
         let mut state = state.unwrap_or_else(|| {
             StdState::new(
                 // RNG
-                StdRand::with_seed(current_nanos()),
+                StdRand::with_seed(seed),
                 // Corpus that will be evolved, we keep it in memory for performance
                 CachedOnDiskCorpus::new(PathBuf::from("./corpus_discovered"), 64).unwrap(),
                 // Corpus in which we store solutions (crashes in this example),
@@ -324,6 +334,8 @@ unsafe fn fuzz(
             &mut frida_helper,
         );
 
+        print_observers(&executor);
+
         // In case the corpus is empty (on first run), reset
         if state.corpus().count() < 1 {
             state
@@ -332,6 +344,21 @@ unsafe fn fuzz(
             println!("We imported {} inputs from disk.", state.corpus().count());
         }
 
+        // Without seeds, fuzz from nothing: start with random inputs
+        if state.corpus().count() < 1 {
+            let mut generator = RandBytesGenerator::new(32);
+            state
+                .generate_initial_inputs_forced(
+                    &mut fuzzer,
+                    &mut executor,
+                    &mut generator,
+                    &mut mgr,
+                    8,
+                )
+                .expect("Failed to generate the initial corpus");
+            println!("We generated {} initial inputs.", state.corpus().count());
+        }
+
         if frida_options.cmplog_enabled() {
             // Create an observation channel using cmplog map
             let cmplog_observer = CmpLogObserver::new("cmplog", &mut CMPLOG_MAP, true);
@@ -340,9 +367,10 @@ unsafe fn fuzz(
 
             let tracing = ShadowTracingStage::new(&mut executor);
 
-            // Setup a randomic Input2State stage
+            // Setup a randomic Input2State stage, also trying the dictionary tokens at the cmplog positions
             let i2s = StdMutationalStage::new(StdScheduledMutator::new(tuple_list!(
-                I2SRandReplace::new()
+                I2SRandReplace::new(),
+                I2STokenReplace::new()
             )));
 
             // Setup a basic mutator
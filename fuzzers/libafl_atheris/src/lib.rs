@@ -13,7 +13,6 @@ use std::{
 
 use libafl::{
     bolts::{
-        current_nanos,
         launcher::Launcher,
         os::Cores,
         rands::StdRand,
@@ -215,7 +214,7 @@ pub fn LLVMFuzzerRunDriver(
 
     // TODO: we need to handle Atheris calls to `exit` on errors somhow.
 
-    let mut run_client = |state: Option<StdState<_, _, _, _, _>>, mut mgr, _core_id| {
+    let mut run_client = |state: Option<StdState<_, _, _, _, _>>, mut mgr, _core_id, seed| {
         // Create an observation channel using the coverage map
         let edges_observer = unsafe {
             HitcountsMapObserver::new(StdMapObserver::new_from_ptr(
@@ -251,7 +250,7 @@ pub fn LLVMFuzzerRunDriver(
         let mut state = state.unwrap_or_else(|| {
             StdState::new(
                 // RNG
-                StdRand::with_seed(current_nanos()),
+                StdRand::with_seed(seed),
                 // Corpus that will be evolved, we keep it in memory for performance
                 InMemoryCorpus::new(),
                 // Corpus in which we store solutions (crashes in this example),
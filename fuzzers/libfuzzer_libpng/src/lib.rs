@@ -25,6 +25,7 @@ use libafl::{
     fuzzer::{Fuzzer, StdFuzzer},
     inputs::{BytesInput, HasTargetBytes},
     monitors::MultiMonitor,
+    mutators::fixup::FixupMutator,
     mutators::scheduled::{havoc_mutations, tokens_mutations, StdScheduledMutator},
     mutators::token_mutations::Tokens,
     observers::{HitcountsMapObserver, StdMapObserver, TimeObserver},
@@ -38,6 +39,39 @@ use libafl::{
 
 use libafl_targets::{libfuzzer_initialize, libfuzzer_test_one_input, EDGES_MAP, MAX_EDGES_NUM};
 
+/// The CRC-32 of PNG chunks, over the chunk type and data
+fn png_crc(bytes: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 0 {
+                crc >> 1
+            } else {
+                (crc >> 1) ^ 0xedb8_8320
+            };
+        }
+    }
+    !crc
+}
+
+/// Rewrites the CRC of each complete chunk after the PNG signature
+#[allow(clippy::ptr_arg)]
+fn fix_png_crcs(bytes: &mut Vec<u8>) {
+    let mut pos = 8;
+    while pos + 12 <= bytes.len() {
+        let len = u32::from_be_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+            as usize;
+        let crc_pos = match (pos + 8).checked_add(len) {
+            Some(crc_pos) if crc_pos + 4 <= bytes.len() => crc_pos,
+            _ => break,
+        };
+        let crc = png_crc(&bytes[pos + 4..crc_pos]);
+        bytes[crc_pos..crc_pos + 4].copy_from_slice(&crc.to_be_bytes());
+        pos = crc_pos + 4;
+    }
+}
+
 /// The main fn, `no_mangle` as it is a C main
 #[cfg(not(test))]
 #[no_mangle]
@@ -130,7 +164,11 @@ fn fuzz(corpus_dirs: &[PathBuf], objective_dir: PathBuf, broker_port: u16) -> Re
 
     // Setup a basic mutator with a mutational stage
 
-    let mutator = StdScheduledMutator::new(havoc_mutations().merge(tokens_mutations()));
+    // Recompute the chunk CRCs after each mutation, else libpng rejects most mutated inputs
+    let mutator = FixupMutator::new(
+        StdScheduledMutator::new(havoc_mutations().merge(tokens_mutations())),
+        fix_png_crcs,
+    );
 
     let calibration = CalibrationStage::new(&mut state, &edges_observer);
     let power = PowerMutationalStage::new(mutator, PowerSchedule::FAST, &edges_observer);
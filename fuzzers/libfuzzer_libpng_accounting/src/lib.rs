@@ -12,7 +12,6 @@ use std::{env, net::SocketAddr, path::PathBuf};
 
 use libafl::{
     bolts::{
-        current_nanos,
         launcher::Launcher,
         os::Cores,
         rands::StdRand,
@@ -145,7 +144,10 @@ pub fn libafl_main() {
 
     let monitor = MultiMonitor::new(|s| println!("{}", s));
 
-    let mut run_client = |state: Option<StdState<_, _, _, _, _>>, mut restarting_mgr, _core_id| {
+    let mut run_client = |state: Option<StdState<_, _, _, _, _>>,
+                          mut restarting_mgr,
+                          _core_id,
+                          seed| {
         // Create an observation channel using the coverage map
         let edges = unsafe { &mut EDGES_MAP[0..MAX_EDGES_NUM] };
         let edges_observer = HitcountsMapObserver::new(StdMapObserver::new("edges", edges));
@@ -172,7 +174,7 @@ pub fn libafl_main() {
         let mut state = state.unwrap_or_else(|| {
             StdState::new(
                 // RNG
-                StdRand::with_seed(current_nanos()),
+                StdRand::with_seed(seed),
                 // Corpus that will be evolved, we keep it in memory for performance
                 InMemoryCorpus::new(),
                 // Corpus in which we store solutions (crashes in this example),
@@ -5,7 +5,6 @@ use std::{env, path::PathBuf, process};
 
 use libafl::{
     bolts::{
-        current_nanos,
         launcher::Launcher,
         os::Cores,
         rands::StdRand,
@@ -111,7 +110,7 @@ pub fn fuzz() {
         ExitKind::Ok
     };
 
-    let mut run_client = |state: Option<_>, mut mgr, _core_id| {
+    let mut run_client = |state: Option<_>, mut mgr, _core_id, seed| {
         // Create an observation channel using the coverage map
         let edges = unsafe { &mut edges::EDGES_MAP };
         let edges_counter = unsafe { &mut edges::MAX_EDGES_NUM };
@@ -140,7 +139,7 @@ pub fn fuzz() {
         let mut state = state.unwrap_or_else(|| {
             StdState::new(
                 // RNG
-                StdRand::with_seed(current_nanos()),
+                StdRand::with_seed(seed),
                 // Corpus that will be evolved, we keep it in memory for performance
                 InMemoryCorpus::new(),
                 // Corpus in which we store solutions (crashes in this example),
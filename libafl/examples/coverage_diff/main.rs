@@ -0,0 +1,25 @@
+/*!
+Compares the coverage of two campaigns, saved using `MapFeedbackState::save_map`,
+and prints the edges found only by the first, only by the second, and by both, as JSON.
+
+Usage: `coverage_diff <map_a> <map_b>`
+*/
+
+use libafl::{
+    feedbacks::{CoverageComparison, MapFeedbackState},
+    Error,
+};
+
+fn main() -> Result<(), Error> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 3 {
+        println!("Usage: {} <map_a> <map_b>", args[0]);
+        return Ok(());
+    }
+
+    let a = MapFeedbackState::<u8>::load_map(&args[1])?;
+    let b = MapFeedbackState::<u8>::load_map(&args[2])?;
+    let comparison = CoverageComparison::new(&a, &b);
+    println!("{}", serde_json::to_string(&comparison)?);
+    Ok(())
+}
@@ -9,6 +9,8 @@ use std::{
 #[cfg(unix)]
 use std::os::unix::prelude::{AsRawFd, RawFd};
 
+#[cfg(unix)]
+use crate::bolts::AsSlice;
 use crate::Error;
 
 /// The default filename to use to deliver testcases to the target
@@ -127,6 +129,100 @@ impl Drop for OutFile {
     }
 }
 
+/// A file mapped read-only into memory, so that its content does not need to be copied to the heap.
+/// The mapping gets removed on drop.
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct MmapFile {
+    path: PathBuf,
+    map: *const u8,
+    len: usize,
+}
+
+#[cfg(unix)]
+impl MmapFile {
+    /// Maps the file at `path` into memory.
+    /// The file should not get truncated while it is mapped.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn open<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(&path)?;
+        let len = file.metadata()?.len() as usize;
+        let map = if len == 0 {
+            // Empty mappings are not allowed
+            core::ptr::null()
+        } else {
+            let map = unsafe {
+                libc::mmap(
+                    core::ptr::null_mut(),
+                    len,
+                    libc::PROT_READ,
+                    libc::MAP_PRIVATE,
+                    file.as_raw_fd(),
+                    0,
+                )
+            };
+            if map == libc::MAP_FAILED {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            map as *const u8
+        };
+        Ok(Self {
+            path: path.as_ref().to_owned(),
+            map,
+            len,
+        })
+    }
+
+    /// The path of the mapped file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The length of the mapped file
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the mapped file is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+// The mapping is read-only and owned by the `MmapFile`, so it can be shared between threads
+#[cfg(unix)]
+unsafe impl Send for MmapFile {}
+#[cfg(unix)]
+unsafe impl Sync for MmapFile {}
+
+#[cfg(unix)]
+impl AsSlice<u8> for MmapFile {
+    fn as_slice(&self) -> &[u8] {
+        if self.map.is_null() {
+            &[]
+        } else {
+            unsafe { core::slice::from_raw_parts(self.map, self.len) }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for MmapFile {
+    fn drop(&mut self) {
+        if !self.map.is_null() {
+            unsafe {
+                libc::munmap(self.map as *mut libc::c_void, self.len);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::bolts::fs::write_file_atomic;
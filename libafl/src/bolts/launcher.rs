@@ -4,6 +4,14 @@
 //! To use multiple [`Launcher`]`s` for individual configurations,
 //! we can set `spawn_broker` to `false` on all but one.
 //!
+//! Each client gets its own seed for its RNG, derived from the `base_seed` with [`client_seed`].
+//! Setting the `base_seed` makes a campaign reproducible.
+//!
+//! Once all clients stopped on purpose, see [`crate::events::EventRestarter::send_exiting`],
+//! for example after [`crate::Fuzzer::fuzz_loop_for_duration`], the broker exits.
+//! With `stop_all_on_client_exit`, the broker already exits once the first client stops, for example on its first solution,
+//! and with it stops all other clients.
+//!
 //! To connect multiple nodes together via TCP, we can use the `remote_broker_addr`.
 //! (this requires the `llmp_bind_public` compile-time feature for `LibAFL`).
 //!
@@ -16,9 +24,10 @@ use crate::bolts::os::startable_self;
 use crate::bolts::os::{dup2, fork, ForkResult};
 #[cfg(feature = "std")]
 use crate::{
-    bolts::{os::Cores, shmem::ShMemProvider},
+    bolts::{current_nanos, os::Cores, shmem::ShMemProvider},
     events::{EventConfig, LlmpRestartingEventManager, ManagerKind, RestartingMgr},
     inputs::Input,
+    libafl_log,
     monitors::Monitor,
     observers::ObserversTuple,
     Error,
@@ -26,29 +35,159 @@ use crate::{
 
 use core::fmt::{self, Debug, Formatter};
 #[cfg(feature = "std")]
-use core::marker::PhantomData;
+use core::{marker::PhantomData, num::NonZeroUsize};
 #[cfg(all(feature = "std", any(windows, not(feature = "fork"))))]
 use core_affinity::CoreId;
 #[cfg(feature = "std")]
 use serde::de::DeserializeOwned;
-#[cfg(feature = "std")]
-use std::net::SocketAddr;
+#[cfg(all(unix, feature = "std"))]
+use std::os::unix::io::AsRawFd;
 #[cfg(all(feature = "std", any(windows, not(feature = "fork"))))]
 use std::process::Stdio;
-#[cfg(all(unix, feature = "std", feature = "fork"))]
-use std::{fs::File, os::unix::io::AsRawFd};
+#[cfg(feature = "std")]
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
 #[cfg(feature = "std")]
 use typed_builder::TypedBuilder;
 
 /// The (internal) `env` that indicates we're running as client.
 const _AFL_LAUNCHER_CLIENT: &str = "AFL_LAUNCHER_CLIENT";
+
+/// Derives the seed of the client on `core_id` from the `base_seed` of the [`Launcher`].
+/// Each client gets a distinct seed, and the same base seed always gives the same client seeds.
+#[must_use]
+pub fn client_seed(base_seed: u64, core_id: usize) -> u64 {
+    // splitmix64 finalizer, so neighbouring cores get unrelated seeds
+    let mut z = base_seed.wrapping_add((core_id as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A lock file holding the PID of a running campaign, so that no second campaign is launched on the same files.
+/// On unix, the campaign holds a `flock` on the file, which the kernel releases once the broker and all
+/// its forked clients exited, so a file left behind gets reclaimed, even if its PID got reused meanwhile.
+/// Elsewhere, there is no check if the recorded process still runs, so a file left behind blocks new campaigns
+/// until it gets removed by hand, or taken over with [`PidFile::acquire_with_force`].
+/// The file is removed again once the [`PidFile`] is dropped by the process that wrote it.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+    pid: u32,
+    /// The open file, holding the lock
+    _file: File,
+}
+
+#[cfg(feature = "std")]
+impl PidFile {
+    /// Locks the file at `path` and writes the PID of this process to it.
+    /// Fails if the file is locked by a running campaign.
+    pub fn acquire<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Self::acquire_with_force(path, false)
+    }
+
+    /// Like [`PidFile::acquire`], but with `force`, a file recording a PID is taken over without further checks.
+    /// Only use it if no campaign runs on the file, to get past a stale file where it is not reclaimed on its own.
+    /// On unix, a file locked by a running campaign is never taken over.
+    pub fn acquire_with_force<P>(path: P, force: bool) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(&path)?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        if !try_lock(&file, &content, force)? {
+            return Err(Error::IllegalState(format!(
+                "The pid file {} is held by the running process {}",
+                path.display(),
+                content.trim()
+            )));
+        }
+        if !content.trim().is_empty() {
+            libafl_log!(Info, "Reclaiming the stale pid file {}", path.display());
+        }
+
+        let pid = std::process::id();
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        writeln!(file, "{}", pid)?;
+        file.flush()?;
+        Ok(Self {
+            path,
+            pid,
+            _file: file,
+        })
+    }
+
+    /// The path of the pid file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The PID written to the pid file
+    #[must_use]
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Forked clients inherit the lock, only the process that wrote it may remove it
+        if std::process::id() == self.pid {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Tries to lock the pid `file`, without blocking. Returns `false` if a running campaign holds it.
+#[cfg(all(feature = "std", unix))]
+fn try_lock(file: &File, _content: &str, _force: bool) -> Result<bool, Error> {
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let err = std::io::Error::last_os_error();
+    if err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+        Ok(false)
+    } else {
+        Err(Error::File(err))
+    }
+}
+
+/// Tries to lock the pid `file`, without blocking. Returns `false` if a running campaign holds it.
+/// Without a way to check if the recorded process still runs, any recorded PID holds the file, unless forced.
+#[cfg(all(feature = "std", not(unix)))]
+fn try_lock(_file: &File, content: &str, force: bool) -> Result<bool, Error> {
+    Ok(force || content.trim().is_empty())
+}
+
 /// Provides a Launcher, which can be used to launch a fuzzing run on a specified list of cores
 #[cfg(feature = "std")]
 #[derive(TypedBuilder)]
 #[allow(clippy::type_complexity, missing_debug_implementations)]
 pub struct Launcher<'a, CF, I, MT, OT, S, SP>
 where
-    CF: FnOnce(Option<S>, LlmpRestartingEventManager<I, OT, S, SP>, usize) -> Result<(), Error>,
+    CF: FnOnce(
+        Option<S>,
+        LlmpRestartingEventManager<I, OT, S, SP>,
+        usize,
+        u64,
+    ) -> Result<(), Error>,
     I: Input + 'a,
     MT: Monitor,
     SP: ShMemProvider + 'static,
@@ -61,9 +200,14 @@ where
     monitor: MT,
     /// The configuration
     configuration: EventConfig,
-    /// The 'main' function to run for each client forked. This probably shouldn't return
+    /// The 'main' function to run for each client forked. This probably shouldn't return.
+    /// It gets the state (if restarting), the event manager, the core id, and the seed for the RNG of the client.
     #[builder(default, setter(strip_option))]
     run_client: Option<CF>,
+    /// The seed the seeds of all clients are derived from, see [`client_seed`].
+    /// Set it to make a campaign reproducible. Defaults to the current time.
+    #[builder(default = current_nanos())]
+    base_seed: u64,
     /// The broker port to use (or to attach to, in case [`Self::spawn_broker`] is `false`)
     #[builder(default = 1337_u16)]
     broker_port: u16,
@@ -72,6 +216,14 @@ where
     /// A file name to write all client output to
     #[builder(default = None)]
     stdout_file: Option<&'a str>,
+    /// A file to lock the campaign with, holding the PID of the broker while it runs, see [`PidFile`].
+    /// Launching fails if the file is held by another live process.
+    #[builder(default = None, setter(strip_option))]
+    pid_file: Option<&'a str>,
+    /// Take over the `pid_file` even if it records a PID, see [`PidFile::acquire_with_force`].
+    /// Needed to get past a stale pid file on non-unix systems, where it is not reclaimed automatically.
+    #[builder(default = false)]
+    force_pid_file: bool,
     /// The `ip:port` address of another broker to connect our new broker to for multi-machine
     /// clusters.
     #[builder(default = None)]
@@ -82,13 +234,22 @@ where
     /// Then, clients launched by this [`Launcher`] can connect to the original `broker`.
     #[builder(default = true)]
     spawn_broker: bool,
+    /// If the first client stopping on purpose stops all clients, e.g. with [`crate::StdFuzzer::stop_on_first_solution`].
+    /// Else, the broker keeps running until all clients stopped.
+    #[builder(default = false)]
+    stop_all_on_client_exit: bool,
     #[builder(setter(skip), default = PhantomData)]
     phantom_data: PhantomData<(&'a I, &'a OT, &'a S, &'a SP)>,
 }
 
 impl<'a, CF, I, MT, OT, S, SP> Debug for Launcher<'_, CF, I, MT, OT, S, SP>
 where
-    CF: FnOnce(Option<S>, LlmpRestartingEventManager<I, OT, S, SP>, usize) -> Result<(), Error>,
+    CF: FnOnce(
+        Option<S>,
+        LlmpRestartingEventManager<I, OT, S, SP>,
+        usize,
+        u64,
+    ) -> Result<(), Error>,
     I: Input,
     OT: ObserversTuple<I, S> + DeserializeOwned,
     MT: Monitor + Clone,
@@ -103,6 +264,10 @@ where
             .field("spawn_broker", &self.spawn_broker)
             .field("remote_broker_addr", &self.remote_broker_addr)
             .field("stdout_file", &self.stdout_file)
+            .field("pid_file", &self.pid_file)
+            .field("force_pid_file", &self.force_pid_file)
+            .field("base_seed", &self.base_seed)
+            .field("stop_all_on_client_exit", &self.stop_all_on_client_exit)
             .finish_non_exhaustive()
     }
 }
@@ -110,13 +275,27 @@ where
 #[cfg(feature = "std")]
 impl<'a, CF, I, MT, OT, S, SP> Launcher<'a, CF, I, MT, OT, S, SP>
 where
-    CF: FnOnce(Option<S>, LlmpRestartingEventManager<I, OT, S, SP>, usize) -> Result<(), Error>,
+    CF: FnOnce(
+        Option<S>,
+        LlmpRestartingEventManager<I, OT, S, SP>,
+        usize,
+        u64,
+    ) -> Result<(), Error>,
     I: Input,
     OT: ObserversTuple<I, S> + DeserializeOwned,
     MT: Monitor + Clone,
     SP: ShMemProvider + 'static,
     S: DeserializeOwned,
 {
+    /// The amount of clients stopping on purpose after which the broker exits
+    fn exiting_clients(&self) -> Option<NonZeroUsize> {
+        if self.stop_all_on_client_exit {
+            NonZeroUsize::new(1)
+        } else {
+            NonZeroUsize::new(self.cores.ids.len())
+        }
+    }
+
     /// Launch the broker and the clients and fuzz
     #[cfg(all(unix, feature = "std", feature = "fork"))]
     #[allow(clippy::similar_names)]
@@ -127,6 +306,12 @@ where
             ));
         }
 
+        // Held until the broker returns, the forked clients leave it alone
+        let _pid_file = self
+            .pid_file
+            .map(|path| PidFile::acquire_with_force(path, self.force_pid_file))
+            .transpose()?;
+
         let core_ids = core_affinity::get_core_ids().unwrap();
         let num_cores = core_ids.len();
         let mut handles = vec![];
@@ -172,11 +357,20 @@ where
                             })
                             .configuration(self.configuration)
                             .build()
-                            .launch()?;
-
-                        (self.run_client.take().unwrap())(state, mgr, bind_to.id)
-                            .expect("Client closure failed");
-                        break;
+                            .launch()
+                            .map_err(|err| match err {
+                                // The respawned client stopped on purpose, and told the broker already
+                                Error::ShuttingDown => std::process::exit(0),
+                                err => err,
+                            })?;
+
+                        let seed = client_seed(self.base_seed, bind_to.id);
+                        match (self.run_client.take().unwrap())(state, mgr, bind_to.id, seed) {
+                            Ok(()) => break,
+                            // The client stopped on purpose, and told its respawner and the broker already
+                            Err(Error::ShuttingDown) => std::process::exit(0),
+                            Err(err) => panic!("Client closure failed: {}", err),
+                        }
                     }
                 };
             }
@@ -193,6 +387,7 @@ where
                 .broker_port(self.broker_port)
                 .kind(ManagerKind::Broker)
                 .remote_broker_addr(self.remote_broker_addr)
+                .exit_cleanly_after(self.exiting_clients())
                 .configuration(self.configuration)
                 .build()
                 .launch()?;
@@ -224,6 +419,8 @@ where
     #[allow(unused_mut, clippy::match_wild_err_arm)]
     pub fn launch(&mut self) -> Result<(), Error> {
         let is_client = std::env::var(_AFL_LAUNCHER_CLIENT);
+        // Only the broker holds the pid file, the clients are started with the same commandline
+        let mut _pid_file = None;
 
         let mut handles = match is_client {
             Ok(core_conf) => {
@@ -242,14 +439,23 @@ where
                     .build()
                     .launch()?;
 
-                (self.run_client.take().unwrap())(state, mgr, core_id)
-                    .expect("Client closure failed");
+                let seed = client_seed(self.base_seed, core_id);
+                match (self.run_client.take().unwrap())(state, mgr, core_id, seed) {
+                    Ok(()) => (),
+                    // The client stopped on purpose, and told its respawner and the broker already
+                    Err(Error::ShuttingDown) => std::process::exit(0),
+                    Err(err) => panic!("Client closure failed: {}", err),
+                }
 
                 unreachable!("Fuzzer client code should never get here!");
             }
             Err(std::env::VarError::NotPresent) => {
                 // I am a broker
                 // before going to the broker loop, spawn n clients
+                _pid_file = self
+                    .pid_file
+                    .map(|path| PidFile::acquire_with_force(path, self.force_pid_file))
+                    .transpose()?;
 
                 if self.stdout_file.is_some() {
                     println!("Child process file stdio is not supported on Windows yet. Dumping to stdout instead...");
@@ -291,6 +497,7 @@ where
                 .broker_port(self.broker_port)
                 .kind(ManagerKind::Broker)
                 .remote_broker_addr(self.remote_broker_addr)
+                .exit_cleanly_after(self.exiting_clients())
                 .configuration(self.configuration)
                 .build()
                 .launch()?;
@@ -312,3 +519,55 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use crate::bolts::launcher::{client_seed, PidFile};
+
+    #[test]
+    fn test_client_seed() {
+        assert_ne!(client_seed(1337, 0), client_seed(1337, 1));
+        assert_ne!(client_seed(1337, 0), client_seed(1338, 0));
+        // Reproducible from the base seed
+        assert_eq!(client_seed(1337, 3), client_seed(1337, 3));
+    }
+
+    #[test]
+    fn test_pid_file() {
+        let path = PathBuf::from("target/.test/launcher.pid");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let _ = fs::remove_file(&path);
+
+        let pid_file = PidFile::acquire(&path).unwrap();
+        assert_eq!(pid_file.pid(), std::process::id());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap().trim(),
+            std::process::id().to_string()
+        );
+        // A second launch with the same pid file is rejected while the first one runs
+        assert!(PidFile::acquire(&path).is_err());
+
+        drop(pid_file);
+        assert!(!path.exists());
+
+        // Forcing takes over a file recording a PID anywhere
+        fs::write(&path, format!("{}\n", i32::MAX)).unwrap();
+        let pid_file = PidFile::acquire_with_force(&path, true).unwrap();
+        assert_eq!(pid_file.pid(), std::process::id());
+        drop(pid_file);
+
+        // A stale pid file is reclaimed, even if its PID belongs to a live process by now
+        #[cfg(unix)]
+        for pid in [i32::MAX as u32, std::process::id()] {
+            fs::write(&path, format!("{}\n", pid)).unwrap();
+            let pid_file = PidFile::acquire(&path).unwrap();
+            assert_eq!(pid_file.pid(), std::process::id());
+            assert_eq!(
+                fs::read_to_string(&path).unwrap().trim(),
+                std::process::id().to_string()
+            );
+        }
+    }
+}
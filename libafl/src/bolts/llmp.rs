@@ -67,6 +67,7 @@ use core::{
     fmt::Debug,
     hint,
     mem::size_of,
+    num::NonZeroUsize,
     ptr, slice,
     sync::atomic::{fence, AtomicU16, Ordering},
     time::Duration,
@@ -121,6 +122,8 @@ const LLMP_TAG_UNINITIALIZED: Tag = 0xA143AF11;
 const LLMP_TAG_END_OF_PAGE: Tag = 0xAF1E0F1;
 /// A new client for this broker got added.
 const LLMP_TAG_NEW_SHM_CLIENT: Tag = 0xC11E471;
+/// A client stopped on purpose, and will not send any more messages.
+const LLMP_TAG_CLIENT_EXIT: Tag = 0xC11E472;
 /// The sender on this map is exiting (if broker exits, clients should exit gracefully);
 const LLMP_TAG_EXITING: Tag = 0x13C5171;
 /// Client gave up as the receiver/broker was too slow
@@ -1247,6 +1250,7 @@ where
     pub fn send_buf(&mut self, tag: Tag, buf: &[u8]) -> Result<(), Error> {
         // Make sure we don't reuse already allocated tags
         if tag == LLMP_TAG_NEW_SHM_CLIENT
+            || tag == LLMP_TAG_CLIENT_EXIT
             || tag == LLMP_TAG_END_OF_PAGE
             || tag == LLMP_TAG_UNINITIALIZED
             || tag == LLMP_TAG_UNSET
@@ -1267,10 +1271,23 @@ where
         }
     }
 
+    /// Tells the broker that this client stopped on purpose.
+    /// A broker set up using [`LlmpBroker::set_exit_cleanly_after`] exits once enough clients did so.
+    pub fn send_exiting(&mut self) -> Result<(), Error> {
+        // We write this by hand to get around checks in send_buf
+        unsafe {
+            let msg = self.alloc_next(0)?;
+            (*msg).tag = LLMP_TAG_CLIENT_EXIT;
+            (*msg).flags = LLMP_FLAG_INITIALIZED;
+            self.send(msg, true)
+        }
+    }
+
     /// Send a `buf` with the given `flags`.
     pub fn send_buf_with_flags(&mut self, tag: Tag, flags: Flags, buf: &[u8]) -> Result<(), Error> {
         // Make sure we don't reuse already allocated tags
         if tag == LLMP_TAG_NEW_SHM_CLIENT
+            || tag == LLMP_TAG_CLIENT_EXIT
             || tag == LLMP_TAG_END_OF_PAGE
             || tag == LLMP_TAG_UNINITIALIZED
             || tag == LLMP_TAG_UNSET
@@ -1760,6 +1777,10 @@ where
     pub llmp_clients: Vec<LlmpReceiver<SP>>,
     /// The ShMemProvider to use
     shmem_provider: SP,
+    /// The amount of clients that stopped on purpose, see [`LlmpSender::send_exiting`]
+    num_clients_exited: usize,
+    /// Exit the broker loop once this many clients stopped on purpose
+    exit_cleanly_after: Option<NonZeroUsize>,
 }
 
 /// A signal handler for the [`LlmpBroker`].
@@ -1806,9 +1827,25 @@ where
             },
             llmp_clients: vec![],
             shmem_provider,
+            num_clients_exited: 0,
+            exit_cleanly_after: None,
         })
     }
 
+    /// Makes [`LlmpBroker::loop_forever`] return once `n_clients` clients stopped on purpose,
+    /// see [`LlmpSender::send_exiting`].
+    /// Without this, the broker keeps on running until it receives a shutdown signal.
+    pub fn set_exit_cleanly_after(&mut self, n_clients: NonZeroUsize) {
+        self.exit_cleanly_after = Some(n_clients);
+    }
+
+    /// Returns true, if enough clients stopped on purpose, see [`LlmpBroker::set_exit_cleanly_after`]
+    #[inline]
+    fn is_exit_cleanly(&self) -> bool {
+        self.exit_cleanly_after
+            .map_or(false, |n| self.num_clients_exited >= n.get())
+    }
+
     /// Create a new [`LlmpBroker`] sttaching to a TCP port
     #[cfg(feature = "std")]
     pub fn create_attach_to_tcp(shmem_provider: SP, port: u16) -> Result<Self, Error> {
@@ -1964,7 +2001,8 @@ where
     }
 
     /// Loops infinitely, forwarding and handling all incoming messages from clients.
-    /// Never returns. Panics on error.
+    /// Only returns on a shutdown signal, or once enough clients stopped, see [`LlmpBroker::set_exit_cleanly_after`].
+    /// Panics on error.
     /// 5 millis of sleep can't hurt to keep busywait not at 100%
     pub fn loop_forever<F>(&mut self, on_new_msg: &mut F, sleep_time: Option<Duration>)
     where
@@ -1977,7 +2015,7 @@ where
             println!("Failed to setup signal handlers: {}", _e);
         }
 
-        while !self.is_shutting_down() {
+        while !self.is_shutting_down() && !self.is_exit_cleanly() {
             self.once(on_new_msg)
                 .expect("An error occurred when brokering. Exiting.");
 
@@ -2344,6 +2382,10 @@ where
                 LLMP_SLOW_RECEIVER_PANIC => {
                     return Err(Error::Unknown(format!("The broker was too slow to handle messages of client {} in time, so it quit. Either the client sent messages too fast, or we (the broker) got stuck!", client_id)));
                 }
+                LLMP_TAG_CLIENT_EXIT => {
+                    // This client stopped on purpose. No need to forward this msg.
+                    self.num_clients_exited += 1;
+                }
                 LLMP_TAG_NEW_SHM_CLIENT => {
                     /* This client informs us about yet another new client
                     add it to the list! Also, no need to forward this msg. */
@@ -2577,6 +2619,11 @@ where
         self.sender.send_buf_with_flags(tag, flags, buf)
     }
 
+    /// Tells the broker that this client stopped on purpose, see [`LlmpSender::send_exiting`].
+    pub fn send_exiting(&mut self) -> Result<(), Error> {
+        self.sender.send_exiting()
+    }
+
     /// Informs the broker about a new client in town, with the given map id
     pub fn send_client_added_msg(
         &mut self,
@@ -2723,6 +2770,7 @@ where
 #[cfg(all(unix, feature = "std"))]
 mod tests {
 
+    use core::num::NonZeroUsize;
     use std::{thread::sleep, time::Duration};
 
     use serial_test::serial;
@@ -2734,7 +2782,10 @@ mod tests {
         Tag,
     };
 
-    use crate::bolts::shmem::{ShMemProvider, StdShMemProvider};
+    use crate::{
+        bolts::shmem::{ShMemProvider, StdShMemProvider},
+        Error,
+    };
 
     #[test]
     #[serial]
@@ -2787,4 +2838,36 @@ mod tests {
         // We want at least the tcp and sender clients.
         assert_eq!(broker.llmp_clients.len(), 2);
     }
+
+    #[test]
+    #[serial]
+    pub fn llmp_client_exit() {
+        let shmem_provider = StdShMemProvider::new().unwrap();
+        let mut broker = match LlmpConnection::on_port(shmem_provider.clone(), 1338).unwrap() {
+            IsClient { client: _ } => panic!("Could not bind to port as broker"),
+            IsBroker { broker } => broker,
+        };
+        let mut client = match LlmpConnection::on_port(shmem_provider, 1338).unwrap() {
+            IsBroker { broker: _ } => panic!("Second connect should be a client!"),
+            IsClient { client } => client,
+        };
+
+        sleep(Duration::from_millis(100));
+        broker
+            .once(&mut |_sender_id, _tag, _flags, _msg| Ok(ForwardToClients))
+            .unwrap();
+
+        broker.set_exit_cleanly_after(NonZeroUsize::new(1).unwrap());
+        client.send_exiting().unwrap();
+
+        // The exit msg is for the broker only, and stops its loop
+        broker.loop_forever(
+            &mut |_sender_id, _tag, _flags, _msg| panic!("The exit msg reached the hook"),
+            Some(Duration::from_millis(1)),
+        );
+        assert!(matches!(
+            client.recv_buf_blocking(),
+            Err(Error::ShuttingDown)
+        ));
+    }
 }
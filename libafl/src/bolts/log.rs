@@ -0,0 +1,220 @@
+//! Leveled logging for the internal diagnostics of the crate.
+//! By default, messages from [`LogLevel::Info`] up get written to `stdout` (with `std`) or dropped (without `std`).
+//! Use [`set_log_level`] to filter the messages, and [`set_log_sink`] to redirect them.
+//!
+//! The sink may get called from signal handlers, such as the crash handler of the `InProcessExecutor`,
+//! so it should avoid locks and allocations where possible.
+
+use core::{
+    fmt,
+    sync::atomic::{AtomicPtr, AtomicU8, Ordering},
+};
+
+/// The severity of a log message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum LogLevel {
+    /// Detailed messages, only useful to debug the fuzzer itself
+    Debug = 0,
+    /// Informational messages about the progress of the fuzzer
+    Info = 1,
+    /// Something unexpected happened, but the fuzzer continues
+    Warn = 2,
+    /// An error, usually before the process exits
+    Error = 3,
+    /// No message passes this level, used to silence the log
+    Off = 4,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogLevel::Debug => write!(f, "Debug"),
+            LogLevel::Info => write!(f, "Info"),
+            LogLevel::Warn => write!(f, "Warn"),
+            LogLevel::Error => write!(f, "Error"),
+            LogLevel::Off => write!(f, "Off"),
+        }
+    }
+}
+
+/// A sink receiving all the log messages that pass the log level
+pub type LogSink = fn(LogLevel, fmt::Arguments);
+
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+
+/// The current sink, or null for the [`default_log_sink`]
+static LOG_SINK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// The default sink, writing to `stdout` with plain `write` calls.
+/// It neither takes the lock of [`std::io::Stdout`] nor allocates, so it may get called from a signal handler.
+#[cfg(all(feature = "std", unix))]
+pub fn default_log_sink(level: LogLevel, args: fmt::Arguments) {
+    let mut out = StdoutWriter {
+        buf: [0; 256],
+        len: 0,
+    };
+    let _ = fmt::Write::write_fmt(&mut out, format_args!("[{}] {}\n", level, args));
+    out.flush();
+}
+
+/// The default sink, writing to `stdout`, like the `println!`s it replaces
+#[cfg(all(feature = "std", not(unix)))]
+pub fn default_log_sink(level: LogLevel, args: fmt::Arguments) {
+    println!("[{}] {}", level, args);
+}
+
+/// Buffers the formatted message on the stack, and writes it to `stdout` whenever the buffer is full
+#[cfg(all(feature = "std", unix))]
+struct StdoutWriter {
+    buf: [u8; 256],
+    len: usize,
+}
+
+#[cfg(all(feature = "std", unix))]
+impl StdoutWriter {
+    /// Writes the buffered bytes to `stdout`. If that fails, they are lost.
+    fn flush(&mut self) {
+        let mut written = 0;
+        while written < self.len {
+            let ret = unsafe {
+                libc::write(
+                    libc::STDOUT_FILENO,
+                    self.buf[written..self.len].as_ptr().cast(),
+                    self.len - written,
+                )
+            };
+            match usize::try_from(ret) {
+                Ok(count) if count > 0 => written += count,
+                _ if std::io::Error::last_os_error().raw_os_error() == Some(libc::EINTR) => (),
+                _ => break,
+            }
+        }
+        self.len = 0;
+    }
+}
+
+#[cfg(all(feature = "std", unix))]
+impl fmt::Write for StdoutWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut bytes = s.as_bytes();
+        while !bytes.is_empty() {
+            if self.len == self.buf.len() {
+                self.flush();
+            }
+            let count = bytes.len().min(self.buf.len() - self.len);
+            self.buf[self.len..self.len + count].copy_from_slice(&bytes[..count]);
+            self.len += count;
+            bytes = &bytes[count..];
+        }
+        Ok(())
+    }
+}
+
+/// The default sink, dropping all messages without `std`
+#[cfg(not(feature = "std"))]
+pub fn default_log_sink(_level: LogLevel, _args: fmt::Arguments) {}
+
+/// Sets the minimum level of the messages that get passed to the sink
+pub fn set_log_level(level: LogLevel) {
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Gets the minimum level of the messages that get passed to the sink
+#[must_use]
+pub fn log_level() -> LogLevel {
+    match LOG_LEVEL.load(Ordering::Relaxed) {
+        0 => LogLevel::Debug,
+        1 => LogLevel::Info,
+        2 => LogLevel::Warn,
+        3 => LogLevel::Error,
+        _ => LogLevel::Off,
+    }
+}
+
+/// Sets the global sink for all log messages of the crate
+pub fn set_log_sink(sink: LogSink) {
+    LOG_SINK.store(sink as *mut (), Ordering::Release);
+}
+
+/// Resets the global sink to the [`default_log_sink`]
+pub fn reset_log_sink() {
+    LOG_SINK.store(core::ptr::null_mut(), Ordering::Release);
+}
+
+/// Passes a message to the sink, if its `level` is at least the current log level.
+/// Usually called through the [`crate::libafl_log`] macro.
+pub fn log(level: LogLevel, args: fmt::Arguments) {
+    let sink = LOG_SINK.load(Ordering::Acquire);
+    let sink: LogSink = if sink.is_null() {
+        default_log_sink
+    } else {
+        // Safety: only ever set from a valid `LogSink` in `set_log_sink`
+        unsafe { core::mem::transmute(sink) }
+    };
+    log_to(sink, log_level(), level, args);
+}
+
+/// Passes a message to the `sink`, if its `level` is at least `min_level`
+fn log_to(sink: LogSink, min_level: LogLevel, level: LogLevel, args: fmt::Arguments) {
+    if level >= min_level && level != LogLevel::Off {
+        sink(level, args);
+    }
+}
+
+/// Logs a message with the given [`LogLevel`] variant, formatted like `format!`.
+///
+/// ```
+/// libafl::libafl_log!(Info, "Loaded {} testcases", 42);
+/// ```
+#[macro_export]
+macro_rules! libafl_log {
+    ($level:ident, $($arg:tt)+) => {
+        $crate::bolts::log::log($crate::bolts::log::LogLevel::$level, format_args!($($arg)+))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        fmt,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use crate::bolts::log::{log_to, LogLevel};
+
+    /// Only touched by this test, unlike the global sink, which all tests log to
+    static LOGGED: AtomicUsize = AtomicUsize::new(0);
+
+    fn counting_sink(level: LogLevel, _args: fmt::Arguments) {
+        assert!(level >= LogLevel::Warn);
+        LOGGED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn test_log_sink() {
+        // Debug and Info are filtered
+        for level in [
+            LogLevel::Debug,
+            LogLevel::Info,
+            LogLevel::Warn,
+            LogLevel::Error,
+        ] {
+            log_to(
+                counting_sink,
+                LogLevel::Warn,
+                level,
+                format_args!("{}", level),
+            );
+        }
+        assert_eq!(LOGGED.load(Ordering::SeqCst), 2);
+
+        log_to(
+            counting_sink,
+            LogLevel::Off,
+            LogLevel::Error,
+            format_args!("filtered"),
+        );
+        assert_eq!(LOGGED.load(Ordering::SeqCst), 2);
+    }
+}
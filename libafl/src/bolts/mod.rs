@@ -14,6 +14,7 @@ pub mod fs;
 #[cfg(feature = "std")]
 pub mod launcher;
 pub mod llmp;
+pub mod log;
 #[cfg(all(feature = "std", unix))]
 pub mod minibsod;
 pub mod os;
@@ -21,6 +22,8 @@ pub mod ownedref;
 pub mod rands;
 pub mod serdeany;
 pub mod shmem;
+#[cfg(feature = "simd")]
+pub mod simd;
 #[cfg(feature = "std")]
 pub mod staterestore;
 pub mod tuples;
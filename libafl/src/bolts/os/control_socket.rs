@@ -0,0 +1,192 @@
+//! A unix control socket to pause and resume a running fuzzer, e.g. to snapshot the corpus in a consistent state.
+//! Each connection sends a single command line, `pause`, `resume`, or `status`, and receives a single reply line.
+
+use alloc::string::{String, ToString};
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    time::Duration,
+};
+
+use uds::{UnixListenerExt, UnixSocketAddr, UnixStreamExt};
+
+use crate::{libafl_log, Error};
+
+/// How long to wait for a connected client to send its command
+const CONTROL_CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A command sent to the [`ControlSocket`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlCommand {
+    /// Stop fuzzing until `resume` is received
+    Pause,
+    /// Continue fuzzing after a `pause`
+    Resume,
+    /// Reply with the current stats
+    Status,
+}
+
+impl ControlCommand {
+    /// Parse a command line
+    #[must_use]
+    pub fn parse(line: &str) -> Option<Self> {
+        match line.trim() {
+            "pause" => Some(ControlCommand::Pause),
+            "resume" => Some(ControlCommand::Resume),
+            "status" => Some(ControlCommand::Status),
+            _ => None,
+        }
+    }
+}
+
+/// A control socket, checked between executions with [`ControlSocket::handle_commands`].
+/// While paused, [`ControlSocket::handle_commands`] blocks until a `resume` command arrives.
+#[derive(Debug)]
+pub struct ControlSocket {
+    listener: UnixListener,
+    paused: bool,
+}
+
+impl ControlSocket {
+    /// Listen on a filename (or abstract name, starting with `@`) for control commands
+    pub fn new(name: &str) -> Result<Self, Error> {
+        let listener = UnixListener::bind_unix_addr(&UnixSocketAddr::new(name)?)?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            paused: false,
+        })
+    }
+
+    /// Returns `true` if the fuzzer is currently paused
+    #[must_use]
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Handles all the pending commands, calling `status` to get the reply to a `status` command.
+    /// If a `pause` command arrived, blocks, still serving commands, until a `resume` command arrives.
+    pub fn handle_commands<F>(&mut self, mut status: F) -> Result<(), Error>
+    where
+        F: FnMut() -> String,
+    {
+        loop {
+            self.listener.set_nonblocking(!self.paused)?;
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    // A misbehaving client must not stop the fuzzer
+                    if let Err(e) = self.handle_client(stream, &mut status) {
+                        libafl_log!(Warn, "Ignoring failed control client: {:?}", e);
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Reads a single command from the client and replies to it
+    fn handle_client<F>(&mut self, stream: UnixStream, status: &mut F) -> Result<(), Error>
+    where
+        F: FnMut() -> String,
+    {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(CONTROL_CLIENT_TIMEOUT))?;
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+
+        let reply = match ControlCommand::parse(&line) {
+            Some(ControlCommand::Pause) => {
+                self.paused = true;
+                libafl_log!(Info, "Fuzzer paused by the control socket");
+                "paused".to_string()
+            }
+            Some(ControlCommand::Resume) => {
+                self.paused = false;
+                libafl_log!(Info, "Fuzzer resumed by the control socket");
+                "resumed".to_string()
+            }
+            Some(ControlCommand::Status) => {
+                let state = if self.paused { "paused" } else { "running" };
+                format!("{} {}", state, status())
+            }
+            None => format!("unknown command: {}", line.trim()),
+        };
+
+        let mut stream = reader.into_inner();
+        stream.write_all(reply.as_bytes())?;
+        stream.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+/// Sends a `command` to the [`ControlSocket`] listening on `name`, returning the reply
+pub fn send_control_command(name: &str, command: ControlCommand) -> Result<String, Error> {
+    let mut stream = UnixStream::connect_to_unix_addr(&UnixSocketAddr::new(name)?)?;
+    let line = match command {
+        ControlCommand::Pause => "pause\n",
+        ControlCommand::Resume => "resume\n",
+        ControlCommand::Status => "status\n",
+    };
+    stream.write_all(line.as_bytes())?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+    Ok(reply.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    use crate::bolts::os::control_socket::{send_control_command, ControlCommand, ControlSocket};
+
+    #[test]
+    fn test_control_socket_pause_resume() {
+        let name = "target/.test/control.sock";
+        fs::create_dir_all("target/.test").unwrap();
+        let _ = fs::remove_file(name);
+
+        let mut socket = ControlSocket::new(name).unwrap();
+        // Nothing pending, returns right away
+        socket.handle_commands(|| "executions: 0".into()).unwrap();
+
+        let done = Arc::new(AtomicBool::new(false));
+        let client_done = Arc::clone(&done);
+        let client = thread::spawn(move || {
+            let replies = vec![
+                send_control_command(name, ControlCommand::Status).unwrap(),
+                send_control_command(name, ControlCommand::Pause).unwrap(),
+                send_control_command(name, ControlCommand::Status).unwrap(),
+                send_control_command(name, ControlCommand::Resume).unwrap(),
+            ];
+            client_done.store(true, Ordering::SeqCst);
+            replies
+        });
+
+        let mut executions = 0;
+        while !done.load(Ordering::SeqCst) {
+            socket
+                .handle_commands(|| format!("executions: {}", executions))
+                .unwrap();
+            // The executions only go on while not paused
+            assert!(!socket.paused());
+            executions += 1;
+        }
+
+        let replies = client.join().unwrap();
+        assert!(replies[0].starts_with("running executions: "));
+        assert_eq!(replies[1], "paused");
+        assert!(replies[2].starts_with("paused executions: "));
+        assert_eq!(replies[3], "resumed");
+
+        fs::remove_file(name).unwrap();
+    }
+}
@@ -15,6 +15,9 @@ use std::{env, process::Command};
 #[cfg(all(unix, feature = "std"))]
 pub mod unix_shmem_server;
 
+#[cfg(all(unix, feature = "std"))]
+pub mod control_socket;
+
 #[cfg(unix)]
 pub mod unix_signals;
 
@@ -10,20 +10,24 @@ use crate::{
         shmem::{ShMem, ShMemDescription, ShMemId, ShMemProvider},
         AsMutSlice, AsSlice,
     },
-    Error,
+    libafl_log, Error,
 };
-use core::{mem::ManuallyDrop, ptr::addr_of};
+use core::{mem::ManuallyDrop, ptr::addr_of, time::Duration};
 use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
 use std::{
     borrow::BorrowMut,
     cell::RefCell,
     env,
-    io::{Read, Write},
+    io::{self, ErrorKind, Read, Write},
     marker::PhantomData,
     rc::{Rc, Weak},
-    sync::{Arc, Condvar, Mutex},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
     thread::JoinHandle,
+    time::Instant,
 };
 
 #[cfg(target_vendor = "apple")]
@@ -54,6 +58,97 @@ const UNIX_SERVER_NAME: &str = "./libafl_unix_shmem_server";
 /// Env variable. If set, we won't try to spawn the service
 const AFL_SHMEM_SERVICE_STARTED: &str = "AFL_SHMEM_SERVICE_STARTED";
 
+/// The amount of services started by [`ShMemService::start_unique`] in this process, to make their names unique
+static UNIQUE_SERVICES: AtomicUsize = AtomicUsize::new(0);
+
+/// The length of each response of the [`ShMemService`]:
+/// a [`ServedShMemStatus`] byte, an `i32` (the map id, client id, or refcount, `-1` on failure),
+/// and the `u64` size of the map, both little endian.
+const RESPONSE_LEN: usize = 13;
+
+/// The status of a response of the [`ShMemService`].
+/// Requests violating the protocol get rejected with an explicit status, instead of being served.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ServedShMemStatus {
+    /// The request got served
+    Ok = 0,
+    /// The service could not allocate the requested map
+    AllocationFailed = 1,
+    /// The client sent a request before registering with a [`ServedShMemRequest::Hello`]
+    NotRegistered = 2,
+    /// The client sent a second [`ServedShMemRequest::Hello`] or [`ServedShMemRequest::PostForkChildHello`]
+    AlreadyRegistered = 3,
+    /// The map of a [`ServedShMemRequest::ExistingMap`] is not known to the service,
+    /// or the map of a [`ServedShMemRequest::Deregister`] is not held by the client
+    UnknownMap = 4,
+    /// The parent of a [`ServedShMemRequest::PostForkChildHello`] did not send a [`ServedShMemRequest::PreFork`]
+    UnknownParent = 5,
+}
+
+impl TryFrom<u8> for ServedShMemStatus {
+    type Error = Error;
+
+    fn try_from(status: u8) -> Result<Self, Error> {
+        Ok(match status {
+            0 => Self::Ok,
+            1 => Self::AllocationFailed,
+            2 => Self::NotRegistered,
+            3 => Self::AlreadyRegistered,
+            4 => Self::UnknownMap,
+            5 => Self::UnknownParent,
+            _ => {
+                return Err(Error::IllegalState(format!(
+                    "Unknown status {} in a response of the ShMemService",
+                    status
+                )))
+            }
+        })
+    }
+}
+
+impl ServedShMemStatus {
+    /// Turns a status other than [`ServedShMemStatus::Ok`] into an error for the given request
+    fn check(self, request: &ServedShMemRequest) -> Result<(), Error> {
+        match self {
+            Self::Ok => Ok(()),
+            Self::AllocationFailed => Err(Error::Unknown(format!(
+                "The ShMemService could not allocate a map for {:?}",
+                request
+            ))),
+            _ => Err(Error::IllegalState(format!(
+                "The ShMemService rejected {:?}: {:?}",
+                request, self
+            ))),
+        }
+    }
+}
+
+/// Frames a response of the [`ShMemService`]
+fn encode_response(status: ServedShMemStatus, id: i32, size: usize) -> [u8; RESPONSE_LEN] {
+    let mut response = [0_u8; RESPONSE_LEN];
+    response[0] = status as u8;
+    response[1..5].copy_from_slice(&id.to_le_bytes());
+    response[5..].copy_from_slice(&(size as u64).to_le_bytes());
+    response
+}
+
+/// Parses a response of the [`ShMemService`], returning the status, the id, and the size of the map
+#[allow(clippy::cast_possible_truncation)]
+fn decode_response(
+    response: &[u8; RESPONSE_LEN],
+) -> Result<(ServedShMemStatus, i32, usize), Error> {
+    let mut id = [0_u8; 4];
+    id.copy_from_slice(&response[1..5]);
+    let mut size = [0_u8; 8];
+    size.copy_from_slice(&response[5..]);
+    Ok((
+        ServedShMemStatus::try_from(response[0])?,
+        i32::from_le_bytes(id),
+        u64::from_le_bytes(size) as usize,
+    ))
+}
+
 /// Hands out served shared maps, as used on Android.
 #[derive(Debug)]
 pub struct ServedShMemProvider<SP>
@@ -63,6 +158,12 @@ where
     stream: UnixStream,
     inner: SP,
     id: i32,
+    /// The maximum time to wait for each response, if any
+    timeout: Option<Duration>,
+    /// Set once a request timed out: a late response may still arrive, so the connection is out of sync
+    desynced: bool,
+    /// The name of the socket of the [`ShMemService`], to reconnect after a fork
+    service_name: String,
     /// A referencde to the [`ShMemService`] backing this provider.
     /// It will be started only once for all processes and providers.
     service: ShMemService<SP>,
@@ -114,31 +215,206 @@ impl<SP> ServedShMemProvider<SP>
 where
     SP: ShMemProvider,
 {
-    /// Send a request to the server, and wait for a response
+    /// Send a request to the server, and wait for a response, at most for the timeout, if set.
+    /// Returns the id from the server, the received fd, and the size of the map, if any,
+    /// or an error if the server rejected the request.
     #[allow(clippy::similar_names)] // id and fd
-    fn send_receive(&mut self, request: ServedShMemRequest) -> Result<(i32, i32), Error> {
+    fn send_receive(&mut self, request: ServedShMemRequest) -> Result<(i32, i32, usize), Error> {
         //let bt = Backtrace::new();
         //println!("Sending {:?} with bt:\n{:?}", request, bt);
 
-        let body = postcard::to_allocvec(&request)?;
+        self.check_synced()?;
 
-        let header = (body.len() as u32).to_be_bytes();
-        let mut message = header.to_vec();
-        message.extend(body);
+        if let Some(timeout) = self.timeout {
+            return self.send_receive_timeout(request, timeout);
+        }
+
+        let message = Self::encode_request(&request)?;
 
         self.stream
             .write_all(&message)
             .expect("Failed to send message");
 
-        let mut shm_slice = [0_u8; 20];
+        let mut response = [0_u8; RESPONSE_LEN];
         let mut fd_buf = [-1; 1];
-        self.stream
-            .recv_fds(&mut shm_slice, &mut fd_buf)
+        let (len, _) = self
+            .stream
+            .recv_fds(&mut response, &mut fd_buf)
             .expect("Did not receive a response");
+        Self::check_response(&request, len, &response, fd_buf[0])
+    }
 
-        let server_id = ShMemId::from_array(&shm_slice);
-        let server_fd: i32 = server_id.into();
-        Ok((server_fd, fd_buf[0]))
+    /// Send a request to the server, and wait for a response, like [`Self::send_receive`],
+    /// but gives up with an [`ErrorKind::TimedOut`] error if the request could not be sent
+    /// or no response arrived within `timeout`, so a slow server can not wedge the client.
+    /// After a timeout, the connection is out of sync: a late response would be taken as response
+    /// to the next request, so all later requests of this provider fail, including the releases of its maps.
+    /// A clone of the provider gets a new connection.
+    #[allow(clippy::similar_names)] // id and fd
+    pub fn send_receive_timeout(
+        &mut self,
+        request: ServedShMemRequest,
+        timeout: Duration,
+    ) -> Result<(i32, i32, usize), Error> {
+        self.check_synced()?;
+        let deadline = Instant::now() + timeout;
+        let message = Self::encode_request(&request)?;
+
+        self.stream.set_nonblocking(true)?;
+        let received = self.exchange_until(&message, deadline);
+        self.stream.set_nonblocking(false)?;
+
+        // A partial exchange leaves the request or its response in the connection
+        self.desynced = received.is_err();
+        let (len, response, fd) = received?;
+        Self::check_response(&request, len, &response, fd)
+    }
+
+    /// Fails if a request timed out before, see [`Self::send_receive_timeout`]
+    fn check_synced(&self) -> Result<(), Error> {
+        if self.desynced {
+            return Err(Error::IllegalState(
+                "The connection to the ShMemService is out of sync after a timeout".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Sets the maximum time to wait for each response of the server, see [`Self::send_receive_timeout`].
+    /// `None`, the default, waits forever.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// The maximum time to wait for each response of the server, if any
+    #[must_use]
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Connect to the given, already started, [`ShMemService`], instead of the default one.
+    /// Together with [`ShMemService::start_unique`], tests running in parallel each get their own service.
+    pub fn with_service(service: ShMemService<SP>) -> Result<Self, Error> {
+        let service_name = match service.name() {
+            Some(name) => name.to_string(),
+            None => {
+                return Err(Error::IllegalArgument(
+                    "Cannot connect to a ShMemService that failed to start".into(),
+                ))
+            }
+        };
+        Self::connect(service, service_name)
+    }
+
+    /// Connect to the service listening on `service_name`, and register with it
+    fn connect(service: ShMemService<SP>, service_name: String) -> Result<Self, Error> {
+        let mut res = Self {
+            stream: UnixStream::connect_to_unix_addr(&UnixSocketAddr::new(&service_name)?)?,
+            inner: SP::new()?,
+            id: -1,
+            timeout: None,
+            desynced: false,
+            service_name,
+            service,
+        };
+        let (id, _, _) = res.send_receive(ServedShMemRequest::Hello())?;
+        res.id = id;
+        Ok(res)
+    }
+
+    /// Frames a request, as big endian `u32` length followed by the serialized request
+    fn encode_request(request: &ServedShMemRequest) -> Result<Vec<u8>, Error> {
+        let body = postcard::to_allocvec(request)?;
+
+        let header = (body.len() as u32).to_be_bytes();
+        let mut message = header.to_vec();
+        message.extend(body);
+        Ok(message)
+    }
+
+    /// Sends the message and receives the response on the non-blocking stream, until the `deadline`
+    fn exchange_until(
+        &mut self,
+        message: &[u8],
+        deadline: Instant,
+    ) -> Result<(usize, [u8; RESPONSE_LEN], i32), Error> {
+        let mut written = 0;
+        while written < message.len() {
+            match self.stream.write(&message[written..]) {
+                Ok(len) => written += len,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    self.wait_until(PollFlags::POLLOUT, deadline)?;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let mut response = [0_u8; RESPONSE_LEN];
+        let mut fd_buf = [-1; 1];
+        loop {
+            self.wait_until(PollFlags::POLLIN, deadline)?;
+            match self.stream.recv_fds(&mut response, &mut fd_buf) {
+                Ok((len, _)) => return Ok((len, response, fd_buf[0])),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Polls the stream for the `events`, failing with [`ErrorKind::TimedOut`] once the `deadline` passed
+    fn wait_until(&self, events: PollFlags, deadline: Instant) -> Result<(), Error> {
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(Error::File(io::Error::new(
+                    ErrorKind::TimedOut,
+                    "The ShMemService did not respond in time",
+                )));
+            }
+            let mut poll_fds = [PollFd::new(self.stream.as_raw_fd(), events)];
+            // Round up, so we don't spin on sub-millisecond remainders
+            let timeout_ms = i32::try_from(remaining.as_millis() + 1).unwrap_or(i32::MAX);
+            match poll(&mut poll_fds, timeout_ms) {
+                Ok(0) | Err(nix::errno::Errno::EINTR) => (),
+                Ok(_) => return Ok(()),
+                Err(err) => {
+                    return Err(Error::Unknown(format!(
+                        "Failed to poll the ShMemService connection: {:?}",
+                        err
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Checks a response of the server, returning the id, the received fd, and the size of the map
+    fn check_response(
+        request: &ServedShMemRequest,
+        len: usize,
+        response: &[u8; RESPONSE_LEN],
+        fd: i32,
+    ) -> Result<(i32, i32, usize), Error> {
+        if len != RESPONSE_LEN {
+            return Err(Error::IllegalState(format!(
+                "Expected a response of {} bytes from the ShMemService, but got {}",
+                RESPONSE_LEN, len
+            )));
+        }
+
+        let (status, server_fd, size) = decode_response(response)?;
+        status.check(request)?;
+        Ok((server_fd, fd, size))
+    }
+
+    /// Checks the response to a map request, returning the size to map
+    fn check_map_response(size: usize, requested: usize) -> Result<usize, Error> {
+        if size < requested {
+            return Err(Error::IllegalState(format!(
+                "The ShMemService provided a map of size {}, but {} were requested",
+                size, requested
+            )));
+        }
+        Ok(size)
     }
 }
 
@@ -156,8 +432,8 @@ where
     SP: ShMemProvider,
 {
     fn clone(&self) -> Self {
-        let mut cloned = Self::new().unwrap();
-        cloned.service = self.service.clone();
+        let mut cloned = Self::connect(self.service.clone(), self.service_name.clone()).unwrap();
+        cloned.timeout = self.timeout;
         cloned
     }
 }
@@ -174,24 +450,20 @@ where
         // Needed for MacOS and Android to get sharedmaps working.
         let service = ShMemService::<SP>::start();
 
-        let mut res = Self {
-            stream: UnixStream::connect_to_unix_addr(&UnixSocketAddr::new(UNIX_SERVER_NAME)?)?,
-            inner: SP::new()?,
-            id: -1,
-            service,
-        };
-        let (id, _) = res.send_receive(ServedShMemRequest::Hello())?;
-        res.id = id;
-        Ok(res)
+        Self::connect(service, UNIX_SERVER_NAME.to_string())
     }
     fn new_shmem(&mut self, map_size: usize) -> Result<Self::ShMem, Error> {
-        let (server_fd, client_fd) = self.send_receive(ServedShMemRequest::NewMap(map_size))?;
+        let (server_fd, client_fd, size) =
+            self.send_receive(ServedShMemRequest::NewMap(map_size))?;
+        let size = Self::check_map_response(size, map_size)?;
 
         Ok(ServedShMem {
-            inner: ManuallyDrop::new(self.inner.shmem_from_id_and_size(
-                ShMemId::from_string(&format!("{}", client_fd)),
-                map_size,
-            )?),
+            inner: ManuallyDrop::new(
+                self.inner.shmem_from_id_and_size(
+                    ShMemId::from_string(&format!("{}", client_fd)),
+                    size,
+                )?,
+            ),
             server_fd,
         })
     }
@@ -199,9 +471,11 @@ where
     fn shmem_from_id_and_size(&mut self, id: ShMemId, size: usize) -> Result<Self::ShMem, Error> {
         let parts = id.as_str().split(':').collect::<Vec<&str>>();
         let server_id_str = parts.get(0).unwrap();
-        let (server_fd, client_fd) = self.send_receive(ServedShMemRequest::ExistingMap(
-            ShMemDescription::from_string_and_size(server_id_str, size),
-        ))?;
+        let (server_fd, client_fd, mapped_size) =
+            self.send_receive(ServedShMemRequest::ExistingMap(
+                ShMemDescription::from_string_and_size(server_id_str, size),
+            ))?;
+        let size = Self::check_map_response(mapped_size, size)?;
         Ok(ServedShMem {
             inner: ManuallyDrop::new(
                 self.inner.shmem_from_id_and_size(
@@ -229,22 +503,22 @@ where
 
             // After fork, the child needs to reconnect as to not share the fds with the parent.
             self.stream =
-                UnixStream::connect_to_unix_addr(&UnixSocketAddr::new(UNIX_SERVER_NAME)?)?;
-            let (id, _) = self.send_receive(ServedShMemRequest::PostForkChildHello(self.id))?;
+                UnixStream::connect_to_unix_addr(&UnixSocketAddr::new(&self.service_name)?)?;
+            self.desynced = false;
+            let (id, _, _) = self.send_receive(ServedShMemRequest::PostForkChildHello(self.id))?;
             self.id = id;
         }
         Ok(())
     }
 
-    fn release_shmem(&mut self, map: &mut Self::ShMem) {
-        let (refcount, _) = self
-            .send_receive(ServedShMemRequest::Deregister(map.server_fd))
-            .expect("Could not communicate with ServedShMem server!");
+    fn release_shmem(&mut self, map: &mut Self::ShMem) -> Result<(), Error> {
+        let (refcount, _, _) = self.send_receive(ServedShMemRequest::Deregister(map.server_fd))?;
         if refcount == 1 {
             unsafe {
                 ManuallyDrop::drop(&mut map.inner);
             }
         }
+        Ok(())
     }
 }
 
@@ -276,6 +550,8 @@ where
 {
     stream: UnixStream,
     maps: HashMap<i32, Vec<Rc<RefCell<SH>>>>,
+    /// If the client registered with a [`ServedShMemRequest::Hello`] or a [`ServedShMemRequest::PostForkChildHello`]
+    registered: bool,
 }
 
 impl<SH> SharedShMemClient<SH>
@@ -286,6 +562,7 @@ where
         Self {
             stream,
             maps: HashMap::new(),
+            registered: false,
         }
     }
 }
@@ -299,6 +576,8 @@ where
     Mapping(Rc<RefCell<SP::ShMem>>),
     Id(i32),
     RefCount(u32),
+    /// The request could not be served, or violated the protocol
+    Rejected(ServedShMemStatus),
 }
 
 /// Report the status of the [`ShMem`] background thread start status
@@ -320,6 +599,8 @@ where
     Started {
         /// The background thread
         bg_thread: Arc<Mutex<ShMemServiceThread>>,
+        /// The name of the socket the service listens on
+        name: String,
         /// The pantom data
         phantom: PhantomData<SP>,
     },
@@ -337,18 +618,20 @@ where
 #[derive(Debug)]
 pub struct ShMemServiceThread {
     join_handle: Option<JoinHandle<Result<(), Error>>>,
+    /// The name of the socket the service listens on
+    name: String,
 }
 
-impl Drop for ShMemServiceThread {
-    fn drop(&mut self) {
+impl ShMemServiceThread {
+    /// Stops the service, if it still runs in this process, and waits for the background thread to finish
+    fn stop(&mut self) {
         if self.join_handle.is_some() {
-            println!("Stopping ShMemService");
-            let mut stream = match UnixStream::connect_to_unix_addr(
-                &UnixSocketAddr::new(UNIX_SERVER_NAME).unwrap(),
-            ) {
-                Ok(stream) => stream,
-                Err(_) => return, // ignoring non-started server
-            };
+            libafl_log!(Info, "Stopping ShMemService");
+            let mut stream =
+                match UnixStream::connect_to_unix_addr(&UnixSocketAddr::new(&self.name).unwrap()) {
+                    Ok(stream) => stream,
+                    Err(_) => return, // ignoring non-started server
+                };
 
             let body = postcard::to_allocvec(&ServedShMemRequest::Exit).unwrap();
 
@@ -367,13 +650,22 @@ impl Drop for ShMemServiceThread {
                 .expect("Error in ShMemService background thread!");
             // try to remove the file from fs, and ignore errors.
             #[cfg(target_vendor = "apple")]
-            fs::remove_file(&UNIX_SERVER_NAME).unwrap();
+            fs::remove_file(&self.name).unwrap();
 
-            env::remove_var(AFL_SHMEM_SERVICE_STARTED);
+            // Only the default service keeps other providers from starting their own
+            if self.name == UNIX_SERVER_NAME {
+                env::remove_var(AFL_SHMEM_SERVICE_STARTED);
+            }
         }
     }
 }
 
+impl Drop for ShMemServiceThread {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
 impl<SP> ShMemService<SP>
 where
     SP: ShMemProvider,
@@ -390,9 +682,55 @@ where
             };
         }
 
+        let service = Self::start_on(UNIX_SERVER_NAME.to_string());
+
+        // Optimization: Following calls or even child processe don't need to try to start a service anymore.
+        // It's either running at this point, or we won't be able to spawn it anyway.
+        env::set_var(AFL_SHMEM_SERVICE_STARTED, "true");
+
+        service
+    }
+
+    /// Create a new [`ShMemService`] listening on a name unique to this process and call,
+    /// next to the default service started by [`ShMemService::start`].
+    /// Meant for tests, which may run in parallel, each connecting to its own service
+    /// using [`ServedShMemProvider::with_service`].
+    /// Returns [`ShMemService::Failed`] on error.
+    #[must_use]
+    pub fn start_unique() -> Self {
+        let name = format!(
+            "{}_{}_{}",
+            UNIX_SERVER_NAME,
+            std::process::id(),
+            UNIQUE_SERVICES.fetch_add(1, Ordering::SeqCst)
+        );
+        Self::start_on(name)
+    }
+
+    /// The name of the socket the service listens on, or `None` if it failed to start
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Self::Started { name, .. } => Some(name),
+            Self::Failed { .. } => None,
+        }
+    }
+
+    /// Stops the service and waits for its background thread, even if providers still hold it.
+    /// Afterwards, providers connected to it fail to communicate, and no new ones can connect.
+    /// Dropping the last handle to the service stops it as well.
+    pub fn shutdown(&self) {
+        if let Self::Started { bg_thread, .. } = self {
+            bg_thread.lock().unwrap().stop();
+        }
+    }
+
+    /// Spawn the service thread, listening on `name`, and wait until it started or failed
+    fn start_on(name: String) -> Self {
         #[allow(clippy::mutex_atomic)]
         let syncpair = Arc::new((Mutex::new(ShMemServiceStatus::Starting), Condvar::new()));
         let childsyncpair = Arc::clone(&syncpair);
+        let thread_name = name.clone();
         let join_handle = thread::spawn(move || {
             let mut worker = match ServedShMemServiceWorker::<SP>::new() {
                 Ok(worker) => worker,
@@ -402,12 +740,12 @@ where
                     *lock.lock().unwrap() = ShMemServiceStatus::Failed;
                     cvar.notify_one();
 
-                    println!("Error creating ShMemService: {:?}", e);
+                    libafl_log!(Error, "Error creating ShMemService: {:?}", e);
                     return Err(e);
                 }
             };
-            if let Err(e) = worker.listen(UNIX_SERVER_NAME, &childsyncpair) {
-                println!("Error spawning ShMemService: {:?}", e);
+            if let Err(e) = worker.listen(&thread_name, &childsyncpair) {
+                libafl_log!(Error, "Error spawning ShMemService: {:?}", e);
                 Err(e)
             } else {
                 Ok(())
@@ -420,19 +758,17 @@ where
             success = cvar.wait(success).unwrap();
         }
 
-        // Optimization: Following calls or even child processe don't need to try to start a service anymore.
-        // It's either running at this point, or we won't be able to spawn it anyway.
-        env::set_var(AFL_SHMEM_SERVICE_STARTED, "true");
-
         match *success {
             ShMemServiceStatus::Starting => panic!("Unreachable"),
             ShMemServiceStatus::Started => {
-                println!("Started ShMem Service");
+                libafl_log!(Info, "Started ShMem Service");
                 // We got a service
                 Self::Started {
                     bg_thread: Arc::new(Mutex::new(ShMemServiceThread {
                         join_handle: Some(join_handle),
+                        name: name.clone(),
                     })),
+                    name,
                     phantom: PhantomData,
                 }
             }
@@ -478,24 +814,39 @@ where
         })
     }
 
-    fn upgrade_shmem_with_id(&mut self, description_id: i32) -> Rc<RefCell<SP::ShMem>> {
-        self.all_shmems
-            .get_mut(&description_id)
-            .unwrap()
-            .clone()
-            .upgrade()
-            .unwrap()
-    }
-
     /// Read and handle the client request, send the answer over unix fd.
     fn handle_request(&mut self, client_id: RawFd) -> Result<ServedShMemResponse<SP>, Error> {
         let request = self.read_request(client_id)?;
 
         // println!("got ashmem client: {}, request:{:?}", client_id, request);
 
+        // Check the request is valid for the protocol state of the client
+        let client = self.clients.get_mut(&client_id).unwrap();
+        match request {
+            ServedShMemRequest::Hello() | ServedShMemRequest::PostForkChildHello(_)
+                if client.registered =>
+            {
+                return Ok(ServedShMemResponse::Rejected(
+                    ServedShMemStatus::AlreadyRegistered,
+                ));
+            }
+            ServedShMemRequest::Hello()
+            | ServedShMemRequest::PostForkChildHello(_)
+            | ServedShMemRequest::Exit => (),
+            _ if !client.registered => {
+                return Ok(ServedShMemResponse::Rejected(
+                    ServedShMemStatus::NotRegistered,
+                ));
+            }
+            _ => (),
+        }
+
         // Handle the client request
         let response = match request {
-            ServedShMemRequest::Hello() => Ok(ServedShMemResponse::Id(client_id)),
+            ServedShMemRequest::Hello() => {
+                client.registered = true;
+                Ok(ServedShMemResponse::Id(client_id))
+            }
             ServedShMemRequest::PreFork() => {
                 // We clone the provider already, waiting for it to reconnect [`PostFork`].
                 // That wa, even if the parent dies before the child sends its `PostFork`, we should be good.
@@ -519,57 +870,58 @@ where
                 Ok(ServedShMemResponse::Id(client_id))
             }
             ServedShMemRequest::PostForkChildHello(other_id) => {
-                let client = self.clients.get_mut(&client_id).unwrap();
-                client.maps = self.forking_clients.remove(&other_id).unwrap();
-                Ok(ServedShMemResponse::Id(client_id))
-            }
-            ServedShMemRequest::NewMap(map_size) => {
-                let new_shmem = self.provider.new_shmem(map_size)?;
-                let description = new_shmem.description();
-                let new_rc = Rc::new(RefCell::new(new_shmem));
-                self.all_shmems
-                    .insert(description.id.into(), Rc::downgrade(&new_rc));
-                Ok(ServedShMemResponse::Mapping(new_rc))
+                match self.forking_clients.remove(&other_id) {
+                    Some(maps) => {
+                        client.maps = maps;
+                        client.registered = true;
+                        Ok(ServedShMemResponse::Id(client_id))
+                    }
+                    None => Ok(ServedShMemResponse::Rejected(
+                        ServedShMemStatus::UnknownParent,
+                    )),
+                }
             }
+            ServedShMemRequest::NewMap(map_size) => match self.provider.new_shmem(map_size) {
+                Ok(new_shmem) => {
+                    let description = new_shmem.description();
+                    let new_rc = Rc::new(RefCell::new(new_shmem));
+                    self.all_shmems
+                        .insert(description.id.into(), Rc::downgrade(&new_rc));
+                    Ok(ServedShMemResponse::Mapping(new_rc))
+                }
+                Err(err) => {
+                    libafl_log!(
+                        Warn,
+                        "Could not allocate a map of size {}: {:?}",
+                        map_size,
+                        err
+                    );
+                    Ok(ServedShMemResponse::Rejected(
+                        ServedShMemStatus::AllocationFailed,
+                    ))
+                }
+            },
             ServedShMemRequest::ExistingMap(description) => {
-                let client = self.clients.get_mut(&client_id).unwrap();
                 let description_id: i32 = description.id.into();
-                if client.maps.contains_key(&description_id) {
-                    // Using let else here as self needs to be accessed in the else branch.
-                    #[allow(clippy::option_if_let_else)]
-                    Ok(ServedShMemResponse::Mapping(
-                        if let Some(map) = client
-                            .maps
-                            .get_mut(&description_id)
-                            .as_mut()
-                            .unwrap()
-                            .first()
-                            .as_mut()
-                        {
-                            map.clone()
-                        } else {
-                            self.upgrade_shmem_with_id(description_id)
-                        },
-                    ))
-                } else {
-                    Ok(ServedShMemResponse::Mapping(
-                        self.upgrade_shmem_with_id(description_id),
-                    ))
+                let map = client
+                    .maps
+                    .get(&description_id)
+                    .and_then(|maps| maps.first())
+                    .cloned()
+                    .or_else(|| self.all_shmems.get(&description_id).and_then(Weak::upgrade));
+                match map {
+                    Some(map) => Ok(ServedShMemResponse::Mapping(map)),
+                    None => Ok(ServedShMemResponse::Rejected(ServedShMemStatus::UnknownMap)),
                 }
             }
             ServedShMemRequest::Deregister(map_id) => {
-                let client = self.clients.get_mut(&client_id).unwrap();
-                let maps = client.maps.entry(map_id).or_default();
-                if maps.is_empty() {
-                    Ok(ServedShMemResponse::RefCount(0_u32))
-                } else {
-                    Ok(ServedShMemResponse::RefCount(
-                        Rc::strong_count(&maps.pop().unwrap()) as u32,
-                    ))
+                match client.maps.get_mut(&map_id).and_then(Vec::pop) {
+                    Some(map) => Ok(ServedShMemResponse::RefCount(Rc::strong_count(&map) as u32)),
+                    None => Ok(ServedShMemResponse::Rejected(ServedShMemStatus::UnknownMap)),
                 }
             }
             ServedShMemRequest::Exit => {
-                println!("ShMemService - Exiting");
+                libafl_log!(Info, "ShMemService - Exiting");
                 // stopping the server
                 return Err(Error::ShuttingDown);
             }
@@ -601,23 +953,43 @@ where
 
         match response {
             ServedShMemResponse::Mapping(mapping) => {
-                let id = mapping.as_ref().borrow().id();
+                let (id, size) = {
+                    let map = mapping.as_ref().borrow();
+                    (map.id(), map.len())
+                };
                 let server_fd: i32 = id.to_string().parse().unwrap();
                 let client = self.clients.get_mut(&client_id).unwrap();
-                client
-                    .stream
-                    .send_fds(id.to_string().as_bytes(), &[server_fd])?;
+                client.stream.send_fds(
+                    &encode_response(ServedShMemStatus::Ok, server_fd, size),
+                    &[server_fd],
+                )?;
                 client.maps.entry(server_fd).or_default().push(mapping);
             }
             ServedShMemResponse::Id(id) => {
                 let client = self.clients.get_mut(&client_id).unwrap();
-                client.stream.send_fds(id.to_string().as_bytes(), &[])?;
+                client
+                    .stream
+                    .send_fds(&encode_response(ServedShMemStatus::Ok, id, 0), &[])?;
             }
+            #[allow(clippy::cast_possible_wrap)]
             ServedShMemResponse::RefCount(refcount) => {
+                let client = self.clients.get_mut(&client_id).unwrap();
+                client.stream.send_fds(
+                    &encode_response(ServedShMemStatus::Ok, refcount as i32, 0),
+                    &[],
+                )?;
+            }
+            ServedShMemResponse::Rejected(status) => {
+                libafl_log!(
+                    Debug,
+                    "Rejecting a request of client {}: {:?}",
+                    client_id,
+                    status
+                );
                 let client = self.clients.get_mut(&client_id).unwrap();
                 client
                     .stream
-                    .send_fds(refcount.to_string().as_bytes(), &[])?;
+                    .send_fds(&encode_response(status, -1, 0), &[])?;
             }
         }
         Ok(())
@@ -656,7 +1028,7 @@ where
                 Ok(num_fds) if num_fds > 0 => (),
                 Ok(_) => continue,
                 Err(e) => {
-                    println!("Error polling for activity: {:?}", e);
+                    libafl_log!(Warn, "Error polling for activity: {:?}", e);
                     continue;
                 }
             };
@@ -672,7 +1044,12 @@ where
                         match self.handle_client(raw_polled_fd) {
                             Ok(()) => (),
                             Err(e) => {
-                                dbg!("Ignoring failed read from client", e, poll_fd);
+                                libafl_log!(
+                                    Debug,
+                                    "Ignoring failed read from client {:?}: {:?}",
+                                    poll_fd,
+                                    e
+                                );
                                 continue;
                             }
                         };
@@ -680,12 +1057,12 @@ where
                         let (stream, _addr) = match listener.accept_unix_addr() {
                             Ok(stream_val) => stream_val,
                             Err(e) => {
-                                println!("Error accepting client: {:?}", e);
+                                libafl_log!(Warn, "Error accepting client: {:?}", e);
                                 continue;
                             }
                         };
 
-                        println!("Recieved connection from {:?}", _addr);
+                        libafl_log!(Debug, "Recieved connection from {:?}", _addr);
                         let pollfd = PollFd::new(
                             stream.as_raw_fd(),
                             PollFlags::POLLIN | PollFlags::POLLRDNORM | PollFlags::POLLRDBAND,
@@ -697,11 +1074,11 @@ where
                         match self.handle_client(client_id) {
                             Ok(()) => (),
                             Err(Error::ShuttingDown) => {
-                                println!("Shutting down");
+                                libafl_log!(Info, "Shutting down");
                                 return Ok(());
                             }
                             Err(e) => {
-                                dbg!("Ignoring failed read from client", e);
+                                libafl_log!(Debug, "Ignoring failed read from client: {:?}", e);
                             }
                         };
                     }
@@ -712,3 +1089,330 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(target_os = "android"))]
+    use core::{marker::PhantomData, mem::ManuallyDrop, time::Duration};
+    use serial_test::serial;
+    #[cfg(not(target_os = "android"))]
+    use std::{
+        io::{ErrorKind, Read, Write},
+        os::unix::{io::AsRawFd, net::UnixStream},
+        thread::{self, JoinHandle},
+        time::Instant,
+    };
+    #[cfg(not(target_os = "android"))]
+    use uds::{UnixSocketAddr, UnixStreamExt};
+
+    use crate::bolts::{
+        os::unix_shmem_server::{
+            decode_response, encode_response, ServedShMemProvider, ServedShMemStatus,
+        },
+        shmem::{ShMem, ShMemProvider},
+        AsMutSlice, AsSlice,
+    };
+    #[cfg(not(target_os = "android"))]
+    use crate::{
+        bolts::{
+            os::unix_shmem_server::{
+                ServedShMem, ServedShMemRequest, ServedShMemServiceWorker, ShMemService,
+                SharedShMemClient, RESPONSE_LEN, UNIX_SERVER_NAME,
+            },
+            shmem::{MmapShMemProvider, ShMemDescription},
+        },
+        Error,
+    };
+
+    #[test]
+    fn test_response_framing() {
+        assert_eq!(
+            decode_response(&encode_response(ServedShMemStatus::Ok, 42, 1 << 33)).unwrap(),
+            (ServedShMemStatus::Ok, 42, 1 << 33)
+        );
+        assert_eq!(
+            decode_response(&encode_response(ServedShMemStatus::UnknownMap, -1, 0)).unwrap(),
+            (ServedShMemStatus::UnknownMap, -1, 0)
+        );
+        let mut response = encode_response(ServedShMemStatus::Ok, 0, 0);
+        response[0] = 0xff;
+        assert!(decode_response(&response).is_err());
+    }
+
+    /// A service worker with a single client, connected over a socket pair, as the client side
+    #[cfg(not(target_os = "android"))]
+    struct TestClient {
+        worker: ServedShMemServiceWorker<MmapShMemProvider>,
+        stream: UnixStream,
+        client_id: i32,
+    }
+
+    #[cfg(not(target_os = "android"))]
+    impl TestClient {
+        fn new() -> Self {
+            let mut worker = ServedShMemServiceWorker::<MmapShMemProvider>::new().unwrap();
+            let (stream, server_stream) = UnixStream::pair().unwrap();
+            let client = SharedShMemClient::new(server_stream);
+            let client_id = client.stream.as_raw_fd();
+            worker.clients.insert(client_id, client);
+            Self {
+                worker,
+                stream,
+                client_id,
+            }
+        }
+
+        /// Lets the worker handle a request, returning the status and the id of the response
+        fn request(&mut self, request: ServedShMemRequest) -> (ServedShMemStatus, i32) {
+            let body = postcard::to_allocvec(&request).unwrap();
+            self.stream
+                .write_all(&(body.len() as u32).to_be_bytes())
+                .unwrap();
+            self.stream.write_all(&body).unwrap();
+            self.worker.handle_client(self.client_id).unwrap();
+
+            let mut response = [0_u8; RESPONSE_LEN];
+            let mut fd_buf = [-1; 1];
+            self.stream.recv_fds(&mut response, &mut fd_buf).unwrap();
+            let (status, id, _) = decode_response(&response).unwrap();
+            (status, id)
+        }
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn test_served_not_registered() {
+        let mut client = TestClient::new();
+        assert_eq!(
+            client.request(ServedShMemRequest::NewMap(4096)),
+            (ServedShMemStatus::NotRegistered, -1)
+        );
+        assert_eq!(
+            client.request(ServedShMemRequest::PreFork()).0,
+            ServedShMemStatus::NotRegistered
+        );
+        assert_eq!(
+            client.request(ServedShMemRequest::Hello()).0,
+            ServedShMemStatus::Ok
+        );
+        assert_eq!(
+            client.request(ServedShMemRequest::NewMap(4096)).0,
+            ServedShMemStatus::Ok
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn test_served_already_registered() {
+        let mut client = TestClient::new();
+        assert_eq!(
+            client.request(ServedShMemRequest::Hello()),
+            (ServedShMemStatus::Ok, client.client_id)
+        );
+        assert_eq!(
+            client.request(ServedShMemRequest::Hello()).0,
+            ServedShMemStatus::AlreadyRegistered
+        );
+        assert_eq!(
+            client
+                .request(ServedShMemRequest::PostForkChildHello(client.client_id))
+                .0,
+            ServedShMemStatus::AlreadyRegistered
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn test_served_unknown_existing_map() {
+        let mut client = TestClient::new();
+        client.request(ServedShMemRequest::Hello());
+        assert_eq!(
+            client.request(ServedShMemRequest::ExistingMap(
+                ShMemDescription::from_string_and_size("123456", 4096)
+            )),
+            (ServedShMemStatus::UnknownMap, -1)
+        );
+
+        let (status, map_id) = client.request(ServedShMemRequest::NewMap(4096));
+        assert_eq!(status, ServedShMemStatus::Ok);
+        assert_eq!(
+            client.request(ServedShMemRequest::ExistingMap(
+                ShMemDescription::from_string_and_size(&map_id.to_string(), 4096)
+            )),
+            (ServedShMemStatus::Ok, map_id)
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn test_served_unknown_deregister() {
+        let mut client = TestClient::new();
+        client.request(ServedShMemRequest::Hello());
+        assert_eq!(
+            client.request(ServedShMemRequest::Deregister(123_456)).0,
+            ServedShMemStatus::UnknownMap
+        );
+
+        // A map can only be deregistered as often as the client got it
+        let (_, map_id) = client.request(ServedShMemRequest::NewMap(4096));
+        assert_eq!(
+            client.request(ServedShMemRequest::Deregister(map_id)),
+            (ServedShMemStatus::Ok, 1)
+        );
+        assert_eq!(
+            client.request(ServedShMemRequest::Deregister(map_id)).0,
+            ServedShMemStatus::UnknownMap
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn test_served_unknown_parent() {
+        let mut client = TestClient::new();
+        assert_eq!(
+            client
+                .request(ServedShMemRequest::PostForkChildHello(123_456))
+                .0,
+            ServedShMemStatus::UnknownParent
+        );
+        // The rejected child is still not registered
+        assert_eq!(
+            client.request(ServedShMemRequest::NewMap(4096)).0,
+            ServedShMemStatus::NotRegistered
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn test_served_send_receive_timeout() {
+        /// A server answering each request with an `Ok` for id 7, after `delay`
+        fn slow_server(mut stream: UnixStream, delay: Duration) -> JoinHandle<()> {
+            thread::spawn(move || {
+                let mut size = [0_u8; 4];
+                stream.read_exact(&mut size).unwrap();
+                let mut body = vec![0_u8; u32::from_be_bytes(size) as usize];
+                stream.read_exact(&mut body).unwrap();
+                thread::sleep(delay);
+                stream
+                    .write_all(&encode_response(ServedShMemStatus::Ok, 7, 0))
+                    .unwrap();
+            })
+        }
+
+        let (stream, server_stream) = UnixStream::pair().unwrap();
+        let mut provider = ServedShMemProvider::<MmapShMemProvider> {
+            stream,
+            inner: MmapShMemProvider::new().unwrap(),
+            id: -1,
+            timeout: None,
+            desynced: false,
+            service_name: "unused".into(),
+            service: ShMemService::Failed {
+                err_msg: "Not started in this test".into(),
+                phantom: PhantomData,
+            },
+        };
+
+        // A fast enough server
+        let server = slow_server(server_stream, Duration::from_millis(0));
+        assert_eq!(
+            provider
+                .send_receive_timeout(ServedShMemRequest::PreFork(), Duration::from_secs(5))
+                .unwrap(),
+            (7, -1, 0)
+        );
+        server.join().unwrap();
+
+        // A server too slow for the timeout
+        let (stream, server_stream) = UnixStream::pair().unwrap();
+        provider.stream = stream;
+        provider.set_timeout(Some(Duration::from_millis(50)));
+        let server = slow_server(server_stream, Duration::from_millis(500));
+        let start = Instant::now();
+        match provider.pre_fork() {
+            Err(Error::File(err)) => assert_eq!(err.kind(), ErrorKind::TimedOut),
+            res => panic!("Expected a timeout, got {:?}", res),
+        }
+        assert!(start.elapsed() < Duration::from_millis(500));
+        server.join().unwrap();
+        // The late response arrived, but is never taken as the response to a later request
+        match provider.pre_fork() {
+            Err(Error::IllegalState(_)) => (),
+            res => panic!("Expected the provider to be out of sync, got {:?}", res),
+        }
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn test_served_release_dead_server() {
+        let (stream, server_stream) = UnixStream::pair().unwrap();
+        let mut provider = ServedShMemProvider::<MmapShMemProvider> {
+            stream,
+            inner: MmapShMemProvider::new().unwrap(),
+            id: -1,
+            timeout: None,
+            desynced: false,
+            service_name: "unused".into(),
+            service: ShMemService::Failed {
+                err_msg: "Not started in this test".into(),
+                phantom: PhantomData,
+            },
+        };
+        let mut map = ServedShMem {
+            inner: ManuallyDrop::new(provider.inner.new_shmem(4096).unwrap()),
+            server_fd: 1,
+        };
+
+        // The server is gone, releasing the map reports it instead of panicking
+        drop(server_stream);
+        assert!(provider.release_shmem(&mut map).is_err());
+        unsafe {
+            ManuallyDrop::drop(&mut map.inner);
+        }
+    }
+
+    #[test]
+    #[serial]
+    #[cfg(not(target_os = "android"))]
+    fn test_served_map_size() {
+        let mut provider = ServedShMemProvider::<MmapShMemProvider>::new().unwrap();
+        let map = provider.new_shmem(4096).unwrap();
+        assert_eq!(map.len(), 4096);
+
+        let existing = provider
+            .shmem_from_id_and_size(map.id(), map.len())
+            .unwrap();
+        assert_eq!(existing.len(), 4096);
+    }
+
+    #[test]
+    #[cfg(not(target_os = "android"))]
+    fn test_served_unique_service() {
+        // Not serial, the service is independent of the default one, and of other tests
+        let service = ShMemService::<MmapShMemProvider>::start_unique();
+        let name = service.name().unwrap().to_string();
+        assert!(name.starts_with(UNIX_SERVER_NAME));
+        assert_ne!(
+            ShMemService::<MmapShMemProvider>::start_unique().name(),
+            Some(name.as_str())
+        );
+
+        let mut provider = ServedShMemProvider::with_service(service.clone()).unwrap();
+        let mut map = provider.new_shmem(1024).unwrap();
+        map.as_mut_slice()[..4].copy_from_slice(b"test");
+
+        // Another client gets the same map from the service
+        let mut other = ServedShMemProvider::with_service(service.clone()).unwrap();
+        let existing = other.shmem_from_id_and_size(map.id(), map.len()).unwrap();
+        assert_eq!(existing.len(), 1024);
+        assert_eq!(&existing.as_slice()[..4], b"test");
+
+        service.shutdown();
+        assert!(UnixStream::connect_to_unix_addr(&UnixSocketAddr::new(&name).unwrap()).is_err());
+        let failed = ShMemService::<MmapShMemProvider>::Failed {
+            err_msg: "Not started in this test".into(),
+            phantom: PhantomData,
+        };
+        assert!(ServedShMemProvider::with_service(failed).is_err());
+    }
+}
@@ -10,6 +10,11 @@ use alloc::{
 use core::{clone::Clone, fmt::Debug, slice};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+#[cfg(all(feature = "std", unix))]
+use crate::bolts::fs::MmapFile;
+#[cfg(all(feature = "std", unix))]
+use alloc::sync::Arc;
+
 /// Trait to convert into an Owned type
 pub trait IntoOwned {
     /// Returns if the current type is an owned type.
@@ -178,6 +183,21 @@ enum OwnedSliceInner<'a, T: 'a + Sized> {
     Ref(&'a [T]),
     /// A ref to an owned [`Vec`]
     Owned(Vec<T>),
+    /// A file mapped into memory, only ever constructed for `u8`
+    #[cfg(all(feature = "std", unix))]
+    Mmap(Arc<MmapFile>),
+}
+
+/// Get the content of a mapped file as slice of `T`
+#[cfg(all(feature = "std", unix))]
+fn mmap_as_slice<T>(map: &MmapFile) -> &[T] {
+    let bytes = map.as_slice();
+    unsafe {
+        slice::from_raw_parts(
+            bytes.as_ptr() as *const T,
+            bytes.len() / core::mem::size_of::<T>(),
+        )
+    }
 }
 
 impl<'a, T: 'a + Sized + Serialize> Serialize for OwnedSliceInner<'a, T> {
@@ -191,6 +211,8 @@ impl<'a, T: 'a + Sized + Serialize> Serialize for OwnedSliceInner<'a, T> {
             },
             OwnedSliceInner::Ref(r) => r.serialize(se),
             OwnedSliceInner::Owned(b) => b.serialize(se),
+            #[cfg(all(feature = "std", unix))]
+            OwnedSliceInner::Mmap(m) => mmap_as_slice::<T>(m).serialize(se),
         }
     }
 }
@@ -275,6 +297,27 @@ impl<'a, T> From<&'a [T]> for OwnedSlice<'a, T> {
     }
 }
 
+/// Create a new [`OwnedSlice`] from a file mapped into memory.
+/// The mapping stays alive as long as the [`OwnedSlice`], e.g. for a whole run of the target.
+#[cfg(all(feature = "std", unix))]
+impl<'a> From<MmapFile> for OwnedSlice<'a, u8> {
+    fn from(map: MmapFile) -> Self {
+        Self {
+            inner: OwnedSliceInner::Mmap(Arc::new(map)),
+        }
+    }
+}
+
+/// Create a new [`OwnedSlice`] from a shared file mapping, without mapping the file again
+#[cfg(all(feature = "std", unix))]
+impl<'a> From<Arc<MmapFile>> for OwnedSlice<'a, u8> {
+    fn from(map: Arc<MmapFile>) -> Self {
+        Self {
+            inner: OwnedSliceInner::Mmap(map),
+        }
+    }
+}
+
 /// Create a new [`OwnedSlice`] from a [`OwnedSliceMut`]
 impl<'a, T> From<OwnedSliceMut<'a, T>> for OwnedSlice<'a, T> {
     fn from(mut_slice: OwnedSliceMut<'a, T>) -> Self {
@@ -296,6 +339,8 @@ impl<'a, T: Sized> AsSlice<T> for OwnedSlice<'a, T> {
             OwnedSliceInner::Ref(r) => r,
             OwnedSliceInner::RefRaw(rr, len) => unsafe { slice::from_raw_parts(*rr, *len) },
             OwnedSliceInner::Owned(v) => v.as_slice(),
+            #[cfg(all(feature = "std", unix))]
+            OwnedSliceInner::Mmap(m) => mmap_as_slice(m),
         }
     }
 }
@@ -309,6 +354,9 @@ where
         match self.inner {
             OwnedSliceInner::RefRaw(_, _) | OwnedSliceInner::Ref(_) => false,
             OwnedSliceInner::Owned(_) => true,
+            // The mapping does not borrow anything
+            #[cfg(all(feature = "std", unix))]
+            OwnedSliceInner::Mmap(_) => true,
         }
     }
 
@@ -324,6 +372,10 @@ where
             OwnedSliceInner::Owned(v) => Self {
                 inner: OwnedSliceInner::Owned(v),
             },
+            #[cfg(all(feature = "std", unix))]
+            OwnedSliceInner::Mmap(m) => Self {
+                inner: OwnedSliceInner::Mmap(m),
+            },
         }
     }
 }
@@ -641,3 +641,67 @@ macro_rules! impl_serdeany {
         }
     };
 }
+
+/// Registers each of the given [`SerdeAny`] types in the [`RegistryBuilder`], so they can be deserialized,
+/// e.g. `register_metadata_types!(Tokens, QemuCmpsMapMetadata);`.
+/// On `std`, [`crate::impl_serdeany`] registers each type on startup already, and registering it again is harmless.
+/// On `no_std`, call this once with all the metadata types of the fuzzer, before (de)serializing any state:
+/// deserializing a type that was not registered fails at runtime.
+#[macro_export]
+macro_rules! register_metadata_types {
+    ($($type_name:ty),+ $(,)?) => {
+        $(
+            $crate::bolts::serdeany::RegistryBuilder::register::<$type_name>();
+        )+
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+    use serde::{Deserialize, Serialize};
+
+    use crate::bolts::serdeany::SerdeAnyMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct FirstTestMetadata {
+        value: u32,
+    }
+
+    crate::impl_serdeany!(FirstTestMetadata);
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct SecondTestMetadata {
+        name: String,
+        entries: Vec<u64>,
+    }
+
+    crate::impl_serdeany!(SecondTestMetadata);
+
+    #[test]
+    fn test_register_metadata_types() {
+        crate::register_metadata_types!(FirstTestMetadata, SecondTestMetadata,);
+
+        let mut map = SerdeAnyMap::new();
+        map.insert(FirstTestMetadata { value: 1337 });
+        map.insert(SecondTestMetadata {
+            name: "second".into(),
+            entries: vec![1, 2, 3],
+        });
+
+        let serialized = postcard::to_allocvec(&map).unwrap();
+        let deserialized: SerdeAnyMap = postcard::from_bytes(&serialized).unwrap();
+        assert_eq!(deserialized.len(), 2);
+        assert_eq!(
+            deserialized.get::<FirstTestMetadata>(),
+            Some(&FirstTestMetadata { value: 1337 })
+        );
+        assert_eq!(
+            deserialized.get::<SecondTestMetadata>(),
+            Some(&SecondTestMetadata {
+                name: "second".into(),
+                entries: vec![1, 2, 3],
+            })
+        );
+    }
+}
@@ -5,7 +5,7 @@
 use crate::bolts::os::pipes::Pipe;
 use crate::{
     bolts::{AsMutSlice, AsSlice},
-    Error,
+    libafl_log, Error,
 };
 use alloc::{rc::Rc, string::ToString};
 use core::{
@@ -287,8 +287,9 @@ pub trait ShMemProvider: Clone + Default + Debug {
     }
 
     /// Release the resources associated with the given [`ShMem`]
-    fn release_shmem(&mut self, _shmem: &mut Self::ShMem) {
+    fn release_shmem(&mut self, _shmem: &mut Self::ShMem) -> Result<(), Error> {
         // do nothing
+        Ok(())
     }
 }
 
@@ -334,7 +335,15 @@ where
 
 impl<T: ShMemProvider> Drop for RcShMem<T> {
     fn drop(&mut self) {
-        self.provider.borrow_mut().release_shmem(&mut self.internal);
+        // There is no one to report this to, the map just stays around
+        if let Err(err) = self.provider.borrow_mut().release_shmem(&mut self.internal) {
+            libafl_log!(
+                Warn,
+                "Failed to release shared map {}: {:?}",
+                self.internal.id(),
+                err
+            );
+        }
     }
 }
 
@@ -395,8 +404,8 @@ where
         })
     }
 
-    fn release_shmem(&mut self, map: &mut Self::ShMem) {
-        self.internal.borrow_mut().release_shmem(&mut map.internal);
+    fn release_shmem(&mut self, map: &mut Self::ShMem) -> Result<(), Error> {
+        self.internal.borrow_mut().release_shmem(&mut map.internal)
     }
 
     fn clone_ref(&mut self, mapping: &Self::ShMem) -> Result<Self::ShMem, Error> {
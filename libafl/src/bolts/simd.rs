@@ -0,0 +1,184 @@
+//! Vectorized helpers for the hot loops over coverage maps, compiled with the `simd` feature.
+//! The vectorized paths are only used on `x86_64`, with scalar fallbacks elsewhere:
+//! they use `SSE2`, which all `x86_64` cpus have, or `AVX2`, if the cpu supports it, on `std`.
+
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::{
+    __m128i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_max_epu8, _mm_movemask_epi8, _mm_storeu_si128,
+};
+#[cfg(all(feature = "std", target_arch = "x86_64"))]
+use core::arch::x86_64::{
+    __m256i, _mm256_cmpeq_epi8, _mm256_loadu_si256, _mm256_max_epu8, _mm256_movemask_epi8,
+    _mm256_storeu_si256,
+};
+
+/// Merges `map` into `history`, keeping the maximum of each entry,
+/// as a `MaxMapFeedback` with `u8` entries does.
+/// Returns `true` if any entry of `map` exceeded its entry in `history`.
+///
+/// # Panics
+/// Panics if `history` is shorter than `map`
+#[inline]
+#[must_use]
+pub fn max_merge_u8(history: &mut [u8], map: &[u8]) -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        #[cfg(feature = "std")]
+        if std::is_x86_feature_detected!("avx2") {
+            return unsafe { max_merge_u8_avx2(history, map) };
+        }
+        max_merge_u8_sse2(history, map)
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        max_merge_u8_scalar(history, map)
+    }
+}
+
+/// The scalar version of [`max_merge_u8`], comparing one entry at a time
+///
+/// # Panics
+/// Panics if `history` is shorter than `map`
+#[must_use]
+pub fn max_merge_u8_scalar(history: &mut [u8], map: &[u8]) -> bool {
+    let mut novel = false;
+    for (old, &new) in history[..map.len()].iter_mut().zip(map) {
+        if new > *old {
+            *old = new;
+            novel = true;
+        }
+    }
+    novel
+}
+
+/// The `SSE2` version of [`max_merge_u8`], comparing 16 entries at a time
+///
+/// # Panics
+/// Panics if `history` is shorter than `map`
+#[cfg(target_arch = "x86_64")]
+#[allow(clippy::cast_ptr_alignment)] // The loads and stores are unaligned
+#[must_use]
+pub fn max_merge_u8_sse2(history: &mut [u8], map: &[u8]) -> bool {
+    const LANES: usize = 16;
+    let history = &mut history[..map.len()];
+    let vectorized = map.len() - map.len() % LANES;
+    let mut novel = false;
+    for i in (0..vectorized).step_by(LANES) {
+        // Safety: both slices hold at least `i + LANES` entries
+        unsafe {
+            let old = _mm_loadu_si128(history.as_ptr().add(i) as *const __m128i);
+            let new = _mm_loadu_si128(map.as_ptr().add(i) as *const __m128i);
+            let max = _mm_max_epu8(old, new);
+            // The max equals the history in all lanes, unless an entry is novel
+            if _mm_movemask_epi8(_mm_cmpeq_epi8(max, old)) != 0xffff {
+                _mm_storeu_si128(history.as_mut_ptr().add(i) as *mut __m128i, max);
+                novel = true;
+            }
+        }
+    }
+    max_merge_u8_scalar(&mut history[vectorized..], &map[vectorized..]) || novel
+}
+
+/// The `AVX2` version of [`max_merge_u8`], comparing 32 entries at a time
+///
+/// # Safety
+/// The cpu has to support `AVX2`
+#[cfg(all(feature = "std", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+#[allow(clippy::cast_ptr_alignment)] // The loads and stores are unaligned
+#[must_use]
+pub unsafe fn max_merge_u8_avx2(history: &mut [u8], map: &[u8]) -> bool {
+    const LANES: usize = 32;
+    let history = &mut history[..map.len()];
+    let vectorized = map.len() - map.len() % LANES;
+    let mut novel = false;
+    for i in (0..vectorized).step_by(LANES) {
+        let old = _mm256_loadu_si256(history.as_ptr().add(i) as *const __m256i);
+        let new = _mm256_loadu_si256(map.as_ptr().add(i) as *const __m256i);
+        let max = _mm256_max_epu8(old, new);
+        if _mm256_movemask_epi8(_mm256_cmpeq_epi8(max, old)) != -1 {
+            _mm256_storeu_si256(history.as_mut_ptr().add(i) as *mut __m256i, max);
+            novel = true;
+        }
+    }
+    max_merge_u8_scalar(&mut history[vectorized..], &map[vectorized..]) || novel
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    #[cfg(target_arch = "x86_64")]
+    use crate::bolts::simd::max_merge_u8_sse2;
+    use crate::bolts::{
+        rands::{Rand, StdRand},
+        simd::{max_merge_u8, max_merge_u8_scalar},
+    };
+
+    /// A random map, with most entries unset, as coverage maps are
+    fn random_map(rand: &mut StdRand, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                if rand.below(16) == 0 {
+                    rand.below(256) as u8
+                } else {
+                    0
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_max_merge_u8() {
+        let mut rand = StdRand::with_seed(1337);
+        for len in [0, 1, 15, 16, 17, 31, 32, 33, 100, 1 << 16] {
+            let mut scalar_history = random_map(&mut rand, len);
+            let mut history = scalar_history.clone();
+            for _ in 0..16 {
+                let map = random_map(&mut rand, len);
+                assert_eq!(
+                    max_merge_u8(&mut history, &map),
+                    max_merge_u8_scalar(&mut scalar_history, &map)
+                );
+                assert_eq!(history, scalar_history);
+                // The same map again is not novel
+                assert!(!max_merge_u8(&mut history, &map));
+
+                #[cfg(target_arch = "x86_64")]
+                {
+                    let mut sse2_history = random_map(&mut rand, len);
+                    let mut scalar_history = sse2_history.clone();
+                    assert_eq!(
+                        max_merge_u8_sse2(&mut sse2_history, &map),
+                        max_merge_u8_scalar(&mut scalar_history, &map)
+                    );
+                    assert_eq!(sse2_history, scalar_history);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_merge_u8_many_maps() {
+        const MAP_SIZE: usize = 1 << 16;
+        const RUNS: usize = 256;
+
+        let mut rand = StdRand::with_seed(0);
+        let maps: Vec<Vec<u8>> = (0..RUNS).map(|_| random_map(&mut rand, MAP_SIZE)).collect();
+
+        let mut history = vec![0_u8; MAP_SIZE];
+        let novel = maps
+            .iter()
+            .filter(|map| max_merge_u8_scalar(&mut history, map))
+            .count();
+
+        let mut fast_history = vec![0_u8; MAP_SIZE];
+        let fast_novel = maps
+            .iter()
+            .filter(|map| max_merge_u8(&mut fast_history, map))
+            .count();
+
+        assert_eq!(novel, fast_novel);
+        assert_eq!(history, fast_history);
+    }
+}
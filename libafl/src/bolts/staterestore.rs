@@ -23,6 +23,7 @@ use crate::{
 #[repr(C)]
 struct StateShMemContent {
     is_disk: bool,
+    is_exiting: bool,
     buf_len: usize,
     buf: [u8; 0],
 }
@@ -177,9 +178,20 @@ where
             drop(fs::remove_file(tmpfile));
         }
         content_mut.is_disk = false;
+        content_mut.is_exiting = false;
         content_mut.buf_len = 0;
     }
 
+    /// Marks this [`StateRestorer`] as exiting, telling the respawner that the client stopped on purpose.
+    pub fn send_exiting(&mut self) {
+        self.content_mut().is_exiting = true;
+    }
+
+    /// Returns true, if the client marked this [`StateRestorer`] as exiting, see [`StateRestorer::send_exiting`].
+    pub fn is_exiting(&self) -> bool {
+        unsafe { read_volatile(&self.content().is_exiting) }
+    }
+
     fn content_mut(&mut self) -> &mut StateShMemContent {
         let ptr = self.shmem.as_slice().as_ptr();
         #[allow(clippy::cast_ptr_alignment)] // Beginning of the page will always be aligned
@@ -296,5 +308,11 @@ mod tests {
         state_restorer.reset();
         assert!(!state_restorer.has_content());
         assert!(!tmpfile.exists());
+
+        assert!(!state_restorer.is_exiting());
+        state_restorer.send_exiting();
+        assert!(state_restorer.is_exiting());
+        state_restorer.reset();
+        assert!(!state_restorer.is_exiting());
     }
 }
@@ -129,6 +129,10 @@ where
         self.inner.on_remove(state, idx, testcase)
     }
 
+    fn on_schedule(&self, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        self.inner.on_schedule(idx, testcase)
+    }
+
     fn next(&self, state: &mut S) -> Result<usize, Error> {
         if state
             .metadata()
@@ -55,7 +55,12 @@ where
     fn remove(&mut self, idx: usize) -> Result<Option<Testcase<I>>, Error> {
         let testcase = self.inner.remove(idx)?;
         if testcase.is_some() {
-            self.cached_indexes.borrow_mut().retain(|e| *e != idx);
+            let mut cached_indexes = self.cached_indexes.borrow_mut();
+            cached_indexes.retain(|e| *e != idx);
+            // The entries after the removed one moved down
+            for e in cached_indexes.iter_mut().filter(|e| **e > idx) {
+                *e -= 1;
+            }
         }
         Ok(testcase)
     }
@@ -66,20 +71,7 @@ where
         let testcase = { self.inner.get(idx)? };
         if testcase.borrow().input().is_none() {
             let _ = testcase.borrow_mut().load_input()?;
-            let mut borrowed_num = 0;
-            while self.cached_indexes.borrow().len() >= self.cache_max_len {
-                let removed = self.cached_indexes.borrow_mut().pop_front().unwrap();
-                if let Ok(mut borrowed) = self.inner.get(removed)?.try_borrow_mut() {
-                    *borrowed.input_mut() = None;
-                } else {
-                    self.cached_indexes.borrow_mut().push_back(removed);
-                    borrowed_num += 1;
-                    if self.cache_max_len == borrowed_num {
-                        break;
-                    }
-                }
-            }
-            self.cached_indexes.borrow_mut().push_back(idx);
+            self.cache_insert(idx)?;
         }
         Ok(testcase)
     }
@@ -101,6 +93,38 @@ impl<I> CachedOnDiskCorpus<I>
 where
     I: Input,
 {
+    /// The amount of entries with their input held in memory
+    #[must_use]
+    pub fn cached_count(&self) -> usize {
+        self.cached_indexes.borrow().len()
+    }
+
+    /// Deletes the files this corpus wrote for entries no longer in it, optionally renaming the files
+    /// of the entries to start with their index, see [`OnDiskCorpus::compact`].
+    /// Returns the amount of deleted files.
+    pub fn compact(&mut self, renumber: bool) -> Result<usize, Error> {
+        self.inner.compact(renumber)
+    }
+
+    /// Adds the entry at `idx` to the cached entries, evicting the inputs of the oldest ones from memory
+    fn cache_insert(&self, idx: usize) -> Result<(), Error> {
+        let mut borrowed_num = 0;
+        while self.cached_indexes.borrow().len() >= self.cache_max_len {
+            let removed = self.cached_indexes.borrow_mut().pop_front().unwrap();
+            if let Ok(mut borrowed) = self.inner.get(removed)?.try_borrow_mut() {
+                *borrowed.input_mut() = None;
+            } else {
+                self.cached_indexes.borrow_mut().push_back(removed);
+                borrowed_num += 1;
+                if self.cache_max_len == borrowed_num {
+                    break;
+                }
+            }
+        }
+        self.cached_indexes.borrow_mut().push_back(idx);
+        Ok(())
+    }
+
     /// Creates the [`CachedOnDiskCorpus`].
     pub fn new(dir_path: PathBuf, cache_max_len: usize) -> Result<Self, Error> {
         if cache_max_len == 0 {
@@ -168,3 +192,101 @@ pub mod pybind {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use crate::{
+        corpus::{CachedOnDiskCorpus, Corpus, Testcase},
+        inputs::{BytesInput, HasBytesVec},
+    };
+
+    #[test]
+    fn test_cached_iter() {
+        let mut corpus =
+            CachedOnDiskCorpus::<BytesInput>::new(PathBuf::from("target/.test/cached_iter"), 1)
+                .unwrap();
+        for i in 0..3_u8 {
+            corpus
+                .add(Testcase::new(BytesInput::new(vec![i; 4])))
+                .unwrap();
+        }
+        assert_eq!(corpus.cached_count(), 1);
+
+        // The evicted inputs get loaded back from disk while iterating
+        let mut count = 0;
+        for entry in corpus.iter() {
+            let (idx, testcase) = entry.unwrap();
+            assert_eq!(idx, count);
+            assert_eq!(
+                testcase.borrow().input().as_ref().unwrap().bytes(),
+                &[idx as u8; 4]
+            );
+            count += 1;
+        }
+        assert_eq!(count, corpus.count());
+        assert_eq!(*corpus.current(), None);
+
+        fs::remove_dir_all("target/.test/cached_iter").unwrap();
+    }
+
+    #[test]
+    fn test_cached_compact() {
+        let dir = PathBuf::from("target/.test/cached_compact");
+        let mut corpus = CachedOnDiskCorpus::<BytesInput>::new(dir.clone(), 1).unwrap();
+        for i in 0..4_u8 {
+            corpus
+                .add(Testcase::new(BytesInput::new(vec![i; 4])))
+                .unwrap();
+        }
+        // The removed entry leaves its input and its lock file behind
+        corpus.remove(1).unwrap();
+        // The files of another client sharing the directory
+        fs::write(dir.join("other"), b"other").unwrap();
+        fs::write(dir.join(".other.metadata"), b"{}").unwrap();
+        fs::write(dir.join(".other.lafl_lock"), b"").unwrap();
+        fs::create_dir_all(dir.join("subdir")).unwrap();
+
+        let files = || fs::read_dir(&dir).unwrap().count();
+        let before = files();
+        // Only the input gets deleted, the lock file stays to keep the name taken
+        assert_eq!(corpus.compact(false).unwrap(), 1);
+        assert_eq!(files(), before - 1);
+        assert_eq!(corpus.compact(false).unwrap(), 0);
+        assert!(dir.join("other").exists());
+        assert!(dir.join(".other.metadata").exists());
+        assert!(dir.join(".other.lafl_lock").exists());
+        assert!(dir.join("subdir").exists());
+
+        // The live entries are intact, also the evicted ones, loaded back from disk
+        let expected = [0_u8, 2, 3];
+        for (idx, i) in expected.iter().enumerate() {
+            let testcase = corpus.get(idx).unwrap().borrow();
+            assert_eq!(testcase.input().as_ref().unwrap().bytes(), &[*i; 4]);
+        }
+
+        // Renumbering renames the files in corpus order, nothing gets deleted
+        assert_eq!(corpus.compact(true).unwrap(), 0);
+        assert_eq!(corpus.compact(true).unwrap(), 0);
+        for (idx, i) in expected.iter().enumerate() {
+            let filename = corpus
+                .get(idx)
+                .unwrap()
+                .borrow()
+                .filename()
+                .clone()
+                .unwrap();
+            let filename = PathBuf::from(filename);
+            assert!(filename
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with(&format!("{:08}-", idx)));
+            assert_eq!(fs::read(filename).unwrap(), vec![*i; 4]);
+        }
+        assert_eq!(files(), before - 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
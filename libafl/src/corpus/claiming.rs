@@ -0,0 +1,218 @@
+//! The claiming corpus scheduler lets multiple clients coordinate through a shared memory map,
+//! so that they do not all pick the same newly-imported testcase at the same time.
+
+use ahash::AHasher;
+use core::{
+    cell::RefCell,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{shmem::ShMem, AsMutSlice},
+    corpus::{Corpus, CorpusScheduler, Testcase},
+    inputs::Input,
+    state::{HasCorpus, HasMetadata},
+    Error,
+};
+
+/// Default number of times the [`ClaimingCorpusScheduler`] asks the base scheduler for another testcase
+/// if the proposed one has been claimed by another client.
+pub const DEFAULT_CLAIM_RETRIES: usize = 8;
+
+/// The maximum number of slots probed in the shared claims table to claim a testcase.
+const MAX_CLAIM_PROBES: usize = 16;
+
+/// A testcase metadata saying that this client already went through the claiming process for this testcase.
+/// From then on, the testcase gets scheduled as usual.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClaimedMetadata {
+    /// If this client won the claim, else another client claimed it first.
+    pub won: bool,
+}
+
+crate::impl_serdeany!(ClaimedMetadata);
+
+/// A [`ClaimingCorpusScheduler`] wraps a `base` [`CorpusScheduler`] and shares a table of claimed testcases,
+/// identified by the hash of their input, with the other clients.
+/// The first time a testcase gets scheduled on a client, the client tries to claim it.
+/// If another client claimed it already, the testcase is deferred and the base scheduler gets asked again,
+/// so that the clients spread over the newly-shared entries instead of all fuzzing the same one.
+/// The coordination costs a hash and an atomic operation on the first schedule of each testcase.
+#[derive(Debug)]
+pub struct ClaimingCorpusScheduler<CS, I, S, SH>
+where
+    CS: CorpusScheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I>,
+    SH: ShMem,
+{
+    base: CS,
+    claims: RefCell<SH>,
+    retries: usize,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<CS, I, S, SH> CorpusScheduler<I, S> for ClaimingCorpusScheduler<CS, I, S, SH>
+where
+    CS: CorpusScheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I>,
+    SH: ShMem,
+{
+    /// Add an entry to the corpus and return its index
+    fn on_add(&self, state: &mut S, idx: usize) -> Result<(), Error> {
+        self.base.on_add(state, idx)
+    }
+
+    /// Replaces the testcase at the given idx
+    fn on_replace(&self, state: &mut S, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        self.base.on_replace(state, idx, testcase)
+    }
+
+    /// Removes an entry from the corpus, returning it if it was present.
+    fn on_remove(
+        &self,
+        state: &mut S,
+        idx: usize,
+        testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        self.base.on_remove(state, idx, testcase)
+    }
+
+    fn on_schedule(&self, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        self.base.on_schedule(idx, testcase)
+    }
+
+    /// Gets the next entry, skipping entries claimed by other clients
+    fn next(&self, state: &mut S) -> Result<usize, Error> {
+        let mut idx = self.base.next(state)?;
+        for _ in 0..self.retries {
+            let mut testcase = state.corpus().get(idx)?.borrow_mut();
+            if testcase.has_metadata::<ClaimedMetadata>() {
+                break;
+            }
+            let mut hasher = AHasher::new_with_keys(0, 0);
+            testcase.load_input()?.hash(&mut hasher);
+            let won = self.try_claim(hasher.finish());
+            testcase.add_metadata(ClaimedMetadata { won });
+            if won {
+                break;
+            }
+            drop(testcase);
+            idx = self.base.next(state)?;
+        }
+        Ok(idx)
+    }
+}
+
+impl<CS, I, S, SH> ClaimingCorpusScheduler<CS, I, S, SH>
+where
+    CS: CorpusScheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I>,
+    SH: ShMem,
+{
+    /// Creates a new [`ClaimingCorpusScheduler`] that wraps a `base` [`CorpusScheduler`].
+    /// All clients have to be created using (a mapping of) the same zero-initialized `claims` shared map.
+    #[must_use]
+    pub fn new(base: CS, claims: SH) -> Self {
+        Self::with_retries(base, claims, DEFAULT_CLAIM_RETRIES)
+    }
+
+    /// Creates a new [`ClaimingCorpusScheduler`] that asks the `base` [`CorpusScheduler`]
+    /// at most `retries` times for another testcase, if the proposed ones are claimed.
+    #[must_use]
+    pub fn with_retries(base: CS, claims: SH, retries: usize) -> Self {
+        Self {
+            base,
+            claims: RefCell::new(claims),
+            retries,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Get the base scheduler
+    pub fn base(&self) -> &CS {
+        &self.base
+    }
+
+    /// Get the base scheduler (as mutable borrow)
+    pub fn base_mut(&mut self) -> &mut CS {
+        &mut self.base
+    }
+
+    /// Try to claim the testcase with the given hash in the shared claims table.
+    /// Returns `false` if another client claimed it before.
+    /// If the table is (locally) full, the claim always succeeds.
+    #[allow(clippy::cast_ptr_alignment)]
+    fn try_claim(&self, hash: u64) -> bool {
+        // 0 marks an empty slot
+        let hash = hash.max(1);
+        let mut claims = self.claims.borrow_mut();
+        let slice = claims.as_mut_slice();
+        let slots_count = slice.len() / core::mem::size_of::<AtomicU64>();
+        if slots_count == 0 {
+            return true;
+        }
+        let slots = slice.as_mut_ptr() as *const AtomicU64;
+        for probe in 0..MAX_CLAIM_PROBES.min(slots_count) {
+            let slot_idx = (hash as usize).wrapping_add(probe) % slots_count;
+            // # Safety
+            // The shared map is page-aligned and at least `slots_count` slots big.
+            let slot = unsafe { &*slots.add(slot_idx) };
+            match slot.compare_exchange(0, hash, Ordering::SeqCst, Ordering::SeqCst) {
+                Ok(_) => return true,
+                Err(current) if current == hash => return false,
+                Err(_) => (),
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{
+            rands::StdRand,
+            shmem::{ShMemProvider, StdShMemProvider},
+        },
+        corpus::{
+            ClaimingCorpusScheduler, Corpus, CorpusScheduler, InMemoryCorpus, QueueCorpusScheduler,
+            Testcase,
+        },
+        inputs::BytesInput,
+        state::StdState,
+    };
+
+    #[test]
+    fn test_claiming_no_collision() {
+        let mut provider = StdShMemProvider::new().unwrap();
+        let claims = provider.new_shmem(4096).unwrap();
+        let claims_clone = provider.clone_ref(&claims).unwrap();
+
+        let new_state = || {
+            let mut corpus = InMemoryCorpus::<BytesInput>::new();
+            corpus.add(Testcase::new(b"seed_a".to_vec())).unwrap();
+            corpus.add(Testcase::new(b"seed_b".to_vec())).unwrap();
+            StdState::new(
+                StdRand::with_seed(0),
+                corpus,
+                InMemoryCorpus::<BytesInput>::new(),
+                (),
+            )
+        };
+        let mut state_1 = new_state();
+        let mut state_2 = new_state();
+
+        let scheduler_1 = ClaimingCorpusScheduler::new(QueueCorpusScheduler::new(), claims);
+        let scheduler_2 = ClaimingCorpusScheduler::new(QueueCorpusScheduler::new(), claims_clone);
+
+        let idx_1 = scheduler_1.next(&mut state_1).unwrap();
+        let idx_2 = scheduler_2.next(&mut state_2).unwrap();
+        assert_ne!(idx_1, idx_2);
+    }
+}
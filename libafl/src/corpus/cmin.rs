@@ -0,0 +1,317 @@
+//! The [`CminCorpusScheduler`] is a corpus scheduler for pure corpus minimization runs:
+//! it schedules every entry once, then computes a minimal subset of the corpus with the same coverage and stops.
+
+use alloc::{borrow::ToOwned, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{serdeany::SerdeAny, AsSlice, HasRefCnt},
+    corpus::{
+        Corpus, CorpusScheduler, FavFactor, IsFavoredMetadata, LenTimeMulFavFactor,
+        MinimizerCorpusScheduler, Testcase, TopRatedsMetadata,
+    },
+    feedbacks::MapIndexesMetadata,
+    inputs::Input,
+    state::{HasCorpus, HasMetadata, HasRand},
+    Error,
+};
+
+/// A testcase metadata marking a testcase that adds no coverage to the minimal set, so it can be evicted
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RedundantMetadata {}
+
+crate::impl_serdeany!(RedundantMetadata);
+
+/// A state metadata holding the result of a minimization run of the [`CminCorpusScheduler`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MinimalSetMetadata {
+    /// The indexes of the entries in the minimal set, sorted
+    pub entries: Vec<usize>,
+    /// The amount of covered map entries
+    pub covered: usize,
+}
+
+crate::impl_serdeany!(MinimalSetMetadata);
+
+impl MinimalSetMetadata {
+    /// The size of the minimal set
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the minimal set is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// A [`CorpusScheduler`] for minimization runs, that never mutates: use it with a fuzzer without mutational stages.
+/// It schedules each entry once, in order, starting after the current entry of the corpus.
+/// The coverage of each scheduled execution, the `M` metadata recorded by a [`crate::stages::CminCoverageStage`],
+/// is scored by the inner [`MinimizerCorpusScheduler`] once the next entry gets scheduled.
+/// Entries without `M` metadata add no coverage.
+/// Once all entries got scheduled, the favored entries of the minimizer form the minimal set:
+/// it gets stored as [`MinimalSetMetadata`], the other entries get marked with a [`RedundantMetadata`],
+/// and [`Error::ShuttingDown`] is returned, to stop the fuzz loop.
+#[derive(Debug, Clone)]
+pub struct CminCorpusScheduler<CS, F, I, M, S>
+where
+    CS: CorpusScheduler<I, S>,
+    F: FavFactor<I>,
+    I: Input,
+    M: AsSlice<usize> + SerdeAny + HasRefCnt,
+    S: HasCorpus<I> + HasMetadata,
+{
+    minimizer: MinimizerCorpusScheduler<CS, F, I, M, S>,
+}
+
+impl<CS, F, I, M, S> CorpusScheduler<I, S> for CminCorpusScheduler<CS, F, I, M, S>
+where
+    CS: CorpusScheduler<I, S>,
+    F: FavFactor<I>,
+    I: Input,
+    M: AsSlice<usize> + SerdeAny + HasRefCnt,
+    S: HasCorpus<I> + HasMetadata + HasRand,
+{
+    /// Adds an entry to the base scheduler, its coverage is only scored once it got scheduled
+    fn on_add(&self, state: &mut S, idx: usize) -> Result<(), Error> {
+        self.minimizer.base().on_add(state, idx)
+    }
+
+    /// Replaces the testcase at the given idx
+    fn on_replace(&self, state: &mut S, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        self.minimizer.on_replace(state, idx, testcase)
+    }
+
+    /// Removes an entry from the corpus
+    fn on_remove(
+        &self,
+        state: &mut S,
+        idx: usize,
+        testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        self.minimizer.on_remove(state, idx, testcase)
+    }
+
+    /// Forwards the scheduled entry to the minimizer
+    fn on_schedule(&self, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        self.minimizer.on_schedule(idx, testcase)
+    }
+
+    /// Scores the coverage of the last scheduled entry, then gets the next entry, or stops once all entries got scheduled
+    fn next(&self, state: &mut S) -> Result<usize, Error> {
+        let count = state.corpus().count();
+        if count == 0 {
+            return Err(Error::Empty("No entries in corpus".to_owned()));
+        }
+        let current = *state.corpus().current();
+        if let Some(cur) = current {
+            if cur < count && state.corpus().get(cur)?.borrow().has_metadata::<M>() {
+                self.minimizer.update_score(state, cur)?;
+            }
+        }
+        let id = current.map_or(0, |cur| cur + 1);
+        if id >= count {
+            self.minimize(state)?;
+            return Err(Error::ShuttingDown);
+        }
+        *state.corpus_mut().current_mut() = Some(id);
+        Ok(id)
+    }
+}
+
+impl<CS, F, I, M, S> CminCorpusScheduler<CS, F, I, M, S>
+where
+    CS: CorpusScheduler<I, S>,
+    F: FavFactor<I>,
+    I: Input,
+    M: AsSlice<usize> + SerdeAny + HasRefCnt,
+    S: HasCorpus<I> + HasMetadata + HasRand,
+{
+    /// Creates a new [`CminCorpusScheduler`] that wraps a `base` [`CorpusScheduler`]
+    pub fn new(base: CS) -> Self {
+        Self {
+            minimizer: MinimizerCorpusScheduler::new(base),
+        }
+    }
+
+    /// Get a reference to the inner minimizer
+    pub fn minimizer(&self) -> &MinimizerCorpusScheduler<CS, F, I, M, S> {
+        &self.minimizer
+    }
+
+    /// Computes the minimal set of the corpus from the favored entries of the minimizer,
+    /// and marks the redundant entries, see [`CminCorpusScheduler`].
+    /// Returns the size of the minimal set.
+    pub fn minimize(&self, state: &mut S) -> Result<usize, Error> {
+        let count = state.corpus().count();
+        // Favored marks from earlier runs are stale
+        for idx in 0..count {
+            drop(
+                state
+                    .corpus()
+                    .get(idx)?
+                    .borrow_mut()
+                    .metadata_mut()
+                    .remove::<IsFavoredMetadata>(),
+            );
+        }
+        self.minimizer.cull(state)?;
+
+        let mut entries = vec![];
+        for idx in 0..count {
+            let mut entry = state.corpus().get(idx)?.borrow_mut();
+            if entry.has_metadata::<IsFavoredMetadata>() {
+                drop(entry.metadata_mut().remove::<RedundantMetadata>());
+                entries.push(idx);
+            } else {
+                entry.add_metadata(RedundantMetadata {});
+            }
+        }
+
+        let len = entries.len();
+        let covered = state
+            .metadata()
+            .get::<TopRatedsMetadata>()
+            .map_or(0, |top_rated| top_rated.map.len());
+        state.add_metadata(MinimalSetMetadata { entries, covered });
+        Ok(len)
+    }
+}
+
+/// A [`CminCorpusScheduler`] with [`LenTimeMulFavFactor`], keeping quick and small [`Testcase`]`s`,
+/// minimizing the coverage registered in the [`MapIndexesMetadata`] of the entries, see [`crate::stages::CminCoverageStage`].
+pub type IndexesLenTimeCminCorpusScheduler<CS, I, S> =
+    CminCorpusScheduler<CS, LenTimeMulFavFactor<I>, I, MapIndexesMetadata, S>;
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{
+            cmin::{IndexesLenTimeCminCorpusScheduler, MinimalSetMetadata, RedundantMetadata},
+            Corpus, CorpusScheduler, InMemoryCorpus, QueueCorpusScheduler, Testcase,
+        },
+        events::NopEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        fuzzer::{Fuzzer, StdFuzzer},
+        inputs::{BytesInput, HasBytesVec},
+        observers::StdMapObserver,
+        stages::CminCoverageStage,
+        state::{HasCorpus, HasMetadata, StdState},
+        Error,
+    };
+
+    static mut CMIN_MAP: [u8; 8] = [0; 8];
+
+    /// The entries marked as redundant
+    fn redundant<S>(state: &S) -> Vec<usize>
+    where
+        S: HasCorpus<BytesInput>,
+    {
+        (0..state.corpus().count())
+            .filter(|&idx| {
+                state
+                    .corpus()
+                    .get(idx)
+                    .unwrap()
+                    .borrow()
+                    .has_metadata::<RedundantMetadata>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cmin_scheduler() {
+        // Each byte of an input covers the map entry at its value
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        for bytes in [
+            vec![1, 2],
+            vec![3],
+            vec![1, 2, 3],
+            vec![],
+            vec![4, 5],
+            vec![2, 4, 4],
+            vec![5, 5, 5],
+        ] {
+            corpus.add(Testcase::new(bytes)).unwrap();
+        }
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+
+        let observer = unsafe { StdMapObserver::new("cmin", &mut CMIN_MAP) };
+        let mut stages = tuple_list!(CminCoverageStage::new(&observer));
+        let scheduler = IndexesLenTimeCminCorpusScheduler::new(QueueCorpusScheduler::new());
+        let mut fuzzer = StdFuzzer::new(scheduler, (), ());
+        let mut harness = |input: &BytesInput| {
+            for &byte in input.bytes() {
+                unsafe { CMIN_MAP[byte as usize] = 1 };
+            }
+            ExitKind::Ok
+        };
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(observer),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        let mut scheduled = vec![];
+        let res = loop {
+            match fuzzer.fuzz_one(&mut stages, &mut executor, &mut state, &mut mgr) {
+                Ok(idx) => scheduled.push(idx),
+                Err(err) => break err,
+            }
+        };
+        // Each entry got scheduled, and executed, once, then the run stopped
+        assert!(matches!(res, Error::ShuttingDown));
+        assert_eq!(scheduled, (0..7).collect::<Vec<_>>());
+
+        // [1, 2], [3] and [4, 5] cover everything, the longer entries are redundant
+        let minimal = state.metadata().get::<MinimalSetMetadata>().unwrap();
+        assert_eq!(minimal.entries, vec![0, 1, 4]);
+        assert_eq!(minimal.len(), 3);
+        assert_eq!(minimal.covered, 5);
+        assert_eq!(redundant(&state), vec![2, 3, 5, 6]);
+    }
+
+    #[test]
+    fn test_cmin_scheduler_no_coverage() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        for i in 0..3_u8 {
+            corpus.add(Testcase::new(vec![i])).unwrap();
+        }
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+
+        // Without coverage recorded for the entries, the minimal set is empty, and nothing fails
+        let scheduler = IndexesLenTimeCminCorpusScheduler::new(QueueCorpusScheduler::new());
+        let res = loop {
+            if let Err(err) = scheduler.next(&mut state) {
+                break err;
+            }
+        };
+        assert!(matches!(res, Error::ShuttingDown));
+        assert!(state
+            .metadata()
+            .get::<MinimalSetMetadata>()
+            .unwrap()
+            .is_empty());
+        assert_eq!(redundant(&state), vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,195 @@
+//! Side-by-side hexdump diffs between a solution and its parent, to aid the manual triage of crashes.
+//! The parent of a solution is recorded in its [`ParentMetadata`] when it is found,
+//! if the fuzzer records parents, see [`crate::StdFuzzer::record_parents`].
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+
+use crate::{
+    bolts::AsSlice,
+    corpus::{Corpus, ParentMetadata},
+    inputs::{HasTargetBytes, Input},
+    state::HasMetadata,
+    Error,
+};
+
+/// The amount of bytes shown per line, on each side of the diff
+const BYTES_PER_LINE: usize = 8;
+
+/// Renders a side-by-side hexdump of `parent` and `solution`.
+/// Each line starts with a `!` if it holds a changed byte, and each changed byte is followed by a `*`.
+/// Bytes missing on one side, if the lengths differ, are shown as `--` and count as changed.
+#[must_use]
+pub fn hexdump_diff(parent: &[u8], solution: &[u8]) -> String {
+    let len = parent.len().max(solution.len());
+    let changed = (0..len)
+        .filter(|&i| parent.get(i) != solution.get(i))
+        .count();
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "parent: {} bytes, solution: {} bytes, {} changed",
+        parent.len(),
+        solution.len(),
+        changed
+    )
+    .unwrap();
+
+    for offset in (0..len).step_by(BYTES_PER_LINE) {
+        let end = (offset + BYTES_PER_LINE).min(len);
+        let line_changed = (offset..end).any(|i| parent.get(i) != solution.get(i));
+        write!(
+            out,
+            "{} {:08x}  ",
+            if line_changed { '!' } else { ' ' },
+            offset
+        )
+        .unwrap();
+        write_side(&mut out, parent, solution, offset);
+        out.push_str("  ");
+        write_side(&mut out, solution, parent, offset);
+        out.push('\n');
+    }
+    out
+}
+
+/// Writes the hex and ascii columns of `bytes` for the line at `offset`, marking the bytes differing from `other`
+fn write_side(out: &mut String, bytes: &[u8], other: &[u8], offset: usize) {
+    let mut ascii = String::with_capacity(BYTES_PER_LINE);
+    for i in offset..offset + BYTES_PER_LINE {
+        match bytes.get(i) {
+            Some(&b) => {
+                let mark = if other.get(i) == Some(&b) { ' ' } else { '*' };
+                write!(out, "{:02x}{}", b, mark).unwrap();
+                ascii.push(if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                });
+            }
+            None if i < other.len() => {
+                out.push_str("-- ");
+                ascii.push(' ');
+            }
+            None => {
+                out.push_str("   ");
+                ascii.push(' ');
+            }
+        }
+    }
+    write!(out, "|{}|", ascii).unwrap();
+}
+
+/// Loads the bytes of the testcase at `idx` in `corpus`
+fn load_bytes<C, I>(corpus: &C, idx: usize) -> Result<Vec<u8>, Error>
+where
+    C: Corpus<I>,
+    I: Input + HasTargetBytes,
+{
+    let mut testcase = corpus.get(idx)?.borrow_mut();
+    Ok(testcase.load_input()?.target_bytes().as_slice().to_vec())
+}
+
+/// Finds the testcase in `corpus` whose input is named `name`, see [`Input::generate_name`]
+fn find_by_name<C, I>(corpus: &C, name: &str) -> Result<Option<usize>, Error>
+where
+    C: Corpus<I>,
+    I: Input,
+{
+    for idx in 0..corpus.count() {
+        if corpus
+            .get(idx)?
+            .borrow_mut()
+            .load_input()?
+            .generate_name(idx)
+            == name
+        {
+            return Ok(Some(idx));
+        }
+    }
+    Ok(None)
+}
+
+/// Renders a [`hexdump_diff`] between the solution at `idx` in `solutions` and its parent in `corpus`,
+/// as recorded in the [`ParentMetadata`] of the solution.
+pub fn diff_with_parent<C, SC, I>(corpus: &C, solutions: &SC, idx: usize) -> Result<String, Error>
+where
+    C: Corpus<I>,
+    SC: Corpus<I>,
+    I: Input + HasTargetBytes,
+{
+    let parent_name = solutions
+        .get(idx)?
+        .borrow()
+        .metadata()
+        .get::<ParentMetadata>()
+        .ok_or_else(|| Error::KeyNotFound(format!("The solution {} has no parent recorded", idx)))?
+        .parent_name
+        .clone();
+    let parent_idx = find_by_name(corpus, &parent_name)?.ok_or_else(|| {
+        Error::KeyNotFound(format!(
+            "The parent {} of the solution {} is not in the corpus",
+            parent_name, idx
+        ))
+    })?;
+    let solution = load_bytes(solutions, idx)?;
+    let parent = load_bytes(corpus, parent_idx)?;
+    Ok(hexdump_diff(&parent, &solution))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        corpus::{
+            diff::{diff_with_parent, hexdump_diff},
+            Corpus, InMemoryCorpus, ParentMetadata, Testcase,
+        },
+        inputs::{BytesInput, Input},
+        state::HasMetadata,
+    };
+
+    #[test]
+    fn test_hexdump_diff() {
+        let diff = hexdump_diff(b"ABCDEFGHIJ", b"ABXDEFGHIJKL");
+        assert_eq!(
+            diff,
+            "parent: 10 bytes, solution: 12 bytes, 3 changed\n\
+             ! 00000000  41 42 43*44 45 46 47 48 |ABCDEFGH|  41 42 58*44 45 46 47 48 |ABXDEFGH|\n\
+             ! 00000008  49 4a -- --             |IJ      |  49 4a 4b*4c*            |IJKL    |\n"
+        );
+
+        let diff = hexdump_diff(b"same", b"same");
+        assert!(diff.starts_with("parent: 4 bytes, solution: 4 bytes, 0 changed\n  00000000"));
+        assert!(!diff.contains('*'));
+
+        // The parent is looked up by name, wherever it ended up in the corpus
+        let parent = BytesInput::new(b"ABCDEFGHIJ".to_vec());
+        let mut corpus = InMemoryCorpus::new();
+        corpus
+            .add(Testcase::new(BytesInput::new(b"other".to_vec())))
+            .unwrap();
+        corpus.add(Testcase::new(parent.clone())).unwrap();
+        let mut solutions = InMemoryCorpus::new();
+        let mut solution = Testcase::new(BytesInput::new(b"ABXDEFGHIJKL".to_vec()));
+        solution.add_metadata(ParentMetadata {
+            parent_name: parent.generate_name(0),
+        });
+        solutions.add(solution).unwrap();
+        solutions
+            .add(Testcase::new(BytesInput::new(b"orphan".to_vec())))
+            .unwrap();
+        let mut lost = Testcase::new(BytesInput::new(b"lost".to_vec()));
+        lost.add_metadata(ParentMetadata {
+            parent_name: BytesInput::new(b"evicted".to_vec()).generate_name(0),
+        });
+        solutions.add(lost).unwrap();
+
+        assert_eq!(
+            diff_with_parent(&corpus, &solutions, 0).unwrap(),
+            hexdump_diff(b"ABCDEFGHIJ", b"ABXDEFGHIJKL")
+        );
+        assert!(diff_with_parent(&corpus, &solutions, 1).is_err());
+        assert!(diff_with_parent(&corpus, &solutions, 2).is_err());
+    }
+}
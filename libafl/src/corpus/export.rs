@@ -0,0 +1,448 @@
+//! Exports the solutions of an [`OnDiskCorpus`] as a single tarball, e.g. to attach the findings to a bug report.
+//! The tarball holds each input, its metadata as JSON, and a [`SolutionsManifest`] listing the backtrace buckets.
+//! Structured sanitizer data, a [`SolutionReport`] such as the frida `AsanErrors`, takes precedence over the backtrace hashes.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+#[cfg(unix)]
+use crate::feedbacks::triage::{CrashKind, CrashTriageMetadata, Exploitability};
+use crate::{
+    bolts::serdeany::SerdeAny,
+    corpus::{Corpus, OnDiskCorpus, Testcase},
+    feedbacks::BacktraceHashMetadata,
+    inputs::Input,
+    state::HasMetadata,
+    Error,
+};
+
+/// The name of the [`SolutionsManifest`] in the tarball
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// The directory holding the solutions in the tarball
+const SOLUTIONS_DIR: &str = "solutions";
+/// The size of a tar block
+const BLOCK_SIZE: usize = 512;
+/// The maximum length of a name in a tar header
+const NAME_LEN: usize = 100;
+
+/// Structured data about a solution, attached as metadata by a sanitizer, e.g. the frida `AsanErrors`.
+/// [`export_solutions_with_report`] prefers it over the [`BacktraceHashMetadata`] and [`CrashTriageMetadata`].
+pub trait SolutionReport: SerdeAny {
+    /// A short description of the error, e.g. `heap out-of-bounds read`
+    fn description(&self) -> String;
+
+    /// The hash of the backtrace of the error, used as the bucket of the solution, if known
+    fn backtrace_hash(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// A solution in the [`SolutionsManifest`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SolutionEntry {
+    /// The name of the input in the tarball
+    pub input: String,
+    /// The name of the metadata of the input in the tarball, as JSON
+    pub metadata: String,
+    /// The backtrace hash of the solution, from its [`SolutionReport`] or [`BacktraceHashMetadata`], if known
+    pub bucket: Option<u64>,
+    /// The description of the error, from the [`SolutionReport`] of the solution, if any
+    #[serde(default)]
+    pub report: Option<String>,
+    /// The kind of the crash, see [`CrashTriageMetadata`], if known
+    #[cfg(unix)]
+    pub kind: Option<CrashKind>,
+    /// The rated exploitability of the crash, see [`CrashTriageMetadata`], if known
+    #[cfg(unix)]
+    pub exploitability: Option<Exploitability>,
+}
+
+/// A backtrace bucket in the [`SolutionsManifest`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SolutionBucket {
+    /// The backtrace hash of the bucket
+    pub hash: u64,
+    /// The names of the inputs in the bucket, in the tarball
+    pub inputs: Vec<String>,
+}
+
+/// The manifest of an exported tarball
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolutionsManifest {
+    /// The solutions, in the order of the corpus
+    pub solutions: Vec<SolutionEntry>,
+    /// The backtrace buckets, in the order of their first solution
+    pub buckets: Vec<SolutionBucket>,
+}
+
+/// Writes the solutions in `corpus` to a tarball at `tarball`, with the inputs, their metadata and a manifest.
+/// An empty corpus results in a valid tarball, holding an empty manifest.
+/// Returns the manifest.
+pub fn export_solutions<I, P>(
+    corpus: &OnDiskCorpus<I>,
+    tarball: P,
+) -> Result<SolutionsManifest, Error>
+where
+    I: Input,
+    P: AsRef<Path>,
+{
+    export_solutions_inner(corpus, tarball, |_| None)
+}
+
+/// Writes the solutions in `corpus` to a tarball at `tarball`, as [`export_solutions`] does,
+/// using the [`SolutionReport`] `R` of each solution, if present, as the first-choice source of its manifest entry.
+pub fn export_solutions_with_report<I, R, P>(
+    corpus: &OnDiskCorpus<I>,
+    tarball: P,
+) -> Result<SolutionsManifest, Error>
+where
+    I: Input,
+    R: SolutionReport,
+    P: AsRef<Path>,
+{
+    export_solutions_inner(corpus, tarball, |testcase| {
+        testcase
+            .metadata()
+            .get::<R>()
+            .map(|report| (report.description(), report.backtrace_hash()))
+    })
+}
+
+/// Writes the tarball, with `report` looking up the description and backtrace hash of the error of each solution
+fn export_solutions_inner<I, P, F>(
+    corpus: &OnDiskCorpus<I>,
+    tarball: P,
+    report: F,
+) -> Result<SolutionsManifest, Error>
+where
+    I: Input,
+    P: AsRef<Path>,
+    F: Fn(&Testcase<I>) -> Option<(String, Option<u64>)>,
+{
+    let mut writer = BufWriter::new(File::create(tarball)?);
+    let mut manifest = SolutionsManifest::default();
+
+    for idx in 0..corpus.count() {
+        let testcase = corpus.get(idx)?.borrow();
+        let filename = PathBuf::from(testcase.filename().as_ref().ok_or_else(|| {
+            Error::IllegalState(format!("The solution {} is not stored on disk", idx))
+        })?);
+        let name = filename.file_name().unwrap().to_string_lossy().to_string();
+        let input = format!("{}/{}", SOLUTIONS_DIR, name);
+        let metadata = format!("{}/{}.metadata.json", SOLUTIONS_DIR, name);
+
+        write_entry(&mut writer, &input, &fs::read(&filename)?)?;
+        write_entry(
+            &mut writer,
+            &metadata,
+            &serde_json::to_vec_pretty(testcase.metadata())?,
+        )?;
+
+        let (report, report_hash) = match report(&testcase) {
+            Some((description, hash)) => (Some(description), hash),
+            None => (None, None),
+        };
+        let bucket = report_hash.or_else(|| {
+            testcase
+                .metadata()
+                .get::<BacktraceHashMetadata>()
+                .map(|meta| meta.hash)
+        });
+        if let Some(hash) = bucket {
+            match manifest.buckets.iter_mut().find(|b| b.hash == hash) {
+                Some(b) => b.inputs.push(input.clone()),
+                None => manifest.buckets.push(SolutionBucket {
+                    hash,
+                    inputs: vec![input.clone()],
+                }),
+            }
+        }
+        #[cfg(unix)]
+        let triage = testcase.metadata().get::<CrashTriageMetadata>();
+        manifest.solutions.push(SolutionEntry {
+            input,
+            metadata,
+            bucket,
+            report,
+            #[cfg(unix)]
+            kind: triage.map(|t| t.kind),
+            #[cfg(unix)]
+            exploitability: triage.map(|t| t.exploitability),
+        });
+    }
+
+    write_entry(
+        &mut writer,
+        MANIFEST_NAME,
+        &serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    // The end of the archive is marked by two empty blocks
+    writer.write_all(&[0; 2 * BLOCK_SIZE])?;
+    writer.flush()?;
+    Ok(manifest)
+}
+
+/// A tarball written by [`export_solutions`], read back
+#[derive(Debug, Clone)]
+pub struct SolutionsArchive {
+    /// The manifest of the tarball
+    pub manifest: SolutionsManifest,
+    /// The files in the tarball, with their names
+    pub files: Vec<(String, Vec<u8>)>,
+}
+
+impl SolutionsArchive {
+    /// Reads a tarball written by [`export_solutions`]
+    pub fn read<P>(tarball: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let data = fs::read(tarball)?;
+        let mut files = vec![];
+        let mut offset = 0;
+        while offset + BLOCK_SIZE <= data.len() {
+            let header = &data[offset..offset + BLOCK_SIZE];
+            if header.iter().all(|&b| b == 0) {
+                break;
+            }
+            if parse_octal(&header[148..156])? != checksum(header) {
+                return Err(Error::IllegalState(format!(
+                    "Invalid tar header checksum at offset {}",
+                    offset
+                )));
+            }
+            let name_len = header[..NAME_LEN]
+                .iter()
+                .position(|&b| b == 0)
+                .unwrap_or(NAME_LEN);
+            let name = String::from_utf8_lossy(&header[..name_len]).to_string();
+            let size = parse_octal(&header[124..136])? as usize;
+            offset += BLOCK_SIZE;
+            if offset + size > data.len() {
+                return Err(Error::IllegalState(format!("Truncated tar entry {}", name)));
+            }
+            files.push((name, data[offset..offset + size].to_vec()));
+            offset += size + padding(size);
+        }
+
+        let manifest = files
+            .iter()
+            .find(|(name, _)| name == MANIFEST_NAME)
+            .ok_or_else(|| Error::KeyNotFound(format!("No {} in the tarball", MANIFEST_NAME)))?;
+        Ok(Self {
+            manifest: serde_json::from_slice(&manifest.1)?,
+            files,
+        })
+    }
+
+    /// The content of the file with the given `name` in the tarball
+    #[must_use]
+    pub fn file(&self, name: &str) -> Option<&[u8]> {
+        self.files
+            .iter()
+            .find(|(file, _)| file == name)
+            .map(|(_, content)| content.as_slice())
+    }
+}
+
+/// Writes a file to the tarball, as `ustar` header followed by the content, padded to a full block
+fn write_entry<W>(writer: &mut W, name: &str, content: &[u8]) -> Result<(), Error>
+where
+    W: Write,
+{
+    if name.len() >= NAME_LEN {
+        return Err(Error::IllegalArgument(format!(
+            "The name {} is too long for a tar header",
+            name
+        )));
+    }
+    let mut header = [0_u8; BLOCK_SIZE];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], content.len() as u64);
+    write_octal(&mut header[136..148], 0);
+    // A regular file
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    let checksum = checksum(&header);
+    write_octal(&mut header[148..155], checksum);
+    header[155] = b' ';
+
+    writer.write_all(&header)?;
+    writer.write_all(content)?;
+    writer.write_all(&[0; BLOCK_SIZE][..padding(content.len())])?;
+    Ok(())
+}
+
+/// The checksum of a tar header, the sum of its bytes, with the checksum field counted as spaces
+fn checksum(header: &[u8]) -> u64 {
+    header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { b' ' } else { b })
+        .map(u64::from)
+        .sum()
+}
+
+/// Writes `value` as zero-padded octal number, terminated by a nul byte
+fn write_octal(field: &mut [u8], value: u64) {
+    let len = field.len() - 1;
+    let digits = format!("{:0width$o}", value, width = len);
+    field[..len].copy_from_slice(digits.as_bytes());
+    field[len] = 0;
+}
+
+/// Parses an octal number, terminated by a nul byte or a space
+fn parse_octal(field: &[u8]) -> Result<u64, Error> {
+    let digits = String::from_utf8_lossy(field);
+    let digits = digits.trim_matches(|c: char| c == '\0' || c == ' ');
+    u64::from_str_radix(digits, 8)
+        .map_err(|_| Error::IllegalState(format!("Invalid octal number {} in tar header", digits)))
+}
+
+/// The amount of bytes to pad `len` to a full block
+fn padding(len: usize) -> usize {
+    (BLOCK_SIZE - len % BLOCK_SIZE) % BLOCK_SIZE
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use std::{fs, path::PathBuf};
+
+    use crate::{
+        corpus::{
+            export::{
+                export_solutions, export_solutions_with_report, SolutionReport, SolutionsArchive,
+                MANIFEST_NAME,
+            },
+            Corpus, OnDiskCorpus, Testcase,
+        },
+        feedbacks::BacktraceHashMetadata,
+        inputs::BytesInput,
+        state::HasMetadata,
+    };
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct TestReport {
+        hash: Option<u64>,
+    }
+
+    crate::impl_serdeany!(TestReport);
+
+    impl SolutionReport for TestReport {
+        fn description(&self) -> String {
+            "heap out-of-bounds read".into()
+        }
+
+        fn backtrace_hash(&self) -> Option<u64> {
+            self.hash
+        }
+    }
+
+    #[test]
+    fn test_export_solutions() {
+        let dir = PathBuf::from("target/.test/export");
+        let tarball = PathBuf::from("target/.test/export.tar");
+
+        // No solutions yet, still a valid tarball
+        let mut corpus = OnDiskCorpus::<BytesInput>::new(&dir).unwrap();
+        let manifest = export_solutions(&corpus, &tarball).unwrap();
+        assert!(manifest.solutions.is_empty());
+        let archive = SolutionsArchive::read(&tarball).unwrap();
+        assert_eq!(archive.manifest, manifest);
+        assert_eq!(archive.files.len(), 1);
+
+        for (content, hash) in [
+            (&b"crash1"[..], 1_u64),
+            (&b"crash2"[..], 2),
+            (&b"crash3"[..], 1),
+        ] {
+            let mut testcase = Testcase::new(BytesInput::new(content.to_vec()));
+            testcase.add_metadata(BacktraceHashMetadata { hash });
+            corpus.add(testcase).unwrap();
+        }
+        // A long input, spanning multiple blocks
+        corpus
+            .add(Testcase::new(BytesInput::new(vec![0x41; 1337])))
+            .unwrap();
+
+        let manifest = export_solutions(&corpus, &tarball).unwrap();
+        let archive = SolutionsArchive::read(&tarball).unwrap();
+        assert_eq!(archive.manifest, manifest);
+        assert!(archive.file(MANIFEST_NAME).is_some());
+        assert_eq!(archive.manifest.solutions.len(), 4);
+        assert_eq!(archive.manifest.buckets.len(), 2);
+        assert_eq!(archive.manifest.buckets[0].hash, 1);
+        assert_eq!(
+            archive.manifest.buckets[0].inputs,
+            vec![
+                archive.manifest.solutions[0].input.clone(),
+                archive.manifest.solutions[2].input.clone()
+            ]
+        );
+        assert_eq!(archive.manifest.solutions[3].bucket, None);
+
+        let contents: [&[u8]; 4] = [b"crash1", b"crash2", b"crash3", &[0x41; 1337]];
+        for (solution, content) in archive.manifest.solutions.iter().zip(contents) {
+            assert_eq!(archive.file(&solution.input).unwrap(), content);
+            assert!(archive.file(&solution.metadata).is_some());
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_file(tarball).unwrap();
+    }
+
+    #[test]
+    fn test_export_solutions_with_report() {
+        let dir = PathBuf::from("target/.test/export_report");
+        let tarball = PathBuf::from("target/.test/export_report.tar");
+        let mut corpus = OnDiskCorpus::<BytesInput>::new(&dir).unwrap();
+
+        // The report wins over the backtrace hash
+        let mut testcase = Testcase::new(BytesInput::new(b"asan1".to_vec()));
+        testcase.add_metadata(BacktraceHashMetadata { hash: 1 });
+        testcase.add_metadata(TestReport { hash: Some(7) });
+        corpus.add(testcase).unwrap();
+        // A report without a backtrace falls back to the backtrace hash
+        let mut testcase = Testcase::new(BytesInput::new(b"asan2".to_vec()));
+        testcase.add_metadata(BacktraceHashMetadata { hash: 1 });
+        testcase.add_metadata(TestReport { hash: None });
+        corpus.add(testcase).unwrap();
+        // No report at all
+        let mut testcase = Testcase::new(BytesInput::new(b"crash".to_vec()));
+        testcase.add_metadata(BacktraceHashMetadata { hash: 2 });
+        corpus.add(testcase).unwrap();
+
+        let manifest = export_solutions_with_report::<_, TestReport, _>(&corpus, &tarball).unwrap();
+        let buckets: Vec<Option<u64>> = manifest.solutions.iter().map(|s| s.bucket).collect();
+        assert_eq!(buckets, vec![Some(7), Some(1), Some(2)]);
+        assert_eq!(
+            manifest.solutions[0].report.as_deref(),
+            Some("heap out-of-bounds read")
+        );
+        assert!(manifest.solutions[2].report.is_none());
+        assert_eq!(SolutionsArchive::read(&tarball).unwrap().manifest, manifest);
+
+        // Without the report type, only the backtrace hashes are used
+        let manifest = export_solutions(&corpus, &tarball).unwrap();
+        let buckets: Vec<Option<u64>> = manifest.solutions.iter().map(|s| s.bucket).collect();
+        assert_eq!(buckets, vec![Some(1), Some(1), Some(2)]);
+        assert!(manifest.solutions[0].report.is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_file(tarball).unwrap();
+    }
+}
@@ -123,6 +123,11 @@ where
         self.base.on_remove(state, idx, testcase)
     }
 
+    /// Forwards the scheduled entry to the base scheduler
+    fn on_schedule(&self, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        self.base.on_schedule(idx, testcase)
+    }
+
     /// Gets the next entry
     fn next(&self, state: &mut S) -> Result<usize, Error> {
         self.cull(state)?;
@@ -262,6 +267,11 @@ where
         &self.base
     }
 
+    /// Get a mutable reference to the base scheduler, e.g. to [`crate::corpus::QueueCorpusScheduler::reorder`] it
+    pub fn base_mut(&mut self) -> &mut CS {
+        &mut self.base
+    }
+
     /// Creates a new [`MinimizerCorpusScheduler`] that wraps a `base` [`CorpusScheduler`]
     /// and has a default probability to skip non-faved [`Testcase`]s of [`DEFAULT_SKIP_NON_FAVORED_PROB`].
     pub fn new(base: CS) -> Self {
@@ -291,3 +301,92 @@ pub type LenTimeMinimizerCorpusScheduler<CS, I, M, S> =
 /// that exercise all the entries registered in the [`MapIndexesMetadata`].
 pub type IndexesLenTimeMinimizerCorpusScheduler<CS, I, S> =
     MinimizerCorpusScheduler<CS, LenTimeMulFavFactor<I>, I, MapIndexesMetadata, S>;
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{
+            Corpus, CorpusScheduler, InMemoryCorpus, IndexesLenTimeMinimizerCorpusScheduler,
+            IsFavoredMetadata, QueueCorpusScheduler, Testcase,
+        },
+        events::NopEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        fuzzer::{Fuzzer, HasCorpusScheduler, StdFuzzer},
+        inputs::{BytesInput, Input},
+        state::{HasCorpus, HasMetadata, StdState},
+        Error,
+    };
+
+    /// Schedules like a queue, recording each scheduled entry
+    #[derive(Debug, Default)]
+    struct RecordingScheduler {
+        queue: QueueCorpusScheduler,
+        scheduled: RefCell<Vec<usize>>,
+    }
+
+    impl<I, S> CorpusScheduler<I, S> for RecordingScheduler
+    where
+        I: Input,
+        S: HasCorpus<I>,
+    {
+        fn on_schedule(&self, idx: usize, _testcase: &Testcase<I>) -> Result<(), Error> {
+            self.scheduled.borrow_mut().push(idx);
+            Ok(())
+        }
+
+        fn next(&self, state: &mut S) -> Result<usize, Error> {
+            self.queue.next(state)
+        }
+    }
+
+    #[test]
+    fn test_on_schedule() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        for i in 0..3_u8 {
+            let mut testcase = Testcase::new(vec![i]);
+            if i == 1 {
+                testcase.add_metadata(IsFavoredMetadata {});
+            }
+            corpus.add(testcase).unwrap();
+        }
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+
+        // Skips some of the entries that are not favored
+        let scheduler = IndexesLenTimeMinimizerCorpusScheduler::with_skip_prob(
+            RecordingScheduler::default(),
+            50,
+        );
+        let mut fuzzer = StdFuzzer::new(scheduler, (), ());
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        let picked: Vec<usize> = (0..10)
+            .map(|_| {
+                fuzzer
+                    .fuzz_one(&mut (), &mut executor, &mut state, &mut mgr)
+                    .unwrap()
+            })
+            .collect();
+
+        // Each pick is reported exactly once, skipped entries are not reported
+        assert_eq!(*fuzzer.scheduler().base().scheduled.borrow(), picked);
+        assert_eq!(*state.corpus().current(), picked.last().copied());
+    }
+}
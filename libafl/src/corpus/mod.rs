@@ -1,7 +1,10 @@
 //! Corpuses contain the testcases, either in memory, on disk, or somewhere else.
 
 pub mod testcase;
-pub use testcase::{PowerScheduleTestcaseMetaData, Testcase};
+pub use testcase::{
+    InitialEnergyMetadata, ParentMetadata, PowerScheduleTestcaseMetaData, Testcase,
+    TestcaseTagsMetadata, MAX_ENERGY, MIN_ENERGY,
+};
 
 pub mod inmemory;
 pub use inmemory::InMemoryCorpus;
@@ -16,6 +19,17 @@ pub mod cached;
 #[cfg(feature = "std")]
 pub use cached::CachedOnDiskCorpus;
 
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
+pub use export::{
+    export_solutions, export_solutions_with_report, SolutionReport, SolutionsArchive,
+    SolutionsManifest,
+};
+
+pub mod diff;
+pub use diff::{diff_with_parent, hexdump_diff};
+
 pub mod queue;
 pub use queue::QueueCorpusScheduler;
 
@@ -32,8 +46,16 @@ pub use minimizer::{
 pub mod powersched;
 pub use powersched::PowerQueueCorpusScheduler;
 
-use alloc::borrow::ToOwned;
-use core::cell::RefCell;
+pub mod cmin;
+pub use cmin::{CminCorpusScheduler, IndexesLenTimeCminCorpusScheduler};
+
+#[cfg(feature = "std")]
+pub mod claiming;
+#[cfg(feature = "std")]
+pub use claiming::ClaimingCorpusScheduler;
+
+use alloc::{borrow::ToOwned, vec::Vec};
+use core::{cell::RefCell, marker::PhantomData};
 
 use crate::{
     bolts::rands::Rand,
@@ -72,6 +94,60 @@ where
 
     /// Current testcase scheduled (mutable)
     fn current_mut(&mut self) -> &mut Option<usize>;
+
+    /// Returns the indexes of the entries tagged with `tag`, see [`Testcase::add_tag`]
+    fn entries_with_tag(&self, tag: &str) -> Vec<usize> {
+        (0..self.count())
+            .filter(|idx| {
+                self.get(*idx)
+                    .map_or(false, |testcase| testcase.borrow().has_tag(tag))
+            })
+            .collect()
+    }
+
+    /// Iterates over all entries, yielding each index with its testcase, in order.
+    /// This does not change the [`Corpus::current`] entry, nor any scheduling state.
+    /// For corpora keeping their inputs on disk, e.g. the `CachedOnDiskCorpus`,
+    /// iterating loads the inputs not in memory, so it may trigger disk reads.
+    fn iter(&self) -> CorpusIter<'_, Self, I>
+    where
+        Self: Sized,
+    {
+        CorpusIter {
+            corpus: self,
+            idx: 0,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// An iterator over the entries of a [`Corpus`], see [`Corpus::iter`]
+#[derive(Debug)]
+pub struct CorpusIter<'a, C, I>
+where
+    C: Corpus<I>,
+    I: Input,
+{
+    corpus: &'a C,
+    idx: usize,
+    phantom: PhantomData<I>,
+}
+
+impl<'a, C, I> Iterator for CorpusIter<'a, C, I>
+where
+    C: Corpus<I>,
+    I: Input,
+{
+    type Item = Result<(usize, &'a RefCell<Testcase<I>>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.idx >= self.corpus.count() {
+            return None;
+        }
+        let idx = self.idx;
+        self.idx += 1;
+        Some(self.corpus.get(idx).map(|testcase| (idx, testcase)))
+    }
 }
 
 /// The scheduler define how the fuzzer requests a testcase from the corpus.
@@ -105,6 +181,13 @@ where
         Ok(())
     }
 
+    /// Called by the fuzzer with the entry `next` picked, and the testcase at that index, e.g. to log its score or energy.
+    /// It only gets called on the outermost scheduler, once per pick: `next` does not call it, and
+    /// schedulers wrapping a base scheduler forward it to their base.
+    fn on_schedule(&self, _idx: usize, _testcase: &Testcase<I>) -> Result<(), Error> {
+        Ok(())
+    }
+
     /// Gets the next entry
     fn next(&self, state: &mut S) -> Result<usize, Error>;
 }
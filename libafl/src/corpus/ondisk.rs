@@ -1,7 +1,13 @@
 //! The ondisk corpus stores unused testcases to disk.
 
+use ahash::AHasher;
 use alloc::vec::Vec;
-use core::{cell::RefCell, time::Duration};
+use core::{
+    cell::RefCell,
+    hash::{Hash, Hasher},
+    time::Duration,
+};
+use hashbrown::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 use std::{
     fs::OpenOptions,
@@ -12,8 +18,13 @@ use std::{
 use std::{fs, fs::File, io::Write};
 
 use crate::{
-    bolts::serdeany::SerdeAnyMap, corpus::Corpus, corpus::Testcase, inputs::Input,
-    state::HasMetadata, Error,
+    bolts::serdeany::SerdeAnyMap,
+    corpus::Corpus,
+    corpus::Testcase,
+    feedbacks::{BacktraceHashMetadata, MapCoverageMetadata},
+    inputs::Input,
+    state::HasMetadata,
+    Error,
 };
 
 /// Options for the the format of the on-disk metadata
@@ -49,6 +60,17 @@ where
     current: Option<usize>,
     dir_path: PathBuf,
     meta_format: Option<OnDiskMetadataFormat>,
+    /// The hashes of the stored inputs with the file they are stored in, if deduplication is enabled
+    input_hashes: Option<HashMap<u64, PathBuf>>,
+    /// The maximum amount of entries, if this is a ring buffer
+    capacity: Option<usize>,
+    /// If the ring buffer keeps the last entry of each backtrace bucket
+    keep_buckets: bool,
+    /// If the [`MapCoverageMetadata`] of each entry is stored next to its input
+    save_coverage: bool,
+    /// The inputs written to the directory by this corpus, the only files [`OnDiskCorpus::compact`] deletes
+    #[serde(default)]
+    written: HashSet<PathBuf>,
 }
 
 impl<I> Corpus<I> for OnDiskCorpus<I>
@@ -64,6 +86,34 @@ where
     /// Add an entry to the corpus and return its index
     #[inline]
     fn add(&mut self, mut testcase: Testcase<I>) -> Result<usize, Error> {
+        let input_hash = if self.input_hashes.is_some() {
+            Self::input_hash(&testcase)
+        } else {
+            None
+        };
+
+        if let Some(capacity) = self.capacity {
+            let bucket = testcase
+                .metadata()
+                .get::<BacktraceHashMetadata>()
+                .map(|meta| meta.hash);
+            while self.entries.len() >= capacity {
+                self.evict(bucket)?;
+            }
+        }
+
+        if let Some(stored) =
+            input_hash.and_then(|hash| self.input_hashes.as_ref().unwrap().get(&hash))
+        {
+            if testcase.filename().is_none() && stored.exists() {
+                // The same input has already been stored, the new entry shares its file
+                testcase.set_filename(stored.to_str().expect("Invalid Path").into());
+                *testcase.input_mut() = None;
+                self.entries.push(RefCell::new(testcase));
+                return Ok(self.entries.len() - 1);
+            }
+        }
+
         if testcase.filename().is_none() {
             // TODO walk entry metadata to ask for pieces of filename (e.g. :havoc in AFL)
             let file_orig = testcase
@@ -125,6 +175,17 @@ where
         testcase
             .store_input()
             .expect("Could not save testcase to disk");
+        if self.save_coverage {
+            if let Some(coverage) = testcase.metadata().get::<MapCoverageMetadata>() {
+                let filename = PathBuf::from(testcase.filename().as_ref().unwrap());
+                fs::write(Self::coverage_filename(&filename), coverage.encode()?)?;
+            }
+        }
+        if let (Some(hashes), Some(hash)) = (self.input_hashes.as_mut(), input_hash) {
+            hashes.insert(hash, PathBuf::from(testcase.filename().as_ref().unwrap()));
+        }
+        self.written
+            .insert(PathBuf::from(testcase.filename().as_ref().unwrap()));
         self.entries.push(RefCell::new(testcase));
         Ok(self.entries.len() - 1)
     }
@@ -185,11 +246,55 @@ where
                 current: None,
                 dir_path,
                 meta_format: None,
+                input_hashes: None,
+                capacity: None,
+                keep_buckets: false,
+                save_coverage: false,
+                written: HashSet::new(),
             })
         }
         new(dir_path.as_ref().to_path_buf())
     }
 
+    /// Creates the [`OnDiskCorpus`] storing each input only once.
+    /// Inputs are deduplicated by the hash of their content, so the same (crashing) input found
+    /// multiple times is written to disk a single time.
+    /// Each [`Corpus::add`] still adds an entry, the entries of the same input share its file,
+    /// and its metadata and coverage files, which are the ones of the first entry.
+    /// The inputs already in `dir_path`, e.g. from before a restart, are deduplicated against as well.
+    /// Will error, if [`std::fs::create_dir_all()`] failed for `dir_path`.
+    pub fn new_dedup(
+        dir_path: PathBuf,
+        meta_format: Option<OnDiskMetadataFormat>,
+    ) -> Result<Self, Error> {
+        fs::create_dir_all(&dir_path)?;
+        let mut input_hashes = HashMap::new();
+        for file in fs::read_dir(&dir_path)? {
+            let file = file?;
+            // Hidden files are metadata, lock and coverage files
+            if !file.file_type()?.is_file() || file.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            // Files that are no inputs are not deduplicated against
+            if let Ok(input) = I::from_file(file.path()) {
+                let mut hasher = AHasher::new_with_keys(0, 0);
+                input.hash(&mut hasher);
+                input_hashes.insert(hasher.finish(), file.path());
+            }
+        }
+        Ok(Self {
+            entries: vec![],
+            current: None,
+            dir_path,
+            meta_format,
+            input_hashes: Some(input_hashes),
+            capacity: None,
+            keep_buckets: false,
+            save_coverage: false,
+            written: HashSet::new(),
+        })
+    }
+
     /// Creates the [`OnDiskCorpus`] specifying the type of `Metadata` to be saved to disk.
     /// Will error, if [`std::fs::create_dir_all()`] failed for `dir_path`.
     pub fn new_save_meta(
@@ -202,8 +307,525 @@ where
             current: None,
             dir_path,
             meta_format,
+            input_hashes: None,
+            capacity: None,
+            keep_buckets: false,
+            save_coverage: false,
+            written: HashSet::new(),
         })
     }
+
+    /// Creates the [`OnDiskCorpus`] as ring buffer of at most `capacity` entries, e.g. for the solutions of a very buggy target.
+    /// Once full, each new entry evicts the oldest one.
+    /// Evicted entries are removed from disk, they are lost for good.
+    /// Will error, if `capacity` is `0`, or if [`std::fs::create_dir_all()`] failed for `dir_path`.
+    pub fn new_ring<P>(dir_path: P, capacity: usize) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        if capacity == 0 {
+            return Err(Error::IllegalArgument(
+                "The capacity of a ring corpus must not be 0".into(),
+            ));
+        }
+        let mut corpus = Self::new(dir_path)?;
+        corpus.capacity = Some(capacity);
+        Ok(corpus)
+    }
+
+    /// Creates the [`OnDiskCorpus`] as ring buffer of at most `capacity` entries, like [`OnDiskCorpus::new_ring`],
+    /// but the eviction skips the last remaining entry of a backtrace bucket, see [`BacktraceHashMetadata`],
+    /// as long as other entries can be evicted.
+    /// Evicted entries are removed from disk, they are lost for good.
+    pub fn new_ring_bucketed<P>(dir_path: P, capacity: usize) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut corpus = Self::new_ring(dir_path, capacity)?;
+        corpus.keep_buckets = true;
+        Ok(corpus)
+    }
+
+    /// Returns `true` if this corpus deduplicates inputs by their content hash
+    #[must_use]
+    pub fn dedup(&self) -> bool {
+        self.input_hashes.is_some()
+    }
+
+    /// The maximum amount of entries, if this corpus is a ring buffer
+    #[must_use]
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /// Stores the coverage of each entry, its [`MapCoverageMetadata`], in a `.<input>.coverage` file
+    /// next to the input, so corpus minimization can use it instead of executing all entries again.
+    /// The metadata is added by a [`crate::feedbacks::MapCoverageFeedback`].
+    /// Disabled by default, as it costs some space per entry.
+    pub fn set_save_coverage(&mut self, save_coverage: bool) {
+        self.save_coverage = save_coverage;
+    }
+
+    /// Returns `true` if the coverage of each entry is stored next to its input
+    #[must_use]
+    pub fn save_coverage(&self) -> bool {
+        self.save_coverage
+    }
+
+    /// The stored coverage of an entry, if any
+    pub fn coverage(&self, idx: usize) -> Result<Option<MapCoverageMetadata>, Error> {
+        match self.get(idx)?.borrow().filename() {
+            Some(filename) => Self::load_coverage(filename),
+            None => Ok(None),
+        }
+    }
+
+    /// Loads the coverage stored next to the input at `input_path`, if any, e.g. for a corpus of a previous run
+    pub fn load_coverage<P>(input_path: P) -> Result<Option<MapCoverageMetadata>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let coverage_file = Self::coverage_filename(input_path.as_ref());
+        if !coverage_file.exists() {
+            return Ok(None);
+        }
+        Ok(Some(MapCoverageMetadata::decode(&fs::read(
+            coverage_file,
+        )?)?))
+    }
+
+    /// The path of the coverage file for the input at `input_path`
+    fn coverage_filename(input_path: &Path) -> PathBuf {
+        input_path.with_file_name(format!(
+            ".{}.coverage",
+            input_path.file_name().unwrap().to_string_lossy()
+        ))
+    }
+
+    /// The files of the entry with its input at `input_path`: the input, its metadata, its coverage and its lock file.
+    /// All but the input may not exist.
+    fn entry_files(input_path: &Path) -> [PathBuf; 4] {
+        let name = input_path.file_name().unwrap().to_string_lossy();
+        [
+            input_path.to_path_buf(),
+            input_path.with_file_name(format!(".{}.metadata", name)),
+            Self::coverage_filename(input_path),
+            input_path.with_file_name(format!(".{}.lafl_lock", name)),
+        ]
+    }
+
+    /// Reconciles the corpus directory with the entries of the corpus: deletes the files left behind by entries
+    /// no longer in the corpus, e.g. removed from the index with their files still on disk.
+    /// If `renumber` is set, the files of each entry are renamed to start with the index of the entry first,
+    /// so that the directory lists the entries in corpus order.
+    /// Only the inputs this corpus wrote, with their metadata and coverage files, get deleted.
+    /// Lock files stay, as do the files of other corpora sharing the directory, e.g. other clients of a `Launcher`.
+    /// Call it between executions, never while an entry gets added.
+    /// Returns the amount of deleted files.
+    pub fn compact(&mut self, renumber: bool) -> Result<usize, Error> {
+        if renumber {
+            self.renumber()?;
+        }
+
+        let live: HashSet<PathBuf> = self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.borrow().filename().as_ref().map(PathBuf::from))
+            .collect();
+        let orphans: Vec<PathBuf> = self.written.difference(&live).cloned().collect();
+
+        let mut removed = 0;
+        for orphan in orphans {
+            let [files @ .., _lockfile] = Self::entry_files(&orphan);
+            for file in files {
+                if file.is_file() {
+                    fs::remove_file(file)?;
+                    removed += 1;
+                }
+            }
+            self.written.remove(&orphan);
+        }
+        Ok(removed)
+    }
+
+    /// Renames the files of each entry in the corpus directory to start with the index of the entry
+    fn renumber(&mut self) -> Result<(), Error> {
+        // The files shared by entries of the same input get renamed once, see [`OnDiskCorpus::new_dedup`]
+        let mut renamed_files = HashMap::new();
+        for (idx, entry) in self.entries.iter().enumerate() {
+            let mut testcase = entry.borrow_mut();
+            let filename = match testcase.filename() {
+                Some(filename) => PathBuf::from(filename),
+                None => continue,
+            };
+            if filename.parent() != Some(self.dir_path.as_path()) {
+                continue;
+            }
+            if let Some(renamed) = renamed_files.get(&filename) {
+                testcase.set_filename(renamed.to_str().expect("Invalid Path").into());
+                continue;
+            }
+            let name = filename.file_name().unwrap().to_string_lossy().to_string();
+            // The index of a previous renumbering gets replaced
+            let name = match name.split_once('-') {
+                Some((prefix, rest))
+                    if prefix.len() == 8 && prefix.bytes().all(|b| b.is_ascii_digit()) =>
+                {
+                    rest
+                }
+                _ => &name,
+            };
+            let renamed = filename.with_file_name(format!("{:08}-{}", idx, name));
+            if renamed == filename {
+                continue;
+            }
+            if renamed.exists() {
+                return Err(Error::IllegalState(format!(
+                    "Cannot renumber {:?}, {:?} already exists",
+                    filename, renamed
+                )));
+            }
+            for (from, to) in Self::entry_files(&filename)
+                .iter()
+                .zip(Self::entry_files(&renamed))
+            {
+                if from.exists() {
+                    fs::rename(from, to)?;
+                }
+            }
+            testcase.set_filename(renamed.to_str().expect("Invalid Path").into());
+            if self.written.remove(&filename) {
+                self.written.insert(renamed.clone());
+            }
+            renamed_files.insert(filename, renamed);
+        }
+        if let Some(hashes) = self.input_hashes.as_mut() {
+            for stored in hashes.values_mut() {
+                if let Some(renamed) = renamed_files.get(stored) {
+                    *stored = renamed.clone();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The bucket of an entry, if it is known
+    fn bucket(&self, idx: usize) -> Option<u64> {
+        self.entries[idx]
+            .borrow()
+            .metadata()
+            .get::<BacktraceHashMetadata>()
+            .map(|meta| meta.hash)
+    }
+
+    /// Removes the oldest entry, or, keeping buckets, the oldest entry that is not the last of its bucket,
+    /// counting the bucket of the entry to be added, and deletes its files
+    fn evict(&mut self, incoming: Option<u64>) -> Result<(), Error> {
+        let count = self.entries.len();
+        let victim = if self.keep_buckets {
+            (0..count)
+                .find(|&idx| match self.bucket(idx) {
+                    None => true,
+                    Some(bucket) => {
+                        incoming == Some(bucket)
+                            || (0..count)
+                                .any(|other| other != idx && self.bucket(other) == Some(bucket))
+                    }
+                })
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        self.current = match self.current {
+            Some(current) if current == victim => None,
+            Some(current) if current > victim => Some(current - 1),
+            current => current,
+        };
+        if let Some(testcase) = self.remove(victim)? {
+            if let Some(filename) = testcase.filename() {
+                if self
+                    .entries
+                    .iter()
+                    .any(|entry| entry.borrow().filename().as_ref() == Some(filename))
+                {
+                    // The files are shared with an entry of the same input, see [`OnDiskCorpus::new_dedup`]
+                    return Ok(());
+                }
+                if let Some(hashes) = self.input_hashes.as_mut() {
+                    hashes.retain(|_, stored| stored.as_path() != Path::new(filename));
+                }
+                self.written.remove(Path::new(filename));
+                let [input, companions @ ..] = Self::entry_files(Path::new(filename));
+                fs::remove_file(&input)?;
+                // The metadata, the coverage and the lock file may not exist
+                for companion in companions {
+                    let _ = fs::remove_file(companion);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Hash the content of the input of a [`Testcase`], if it is loaded
+    fn input_hash(testcase: &Testcase<I>) -> Option<u64> {
+        testcase.input().as_ref().map(|input| {
+            let mut hasher = AHasher::new_with_keys(0, 0);
+            input.hash(&mut hasher);
+            hasher.finish()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use serial_test::serial;
+    use std::{fs, path::PathBuf};
+
+    use crate::{
+        bolts::{
+            rands::StdRand,
+            shmem::{ShMemProvider, StdShMemProvider},
+            staterestore::StateRestorer,
+            tuples::tuple_list,
+            AsMutSlice, AsSlice,
+        },
+        corpus::{ondisk::OnDiskMetadataFormat, Corpus, InMemoryCorpus, OnDiskCorpus, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{BacktraceHashMetadata, Feedback, MapCoverageFeedback},
+        inputs::{BytesInput, HasBytesVec},
+        observers::{MapObserver, StdMapObserver},
+        state::{HasCorpus, HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_ondisk_dedup() {
+        let mut corpus =
+            OnDiskCorpus::<BytesInput>::new_dedup(PathBuf::from("target/.test/dedup"), None)
+                .unwrap();
+
+        let first = corpus
+            .add(Testcase::new(BytesInput::new(b"crash".to_vec())))
+            .unwrap();
+        let second = corpus
+            .add(Testcase::new(BytesInput::new(b"crash".to_vec())))
+            .unwrap();
+        // Each add is a new entry, sharing the stored file
+        assert_eq!((first, second), (0, 1));
+        assert_eq!(corpus.count(), 2);
+        assert_eq!(
+            corpus.get(0).unwrap().borrow().filename(),
+            corpus.get(1).unwrap().borrow().filename()
+        );
+        assert_eq!(
+            corpus
+                .get(1)
+                .unwrap()
+                .borrow_mut()
+                .load_input()
+                .unwrap()
+                .bytes(),
+            b"crash"
+        );
+
+        corpus
+            .add(Testcase::new(BytesInput::new(b"other crash".to_vec())))
+            .unwrap();
+        assert_eq!(corpus.count(), 3);
+        let stored_files = || {
+            fs::read_dir("target/.test/dedup")
+                .unwrap()
+                .filter_map(Result::ok)
+                .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+                .count()
+        };
+        assert_eq!(stored_files(), 2);
+
+        // After a restart, the stored inputs are still known
+        let mut corpus =
+            OnDiskCorpus::<BytesInput>::new_dedup(PathBuf::from("target/.test/dedup"), None)
+                .unwrap();
+        corpus
+            .add(Testcase::new(BytesInput::new(b"crash".to_vec())))
+            .unwrap();
+        assert_eq!(corpus.count(), 1);
+        assert_eq!(stored_files(), 2);
+
+        fs::remove_dir_all("target/.test/dedup").unwrap();
+    }
+
+    #[test]
+    fn test_ondisk_ring() {
+        let dir = PathBuf::from("target/.test/ring");
+        let mut corpus = OnDiskCorpus::<BytesInput>::new_ring(&dir, 3).unwrap();
+        for i in 0..10_u8 {
+            corpus
+                .add(Testcase::new(BytesInput::new(vec![b'c', i])))
+                .unwrap();
+            assert!(corpus.count() <= 3);
+        }
+        // Only the newest crashes are kept, on disk as well
+        for (idx, i) in (7..10_u8).enumerate() {
+            let mut testcase = corpus.get(idx).unwrap().borrow_mut();
+            assert_eq!(testcase.load_input().unwrap().bytes(), &[b'c', i]);
+        }
+        assert_eq!(
+            fs::read_dir(&dir)
+                .unwrap()
+                .filter_map(Result::ok)
+                .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+                .count(),
+            3
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        // The last crash of a bucket survives
+        let dir = PathBuf::from("target/.test/ring_bucketed");
+        let mut corpus = OnDiskCorpus::<BytesInput>::new_ring_bucketed(&dir, 2).unwrap();
+        for (i, hash) in [(0_u8, 1_u64), (1, 2), (2, 2), (3, 2)] {
+            let mut testcase = Testcase::new(BytesInput::new(vec![b'b', i]));
+            testcase.add_metadata(BacktraceHashMetadata { hash });
+            corpus.add(testcase).unwrap();
+        }
+        assert_eq!(corpus.count(), 2);
+        let mut first = corpus.get(0).unwrap().borrow_mut();
+        assert_eq!(first.load_input().unwrap().bytes(), &[b'b', 0]);
+        drop(first);
+        let mut second = corpus.get(1).unwrap().borrow_mut();
+        assert_eq!(second.load_input().unwrap().bytes(), &[b'b', 3]);
+        drop(second);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_ondisk_tags() {
+        let dir = PathBuf::from("target/.test/tags");
+        let mut corpus = OnDiskCorpus::<BytesInput>::new_save_meta(
+            dir.clone(),
+            Some(OnDiskMetadataFormat::Json),
+        )
+        .unwrap();
+
+        for (content, tags) in [
+            (&b"GET /"[..], &["network", "parser"][..]),
+            (&b"{}"[..], &["parser"][..]),
+            (&b"crash"[..], &["regression"][..]),
+        ] {
+            let mut testcase = Testcase::new(BytesInput::new(content.to_vec()));
+            for tag in tags {
+                testcase.add_tag(tag);
+            }
+            corpus.add(testcase).unwrap();
+        }
+
+        // The tags end up in the JSON metadata
+        let filename = corpus.get(2).unwrap().borrow().filename().clone().unwrap();
+        let filename = PathBuf::from(filename);
+        let meta_file = filename.with_file_name(format!(
+            ".{}.metadata",
+            filename.file_name().unwrap().to_string_lossy()
+        ));
+        assert!(fs::read_to_string(meta_file)
+            .unwrap()
+            .contains("regression"));
+
+        // and survive a restart, restoring the state the way the restarting event managers do
+        let state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut shmem_provider = StdShMemProvider::new().unwrap();
+        let mut staterestorer =
+            StateRestorer::<StdShMemProvider>::new(shmem_provider.new_shmem(1024).unwrap());
+        staterestorer.save(&state).unwrap();
+        drop(state);
+
+        let state: StdState<
+            OnDiskCorpus<BytesInput>,
+            (),
+            BytesInput,
+            StdRand,
+            InMemoryCorpus<BytesInput>,
+        > = staterestorer.restore().unwrap().unwrap();
+        staterestorer.reset();
+        let corpus = state.corpus();
+        assert_eq!(corpus.entries_with_tag("parser"), vec![0, 1]);
+        assert_eq!(corpus.entries_with_tag("network"), vec![0]);
+        assert!(corpus.entries_with_tag("unknown").is_empty());
+        let mut testcase = corpus.get(2).unwrap().borrow_mut();
+        assert!(testcase.has_tag("regression"));
+        // The input itself is loaded back from disk
+        assert!(testcase.input().is_none());
+        assert_eq!(testcase.load_input().unwrap().bytes(), b"crash");
+        drop(testcase);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_ondisk_coverage() {
+        /// A deterministic target, covering an edge per distinct input byte
+        fn run(observer: &mut StdMapObserver<u8>, input: &BytesInput) {
+            observer.reset_map().unwrap();
+            for &byte in input.bytes() {
+                observer.as_mut_slice()[usize::from(byte) % 64] = 1;
+            }
+        }
+
+        let dir = PathBuf::from("target/.test/coverage");
+        let mut corpus = OnDiskCorpus::<BytesInput>::new(&dir).unwrap();
+        assert!(!corpus.save_coverage());
+        corpus.set_save_coverage(true);
+
+        let mut observers = tuple_list!(StdMapObserver::new_owned("cov", vec![0_u8; 64]));
+        let mut feedback = MapCoverageFeedback::new(&observers.0);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+        for bytes in [&b"abc"[..], &b"zzz"[..], &b""[..], &b"\x00\x7f\xff"[..]] {
+            let input = BytesInput::new(bytes.to_vec());
+            run(&mut observers.0, &input);
+            assert!(!feedback
+                .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+                .unwrap());
+            let mut testcase = Testcase::new(input);
+            feedback.append_metadata(&mut state, &mut testcase).unwrap();
+            corpus.add(testcase).unwrap();
+        }
+
+        // The stored coverage matches a fresh execution of each entry
+        for idx in 0..corpus.count() {
+            let input = corpus
+                .get(idx)
+                .unwrap()
+                .borrow_mut()
+                .load_input()
+                .unwrap()
+                .clone();
+            run(&mut observers.0, &input);
+            let edges: Vec<usize> = (0..64)
+                .filter(|&i| observers.0.as_slice()[i] != 0)
+                .collect();
+            assert_eq!(corpus.coverage(idx).unwrap().unwrap().edges, edges);
+        }
+
+        // Entries without coverage have no file
+        corpus.set_save_coverage(false);
+        let idx = corpus
+            .add(Testcase::new(BytesInput::new(b"nocov".to_vec())))
+            .unwrap();
+        assert!(corpus.coverage(idx).unwrap().is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
 #[cfg(feature = "python")]
 /// `OnDiskCorpus` Python bindings
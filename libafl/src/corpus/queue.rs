@@ -1,38 +1,65 @@
 //! The queue corpus scheduler implements an AFL-like queue mechanism
 
-use alloc::borrow::ToOwned;
+use alloc::{borrow::ToOwned, vec::Vec};
+use core::cell::{Cell, RefCell};
 
 use crate::{
-    corpus::{Corpus, CorpusScheduler},
+    corpus::{Corpus, CorpusScheduler, Testcase},
     inputs::Input,
     state::HasCorpus,
     Error,
 };
 
-/// Walk the corpus in a queue-like fashion
+/// Walk the corpus in a queue-like fashion.
+/// The queue follows the order of the corpus, unless changed with [`QueueCorpusScheduler::reorder`].
 #[derive(Debug, Clone)]
-pub struct QueueCorpusScheduler;
+pub struct QueueCorpusScheduler {
+    /// The corpus indexes, in the order they get scheduled
+    order: RefCell<Vec<usize>>,
+    /// If set, the queue starts over at the front of the order
+    restart: Cell<bool>,
+}
 
 impl<I, S> CorpusScheduler<I, S> for QueueCorpusScheduler
 where
     S: HasCorpus<I>,
     I: Input,
 {
+    /// Drops the removed entry from the order
+    fn on_remove(
+        &self,
+        _state: &mut S,
+        idx: usize,
+        _testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        let mut order = self.order.borrow_mut();
+        order.retain(|&entry| entry != idx);
+        for entry in order.iter_mut() {
+            if *entry > idx {
+                *entry -= 1;
+            }
+        }
+        Ok(())
+    }
+
     /// Gets the next entry in the queue
     fn next(&self, state: &mut S) -> Result<usize, Error> {
-        if state.corpus().count() == 0 {
+        let count = state.corpus().count();
+        if count == 0 {
             Err(Error::Empty("No entries in corpus".to_owned()))
         } else {
-            let id = match state.corpus().current() {
-                Some(cur) => {
-                    if *cur + 1 >= state.corpus().count() {
-                        0
-                    } else {
-                        *cur + 1
-                    }
-                }
-                None => 0,
+            let mut order = self.order.borrow_mut();
+            Self::sync_order(&mut order, count);
+            let pos = match state.corpus().current() {
+                Some(cur) if !self.restart.get() => order
+                    .iter()
+                    .position(|entry| entry == cur)
+                    .map_or(0, |pos| (pos + 1) % count),
+                _ => 0,
             };
+            self.restart.set(false);
+            let id = order[pos];
+            drop(order);
             *state.corpus_mut().current_mut() = Some(id);
             Ok(id)
         }
@@ -43,7 +70,62 @@ impl QueueCorpusScheduler {
     /// Creates a new `QueueCorpusScheduler`
     #[must_use]
     pub fn new() -> Self {
-        Self
+        Self {
+            order: RefCell::new(vec![]),
+            restart: Cell::new(false),
+        }
+    }
+
+    /// Keeps the order in line with the corpus: new entries get queued at the end.
+    /// Entries removed without [`CorpusScheduler::on_remove`], such as evicted from a ring corpus,
+    /// leave it unknown which indexes moved, so the queue falls back to the order of the corpus.
+    fn sync_order(order: &mut Vec<usize>, count: usize) {
+        if order.len() > count {
+            order.clear();
+        }
+        let len = order.len();
+        order.extend(len..count);
+    }
+
+    /// Changes the order of the queue, such as to move a seed to the front, or to shuffle it.
+    /// The closure gets the corpus indexes in the order they get scheduled,
+    /// and has to leave each of them in there exactly once.
+    /// Otherwise, the order stays unchanged and an [`Error::IllegalArgument`] is returned.
+    /// Afterwards, the queue starts over at the front.
+    pub fn reorder<I, S, F>(&mut self, state: &S, f: F) -> Result<(), Error>
+    where
+        I: Input,
+        S: HasCorpus<I>,
+        F: FnOnce(&mut Vec<usize>),
+    {
+        let count = state.corpus().count();
+        let order = self.order.get_mut();
+        Self::sync_order(order, count);
+
+        let mut reordered = order.clone();
+        f(&mut reordered);
+
+        let mut seen = vec![false; count];
+        for &idx in &reordered {
+            if idx >= count || seen[idx] {
+                return Err(Error::IllegalArgument(format!(
+                    "Invalid queue order, corpus index {} is out of bounds or queued twice (corpus count: {})",
+                    idx, count
+                )));
+            }
+            seen[idx] = true;
+        }
+        if reordered.len() != count {
+            return Err(Error::IllegalArgument(format!(
+                "Invalid queue order, {} of {} corpus entries queued",
+                reordered.len(),
+                count
+            )));
+        }
+
+        *order = reordered;
+        self.restart.set(true);
+        Ok(())
     }
 }
 
@@ -61,7 +143,9 @@ mod tests {
 
     use crate::{
         bolts::rands::StdRand,
-        corpus::{Corpus, CorpusScheduler, OnDiskCorpus, QueueCorpusScheduler, Testcase},
+        corpus::{
+            Corpus, CorpusScheduler, InMemoryCorpus, OnDiskCorpus, QueueCorpusScheduler, Testcase,
+        },
         inputs::bytes::BytesInput,
         state::{HasCorpus, StdState},
     };
@@ -100,4 +184,62 @@ mod tests {
 
         fs::remove_dir_all("target/.test/fancy").unwrap();
     }
+
+    #[test]
+    fn test_queue_reorder() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        for i in 0..4 {
+            corpus.add(Testcase::new(BytesInput::new(vec![i]))).unwrap();
+        }
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut scheduler = QueueCorpusScheduler::new();
+        assert_eq!(scheduler.next(&mut state).unwrap(), 0);
+        assert_eq!(scheduler.next(&mut state).unwrap(), 1);
+
+        // Move the last entry to the front, the queue starts over with it
+        scheduler
+            .reorder(&state, |order| {
+                let idx = order.pop().unwrap();
+                order.insert(0, idx);
+            })
+            .unwrap();
+        let scheduled: Vec<usize> = (0..5)
+            .map(|_| scheduler.next(&mut state).unwrap())
+            .collect();
+        assert_eq!(scheduled, vec![3, 0, 1, 2, 3]);
+
+        // Orders with unknown, duplicate or missing indexes are rejected, and leave the order unchanged
+        assert!(scheduler.reorder(&state, |order| order.push(4)).is_err());
+        assert!(scheduler.reorder(&state, |order| order[1] = 3).is_err());
+        assert!(scheduler
+            .reorder(&state, |order| {
+                order.pop();
+            })
+            .is_err());
+        assert_eq!(scheduler.next(&mut state).unwrap(), 0);
+
+        // New entries get queued at the end, removed ones dropped
+        state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(vec![4])))
+            .unwrap();
+        let testcase = state.corpus_mut().remove(1).unwrap();
+        scheduler.on_remove(&mut state, 1, &testcase).unwrap();
+        let scheduled: Vec<usize> = (0..4)
+            .map(|_| scheduler.next(&mut state).unwrap())
+            .collect();
+        assert_eq!(scheduled, vec![1, 3, 2, 0]);
+
+        // Without notification of a removal, the custom order is dropped
+        drop(state.corpus_mut().remove(0).unwrap());
+        let scheduled: Vec<usize> = (0..3)
+            .map(|_| scheduler.next(&mut state).unwrap())
+            .collect();
+        assert_eq!(scheduled, vec![1, 2, 0]);
+    }
 }
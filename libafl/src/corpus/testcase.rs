@@ -1,7 +1,10 @@
 //! The testcase is a struct embedded in each corpus.
 //! It will contain a respective input, and metadata.
 
-use alloc::string::String;
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
 use core::{convert::Into, default::Default, option::Option, time::Duration};
 use serde::{Deserialize, Serialize};
 
@@ -33,6 +36,21 @@ where
     executions: usize,
     /// If it has been fuzzed
     fuzzed: bool,
+    /// If mutational stages must run it as is, instead of mutating it
+    #[serde(default)]
+    no_mutate: bool,
+    /// The energy left to fuzz this testcase, boosted on finds and decayed otherwise
+    #[serde(default = "default_energy")]
+    energy: f64,
+}
+
+/// The energy of a fresh [`Testcase`], also the maximum energy
+pub const MAX_ENERGY: f64 = 1.0;
+/// The minimum energy of a [`Testcase`], so that tapped-out testcases still get fuzzed now and then
+pub const MIN_ENERGY: f64 = 0.01;
+
+fn default_energy() -> f64 {
+    MAX_ENERGY
 }
 
 impl<I> HasMetadata for Testcase<I>
@@ -166,6 +184,56 @@ where
         self.fuzzed = fuzzed;
     }
 
+    /// Get if mutational stages must leave this testcase unmutated
+    #[inline]
+    pub fn no_mutate(&self) -> bool {
+        self.no_mutate
+    }
+
+    /// Pin this testcase, e.g. a minimal regression case: it keeps being scheduled,
+    /// and mutational stages execute it once as is, instead of mutating it.
+    /// Other testcases may still splice from it.
+    #[inline]
+    pub fn set_no_mutate(&mut self, no_mutate: bool) {
+        self.no_mutate = no_mutate;
+    }
+
+    /// Get the energy left to fuzz this testcase, between [`MIN_ENERGY`] and [`MAX_ENERGY`]
+    #[inline]
+    #[must_use]
+    pub fn energy(&self) -> f64 {
+        self.energy
+    }
+
+    /// Set the energy left to fuzz this testcase, clamped between [`MIN_ENERGY`] and [`MAX_ENERGY`]
+    #[inline]
+    pub fn set_energy(&mut self, energy: f64) {
+        self.energy = energy.clamp(MIN_ENERGY, MAX_ENERGY);
+    }
+
+    /// Tag this testcase, e.g. with the component it exercises.
+    /// The tags are stored in the [`TestcaseTagsMetadata`], so tag the testcase before adding it to an
+    /// `OnDiskCorpus` to get the tags in the metadata file.
+    pub fn add_tag(&mut self, tag: &str) {
+        if let Some(meta) = self.metadata.get_mut::<TestcaseTagsMetadata>() {
+            if !meta.tags.iter().any(|t| t == tag) {
+                meta.tags.push(tag.to_string());
+            }
+        } else {
+            self.add_metadata(TestcaseTagsMetadata {
+                tags: vec![tag.to_string()],
+            });
+        }
+    }
+
+    /// Returns `true` if this testcase has been tagged with `tag`
+    #[must_use]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.metadata
+            .get::<TestcaseTagsMetadata>()
+            .map_or(false, |meta| meta.tags.iter().any(|t| t == tag))
+    }
+
     /// Create a new Testcase instace given an input
     #[inline]
     pub fn new<T>(input: T) -> Self
@@ -218,6 +286,8 @@ where
             cached_len: None,
             executions: 0,
             fuzzed: false,
+            no_mutate: false,
+            energy: MAX_ENERGY,
         }
     }
 }
@@ -259,6 +329,36 @@ where
     }
 }
 
+/// The tags of a testcase, to categorize the entries of a corpus
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TestcaseTagsMetadata {
+    /// The tags, each one stored once
+    pub tags: Vec<String>,
+}
+
+crate::impl_serdeany!(TestcaseTagsMetadata);
+
+/// A testcase metadata scaling the energy of the first power schedule of a seed,
+/// see [`crate::state::StdState::weight_initial_inputs_by_size`].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct InitialEnergyMetadata {
+    /// The factor applied to the performance score, while the testcase has not been fuzzed yet
+    pub factor: f64,
+}
+
+crate::impl_serdeany!(InitialEnergyMetadata);
+
+/// The provenance of a solution: the corpus entry the stages were fuzzing when it was found.
+/// Only added if the fuzzer records parents, see [`crate::StdFuzzer::record_parents`].
+/// The parent is named after its input, see [`Input::generate_name`], so eviction or reordering does not mix it up.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ParentMetadata {
+    /// The name of the parent input
+    pub parent_name: String,
+}
+
+crate::impl_serdeany!(ParentMetadata);
+
 /// The Metadata for each testcase used in power schedules.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct PowerScheduleTestcaseMetaData {
@@ -27,10 +27,10 @@ use crate::{
     observers::ObserversTuple,
     Error,
 };
-use alloc::string::ToString;
+use alloc::{string::ToString, vec::Vec};
 #[cfg(feature = "std")]
 use core::sync::atomic::{compiler_fence, Ordering};
-use core::{marker::PhantomData, time::Duration};
+use core::{marker::PhantomData, num::NonZeroUsize, time::Duration};
 #[cfg(feature = "std")]
 use core_affinity::CoreId;
 use serde::de::DeserializeOwned;
@@ -110,6 +110,11 @@ where
         self.llmp.connect_b2b(addr)
     }
 
+    /// Exit the broker loop once `n_clients` clients stopped on purpose, see [`EventRestarter::send_exiting`]
+    pub fn set_exit_cleanly_after(&mut self, n_clients: NonZeroUsize) {
+        self.llmp.set_exit_cleanly_after(n_clients);
+    }
+
     /// Run forever in the broker
     pub fn broker_loop(&mut self) -> Result<(), Error> {
         let monitor = &mut self.monitor;
@@ -228,11 +233,19 @@ where
                 #[cfg(feature = "std")]
                 println!("[LOG {}]: {}", severity_level, message);
                 Ok(BrokerEventResult::Handled)
-            } //_ => Ok(BrokerEventResult::Forward),
+            }
+            Event::NewTokens {
+                tokens: _,
+                phantom: _,
+            } => Ok(BrokerEventResult::Forward),
+            //_ => Ok(BrokerEventResult::Forward),
         }
     }
 }
 
+/// The default maximum amount of tokens shared by other clients a client buffers until a stage takes them
+pub const DEFAULT_MAX_SHARED_TOKENS: usize = 4096;
+
 /// An [`EventManager`] that forwards all events to other attached fuzzers on shared maps or via tcp,
 /// using low-level message passing, [`crate::bolts::llmp`].
 #[derive(Debug)]
@@ -247,6 +260,8 @@ where
     #[cfg(feature = "llmp_compression")]
     compressor: GzipCompressor,
     configuration: EventConfig,
+    max_shared_tokens: usize,
+    shared_tokens: Vec<Vec<u8>>,
     phantom: PhantomData<(I, OT, S)>,
 }
 
@@ -275,6 +290,8 @@ where
             #[cfg(feature = "llmp_compression")]
             compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
             configuration,
+            max_shared_tokens: DEFAULT_MAX_SHARED_TOKENS,
+            shared_tokens: vec![],
             phantom: PhantomData,
         })
     }
@@ -293,6 +310,8 @@ where
             #[cfg(feature = "llmp_compression")]
             compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
             configuration,
+            max_shared_tokens: DEFAULT_MAX_SHARED_TOKENS,
+            shared_tokens: vec![],
             phantom: PhantomData,
         })
     }
@@ -309,6 +328,8 @@ where
             #[cfg(feature = "llmp_compression")]
             compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
             configuration,
+            max_shared_tokens: DEFAULT_MAX_SHARED_TOKENS,
+            shared_tokens: vec![],
             phantom: PhantomData,
         })
     }
@@ -329,10 +350,18 @@ where
             #[cfg(feature = "llmp_compression")]
             compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
             configuration,
+            max_shared_tokens: DEFAULT_MAX_SHARED_TOKENS,
+            shared_tokens: vec![],
             phantom: PhantomData,
         })
     }
 
+    /// Sets the maximum amount of tokens shared by other clients to buffer until a stage takes them,
+    /// see [`EventFirer::take_shared_tokens`]. Further tokens get dropped.
+    pub fn set_max_shared_tokens(&mut self, max_shared_tokens: usize) {
+        self.max_shared_tokens = max_shared_tokens;
+    }
+
     /// Write the config for a client [`EventManager`] to env vars, a new client can reattach using [`LlmpEventManager::existing_client_from_env()`].
     #[cfg(feature = "std")]
     pub fn to_env(&self, env_name: &str) {
@@ -370,7 +399,7 @@ where
                     _client_id, client_config
                 );
 
-                let _res = if client_config.match_with(&self.configuration)
+                let mut _res = if client_config.match_with(&self.configuration)
                     && observers_buf.is_some()
                 {
                     let observers: OT = postcard::from_bytes(observers_buf.as_ref().unwrap())?;
@@ -378,12 +407,27 @@ where
                 } else {
                     fuzzer.evaluate_input_with_observers(state, executor, self, input, false)?
                 };
+                // A feedback wants to see the new coverage again, see `MapFeedback::with_reexec_verification`
+                if let Some(input) = fuzzer.take_reexec_input() {
+                    _res = fuzzer
+                        .evaluate_input_with_observers(state, executor, self, input, false)?;
+                }
                 #[cfg(feature = "std")]
                 if let Some(item) = _res.1 {
                     println!("Added received Testcase as item #{}", item);
                 }
                 Ok(())
             }
+            Event::NewTokens { tokens, phantom: _ } => {
+                let room = self
+                    .max_shared_tokens
+                    .saturating_sub(self.shared_tokens.len());
+                let _received = tokens.len().min(room);
+                self.shared_tokens.extend(tokens.into_iter().take(room));
+                #[cfg(feature = "std")]
+                println!("Received {} tokens from {}", _received, _client_id);
+                Ok(())
+            }
             _ => Err(Error::Unknown(format!(
                 "Received illegal message that message should not have arrived: {:?}.",
                 event.name()
@@ -429,6 +473,10 @@ where
     fn configuration(&self) -> EventConfig {
         self.configuration
     }
+
+    fn take_shared_tokens(&mut self) -> Vec<Vec<u8>> {
+        core::mem::take(&mut self.shared_tokens)
+    }
 }
 
 impl<I, OT, S, SP> EventRestarter<S> for LlmpEventManager<I, OT, S, SP>
@@ -444,6 +492,11 @@ where
         // wait until we can drop the message safely.
         self.llmp.await_safe_to_unmap_blocking();
     }
+
+    /// Tells the broker that this client is done.
+    fn send_exiting(&mut self) -> Result<(), Error> {
+        self.llmp.send_exiting()
+    }
 }
 
 impl<E, I, OT, S, SP, Z> EventProcessor<E, I, S, Z> for LlmpEventManager<I, OT, S, SP>
@@ -563,6 +616,10 @@ where
     fn configuration(&self) -> EventConfig {
         self.llmp_mgr.configuration()
     }
+
+    fn take_shared_tokens(&mut self) -> Vec<Vec<u8>> {
+        self.llmp_mgr.take_shared_tokens()
+    }
 }
 
 #[cfg(feature = "std")]
@@ -588,6 +645,12 @@ where
         self.staterestorer
             .save(&(state, &self.llmp_mgr.describe()?))
     }
+
+    /// Tells the respawner not to restart this client, and the broker that this client is done.
+    fn send_exiting(&mut self) -> Result<(), Error> {
+        self.staterestorer.send_exiting();
+        self.llmp_mgr.send_exiting()
+    }
 }
 
 #[cfg(feature = "std")]
@@ -743,6 +806,9 @@ where
     /// The type of manager to build
     #[builder(default = ManagerKind::Any)]
     kind: ManagerKind,
+    /// The broker exits once this many clients stopped on purpose, see [`EventRestarter::send_exiting`]
+    #[builder(default = None)]
+    exit_cleanly_after: Option<NonZeroUsize>,
     #[builder(setter(skip), default = PhantomData)]
     phantom_data: PhantomData<(I, OT, S)>,
 }
@@ -765,14 +831,19 @@ where
         let (staterestorer, new_shmem_provider, core_id) = if std::env::var(_ENV_FUZZER_SENDER)
             .is_err()
         {
-            let broker_things = |mut broker: LlmpEventBroker<I, MT, SP>, remote_broker_addr| {
-                if let Some(remote_broker_addr) = remote_broker_addr {
-                    println!("B2b: Connecting to {:?}", &remote_broker_addr);
-                    broker.connect_b2b(remote_broker_addr)?;
-                };
+            let broker_things =
+                |mut broker: LlmpEventBroker<I, MT, SP>, remote_broker_addr, exit_cleanly_after| {
+                    if let Some(remote_broker_addr) = remote_broker_addr {
+                        println!("B2b: Connecting to {:?}", &remote_broker_addr);
+                        broker.connect_b2b(remote_broker_addr)?;
+                    };
 
-                broker.broker_loop()
-            };
+                    if let Some(exit_cleanly_after) = exit_cleanly_after {
+                        broker.set_exit_cleanly_after(exit_cleanly_after);
+                    }
+
+                    broker.broker_loop()
+                };
 
             // We get here if we are on Unix, or we are a broker on Windows (or without forks).
             let (mgr, core_id) = match self.kind {
@@ -791,7 +862,11 @@ where
                                 "Doing broker things. Run this tool again to start fuzzing in a client."
                             );
 
-                            broker_things(event_broker, self.remote_broker_addr)?;
+                            broker_things(
+                                event_broker,
+                                self.remote_broker_addr,
+                                self.exit_cleanly_after,
+                            )?;
 
                             return Err(Error::ShuttingDown);
                         }
@@ -809,7 +884,11 @@ where
                         self.broker_port,
                     )?;
 
-                    broker_things(event_broker, self.remote_broker_addr)?;
+                    broker_things(
+                        event_broker,
+                        self.remote_broker_addr,
+                        self.exit_cleanly_after,
+                    )?;
 
                     return Err(Error::ShuttingDown);
                 }
@@ -868,6 +947,11 @@ where
 
                 compiler_fence(Ordering::SeqCst);
 
+                if staterestorer.is_exiting() {
+                    println!("Fuzzer-respawner: The client finished, not restarting it.");
+                    return Err(Error::ShuttingDown);
+                }
+
                 #[allow(clippy::manual_assert)]
                 if !staterestorer.has_content() {
                     #[cfg(unix)]
@@ -949,15 +1033,31 @@ mod tests {
             tuples::tuple_list,
         },
         corpus::{Corpus, InMemoryCorpus, RandCorpusScheduler, Testcase},
-        events::{llmp::_ENV_FUZZER_SENDER, LlmpEventManager},
+        events::{llmp::_ENV_FUZZER_SENDER, Event, EventFirer, LlmpEventManager},
         executors::{ExitKind, InProcessExecutor},
         inputs::BytesInput,
-        mutators::BitFlipMutator,
-        stages::StdMutationalStage,
-        state::StdState,
-        Fuzzer, StdFuzzer,
+        mutators::{BitFlipMutator, Tokens},
+        stages::{AutoTokensStage, Stage, StdMutationalStage},
+        state::{HasMetadata, StdState},
+        Error, Fuzzer, StdFuzzer,
     };
-    use core::sync::atomic::{compiler_fence, Ordering};
+    use core::{
+        marker::PhantomData,
+        sync::atomic::{compiler_fence, Ordering},
+    };
+
+    /// Records the serialized events, like a client sends them to the broker
+    #[derive(Debug, Default)]
+    struct EventRecorder {
+        sent: Vec<Vec<u8>>,
+    }
+
+    impl EventFirer<BytesInput> for EventRecorder {
+        fn fire<S>(&mut self, _state: &mut S, event: Event<BytesInput>) -> Result<(), Error> {
+            self.sent.push(postcard::to_allocvec(&event)?);
+            Ok(())
+        }
+    }
 
     #[test]
     #[serial]
@@ -1043,4 +1143,88 @@ mod tests {
                 .unwrap();
         }
     }
+
+    #[test]
+    #[serial]
+    fn test_share_tokens() {
+        // The first client discovers a token, and shares it
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        corpus.add(Testcase::new(b"\x00IHDR\x00".to_vec())).unwrap();
+        let mut state_a = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut recorder = EventRecorder::default();
+        AutoTokensStage::new()
+            .perform(&mut (), &mut (), &mut state_a, &mut recorder, 0)
+            .unwrap();
+        assert_eq!(recorder.sent.len(), 1);
+
+        // The second client has its own dictionary
+        let mut state_b = StdState::new(
+            StdRand::with_seed(1),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut tokens = Tokens::new();
+        tokens.add_token(&b"PNG".to_vec());
+        state_b.add_metadata(tokens);
+
+        let mut shmem_provider = StdShMemProvider::new().unwrap();
+        let mut llmp_client = LlmpClient::new(
+            shmem_provider.clone(),
+            LlmpSharedMap::new(0, shmem_provider.new_shmem(1024).unwrap()),
+        )
+        .unwrap();
+        unsafe {
+            llmp_client.mark_safe_to_unmap();
+        }
+        let mut mgr_b =
+            LlmpEventManager::<BytesInput, (), _, _>::new(llmp_client, "fuzzer".into()).unwrap();
+
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), ());
+        let mut harness = |_buf: &BytesInput| ExitKind::Ok;
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state_b,
+            &mut mgr_b,
+        )
+        .unwrap();
+
+        // Receiving the tokens twice only adds them once, when the stage runs
+        for _ in 0..2 {
+            let event = postcard::from_bytes(&recorder.sent[0]).unwrap();
+            mgr_b
+                .handle_in_client(&mut fuzzer, &mut executor, &mut state_b, 1, event)
+                .unwrap();
+        }
+        assert_eq!(
+            state_b.metadata().get::<Tokens>().unwrap().tokens(),
+            &[b"PNG".to_vec()]
+        );
+        AutoTokensStage::new()
+            .perform(&mut (), &mut (), &mut state_b, &mut mgr_b, 0)
+            .unwrap();
+        assert_eq!(
+            state_b.metadata().get::<Tokens>().unwrap().tokens(),
+            &[b"PNG".to_vec(), b"IHDR".to_vec()]
+        );
+        assert!(mgr_b.take_shared_tokens().is_empty());
+
+        // The manager stops buffering tokens at the maximum amount
+        mgr_b.set_max_shared_tokens(1);
+        let event = Event::NewTokens {
+            tokens: vec![b"IEND".to_vec(), b"tEXt".to_vec()],
+            phantom: PhantomData,
+        };
+        mgr_b
+            .handle_in_client(&mut fuzzer, &mut executor, &mut state_b, 1, event)
+            .unwrap();
+        assert_eq!(mgr_b.take_shared_tokens(), vec![b"IEND".to_vec()]);
+    }
 }
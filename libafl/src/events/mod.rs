@@ -231,6 +231,13 @@ where
         /// `PhantomData`
         phantom: PhantomData<I>,
     },
+    /// New tokens, for example found by an `AutoTokensStage`, to share with the [`crate::mutators::Tokens`] of all clients
+    NewTokens {
+        /// The new tokens
+        tokens: Vec<Vec<u8>>,
+        /// `PhantomData`
+        phantom: PhantomData<I>,
+    },
     /*/// A custom type
     Custom {
         // TODO: Allow custom events
@@ -276,6 +283,10 @@ where
                 message: _,
                 phantom: _,
             } => "Log",
+            Event::NewTokens {
+                tokens: _,
+                phantom: _,
+            } => "Tokens",
             /*Event::Custom {
                 sender_id: _, /*custom_event} => custom_event.name()*/
             } => "todo",*/
@@ -328,6 +339,13 @@ where
     fn configuration(&self) -> EventConfig {
         EventConfig::AlwaysUnique
     }
+
+    /// Takes the tokens other clients shared in [`Event::NewTokens`] since the last call,
+    /// for a stage to merge them into the [`crate::mutators::Tokens`] of the state.
+    /// Empty, unless the manager receives events from other clients.
+    fn take_shared_tokens(&mut self) -> Vec<Vec<u8>> {
+        Vec::new()
+    }
 }
 
 /// [`ProgressReporter`] report progress to the broker.
@@ -410,6 +428,13 @@ pub trait EventRestarter<S> {
         Ok(())
     }
 
+    /// Send information that this client is exiting on purpose, and should not be restarted.
+    /// A broker set up to exit once its clients are done, for example in a [`crate::bolts::launcher::Launcher`], stops as well.
+    #[inline]
+    fn send_exiting(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
     /// Block until we are safe to exit.
     #[inline]
     fn await_restart_safe(&mut self) {}
@@ -201,7 +201,13 @@ where
                 #[cfg(feature = "std")]
                 println!("[LOG {}]: {}", severity_level, message);
                 Ok(BrokerEventResult::Handled)
-            } //_ => Ok(BrokerEventResult::Forward),
+            }
+            // There are no other clients to share the tokens with
+            Event::NewTokens {
+                tokens: _,
+                phantom: _,
+            } => Ok(BrokerEventResult::Handled),
+            //_ => Ok(BrokerEventResult::Forward),
         }
     }
 
@@ -259,6 +265,12 @@ where
         self.staterestorer.reset();
         self.staterestorer.save(state)
     }
+
+    /// Tells the respawner not to restart this client.
+    fn send_exiting(&mut self) -> Result<(), Error> {
+        self.staterestorer.send_exiting();
+        Ok(())
+    }
 }
 
 #[cfg(feature = "std")]
@@ -366,6 +378,11 @@ where
 
                 compiler_fence(Ordering::SeqCst);
 
+                if staterestorer.is_exiting() {
+                    println!("Fuzzer-respawner: The client finished, not restarting it.");
+                    return Err(Error::ShuttingDown);
+                }
+
                 #[allow(clippy::manual_assert)]
                 if !staterestorer.has_content() {
                     #[cfg(unix)]
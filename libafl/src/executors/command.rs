@@ -18,11 +18,14 @@ use std::{
 use crate::{
     bolts::{
         fs::{OutFile, OUTFILE_STD},
-        tuples::MatchName,
+        shmem::ShMem,
+        tuples::{MatchName, Named},
         AsSlice,
     },
-    inputs::HasTargetBytes,
-    observers::{ASANBacktraceObserver, ObserversTuple, StdErrObserver, StdOutObserver},
+    inputs::{HasTargetBytes, INPUT_FILE_PLACEHOLDER},
+    observers::{
+        ASANBacktraceObserver, MsanErrorsObserver, ObserversTuple, StdErrObserver, StdOutObserver,
+    },
 };
 #[cfg(feature = "std")]
 use crate::{inputs::Input, Error};
@@ -35,6 +38,10 @@ use std::time::Duration;
 
 use super::HasObservers;
 
+/// The env var holding the id of the shared coverage map for the target, see [`CommandExecutorBuilder::coverage_map`].
+/// The size of the map is in the same var, suffixed with `_SIZE`.
+pub const COVERAGE_MAP_SHM_ENV: &str = "LIBAFL_COVERAGE_MAP_SHM";
+
 /// How to deliver input to an external program
 /// `StdIn`: The traget reads from stdin
 /// `File`: The target reads from the specified [`OutFile`]
@@ -89,6 +96,9 @@ impl CommandConfigurator for StdCommandConfigurator {
     where
         I: Input + HasTargetBytes,
     {
+        if let Some((args, envs)) = input.target_command_line() {
+            return self.spawn_child_with_command_line(input, args, envs);
+        }
         match &mut self.input_location {
             InputLocation::Arg { argnum } => {
                 let args = self.command.get_args();
@@ -139,6 +149,79 @@ impl CommandConfigurator for StdCommandConfigurator {
     }
 }
 
+impl StdCommandConfigurator {
+    /// Spawns the child with the arguments and environment variables of the input, see [`HasTargetBytes::target_command_line`].
+    /// The arguments replace the configured ones, an [`INPUT_FILE_PLACEHOLDER`] argument gets replaced by
+    /// the path of the input file, or by the input itself if it is delivered as argument.
+    /// Arguments and variables no process can get, containing NUL bytes, or with a `=` in the key, are left out.
+    fn spawn_child_with_command_line<I>(
+        &mut self,
+        input: &I,
+        args: &[String],
+        envs: &[(String, String)],
+    ) -> Result<Child, Error>
+    where
+        I: Input + HasTargetBytes,
+    {
+        let mut cmd = Command::new(self.command.get_program());
+        if !self.debug_child {
+            cmd.stdout(Stdio::null());
+            cmd.stderr(Stdio::null());
+        }
+        for arg in args.iter().filter(|arg| !arg.contains('\0')) {
+            if arg != INPUT_FILE_PLACEHOLDER {
+                cmd.arg(arg);
+                continue;
+            }
+            match &self.input_location {
+                InputLocation::Arg { .. } => {
+                    #[cfg(unix)]
+                    cmd.arg(OsStr::from_bytes(input.target_bytes().as_slice()));
+                    #[cfg(not(unix))]
+                    cmd.arg(OsString::from_vec(input.target_bytes().as_vec()));
+                }
+                InputLocation::File { out_file } => {
+                    cmd.arg(&out_file.path);
+                }
+                InputLocation::StdIn => {
+                    cmd.arg(arg);
+                }
+            }
+        }
+        cmd.envs(
+            self.command
+                .get_envs()
+                .filter_map(|(key, value)| value.map(|value| (key, value))),
+        );
+        cmd.envs(
+            envs.iter()
+                .filter(|(key, value)| {
+                    !key.is_empty() && !key.contains(&['=', '\0'][..]) && !value.contains('\0')
+                })
+                .map(|(key, value)| (key, value)),
+        );
+        if let Some(cwd) = self.command.get_current_dir() {
+            cmd.current_dir(cwd);
+        }
+
+        match &mut self.input_location {
+            InputLocation::StdIn => {
+                let mut handle = cmd.stdin(Stdio::piped()).spawn()?;
+                let mut stdin = handle.stdin.take().unwrap();
+                stdin.write_all(input.target_bytes().as_slice())?;
+                stdin.flush()?;
+                drop(stdin);
+                Ok(handle)
+            }
+            InputLocation::File { out_file } => {
+                out_file.write_buf(input.target_bytes().as_slice())?;
+                Ok(cmd.stdin(Stdio::null()).spawn()?)
+            }
+            InputLocation::Arg { .. } => Ok(cmd.stdin(Stdio::null()).spawn()?),
+        }
+    }
+}
+
 /// A `CommandExecutor` is a wrapper around [`std::process::Command`] to execute a target as a child process.
 /// Construct a `CommandExecutor` by implementing [`CommandConfigurator`] for a type of your choice and calling [`CommandConfigurator::into_executor`] on it.
 /// Instead, you can use [`CommandExecutor::builder()`] to construct a [`CommandExecutor`] backed by a [`StdCommandConfigurator`].
@@ -152,12 +235,16 @@ where
     observers: OT,
     /// cache if the AsanBacktraceObserver is present
     has_asan_observer: bool,
+    /// cache if the [`MsanErrorsObserver`] is present
+    has_msan_observer: bool,
     /// If set, we found a [`StdErrObserver`] in the observer list.
     /// Pipe the child's `stderr` instead of closing it.
     has_stdout_observer: bool,
     /// If set, we found a [`StdOutObserver`] in the observer list
     /// Pipe the child's `stdout` instead of closing it.
     has_stderr_observer: bool,
+    /// If set, a target exiting with a nonzero exit code counts as crash
+    crash_on_nonzero_exit: bool,
     phantom: PhantomData<(EM, I, S, Z)>,
 }
 
@@ -202,6 +289,28 @@ where
     pub fn inner(&mut self) -> &mut T {
         &mut self.configurer
     }
+
+    /// If a target exiting with a nonzero exit code counts as crash (default)
+    #[must_use]
+    pub fn crash_on_nonzero_exit(&self) -> bool {
+        self.crash_on_nonzero_exit
+    }
+
+    /// Sets if a target exiting with a nonzero exit code counts as crash.
+    /// Targets rejecting most inputs with an error code should not.
+    pub fn set_crash_on_nonzero_exit(&mut self, crash_on_nonzero_exit: bool) {
+        self.crash_on_nonzero_exit = crash_on_nonzero_exit;
+    }
+}
+
+impl<EM, I, OT, S, T, Z> Named for CommandExecutor<EM, I, OT, S, T, Z>
+where
+    T: Debug,
+    OT: Debug,
+{
+    fn name(&self) -> &str {
+        "CommandExecutor"
+    }
 }
 
 impl<EM, I, OT, S, Z> CommandExecutor<EM, I, OT, S, StdCommandConfigurator, Z>
@@ -239,13 +348,17 @@ where
         let has_asan_observer = observers
             .match_name::<ASANBacktraceObserver>("ASANBacktraceObserver")
             .is_some();
-        if has_stderr_observer || has_asan_observer {
+        let has_msan_observer = observers
+            .match_name::<MsanErrorsObserver>("MsanErrorsObserver")
+            .is_some();
+        if has_stderr_observer || has_asan_observer || has_msan_observer {
             command.stderr(Stdio::piped());
         }
 
         Ok(Self {
             observers,
             has_asan_observer,
+            has_msan_observer,
             configurer: StdCommandConfigurator {
                 input_location: InputLocation::File {
                     out_file: OutFile::create(path)?,
@@ -255,6 +368,7 @@ where
             },
             has_stdout_observer,
             has_stderr_observer,
+            crash_on_nonzero_exit: true,
             phantom: PhantomData,
         })
     }
@@ -320,17 +434,20 @@ where
         use std::os::unix::prelude::ExitStatusExt;
         use wait_timeout::ChildExt;
 
+        // A target failing to start up, or to take the input, is a setup problem, not a crash
         let mut child = self.configurer.spawn_child(input)?;
 
         let res = match child
             .wait_timeout(Duration::from_secs(5))
             .expect("waiting on child failed")
-            .map(|status| status.signal())
         {
-            // for reference: https://www.man7.org/linux/man-pages/man7/signal.7.html
-            Some(Some(9)) => Ok(ExitKind::Oom),
-            Some(Some(_)) => Ok(ExitKind::Crash),
-            Some(None) => Ok(ExitKind::Ok),
+            Some(status) => match status.signal() {
+                // for reference: https://www.man7.org/linux/man-pages/man7/signal.7.html
+                Some(9) => Ok(ExitKind::Oom),
+                Some(_) => Ok(ExitKind::Crash),
+                None if self.crash_on_nonzero_exit && !status.success() => Ok(ExitKind::Crash),
+                None => Ok(ExitKind::Ok),
+            },
             None => {
                 // if this fails, there is not much we can do. let's hope it failed because the process finished
                 // in the meantime.
@@ -341,7 +458,7 @@ where
             }
         };
 
-        if self.has_asan_observer || self.has_stderr_observer {
+        if self.has_asan_observer || self.has_msan_observer || self.has_stderr_observer {
             let mut stderr = String::new();
             child.stderr.as_mut().ok_or_else(|| {
                 Error::IllegalState(
@@ -354,6 +471,12 @@ where
                     .unwrap()
                     .parse_asan_output(&stderr);
             }
+            if self.has_msan_observer {
+                self.observers
+                    .match_name_mut::<MsanErrorsObserver>("MsanErrorsObserver")
+                    .unwrap()
+                    .parse_msan_output(&stderr);
+            }
             if self.has_stderr_observer {
                 self.observers
                     .match_name_mut::<StdErrObserver>("StdErrObserver")
@@ -399,6 +522,7 @@ pub struct CommandExecutorBuilder {
     input_location: InputLocation,
     cwd: Option<PathBuf>,
     envs: Vec<(OsString, OsString)>,
+    crash_on_nonzero_exit: bool,
 }
 
 impl Default for CommandExecutorBuilder {
@@ -418,6 +542,7 @@ impl CommandExecutorBuilder {
             cwd: None,
             envs: vec![],
             debug_child: false,
+            crash_on_nonzero_exit: true,
         }
     }
 
@@ -522,6 +647,30 @@ impl CommandExecutorBuilder {
         self
     }
 
+    /// Passes the shared map `shmem` to the target, via the [`COVERAGE_MAP_SHM_ENV`] env var.
+    /// A target instrumented with the `libafl_targets` runtime records its coverage to it,
+    /// so observe the map, e.g. with a `StdMapObserver`, to get the coverage of each run.
+    pub fn coverage_map<SHM>(&mut self, shmem: &SHM) -> &mut CommandExecutorBuilder
+    where
+        SHM: ShMem,
+    {
+        self.env(COVERAGE_MAP_SHM_ENV, shmem.id().to_string());
+        self.env(
+            format!("{}_SIZE", COVERAGE_MAP_SHM_ENV),
+            shmem.len().to_string(),
+        );
+        self
+    }
+
+    /// If set to true (default), a target exiting with a nonzero exit code counts as crash.
+    pub fn crash_on_nonzero_exit(
+        &mut self,
+        crash_on_nonzero_exit: bool,
+    ) -> &mut CommandExecutorBuilder {
+        self.crash_on_nonzero_exit = crash_on_nonzero_exit;
+        self
+    }
+
     /// If set to true, the child's output won't be redirecited to `/dev/null`.
     /// Defaults to `false`.
     pub fn debug_child(&mut self, debug_child: bool) -> &mut CommandExecutorBuilder {
@@ -569,6 +718,9 @@ impl CommandExecutorBuilder {
         if observers
             .match_name::<ASANBacktraceObserver>("ASANBacktraceObserver")
             .is_some()
+            || observers
+                .match_name::<MsanErrorsObserver>("MsanErrorsObserver")
+                .is_some()
             || observers
                 .match_name::<StdErrObserver>("StdErrObserver")
                 .is_some()
@@ -588,7 +740,9 @@ impl CommandExecutorBuilder {
             input_location: self.input_location.clone(),
             command,
         };
-        Ok(configurator.into_executor(observers))
+        let mut executor = configurator.into_executor(observers);
+        executor.set_crash_on_nonzero_exit(self.crash_on_nonzero_exit);
+        Ok(executor)
     }
 }
 
@@ -638,6 +792,10 @@ pub trait CommandConfigurator: Sized + Debug {
             .match_name::<ASANBacktraceObserver>("ASANBacktraceObserver")
             .is_some();
 
+        let has_msan_observer = observers
+            .match_name::<MsanErrorsObserver>("MsanErrorsObserver")
+            .is_some();
+
         let has_stdout_observer = observers
             .match_name::<StdOutObserver>("StdOutObserver")
             .is_some();
@@ -649,8 +807,10 @@ pub trait CommandConfigurator: Sized + Debug {
         CommandExecutor {
             observers,
             has_asan_observer,
+            has_msan_observer,
             has_stdout_observer,
             has_stderr_observer,
+            crash_on_nonzero_exit: true,
             configurer: self,
             phantom: PhantomData,
         }
@@ -659,14 +819,18 @@ pub trait CommandConfigurator: Sized + Debug {
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
+
     use crate::{
+        bolts::shmem::{ShMem, ShMemDescription, ShMemProvider, StdShMemProvider},
         events::SimpleEventManager,
         executors::{
-            command::{CommandExecutor, InputLocation},
-            Executor,
+            command::{CommandExecutor, InputLocation, COVERAGE_MAP_SHM_ENV},
+            Executor, ExitKind,
         },
-        inputs::BytesInput,
+        inputs::{BytesInput, CommandLineInput, INPUT_FILE_PLACEHOLDER},
         monitors::SimpleMonitor,
+        Error,
     };
 
     #[test]
@@ -712,4 +876,173 @@ mod tests {
             )
             .unwrap();
     }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_file_input() {
+        let mut mgr = SimpleEventManager::<BytesInput, _>::new(SimpleMonitor::new(|status| {
+            println!("{}", status);
+        }));
+        fs::create_dir_all("target/.test").unwrap();
+
+        // A trivial target, reading the file it gets as `$0`, failing on `crash`
+        let mut executor = CommandExecutor::builder();
+        executor
+            .program("sh")
+            .arg("-c")
+            .arg("grep -q crash \"$0\" && exit 1; exit 0")
+            .arg_input_file("target/.test/command_input");
+        let mut executor = executor.build(()).unwrap();
+
+        for (input, exit_kind) in [
+            (&b"fine"[..], ExitKind::Ok),
+            (&b"crash"[..], ExitKind::Crash),
+        ] {
+            assert_eq!(
+                executor
+                    .run_target(&mut (), &mut (), &mut mgr, &BytesInput::new(input.to_vec()))
+                    .unwrap(),
+                exit_kind
+            );
+        }
+
+        executor.set_crash_on_nonzero_exit(false);
+        assert_eq!(
+            executor
+                .run_target(
+                    &mut (),
+                    &mut (),
+                    &mut mgr,
+                    &BytesInput::new(b"crash".to_vec())
+                )
+                .unwrap(),
+            ExitKind::Ok
+        );
+
+        // A target that does not start up is an error, not a crash
+        let mut executor = CommandExecutor::builder();
+        executor
+            .program("target/.test/no_such_target")
+            .arg_input_file("target/.test/command_input");
+        let mut executor = executor.build(()).unwrap();
+        assert!(matches!(
+            executor.run_target(
+                &mut (),
+                &mut (),
+                &mut mgr,
+                &BytesInput::new(b"fine".to_vec())
+            ),
+            Err(Error::File(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_coverage_map() {
+        let mut mgr = SimpleEventManager::<BytesInput, _>::new(SimpleMonitor::new(|status| {
+            println!("{}", status);
+        }));
+        fs::create_dir_all("target/.test").unwrap();
+        let mut provider = StdShMemProvider::new().unwrap();
+        let mut shmem = provider.new_shmem(64).unwrap();
+        let id = shmem.id().to_string();
+
+        // The target fails unless it gets the id and the size of the map
+        let mut executor = CommandExecutor::builder();
+        executor
+            .program("sh")
+            .arg("-c")
+            .arg(format!(
+                "[ \"${0}\" = \"{1}\" ] && [ \"${0}_SIZE\" = {2} ]",
+                COVERAGE_MAP_SHM_ENV,
+                id,
+                shmem.len()
+            ))
+            .arg_input_file("target/.test/coverage_map_input")
+            .coverage_map(&shmem);
+        let mut executor = executor.build(()).unwrap();
+        assert_eq!(
+            executor
+                .run_target(
+                    &mut (),
+                    &mut (),
+                    &mut mgr,
+                    &BytesInput::new(b"fine".to_vec())
+                )
+                .unwrap(),
+            ExitKind::Ok
+        );
+
+        // The target attaches to the same map, as `edges_map_from_env` does
+        let envs: Vec<(String, String)> = executor
+            .inner()
+            .command
+            .get_envs()
+            .filter_map(|(key, value)| {
+                Some((key.to_str()?.to_string(), value?.to_str()?.to_string()))
+            })
+            .collect();
+        let env = |name: &str| {
+            envs.iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+                .unwrap()
+        };
+        let size = env(&format!("{}_SIZE", COVERAGE_MAP_SHM_ENV))
+            .parse()
+            .unwrap();
+        let mut attached = provider
+            .shmem_from_description(ShMemDescription::from_string_and_size(
+                &env(COVERAGE_MAP_SHM_ENV),
+                size,
+            ))
+            .unwrap();
+        attached.as_mut_slice()[3] = 1;
+        assert_eq!(shmem.as_mut_slice()[3], 1);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_command_line_input() {
+        let mut mgr =
+            SimpleEventManager::<CommandLineInput, _>::new(SimpleMonitor::new(|status| {
+                println!("{}", status);
+            }));
+        fs::create_dir_all("target/.test").unwrap();
+
+        // The configured arguments get replaced by the ones of the input
+        let mut executor = CommandExecutor::builder();
+        executor
+            .program("sh")
+            .arg("-c")
+            .arg("exit 0")
+            .arg_input_file("target/.test/command_line_input");
+        let mut executor = executor.build(()).unwrap();
+
+        // Crashes if the file, given for the placeholder, contains the pattern from the env
+        let script = "grep -q \"$PATTERN\" \"$0\" && exit 1; exit 0";
+        for (pattern, exit_kind) in [("fine", ExitKind::Crash), ("crash", ExitKind::Ok)] {
+            // The argument and variables a process cannot get are left out, instead of failing the spawn
+            let input = CommandLineInput::new(
+                b"fine".to_vec(),
+                vec![
+                    "-c".to_string(),
+                    "nul\0".to_string(),
+                    script.to_string(),
+                    INPUT_FILE_PLACEHOLDER.to_string(),
+                ],
+                vec![
+                    ("PATTERN".to_string(), pattern.to_string()),
+                    ("KEY=".to_string(), "value".to_string()),
+                    ("NUL".to_string(), "\0".to_string()),
+                ],
+            );
+            assert_eq!(
+                executor
+                    .run_target(&mut (), &mut (), &mut mgr, &input)
+                    .unwrap(),
+                exit_kind
+            );
+        }
+    }
 }
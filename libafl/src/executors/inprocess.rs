@@ -25,11 +25,17 @@ use std::intrinsics::transmute;
 #[cfg(all(feature = "std", unix))]
 use libc::{siginfo_t, ucontext_t};
 
+#[cfg(all(feature = "std", unix, unstable_feature))]
+use core::sync::atomic::AtomicBool;
+#[cfg(all(feature = "std", unix))]
+use core::{mem, sync::atomic::AtomicI32};
 #[cfg(all(feature = "std", unix))]
 use nix::{
     sys::wait::{waitpid, WaitStatus},
-    unistd::{fork, ForkResult},
+    unistd::{close, fork, pipe, read, ForkResult},
 };
+#[cfg(all(feature = "std", unix))]
+use std::os::unix::io::RawFd;
 
 #[cfg(unix)]
 use crate::bolts::os::unix_signals::setup_signal_handler;
@@ -117,6 +123,18 @@ where
         self.handlers.post_run_target();
         Ok(ret)
     }
+
+    /// Runs the target on each of the `inputs`, arming the handlers only once for the whole batch.
+    /// The observers run for each input, see [`Self::run_targets_observed`].
+    fn run_targets(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        inputs: &[I],
+    ) -> Result<Vec<ExitKind>, Error> {
+        self.run_targets_observed(fuzzer, state, mgr, inputs, |_, _, _, _| Ok(()))
+    }
 }
 
 impl<H, HB, I, OT, S> HasObservers<I, OT, S> for GenericInProcessExecutor<H, HB, I, OT, S>
@@ -137,6 +155,51 @@ where
     }
 }
 
+impl<H, HB, I, OT, S> GenericInProcessExecutor<H, HB, I, OT, S>
+where
+    H: FnMut(&I) -> ExitKind + ?Sized,
+    HB: BorrowMut<H>,
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    /// Runs the target on each of the `inputs`, in order, arming the handlers only once for the whole batch.
+    /// The observers run around each input, and `on_run` gets called with them after each run,
+    /// to collect the observations (such as the coverage) of each input.
+    /// Returns the [`ExitKind`] of each run.
+    pub fn run_targets_observed<EM, F, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        inputs: &[I],
+        mut on_run: F,
+    ) -> Result<Vec<ExitKind>, Error>
+    where
+        F: FnMut(&mut S, &I, &OT, &ExitKind) -> Result<(), Error>,
+    {
+        let first = match inputs.first() {
+            Some(first) => first,
+            None => return Ok(vec![]),
+        };
+        self.handlers
+            .pre_run_target(self, fuzzer, state, mgr, first);
+
+        let mut exit_kinds = Vec::with_capacity(inputs.len());
+        let res: Result<(), Error> = inputs.iter().try_for_each(|input| {
+            self.handlers.set_current_input(input);
+            self.observers.pre_exec_all(state, input)?;
+            let exit_kind = (self.harness_fn.borrow_mut())(input);
+            self.observers.post_exec_all(state, input, &exit_kind)?;
+            on_run(state, input, &self.observers, &exit_kind)?;
+            exit_kinds.push(exit_kind);
+            Ok(())
+        });
+
+        self.handlers.post_run_target();
+        res.map(|()| exit_kinds)
+    }
+}
+
 impl<H, HB, I, OT, S> GenericInProcessExecutor<H, HB, I, OT, S>
 where
     H: FnMut(&I) -> ExitKind + ?Sized,
@@ -210,6 +273,28 @@ where
     pub fn handlers_mut(&mut self) -> &mut InProcessHandlers {
         &mut self.handlers
     }
+
+    /// Handles the signals of threads spawned by the harness, `false` by default.
+    /// Crash signals get delivered to the thread that crashed, so a crash on a harness thread
+    /// gets handled on that thread, while the fuzzing thread keeps running.
+    /// If set, the first crashing thread stores the solution and the other crashing threads stop until the process exits,
+    /// and timeouts delivered to a harness thread get forwarded to the fuzzing thread,
+    /// after which the harness thread blocks the timeout signals.
+    /// The harness should still join its threads before returning, as a thread crashing after the run
+    /// can not be attributed to an input.
+    /// Harness threads that must not get interrupted at all can call [`block_timeout_signals`] when they start.
+    /// Only has an effect on unix.
+    #[inline]
+    pub fn set_handle_threads(&mut self, handle_threads: bool) {
+        self.handlers.handle_threads = handle_threads;
+    }
+
+    /// Returns `true` if the signals of threads spawned by the harness get handled, see [`Self::set_handle_threads`]
+    #[inline]
+    #[must_use]
+    pub fn handle_threads(&self) -> bool {
+        self.handlers.handle_threads
+    }
 }
 
 /// The struct has [`InProcessHandlers`].
@@ -240,6 +325,8 @@ pub struct InProcessHandlers {
     pub crash_handler: *const c_void,
     /// On timeout C function pointer
     pub timeout_handler: *const c_void,
+    /// If the signals of threads spawned by the harness get handled
+    pub handle_threads: bool,
 }
 
 impl InProcessHandlers {
@@ -266,6 +353,16 @@ impl InProcessHandlers {
             );
             data.crash_handler = self.crash_handler;
             data.timeout_handler = self.timeout_handler;
+            write_volatile(
+                &mut data.fuzz_thread,
+                if self.handle_threads {
+                    Some(libc::pthread_self())
+                } else {
+                    None
+                },
+            );
+            // A new run, no crash got handled yet
+            unix_signal_handler::CRASH_HANDLED.store(false, Ordering::SeqCst);
             // Direct raw pointers access /aliasing is pretty undefined behavior.
             // Since the state and event may have moved in memory, refresh them right before the signal may happen
             write_volatile(&mut data.state_ptr, _state as *mut _ as *mut c_void);
@@ -310,6 +407,19 @@ impl InProcessHandlers {
         }
     }
 
+    /// Points the handlers to the input of the next run, in a batch of runs armed by [`Self::pre_run_target`]
+    #[inline]
+    pub fn set_current_input<I>(&self, _input: &I) {
+        #[cfg(any(unix, all(windows, feature = "std")))]
+        unsafe {
+            write_volatile(
+                &mut GLOBAL_STATE.current_input_ptr,
+                _input as *const _ as *const c_void,
+            );
+            compiler_fence(Ordering::SeqCst);
+        }
+    }
+
     /// Create new [`InProcessHandlers`].
     pub fn new<E, EM, I, OF, OT, S, Z, H>() -> Result<Self, Error>
     where
@@ -334,6 +444,7 @@ impl InProcessHandlers {
                     as *const c_void,
                 timeout_handler: unix_signal_handler::inproc_timeout_handler::<E, EM, I, OF, OT, S, Z>
                     as *const _,
+                handle_threads: false,
             })
         }
         #[cfg(all(windows, feature = "std"))]
@@ -363,12 +474,14 @@ impl InProcessHandlers {
                     S,
                     Z,
                 > as *const c_void,
+                handle_threads: false,
             })
         }
         #[cfg(not(any(unix, all(windows, feature = "std"))))]
         Ok(Self {
             crash_handler: ptr::null(),
             timeout_handler: ptr::null(),
+            handle_threads: false,
         })
     }
 
@@ -378,6 +491,7 @@ impl InProcessHandlers {
         Self {
             crash_handler: ptr::null(),
             timeout_handler: ptr::null(),
+            handle_threads: false,
         }
     }
 }
@@ -393,6 +507,8 @@ pub struct InProcessExecutorHandlerData {
     pub current_input_ptr: *const c_void,
     pub crash_handler: *const c_void,
     pub timeout_handler: *const c_void,
+    #[cfg(unix)]
+    pub fuzz_thread: Option<libc::pthread_t>,
     #[cfg(windows)]
     pub tp_timer: *mut c_void,
     #[cfg(windows)]
@@ -461,6 +577,9 @@ pub static mut GLOBAL_STATE: InProcessExecutorHandlerData = InProcessExecutorHan
     crash_handler: ptr::null(),
     /// The timeout handler fn
     timeout_handler: ptr::null(),
+    /// The fuzzing thread, if the signals of harness threads get handled
+    #[cfg(unix)]
+    fuzz_thread: None,
     #[cfg(windows)]
     tp_timer: ptr::null_mut(),
     #[cfg(windows)]
@@ -501,10 +620,36 @@ pub fn inprocess_get_input<'a, I>() -> Option<&'a I> {
     unsafe { (GLOBAL_STATE.current_input_ptr as *const I).as_ref() }
 }
 
+/// Blocks the timeout signals of the executors, `SIGALRM` and `SIGUSR2`, on the calling thread.
+/// Call it at the start of the threads spawned by a harness, so that timeouts only ever interrupt the fuzzing thread,
+/// see [`InProcessExecutor::set_handle_threads`].
+#[cfg(unix)]
+pub fn block_timeout_signals() -> Result<(), Error> {
+    unsafe {
+        let mut set: libc::sigset_t = core::mem::zeroed();
+        libc::sigemptyset(&mut set);
+        add_timeout_signals(&mut set);
+        if libc::pthread_sigmask(libc::SIG_BLOCK, &set, ptr::null_mut()) != 0 {
+            return Err(Error::Unknown("Failed to block the timeout signals".into()));
+        }
+    }
+    Ok(())
+}
+
+/// Adds the timeout signals, `SIGALRM` and `SIGUSR2`, to the given set
+#[cfg(unix)]
+unsafe fn add_timeout_signals(set: &mut libc::sigset_t) {
+    libc::sigaddset(set, libc::SIGALRM);
+    libc::sigaddset(set, libc::SIGUSR2);
+}
+
 #[cfg(unix)]
 mod unix_signal_handler {
     use alloc::vec::Vec;
-    use core::mem::transmute;
+    use core::{
+        mem::transmute,
+        sync::atomic::{AtomicBool, Ordering},
+    };
     use libc::siginfo_t;
     #[cfg(feature = "std")]
     use std::{
@@ -517,12 +662,16 @@ mod unix_signal_handler {
         corpus::{Corpus, Testcase},
         events::{Event, EventFirer, EventRestarter},
         executors::{
-            inprocess::{InProcessExecutorHandlerData, GLOBAL_STATE},
+            inprocess::{add_timeout_signals, InProcessExecutorHandlerData, GLOBAL_STATE},
             Executor, ExitKind, HasObservers,
         },
-        feedbacks::Feedback,
+        feedbacks::{
+            triage::{record_crash_context, CrashContext},
+            Feedback,
+        },
         fuzzer::HasObjective,
         inputs::Input,
+        libafl_log,
         observers::ObserversTuple,
         state::{HasClientPerfMonitor, HasMetadata, HasSolutions},
     };
@@ -530,6 +679,39 @@ mod unix_signal_handler {
     pub type HandlerFuncPtr =
         unsafe fn(Signal, siginfo_t, &mut ucontext_t, data: &mut InProcessExecutorHandlerData);
 
+    /// Set by the first thread handling a crash, if the signals of harness threads get handled
+    pub static CRASH_HANDLED: AtomicBool = AtomicBool::new(false);
+
+    /// What a thread receiving a signal does, if the signals of harness threads get handled
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ThreadSignalAction {
+        /// Handles the signal on this thread
+        Handle,
+        /// Forwards the timeout to the fuzzing thread
+        Forward,
+        /// Waits for the exit of the process, as another thread handles a crash already
+        Wait,
+    }
+
+    /// Decides what the `current` thread does with the `signal`, given the fuzzing thread
+    pub fn thread_signal_action(
+        signal: Signal,
+        fuzz_thread: libc::pthread_t,
+        current: libc::pthread_t,
+    ) -> ThreadSignalAction {
+        if matches!(signal, Signal::SigUser2 | Signal::SigAlarm) {
+            if current == fuzz_thread {
+                ThreadSignalAction::Handle
+            } else {
+                ThreadSignalAction::Forward
+            }
+        } else if CRASH_HANDLED.swap(true, Ordering::SeqCst) {
+            ThreadSignalAction::Wait
+        } else {
+            ThreadSignalAction::Handle
+        }
+    }
+
     /// A handler that does nothing.
     /*pub fn nop_handler(
         _signal: Signal,
@@ -544,6 +726,24 @@ mod unix_signal_handler {
         fn handle(&mut self, signal: Signal, info: siginfo_t, context: &mut ucontext_t) {
             unsafe {
                 let data = &mut GLOBAL_STATE;
+                if let Some(fuzz_thread) = data.fuzz_thread {
+                    match thread_signal_action(signal, fuzz_thread, libc::pthread_self()) {
+                        ThreadSignalAction::Handle => (),
+                        ThreadSignalAction::Forward => {
+                            // A harness thread got the timeout, the fuzzing thread handles it.
+                            // The harness thread blocks the timeouts once this handler returns.
+                            libc::pthread_kill(fuzz_thread, signal as i32);
+                            add_timeout_signals(&mut context.uc_sigmask);
+                            return;
+                        }
+                        ThreadSignalAction::Wait => {
+                            // Another thread is handling a crash already, and will exit the process
+                            loop {
+                                libc::pause();
+                            }
+                        }
+                    }
+                }
                 match signal {
                     Signal::SigUser2 | Signal::SigAlarm => {
                         if !data.timeout_handler.is_null() {
@@ -633,11 +833,9 @@ mod unix_signal_handler {
 
                 event_mgr.on_restart(state).unwrap();
 
-                #[cfg(feature = "std")]
-                println!("Waiting for broker...");
+                libafl_log!(Info, "Waiting for broker...");
                 event_mgr.await_restart_safe();
-                #[cfg(feature = "std")]
-                println!("Bye!");
+                libafl_log!(Info, "Bye!");
 
                 event_mgr.await_restart_safe();
 
@@ -664,8 +862,10 @@ mod unix_signal_handler {
         Z: HasObjective<I, OF, S>,
     {
         if !data.is_valid() {
-            #[cfg(feature = "std")]
-            println!("TIMEOUT or SIGUSR2 happened, but currently not fuzzing.");
+            libafl_log!(
+                Warn,
+                "TIMEOUT or SIGUSR2 happened, but currently not fuzzing."
+            );
             return;
         }
 
@@ -677,8 +877,7 @@ mod unix_signal_handler {
 
         let input = data.take_current_input::<I>();
 
-        #[cfg(feature = "std")]
-        println!("Timeout in fuzz run.");
+        libafl_log!(Error, "Timeout in fuzz run.");
         #[cfg(feature = "std")]
         let _res = stdout().flush();
 
@@ -714,11 +913,9 @@ mod unix_signal_handler {
 
         event_mgr.on_restart(state).unwrap();
 
-        #[cfg(feature = "std")]
-        println!("Waiting for broker...");
+        libafl_log!(Info, "Waiting for broker...");
         event_mgr.await_restart_safe();
-        #[cfg(feature = "std")]
-        println!("Bye!");
+        libafl_log!(Info, "Bye!");
 
         event_mgr.await_restart_safe();
 
@@ -747,8 +944,7 @@ mod unix_signal_handler {
         let _context = &mut *(((_context as *mut _ as *mut libc::c_void as usize) + 128)
             as *mut libc::c_void as *mut ucontext_t);
 
-        #[cfg(feature = "std")]
-        eprintln!("Crashed with {}", signal);
+        libafl_log!(Error, "Crashed with {}", signal);
         if data.is_valid() {
             let executor = data.executor_mut::<E>();
             // disarms timeout in case of TimeoutExecutor
@@ -764,8 +960,8 @@ mod unix_signal_handler {
                 .post_exec_all(state, input, &ExitKind::Crash)
                 .expect("Observers post_exec_all failed");
 
-            #[cfg(feature = "std")]
-            eprintln!("Child crashed!");
+            libafl_log!(Error, "Child crashed!");
+            record_crash_context(CrashContext::from_signal(signal, &_info, _context));
 
             #[cfg(all(feature = "std", unix))]
             {
@@ -805,24 +1001,19 @@ mod unix_signal_handler {
 
             event_mgr.on_restart(state).unwrap();
 
-            #[cfg(feature = "std")]
-            eprintln!("Waiting for broker...");
+            libafl_log!(Info, "Waiting for broker...");
             event_mgr.await_restart_safe();
-            #[cfg(feature = "std")]
-            eprintln!("Bye!");
+            libafl_log!(Info, "Bye!");
         } else {
             #[cfg(feature = "std")]
             {
-                eprintln!("Double crash\n");
+                libafl_log!(Error, "Double crash");
                 #[cfg(target_os = "android")]
                 let si_addr = (_info._pad[0] as i64) | ((_info._pad[1] as i64) << 32);
                 #[cfg(not(target_os = "android"))]
                 let si_addr = { _info.si_addr() as usize };
 
-                eprintln!(
-                "We crashed at addr 0x{:x}, but are not in the target... Bug in the fuzzer? Exiting.",
-                si_addr
-                );
+                libafl_log!(Error, "We crashed at addr 0x{:x}, but are not in the target... Bug in the fuzzer? Exiting.", si_addr);
 
                 #[cfg(all(feature = "std", unix))]
                 {
@@ -873,6 +1064,7 @@ mod windows_exception_handler {
         feedbacks::Feedback,
         fuzzer::HasObjective,
         inputs::Input,
+        libafl_log,
         observers::ObserversTuple,
         state::{HasClientPerfMonitor, HasMetadata, HasSolutions},
     };
@@ -985,11 +1177,9 @@ mod windows_exception_handler {
 
                 event_mgr.on_restart(state).unwrap();
 
-                #[cfg(feature = "std")]
-                println!("Waiting for broker...");
+                libafl_log!(Info, "Waiting for broker...");
                 event_mgr.await_restart_safe();
-                #[cfg(feature = "std")]
-                println!("Bye!");
+                libafl_log!(Info, "Bye!");
 
                 event_mgr.await_restart_safe();
 
@@ -1032,11 +1222,12 @@ mod windows_exception_handler {
             let observers = executor.observers_mut();
 
             if data.timeout_input_ptr.is_null() {
-                #[cfg(feature = "std")]
-                dbg!("TIMEOUT or SIGUSR2 happened, but currently not fuzzing. Exiting");
+                libafl_log!(
+                    Warn,
+                    "TIMEOUT or SIGUSR2 happened, but currently not fuzzing. Exiting"
+                );
             } else {
-                #[cfg(feature = "std")]
-                eprintln!("Timeout in fuzz run.");
+                libafl_log!(Error, "Timeout in fuzz run.");
                 #[cfg(feature = "std")]
                 let _res = stdout().flush();
 
@@ -1075,11 +1266,9 @@ mod windows_exception_handler {
 
                 event_mgr.on_restart(state).unwrap();
 
-                #[cfg(feature = "std")]
-                eprintln!("Waiting for broker...");
+                libafl_log!(Info, "Waiting for broker...");
                 event_mgr.await_restart_safe();
-                #[cfg(feature = "std")]
-                eprintln!("Bye!");
+                libafl_log!(Info, "Bye!");
 
                 event_mgr.await_restart_safe();
                 compiler_fence(Ordering::SeqCst);
@@ -1144,12 +1333,11 @@ mod windows_exception_handler {
         )
         .unwrap();
 
-        #[cfg(feature = "std")]
-        eprintln!("Crashed with {}", code);
+        libafl_log!(Error, "Crashed with {}", code);
         if data.current_input_ptr.is_null() {
             #[cfg(feature = "std")]
             {
-                eprintln!("Double crash\n");
+                libafl_log!(Error, "Double crash");
                 let crash_addr = exception_pointers
                     .as_mut()
                     .unwrap()
@@ -1158,10 +1346,7 @@ mod windows_exception_handler {
                     .unwrap()
                     .ExceptionAddress as usize;
 
-                eprintln!(
-                "We crashed at addr 0x{:x}, but are not in the target... Bug in the fuzzer? Exiting.",
-                    crash_addr
-                );
+                libafl_log!(Error, "We crashed at addr 0x{:x}, but are not in the target... Bug in the fuzzer? Exiting.", crash_addr);
             }
             #[cfg(feature = "std")]
             {
@@ -1186,16 +1371,14 @@ mod windows_exception_handler {
             let event_mgr = data.event_mgr_mut::<EM>();
             let observers = executor.observers_mut();
 
-            #[cfg(feature = "std")]
-            eprintln!("Child crashed!");
+            libafl_log!(Error, "Child crashed!");
             #[cfg(feature = "std")]
             drop(stdout().flush());
 
             // Make sure we don't crash in the crash handler forever.
             let input = data.take_current_input::<I>();
 
-            #[cfg(feature = "std")]
-            eprintln!("Child crashed!");
+            libafl_log!(Error, "Child crashed!");
             #[cfg(feature = "std")]
             drop(stdout().flush());
 
@@ -1232,11 +1415,9 @@ mod windows_exception_handler {
 
             event_mgr.on_restart(state).unwrap();
 
-            #[cfg(feature = "std")]
-            eprintln!("Waiting for broker...");
+            libafl_log!(Info, "Waiting for broker...");
             event_mgr.await_restart_safe();
-            #[cfg(feature = "std")]
-            eprintln!("Bye!");
+            libafl_log!(Info, "Bye!");
         }
         ExitProcess(1);
     }
@@ -1347,6 +1528,43 @@ impl InProcessForkExecutorGlobalData {
     }
 }
 
+/// The write end of the pipe the child of an [`InProcessForkExecutor`] with a memory limit reports running out of memory on,
+/// or `-1`
+#[cfg(all(feature = "std", unix))]
+static FORK_CHILD_OOM_FD: AtomicI32 = AtomicI32::new(-1);
+
+/// Set by the alloc error hook of the child of an [`InProcessForkExecutor`], right before it aborts
+#[cfg(all(feature = "std", unix, unstable_feature))]
+static FORK_CHILD_OOM: AtomicBool = AtomicBool::new(false);
+
+/// One end of a pipe, closed on drop
+#[cfg(all(feature = "std", unix))]
+#[derive(Debug)]
+struct PipeEnd(RawFd);
+
+#[cfg(all(feature = "std", unix))]
+impl PipeEnd {
+    /// Opens a new pipe, returning its read and its write end
+    fn pipe() -> Result<(Self, Self), Error> {
+        let (read_fd, write_fd) = pipe()?;
+        Ok((Self(read_fd), Self(write_fd)))
+    }
+
+    /// Gives up ownership of the fd, which is no longer closed on drop
+    fn into_raw_fd(self) -> RawFd {
+        let fd = self.0;
+        mem::forget(self);
+        fd
+    }
+}
+
+#[cfg(all(feature = "std", unix))]
+impl Drop for PipeEnd {
+    fn drop(&mut self) {
+        let _ = close(self.0);
+    }
+}
+
 /// a static variable storing the global state
 #[cfg(all(feature = "std", unix))]
 pub static mut FORK_EXECUTOR_GLOBAL_DATA: InProcessForkExecutorGlobalData =
@@ -1400,6 +1618,7 @@ where
     shmem_provider: SP,
     observers: OT,
     handlers: InChildProcessHandlers,
+    memory_limit: Option<u64>,
     phantom: PhantomData<(I, S)>,
 }
 
@@ -1415,6 +1634,7 @@ where
         f.debug_struct("InProcessForkExecutor")
             .field("observers", &self.observers)
             .field("shmem_provider", &self.shmem_provider)
+            .field("memory_limit", &self.memory_limit)
             .finish()
     }
 }
@@ -1438,12 +1658,36 @@ where
         input: &I,
     ) -> Result<ExitKind, Error> {
         unsafe {
+            // The child reports running out of memory on this pipe, telling it apart from a crash
+            let oom_pipe = match self.memory_limit {
+                Some(_) => Some(PipeEnd::pipe()?),
+                None => None,
+            };
             self.shmem_provider.pre_fork()?;
             match fork() {
                 Ok(ForkResult::Child) => {
                     // Child
                     self.shmem_provider.post_fork(true)?;
 
+                    if let Some((oom_read, oom_write)) = oom_pipe {
+                        drop(oom_read);
+                        FORK_CHILD_OOM_FD.store(oom_write.into_raw_fd(), Ordering::Relaxed);
+                        #[cfg(unstable_feature)]
+                        std::alloc::set_alloc_error_hook(
+                            child_signal_handlers::child_alloc_error_hook,
+                        );
+                    }
+                    if let Some(memory_limit) = self.memory_limit {
+                        let r = libc::rlimit {
+                            rlim_cur: memory_limit,
+                            rlim_max: memory_limit,
+                        };
+                        assert!(
+                            libc::setrlimit(libc::RLIMIT_AS, &r) == 0,
+                            "Failed to set the memory limit of the child"
+                        );
+                    }
+
                     self.handlers.pre_run_target(self, state, input);
 
                     self.observers
@@ -1465,9 +1709,22 @@ where
                     // println!("from parent {} child is {}", std::process::id(), child);
                     self.shmem_provider.post_fork(false)?;
 
+                    // Only the child writes to the pipe
+                    let oom_read = oom_pipe.map(|(oom_read, _)| oom_read);
+
                     let res = waitpid(child, None)?;
 
+                    // The child is gone, so this does not block
+                    let oom = match oom_read {
+                        Some(oom_read) => {
+                            let mut buf = [0_u8; 1];
+                            read(oom_read.0, &mut buf)? > 0
+                        }
+                        None => false,
+                    };
+
                     match res {
+                        WaitStatus::Signaled(_, _, _) if oom => Ok(ExitKind::Oom),
                         WaitStatus::Signaled(_, _, _) => Ok(ExitKind::Crash),
                         _ => Ok(ExitKind::Ok),
                     }
@@ -1507,10 +1764,26 @@ where
             shmem_provider,
             observers,
             handlers,
+            memory_limit: None,
             phantom: PhantomData,
         })
     }
 
+    /// The memory limit of the child, in bytes
+    #[inline]
+    #[must_use]
+    pub fn memory_limit(&self) -> Option<u64> {
+        self.memory_limit
+    }
+
+    /// Limits the address space of the child to `memory_limit` bytes (using `RLIMIT_AS`).
+    /// Runs failing to allocate memory past the limit are reported as [`ExitKind::Oom`], instead of [`ExitKind::Crash`].
+    /// Any other death of the child, such as a `SIGKILL`, is still a [`ExitKind::Crash`].
+    #[inline]
+    pub fn set_memory_limit(&mut self, memory_limit: Option<u64>) {
+        self.memory_limit = memory_limit;
+    }
+
     /// Retrieve the harness function.
     #[inline]
     pub fn harness(&self) -> &H {
@@ -1546,10 +1819,17 @@ where
 /// signal handlers and `panic_hooks` for the child process
 #[cfg(all(feature = "std", unix))]
 pub mod child_signal_handlers {
+    #[cfg(unstable_feature)]
+    use core::alloc::Layout;
+    use core::sync::atomic::Ordering;
     use libc::{siginfo_t, ucontext_t};
+    #[cfg(not(unstable_feature))]
+    use nix::errno::errno;
     use std::panic;
 
-    use super::InProcessForkExecutorGlobalData;
+    #[cfg(unstable_feature)]
+    use super::FORK_CHILD_OOM;
+    use super::{InProcessForkExecutorGlobalData, FORK_CHILD_OOM_FD};
 
     use super::FORK_EXECUTOR_GLOBAL_DATA;
     use crate::{
@@ -1585,6 +1865,26 @@ pub mod child_signal_handlers {
         }));
     }
 
+    /// Marks the abort following a failed allocation as running out of memory, for the crash handler
+    #[cfg(unstable_feature)]
+    pub fn child_alloc_error_hook(layout: Layout) {
+        eprintln!("memory allocation of {} bytes failed", layout.size());
+        FORK_CHILD_OOM.store(true, Ordering::Relaxed);
+    }
+
+    /// If the child aborted on a failed allocation
+    #[cfg(unstable_feature)]
+    fn child_out_of_memory(signal: Signal) -> bool {
+        matches!(signal, Signal::SigAbort) && FORK_CHILD_OOM.load(Ordering::Relaxed)
+    }
+
+    /// If the child aborted on a failed allocation.
+    /// Without an alloc error hook on stable, this relies on the failed allocation leaving `ENOMEM` behind.
+    #[cfg(not(unstable_feature))]
+    fn child_out_of_memory(signal: Signal) -> bool {
+        matches!(signal, Signal::SigAbort) && errno() == libc::ENOMEM
+    }
+
     /// invokes the `post_exec` hook on all observer in case the child process crashes
     ///
     /// # Safety
@@ -1592,7 +1892,7 @@ pub mod child_signal_handlers {
     /// It will dereference the `data` pointer and assume it's valid.
     #[cfg(unix)]
     pub unsafe fn child_crash_handler<E, I, OT, S>(
-        _signal: Signal,
+        signal: Signal,
         _info: siginfo_t,
         _context: &mut ucontext_t,
         data: &mut InProcessForkExecutorGlobalData,
@@ -1601,16 +1901,29 @@ pub mod child_signal_handlers {
         OT: ObserversTuple<I, S>,
         I: Input,
     {
+        // Only a child with a memory limit reports running out of memory, on the pipe to the parent.
+        let oom_fd = FORK_CHILD_OOM_FD.load(Ordering::Relaxed);
+        let exit_kind = if oom_fd >= 0 && child_out_of_memory(signal) {
+            ExitKind::Oom
+        } else {
+            ExitKind::Crash
+        };
+
         if data.is_valid() {
             let executor = data.executor_mut::<E>();
             let observers = executor.observers_mut();
             let state = data.state_mut::<S>();
             let input = data.take_current_input::<I>();
             observers
-                .post_exec_child_all(state, input, &ExitKind::Crash)
+                .post_exec_child_all(state, input, &exit_kind)
                 .expect("Failed to run post_exec on observers");
         }
 
+        if exit_kind == ExitKind::Oom {
+            // Tell the parent to report an [`ExitKind::Oom`] once we are gone
+            libc::write(oom_fd, [1_u8].as_ptr().cast(), 1);
+        }
+
         //libc::_exit(128 + (_signal as i32));
     }
 }
@@ -1646,6 +1959,372 @@ mod tests {
             .is_ok());
     }
 
+    #[test]
+    fn test_inmem_exec_batch() {
+        use crate::inputs::{BytesInput, HasBytesVec};
+        use alloc::vec::Vec;
+
+        let mut runs = 0;
+        let mut harness = |input: &BytesInput| {
+            runs += 1;
+            match input.bytes().first() {
+                Some(b'c') => ExitKind::Crash,
+                Some(b't') => ExitKind::Timeout,
+                _ => ExitKind::Ok,
+            }
+        };
+        let mut in_process_executor = InProcessExecutor::<_, BytesInput, (), ()> {
+            harness_fn: &mut harness,
+            observers: tuple_list!(),
+            handlers: InProcessHandlers::nop(),
+            phantom: PhantomData,
+        };
+
+        let inputs: Vec<BytesInput> = [&b"ok"[..], b"crash", b"", b"timeout", b"ok"]
+            .iter()
+            .map(|bytes| BytesInput::new(bytes.to_vec()))
+            .collect();
+        let sequential: Vec<ExitKind> = inputs
+            .iter()
+            .map(|input| {
+                in_process_executor
+                    .run_target(&mut (), &mut (), &mut (), input)
+                    .unwrap()
+            })
+            .collect();
+        let batch = in_process_executor
+            .run_targets(&mut (), &mut (), &mut (), &inputs)
+            .unwrap();
+        assert_eq!(batch, sequential);
+        assert!(in_process_executor
+            .run_targets(&mut (), &mut (), &mut (), &[])
+            .unwrap()
+            .is_empty());
+        drop(in_process_executor);
+        assert_eq!(runs, 10);
+    }
+
+    #[test]
+    fn test_inmem_exec_batch_observed() {
+        use crate::{
+            inputs::{BytesInput, HasBytesVec},
+            observers::{MapObserver, StdMapObserver},
+        };
+        use alloc::vec::Vec;
+
+        // Each input covers the map entry at its length
+        let mut map = [0_u8; 8];
+        let map_ptr = map.as_mut_ptr();
+        let mut harness = |input: &BytesInput| {
+            unsafe {
+                *map_ptr.add(input.bytes().len()) = 1;
+            }
+            ExitKind::Ok
+        };
+        let mut in_process_executor = InProcessExecutor::<_, BytesInput, _, ()> {
+            harness_fn: &mut harness,
+            observers: tuple_list!(unsafe { StdMapObserver::new_from_ptr("map", map_ptr, 8) }),
+            handlers: InProcessHandlers::nop(),
+            phantom: PhantomData,
+        };
+
+        let inputs: Vec<BytesInput> = (1..4).map(|len| BytesInput::new(vec![0; len])).collect();
+        let mut covered = vec![];
+        let exit_kinds = in_process_executor
+            .run_targets_observed(
+                &mut (),
+                &mut (),
+                &mut (),
+                &inputs,
+                |_, input, observers, _| {
+                    // The observers got reset in between, only holding the coverage of this input
+                    assert_eq!(observers.0.count_bytes(), 1);
+                    assert_eq!(*observers.0.get(input.bytes().len()), 1);
+                    covered.push(input.bytes().len());
+                    Ok(())
+                },
+            )
+            .unwrap();
+        assert_eq!(exit_kinds, vec![ExitKind::Ok; 3]);
+        assert_eq!(covered, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_inmem_exec_multi_input() {
+        use crate::inputs::{BytesInput, MultiInput};
+        use alloc::vec::Vec;
+
+        /// A stateful target, crashing on a login after the handshake
+        fn target(messages: &[&[u8]]) -> ExitKind {
+            let mut greeted = false;
+            for message in messages {
+                match *message {
+                    b"HELLO" => greeted = true,
+                    b"LOGIN" if greeted => return ExitKind::Crash,
+                    _ => greeted = false,
+                }
+            }
+            ExitKind::Ok
+        }
+
+        let mut harness = |input: &MultiInput| target(&input.message_slices());
+        let mut in_process_executor = InProcessExecutor::<_, MultiInput, (), ()> {
+            harness_fn: &mut harness,
+            observers: tuple_list!(),
+            handlers: InProcessHandlers::nop(),
+            phantom: PhantomData,
+        };
+
+        let hello = BytesInput::new(b"HELLO".to_vec());
+        let login = BytesInput::new(b"LOGIN".to_vec());
+        let mut run = |messages: Vec<BytesInput>| {
+            in_process_executor
+                .run_target(&mut (), &mut (), &mut (), &MultiInput::new(messages))
+                .unwrap()
+        };
+        assert_eq!(run(vec![login.clone(), hello.clone()]), ExitKind::Ok);
+        assert_eq!(run(vec![hello.clone()]), ExitKind::Ok);
+        assert_eq!(run(vec![hello, login]), ExitKind::Crash);
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", unix))]
+    fn test_inmem_exec_mmap_input() {
+        use crate::{
+            bolts::{fs::MmapFile, ownedref::OwnedSlice, AsSlice},
+            inputs::{HasTargetBytes, Input},
+        };
+        use serde::{Deserialize, Serialize};
+        use std::{
+            fs::{self, File},
+            io::{Seek, SeekFrom, Write},
+            path::PathBuf,
+        };
+
+        /// An input passed to the target straight from its file
+        #[derive(Clone, Debug, Hash, Serialize, Deserialize)]
+        struct FileInput {
+            path: PathBuf,
+        }
+
+        impl Input for FileInput {
+            fn generate_name(&self, _idx: usize) -> String {
+                self.path.to_string_lossy().into()
+            }
+        }
+
+        impl HasTargetBytes for FileInput {
+            fn target_bytes(&self) -> OwnedSlice<u8> {
+                OwnedSlice::from(MmapFile::open(&self.path).unwrap())
+            }
+        }
+
+        // A sparse 256 MiB file, ending with a marker byte
+        let len = 256 << 20;
+        let path = PathBuf::from("target/.test/mmap_input");
+        fs::create_dir_all("target/.test").unwrap();
+        let mut file = File::create(&path).unwrap();
+        file.set_len(len as u64).unwrap();
+        file.seek(SeekFrom::End(-1)).unwrap();
+        file.write_all(b"B").unwrap();
+        drop(file);
+
+        let mut harness = |input: &FileInput| {
+            let bytes = input.target_bytes();
+            let buf = bytes.as_slice();
+            if buf.len() == len && buf[0] == 0 && buf[len - 1] == b'B' {
+                ExitKind::Crash
+            } else {
+                ExitKind::Ok
+            }
+        };
+        let mut in_process_executor = InProcessExecutor::<_, FileInput, (), ()> {
+            harness_fn: &mut harness,
+            observers: tuple_list!(),
+            handlers: InProcessHandlers::nop(),
+            phantom: PhantomData,
+        };
+        let input = FileInput { path: path.clone() };
+        assert_eq!(
+            in_process_executor
+                .run_target(&mut (), &mut (), &mut (), &input)
+                .unwrap(),
+            ExitKind::Crash
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", feature = "fork", target_os = "linux"))]
+    fn test_inmem_exec_thread_timeout() {
+        use nix::{
+            sys::wait::{waitpid, WaitStatus},
+            unistd::{fork, ForkResult},
+        };
+        use std::{ptr, thread};
+
+        use crate::{
+            bolts::rands::StdRand,
+            corpus::{InMemoryCorpus, QueueCorpusScheduler},
+            events::NopEventManager,
+            executors::inprocess::block_timeout_signals,
+            fuzzer::StdFuzzer,
+            inputs::BytesInput,
+            state::StdState,
+        };
+
+        /// Returns `true` if the timeouts are blocked on the calling thread
+        fn timeouts_blocked() -> bool {
+            unsafe {
+                let mut set: libc::sigset_t = core::mem::zeroed();
+                libc::pthread_sigmask(libc::SIG_BLOCK, ptr::null(), &mut set);
+                libc::sigismember(&set, libc::SIGUSR2) == 1
+            }
+        }
+
+        match unsafe { fork() }.unwrap() {
+            ForkResult::Child => {
+                let mut state = StdState::new(
+                    StdRand::with_seed(0),
+                    InMemoryCorpus::<BytesInput>::new(),
+                    InMemoryCorpus::new(),
+                    (),
+                );
+                let mut mgr = NopEventManager {};
+                let mut fuzzer = StdFuzzer::new(QueueCorpusScheduler::new(), (), ());
+
+                // A timeout reaches a worker thread, which reports if it blocks the timeouts afterwards
+                let mut harness = |_input: &BytesInput| {
+                    let blocked = thread::spawn(|| unsafe {
+                        libc::raise(libc::SIGUSR2);
+                        timeouts_blocked()
+                    })
+                    .join()
+                    .unwrap();
+                    if blocked {
+                        ExitKind::Timeout
+                    } else {
+                        ExitKind::Ok
+                    }
+                };
+                let mut executor = InProcessExecutor::new(
+                    &mut harness,
+                    tuple_list!(),
+                    &mut fuzzer,
+                    &mut state,
+                    &mut mgr,
+                )
+                .unwrap();
+                // Without a timeout handler, the forwarded timeout does not end the process
+                executor.handlers_mut().timeout_handler = ptr::null();
+                let input = BytesInput::new(vec![]);
+
+                let unhandled = executor
+                    .run_target(&mut fuzzer, &mut state, &mut mgr, &input)
+                    .unwrap();
+                executor.set_handle_threads(true);
+                let handled = executor
+                    .run_target(&mut fuzzer, &mut state, &mut mgr, &input)
+                    .unwrap();
+                let self_blocked = thread::spawn(|| {
+                    block_timeout_signals().unwrap();
+                    timeouts_blocked()
+                })
+                .join()
+                .unwrap();
+
+                // Only harness threads block the timeouts, never the fuzzing thread
+                let ok = unhandled == ExitKind::Ok
+                    && handled == ExitKind::Timeout
+                    && self_blocked
+                    && !timeouts_blocked();
+                unsafe { libc::_exit(i32::from(!ok)) };
+            }
+            ForkResult::Parent { child } => {
+                assert_eq!(waitpid(child, None).unwrap(), WaitStatus::Exited(child, 0));
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", feature = "fork", target_os = "linux"))]
+    fn test_inmem_exec_thread_crash() {
+        use nix::{
+            sys::wait::{waitpid, WaitStatus},
+            unistd::{fork, ForkResult},
+        };
+        use std::{fs, path::PathBuf, thread};
+
+        use crate::{
+            bolts::rands::StdRand,
+            corpus::{InMemoryCorpus, OnDiskCorpus, QueueCorpusScheduler},
+            events::NopEventManager,
+            feedbacks::CrashFeedback,
+            fuzzer::StdFuzzer,
+            inputs::BytesInput,
+            state::StdState,
+        };
+
+        let dir = PathBuf::from("target/.test/thread_crash");
+        let _ = fs::remove_dir_all(&dir);
+        match unsafe { fork() }.unwrap() {
+            ForkResult::Child => {
+                let mut state = StdState::new(
+                    StdRand::with_seed(0),
+                    InMemoryCorpus::<BytesInput>::new(),
+                    OnDiskCorpus::new(&dir).unwrap(),
+                    (),
+                );
+                let mut mgr = NopEventManager {};
+                let mut fuzzer =
+                    StdFuzzer::new(QueueCorpusScheduler::new(), (), CrashFeedback::new());
+
+                // The harness crashes on a worker thread
+                let mut harness = |_input: &BytesInput| {
+                    thread::spawn(|| unsafe {
+                        libc::raise(libc::SIGSEGV);
+                    })
+                    .join()
+                    .unwrap();
+                    ExitKind::Ok
+                };
+                let mut executor = InProcessExecutor::new(
+                    &mut harness,
+                    tuple_list!(),
+                    &mut fuzzer,
+                    &mut state,
+                    &mut mgr,
+                )
+                .unwrap();
+                executor.set_handle_threads(true);
+                assert!(executor.handle_threads());
+                let _res = executor.run_target(
+                    &mut fuzzer,
+                    &mut state,
+                    &mut mgr,
+                    &BytesInput::new(b"crash".to_vec()),
+                );
+                // Not reached, the crash handler exits the process
+                unsafe { libc::_exit(0) };
+            }
+            ForkResult::Parent { child } => {
+                // The crash handler exits with 128 + the signal, after storing the solution
+                assert_eq!(
+                    waitpid(child, None).unwrap(),
+                    WaitStatus::Exited(child, 128 + libc::SIGSEGV)
+                );
+                let solutions = fs::read_dir(&dir)
+                    .unwrap()
+                    .filter_map(Result::ok)
+                    .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+                    .count();
+                assert_eq!(solutions, 1);
+                fs::remove_dir_all(&dir).unwrap();
+            }
+        }
+    }
+
     #[test]
     #[cfg(all(feature = "std", feature = "fork", unix))]
     fn test_inprocessfork_exec() {
@@ -1659,6 +2338,7 @@ mod tests {
             shmem_provider: provider,
             observers: tuple_list!(),
             handlers: InChildProcessHandlers::nop(),
+            memory_limit: None,
             phantom: PhantomData,
         };
         let input = NopInput {};
@@ -1666,6 +2346,90 @@ mod tests {
             .run_target(&mut (), &mut (), &mut (), &input)
             .is_ok());
     }
+
+    #[test]
+    #[cfg(all(feature = "std", feature = "fork", target_os = "linux"))]
+    fn test_inprocessfork_oom() {
+        use std::fs;
+
+        use crate::{
+            bolts::rands::StdRand,
+            corpus::{Corpus, InMemoryCorpus, QueueCorpusScheduler},
+            events::NopEventManager,
+            feedbacks::OomFeedback,
+            fuzzer::{Evaluator, StdFuzzer},
+            inputs::{BytesInput, HasBytesVec},
+            state::{HasSolutions, StdState},
+        };
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+        let mut fuzzer = StdFuzzer::new(QueueCorpusScheduler::new(), (), OomFeedback::new());
+
+        // Allocates (and touches) one MiB per input byte, and gets killed on empty inputs
+        let mut harness = |input: &BytesInput| {
+            if input.bytes().is_empty() {
+                unsafe {
+                    libc::kill(libc::getpid(), libc::SIGKILL);
+                }
+            }
+            let size = input.bytes().len() << 20;
+            let buf = vec![0xaa_u8; size];
+            assert_eq!(unsafe { core::ptr::read_volatile(&buf[size - 1]) }, 0xaa);
+            ExitKind::Ok
+        };
+        let mut executor = InProcessForkExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+            StdShMemProvider::new().unwrap(),
+        )
+        .unwrap();
+
+        // Leave 512 MiB on top of the address space we already use
+        let statm = fs::read_to_string("/proc/self/statm").unwrap();
+        let pages: u64 = statm.split_whitespace().next().unwrap().parse().unwrap();
+        let page_size = u64::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).unwrap();
+        executor.set_memory_limit(Some(pages * page_size + (512 << 20)));
+
+        let small = BytesInput::new(vec![0; 1]);
+        let large = BytesInput::new(vec![0; 2048]);
+        assert_eq!(
+            executor
+                .run_target(&mut fuzzer, &mut state, &mut mgr, &small)
+                .unwrap(),
+            ExitKind::Ok
+        );
+        assert_eq!(
+            executor
+                .run_target(&mut fuzzer, &mut state, &mut mgr, &large)
+                .unwrap(),
+            ExitKind::Oom
+        );
+        // Being killed is not running out of memory
+        assert_eq!(
+            executor
+                .run_target(&mut fuzzer, &mut state, &mut mgr, &BytesInput::new(vec![]))
+                .unwrap(),
+            ExitKind::Crash
+        );
+
+        // The OomFeedback stores it as a solution
+        fuzzer
+            .evaluate_input(&mut state, &mut executor, &mut mgr, large)
+            .unwrap();
+        fuzzer
+            .evaluate_input(&mut state, &mut executor, &mut mgr, small)
+            .unwrap();
+        assert_eq!(state.solutions().count(), 1);
+    }
 }
 
 #[cfg(feature = "python")]
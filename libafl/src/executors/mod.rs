@@ -13,7 +13,7 @@ pub use differential::DiffExecutor;
 #[cfg(any(unix, feature = "std"))]
 pub mod timeout;
 #[cfg(any(unix, feature = "std"))]
-pub use timeout::TimeoutExecutor;
+pub use timeout::{SlowInputsMetadata, TimeoutExecutor, SLOW_INPUTS_MAX_COUNT};
 
 #[cfg(all(feature = "std", feature = "fork", unix))]
 pub mod forkserver;
@@ -26,14 +26,31 @@ pub use combined::CombinedExecutor;
 pub mod shadow;
 pub use shadow::ShadowExecutor;
 
+#[cfg(feature = "std")]
+pub mod record;
+#[cfg(feature = "std")]
+pub use record::{RecordingExecutor, ReplayExecutor};
+
 pub mod with_observers;
 pub use with_observers::WithObservers;
 
+#[cfg(feature = "std")]
+pub mod watchdog;
+#[cfg(feature = "std")]
+pub use watchdog::WatchdogExecutor;
+
+#[cfg(feature = "std")]
+pub mod oneshot;
+#[cfg(feature = "std")]
+pub use oneshot::{run_once, run_stdin_once};
+
 #[cfg(all(feature = "std", unix))]
 pub mod command;
 #[cfg(all(feature = "std", unix))]
 pub use command::CommandExecutor;
 
+use alloc::vec::Vec;
+
 use crate::{
     bolts::AsSlice,
     inputs::{HasTargetBytes, Input},
@@ -125,6 +142,23 @@ where
         input: &I,
     ) -> Result<ExitKind, Error>;
 
+    /// Runs the target on each of the `inputs`, in order, returning the [`ExitKind`] of each run.
+    /// Executors may override this to amortize their per-run overhead over the batch.
+    /// This is meant for observer-less use, such as replaying inputs for their [`ExitKind`]s:
+    /// no per-input observations are returned. For those, see [`inprocess::GenericInProcessExecutor::run_targets_observed`].
+    fn run_targets(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        inputs: &[I],
+    ) -> Result<Vec<ExitKind>, Error> {
+        inputs
+            .iter()
+            .map(|input| self.run_target(fuzzer, state, mgr, input))
+            .collect()
+    }
+
     /// Wraps this Executor with the given [`ObserversTuple`] to implement [`HasObservers`].
     ///
     /// If the executor already implements [`HasObservers`], then the original implementation will be overshadowed by
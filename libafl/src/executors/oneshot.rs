@@ -0,0 +1,148 @@
+//! Runs a single input through an executor, like `afl-showmap`, so harnesses can be driven by external tools.
+//! [`run_stdin_once`] reads the input from `stdin`, prints the [`ExitKind`], and exits with the matching [`exit_code`].
+//!
+//! In-process, a real crash terminates the process from the crash handler.
+//! Use an `InProcessForkExecutor` to get [`ExitKind::Crash`] (and the exit code `1`) for crashing signals as well.
+//! Sanitizer reports are written to `stderr` by the target itself.
+
+use std::io::{self, Read, Write};
+
+use crate::{
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::BytesInput,
+    observers::ObserversTuple,
+    Error,
+};
+
+/// The process exit code for an [`ExitKind`]: `0` for a normal run, `1` for a crash, `2` for a timeout.
+/// OOMs and diffs count as crashes.
+#[must_use]
+pub fn exit_code(exit_kind: &ExitKind) -> i32 {
+    match exit_kind {
+        ExitKind::Ok => 0,
+        ExitKind::Crash | ExitKind::Oom | ExitKind::Diff { .. } => 1,
+        ExitKind::Timeout => 2,
+    }
+}
+
+/// Runs the `input` once, including the observers, and returns how the run finished
+pub fn run_once<E, EM, I, OT, S, Z>(
+    fuzzer: &mut Z,
+    executor: &mut E,
+    state: &mut S,
+    mgr: &mut EM,
+    input: &I,
+) -> Result<ExitKind, Error>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    OT: ObserversTuple<I, S>,
+{
+    executor.observers_mut().pre_exec_all(state, input)?;
+    let exit_kind = executor.run_target(fuzzer, state, mgr, input)?;
+    executor
+        .observers_mut()
+        .post_exec_all(state, input, &exit_kind)?;
+    Ok(exit_kind)
+}
+
+/// Reads all bytes from `reader` into a [`BytesInput`] and runs it once
+pub fn run_once_from_reader<E, EM, OT, R, S, Z>(
+    reader: &mut R,
+    fuzzer: &mut Z,
+    executor: &mut E,
+    state: &mut S,
+    mgr: &mut EM,
+) -> Result<ExitKind, Error>
+where
+    E: Executor<EM, BytesInput, S, Z> + HasObservers<BytesInput, OT, S>,
+    OT: ObserversTuple<BytesInput, S>,
+    R: Read,
+{
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    run_once(fuzzer, executor, state, mgr, &BytesInput::new(bytes))
+}
+
+/// Reads the input from `stdin`, runs it once, prints the [`ExitKind`] to `stdout`,
+/// and exits the process with its [`exit_code`].
+pub fn run_stdin_once<E, EM, OT, S, Z>(
+    fuzzer: &mut Z,
+    executor: &mut E,
+    state: &mut S,
+    mgr: &mut EM,
+) -> Result<(), Error>
+where
+    E: Executor<EM, BytesInput, S, Z> + HasObservers<BytesInput, OT, S>,
+    OT: ObserversTuple<BytesInput, S>,
+{
+    let exit_kind = run_once_from_reader(&mut io::stdin().lock(), fuzzer, executor, state, mgr)?;
+    println!("{:?}", exit_kind);
+    io::stdout().flush()?;
+    io::stderr().flush()?;
+    std::process::exit(exit_code(&exit_kind));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{
+        executors::{
+            oneshot::{exit_code, run_once_from_reader},
+            Executor, ExitKind, HasObservers,
+        },
+        inputs::{BytesInput, HasBytesVec},
+        Error,
+    };
+
+    /// Crashes on inputs starting with `crash`
+    #[derive(Debug)]
+    struct CrashingExecutor {
+        observers: (),
+    }
+
+    impl Executor<(), BytesInput, (), ()> for CrashingExecutor {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut (),
+            _state: &mut (),
+            _mgr: &mut (),
+            input: &BytesInput,
+        ) -> Result<ExitKind, Error> {
+            if input.bytes().starts_with(b"crash") {
+                Ok(ExitKind::Crash)
+            } else {
+                Ok(ExitKind::Ok)
+            }
+        }
+    }
+
+    impl HasObservers<BytesInput, (), ()> for CrashingExecutor {
+        fn observers(&self) -> &() {
+            &self.observers
+        }
+
+        fn observers_mut(&mut self) -> &mut () {
+            &mut self.observers
+        }
+    }
+
+    #[test]
+    fn test_run_once_from_reader() {
+        let mut executor = CrashingExecutor { observers: () };
+        let mut run = |bytes: &[u8]| {
+            let exit_kind = run_once_from_reader(
+                &mut Cursor::new(bytes),
+                &mut (),
+                &mut executor,
+                &mut (),
+                &mut (),
+            )
+            .unwrap();
+            exit_code(&exit_kind)
+        };
+        assert_eq!(run(b"crash me"), 1);
+        assert_eq!(run(b"hello"), 0);
+        assert_eq!(exit_code(&ExitKind::Timeout), 2);
+    }
+}
@@ -0,0 +1,353 @@
+//! Executors to record the inputs of a session to a log file, and to replay it later.
+//! Replaying a session and getting a different [`ExitKind`] for the same input shows nondeterminism in the target.
+
+use alloc::vec::Vec;
+use core::{fmt::Debug, marker::PhantomData};
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
+
+use crate::{
+    bolts::tuples::Named,
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::Input,
+    observers::ObserversTuple,
+    Error,
+};
+
+/// A [`RecordingExecutor`] wraps an executor and appends every input it runs, together with the resulting [`ExitKind`],
+/// to a log file. The log can be fed back to the target using a [`ReplayExecutor`].
+#[derive(Debug)]
+pub struct RecordingExecutor<E, I>
+where
+    E: Debug,
+    I: Input,
+{
+    executor: E,
+    log: File,
+    recorded: usize,
+    phantom: PhantomData<I>,
+}
+
+impl<E, I> RecordingExecutor<E, I>
+where
+    E: Debug,
+    I: Input,
+{
+    /// Creates a new [`RecordingExecutor`], wrapping the given `executor` and (over)writing the log at `log_path`.
+    pub fn new<P>(executor: E, log_path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self {
+            executor,
+            log: File::create(log_path)?,
+            recorded: 0,
+            phantom: PhantomData,
+        })
+    }
+
+    /// The amount of inputs recorded so far
+    #[must_use]
+    pub fn recorded(&self) -> usize {
+        self.recorded
+    }
+
+    /// Retrieve the wrapped `Executor`
+    pub fn inner(&mut self) -> &mut E {
+        &mut self.executor
+    }
+}
+
+impl<E, EM, I, S, Z> Executor<EM, I, S, Z> for RecordingExecutor<E, I>
+where
+    E: Executor<EM, I, S, Z>,
+    I: Input,
+{
+    #[allow(clippy::cast_possible_truncation)]
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        let exit_kind = self.executor.run_target(fuzzer, state, mgr, input)?;
+
+        // Each record is the length of the serialized entry, followed by the entry.
+        // Written at once, so that a crash of the fuzzer does not leave a partial record behind.
+        let entry = postcard::to_allocvec(&(input, exit_kind))?;
+        let mut record = Vec::with_capacity(4 + entry.len());
+        record.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+        record.extend_from_slice(&entry);
+        self.log.write_all(&record)?;
+        self.recorded += 1;
+
+        Ok(exit_kind)
+    }
+
+    #[inline]
+    fn post_run_reset(&mut self) {
+        self.executor.post_run_reset();
+    }
+}
+
+impl<E, I, OT, S> HasObservers<I, OT, S> for RecordingExecutor<E, I>
+where
+    E: HasObservers<I, OT, S>,
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    #[inline]
+    fn observers(&self) -> &OT {
+        self.executor.observers()
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> &mut OT {
+        self.executor.observers_mut()
+    }
+}
+
+impl<E, I> Named for RecordingExecutor<E, I>
+where
+    E: Debug + Named,
+    I: Input,
+{
+    #[inline]
+    fn name(&self) -> &str {
+        self.executor.name()
+    }
+}
+
+/// A [`ReplayExecutor`] reads a log written by a [`RecordingExecutor`] and checks, for each run,
+/// that the wrapped executor gives the same [`ExitKind`] as the one recorded at the same position.
+/// If they differ, the run fails with an [`Error::IllegalState`], as the target did not behave deterministically.
+#[derive(Debug)]
+pub struct ReplayExecutor<E, I>
+where
+    E: Debug,
+    I: Input,
+{
+    executor: E,
+    entries: Vec<(I, ExitKind)>,
+    position: usize,
+}
+
+impl<E, I> ReplayExecutor<E, I>
+where
+    E: Debug,
+    I: Input,
+{
+    /// Creates a new [`ReplayExecutor`], wrapping the given `executor` and reading the log at `log_path`.
+    pub fn new<P>(executor: E, log_path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let log = fs::read(log_path)?;
+        let mut entries = vec![];
+        let mut rest = &log[..];
+        while !rest.is_empty() {
+            if rest.len() < 4 {
+                return Err(Error::IllegalState("Truncated replay log".into()));
+            }
+            let len = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+            if rest.len() < 4 + len {
+                return Err(Error::IllegalState("Truncated replay log".into()));
+            }
+            entries.push(postcard::from_bytes(&rest[4..4 + len])?);
+            rest = &rest[4 + len..];
+        }
+        Ok(Self {
+            executor,
+            entries,
+            position: 0,
+        })
+    }
+
+    /// The recorded inputs, with their recorded [`ExitKind`]
+    #[must_use]
+    pub fn entries(&self) -> &[(I, ExitKind)] {
+        &self.entries
+    }
+
+    /// The position of the next run in the log
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Retrieve the wrapped `Executor`
+    pub fn inner(&mut self) -> &mut E {
+        &mut self.executor
+    }
+
+    /// Runs all the remaining recorded inputs in order, stopping at the first divergence.
+    /// Returns the amount of replayed inputs.
+    pub fn replay<EM, S, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+    ) -> Result<usize, Error>
+    where
+        E: Executor<EM, I, S, Z>,
+    {
+        let start = self.position;
+        while self.position < self.entries.len() {
+            let input = self.entries[self.position].0.clone();
+            self.run_target(fuzzer, state, mgr, &input)?;
+            self.executor.post_run_reset();
+        }
+        Ok(self.position - start)
+    }
+}
+
+impl<E, EM, I, S, Z> Executor<EM, I, S, Z> for ReplayExecutor<E, I>
+where
+    E: Executor<EM, I, S, Z>,
+    I: Input,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        let expected = match self.entries.get(self.position) {
+            Some((_, exit_kind)) => *exit_kind,
+            None => return Err(Error::Empty("The replay log is exhausted".into())),
+        };
+        let exit_kind = self.executor.run_target(fuzzer, state, mgr, input)?;
+        if exit_kind != expected {
+            return Err(Error::IllegalState(format!(
+                "Replay diverged at input {}: recorded {:?}, got {:?}",
+                self.position, expected, exit_kind
+            )));
+        }
+        self.position += 1;
+        Ok(exit_kind)
+    }
+
+    #[inline]
+    fn post_run_reset(&mut self) {
+        self.executor.post_run_reset();
+    }
+}
+
+impl<E, I, OT, S> HasObservers<I, OT, S> for ReplayExecutor<E, I>
+where
+    E: HasObservers<I, OT, S>,
+    I: Input,
+    OT: ObserversTuple<I, S>,
+{
+    #[inline]
+    fn observers(&self) -> &OT {
+        self.executor.observers()
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> &mut OT {
+        self.executor.observers_mut()
+    }
+}
+
+impl<E, I> Named for ReplayExecutor<E, I>
+where
+    E: Debug + Named,
+    I: Input,
+{
+    #[inline]
+    fn name(&self) -> &str {
+        self.executor.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        executors::{Executor, ExitKind, RecordingExecutor, ReplayExecutor},
+        inputs::BytesInput,
+        Error,
+    };
+
+    /// An executor crashing on its `crash_on`-th run
+    #[derive(Debug)]
+    struct FlakyExecutor {
+        crash_on: Option<usize>,
+        runs: usize,
+    }
+
+    impl Executor<(), BytesInput, (), ()> for FlakyExecutor {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut (),
+            _state: &mut (),
+            _mgr: &mut (),
+            _input: &BytesInput,
+        ) -> Result<ExitKind, Error> {
+            self.runs += 1;
+            if self.crash_on == Some(self.runs) {
+                Ok(ExitKind::Crash)
+            } else {
+                Ok(ExitKind::Ok)
+            }
+        }
+    }
+
+    #[test]
+    fn test_record_replay() {
+        let log_path = "target/.test/replay.log";
+        fs::create_dir_all("target/.test").unwrap();
+
+        let mut recorder = RecordingExecutor::new(
+            FlakyExecutor {
+                crash_on: Some(3),
+                runs: 0,
+            },
+            log_path,
+        )
+        .unwrap();
+        for i in 0..4_u8 {
+            recorder
+                .run_target(&mut (), &mut (), &mut (), &BytesInput::new(vec![i; 4]))
+                .unwrap();
+        }
+        assert_eq!(recorder.recorded(), 4);
+        drop(recorder);
+
+        // The same behavior replays cleanly
+        let mut replayer = ReplayExecutor::new(
+            FlakyExecutor {
+                crash_on: Some(3),
+                runs: 0,
+            },
+            log_path,
+        )
+        .unwrap();
+        assert_eq!(replayer.entries()[2].0, BytesInput::new(vec![2; 4]));
+        assert_eq!(replayer.replay(&mut (), &mut (), &mut ()).unwrap(), 4);
+
+        // A crash moved to another run is detected
+        let mut replayer = ReplayExecutor::new(
+            FlakyExecutor {
+                crash_on: Some(2),
+                runs: 0,
+            },
+            log_path,
+        )
+        .unwrap();
+        assert!(matches!(
+            replayer.replay(&mut (), &mut (), &mut ()),
+            Err(Error::IllegalState(_))
+        ));
+        assert_eq!(replayer.position(), 1);
+
+        fs::remove_file(log_path).unwrap();
+    }
+}
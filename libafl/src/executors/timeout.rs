@@ -1,15 +1,18 @@
 //! A `TimeoutExecutor` sets a timeout before each target run
 
 #[cfg(any(windows, unix))]
-use core::{
-    fmt::{self, Debug, Formatter},
-    time::Duration,
-};
+use core::fmt::{self, Debug, Formatter};
+use core::time::Duration;
+
+use alloc::{collections::VecDeque, string::String};
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    bolts::current_time,
     executors::{Executor, ExitKind, HasObservers},
     inputs::Input,
     observers::ObserversTuple,
+    state::HasMetadata,
     Error,
 };
 
@@ -78,9 +81,40 @@ extern "C" {
 #[cfg(all(unix, not(target_os = "linux")))]
 const ITIMER_REAL: c_int = 0;
 
-/// The timeout executor is a wrapper that sets a timeout before each run
+/// The maximum amount of slow inputs a [`struct@SlowInputsMetadata`] keeps, the oldest get dropped first
+pub const SLOW_INPUTS_MAX_COUNT: usize = 256;
+
+/// The inputs that exceeded the soft timeout of a [`TimeoutExecutor`], without reaching the hard timeout.
+/// The executor records them in the metadata of the state, so they survive restarts.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SlowInputsMetadata {
+    /// The names of the slow inputs, with their runtime, at most [`SLOW_INPUTS_MAX_COUNT`], oldest first
+    pub inputs: VecDeque<(String, Duration)>,
+}
+
+crate::impl_serdeany!(SlowInputsMetadata);
+
+impl SlowInputsMetadata {
+    /// Creates a new [`struct@SlowInputsMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a slow input, dropping the oldest one once [`SLOW_INPUTS_MAX_COUNT`] are recorded
+    pub fn record(&mut self, name: String, runtime: Duration) {
+        if self.inputs.len() >= SLOW_INPUTS_MAX_COUNT {
+            self.inputs.pop_front();
+        }
+        self.inputs.push_back((name, runtime));
+    }
+}
+
+/// The timeout executor is a wrapper that sets a timeout before each run.
+/// Optionally, a soft timeout records runs that are slow, but not hanging, in the [`struct@SlowInputsMetadata`] of the state.
 pub struct TimeoutExecutor<E> {
     executor: E,
+    soft_timeout: Option<Duration>,
     #[cfg(target_os = "linux")]
     itimerspec: libc::itimerspec,
     #[cfg(target_os = "linux")]
@@ -101,6 +135,7 @@ impl<E: Debug> Debug for TimeoutExecutor<E> {
         f.debug_struct("TimeoutExecutor")
             .field("executor", &self.executor)
             .field("milli_sec", &self.milli_sec)
+            .field("soft_timeout", &self.soft_timeout)
             .finish_non_exhaustive()
     }
 
@@ -113,6 +148,7 @@ impl<E: Debug> Debug for TimeoutExecutor<E> {
                 &(&self.itimerspec.it_value.tv_sec * 1000
                     + &self.itimerspec.it_value.tv_nsec / 1000 / 1000),
             )
+            .field("soft_timeout", &self.soft_timeout)
             .finish()
     }
 
@@ -121,10 +157,67 @@ impl<E: Debug> Debug for TimeoutExecutor<E> {
         f.debug_struct("TimeoutExecutor")
             .field("executor", &self.executor)
             .field("itimerval", &self.itimerval)
+            .field("soft_timeout", &self.soft_timeout)
             .finish()
     }
 }
 
+#[cfg(unix)]
+impl<E> TimeoutExecutor<E> {
+    /// Create a new [`TimeoutExecutor`] with two timeout levels.
+    /// Runs exceeding the `soft` timeout are recorded as slow in the [`struct@SlowInputsMetadata`] of the state,
+    /// runs exceeding the `hard` timeout are aborted and reported as [`ExitKind::Timeout`].
+    pub fn with_soft_and_hard_timeout(executor: E, soft: Duration, hard: Duration) -> Self {
+        Self::new(executor, hard).with_soft_timeout(soft)
+    }
+}
+
+#[cfg(any(windows, unix))]
+impl<E> TimeoutExecutor<E> {
+    /// Sets the `soft` timeout, see `with_soft_and_hard_timeout`
+    fn with_soft_timeout(mut self, soft: Duration) -> Self {
+        self.soft_timeout = Some(soft);
+        self
+    }
+
+    /// Set the soft timeout for this executor, or disable it using `None`
+    pub fn set_soft_timeout(&mut self, soft_timeout: Option<Duration>) {
+        self.soft_timeout = soft_timeout;
+    }
+
+    /// The soft timeout of this executor, if any
+    #[must_use]
+    pub fn soft_timeout(&self) -> Option<Duration> {
+        self.soft_timeout
+    }
+
+    /// Records the `input` as slow in the `state` if its run, started at `start`, exited normally after the soft timeout
+    fn record_slow<I, S>(
+        &self,
+        state: &mut S,
+        input: &I,
+        start: Duration,
+        ret: &Result<ExitKind, Error>,
+    ) where
+        I: Input,
+        S: HasMetadata,
+    {
+        if let (Some(soft_timeout), Ok(ExitKind::Ok)) = (self.soft_timeout, ret) {
+            let runtime = current_time().saturating_sub(start);
+            if runtime >= soft_timeout {
+                if !state.has_metadata::<SlowInputsMetadata>() {
+                    state.add_metadata(SlowInputsMetadata::new());
+                }
+                state
+                    .metadata_mut()
+                    .get_mut::<SlowInputsMetadata>()
+                    .unwrap()
+                    .record(input.generate_name(0), runtime);
+            }
+        }
+    }
+}
+
 #[cfg(windows)]
 #[allow(non_camel_case_types)]
 type PTP_TIMER_CALLBACK = unsafe extern "system" fn(
@@ -158,6 +251,7 @@ impl<E> TimeoutExecutor<E> {
         }
         Self {
             executor,
+            soft_timeout: None,
             itimerspec,
             timerid,
         }
@@ -202,6 +296,7 @@ impl<E> TimeoutExecutor<E> {
         };
         Self {
             executor,
+            soft_timeout: None,
             itimerval,
         }
     }
@@ -247,12 +342,20 @@ impl<E: HasInProcessHandlers> TimeoutExecutor<E> {
 
         Self {
             executor,
+            soft_timeout: None,
             milli_sec,
             tp_timer,
             critical,
         }
     }
 
+    /// Create a new [`TimeoutExecutor`] with two timeout levels.
+    /// Runs exceeding the `soft` timeout are recorded as slow in the [`struct@SlowInputsMetadata`] of the state,
+    /// runs exceeding the `hard` timeout are aborted and reported as [`ExitKind::Timeout`].
+    pub fn with_soft_and_hard_timeout(executor: E, soft: Duration, hard: Duration) -> Self {
+        Self::new(executor, hard).with_soft_timeout(soft)
+    }
+
     /// Set the timeout for this executor
     #[cfg(windows)]
     pub fn set_timeout(&mut self, exec_tmout: Duration) {
@@ -270,6 +373,7 @@ impl<E, EM, I, S, Z> Executor<EM, I, S, Z> for TimeoutExecutor<E>
 where
     E: Executor<EM, I, S, Z> + HasInProcessHandlers,
     I: Input,
+    S: HasMetadata,
 {
     #[allow(clippy::cast_sign_loss)]
     fn run_target(
@@ -306,6 +410,7 @@ where
 
             SetThreadpoolTimer(self.tp_timer, &ft, 0, 0);
 
+            let start = current_time();
             let ret = self.executor.run_target(fuzzer, state, mgr, input);
 
             compiler_fence(Ordering::SeqCst);
@@ -320,6 +425,7 @@ where
             write_volatile(&mut data.timeout_input_ptr, core::ptr::null_mut());
 
             self.post_run_reset();
+            self.record_slow(state, input, start, &ret);
             ret
         }
     }
@@ -340,6 +446,7 @@ impl<E, EM, I, S, Z> Executor<EM, I, S, Z> for TimeoutExecutor<E>
 where
     E: Executor<EM, I, S, Z>,
     I: Input,
+    S: HasMetadata,
 {
     fn run_target(
         &mut self,
@@ -350,9 +457,11 @@ where
     ) -> Result<ExitKind, Error> {
         unsafe {
             libc::timer_settime(self.timerid, 0, addr_of_mut!(self.itimerspec), null_mut());
+            let start = current_time();
             let ret = self.executor.run_target(fuzzer, state, mgr, input);
             // reset timer
             self.post_run_reset();
+            self.record_slow(state, input, start, &ret);
             ret
         }
     }
@@ -371,6 +480,7 @@ impl<E, EM, I, S, Z> Executor<EM, I, S, Z> for TimeoutExecutor<E>
 where
     E: Executor<EM, I, S, Z>,
     I: Input,
+    S: HasMetadata,
 {
     fn run_target(
         &mut self,
@@ -381,8 +491,10 @@ where
     ) -> Result<ExitKind, Error> {
         unsafe {
             setitimer(ITIMER_REAL, &mut self.itimerval, null_mut());
+            let start = current_time();
             let ret = self.executor.run_target(fuzzer, state, mgr, input);
             self.post_run_reset();
+            self.record_slow(state, input, start, &ret);
             ret
         }
     }
@@ -411,3 +523,77 @@ where
         self.executor.observers_mut()
     }
 }
+
+#[cfg(test)]
+#[cfg(all(feature = "std", unix))]
+mod tests {
+    use core::time::Duration;
+    use std::thread::sleep;
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, QueueCorpusScheduler},
+        events::NopEventManager,
+        executors::{
+            timeout::{SlowInputsMetadata, SLOW_INPUTS_MAX_COUNT},
+            ExitKind, InProcessExecutor, TimeoutExecutor,
+        },
+        feedbacks::TimeoutFeedback,
+        inputs::BytesInput,
+        state::{HasMetadata, HasSolutions, StdState},
+        Evaluator, StdFuzzer,
+    };
+
+    #[test]
+    fn test_soft_timeout_records_slow() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+        let mut fuzzer = StdFuzzer::new(QueueCorpusScheduler::new(), (), TimeoutFeedback::new());
+
+        let mut harness = |_input: &BytesInput| {
+            sleep(Duration::from_millis(200));
+            ExitKind::Ok
+        };
+        let mut executor = TimeoutExecutor::with_soft_and_hard_timeout(
+            InProcessExecutor::new(
+                &mut harness,
+                tuple_list!(),
+                &mut fuzzer,
+                &mut state,
+                &mut mgr,
+            )
+            .unwrap(),
+            Duration::from_millis(50),
+            Duration::from_secs(5),
+        );
+
+        fuzzer
+            .evaluate_input(
+                &mut state,
+                &mut executor,
+                &mut mgr,
+                BytesInput::new(b"slow".to_vec()),
+            )
+            .unwrap();
+
+        let slow = state.metadata().get::<SlowInputsMetadata>().unwrap();
+        assert_eq!(slow.inputs.len(), 1);
+        assert!(slow.inputs[0].1 >= Duration::from_millis(50));
+        assert_eq!(state.solutions().count(), 0);
+    }
+
+    #[test]
+    fn test_slow_inputs_capped() {
+        let mut slow = SlowInputsMetadata::new();
+        for i in 0..SLOW_INPUTS_MAX_COUNT + 10 {
+            slow.record(i.to_string(), Duration::from_millis(1));
+        }
+        assert_eq!(slow.inputs.len(), SLOW_INPUTS_MAX_COUNT);
+        assert_eq!(slow.inputs[0].0, "10");
+    }
+}
@@ -0,0 +1,328 @@
+//! A [`WatchdogExecutor`] detects a wedged executor: a background thread checks the time since
+//! the last finished execution, which is not bounded by the timeout of the target if the fuzzer itself got stuck.
+
+use core::{
+    fmt::Debug,
+    sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+use std::{
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    bolts::current_milliseconds,
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::Input,
+    libafl_log,
+    observers::ObserversTuple,
+    Error,
+};
+
+/// The state shared between a [`WatchdogExecutor`] and its watchdog thread
+#[derive(Debug, Default)]
+struct WatchdogShared {
+    /// The heartbeat, the time in milliseconds since the epoch the last execution finished
+    last_exec_ms: AtomicU64,
+    /// The thread running the executions, as `pthread_t`, to signal on a wedge
+    #[cfg(unix)]
+    exec_thread: AtomicUsize,
+    /// If the watchdog stops the client when it detects a wedge, so the `Launcher` restarts it
+    abort_on_wedge: AtomicBool,
+    /// The amount of wedges detected
+    wedges: AtomicUsize,
+    /// Tells the watchdog thread to stop
+    stop: AtomicBool,
+}
+
+impl WatchdogShared {
+    /// Checks for a wedge at `now_ms`, returning for how long no execution finished if this is a new wedge.
+    /// `reported` holds the heartbeat the last wedge was reported for, so each wedge gets reported once.
+    fn check_wedge(
+        &self,
+        now_ms: u64,
+        threshold_ms: u64,
+        reported: &mut Option<u64>,
+    ) -> Option<u64> {
+        let last_exec_ms = self.last_exec_ms.load(Ordering::Relaxed);
+        let stalled_ms = now_ms.saturating_sub(last_exec_ms);
+        if stalled_ms > threshold_ms && *reported != Some(last_exec_ms) {
+            *reported = Some(last_exec_ms);
+            self.wedges.fetch_add(1, Ordering::Relaxed);
+            Some(stalled_ms)
+        } else {
+            None
+        }
+    }
+
+    /// Stops the wedged client. On unix, the execution thread gets a `SIGALRM` first, so the timeout handler
+    /// of an in-process executor reports the input as a [`ExitKind::Timeout`] and restarts the client.
+    /// `signaled_ms` holds the time the signal got sent at: if that did not unwedge the client within the threshold,
+    /// for example because the fuzzer is stuck outside of an execution, it aborts.
+    #[allow(clippy::unused_self)]
+    fn stop_wedged(&self, now_ms: u64, threshold_ms: u64, signaled_ms: &mut Option<u64>) {
+        #[cfg(unix)]
+        match *signaled_ms {
+            None => {
+                *signaled_ms = Some(now_ms);
+                libafl_log!(Error, "Watchdog: timing out the wedged execution");
+                unsafe {
+                    libc::pthread_kill(
+                        self.exec_thread.load(Ordering::Relaxed) as libc::pthread_t,
+                        libc::SIGALRM,
+                    );
+                }
+                return;
+            }
+            Some(at) if now_ms.saturating_sub(at) <= threshold_ms => return,
+            Some(_) => (),
+        }
+        match *signaled_ms {
+            Some(at) => libafl_log!(
+                Error,
+                "Watchdog: still wedged {}ms after timing out the execution (threshold {}ms), aborting the client",
+                now_ms.saturating_sub(at),
+                threshold_ms
+            ),
+            None => libafl_log!(
+                Error,
+                "Watchdog: aborting the client, wedged for longer than {}ms",
+                threshold_ms
+            ),
+        }
+        std::process::abort();
+    }
+}
+
+/// The watchdog thread of a [`WatchdogExecutor`], stopped and joined on drop
+#[derive(Debug)]
+struct WatchdogThread {
+    shared: Arc<WatchdogShared>,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl WatchdogThread {
+    fn spawn(shared: Arc<WatchdogShared>, threshold: Duration) -> Self {
+        let watched = Arc::clone(&shared);
+        let threshold_ms = threshold.as_millis() as u64;
+        let interval = (threshold / 4).max(Duration::from_millis(1));
+        let join_handle = thread::spawn(move || {
+            let mut reported = None;
+            // When the execution thread of the current wedge got signaled
+            let mut signaled_ms = None;
+            while !watched.stop.load(Ordering::Relaxed) {
+                thread::sleep(interval);
+                let now_ms = current_milliseconds();
+                if let Some(stalled_ms) = watched.check_wedge(now_ms, threshold_ms, &mut reported) {
+                    signaled_ms = None;
+                    libafl_log!(
+                        Error,
+                        "Watchdog: no execution finished in the last {}ms (threshold {}ms), the executor seems wedged",
+                        stalled_ms,
+                        threshold_ms
+                    );
+                } else if reported != Some(watched.last_exec_ms.load(Ordering::Relaxed)) {
+                    // Not wedged (anymore)
+                    continue;
+                }
+                if watched.abort_on_wedge.load(Ordering::Relaxed) {
+                    watched.stop_wedged(now_ms, threshold_ms, &mut signaled_ms);
+                }
+            }
+        });
+        Self {
+            shared,
+            join_handle: Some(join_handle),
+        }
+    }
+}
+
+impl Drop for WatchdogThread {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        if let Some(join_handle) = self.join_handle.take() {
+            join_handle
+                .join()
+                .expect("Failed to join the watchdog thread");
+        }
+    }
+}
+
+/// A wrapper for any [`Executor`], updating a heartbeat after each execution.
+/// If a threshold is set, a watchdog thread logs a diagnostic each time no execution finished
+/// for longer than the threshold, e.g. because the executor infrastructure hangs, not the target.
+/// It can also stop the client, so the `Launcher` restarts it, see [`WatchdogExecutor::set_abort_on_wedge`].
+/// The threshold has to be larger than the target timeout, and than anything else the fuzzer does between executions.
+#[derive(Debug)]
+pub struct WatchdogExecutor<E> {
+    executor: E,
+    shared: Arc<WatchdogShared>,
+    watchdog: Option<WatchdogThread>,
+}
+
+impl<E, EM, I, S, Z> Executor<EM, I, S, Z> for WatchdogExecutor<E>
+where
+    E: Executor<EM, I, S, Z>,
+    I: Input,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        #[cfg(unix)]
+        self.shared
+            .exec_thread
+            .store(unsafe { libc::pthread_self() } as usize, Ordering::Relaxed);
+        let ret = self.executor.run_target(fuzzer, state, mgr, input);
+        self.heartbeat();
+        ret
+    }
+
+    #[inline]
+    fn post_run_reset(&mut self) {
+        self.executor.post_run_reset();
+    }
+}
+
+impl<E, I, OT, S> HasObservers<I, OT, S> for WatchdogExecutor<E>
+where
+    E: HasObservers<I, OT, S>,
+    OT: ObserversTuple<I, S>,
+{
+    #[inline]
+    fn observers(&self) -> &OT {
+        self.executor.observers()
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> &mut OT {
+        self.executor.observers_mut()
+    }
+}
+
+impl<E> WatchdogExecutor<E>
+where
+    E: Debug,
+{
+    /// Wraps `executor`, the watchdog is disabled until a threshold is set with [`WatchdogExecutor::with_threshold`]
+    #[must_use]
+    pub fn new(executor: E) -> Self {
+        let shared = Arc::new(WatchdogShared::default());
+        shared
+            .last_exec_ms
+            .store(current_milliseconds(), Ordering::Relaxed);
+        Self {
+            executor,
+            shared,
+            watchdog: None,
+        }
+    }
+
+    /// Wraps `executor`, reporting a wedge if no execution finished for longer than `threshold`
+    #[must_use]
+    pub fn with_threshold(executor: E, threshold: Duration) -> Self {
+        let mut ret = Self::new(executor);
+        ret.watchdog = Some(WatchdogThread::spawn(Arc::clone(&ret.shared), threshold));
+        ret
+    }
+
+    /// If the watchdog stops the client when it detects a wedge, `false` by default.
+    /// On unix, the wedged execution gets a `SIGALRM` first, so an in-process executor reports it as a
+    /// [`ExitKind::Timeout`], not as a crash. If the client is still wedged after that, or on other platforms, it aborts.
+    pub fn set_abort_on_wedge(&mut self, abort_on_wedge: bool) {
+        self.shared
+            .abort_on_wedge
+            .store(abort_on_wedge, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if a watchdog thread is running
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.watchdog.is_some()
+    }
+
+    /// The amount of wedges the watchdog detected so far
+    #[must_use]
+    pub fn wedges(&self) -> usize {
+        self.shared.wedges.load(Ordering::Relaxed)
+    }
+
+    /// Updates the heartbeat, done after each execution
+    #[inline]
+    pub fn heartbeat(&self) {
+        self.shared
+            .last_exec_ms
+            .store(current_milliseconds(), Ordering::Relaxed);
+    }
+
+    /// The wrapped executor
+    #[must_use]
+    pub fn inner(&self) -> &E {
+        &self.executor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{sync::atomic::Ordering, time::Duration};
+
+    use crate::{
+        executors::{watchdog::WatchdogShared, Executor, ExitKind, WatchdogExecutor},
+        inputs::NopInput,
+        Error,
+    };
+
+    #[derive(Debug)]
+    struct NopExecutor {}
+
+    impl<EM, S, Z> Executor<EM, NopInput, S, Z> for NopExecutor {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut S,
+            _mgr: &mut EM,
+            _input: &NopInput,
+        ) -> Result<ExitKind, Error> {
+            Ok(ExitKind::Ok)
+        }
+    }
+
+    #[test]
+    fn test_watchdog_check_wedge() {
+        let shared = WatchdogShared::default();
+        shared.last_exec_ms.store(1000, Ordering::Relaxed);
+        let mut reported = None;
+
+        assert_eq!(shared.check_wedge(1040, 50, &mut reported), None);
+        // A wedged execution gets reported once, even if it keeps hanging
+        assert_eq!(shared.check_wedge(1100, 50, &mut reported), Some(100));
+        assert_eq!(shared.check_wedge(1500, 50, &mut reported), None);
+        assert_eq!(shared.wedges.load(Ordering::Relaxed), 1);
+
+        // The next execution finished, a new wedge gets reported again
+        shared.last_exec_ms.store(1500, Ordering::Relaxed);
+        assert_eq!(shared.check_wedge(1520, 50, &mut reported), None);
+        assert_eq!(shared.check_wedge(1600, 50, &mut reported), Some(100));
+        assert_eq!(shared.wedges.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_watchdog_executor() {
+        let mut executor = WatchdogExecutor::new(NopExecutor {});
+        assert!(!executor.is_enabled());
+        executor.shared.last_exec_ms.store(0, Ordering::Relaxed);
+        executor
+            .run_target(&mut (), &mut (), &mut (), &NopInput {})
+            .unwrap();
+        assert!(executor.shared.last_exec_ms.load(Ordering::Relaxed) > 0);
+
+        // Far from the threshold, the watchdog thread never reports anything
+        let executor = WatchdogExecutor::with_threshold(NopExecutor {}, Duration::from_secs(3600));
+        assert!(executor.is_enabled());
+        assert_eq!(executor.wedges(), 0);
+    }
+}
@@ -0,0 +1,297 @@
+//! A bloom filter in front of a (map) feedback, quickly rejecting map patterns that were seen before.
+
+use ahash::AHasher;
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Debug, Formatter},
+    hash::Hasher,
+    marker::PhantomData,
+};
+
+use crate::{
+    bolts::tuples::{MatchName, Named},
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::{MapObserver, ObserversTuple},
+    state::HasClientPerfMonitor,
+    Error,
+};
+
+/// The default size of the bloom filter, as a power of two bits (128 KiB).
+pub const DEFAULT_BLOOM_BITS_LOG2: u32 = 20;
+
+/// The default amount of hash functions of the bloom filter.
+pub const DEFAULT_BLOOM_HASHES: usize = 3;
+
+/// A [`BloomFilterFeedback`] wraps a feedback, usually a [`crate::feedbacks::MapFeedback`].
+/// It hashes the coverage of the map of the observer, the hit entries with the log2 bucket of their value,
+/// and looks the hash up in a bloom filter.
+/// Coverage seen before is rejected right away, without running the full map comparison of the wrapped feedback,
+/// even if the values differ within their buckets, such as hitcounts of `5` and `6`.
+/// The price is missing novelties within a bucket, and a small chance to miss a novelty,
+/// if its hash is a false positive of the bloom filter.
+/// The false positive rate can be tuned using the size of the filter and the amount of hash functions.
+pub struct BloomFilterFeedback<A, I, O, S>
+where
+    A: Feedback<I, S>,
+    I: Input,
+    O: MapObserver,
+    S: HasClientPerfMonitor,
+{
+    /// The wrapped feedback
+    pub first: A,
+    name: String,
+    observer_name: String,
+    bits: Vec<u64>,
+    bits_mask: u64,
+    hashes: usize,
+    skipped: u64,
+    last_skipped: bool,
+    phantom: PhantomData<(I, O, S)>,
+}
+
+impl<A, I, O, S> Debug for BloomFilterFeedback<A, I, O, S>
+where
+    A: Feedback<I, S>,
+    I: Input,
+    O: MapObserver,
+    S: HasClientPerfMonitor,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BloomFilterFeedback")
+            .field("name", &self.name)
+            .field("first", &self.first)
+            .field("observer_name", &self.observer_name)
+            .field("bits_len", &(self.bits_mask + 1))
+            .field("hashes", &self.hashes)
+            .field("skipped", &self.skipped)
+            .finish()
+    }
+}
+
+impl<A, I, O, S> Feedback<I, S> for BloomFilterFeedback<A, I, O, S>
+where
+    A: Feedback<I, S>,
+    I: Input,
+    O: MapObserver,
+    S: HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let observer = observers.match_name::<O>(&self.observer_name).unwrap();
+        if self.test_and_set(Self::coverage_hash(observer)) {
+            self.skipped += 1;
+            self.last_skipped = true;
+            return Ok(false);
+        }
+        self.last_skipped = false;
+        self.first
+            .is_interesting(state, manager, input, observers, exit_kind)
+    }
+
+    #[inline]
+    fn append_metadata(&mut self, state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if self.last_skipped {
+            return Ok(());
+        }
+        self.first.append_metadata(state, testcase)
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        if self.last_skipped {
+            return Ok(());
+        }
+        self.first.discard_metadata(state, input)
+    }
+}
+
+impl<A, I, O, S> Named for BloomFilterFeedback<A, I, O, S>
+where
+    A: Feedback<I, S>,
+    I: Input,
+    O: MapObserver,
+    S: HasClientPerfMonitor,
+{
+    #[inline]
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<A, I, O, S> BloomFilterFeedback<A, I, O, S>
+where
+    A: Feedback<I, S>,
+    I: Input,
+    O: MapObserver,
+    S: HasClientPerfMonitor,
+{
+    /// Creates a new [`BloomFilterFeedback`] in front of the `first` feedback, hashing the map of `map_observer`.
+    pub fn new(first: A, map_observer: &O) -> Self {
+        Self::with_params(
+            first,
+            map_observer,
+            DEFAULT_BLOOM_BITS_LOG2,
+            DEFAULT_BLOOM_HASHES,
+        )
+    }
+
+    /// Creates a new [`BloomFilterFeedback`] with a filter of `2^bits_log2` bits and `hashes` hash functions.
+    /// Bigger filters have less false positives, more hash functions are a tradeoff between speed and accuracy.
+    pub fn with_params(first: A, map_observer: &O, bits_log2: u32, hashes: usize) -> Self {
+        assert!(
+            (6..64).contains(&bits_log2),
+            "The bloom filter needs between 2^6 and 2^63 bits"
+        );
+        let name = format!("BloomFilter({})", first.name());
+        Self {
+            first,
+            name,
+            observer_name: map_observer.name().to_string(),
+            bits: vec![0; 1 << (bits_log2 - 6)],
+            bits_mask: (1_u64 << bits_log2) - 1,
+            hashes: hashes.max(1),
+            skipped: 0,
+            last_skipped: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The amount of runs for which the full comparison of the wrapped feedback has been skipped
+    #[must_use]
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Hashes the coverage of the map: the indexes of the entries differing from the initial value,
+    /// with the log2 bucket of their value
+    fn coverage_hash(observer: &O) -> u64 {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        let initial = observer.initial();
+        for idx in 0..observer.usable_count() {
+            let value = *observer.get(idx);
+            if value != initial {
+                hasher.write_usize(idx);
+                let bits = value.count_zeros() + value.count_ones();
+                hasher.write_u32(bits - value.leading_zeros());
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Sets the bits for `hash` in the filter, returning `true` if they were all already set.
+    /// The hash functions are derived from the map hash using double hashing.
+    #[allow(clippy::cast_possible_truncation)]
+    fn test_and_set(&mut self, hash: u64) -> bool {
+        let h1 = hash;
+        let h2 = hash.rotate_left(32) | 1;
+        let mut seen = true;
+        for i in 0..self.hashes as u64 {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) & self.bits_mask;
+            let (word, mask) = ((bit >> 6) as usize, 1 << (bit & 63));
+            if self.bits[word] & mask == 0 {
+                seen = false;
+                self.bits[word] |= mask;
+            }
+        }
+        seen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{
+            rands::StdRand,
+            tuples::{tuple_list, MatchName},
+            AsMutSlice,
+        },
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{BloomFilterFeedback, Feedback, MapFeedbackState, MaxMapFeedback},
+        inputs::BytesInput,
+        observers::StdMapObserver,
+        state::StdState,
+    };
+
+    #[test]
+    fn test_bloom_filter_feedback() {
+        let observer = StdMapObserver::new_owned("map", vec![0_u8; 64]);
+        let feedback_state = MapFeedbackState::with_observer(&observer);
+        let mut feedback = BloomFilterFeedback::new(
+            MaxMapFeedback::<BytesInput, _, _, _>::new(&feedback_state, &observer),
+            &observer,
+        );
+        let mut observers = tuple_list!(observer);
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            tuple_list!(feedback_state),
+        );
+        let mut mgr = NopEventManager {};
+        let input = BytesInput::new(vec![]);
+
+        observers
+            .match_name_mut::<StdMapObserver<u8>>("map")
+            .unwrap()
+            .as_mut_slice()[3] = 1;
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+
+        // Repeated identical maps skip the full comparison
+        for _ in 0..10 {
+            assert!(!feedback
+                .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+                .unwrap());
+        }
+        assert_eq!(feedback.skipped(), 10);
+
+        // Counts in the same bucket are the same coverage
+        observers
+            .match_name_mut::<StdMapObserver<u8>>("map")
+            .unwrap()
+            .as_mut_slice()[3] = 5;
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+        observers
+            .match_name_mut::<StdMapObserver<u8>>("map")
+            .unwrap()
+            .as_mut_slice()[3] = 6;
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+        assert_eq!(feedback.skipped(), 11);
+
+        // A new edge goes through the full comparison again
+        observers
+            .match_name_mut::<StdMapObserver<u8>>("map")
+            .unwrap()
+            .as_mut_slice()[7] = 1;
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+        assert_eq!(feedback.skipped(), 11);
+    }
+}
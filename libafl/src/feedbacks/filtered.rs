@@ -0,0 +1,255 @@
+//! The [`FilteredFeedback`] only considers an input interesting if an inner feedback does,
+//! and a predicate on the input and the observers holds as well.
+
+use alloc::string::{String, ToString};
+use core::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+};
+
+use crate::{
+    bolts::tuples::Named, corpus::Testcase, events::EventFirer, executors::ExitKind,
+    feedbacks::Feedback, inputs::Input, observers::ObserversTuple, state::HasClientPerfMonitor,
+    Error,
+};
+
+/// A predicate on an input, and the observers of its execution, gating a [`FilteredFeedback`].
+/// Implement it directly to inspect the whole observers tuple,
+/// or use an [`InputPredicate`] or an [`ObserverPredicate`] wrapping a closure.
+pub trait FeedbackPredicate<I, S>: Debug
+where
+    I: Input,
+{
+    /// Returns `true` if the input may be considered interesting
+    fn holds<OT>(&mut self, input: &I, observers: &OT) -> Result<bool, Error>
+    where
+        OT: ObserversTuple<I, S>;
+}
+
+/// A [`FeedbackPredicate`] on the input only, e.g. to limit its length
+pub struct InputPredicate<P> {
+    predicate: P,
+}
+
+impl<P> Debug for InputPredicate<P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "InputPredicate")
+    }
+}
+
+impl<I, P, S> FeedbackPredicate<I, S> for InputPredicate<P>
+where
+    I: Input,
+    P: FnMut(&I) -> bool,
+{
+    fn holds<OT>(&mut self, input: &I, _observers: &OT) -> Result<bool, Error>
+    where
+        OT: ObserversTuple<I, S>,
+    {
+        Ok((self.predicate)(input))
+    }
+}
+
+impl<P> InputPredicate<P> {
+    /// Creates a new [`InputPredicate`]
+    #[must_use]
+    pub fn new(predicate: P) -> Self {
+        Self { predicate }
+    }
+}
+
+/// A [`FeedbackPredicate`] on the input and a single observer, matched by name,
+/// e.g. to check a custom value the harness reported
+pub struct ObserverPredicate<O, P> {
+    observer_name: String,
+    predicate: P,
+    phantom: PhantomData<O>,
+}
+
+impl<O, P> Debug for ObserverPredicate<O, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ObserverPredicate {{ observer: {} }}",
+            self.observer_name
+        )
+    }
+}
+
+impl<I, O, P, S> FeedbackPredicate<I, S> for ObserverPredicate<O, P>
+where
+    I: Input,
+    P: FnMut(&I, &O) -> bool,
+{
+    fn holds<OT>(&mut self, input: &I, observers: &OT) -> Result<bool, Error>
+    where
+        OT: ObserversTuple<I, S>,
+    {
+        let observer = observers
+            .match_name::<O>(&self.observer_name)
+            .ok_or_else(|| {
+                Error::IllegalArgument(format!(
+                    "ObserverPredicate: observer {} not found",
+                    self.observer_name
+                ))
+            })?;
+        Ok((self.predicate)(input, observer))
+    }
+}
+
+impl<O, P> ObserverPredicate<O, P>
+where
+    O: Named,
+{
+    /// Creates a new [`ObserverPredicate`], passing the given observer of the execution to `predicate`
+    #[must_use]
+    pub fn new(observer: &O, predicate: P) -> Self {
+        Self {
+            observer_name: observer.name().to_string(),
+            predicate,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// A feedback combining an inner feedback and a [`FeedbackPredicate`]:
+/// an input is only interesting if the predicate holds, and the inner feedback considers it interesting.
+/// The predicate gets checked first, so the inner feedback, e.g. a map feedback,
+/// does not record the novelty of inputs that get filtered out.
+#[derive(Debug)]
+pub struct FilteredFeedback<F, P> {
+    feedback: F,
+    predicate: P,
+    name: String,
+}
+
+impl<F, I, P, S> Feedback<I, S> for FilteredFeedback<F, P>
+where
+    F: Feedback<I, S>,
+    I: Input,
+    P: FeedbackPredicate<I, S>,
+    S: HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        if !self.predicate.holds(input, observers)? {
+            return Ok(false);
+        }
+        self.feedback
+            .is_interesting(state, manager, input, observers, exit_kind)
+    }
+
+    #[inline]
+    fn append_metadata(&mut self, state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        self.feedback.append_metadata(state, testcase)
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.feedback.discard_metadata(state, input)
+    }
+
+    #[inline]
+    fn take_reexec_input(&mut self) -> Option<I> {
+        self.feedback.take_reexec_input()
+    }
+}
+
+impl<F, P> Named for FilteredFeedback<F, P> {
+    #[inline]
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<F, P> FilteredFeedback<F, P>
+where
+    F: Named,
+{
+    /// Creates a new [`FilteredFeedback`], gating `feedback` with `predicate`
+    #[must_use]
+    pub fn new(feedback: F, predicate: P) -> Self {
+        let name = format!("Filtered({})", feedback.name());
+        Self {
+            feedback,
+            predicate,
+            name,
+        }
+    }
+
+    /// The inner feedback
+    #[must_use]
+    pub fn inner(&self) -> &F {
+        &self.feedback
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{CrashFeedback, Feedback, FilteredFeedback, InputPredicate, ObserverPredicate},
+        inputs::{BytesInput, HasBytesVec},
+        observers::{MapObserver, StdMapObserver},
+        state::StdState,
+    };
+
+    #[test]
+    fn test_filtered_feedback() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+        let short = BytesInput::new(b"abc".to_vec());
+        let long = BytesInput::new(b"abcdefgh".to_vec());
+
+        // Only short crashes are interesting
+        let mut feedback = FilteredFeedback::new(
+            CrashFeedback::new(),
+            InputPredicate::new(|input: &BytesInput| input.bytes().len() <= 4),
+        );
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &short, &(), &ExitKind::Crash)
+            .unwrap());
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &long, &(), &ExitKind::Crash)
+            .unwrap());
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &short, &(), &ExitKind::Ok)
+            .unwrap());
+
+        // Only crashes with a flag set by the harness are interesting
+        let flag = StdMapObserver::new_owned("flag", vec![0_u8]);
+        let mut feedback = FilteredFeedback::new(
+            CrashFeedback::new(),
+            ObserverPredicate::new(&flag, |_input: &BytesInput, flag: &StdMapObserver<u8>| {
+                *flag.get(0) != 0
+            }),
+        );
+        let mut observers = tuple_list!(flag);
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &short, &observers, &ExitKind::Crash)
+            .unwrap());
+        *observers.0.get_mut(0) = 1;
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &short, &observers, &ExitKind::Crash)
+            .unwrap());
+    }
+}
@@ -0,0 +1,278 @@
+//! The [`FrontierFeedback`] rewards inputs covering frontier edges: edges adjacent to covered edges
+//! in a static control-flow graph, but not covered themselves yet.
+//! This directs the exploration towards the boundary of the explored code.
+//!
+//! The adjacency of the edges is given as text, see [`FrontierFeedbackState::parse_adjacency`]:
+//! one line per edge, the map index of the edge, a colon, and the map indexes of its neighbor edges,
+//! separated by whitespace. Empty lines and lines starting with `#` are ignored.
+//! ```text
+//! # edge: neighbors
+//! 0: 1 2
+//! 1: 3
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt::Debug, marker::PhantomData};
+use hashbrown::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
+
+use crate::{
+    bolts::{
+        tuples::{MatchName, Named},
+        AsRefIterator,
+    },
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, FeedbackState},
+    inputs::Input,
+    observers::{MapObserver, ObserversTuple},
+    state::{HasClientPerfMonitor, HasFeedbackStates},
+    Error,
+};
+
+/// The state of a [`FrontierFeedback`], holding the adjacency of the edges, the covered edges and the frontier
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FrontierFeedbackState {
+    /// The neighbor edges of each edge
+    pub adjacency: HashMap<usize, Vec<usize>>,
+    /// The edges covered so far
+    pub covered: HashSet<usize>,
+    /// The edges adjacent to covered edges, but not covered themselves
+    pub frontier: HashSet<usize>,
+    /// Name identifier of this instance
+    pub name: String,
+}
+
+impl FeedbackState for FrontierFeedbackState {
+    fn reset(&mut self) -> Result<(), Error> {
+        self.covered.clear();
+        self.frontier.clear();
+        Ok(())
+    }
+}
+
+impl Named for FrontierFeedbackState {
+    #[inline]
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl FrontierFeedbackState {
+    /// Creates a new [`FrontierFeedbackState`] with the neighbor edges of each edge
+    #[must_use]
+    pub fn new(name: &str, adjacency: HashMap<usize, Vec<usize>>) -> Self {
+        Self {
+            adjacency,
+            covered: HashSet::new(),
+            frontier: HashSet::new(),
+            name: name.to_string(),
+        }
+    }
+
+    /// Creates a new [`FrontierFeedbackState`], loading the adjacency of the edges from a file,
+    /// in the format described in the [module documentation](self)
+    #[cfg(feature = "std")]
+    pub fn from_file<P>(name: &str, path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self::new(
+            name,
+            Self::parse_adjacency(&fs::read_to_string(path)?)?,
+        ))
+    }
+
+    /// Parses the adjacency of the edges, in the format described in the [module documentation](self)
+    pub fn parse_adjacency(text: &str) -> Result<HashMap<usize, Vec<usize>>, Error> {
+        let parse = |index: &str, line_no: usize| {
+            index.parse::<usize>().map_err(|_| {
+                Error::IllegalArgument(format!(
+                    "Invalid edge {} in line {} of the adjacency",
+                    index,
+                    line_no + 1
+                ))
+            })
+        };
+        let mut adjacency = HashMap::new();
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (edge, neighbors) = line.split_once(':').ok_or_else(|| {
+                Error::IllegalArgument(format!(
+                    "Missing colon in line {} of the adjacency",
+                    line_no + 1
+                ))
+            })?;
+            let neighbors = neighbors
+                .split_whitespace()
+                .map(|neighbor| parse(neighbor, line_no))
+                .collect::<Result<Vec<_>, _>>()?;
+            adjacency
+                .entry(parse(edge.trim(), line_no)?)
+                .or_insert_with(Vec::new)
+                .extend(neighbors);
+        }
+        Ok(adjacency)
+    }
+
+    /// Marks `edge` as covered, moving its uncovered neighbors to the frontier.
+    /// Returns `true` if the edge was on the frontier.
+    pub fn cover(&mut self, edge: usize) -> bool {
+        if !self.covered.insert(edge) {
+            return false;
+        }
+        let on_frontier = self.frontier.remove(&edge);
+        if let Some(neighbors) = self.adjacency.get(&edge) {
+            for neighbor in neighbors {
+                if !self.covered.contains(neighbor) {
+                    self.frontier.insert(*neighbor);
+                }
+            }
+        }
+        on_frontier
+    }
+}
+
+/// A feedback marking an input as interesting if it covers an edge on the frontier of a [`FrontierFeedbackState`],
+/// an edge adjacent to the edges covered so far, shrinking the frontier.
+/// An edge is covered if its entry in the map differs from the initial value.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FrontierFeedback<O> {
+    name: String,
+    observer_name: String,
+    phantom: PhantomData<O>,
+}
+
+impl<I, O, S> Feedback<I, S> for FrontierFeedback<O>
+where
+    I: Input,
+    O: MapObserver,
+    for<'it> O: AsRefIterator<'it, Item = O::Entry>,
+    S: HasFeedbackStates + HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let observer = observers
+            .match_name::<O>(&self.observer_name)
+            .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?;
+        let feedback_state = state
+            .feedback_states_mut()
+            .match_name_mut::<FrontierFeedbackState>(&self.name)
+            .ok_or_else(|| Error::KeyNotFound("FrontierFeedbackState not found".to_string()))?;
+        let initial = observer.initial();
+        let mut interesting = false;
+        for (edge, &item) in observer.as_ref_iter().enumerate() {
+            if item != initial && feedback_state.cover(edge) {
+                interesting = true;
+            }
+        }
+        Ok(interesting)
+    }
+}
+
+impl<O> Named for FrontierFeedback<O> {
+    #[inline]
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl<O> FrontierFeedback<O>
+where
+    O: Named,
+{
+    /// Creates a new [`FrontierFeedback`] for the [`FrontierFeedbackState`] with the given name,
+    /// observing the coverage of `map_observer`
+    #[must_use]
+    pub fn new(name: &str, map_observer: &O) -> Self {
+        Self {
+            name: name.to_string(),
+            observer_name: map_observer.name().to_string(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list, AsMutSlice},
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{Feedback, FrontierFeedback, FrontierFeedbackState},
+        inputs::BytesInput,
+        observers::{MapObserver, StdMapObserver},
+        state::{HasFeedbackStates, StdState},
+    };
+
+    #[test]
+    fn test_frontier_feedback() {
+        let adjacency =
+            FrontierFeedbackState::parse_adjacency("# edge: neighbors\n0: 1 2\n\n1: 3\n2:\n")
+                .unwrap();
+        assert_eq!(adjacency[&0], vec![1, 2]);
+        assert!(adjacency[&2].is_empty());
+        assert!(FrontierFeedbackState::parse_adjacency("0 1").is_err());
+        assert!(FrontierFeedbackState::parse_adjacency("0: x").is_err());
+
+        let mut observers = tuple_list!(StdMapObserver::new_owned("map", vec![0_u8; 4]));
+        let mut feedback = FrontierFeedback::new("frontier", &observers.0);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            tuple_list!(FrontierFeedbackState::new("frontier", adjacency)),
+        );
+        let mut mgr = NopEventManager {};
+        let input = BytesInput::new(vec![]);
+
+        let runs: [(&[usize], bool); 5] = [
+            // Nothing covered yet, so there is no frontier
+            (&[0], false),
+            // 1 is adjacent to 0
+            (&[0, 1], true),
+            (&[0, 1], false),
+            // 3 is adjacent to 1
+            (&[3], true),
+            (&[2], true),
+        ];
+        for (edges, interesting) in runs {
+            observers.0.reset_map().unwrap();
+            for &edge in edges {
+                observers.0.as_mut_slice()[edge] = 1;
+            }
+            assert_eq!(
+                feedback
+                    .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+                    .unwrap(),
+                interesting
+            );
+        }
+
+        // All edges are covered, the frontier is empty
+        let (frontier_state, _) = state.feedback_states();
+        assert_eq!(frontier_state.covered.len(), 4);
+        assert!(frontier_state.frontier.is_empty());
+    }
+}
@@ -4,10 +4,16 @@ use alloc::{
     string::{String, ToString},
     vec::Vec,
 };
+#[cfg(feature = "simd")]
+use core::any::TypeId;
 use core::{fmt::Debug, marker::PhantomData};
 use num_traits::PrimInt;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
 
+#[cfg(feature = "simd")]
+use crate::bolts::simd::max_merge_u8;
 use crate::{
     bolts::{
         tuples::{MatchName, Named},
@@ -113,6 +119,39 @@ where
     }
 }
 
+/// Merges a contiguous `u8` map into the history of a [`MaxMapFeedback`], with the vectorized [`max_merge_u8`].
+/// Returns `None` if the fast path does not apply to this feedback or map.
+#[cfg(feature = "simd")]
+fn try_max_merge_u8<N, R, T>(history: &mut [T], map: Option<&[T]>) -> Option<bool>
+where
+    N: 'static,
+    R: 'static,
+    T: 'static,
+{
+    if TypeId::of::<N>() != TypeId::of::<DifferentIsNovel>()
+        || TypeId::of::<R>() != TypeId::of::<MaxReducer>()
+        || TypeId::of::<T>() != TypeId::of::<u8>()
+    {
+        return None;
+    }
+    let map = map?;
+    // Safety: `T` is `u8`, checked above
+    let (map, history) = unsafe {
+        (
+            core::slice::from_raw_parts(map.as_ptr() as *const u8, map.len()),
+            core::slice::from_raw_parts_mut(history.as_mut_ptr() as *mut u8, history.len()),
+        )
+    };
+    Some(max_merge_u8(history, map))
+}
+
+/// Without the `simd` feature, there is no fast path
+#[cfg(not(feature = "simd"))]
+#[inline]
+fn try_max_merge_u8<N, R, T>(_history: &mut [T], _map: Option<&[T]>) -> Option<bool> {
+    None
+}
+
 /// A `IsNovel` function is used to discriminate if a reduced value is considered novel.
 pub trait IsNovel<T>: 'static + Debug
 where
@@ -270,6 +309,65 @@ impl MapNoveltiesMetadata {
     }
 }
 
+/// A testcase metadata holding the coverage of the run that added the testcase to the corpus:
+/// the sorted indexes of the entries of a map that were set, see [`MapCoverageFeedback`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MapCoverageMetadata {
+    /// The indexes of the set entries
+    pub edges: Vec<usize>,
+}
+
+crate::impl_serdeany!(MapCoverageMetadata);
+
+impl AsSlice<usize> for MapCoverageMetadata {
+    /// Convert to a slice
+    fn as_slice(&self) -> &[usize] {
+        self.edges.as_slice()
+    }
+}
+
+impl MapCoverageMetadata {
+    /// Creates a new [`MapCoverageMetadata`] from `edges`, sorting and deduplicating them
+    #[must_use]
+    pub fn new(mut edges: Vec<usize>) -> Self {
+        edges.sort_unstable();
+        edges.dedup();
+        Self { edges }
+    }
+
+    /// Encodes the edges compactly, as varint deltas, e.g. to store them next to the input.
+    /// Fails if the edges were modified to be out of order.
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        let mut prev = 0;
+        let deltas = self
+            .edges
+            .iter()
+            .map(|&edge| {
+                let delta = edge.checked_sub(prev).ok_or_else(|| {
+                    Error::IllegalArgument("The coverage edges are not sorted".into())
+                })?;
+                prev = edge;
+                Ok(delta)
+            })
+            .collect::<Result<Vec<usize>, Error>>()?;
+        Ok(postcard::to_allocvec(&deltas)?)
+    }
+
+    /// Decodes edges encoded with [`MapCoverageMetadata::encode`]
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let deltas: Vec<usize> = postcard::from_bytes(bytes)?;
+        let mut prev = 0;
+        let edges = deltas
+            .into_iter()
+            .map(|delta| {
+                prev += delta;
+                prev
+            })
+            .collect();
+        Ok(Self { edges })
+    }
+}
+
 /// The state of [`MapFeedback`]
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(bound = "T: serde::de::DeserializeOwned")]
@@ -281,6 +379,14 @@ where
     pub history_map: Vec<T>,
     /// Name identifier of this instance
     pub name: String,
+    /// The input that first covered each entry, if tracked, see [`MapFeedbackState::with_first_cover`],
+    /// as position of its name in `first_cover_names`.
+    /// Untouched entries, and entries first covered by inputs not kept, hold [`usize::MAX`].
+    #[serde(default)]
+    pub first_cover: Option<Vec<usize>>,
+    /// The names of the inputs in `first_cover`, see [`Input::generate_name`]
+    #[serde(default)]
+    pub first_cover_names: Vec<String>,
 }
 
 impl<T> FeedbackState for MapFeedbackState<T>
@@ -291,6 +397,10 @@ where
         self.history_map
             .iter_mut()
             .for_each(|x| *x = T::min_value());
+        if let Some(first_cover) = self.first_cover.as_mut() {
+            first_cover.iter_mut().for_each(|x| *x = usize::MAX);
+        }
+        self.first_cover_names.clear();
         Ok(())
     }
 }
@@ -315,6 +425,8 @@ where
         Self {
             history_map: vec![T::min_value(); map_size],
             name: name.to_string(),
+            first_cover: None,
+            first_cover_names: vec![],
         }
     }
 
@@ -327,6 +439,8 @@ where
         Self {
             history_map: vec![T::min_value(); map_observer.len()],
             name: map_observer.name().to_string(),
+            first_cover: None,
+            first_cover_names: vec![],
         }
     }
 
@@ -337,11 +451,144 @@ where
         Self {
             history_map,
             name: name.to_string(),
+            first_cover: None,
+            first_cover_names: vec![],
+        }
+    }
+
+    /// Tracks the name of the input that first covered each entry, to attribute the coverage to the inputs.
+    /// The names stay valid while the corpus gets renumbered, unlike corpus indices.
+    /// This keeps an index for each entry, next to the history map, so it is off by default.
+    #[must_use]
+    pub fn with_first_cover(mut self, track: bool) -> Self {
+        self.first_cover = if track {
+            Some(vec![usize::MAX; self.history_map.len()])
+        } else {
+            None
+        };
+        self
+    }
+
+    /// The name of the input that first covered the `edge`, if tracked and covered, see [`Input::generate_name`]
+    #[must_use]
+    pub fn first_cover(&self, edge: usize) -> Option<&str> {
+        self.first_cover
+            .as_ref()
+            .and_then(|first_cover| first_cover.get(edge))
+            .and_then(|&pos| self.first_cover_names.get(pos))
+            .map(String::as_str)
+    }
+
+    /// Take a snapshot of the current history map, to later query newly discovered entries
+    /// using [`MapFeedbackState::diff_since`].
+    #[must_use]
+    pub fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    /// Returns the indexes of the entries that have been set since the `prior` snapshot was taken,
+    /// i.e., entries that were untouched in `prior` but are set in this history map.
+    #[must_use]
+    pub fn diff_since(&self, prior: &Self) -> Vec<usize> {
+        let initial = T::min_value();
+        self.history_map
+            .iter()
+            .enumerate()
+            .filter(|(i, &item)| {
+                item != initial
+                    && prior
+                        .history_map
+                        .get(*i)
+                        .map_or(true, |&old| old == initial)
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Saves this feedback state to a file, to compare the coverage of campaigns later,
+    /// see [`CoverageComparison`].
+    #[cfg(feature = "std")]
+    pub fn save_map<P>(&self, path: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(path, postcard::to_allocvec(self)?)?;
+        Ok(())
+    }
+
+    /// Loads a feedback state previously written with [`MapFeedbackState::save_map`]
+    #[cfg(feature = "std")]
+    pub fn load_map<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(postcard::from_bytes(&fs::read(path)?)?)
+    }
+}
+
+/// The edges covered by two campaigns, split into the ones only `a` found, the ones only `b` found, and the shared ones.
+/// It serializes to a machine-readable report, e.g. using `serde_json`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CoverageComparison {
+    /// The edges only covered by `a`
+    pub a_only: Vec<usize>,
+    /// The edges only covered by `b`
+    pub b_only: Vec<usize>,
+    /// The edges covered by both
+    pub both: Vec<usize>,
+    /// The amount of edges only covered by `a`
+    pub a_only_count: usize,
+    /// The amount of edges only covered by `b`
+    pub b_only_count: usize,
+    /// The amount of edges covered by both
+    pub both_count: usize,
+}
+
+impl CoverageComparison {
+    /// Compares the history maps of two campaigns maximizing their entries, such as with a [`MaxMapFeedback`].
+    /// An edge counts as covered if its entry differs from `T::min_value()`, the initial value of their history maps.
+    /// Maps of different sizes are compared up to the larger one.
+    #[must_use]
+    pub fn new<T>(a: &MapFeedbackState<T>, b: &MapFeedbackState<T>) -> Self
+    where
+        T: PrimInt + Default + Copy + 'static + Serialize + serde::de::DeserializeOwned,
+    {
+        Self::with_initial(a, b, T::min_value())
+    }
+
+    /// Compares the history maps of two campaigns, counting an edge as covered if its entry differs from `initial`.
+    /// Use this for history maps starting at another value than `T::min_value()`,
+    /// such as the maps of a [`MinMapFeedback`], created with [`MapFeedbackState::with_history_map`]
+    /// from `T::max_value()` to get minimized.
+    #[must_use]
+    pub fn with_initial<T>(a: &MapFeedbackState<T>, b: &MapFeedbackState<T>, initial: T) -> Self
+    where
+        T: PrimInt + Default + Copy + 'static + Serialize + serde::de::DeserializeOwned,
+    {
+        let covered = |map: &[T], i: usize| map.get(i).map_or(false, |&item| item != initial);
+
+        let mut comparison = Self::default();
+        for i in 0..a.history_map.len().max(b.history_map.len()) {
+            match (covered(&a.history_map, i), covered(&b.history_map, i)) {
+                (true, true) => comparison.both.push(i),
+                (true, false) => comparison.a_only.push(i),
+                (false, true) => comparison.b_only.push(i),
+                (false, false) => (),
+            }
         }
+        comparison.a_only_count = comparison.a_only.len();
+        comparison.b_only_count = comparison.b_only.len();
+        comparison.both_count = comparison.both.len();
+        comparison
     }
 }
 
 /// The most common AFL-like feedback type
+///
+/// To observe multiple maps, e.g. of different instrumented components, use one [`MapFeedbackState`]
+/// and one [`MapFeedback`] for each map observer, and combine the feedbacks with [`crate::feedback_or`].
+/// The eager `feedback_or` makes sure that every history map gets updated, while
+/// [`crate::feedback_or_fast`] would skip the maps following the first interesting one.
 #[derive(Clone, Debug)]
 pub struct MapFeedback<I, N, O, R, S, T>
 where
@@ -360,6 +607,14 @@ where
     name: String,
     /// Name identifier of the observer
     observer_name: String,
+    /// If new coverage only counts once it reproduces in a second execution
+    reexec_verification: bool,
+    /// The input waiting for its verification, with the new entries of its first execution
+    pending: Option<(I, Vec<usize>)>,
+    /// If the last run asked the fuzzer to execute the pending input once more
+    reexec_requested: bool,
+    /// The entries first covered in the last observation, if the feedback state tracks the first cover
+    first_covered: Vec<usize>,
     /// Phantom Data of Reducer
     phantom: PhantomData<(I, N, S, R, O, T)>,
 }
@@ -374,12 +629,12 @@ where
     I: Input,
     S: HasFeedbackStates + HasClientPerfMonitor + Debug,
 {
-    #[allow(clippy::wrong_self_convention)]
+    #[allow(clippy::wrong_self_convention, clippy::too_many_lines)]
     fn is_interesting<EM, OT>(
         &mut self,
         state: &mut S,
         manager: &mut EM,
-        _input: &I,
+        input: &I,
         observers: &OT,
         _exit_kind: &ExitKind,
     ) -> Result<bool, Error>
@@ -402,21 +657,78 @@ where
 
         assert!(size <= observer.len());
 
-        if self.novelties.is_some() {
+        let untouched = T::min_value();
+        let track_first_cover = map_state.first_cover.is_some();
+        self.first_covered.clear();
+
+        self.reexec_requested = false;
+        if self.reexec_verification {
+            let novel: Vec<usize> = observer
+                .as_ref_iter()
+                .enumerate()
+                .filter(|&(i, &item)| {
+                    let history = map_state.history_map[i];
+                    N::is_novel(history, R::reduce(history, item))
+                })
+                .map(|(i, _)| i)
+                .collect();
+            // Only the run right after a candidate may verify it, the inputs are compared just then
+            let verified = match self.pending.take() {
+                Some((pending_input, pending))
+                    if postcard::to_allocvec(&pending_input)? == postcard::to_allocvec(input)? =>
+                {
+                    Some(pending)
+                }
+                _ => None,
+            };
+            if let Some(pending) = verified {
+                // The second execution, only the new entries seen in both executions count
+                for i in novel {
+                    if pending.binary_search(&i).is_ok() {
+                        if track_first_cover && map_state.history_map[i] == untouched {
+                            self.first_covered.push(i);
+                        }
+                        map_state.history_map[i] =
+                            R::reduce(map_state.history_map[i], *observer.get(i));
+                        interesting = true;
+                        if let Some(novelties) = self.novelties.as_mut() {
+                            novelties.push(i);
+                        }
+                    }
+                }
+            } else if !novel.is_empty() {
+                // A candidate, the fuzzer takes it in `take_reexec_input` and runs it again
+                self.pending = Some((input.clone(), novel));
+                self.reexec_requested = true;
+            }
+        } else if self.novelties.is_some() {
             for (i, &item) in observer.as_ref_iter().enumerate() {
                 let history = map_state.history_map[i];
                 let reduced = R::reduce(history, item);
                 if N::is_novel(history, reduced) {
+                    if track_first_cover && history == untouched {
+                        self.first_covered.push(i);
+                    }
                     map_state.history_map[i] = reduced;
                     interesting = true;
                     self.novelties.as_mut().unwrap().push(i);
                 }
             }
+        } else if let Some(novel) = try_max_merge_u8::<N, R, T>(
+            &mut map_state.history_map,
+            observer
+                .as_contiguous_slice()
+                .filter(|_| !track_first_cover),
+        ) {
+            interesting = novel;
         } else {
             for (i, &item) in observer.as_ref_iter().enumerate() {
                 let history = map_state.history_map[i];
                 let reduced = R::reduce(history, item);
                 if N::is_novel(history, reduced) {
+                    if track_first_cover && history == untouched {
+                        self.first_covered.push(i);
+                    }
                     map_state.history_map[i] = reduced;
                     interesting = true;
                 }
@@ -446,7 +758,22 @@ where
         Ok(interesting)
     }
 
-    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+    fn append_metadata(&mut self, state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if !self.first_covered.is_empty() {
+            // The index of the testcase is not known yet, and may change later on, the name does not depend on it
+            let name = testcase.load_input()?.generate_name(0);
+            let map_state = state
+                .feedback_states_mut()
+                .match_name_mut::<MapFeedbackState<T>>(&self.name)
+                .unwrap();
+            if let Some(first_cover) = map_state.first_cover.as_mut() {
+                let pos = map_state.first_cover_names.len();
+                map_state.first_cover_names.push(name);
+                for i in self.first_covered.drain(..) {
+                    first_cover[i] = pos;
+                }
+            }
+        }
         if let Some(v) = self.indexes.as_mut() {
             let meta = MapIndexesMetadata::new(core::mem::take(v));
             testcase.add_metadata(meta);
@@ -460,6 +787,7 @@ where
 
     /// Discard the stored metadata in case that the testcase is not added to the corpus
     fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.first_covered.clear();
         if let Some(v) = self.indexes.as_mut() {
             v.clear();
         }
@@ -468,6 +796,14 @@ where
         }
         Ok(())
     }
+
+    fn take_reexec_input(&mut self) -> Option<I> {
+        if core::mem::take(&mut self.reexec_requested) {
+            self.pending.as_ref().map(|(input, _)| input.clone())
+        } else {
+            None
+        }
+    }
 }
 
 impl<I, N, O, R, S, T> Named for MapFeedback<I, N, O, R, S, T>
@@ -509,6 +845,10 @@ where
             novelties: None,
             name: feedback_state.name().to_string(),
             observer_name: map_observer.name().to_string(),
+            reexec_verification: false,
+            pending: None,
+            reexec_requested: false,
+            first_covered: vec![],
             phantom: PhantomData,
         }
     }
@@ -526,6 +866,10 @@ where
             novelties: if track_novelties { Some(vec![]) } else { None },
             name: feedback_state.name().to_string(),
             observer_name: map_observer.name().to_string(),
+            reexec_verification: false,
+            pending: None,
+            reexec_requested: false,
+            first_covered: vec![],
             phantom: PhantomData,
         }
     }
@@ -538,6 +882,10 @@ where
             novelties: None,
             name: name.to_string(),
             observer_name: observer_name.to_string(),
+            reexec_verification: false,
+            pending: None,
+            reexec_requested: false,
+            first_covered: vec![],
             phantom: PhantomData,
         }
     }
@@ -555,6 +903,98 @@ where
             novelties: if track_novelties { Some(vec![]) } else { None },
             observer_name: observer_name.to_string(),
             name: name.to_string(),
+            reexec_verification: false,
+            pending: None,
+            reexec_requested: false,
+            first_covered: vec![],
+            phantom: PhantomData,
+        }
+    }
+
+    /// Only count new coverage that reproduces, against targets with flaky coverage.
+    /// A run with new entries is not interesting yet, instead the fuzzer executes the input once more,
+    /// with the same executor, and only keeps it if some of the new entries show up again.
+    /// This costs an extra execution per candidate. Where executions only get processed, as in a push stage,
+    /// nothing runs the candidates again, so their coverage never counts.
+    #[must_use]
+    pub fn with_reexec_verification(mut self, verify: bool) -> Self {
+        self.reexec_verification = verify;
+        self
+    }
+}
+
+/// A [`MapCoverageFeedback`] records the coverage of each run in a [`MapCoverageMetadata`],
+/// so corpus minimization does not need to execute the entries again, see [`crate::corpus::OnDiskCorpus::set_save_coverage`].
+/// It never considers a run interesting on its own, combine it with the feedback deciding, e.g.
+/// `feedback_or!(MaxMapFeedback::new(&feedback_state, &observer), MapCoverageFeedback::new(&observer))`.
+#[derive(Clone, Debug)]
+pub struct MapCoverageFeedback<O> {
+    name: String,
+    observer_name: String,
+    edges: Vec<usize>,
+    phantom: PhantomData<O>,
+}
+
+impl<I, O, S> Feedback<I, S> for MapCoverageFeedback<O>
+where
+    I: Input,
+    O: MapObserver,
+    for<'it> O: AsRefIterator<'it, Item = O::Entry>,
+    S: HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let observer = observers.match_name::<O>(&self.observer_name).unwrap();
+        let initial = observer.initial();
+        self.edges.clear();
+        for (i, &item) in observer.as_ref_iter().enumerate() {
+            if item != initial {
+                self.edges.push(i);
+            }
+        }
+        Ok(false)
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        testcase.add_metadata(MapCoverageMetadata::new(core::mem::take(&mut self.edges)));
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.edges.clear();
+        Ok(())
+    }
+}
+
+impl<O> Named for MapCoverageFeedback<O> {
+    #[inline]
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl<O> MapCoverageFeedback<O>
+where
+    O: Named,
+{
+    /// Creates a new [`MapCoverageFeedback`], recording the coverage in `map_observer`
+    #[must_use]
+    pub fn new(map_observer: &O) -> Self {
+        Self {
+            name: format!("MapCoverage({})", map_observer.name()),
+            observer_name: map_observer.name().to_string(),
+            edges: vec![],
             phantom: PhantomData,
         }
     }
@@ -658,7 +1098,335 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::feedbacks::{AllIsNovel, IsNovel, NextPow2IsNovel};
+    use crate::{
+        bolts::{
+            rands::{Rand, StdRand},
+            tuples::{tuple_list, MatchName},
+            AsMutSlice,
+        },
+        corpus::{Corpus, InMemoryCorpus, QueueCorpusScheduler},
+        events::NopEventManager,
+        executors::{ExitKind, HasObservers, InProcessExecutor},
+        feedback_or,
+        feedbacks::{
+            AllIsNovel, CoverageComparison, Feedback, IsNovel, MapCoverageMetadata,
+            MapFeedbackState, MaxMapFeedback, NextPow2IsNovel,
+        },
+        fuzzer::{Evaluator, ExecuteInputResult, ExecutionProcessor, StdFuzzer},
+        inputs::{BytesInput, HasBytesVec, Input},
+        observers::StdMapObserver,
+        state::{HasCorpus, HasExecutions, HasFeedbackStates, StdState},
+    };
+
+    static mut FLAKY_MAP: [u8; 4] = [0; 4];
+    static mut FLAKY_RUNS: usize = 0;
+    static mut FIRST_COVER_MAP: [u8; 4] = [0; 4];
+
+    #[test]
+    fn test_map_feedback_state_diff() {
+        let mut state = MapFeedbackState::<u8>::new("map", 8);
+        state.history_map[1] = 1;
+        let snapshot = state.snapshot();
+        assert!(state.diff_since(&snapshot).is_empty());
+
+        state.history_map[1] = 4;
+        state.history_map[3] = 1;
+        state.history_map[7] = 2;
+        assert_eq!(state.diff_since(&snapshot), vec![3, 7]);
+    }
+
+    #[test]
+    fn test_coverage_comparison() {
+        let a = MapFeedbackState::<u8>::with_history_map("map", vec![1, 0, 3, 0, 1, 0]);
+        let b = MapFeedbackState::<u8>::with_history_map("map", vec![0, 2, 1, 0, 1, 0, 0, 5]);
+
+        let comparison = CoverageComparison::new(&a, &b);
+        assert_eq!(comparison.a_only, vec![0]);
+        assert_eq!(comparison.b_only, vec![1, 7]);
+        assert_eq!(comparison.both, vec![2, 4]);
+        assert_eq!(
+            (
+                comparison.a_only_count,
+                comparison.b_only_count,
+                comparison.both_count
+            ),
+            (1, 2, 2)
+        );
+        assert_eq!(CoverageComparison::new(&b, &a).a_only, vec![1, 7]);
+
+        // Minimized maps start at the max value
+        let a = MapFeedbackState::<u8>::with_history_map("map", vec![u8::MAX, 0, 3]);
+        let b = MapFeedbackState::<u8>::with_history_map("map", vec![u8::MAX, u8::MAX, 1]);
+        let comparison = CoverageComparison::with_initial(&a, &b, u8::MAX);
+        assert_eq!(comparison.a_only, vec![1]);
+        assert!(comparison.b_only.is_empty());
+        assert_eq!(comparison.both, vec![2]);
+    }
+
+    #[test]
+    fn test_coverage_metadata_encode() {
+        let coverage = MapCoverageMetadata::new(vec![300, 2, 70_000, 2, 0]);
+        assert_eq!(coverage.edges, vec![0, 2, 300, 70_000]);
+        let decoded = MapCoverageMetadata::decode(&coverage.encode().unwrap()).unwrap();
+        assert_eq!(decoded, coverage);
+
+        // Unsorted edges, set through the public field, fail instead of underflowing
+        let unsorted = MapCoverageMetadata { edges: vec![5, 3] };
+        assert!(unsorted.encode().is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_save_load_map() {
+        std::fs::create_dir_all("target/.test").unwrap();
+        let path = "target/.test/test_save_load_map.bin";
+        let a = MapFeedbackState::<u8>::with_history_map("map", vec![0, 1, 0, 4]);
+        a.save_map(path).unwrap();
+        let loaded = MapFeedbackState::<u8>::load_map(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded.history_map, a.history_map);
+        assert_eq!(loaded.name, "map");
+    }
+
+    #[test]
+    fn test_multi_map_feedback() {
+        let observer_a = StdMapObserver::new_owned("map_a", vec![0_u8; 4]);
+        let observer_b = StdMapObserver::new_owned("map_b", vec![0_u8; 4]);
+        let feedback_state_a = MapFeedbackState::with_observer(&observer_a);
+        let feedback_state_b = MapFeedbackState::with_observer(&observer_b);
+        let mut feedback = feedback_or!(
+            MaxMapFeedback::<BytesInput, _, _, _>::new(&feedback_state_a, &observer_a),
+            MaxMapFeedback::<BytesInput, _, _, _>::new(&feedback_state_b, &observer_b)
+        );
+        let mut observers = tuple_list!(observer_a, observer_b);
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            tuple_list!(feedback_state_a, feedback_state_b),
+        );
+        let mut mgr = NopEventManager {};
+        let input = BytesInput::new(vec![]);
+
+        // New coverage in either map is interesting, the same coverage again is not
+        for (map_name, idx, expected) in [
+            ("map_a", 1, true),
+            ("map_a", 1, false),
+            ("map_b", 2, true),
+            ("map_b", 2, false),
+        ] {
+            observers
+                .match_name_mut::<StdMapObserver<u8>>(map_name)
+                .unwrap()
+                .as_mut_slice()[idx] = 1;
+            let interesting = feedback
+                .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+                .unwrap();
+            assert_eq!(interesting, expected);
+        }
+    }
+
+    #[test]
+    fn test_max_map_fast_path() {
+        let mut observers = tuple_list!(StdMapObserver::new_owned("map", vec![0_u8; 1000]));
+        // Tracking the novelties takes the scalar path, the same map without tracking the vectorized one
+        let mut fast =
+            MaxMapFeedback::<BytesInput, StdMapObserver<u8>, _, _>::with_names("fast", "map");
+        let mut scalar =
+            MaxMapFeedback::<BytesInput, StdMapObserver<u8>, _, _>::with_names_tracking(
+                "scalar", "map", false, true,
+            );
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            tuple_list!(
+                MapFeedbackState::<u8>::new("fast", 1000),
+                MapFeedbackState::<u8>::new("scalar", 1000)
+            ),
+        );
+        let mut mgr = NopEventManager {};
+        let input = BytesInput::new(vec![]);
+
+        let mut rand = StdRand::with_seed(1337);
+        for _ in 0..64 {
+            for entry in observers.0.as_mut_slice() {
+                *entry = if rand.below(32) == 0 {
+                    rand.below(8) as u8
+                } else {
+                    0
+                };
+            }
+            let fast_interesting = fast
+                .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+                .unwrap();
+            let scalar_interesting = scalar
+                .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+                .unwrap();
+            assert_eq!(fast_interesting, scalar_interesting);
+            scalar.discard_metadata(&mut state, &input).unwrap();
+
+            let fast_history = &state
+                .feedback_states()
+                .match_name::<MapFeedbackState<u8>>("fast")
+                .unwrap()
+                .history_map;
+            let scalar_history = &state
+                .feedback_states()
+                .match_name::<MapFeedbackState<u8>>("scalar")
+                .unwrap()
+                .history_map;
+            assert_eq!(fast_history, scalar_history);
+        }
+    }
+
+    /// A harness covering the first entry, and the second one only on every other run
+    fn flaky_harness(_input: &BytesInput) -> ExitKind {
+        unsafe {
+            FLAKY_RUNS += 1;
+            FLAKY_MAP[0] = 1;
+            if FLAKY_RUNS % 2 == 1 {
+                FLAKY_MAP[1] = 1;
+            }
+        }
+        ExitKind::Ok
+    }
+
+    #[test]
+    fn test_map_feedback_reexec_verification() {
+        let observer = unsafe { StdMapObserver::new("flaky", &mut FLAKY_MAP) };
+        let feedback_state = MapFeedbackState::with_observer(&observer);
+        let feedback =
+            MaxMapFeedback::new(&feedback_state, &observer).with_reexec_verification(true);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            tuple_list!(feedback_state),
+        );
+        let mut fuzzer = StdFuzzer::new(QueueCorpusScheduler::new(), feedback, ());
+        let mut mgr = NopEventManager {};
+        let mut harness = flaky_harness;
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(observer),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        // The first run covers both entries, the second one only the first entry, which is kept
+        let input = BytesInput::new(vec![0]);
+        let (res, idx) = fuzzer
+            .evaluate_input(&mut state, &mut executor, &mut mgr, input)
+            .unwrap();
+        assert_eq!(res, ExecuteInputResult::Corpus);
+        assert_eq!(idx, Some(0));
+        assert_eq!(*state.executions(), 2);
+
+        // The flaky entry never reproduces, so it never makes it to the corpus
+        let input = BytesInput::new(vec![1]);
+        let (res, idx) = fuzzer
+            .evaluate_input(&mut state, &mut executor, &mut mgr, input)
+            .unwrap();
+        assert_eq!(res, ExecuteInputResult::None);
+        assert_eq!(idx, None);
+        assert_eq!(*state.executions(), 4);
+        assert_eq!(state.corpus().count(), 1);
+
+        // A push stage only processes the execution, and never runs the candidate again
+        unsafe {
+            FLAKY_MAP[2] = 1;
+        }
+        let (res, _) = fuzzer
+            .process_execution(
+                &mut state,
+                &mut mgr,
+                BytesInput::new(vec![2]),
+                executor.observers(),
+                &ExitKind::Ok,
+                false,
+            )
+            .unwrap();
+        assert_eq!(res, ExecuteInputResult::None);
+
+        // The next evaluation does not pick up the left-over candidate, only its own one
+        let input = BytesInput::new(vec![3]);
+        let (res, _) = fuzzer
+            .evaluate_input(&mut state, &mut executor, &mut mgr, input)
+            .unwrap();
+        assert_eq!(res, ExecuteInputResult::None);
+        assert_eq!(*state.executions(), 6);
+        assert!(fuzzer.take_reexec_input().is_none());
+
+        let history = &state
+            .feedback_states()
+            .match_name::<MapFeedbackState<u8>>("flaky")
+            .unwrap()
+            .history_map;
+        assert_eq!(history[..], [1, 0, 0, 0]);
+    }
+
+    /// A harness covering the entries at the indices given by the input bytes, once per occurrence
+    fn first_cover_harness(input: &BytesInput) -> ExitKind {
+        for &idx in input.bytes() {
+            unsafe {
+                FIRST_COVER_MAP[idx as usize] += 1;
+            }
+        }
+        ExitKind::Ok
+    }
+
+    #[test]
+    fn test_map_feedback_first_cover() {
+        let observer = unsafe { StdMapObserver::new("first_cover", &mut FIRST_COVER_MAP) };
+        let feedback_state = MapFeedbackState::with_observer(&observer).with_first_cover(true);
+        let feedback = MaxMapFeedback::new(&feedback_state, &observer);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            tuple_list!(feedback_state),
+        );
+        let mut fuzzer = StdFuzzer::new(QueueCorpusScheduler::new(), feedback, ());
+        let mut mgr = NopEventManager {};
+        let mut harness = first_cover_harness;
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(observer),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        // The second input hits the first entry more often, and a new one, the third one hits both again
+        let inputs = [vec![0], vec![0, 0, 1], vec![0, 0, 0, 1, 1]].map(BytesInput::new);
+        for (idx, input) in inputs.iter().enumerate() {
+            let (res, corpus_idx) = fuzzer
+                .evaluate_input(&mut state, &mut executor, &mut mgr, input.clone())
+                .unwrap();
+            assert_eq!(res, ExecuteInputResult::Corpus);
+            assert_eq!(corpus_idx, Some(idx));
+        }
+
+        let feedback_state = state
+            .feedback_states()
+            .match_name::<MapFeedbackState<u8>>("first_cover")
+            .unwrap();
+        let name = |idx: usize| inputs[idx].generate_name(idx);
+        assert_eq!(feedback_state.first_cover(0), Some(name(0).as_str()));
+        assert_eq!(feedback_state.first_cover(1), Some(name(1).as_str()));
+        assert_eq!(feedback_state.first_cover(2), None);
+        assert_eq!(feedback_state.first_cover(4), None);
+
+        // Without tracking, nothing is attributed
+        let untracked = MapFeedbackState::<u8>::new("untracked", 4);
+        assert_eq!(untracked.first_cover(0), None);
+    }
 
     #[test]
     fn test_map_is_novel() {
@@ -4,8 +4,29 @@
 pub mod map;
 pub use map::*;
 
+pub mod bloom;
+pub use bloom::BloomFilterFeedback;
+
 pub mod differential;
 pub use differential::DiffFeedback;
+
+pub mod value;
+pub use value::{MaxValueFeedback, MaxValueFeedbackState};
+
+pub mod frontier;
+pub use frontier::{FrontierFeedback, FrontierFeedbackState};
+
+pub mod trail;
+pub use trail::MutationTrailFeedback;
+
+pub mod filtered;
+pub use filtered::{FeedbackPredicate, FilteredFeedback, InputPredicate, ObserverPredicate};
+
+pub mod toggle;
+pub use toggle::{
+    is_feedback_enabled, set_feedback_enabled, DisabledFeedbacksMetadata, ToggleFeedback,
+};
+
 #[cfg(feature = "std")]
 pub mod concolic;
 #[cfg(feature = "std")]
@@ -14,9 +35,24 @@ pub use concolic::ConcolicFeedback;
 #[cfg(feature = "std")]
 pub mod new_hash_feedback;
 #[cfg(feature = "std")]
-pub use new_hash_feedback::NewHashFeedback;
-#[cfg(feature = "std")]
 pub use new_hash_feedback::NewHashFeedbackState;
+#[cfg(feature = "std")]
+pub use new_hash_feedback::{BacktraceHashMetadata, NewHashFeedback};
+
+#[cfg(feature = "std")]
+pub mod output;
+#[cfg(feature = "std")]
+pub use output::{OutputPatternFeedback, OutputPatternMetadata};
+
+#[cfg(feature = "std")]
+pub mod msan;
+#[cfg(feature = "std")]
+pub use msan::{MsanErrorsFeedback, MsanSitesMetadata};
+
+#[cfg(unix)]
+pub mod triage;
+#[cfg(unix)]
+pub use triage::{CrashTriageMetadata, TriageFeedback};
 
 #[cfg(feature = "nautilus")]
 pub mod nautilus;
@@ -114,6 +150,13 @@ where
     fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
         Ok(())
     }
+
+    /// Takes the input of the last run, if this feedback wants to see it executed once more before it counts,
+    /// see [`MapFeedback::with_reexec_verification`]. The fuzzer takes it after each evaluation.
+    #[inline]
+    fn take_reexec_input(&mut self) -> Option<I> {
+        None
+    }
 }
 
 /// [`FeedbackState`] is the data associated with a [`Feedback`] that must persist as part
@@ -267,6 +310,13 @@ where
         self.first.discard_metadata(state, input)?;
         self.second.discard_metadata(state, input)
     }
+
+    #[inline]
+    fn take_reexec_input(&mut self) -> Option<I> {
+        let first = self.first.take_reexec_input();
+        let second = self.second.take_reexec_input();
+        first.or(second)
+    }
 }
 
 /// Logical combination of two feedbacks
@@ -623,6 +673,11 @@ where
     fn discard_metadata(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
         self.first.discard_metadata(state, input)
     }
+
+    #[inline]
+    fn take_reexec_input(&mut self) -> Option<I> {
+        self.first.take_reexec_input()
+    }
 }
 
 impl<A, I, S> Named for NotFeedback<A, I, S>
@@ -654,6 +709,162 @@ where
     }
 }
 
+/// A conditional combination of two feedbacks: the `second` feedback is only evaluated
+/// if the `first` one found nothing interesting, e.g. to run an expensive feedback only for inputs
+/// that a cheap one rejected.
+///
+/// The outcome is the same as for a [`FastOrFeedback`]. The difference is that the metadata hooks
+/// of the `second` feedback are only called for runs it actually evaluated, so that it never
+/// appends or discards metadata for a run it did not see.
+pub struct ConditionalFeedback<A, B, I, S>
+where
+    A: Feedback<I, S>,
+    B: Feedback<I, S>,
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    /// The feedback evaluated first
+    pub first: A,
+    /// The feedback evaluated if the first one is not interesting
+    pub second: B,
+    name: String,
+    second_evaluated: bool,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<A, B, I, S> Debug for ConditionalFeedback<A, B, I, S>
+where
+    A: Feedback<I, S>,
+    B: Feedback<I, S>,
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConditionalFeedback")
+            .field("name", &self.name)
+            .field("first", &self.first)
+            .field("second", &self.second)
+            .finish()
+    }
+}
+
+impl<A, B, I, S> Feedback<I, S> for ConditionalFeedback<A, B, I, S>
+where
+    A: Feedback<I, S>,
+    B: Feedback<I, S>,
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        self.second_evaluated = false;
+        if self
+            .first
+            .is_interesting(state, manager, input, observers, exit_kind)?
+        {
+            return Ok(true);
+        }
+        self.second_evaluated = true;
+        self.second
+            .is_interesting(state, manager, input, observers, exit_kind)
+    }
+
+    #[cfg(feature = "introspection")]
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting_introspection<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        self.second_evaluated = false;
+        if self
+            .first
+            .is_interesting_introspection(state, manager, input, observers, exit_kind)?
+        {
+            return Ok(true);
+        }
+        self.second_evaluated = true;
+        self.second
+            .is_interesting_introspection(state, manager, input, observers, exit_kind)
+    }
+
+    #[inline]
+    fn append_metadata(&mut self, state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        self.first.append_metadata(state, testcase)?;
+        if self.second_evaluated {
+            self.second.append_metadata(state, testcase)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.first.discard_metadata(state, input)?;
+        if self.second_evaluated {
+            self.second.discard_metadata(state, input)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn take_reexec_input(&mut self) -> Option<I> {
+        let first = self.first.take_reexec_input();
+        let second = self.second.take_reexec_input();
+        first.or(second)
+    }
+}
+
+impl<A, B, I, S> Named for ConditionalFeedback<A, B, I, S>
+where
+    A: Feedback<I, S>,
+    B: Feedback<I, S>,
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    #[inline]
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<A, B, I, S> ConditionalFeedback<A, B, I, S>
+where
+    A: Feedback<I, S>,
+    B: Feedback<I, S>,
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    /// Creates a new [`ConditionalFeedback`], evaluating `second` only if `first` is not interesting.
+    pub fn new(first: A, second: B) -> Self {
+        let name = format!("Conditional ({},{})", first.name(), second.name());
+        Self {
+            first,
+            second,
+            name,
+            second_evaluated: false,
+            phantom: PhantomData,
+        }
+    }
+}
+
 /// Variadic macro to create a chain of [`AndFeedback`](EagerAndFeedback)
 #[macro_export]
 macro_rules! feedback_and {
@@ -698,6 +909,18 @@ macro_rules! feedback_or_fast {
     };
 }
 
+/// Variadic macro to create a chain of [`ConditionalFeedback`]s.
+/// Each feedback is only evaluated if all the previous ones found nothing interesting.
+#[macro_export]
+macro_rules! feedback_or_else {
+    ( $last:expr ) => { $last };
+
+    ( $head:expr, $($tail:expr), +) => {
+        // recursive call
+        $crate::feedbacks::ConditionalFeedback::new($head , feedback_or_else!($($tail),+))
+    };
+}
+
 /// Variadic macro to create a [`NotFeedback`]
 #[macro_export]
 macro_rules! feedback_not {
@@ -838,6 +1061,58 @@ impl Default for TimeoutFeedback {
     }
 }
 
+/// An [`OomFeedback`] reports runs that exceeded the memory limit, see [`ExitKind::Oom`].
+/// Unlike a [`CrashFeedback`], it ignores crashes, so out of memory errors can be stored separately.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OomFeedback {}
+
+impl<I, S> Feedback<I, S> for OomFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        if let ExitKind::Oom = exit_kind {
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+impl Named for OomFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        "OomFeedback"
+    }
+}
+
+impl OomFeedback {
+    /// Returns a new [`OomFeedback`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for OomFeedback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Nop feedback that annotates execution time in the new testcase, if any
 /// for this Feedback, the testcase is never interesting (use with an OR).
 /// It decides, if the given [`TimeObserver`] value of a run is interesting.
@@ -913,3 +1188,106 @@ impl TimeFeedback {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::Named},
+        corpus::{InMemoryCorpus, Testcase},
+        events::{EventFirer, NopEventManager},
+        executors::ExitKind,
+        feedbacks::Feedback,
+        inputs::BytesInput,
+        observers::ObserversTuple,
+        state::{HasClientPerfMonitor, StdState},
+        Error,
+    };
+
+    /// A feedback with a fixed result, counting its calls
+    #[derive(Debug, Default)]
+    struct CountingFeedback {
+        interesting: bool,
+        evaluated: usize,
+        appended: usize,
+    }
+
+    impl<S> Feedback<BytesInput, S> for CountingFeedback
+    where
+        S: HasClientPerfMonitor,
+    {
+        fn is_interesting<EM, OT>(
+            &mut self,
+            _state: &mut S,
+            _manager: &mut EM,
+            _input: &BytesInput,
+            _observers: &OT,
+            _exit_kind: &ExitKind,
+        ) -> Result<bool, Error>
+        where
+            EM: EventFirer<BytesInput>,
+            OT: ObserversTuple<BytesInput, S>,
+        {
+            self.evaluated += 1;
+            Ok(self.interesting)
+        }
+
+        fn append_metadata(
+            &mut self,
+            _state: &mut S,
+            _testcase: &mut Testcase<BytesInput>,
+        ) -> Result<(), Error> {
+            self.appended += 1;
+            Ok(())
+        }
+    }
+
+    impl Named for CountingFeedback {
+        fn name(&self) -> &str {
+            "CountingFeedback"
+        }
+    }
+
+    #[test]
+    fn test_conditional_feedback() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+        let input = BytesInput::new(vec![0]);
+        let mut testcase = Testcase::new(input.clone());
+
+        let mut feedback = feedback_or_else!(
+            CountingFeedback {
+                interesting: true,
+                ..CountingFeedback::default()
+            },
+            CountingFeedback::default()
+        );
+
+        // The first feedback is interesting, the second one is skipped, metadata included
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &(), &ExitKind::Ok)
+            .unwrap());
+        feedback.append_metadata(&mut state, &mut testcase).unwrap();
+        assert_eq!(feedback.first.evaluated, 1);
+        assert_eq!(feedback.second.evaluated, 0);
+        assert_eq!(feedback.second.appended, 0);
+
+        // The first feedback found nothing, the second one decides
+        feedback.first.interesting = false;
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &(), &ExitKind::Ok)
+            .unwrap());
+        feedback.second.interesting = true;
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &(), &ExitKind::Ok)
+            .unwrap());
+        feedback.append_metadata(&mut state, &mut testcase).unwrap();
+        assert_eq!(feedback.first.evaluated, 3);
+        assert_eq!(feedback.second.evaluated, 2);
+        assert_eq!(feedback.second.appended, 1);
+    }
+}
@@ -0,0 +1,193 @@
+//! The [`MsanErrorsFeedback`] reports inputs triggering a `MemorySanitizer` error, such as a read of uninitialized memory,
+//! deduplicated by the site of the error. Requires a [`MsanErrorsObserver`].
+
+use hashbrown::HashSet;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::Named,
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::{MsanErrorsObserver, MsanReport, ObserversTuple},
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+
+/// The sites of the `MSan` errors found so far, see [`MsanReport::site`]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MsanSitesMetadata {
+    /// The sites, each one stored once
+    pub sites: HashSet<u64>,
+}
+
+crate::impl_serdeany!(MsanSitesMetadata);
+
+/// A feedback considering an input a solution if its run triggered a `MSan` error at a site not seen before.
+/// The report gets added to the metadata of the solution.
+#[derive(Debug)]
+pub struct MsanErrorsFeedback {
+    observer_name: String,
+    report: Option<MsanReport>,
+}
+
+impl<I, S> Feedback<I, S> for MsanErrorsFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor + HasMetadata,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let report = observers
+            .match_name::<MsanErrorsObserver>(&self.observer_name)
+            .expect("A MsanErrorsFeedback needs a MsanErrorsObserver")
+            .report();
+
+        self.report = None;
+        if let Some(report) = report {
+            if !state.has_metadata::<MsanSitesMetadata>() {
+                state.add_metadata(MsanSitesMetadata::default());
+            }
+            let sites = state.metadata_mut().get_mut::<MsanSitesMetadata>().unwrap();
+            if sites.sites.insert(report.site()) {
+                self.report = Some(report.clone());
+            }
+        }
+        Ok(self.report.is_some())
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(report) = self.report.take() {
+            testcase.add_metadata(report);
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.report = None;
+        Ok(())
+    }
+}
+
+impl Named for MsanErrorsFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        "MsanErrorsFeedback"
+    }
+}
+
+impl MsanErrorsFeedback {
+    /// Creates a new [`MsanErrorsFeedback`], reporting the errors parsed by the `observer`
+    #[must_use]
+    pub fn new(observer: &MsanErrorsObserver) -> Self {
+        Self {
+            observer_name: observer.name().to_string(),
+            report: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::{CommandExecutor, Executor, HasObservers},
+        feedbacks::{Feedback, MsanErrorsFeedback},
+        inputs::BytesInput,
+        observers::{MsanErrorsObserver, MsanReport},
+        state::{HasMetadata, StdState},
+    };
+
+    /// The report of a `MSan` instrumented target reading an uninitialized stack variable
+    const MSAN_REPORT: &str = "==4242==WARNING: MemorySanitizer: use-of-uninitialized-value
+    #0 0x4a2b3c in main /tmp/uninit.c:7:7
+    #1 0x7f1234 in __libc_start_main
+
+  Uninitialized value was created by an allocation of x in the stack frame of function main
+    #0 0x4a2000 in main /tmp/uninit.c:3
+
+SUMMARY: MemorySanitizer: use-of-uninitialized-value /tmp/uninit.c:7:7 in main
+Exiting
+";
+
+    /// The same report, of a run with the target loaded at another address
+    const MSAN_REPORT_RELOCATED: &str =
+        "==4243==WARNING: MemorySanitizer: use-of-uninitialized-value
+    #0 0x55d0b3c in main /tmp/uninit.c:7:7
+    #1 0x7f9234 in __libc_start_main
+
+  Uninitialized value was created by an allocation of x in the stack frame of function main
+    #0 0x55d0000 in main /tmp/uninit.c:3
+
+SUMMARY: MemorySanitizer: use-of-uninitialized-value /tmp/uninit.c:7:7 in main
+Exiting
+";
+
+    #[test]
+    #[cfg(unix)]
+    fn test_msan_errors_feedback() {
+        let observer = MsanErrorsObserver::default();
+        let mut feedback = MsanErrorsFeedback::new(&observer);
+
+        // The harness prints the report, as the instrumented target would, if the input makes it read uninitialized memory
+        let mut executor = CommandExecutor::builder()
+            .program("sh")
+            .arg("-c")
+            .arg(format!(
+                "case $(cat) in *again*) printf '%s' '{}' >&2;; *uninit*) printf '%s' '{}' >&2;; esac; exit 0",
+                MSAN_REPORT_RELOCATED, MSAN_REPORT
+            ))
+            .build(tuple_list!(observer))
+            .unwrap();
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+
+        for (input, interesting) in [("fine", false), ("uninit", true), ("uninit again", false)] {
+            let input = BytesInput::new(input.as_bytes().to_vec());
+            let exit_kind = executor
+                .run_target(&mut (), &mut state, &mut mgr, &input)
+                .unwrap();
+            let observers = executor.observers();
+            // The same site is only reported once, even at another address
+            assert_eq!(
+                feedback
+                    .is_interesting(&mut state, &mut mgr, &input, observers, &exit_kind)
+                    .unwrap(),
+                interesting
+            );
+            if interesting {
+                let mut testcase = Testcase::new(input);
+                feedback.append_metadata(&mut state, &mut testcase).unwrap();
+                let report = testcase.metadata().get::<MsanReport>().unwrap();
+                assert_eq!(report.kind, "use-of-uninitialized-value");
+                assert_eq!(report.frames, vec![0x4a_2b3c, 0x7f_1234]);
+                assert_eq!(
+                    report.summary.as_deref(),
+                    Some("use-of-uninitialized-value /tmp/uninit.c:7:7 in main")
+                );
+                assert_eq!(report.module_offset, None);
+            }
+        }
+    }
+}
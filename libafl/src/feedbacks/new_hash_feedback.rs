@@ -8,12 +8,13 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     bolts::tuples::{MatchName, Named},
+    corpus::Testcase,
     events::EventFirer,
     executors::ExitKind,
     feedbacks::{Feedback, FeedbackState},
     inputs::Input,
     observers::{ObserverWithHashField, ObserversTuple},
-    state::{HasClientPerfMonitor, HasFeedbackStates},
+    state::{HasClientPerfMonitor, HasFeedbackStates, HasMetadata},
     Error,
 };
 
@@ -99,11 +100,23 @@ where
     }
 }
 
+/// The backtrace hash of a solution, added by the [`NewHashFeedback`]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BacktraceHashMetadata {
+    /// The hash of the backtrace, identifying the bucket of the crash
+    pub hash: u64,
+}
+
+crate::impl_serdeany!(BacktraceHashMetadata);
+
 /// A [`NewHashFeedback`] maintains a hashset of already seen stacktraces and considers interesting unseen ones
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct NewHashFeedback<O> {
     feedback_name: String,
     observer_name: String,
+    /// The hash of the last interesting run, to be stored in its testcase
+    #[serde(skip)]
+    last_hash: Option<u64>,
     o_type: PhantomData<O>,
 }
 
@@ -140,14 +153,28 @@ where
                 let res = backtrace_state
                     .update_hash_set(*hash)
                     .expect("Failed to update the hash state");
+                self.last_hash = if res { Some(*hash) } else { None };
                 Ok(res)
             }
             None => {
                 // We get here if the hash was not updated, i.e the first run or if no crash happens
+                self.last_hash = None;
                 Ok(false)
             }
         }
     }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(hash) = self.last_hash.take() {
+            testcase.add_metadata(BacktraceHashMetadata { hash });
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.last_hash = None;
+        Ok(())
+    }
 }
 
 impl<O> Named for NewHashFeedback<O> {
@@ -168,6 +195,7 @@ where
         Self {
             feedback_name: feedback_name.to_string(),
             observer_name: observer_name.to_string(),
+            last_hash: None,
             o_type: PhantomData,
         }
     }
@@ -178,6 +206,7 @@ where
         Self {
             feedback_name: feedback_name.to_string(),
             observer_name: observer.name().to_string(),
+            last_hash: None,
             o_type: PhantomData,
         }
     }
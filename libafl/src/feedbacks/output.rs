@@ -0,0 +1,178 @@
+//! The [`OutputPatternFeedback`] reports inputs whose captured `stderr` matches one of a set of regexes,
+//! such as failed assertions or sanitizer reports, even if the target did not crash.
+//! Requires a [`StdErrObserver`], filled by an executor supporting it, such as the [`crate::executors::CommandExecutor`].
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::Named,
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::{ObserversTuple, StdErrObserver},
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+
+/// The first `stderr` line matching a pattern of the [`OutputPatternFeedback`]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OutputPatternMetadata {
+    /// The pattern that matched
+    pub pattern: String,
+    /// The matching line
+    pub line: String,
+}
+
+crate::impl_serdeany!(OutputPatternMetadata);
+
+/// A feedback considering an input a solution if the captured `stderr` of its run matches one of the patterns.
+/// Use it as (part of) the objective, to catch bugs that only print, e.g. `assertion failed` or `AddressSanitizer`.
+#[derive(Debug)]
+pub struct OutputPatternFeedback {
+    observer_name: String,
+    patterns: Vec<Regex>,
+    matched: Option<OutputPatternMetadata>,
+}
+
+impl<I, S> Feedback<I, S> for OutputPatternFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let stderr = observers
+            .match_name::<StdErrObserver>(&self.observer_name)
+            .expect("An OutputPatternFeedback needs a StdErrObserver")
+            .stderr
+            .as_deref()
+            .unwrap_or_default();
+
+        self.matched = stderr.lines().find_map(|line| {
+            self.patterns
+                .iter()
+                .find(|pattern| pattern.is_match(line))
+                .map(|pattern| OutputPatternMetadata {
+                    pattern: pattern.as_str().to_string(),
+                    line: line.to_string(),
+                })
+        });
+        Ok(self.matched.is_some())
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(matched) = self.matched.take() {
+            testcase.add_metadata(matched);
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.matched = None;
+        Ok(())
+    }
+}
+
+impl Named for OutputPatternFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        "OutputPatternFeedback"
+    }
+}
+
+impl OutputPatternFeedback {
+    /// Creates a new [`OutputPatternFeedback`], matching the `stderr` captured by the `observer` against the `patterns`
+    pub fn new<P>(observer: &StdErrObserver, patterns: &[P]) -> Result<Self, Error>
+    where
+        P: AsRef<str>,
+    {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern.as_ref()).map_err(|err| {
+                    Error::IllegalArgument(format!(
+                        "Invalid output pattern {}: {}",
+                        pattern.as_ref(),
+                        err
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            observer_name: observer.name().to_string(),
+            patterns,
+            matched: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::{CommandExecutor, Executor, ExitKind, HasObservers},
+        feedbacks::{output::OutputPatternMetadata, Feedback, OutputPatternFeedback},
+        inputs::BytesInput,
+        observers::StdErrObserver,
+        state::StdState,
+    };
+
+    #[test]
+    #[cfg(unix)]
+    fn test_output_pattern_feedback() {
+        let observer = StdErrObserver::new("StdErrObserver".to_string());
+        let mut feedback =
+            OutputPatternFeedback::new(&observer, &["assertion failed", "AddressSanitizer"])
+                .unwrap();
+
+        // The harness prints an assertion message, but exits normally
+        let mut executor = CommandExecutor::builder()
+            .program("sh")
+            .arg("-c")
+            .arg("echo starting >&2; echo 'assertion failed: len > 0' >&2")
+            .build(tuple_list!(observer))
+            .unwrap();
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+        let input = BytesInput::new(vec![]);
+
+        let exit_kind = executor
+            .run_target(&mut (), &mut state, &mut mgr, &input)
+            .unwrap();
+        assert_eq!(exit_kind, ExitKind::Ok);
+
+        let observers = executor.observers();
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, observers, &exit_kind)
+            .unwrap());
+        assert_eq!(
+            feedback.matched,
+            Some(OutputPatternMetadata {
+                pattern: "assertion failed".to_string(),
+                line: "assertion failed: len > 0".to_string(),
+            })
+        );
+    }
+}
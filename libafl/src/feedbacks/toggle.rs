@@ -0,0 +1,236 @@
+//! The [`ToggleFeedback`] lets a feedback branch be switched off and on at runtime, through the state,
+//! e.g. to explore with a coverage feedback only during some phases of the campaign.
+
+use alloc::string::{String, ToString};
+use hashbrown::HashSet;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::Named,
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::ObserversTuple,
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+
+/// A state metadata holding the names of the disabled feedbacks, see [`ToggleFeedback`]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DisabledFeedbacksMetadata {
+    names: HashSet<String>,
+}
+
+crate::impl_serdeany!(DisabledFeedbacksMetadata);
+
+impl DisabledFeedbacksMetadata {
+    /// Creates a new [`DisabledFeedbacksMetadata`], with all feedbacks enabled
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if the feedback called `name` is enabled
+    #[must_use]
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.names.contains(name)
+    }
+
+    /// Enables or disables the feedback called `name`
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+        if enabled {
+            self.names.remove(name);
+        } else {
+            self.names.insert(name.to_string());
+        }
+    }
+}
+
+/// Enables or disables the [`ToggleFeedback`] wrapping the feedback called `name`
+pub fn set_feedback_enabled<S>(state: &mut S, name: &str, enabled: bool)
+where
+    S: HasMetadata,
+{
+    if state
+        .metadata()
+        .get::<DisabledFeedbacksMetadata>()
+        .is_none()
+    {
+        state.add_metadata(DisabledFeedbacksMetadata::new());
+    }
+    state
+        .metadata_mut()
+        .get_mut::<DisabledFeedbacksMetadata>()
+        .unwrap()
+        .set_enabled(name, enabled);
+}
+
+/// Returns `true` unless the feedback called `name` got disabled with [`set_feedback_enabled`]
+pub fn is_feedback_enabled<S>(state: &S, name: &str) -> bool
+where
+    S: HasMetadata,
+{
+    state
+        .metadata()
+        .get::<DisabledFeedbacksMetadata>()
+        .map_or(true, |meta| meta.is_enabled(name))
+}
+
+/// A feedback that can be disabled at runtime, by the name of the wrapped feedback, using [`set_feedback_enabled`].
+/// While disabled, runs are not interesting to it, and the wrapped feedback does not run at all,
+/// so it has no side effects: e.g. the history of a map feedback is not updated.
+#[derive(Debug)]
+pub struct ToggleFeedback<F> {
+    feedback: F,
+    /// If the wrapped feedback ran for the current input, so its metadata may be appended
+    active: bool,
+}
+
+impl<F, I, S> Feedback<I, S> for ToggleFeedback<F>
+where
+    F: Feedback<I, S>,
+    I: Input,
+    S: HasClientPerfMonitor + HasMetadata,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        self.active = is_feedback_enabled(state, self.feedback.name());
+        if !self.active {
+            return Ok(false);
+        }
+        self.feedback
+            .is_interesting(state, manager, input, observers, exit_kind)
+    }
+
+    #[inline]
+    fn append_metadata(&mut self, state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if self.active {
+            self.feedback.append_metadata(state, testcase)
+        } else {
+            Ok(())
+        }
+    }
+
+    #[inline]
+    fn discard_metadata(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        if self.active {
+            self.feedback.discard_metadata(state, input)
+        } else {
+            Ok(())
+        }
+    }
+
+    #[inline]
+    fn take_reexec_input(&mut self) -> Option<I> {
+        self.feedback.take_reexec_input()
+    }
+}
+
+impl<F> Named for ToggleFeedback<F>
+where
+    F: Named,
+{
+    #[inline]
+    fn name(&self) -> &str {
+        self.feedback.name()
+    }
+}
+
+impl<F> ToggleFeedback<F>
+where
+    F: Named,
+{
+    /// Creates a new [`ToggleFeedback`], enabled unless disabled in the state
+    #[must_use]
+    pub fn new(feedback: F) -> Self {
+        Self {
+            feedback,
+            active: false,
+        }
+    }
+
+    /// The wrapped feedback
+    #[must_use]
+    pub fn inner(&self) -> &F {
+        &self.feedback
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{
+            rands::StdRand,
+            tuples::{tuple_list, MatchName, Named},
+            AsMutSlice,
+        },
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{
+            set_feedback_enabled, Feedback, MapFeedbackState, MaxMapFeedback, ToggleFeedback,
+        },
+        inputs::BytesInput,
+        observers::StdMapObserver,
+        state::{HasFeedbackStates, StdState},
+    };
+
+    #[test]
+    fn test_toggle_feedback() {
+        let observer = StdMapObserver::new_owned("map", vec![0_u8; 64]);
+        let feedback_state = MapFeedbackState::with_observer(&observer);
+        let mut feedback = ToggleFeedback::new(MaxMapFeedback::<BytesInput, _, _, _>::new(
+            &feedback_state,
+            &observer,
+        ));
+        let mut observers = tuple_list!(observer);
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            tuple_list!(feedback_state),
+        );
+        let mut mgr = NopEventManager {};
+        let input = BytesInput::new(vec![]);
+        observers
+            .match_name_mut::<StdMapObserver<u8>>("map")
+            .unwrap()
+            .as_mut_slice()[3] = 1;
+
+        // Disabled, new coverage is not interesting, and does not reach the history
+        let name = feedback.name().to_string();
+        set_feedback_enabled(&mut state, &name, false);
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+        let history = &state
+            .feedback_states()
+            .match_name::<MapFeedbackState<u8>>("map")
+            .unwrap()
+            .history_map;
+        assert!(history.iter().all(|&entry| entry == 0));
+
+        // Enabled again, the same coverage is still new
+        set_feedback_enabled(&mut state, &name, true);
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+    }
+}
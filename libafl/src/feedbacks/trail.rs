@@ -0,0 +1,141 @@
+//! The [`MutationTrailFeedback`] stores the mutations that produced an input in its testcase,
+//! to reproduce a solution step by step.
+//! It never considers an input interesting by itself, combine it with the other feedbacks, and with the objective,
+//! using a non-fast `feedback_or`, so the metadata gets appended.
+//! Inputs not produced by the mutator, such as imported ones, get no trail.
+
+use crate::{
+    bolts::tuples::Named,
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    mutators::MutationTrailMetadata,
+    observers::ObserversTuple,
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+
+/// A feedback copying the [`MutationTrailMetadata`] of the last mutation from the state into new testcases.
+/// Requires a `MetadataScheduledMutator` with enabled trail.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MutationTrailFeedback;
+
+impl<I, S> Feedback<I, S> for MutationTrailFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor + HasMetadata,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        Ok(false)
+    }
+
+    fn append_metadata(&mut self, state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(trail) = state.metadata().get::<MutationTrailMetadata>() {
+            testcase.add_metadata(trail.clone());
+        }
+        Ok(())
+    }
+}
+
+impl Named for MutationTrailFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        "MutationTrailFeedback"
+    }
+}
+
+impl MutationTrailFeedback {
+    /// Creates a new [`MutationTrailFeedback`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{InMemoryCorpus, Testcase},
+        feedbacks::{Feedback, MutationTrailFeedback},
+        inputs::BytesInput,
+        mutators::{
+            BitFlipMutator, BytesDeleteMutator, MetadataScheduledMutator, MutationTrailMetadata,
+            Mutator, StdScheduledMutator,
+        },
+        state::{HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_mutation_trail() {
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut input = BytesInput::new(b"trail".to_vec());
+
+        let mut mutator = MetadataScheduledMutator::new(StdScheduledMutator::new(tuple_list!(
+            BitFlipMutator::new(),
+            BytesDeleteMutator::new()
+        )));
+        mutator.mutate(&mut state, &mut input, 0).unwrap();
+        assert!(state.metadata().get::<MutationTrailMetadata>().is_none());
+
+        mutator.set_trail(true);
+        mutator.mutate(&mut state, &mut input, 0).unwrap();
+        let trail = state
+            .metadata()
+            .get::<MutationTrailMetadata>()
+            .unwrap()
+            .trail
+            .clone();
+        // The mutator stacks a power of two of mutations
+        assert!(trail.len().is_power_of_two() && trail.len() >= 2);
+        assert!(trail
+            .iter()
+            .all(|entry| entry.mutation == "BitFlipMutator"
+                || entry.mutation == "BytesDeleteMutator"));
+        assert!(trail.iter().any(|entry| entry.mutated));
+
+        let mut testcase = Testcase::new(input);
+        MutationTrailFeedback::new()
+            .append_metadata(&mut state, &mut testcase)
+            .unwrap();
+        assert_eq!(
+            testcase
+                .metadata()
+                .get::<MutationTrailMetadata>()
+                .unwrap()
+                .trail,
+            trail
+        );
+
+        // Once the mutated input got executed, the trail is gone
+        mutator.post_exec(&mut state, 0, None).unwrap();
+        assert!(state.metadata().get::<MutationTrailMetadata>().is_none());
+        let mut unrelated = Testcase::new(BytesInput::new(b"imported".to_vec()));
+        MutationTrailFeedback::new()
+            .append_metadata(&mut state, &mut unrelated)
+            .unwrap();
+        assert!(unrelated
+            .metadata()
+            .get::<MutationTrailMetadata>()
+            .is_none());
+    }
+}
@@ -0,0 +1,397 @@
+//! Crash triage: the in-process crash handler records the signal and the faulting address of a crash,
+//! and the [`TriageFeedback`] classifies its exploitability with a simple ruleset, similar to `!exploitable`.
+//! The classification is only a first hint for manual triage, it may well be wrong.
+
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display, Formatter};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{os::unix_signals::Signal, tuples::Named},
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::ObserversTuple,
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+
+/// Accesses below this address are considered null pointer dereferences
+pub const NULL_PAGE_LIMIT: usize = 0x1_0000;
+
+/// Accesses within this distance of the stack pointer are considered stack accesses
+pub const STACK_DISTANCE: usize = 0x10_0000;
+
+/// The crash of the last execution, recorded by the crash handler
+static mut LAST_CRASH_CONTEXT: Option<CrashContext> = None;
+
+/// The processor state at a crash, as far as it is relevant for the triage
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrashContext {
+    /// The signal number
+    pub signal: i32,
+    /// The faulting address, as reported by the kernel
+    pub fault_address: usize,
+    /// The program counter at the crash, if known on this platform
+    pub pc: Option<usize>,
+    /// The stack pointer at the crash, if known on this platform
+    pub sp: Option<usize>,
+    /// `true` if the faulting access was a write, if known on this platform
+    pub is_write: Option<bool>,
+}
+
+impl CrashContext {
+    /// Extracts the [`CrashContext`] from the arguments of a signal handler.
+    ///
+    /// # Safety
+    /// The `info` and `context` must be the ones passed to the signal handler for `signal`.
+    #[allow(
+        unused_variables,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub unsafe fn from_signal(
+        signal: Signal,
+        info: &libc::siginfo_t,
+        context: &crate::bolts::os::unix_signals::ucontext_t,
+    ) -> Self {
+        #[cfg(target_os = "android")]
+        let fault_address = ((info._pad[0] as i64) | ((info._pad[1] as i64) << 32)) as usize;
+        #[cfg(not(target_os = "android"))]
+        let fault_address = info.si_addr() as usize;
+
+        #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+        let (pc, sp, is_write) = {
+            let gregs = &context.uc_mcontext.gregs;
+            (
+                Some(gregs[libc::REG_RIP as usize] as usize),
+                Some(gregs[libc::REG_RSP as usize] as usize),
+                // Bit 1 of the page fault error code is set for writes
+                Some(gregs[libc::REG_ERR as usize] & 2 != 0),
+            )
+        };
+        #[cfg(all(
+            any(target_os = "linux", target_os = "android"),
+            target_arch = "aarch64"
+        ))]
+        let (pc, sp, is_write) = (
+            Some(context.uc_mcontext.pc as usize),
+            Some(context.uc_mcontext.sp as usize),
+            None,
+        );
+        #[cfg(not(any(
+            all(target_os = "linux", target_arch = "x86_64"),
+            all(
+                any(target_os = "linux", target_os = "android"),
+                target_arch = "aarch64"
+            )
+        )))]
+        let (pc, sp, is_write) = (None, None, None);
+
+        Self {
+            signal: signal as i32,
+            fault_address,
+            pc,
+            sp,
+            is_write,
+        }
+    }
+}
+
+/// Records the crash of the current execution, to be picked up by the [`TriageFeedback`].
+/// Called by the crash handler of the `InProcessExecutor`.
+pub fn record_crash_context(context: CrashContext) {
+    unsafe {
+        LAST_CRASH_CONTEXT = Some(context);
+    }
+}
+
+/// Takes the crash recorded by [`record_crash_context`], if any
+pub fn take_crash_context() -> Option<CrashContext> {
+    unsafe { LAST_CRASH_CONTEXT.take() }
+}
+
+/// The kind of a crash
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashKind {
+    /// An access to the null page
+    NullDeref,
+    /// The program counter points to the faulting address, i.e. a jump to a bad address
+    PcControl,
+    /// A write close to the stack pointer
+    StackWrite,
+    /// A write outside the stack, usually to the heap
+    HeapWrite,
+    /// A read close to the stack pointer
+    StackRead,
+    /// A read outside the stack, usually from the heap
+    HeapRead,
+    /// An illegal instruction got executed
+    IllegalInstruction,
+    /// The target aborted, e.g. on a failed assertion or a sanitizer report
+    Abort,
+    /// An arithmetic error, such as a division by zero
+    Arithmetic,
+    /// None of the rules matched
+    Unknown,
+}
+
+/// The exploitability of a crash, as rated by [`classify_crash`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Exploitability {
+    /// Most likely not exploitable
+    ProbablyNotExploitable,
+    /// Not enough information to decide
+    Unknown,
+    /// Possibly exploitable, worth a closer look
+    ProbablyExploitable,
+    /// Very likely exploitable
+    Exploitable,
+}
+
+impl Display for Exploitability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Exploitability::ProbablyNotExploitable => write!(f, "PROBABLY_NOT_EXPLOITABLE"),
+            Exploitability::Unknown => write!(f, "UNKNOWN"),
+            Exploitability::ProbablyExploitable => write!(f, "PROBABLY_EXPLOITABLE"),
+            Exploitability::Exploitable => write!(f, "EXPLOITABLE"),
+        }
+    }
+}
+
+/// Classifies a crash, following the rules of `!exploitable`:
+/// null pointer dereferences are rarely exploitable, while bad jumps and writes to non-null addresses usually are.
+#[must_use]
+pub fn classify_crash(context: &CrashContext) -> (CrashKind, Exploitability) {
+    let signal = match Signal::try_from(context.signal) {
+        Ok(signal) => signal,
+        Err(_) => return (CrashKind::Unknown, Exploitability::Unknown),
+    };
+    match signal {
+        Signal::SigSegmentationFault | Signal::SigBus => {
+            if context.pc == Some(context.fault_address) {
+                (CrashKind::PcControl, Exploitability::Exploitable)
+            } else if context.fault_address < NULL_PAGE_LIMIT {
+                (CrashKind::NullDeref, Exploitability::ProbablyNotExploitable)
+            } else {
+                let on_stack = context.sp.map_or(false, |sp| {
+                    let distance = if sp > context.fault_address {
+                        sp - context.fault_address
+                    } else {
+                        context.fault_address - sp
+                    };
+                    distance < STACK_DISTANCE
+                });
+                match (context.is_write, on_stack) {
+                    (Some(true), true) => (CrashKind::StackWrite, Exploitability::Exploitable),
+                    (Some(true), false) => (CrashKind::HeapWrite, Exploitability::Exploitable),
+                    (Some(false), true) => (CrashKind::StackRead, Exploitability::Unknown),
+                    (Some(false), false) => {
+                        (CrashKind::HeapRead, Exploitability::ProbablyExploitable)
+                    }
+                    (None, _) => (CrashKind::Unknown, Exploitability::ProbablyExploitable),
+                }
+            }
+        }
+        Signal::SigIllegalInstruction => (
+            CrashKind::IllegalInstruction,
+            Exploitability::ProbablyExploitable,
+        ),
+        Signal::SigAbort => (CrashKind::Abort, Exploitability::Unknown),
+        Signal::SigFloatingPointException => (
+            CrashKind::Arithmetic,
+            Exploitability::ProbablyNotExploitable,
+        ),
+        _ => (CrashKind::Unknown, Exploitability::Unknown),
+    }
+}
+
+/// The triage of a solution, added by the [`TriageFeedback`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CrashTriageMetadata {
+    /// The recorded crash
+    pub context: CrashContext,
+    /// The kind of the crash
+    pub kind: CrashKind,
+    /// The rated exploitability of the crash
+    pub exploitability: Exploitability,
+}
+
+crate::impl_serdeany!(CrashTriageMetadata);
+
+impl CrashTriageMetadata {
+    /// Classifies the given crash
+    #[must_use]
+    pub fn new(context: CrashContext) -> Self {
+        let (kind, exploitability) = classify_crash(&context);
+        Self {
+            context,
+            kind,
+            exploitability,
+        }
+    }
+}
+
+/// Nop feedback that adds a [`CrashTriageMetadata`] to new solutions, if the crash handler recorded a crash.
+/// For this Feedback, the testcase is never interesting (use with an OR, next to a `CrashFeedback`).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TriageFeedback {
+    name: String,
+    triage: Option<CrashTriageMetadata>,
+}
+
+impl<I, S> Feedback<I, S> for TriageFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let context = take_crash_context();
+        self.triage = match exit_kind {
+            ExitKind::Crash => context.map(CrashTriageMetadata::new),
+            _ => None,
+        };
+        Ok(false)
+    }
+
+    /// Append the triage of the crash to the new solution
+    #[inline]
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(triage) = self.triage.take() {
+            testcase.add_metadata(triage);
+        }
+        Ok(())
+    }
+
+    /// Discard the stored triage in case that the testcase is not added
+    #[inline]
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.triage = None;
+        Ok(())
+    }
+}
+
+impl Named for TriageFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl TriageFeedback {
+    /// Creates a new [`TriageFeedback`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            name: "TriageFeedback".to_string(),
+            triage: None,
+        }
+    }
+}
+
+impl Default for TriageFeedback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{os::unix_signals::Signal, rands::StdRand},
+        corpus::{InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{
+            triage::{
+                classify_crash, record_crash_context, CrashContext, CrashKind, CrashTriageMetadata,
+                Exploitability, TriageFeedback,
+            },
+            Feedback,
+        },
+        inputs::BytesInput,
+        state::{HasMetadata, StdState},
+    };
+
+    fn segv(fault_address: usize, is_write: bool) -> CrashContext {
+        CrashContext {
+            signal: Signal::SigSegmentationFault as i32,
+            fault_address,
+            pc: Some(0x5555_0000_1000),
+            sp: Some(0x7ffc_0000_0000),
+            is_write: Some(is_write),
+        }
+    }
+
+    #[test]
+    fn test_classify_crash() {
+        assert_eq!(
+            classify_crash(&segv(0x8, false)),
+            (CrashKind::NullDeref, Exploitability::ProbablyNotExploitable)
+        );
+        assert_eq!(
+            classify_crash(&segv(0x8, true)).1,
+            Exploitability::ProbablyNotExploitable
+        );
+        assert_eq!(
+            classify_crash(&segv(0x4141_4141_4141, true)),
+            (CrashKind::HeapWrite, Exploitability::Exploitable)
+        );
+        assert_eq!(
+            classify_crash(&segv(0x7ffc_0000_0040, true)),
+            (CrashKind::StackWrite, Exploitability::Exploitable)
+        );
+
+        let mut jump = segv(0x4141_4141_4141, false);
+        jump.pc = Some(0x4141_4141_4141);
+        assert_eq!(
+            classify_crash(&jump),
+            (CrashKind::PcControl, Exploitability::Exploitable)
+        );
+    }
+
+    #[test]
+    fn test_triage_feedback() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+        let mut feedback = TriageFeedback::new();
+        let input = BytesInput::new(vec![0]);
+
+        record_crash_context(segv(0x0, false));
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &(), &ExitKind::Crash)
+            .unwrap());
+        let mut testcase = Testcase::new(input.clone());
+        feedback.append_metadata(&mut state, &mut testcase).unwrap();
+        let triage = testcase.metadata().get::<CrashTriageMetadata>().unwrap();
+        assert_eq!(triage.kind, CrashKind::NullDeref);
+
+        // No recorded crash, no triage
+        feedback
+            .is_interesting(&mut state, &mut mgr, &input, &(), &ExitKind::Crash)
+            .unwrap();
+        let mut testcase = Testcase::new(input);
+        feedback.append_metadata(&mut state, &mut testcase).unwrap();
+        assert!(!testcase.has_metadata::<CrashTriageMetadata>());
+    }
+}
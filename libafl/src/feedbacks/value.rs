@@ -0,0 +1,205 @@
+//! The [`MaxValueFeedback`] rewards inputs pushing a scalar reported by the harness to a new maximum,
+//! e.g. the parse depth of a recursive-descent parser.
+
+use alloc::string::{String, ToString};
+use core::{fmt::Debug, marker::PhantomData};
+use num_traits::PrimInt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::{MatchName, Named},
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, FeedbackState},
+    inputs::Input,
+    observers::{MaxValueObserver, ObserversTuple},
+    state::{HasClientPerfMonitor, HasFeedbackStates},
+    Error,
+};
+
+/// The state of a [`MaxValueFeedback`], holding the maximum value seen so far
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound = "T: serde::de::DeserializeOwned")]
+pub struct MaxValueFeedbackState<T>
+where
+    T: PrimInt + Serialize + Debug + 'static,
+{
+    /// The maximum value seen so far
+    pub max: T,
+    /// Name identifier of this instance
+    pub name: String,
+}
+
+impl<T> FeedbackState for MaxValueFeedbackState<T>
+where
+    T: PrimInt + Serialize + serde::de::DeserializeOwned + Debug + 'static,
+{
+    fn reset(&mut self) -> Result<(), Error> {
+        self.max = T::zero();
+        Ok(())
+    }
+}
+
+impl<T> Named for MaxValueFeedbackState<T>
+where
+    T: PrimInt + Serialize + Debug + 'static,
+{
+    #[inline]
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl<T> MaxValueFeedbackState<T>
+where
+    T: PrimInt + Serialize + Debug + 'static,
+{
+    /// Creates a new [`MaxValueFeedbackState`]
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            max: T::zero(),
+            name: name.to_string(),
+        }
+    }
+
+    /// Creates a new [`MaxValueFeedbackState`] for the given observer
+    #[must_use]
+    pub fn with_observer(observer: &MaxValueObserver<T>) -> Self {
+        Self::new(observer.name())
+    }
+}
+
+/// A feedback marking an input as interesting if the value of its [`MaxValueObserver`] is a new maximum
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MaxValueFeedback<T> {
+    name: String,
+    phantom: PhantomData<T>,
+}
+
+impl<I, S, T> Feedback<I, S> for MaxValueFeedback<T>
+where
+    I: Input,
+    S: HasFeedbackStates + HasClientPerfMonitor,
+    T: PrimInt + Serialize + serde::de::DeserializeOwned + Debug + 'static,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let value = observers
+            .match_name::<MaxValueObserver<T>>(&self.name)
+            .ok_or_else(|| Error::KeyNotFound("MaxValueObserver not found".to_string()))?
+            .value();
+        let feedback_state = state
+            .feedback_states_mut()
+            .match_name_mut::<MaxValueFeedbackState<T>>(&self.name)
+            .ok_or_else(|| Error::KeyNotFound("MaxValueFeedbackState not found".to_string()))?;
+        if value > feedback_state.max {
+            feedback_state.max = value;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+impl<T> Named for MaxValueFeedback<T> {
+    #[inline]
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+}
+
+impl<T> MaxValueFeedback<T>
+where
+    T: PrimInt + Serialize + Debug + 'static,
+{
+    /// Creates a new [`MaxValueFeedback`] for the [`MaxValueObserver`] and [`MaxValueFeedbackState`] with the given name
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates a new [`MaxValueFeedback`] for the given observer
+    #[must_use]
+    pub fn with_observer(observer: &MaxValueObserver<T>) -> Self {
+        Self::new(observer.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{Feedback, MaxValueFeedback, MaxValueFeedbackState},
+        inputs::{BytesInput, HasBytesVec},
+        observers::{MaxValueObserver, ObserversTuple},
+        state::StdState,
+    };
+
+    static mut DEPTH: u32 = 0;
+
+    /// A harness reporting the nesting depth of the opening brackets at the start of the input
+    fn harness(input: &BytesInput) -> ExitKind {
+        for (depth, byte) in input.bytes().iter().enumerate() {
+            if *byte != b'(' {
+                break;
+            }
+            unsafe { DEPTH = u32::try_from(depth + 1).unwrap() };
+        }
+        ExitKind::Ok
+    }
+
+    #[test]
+    fn test_max_value_feedback() {
+        let observer = unsafe { MaxValueObserver::from_ptr("depth", &mut DEPTH) };
+        let mut feedback = MaxValueFeedback::with_observer(&observer);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            tuple_list!(MaxValueFeedbackState::with_observer(&observer)),
+        );
+        let mut observers = tuple_list!(observer);
+        let mut mgr = NopEventManager {};
+
+        let runs: [(&[u8], bool); 6] = [
+            (b"(x", true),
+            (b"((x", true),
+            (b"((y", false),
+            (b"x(((((", false),
+            (b"((((x", true),
+            (b"(x", false),
+        ];
+        for (bytes, new_max) in runs {
+            let input = BytesInput::new(bytes.to_vec());
+            observers.pre_exec_all(&mut state, &input).unwrap();
+            let exit_kind = harness(&input);
+            observers
+                .post_exec_all(&mut state, &input, &exit_kind)
+                .unwrap();
+            assert_eq!(
+                feedback
+                    .is_interesting(&mut state, &mut mgr, &input, &observers, &exit_kind)
+                    .unwrap(),
+                new_max
+            );
+        }
+    }
+}
@@ -2,16 +2,16 @@
 
 use crate::{
     bolts::current_time,
-    corpus::{Corpus, CorpusScheduler, Testcase},
-    events::{Event, EventConfig, EventFirer, EventManager, ProgressReporter},
+    corpus::{Corpus, CorpusScheduler, ParentMetadata, Testcase},
+    events::{Event, EventConfig, EventFirer, EventManager, EventRestarter, ProgressReporter},
     executors::{Executor, ExitKind, HasObservers},
     feedbacks::Feedback,
     inputs::Input,
-    mark_feature_time,
+    libafl_log, mark_feature_time,
     observers::ObserversTuple,
     stages::StagesTuple,
     start_timer,
-    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasSolutions},
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata, HasSolutions},
     Error,
 };
 
@@ -24,6 +24,10 @@ use core::{marker::PhantomData, time::Duration};
 /// Send a monitor update all 15 (or more) seconds
 const STATS_TIMEOUT_DEFAULT: Duration = Duration::from_secs(15);
 
+/// How often evaluating an input executes it again, for a feedback verifying its new coverage.
+/// The re-execution is the verification, so there is no need for more.
+const MAX_REEXECUTIONS: usize = 1;
+
 /// Holds a scheduler
 pub trait HasCorpusScheduler<CS, I, S>
 where
@@ -83,6 +87,13 @@ where
     ) -> Result<(ExecuteInputResult, Option<usize>), Error>
     where
         EM: EventFirer<I>;
+
+    /// Takes the input the last [`ExecutionProcessor::process_execution`] wants to see executed once more,
+    /// see [`crate::feedbacks::MapFeedback::with_reexec_verification`].
+    /// Each processed execution replaces it, so an input nobody executes again does not stay behind.
+    fn take_reexec_input(&mut self) -> Option<I> {
+        None
+    }
 }
 
 /// Evaluate an input modifying the state of the fuzzer
@@ -218,6 +229,131 @@ where
 
         Ok(ret)
     }
+
+    /// Fuzz until `max_duration` passed, checked between two fuzzing iterations,
+    /// then report the final stats and return [`Error::ShuttingDown`].
+    /// This limits the whole campaign, unlike the timeout of a single execution.
+    /// The on-disk corpora write each entry as soon as it gets added, so no testcase is lost.
+    ///
+    /// Before returning, the loop tells the `manager` that this client is exiting, see [`EventRestarter::send_exiting`].
+    /// Inside a [`crate::bolts::launcher::Launcher`], this stops the client for good, and with it the campaign.
+    /// The duration counts from this call, so a client respawned after a crash starts over.
+    fn fuzz_loop_for_duration(
+        &mut self,
+        stages: &mut ST,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        max_duration: Duration,
+    ) -> Result<usize, Error>
+    where
+        EM: EventRestarter<S>,
+    {
+        let start = current_time();
+        let mut last = start;
+        let monitor_timeout = STATS_TIMEOUT_DEFAULT;
+
+        while current_time().checked_sub(start).unwrap_or_default() < max_duration {
+            self.fuzz_one(stages, executor, state, manager)?;
+            last = manager.maybe_report_progress(state, last, monitor_timeout)?;
+        }
+
+        // A zero timeout forces the final report
+        manager.maybe_report_progress(state, Duration::from_secs(0), Duration::from_secs(0))?;
+        manager.send_exiting()?;
+        Err(Error::ShuttingDown)
+    }
+
+    /// Fuzz forever (or until stopped), calling `on_interval` every `interval` executions.
+    /// The callback runs between two fuzzing iterations, it should return quickly,
+    /// as fuzzing is paused for its whole duration.
+    fn fuzz_loop_with_interval<CB>(
+        &mut self,
+        stages: &mut ST,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        interval: u64,
+        mut on_interval: CB,
+    ) -> Result<usize, Error>
+    where
+        S: HasCorpus<I>,
+        CB: FnMut(&S, &S::Corpus),
+    {
+        if interval == 0 {
+            return Err(Error::IllegalArgument(
+                "The execution interval cannot be 0!".to_string(),
+            ));
+        }
+
+        let mut last = current_time();
+        let monitor_timeout = STATS_TIMEOUT_DEFAULT;
+        let mut intervals = *state.executions() as u64 / interval;
+        loop {
+            self.fuzz_one(stages, executor, state, manager)?;
+            last = manager.maybe_report_progress(state, last, monitor_timeout)?;
+            intervals = call_on_interval(state, interval, intervals, &mut on_interval);
+        }
+    }
+
+    /// Fuzz for n iterations, calling `on_interval` every `interval` executions.
+    /// Returns the index of the last fuzzed corpus item.
+    /// The callback runs between two fuzzing iterations, it should return quickly,
+    /// as fuzzing is paused for its whole duration.
+    #[allow(clippy::too_many_arguments)]
+    fn fuzz_loop_for_with_interval<CB>(
+        &mut self,
+        stages: &mut ST,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        iters: u64,
+        interval: u64,
+        mut on_interval: CB,
+    ) -> Result<usize, Error>
+    where
+        S: HasCorpus<I>,
+        CB: FnMut(&S, &S::Corpus),
+    {
+        if iters == 0 {
+            return Err(Error::IllegalArgument(
+                "Cannot fuzz for 0 iterations!".to_string(),
+            ));
+        }
+        if interval == 0 {
+            return Err(Error::IllegalArgument(
+                "The execution interval cannot be 0!".to_string(),
+            ));
+        }
+
+        let mut ret = 0;
+        let mut last = current_time();
+        let monitor_timeout = STATS_TIMEOUT_DEFAULT;
+        let mut intervals = *state.executions() as u64 / interval;
+
+        for _ in 0..iters {
+            ret = self.fuzz_one(stages, executor, state, manager)?;
+            last = manager.maybe_report_progress(state, last, monitor_timeout)?;
+            intervals = call_on_interval(state, interval, intervals, &mut on_interval);
+        }
+
+        Ok(ret)
+    }
+}
+
+/// Calls `on_interval` once for each `interval` of executions passed since the `intervals` already seen,
+/// returning the new amount of intervals seen.
+fn call_on_interval<CB, I, S>(state: &S, interval: u64, intervals: u64, on_interval: &mut CB) -> u64
+where
+    CB: FnMut(&S, &S::Corpus),
+    I: Input,
+    S: HasCorpus<I> + HasExecutions,
+{
+    let current = *state.executions() as u64 / interval;
+    for _ in intervals..current {
+        on_interval(state, state.corpus());
+    }
+    current
 }
 
 /// The corpus this input should be added to
@@ -244,6 +380,16 @@ where
     scheduler: CS,
     feedback: F,
     objective: OF,
+    stop_on_first_solution: bool,
+    first_solution: Option<usize>,
+    restart_after_execs: Option<u64>,
+    start_executions: Option<usize>,
+    /// The input a feedback wants to see again after the last processed execution
+    reexec_input: Option<I>,
+    /// If solutions get a [`ParentMetadata`], see [`StdFuzzer::record_parents`]
+    record_parents: bool,
+    /// The corpus entry the stages are fuzzing, the parent of the solutions they find
+    parent_idx: Option<usize>,
     phantom: PhantomData<(I, OT, S)>,
 }
 
@@ -321,6 +467,7 @@ where
         EM: EventFirer<I>,
     {
         let mut res = ExecuteInputResult::None;
+        self.reexec_input = None;
 
         #[cfg(not(feature = "introspection"))]
         let is_solution = self
@@ -345,8 +492,12 @@ where
                 .feedback_mut()
                 .is_interesting_introspection(state, manager, &input, observers, exit_kind)?;
 
+            // A feedback verifying new coverage wants to see this input again, unless it is kept anyway
+            let reexec_input = self.feedback_mut().take_reexec_input();
             if is_corpus {
                 res = ExecuteInputResult::Corpus;
+            } else {
+                self.reexec_input = reexec_input;
             }
         }
 
@@ -394,8 +545,17 @@ where
 
                 // The input is a solution, add it to the respective corpus
                 let mut testcase = Testcase::with_executions(input, *state.executions());
+                if let Some(parent_idx) = self.parent_idx {
+                    let parent_name = state
+                        .corpus()
+                        .get(parent_idx)?
+                        .borrow_mut()
+                        .load_input()?
+                        .generate_name(parent_idx);
+                    testcase.add_metadata(ParentMetadata { parent_name });
+                }
                 self.objective_mut().append_metadata(state, &mut testcase)?;
-                state.solutions_mut().add(testcase)?;
+                let idx = state.solutions_mut().add(testcase)?;
 
                 if send_events {
                     manager.fire(
@@ -406,10 +566,19 @@ where
                     )?;
                 }
 
+                if self.stop_on_first_solution {
+                    self.first_solution = Some(idx);
+                    return Err(Error::ShuttingDown);
+                }
+
                 Ok((res, None))
             }
         }
     }
+
+    fn take_reexec_input(&mut self) -> Option<I> {
+        self.reexec_input.take()
+    }
 }
 
 impl<CS, F, I, OF, OT, S> EvaluatorObservers<I, OT, S> for StdFuzzer<CS, F, I, OF, OT, S>
@@ -428,16 +597,29 @@ where
         state: &mut S,
         executor: &mut E,
         manager: &mut EM,
-        input: I,
+        mut input: I,
         send_events: bool,
     ) -> Result<(ExecuteInputResult, Option<usize>), Error>
     where
         E: Executor<EM, I, S, Self> + HasObservers<I, OT, S>,
         EM: EventManager<E, I, S, Self>,
     {
-        let exit_kind = self.execute_input(state, executor, manager, &input)?;
-        let observers = executor.observers();
-        self.process_execution(state, manager, input, observers, &exit_kind, send_events)
+        let mut reexecutions = 0;
+        loop {
+            let exit_kind = self.execute_input(state, executor, manager, &input)?;
+            let observers = executor.observers();
+            let res =
+                self.process_execution(state, manager, input, observers, &exit_kind, send_events)?;
+
+            // A feedback wants to see the new coverage again, see `MapFeedback::with_reexec_verification`
+            match self.take_reexec_input() {
+                Some(reexec_input) if reexecutions < MAX_REEXECUTIONS => {
+                    input = reexec_input;
+                    reexecutions += 1;
+                }
+                _ => return Ok(res),
+            }
+        }
     }
 }
 
@@ -513,7 +695,7 @@ where
     EM: EventManager<E, I, S, Self>,
     F: Feedback<I, S>,
     I: Input,
-    S: HasClientPerfMonitor + HasExecutions,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasExecutions + HasSolutions<I>,
     OF: Feedback<I, S>,
     ST: StagesTuple<E, EM, S, Self>,
 {
@@ -524,12 +706,23 @@ where
         state: &mut S,
         manager: &mut EM,
     ) -> Result<usize, Error> {
+        // A crash handler may have stored the solution before restarting this client
+        if self.stop_on_first_solution && state.solutions().count() > 0 {
+            self.first_solution.get_or_insert(0);
+            manager.send_exiting()?;
+            return Err(Error::ShuttingDown);
+        }
+
+        self.start_executions.get_or_insert(*state.executions());
+
         // Init timer for scheduler
         #[cfg(feature = "introspection")]
         state.introspection_monitor_mut().start_timer();
 
         // Get the next index from the scheduler
         let idx = self.scheduler.next(state)?;
+        self.scheduler
+            .on_schedule(idx, &state.corpus().get(idx)?.borrow())?;
 
         // Mark the elapsed time for the scheduler
         #[cfg(feature = "introspection")]
@@ -539,20 +732,40 @@ where
         #[cfg(feature = "introspection")]
         state.introspection_monitor_mut().reset_stage_index();
 
+        // The solutions found by the stages derive from this entry, see `ParentMetadata`
+        if self.record_parents {
+            self.parent_idx = Some(idx);
+        }
+
         // Execute all stages
-        stages.perform_all(self, executor, state, manager, idx)?;
+        let res = stages.perform_all(self, executor, state, manager, idx);
+        self.parent_idx = None;
+        self.exit_on_first_solution(manager, res)?;
 
         // Init timer for manager
         #[cfg(feature = "introspection")]
         state.introspection_monitor_mut().start_timer();
 
         // Execute the manager
-        manager.process(self, state, executor)?;
+        let res = manager.process(self, state, executor).map(|_| ());
+        self.exit_on_first_solution(manager, res)?;
 
         // Mark the elapsed time for the manager
         #[cfg(feature = "introspection")]
         state.introspection_monitor_mut().mark_manager_time();
 
+        if self.restart_due(state) {
+            // Hand the state over to the respawned client, see `StdFuzzer::restart_after_execs`
+            manager.on_restart(state)?;
+            manager.await_restart_safe();
+            libafl_log!(
+                Info,
+                "Restarting the client after {} executions",
+                self.restart_after_execs.unwrap()
+            );
+            return Err(Error::ShuttingDown);
+        }
+
         Ok(idx)
     }
 }
@@ -571,10 +784,80 @@ where
             scheduler,
             feedback,
             objective,
+            stop_on_first_solution: false,
+            first_solution: None,
+            restart_after_execs: None,
+            start_executions: None,
+            reexec_input: None,
+            record_parents: false,
+            parent_idx: None,
             phantom: PhantomData,
         }
     }
 
+    /// Stop fuzzing on the first solution, e.g. to check in CI if a campaign finds any crash.
+    /// The evaluation storing the solution, and with it the fuzz loop, then returns [`Error::ShuttingDown`],
+    /// and [`StdFuzzer::first_solution`] holds the index of the solution in the solutions corpus.
+    /// The fuzzer then tells the event manager that this client is exiting, see [`EventRestarter::send_exiting`].
+    /// Inside a [`crate::bolts::launcher::Launcher`] with `stop_all_on_client_exit`, this stops all clients.
+    #[must_use]
+    pub fn stop_on_first_solution(mut self, stop: bool) -> Self {
+        self.stop_on_first_solution = stop;
+        self
+    }
+
+    /// Record the corpus entry each solution derives from in its [`ParentMetadata`],
+    /// for [`crate::corpus::diff_with_parent`]. The entry is named when a solution is found, so this costs nothing until then.
+    #[must_use]
+    pub fn record_parents(mut self, record: bool) -> Self {
+        self.record_parents = record;
+        self
+    }
+
+    /// The index of the solution that stopped fuzzing, see [`StdFuzzer::stop_on_first_solution`]
+    #[must_use]
+    pub fn first_solution(&self) -> Option<usize> {
+        self.first_solution
+    }
+
+    /// Tells the `manager` that this client is exiting, if `res` stopped fuzzing on the first solution
+    fn exit_on_first_solution<EM>(
+        &self,
+        manager: &mut EM,
+        res: Result<(), Error>,
+    ) -> Result<(), Error>
+    where
+        EM: EventRestarter<S>,
+    {
+        if matches!(res, Err(Error::ShuttingDown)) && self.first_solution.is_some() {
+            manager.send_exiting()?;
+        }
+        res
+    }
+
+    /// Restart the fuzzer process after it ran `execs` executions, against state accumulating in the target,
+    /// such as leaks or memory fragmentation. `0` fuzzes without restarts, the default.
+    /// Between two fuzzing iterations, the state gets stored via [`crate::events::EventRestarter::on_restart`]
+    /// and [`Fuzzer::fuzz_one`] returns [`Error::ShuttingDown`], for the client to exit.
+    /// Unlike [`StdFuzzer::stop_on_first_solution`], the client does not tell its respawner that it is exiting,
+    /// so a restarting event manager, e.g. inside a [`crate::bolts::launcher::Launcher`], respawns it
+    /// with the stored state. The corpus is kept in the stored state, or on disk.
+    #[must_use]
+    pub fn restart_after_execs(mut self, execs: u64) -> Self {
+        self.restart_after_execs = if execs == 0 { None } else { Some(execs) };
+        self
+    }
+
+    /// Returns `true` if this process ran enough executions to restart, see [`StdFuzzer::restart_after_execs`].
+    /// The executions count from the first fuzzing iteration of this process, a restored state starts over.
+    #[must_use]
+    pub fn restart_due(&self, state: &S) -> bool {
+        self.restart_after_execs.map_or(false, |execs| {
+            let start = self.start_executions.unwrap_or(0);
+            state.executions().saturating_sub(start) as u64 >= execs
+        })
+    }
+
     /// Runs the input and triggers observers and feedback
     pub fn execute_input<E, EM>(
         &mut self,
@@ -862,3 +1145,185 @@ pub mod pybind {
         Ok(())
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use crate::{
+        bolts::tuples::tuple_list,
+        corpus::{Corpus, InMemoryCorpus, ParentMetadata, RandCorpusScheduler, Testcase},
+        events::SimpleEventManager,
+        executors::ExitKind,
+        feedbacks::CrashFeedback,
+        inputs::{BytesInput, Input},
+        monitors::SimpleMonitor,
+        mutators::{mutations::BitFlipMutator, StdScheduledMutator},
+        stages::StdMutationalStage,
+        state::{HasExecutions, HasMetadata, HasSolutions},
+        test_utils::{test_executor, test_state},
+        Error, Fuzzer, StdFuzzer,
+    };
+    use core::time::Duration;
+    use std::time::Instant;
+
+    #[test]
+    fn test_fuzz_loop_with_interval() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        corpus.add(Testcase::new(vec![0; 4])).unwrap();
+        let mut state = test_state(corpus);
+
+        let monitor = SimpleMonitor::new(|s| println!("{}", s));
+        let mut event_manager = SimpleEventManager::new(monitor);
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), ());
+
+        let mut harness = |_buf: &BytesInput| ExitKind::Ok;
+        let mut executor = test_executor(&mut harness, &mut fuzzer, &mut state, &mut event_manager);
+
+        let mutator = StdScheduledMutator::new(tuple_list!(BitFlipMutator::new()));
+        let mut stages = tuple_list!(StdMutationalStage::new(mutator));
+
+        let mut calls = 0;
+        fuzzer
+            .fuzz_loop_for_with_interval(
+                &mut stages,
+                &mut executor,
+                &mut state,
+                &mut event_manager,
+                100,
+                50,
+                |_state, corpus| {
+                    assert_eq!(corpus.count(), 1);
+                    calls += 1;
+                },
+            )
+            .unwrap();
+
+        assert!(*state.executions() >= 100);
+        assert_eq!(calls, *state.executions() / 50);
+    }
+    #[test]
+    fn test_fuzz_loop_for_duration() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        corpus.add(Testcase::new(vec![0; 4])).unwrap();
+        let mut state = test_state(corpus);
+
+        let monitor = SimpleMonitor::new(|s| println!("{}", s));
+        let mut event_manager = SimpleEventManager::new(monitor);
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), ());
+
+        let mut harness = |_buf: &BytesInput| ExitKind::Ok;
+        let mut executor = test_executor(&mut harness, &mut fuzzer, &mut state, &mut event_manager);
+
+        let mutator = StdScheduledMutator::new(tuple_list!(BitFlipMutator::new()));
+        let mut stages = tuple_list!(StdMutationalStage::new(mutator));
+
+        let start = Instant::now();
+        let res = fuzzer.fuzz_loop_for_duration(
+            &mut stages,
+            &mut executor,
+            &mut state,
+            &mut event_manager,
+            Duration::from_millis(100),
+        );
+        assert!(matches!(res, Err(Error::ShuttingDown)));
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(*state.executions() > 0);
+    }
+
+    #[test]
+    fn test_restart_after_execs() {
+        let mut state = test_state(InMemoryCorpus::<BytesInput>::new());
+        let fuzzer: StdFuzzer<_, _, BytesInput, _, (), _> =
+            StdFuzzer::new(RandCorpusScheduler::new(), (), ());
+        assert!(!fuzzer.restart_due(&state));
+        *state.executions_mut() = 1_000_000;
+        assert!(!fuzzer.restart_due(&state));
+
+        let fuzzer = fuzzer.restart_after_execs(10);
+        *state.executions_mut() = 9;
+        assert!(!fuzzer.restart_due(&state));
+        *state.executions_mut() = 10;
+        assert!(fuzzer.restart_due(&state));
+
+        // A respawned client counts from the executions of the restored state
+        let mut fuzzer = fuzzer;
+        fuzzer.start_executions = Some(10);
+        *state.executions_mut() = 19;
+        assert!(!fuzzer.restart_due(&state));
+        *state.executions_mut() = 20;
+        assert!(fuzzer.restart_due(&state));
+    }
+
+    #[test]
+    fn test_fuzz_one_restart_after_execs() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        corpus.add(Testcase::new(vec![0; 4])).unwrap();
+        let mut state = test_state(corpus);
+
+        let monitor = SimpleMonitor::new(|s| println!("{}", s));
+        let mut event_manager = SimpleEventManager::new(monitor);
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), ()).restart_after_execs(10);
+
+        let mut harness = |_buf: &BytesInput| ExitKind::Ok;
+        let mut executor = test_executor(&mut harness, &mut fuzzer, &mut state, &mut event_manager);
+
+        let mutator = StdScheduledMutator::new(tuple_list!(BitFlipMutator::new()));
+        let mut stages = tuple_list!(StdMutationalStage::new(mutator));
+
+        // The loop returns, instead of exiting the process, once the iteration reached the executions
+        let res = fuzzer.fuzz_loop(&mut stages, &mut executor, &mut state, &mut event_manager);
+        assert!(matches!(res, Err(Error::ShuttingDown)));
+        assert!(*state.executions() >= 10);
+        assert!(fuzzer.restart_due(&state));
+        assert_eq!(fuzzer.first_solution(), None);
+    }
+
+    #[test]
+    fn test_stop_on_first_solution() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        corpus.add(Testcase::new(vec![0; 4])).unwrap();
+        let mut state = test_state(corpus);
+
+        let monitor = SimpleMonitor::new(|s| println!("{}", s));
+        let mut event_manager = SimpleEventManager::new(monitor);
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), CrashFeedback::new())
+            .stop_on_first_solution(true)
+            .record_parents(true);
+
+        // Crashes on the third execution
+        let mut runs = 0;
+        let mut harness = |_buf: &BytesInput| {
+            runs += 1;
+            if runs == 3 {
+                ExitKind::Crash
+            } else {
+                ExitKind::Ok
+            }
+        };
+        let mut executor = test_executor(&mut harness, &mut fuzzer, &mut state, &mut event_manager);
+
+        let mutator = StdScheduledMutator::new(tuple_list!(BitFlipMutator::new()));
+        let mut stages = tuple_list!(StdMutationalStage::new(mutator));
+
+        let res = fuzzer.fuzz_loop(&mut stages, &mut executor, &mut state, &mut event_manager);
+        assert!(matches!(res, Err(Error::ShuttingDown)));
+        assert_eq!(*state.executions(), 3);
+        assert_eq!(state.solutions().count(), 1);
+        assert_eq!(fuzzer.first_solution(), Some(0));
+
+        // The solution derives from the only corpus entry
+        assert_eq!(
+            state
+                .solutions()
+                .get(0)
+                .unwrap()
+                .borrow()
+                .metadata()
+                .get::<ParentMetadata>(),
+            Some(&ParentMetadata {
+                parent_name: BytesInput::new(vec![0; 4]).generate_name(0)
+            })
+        );
+    }
+}
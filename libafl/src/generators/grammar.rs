@@ -0,0 +1,388 @@
+//! The [`GrammarGenerator`] generates inputs from a simple context-free grammar.
+//!
+//! A grammar is a text file of productions, one per line:
+//!
+//! ```text
+//! # Comments start with a `#`, empty lines are ignored
+//! <expr> ::= <num> | <expr> "+" <expr> | "(" <expr> ")"
+//! <num>  ::= "0" | "1" | @token
+//! ```
+//!
+//! The left side names a nonterminal, in angle brackets. The right side lists alternatives,
+//! separated by `|`, each a sequence of:
+//! * `<name>`, a nonterminal, expanded with one of its productions,
+//! * `"text"`, a terminal, supporting the escapes `\"`, `\\`, `\n`, `\r`, `\t`, and `\xHH`,
+//! * `@token`, a random token of the [`Tokens`] dictionary in the state metadata (nothing, if there is none).
+//!
+//! A nonterminal may have productions on several lines. The first nonterminal is the start symbol.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::marker::PhantomData;
+
+use crate::{
+    bolts::rands::Rand,
+    generators::Generator,
+    inputs::Input,
+    mutators::Tokens,
+    state::{HasMetadata, HasRand},
+    Error,
+};
+
+/// The default maximum expansion depth of a [`GrammarGenerator`]
+pub const DEFAULT_GRAMMAR_MAX_DEPTH: usize = 16;
+
+/// A symbol on the right side of a production
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum GrammarSymbol {
+    /// A nonterminal, by index
+    NonTerminal(usize),
+    /// A literal terminal
+    Terminal(Vec<u8>),
+    /// A random token from the [`Tokens`] metadata
+    Token,
+}
+
+/// Generates inputs expanding a context-free grammar, up to a maximum depth.
+/// Once the maximum depth is reached, it only picks the productions terminating the quickest.
+#[derive(Clone, Debug)]
+pub struct GrammarGenerator<S>
+where
+    S: HasRand + HasMetadata,
+{
+    names: Vec<String>,
+    /// The alternatives of each nonterminal
+    productions: Vec<Vec<Vec<GrammarSymbol>>>,
+    /// The minimum depth to fully expand each alternative
+    depths: Vec<Vec<usize>>,
+    max_depth: usize,
+    phantom: PhantomData<S>,
+}
+
+impl<I, S> Generator<I, S> for GrammarGenerator<S>
+where
+    I: Input + From<Vec<u8>>,
+    S: HasRand + HasMetadata,
+{
+    fn generate(&mut self, state: &mut S) -> Result<I, Error> {
+        let mut bytes = vec![];
+        self.expand(state, 0, 0, &mut bytes);
+        Ok(bytes.into())
+    }
+
+    /// Generates the shortest expansion of the grammar, without tokens
+    fn generate_dummy(&self, _state: &mut S) -> I {
+        let mut bytes = vec![];
+        self.expand_shortest(0, &mut bytes);
+        bytes.into()
+    }
+}
+
+impl<S> GrammarGenerator<S>
+where
+    S: HasRand + HasMetadata,
+{
+    /// Creates a new [`GrammarGenerator`] from the productions in `grammar`,
+    /// expanding up to [`DEFAULT_GRAMMAR_MAX_DEPTH`] nonterminals deep
+    pub fn new(grammar: &str) -> Result<Self, Error> {
+        Self::with_max_depth(grammar, DEFAULT_GRAMMAR_MAX_DEPTH)
+    }
+
+    /// Creates a new [`GrammarGenerator`] from the productions in `grammar`,
+    /// expanding up to `max_depth` nonterminals deep
+    pub fn with_max_depth(grammar: &str, max_depth: usize) -> Result<Self, Error> {
+        let mut names: Vec<String> = vec![];
+        let mut productions: Vec<Vec<Vec<GrammarSymbol>>> = vec![];
+
+        for (line_no, line) in grammar.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |msg: &str| {
+                Error::IllegalArgument(format!("Grammar line {}: {}", line_no + 1, msg))
+            };
+            let (lhs, rhs) = line
+                .split_once("::=")
+                .ok_or_else(|| err("expected `<name> ::= ...`"))?;
+            let lhs = lhs.trim();
+            if !(lhs.len() > 2 && lhs.starts_with('<') && lhs.ends_with('>')) {
+                return Err(err("expected a nonterminal `<name>` on the left side"));
+            }
+            let nonterminal =
+                Self::nonterminal_index(&mut names, &mut productions, &lhs[1..lhs.len() - 1]);
+
+            let mut alternatives = vec![vec![]];
+            let mut rest = rhs.trim_start();
+            while let Some(c) = rest.chars().next() {
+                match c {
+                    '|' => {
+                        alternatives.push(vec![]);
+                        rest = &rest[1..];
+                    }
+                    '<' => {
+                        let end = rest.find('>').ok_or_else(|| err("unterminated `<`"))?;
+                        let symbol =
+                            Self::nonterminal_index(&mut names, &mut productions, &rest[1..end]);
+                        alternatives
+                            .last_mut()
+                            .unwrap()
+                            .push(GrammarSymbol::NonTerminal(symbol));
+                        rest = &rest[end + 1..];
+                    }
+                    '"' => {
+                        let (terminal, len) = Self::parse_terminal(&rest[1..]).map_err(err)?;
+                        alternatives
+                            .last_mut()
+                            .unwrap()
+                            .push(GrammarSymbol::Terminal(terminal));
+                        rest = &rest[1 + len..];
+                    }
+                    '@' if rest.starts_with("@token") => {
+                        alternatives.last_mut().unwrap().push(GrammarSymbol::Token);
+                        rest = &rest["@token".len()..];
+                    }
+                    _ => return Err(err("expected `<name>`, `\"text\"`, `@token`, or `|`")),
+                }
+                rest = rest.trim_start();
+            }
+            productions[nonterminal].append(&mut alternatives);
+        }
+
+        if names.is_empty() {
+            return Err(Error::IllegalArgument("Empty grammar".to_string()));
+        }
+        if let Some(idx) = productions.iter().position(Vec::is_empty) {
+            return Err(Error::IllegalArgument(format!(
+                "Grammar nonterminal <{}> has no productions",
+                names[idx]
+            )));
+        }
+
+        let depths = Self::compute_depths(&productions);
+        if let Some(idx) = depths
+            .iter()
+            .position(|alternatives| alternatives.iter().all(|&d| d == usize::MAX))
+        {
+            return Err(Error::IllegalArgument(format!(
+                "Grammar nonterminal <{}> never terminates",
+                names[idx]
+            )));
+        }
+
+        Ok(Self {
+            names,
+            productions,
+            depths,
+            max_depth,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Creates a new [`GrammarGenerator`] from the productions in the grammar `file`
+    #[cfg(feature = "std")]
+    pub fn from_file<P>(file: P, max_depth: usize) -> Result<Self, Error>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Self::with_max_depth(&std::fs::read_to_string(file)?, max_depth)
+    }
+
+    /// The nonterminals of the grammar, the first one is the start symbol
+    #[must_use]
+    pub fn nonterminals(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The index of the nonterminal `name`, adding it if it is new
+    fn nonterminal_index(
+        names: &mut Vec<String>,
+        productions: &mut Vec<Vec<Vec<GrammarSymbol>>>,
+        name: &str,
+    ) -> usize {
+        names.iter().position(|n| n == name).unwrap_or_else(|| {
+            names.push(name.to_string());
+            productions.push(vec![]);
+            names.len() - 1
+        })
+    }
+
+    /// Parses a quoted terminal, returning its bytes and the length of the consumed text, including the closing quote
+    fn parse_terminal(text: &str) -> Result<(Vec<u8>, usize), &'static str> {
+        let mut bytes = vec![];
+        let mut chars = text.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((bytes, i + 1)),
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('"') => bytes.push(b'"'),
+                    Some('\\') => bytes.push(b'\\'),
+                    Some('n') => bytes.push(b'\n'),
+                    Some('r') => bytes.push(b'\r'),
+                    Some('t') => bytes.push(b'\t'),
+                    Some('x') => {
+                        let hex: String = (0..2)
+                            .filter_map(|_| chars.next().map(|(_, c)| c))
+                            .collect();
+                        bytes.push(
+                            u8::from_str_radix(&hex, 16).map_err(|_| "invalid `\\x` escape")?,
+                        );
+                    }
+                    _ => return Err("invalid escape"),
+                },
+                _ => {
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+            }
+        }
+        Err("unterminated `\"`")
+    }
+
+    /// Computes the minimum depth to fully expand each alternative, `usize::MAX` if it never terminates
+    fn compute_depths(productions: &[Vec<Vec<GrammarSymbol>>]) -> Vec<Vec<usize>> {
+        let mut depths: Vec<Vec<usize>> = productions
+            .iter()
+            .map(|alternatives| vec![usize::MAX; alternatives.len()])
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (nonterminal, alternatives) in productions.iter().enumerate() {
+                for (alternative, symbols) in alternatives.iter().enumerate() {
+                    let mut depth = 1;
+                    for symbol in symbols {
+                        if let GrammarSymbol::NonTerminal(idx) = symbol {
+                            let min = depths[*idx].iter().copied().min().unwrap_or(usize::MAX);
+                            depth = depth.max(min.saturating_add(1));
+                        }
+                    }
+                    if depth < depths[nonterminal][alternative] {
+                        depths[nonterminal][alternative] = depth;
+                        changed = true;
+                    }
+                }
+            }
+        }
+        depths
+    }
+
+    /// Expands `nonterminal` at the given `depth` into `bytes`
+    fn expand(&self, state: &mut S, nonterminal: usize, depth: usize, bytes: &mut Vec<u8>) {
+        let depths = &self.depths[nonterminal];
+        let fitting: Vec<usize> = (0..depths.len())
+            .filter(|&alternative| depth.saturating_add(depths[alternative]) <= self.max_depth)
+            .collect();
+        let alternative = if fitting.is_empty() {
+            Self::shortest(depths)
+        } else {
+            fitting[state.rand_mut().below(fitting.len() as u64) as usize]
+        };
+
+        for symbol in &self.productions[nonterminal][alternative] {
+            match symbol {
+                GrammarSymbol::NonTerminal(idx) => self.expand(state, *idx, depth + 1, bytes),
+                GrammarSymbol::Terminal(terminal) => bytes.extend_from_slice(terminal),
+                GrammarSymbol::Token => {
+                    let len = state.metadata().get::<Tokens>().map_or(0, Tokens::len);
+                    if len > 0 {
+                        let idx = state.rand_mut().below(len as u64) as usize;
+                        let tokens = state.metadata().get::<Tokens>().unwrap();
+                        bytes.extend_from_slice(&tokens.tokens()[idx]);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Expands `nonterminal` the shortest way into `bytes`
+    fn expand_shortest(&self, nonterminal: usize, bytes: &mut Vec<u8>) {
+        let alternative = Self::shortest(&self.depths[nonterminal]);
+        for symbol in &self.productions[nonterminal][alternative] {
+            match symbol {
+                GrammarSymbol::NonTerminal(idx) => self.expand_shortest(*idx, bytes),
+                GrammarSymbol::Terminal(terminal) => bytes.extend_from_slice(terminal),
+                GrammarSymbol::Token => (),
+            }
+        }
+    }
+
+    /// The alternative terminating the quickest
+    fn shortest(depths: &[usize]) -> usize {
+        (0..depths.len()).min_by_key(|&i| depths[i]).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::InMemoryCorpus,
+        generators::{Generator, GrammarGenerator},
+        inputs::{BytesInput, HasBytesVec},
+        mutators::Tokens,
+        state::{HasMetadata, HasRand, StdState},
+    };
+
+    fn is_valid<S>(_state: &S, grammar: &str) -> bool
+    where
+        S: HasRand + HasMetadata,
+    {
+        GrammarGenerator::<S>::new(grammar).is_ok()
+    }
+
+    #[test]
+    fn test_grammar_generator() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        state.add_metadata(
+            Tokens::new()
+                .add_tokens(&[b"x".to_vec(), b"yy".to_vec()])
+                .clone(),
+        );
+
+        let grammar = r#"
+            # A list of items, or tokens in brackets
+            <start> ::= <list> | "[" @token "]"
+            <list> ::= <item> | <item> "," <list>
+            <item> ::= "a"
+            <item> ::= "\x62"
+        "#;
+        let mut generator = GrammarGenerator::with_max_depth(grammar, 6).unwrap();
+        assert_eq!(generator.nonterminals(), &["start", "list", "item"]);
+
+        let (mut lists, mut tokens) = (0, 0);
+        for _ in 0..100 {
+            let input: BytesInput = generator.generate(&mut state).unwrap();
+            let text = core::str::from_utf8(input.bytes()).unwrap();
+            if text.starts_with('[') {
+                assert!(text == "[x]" || text == "[yy]");
+                tokens += 1;
+            } else {
+                // Each item nests the rest of the list a level deeper, at most 4 items fit in 6 levels
+                let items: Vec<&str> = text.split(',').collect();
+                assert!(items.len() <= 4);
+                assert!(items.iter().all(|item| *item == "a" || *item == "b"));
+                lists += 1;
+            }
+        }
+        assert!(lists > 0 && tokens > 0);
+
+        let dummy: BytesInput = generator.generate_dummy(&mut state);
+        assert_eq!(dummy.bytes(), b"[]");
+
+        // Runaway recursion and undefined nonterminals are rejected
+        assert!(is_valid(&state, "<a> ::= <b>\n<b> ::= \"b\""));
+        assert!(!is_valid(&state, "<a> ::= <a> \"x\""));
+        assert!(!is_valid(&state, "<a> ::= <b>"));
+        assert!(!is_valid(&state, "<a> ::= x"));
+    }
+}
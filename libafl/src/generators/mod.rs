@@ -13,6 +13,9 @@ use crate::{
 pub mod gramatron;
 pub use gramatron::*;
 
+pub mod grammar;
+pub use grammar::GrammarGenerator;
+
 #[cfg(feature = "nautilus")]
 pub mod nautilus;
 #[cfg(feature = "nautilus")]
@@ -126,6 +129,64 @@ where
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, RandCorpusScheduler},
+        events::NopEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        fuzzer::StdFuzzer,
+        generators::{RandBytesGenerator, RandPrintablesGenerator},
+        inputs::{BytesInput, HasBytesVec},
+        state::{HasCorpus, HasExecutions, StdState},
+    };
+
+    #[test]
+    fn test_generate_initial_inputs() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), ());
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        let mut generator = RandBytesGenerator::new(32);
+        state
+            .generate_initial_inputs_forced(&mut fuzzer, &mut executor, &mut generator, &mut mgr, 8)
+            .unwrap();
+        let mut generator = RandPrintablesGenerator::new(32);
+        state
+            .generate_initial_inputs_forced(&mut fuzzer, &mut executor, &mut generator, &mut mgr, 8)
+            .unwrap();
+
+        // All inputs got executed, and entered the corpus
+        assert_eq!(*state.executions(), 16);
+        assert_eq!(state.corpus().count(), 16);
+        for idx in 0..16 {
+            let mut testcase = state.corpus().get(idx).unwrap().borrow_mut();
+            let bytes = testcase.load_input().unwrap().bytes();
+            assert!(!bytes.is_empty() && bytes.len() < 32);
+            if idx >= 8 {
+                assert!(bytes
+                    .iter()
+                    .all(|b| b.is_ascii_graphic() || b.is_ascii_whitespace()));
+            }
+        }
+    }
+}
+
 /// `Generator` Python bindings
 #[cfg(feature = "python")]
 pub mod pybind {
@@ -0,0 +1,115 @@
+//! The `CommandLineInput` pairs the bytes fed to a command-based target with its command line,
+//! so that argument and environment parsing can be fuzzed next to the file content.
+
+use ahash::AHasher;
+use alloc::{string::String, vec::Vec};
+use core::hash::Hasher;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{ownedref::OwnedSlice, HasLen},
+    inputs::{HasBytesVec, HasTargetBytes, Input},
+};
+
+/// The argument standing for the input, such as the path of the input file, as in `afl-fuzz`
+pub const INPUT_FILE_PLACEHOLDER: &str = "@@";
+
+/// An input made of bytes, plus the arguments and environment variables the target gets run with.
+/// The arguments replace the ones the [`crate::executors::CommandExecutor`] was configured with,
+/// an argument [`INPUT_FILE_PLACEHOLDER`] gets replaced by the input.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CommandLineInput {
+    bytes: Vec<u8>,
+    args: Vec<String>,
+    envs: Vec<(String, String)>,
+}
+
+impl Input for CommandLineInput {
+    /// Generate a name for this input
+    fn generate_name(&self, _idx: usize) -> String {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        hasher.write(&self.bytes);
+        for arg in &self.args {
+            hasher.write_usize(arg.len());
+            hasher.write(arg.as_bytes());
+        }
+        for (key, value) in &self.envs {
+            hasher.write_usize(key.len());
+            hasher.write(key.as_bytes());
+            hasher.write_usize(value.len());
+            hasher.write(value.as_bytes());
+        }
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+impl HasTargetBytes for CommandLineInput {
+    #[inline]
+    fn target_bytes(&self) -> OwnedSlice<u8> {
+        OwnedSlice::from(&self.bytes)
+    }
+
+    #[inline]
+    fn target_command_line(&self) -> Option<(&[String], &[(String, String)])> {
+        Some((&self.args, &self.envs))
+    }
+}
+
+impl HasBytesVec for CommandLineInput {
+    #[inline]
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    #[inline]
+    fn bytes_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.bytes
+    }
+}
+
+impl HasLen for CommandLineInput {
+    #[inline]
+    fn len(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+impl CommandLineInput {
+    /// Creates a new command line input
+    #[must_use]
+    pub fn new(bytes: Vec<u8>, args: Vec<String>, envs: Vec<(String, String)>) -> Self {
+        Self { bytes, args, envs }
+    }
+
+    /// The arguments, without the program
+    #[must_use]
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// The arguments, without the program (as mutable borrow)
+    pub fn args_mut(&mut self) -> &mut Vec<String> {
+        &mut self.args
+    }
+
+    /// The environment variables, as key and value
+    #[must_use]
+    pub fn envs(&self) -> &[(String, String)] {
+        &self.envs
+    }
+
+    /// The environment variables, as key and value (as mutable borrow)
+    pub fn envs_mut(&mut self) -> &mut Vec<(String, String)> {
+        &mut self.envs
+    }
+
+    /// The strings of the command line that may be mutated:
+    /// all arguments but the [`INPUT_FILE_PLACEHOLDER`], and the values of the environment variables.
+    pub fn mutable_strings(&mut self) -> Vec<&mut String> {
+        self.args
+            .iter_mut()
+            .filter(|arg| *arg != INPUT_FILE_PLACEHOLDER)
+            .chain(self.envs.iter_mut().map(|(_, value)| value))
+            .collect()
+    }
+}
@@ -25,6 +25,29 @@ pub enum GeneralizedItem {
     Gap,
 }
 
+/// A testcase metadata holding the generalized form of a [`GeneralizedInput`],
+/// so that the rules learned by Grimoire get stored (and reloaded) together with the testcase metadata.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GeneralizedInputMetadata {
+    generalized: Vec<GeneralizedItem>,
+}
+
+crate::impl_serdeany!(GeneralizedInputMetadata);
+
+impl GeneralizedInputMetadata {
+    /// Creates a new [`struct@GeneralizedInputMetadata`] from the generalized items
+    #[must_use]
+    pub fn new(generalized: Vec<GeneralizedItem>) -> Self {
+        Self { generalized }
+    }
+
+    /// Get the generalized items
+    #[must_use]
+    pub fn generalized(&self) -> &[GeneralizedItem] {
+        &self.generalized
+    }
+}
+
 /// A bytes input with a generalized version mainly used for Grimoire
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct GeneralizedInput {
@@ -205,6 +228,19 @@ impl GeneralizedInput {
         &mut self.generalized
     }
 
+    /// Get the generalized input as [`struct@GeneralizedInputMetadata`], to be stored in the testcase
+    #[must_use]
+    pub fn generalized_metadata(&self) -> Option<GeneralizedInputMetadata> {
+        self.generalized
+            .as_ref()
+            .map(|gen| GeneralizedInputMetadata::new(gen.clone()))
+    }
+
+    /// Reconstruct the generalized input from a previously stored [`struct@GeneralizedInputMetadata`]
+    pub fn generalized_from_metadata(&mut self, meta: &GeneralizedInputMetadata) {
+        self.generalized = Some(meta.generalized().to_vec());
+    }
+
     /// Load from a plain file of bytes
     #[cfg(feature = "std")]
     pub fn from_bytes_file<P>(path: P) -> Result<Self, Error>
@@ -221,3 +257,33 @@ impl GeneralizedInput {
         })
     }
 }
+
+#[cfg(feature = "std")]
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::serdeany::SerdeAnyMap,
+        corpus::Testcase,
+        inputs::{GeneralizedInput, GeneralizedInputMetadata, GeneralizedItem, HasBytesVec},
+        state::HasMetadata,
+    };
+
+    #[test]
+    fn test_generalized_metadata_roundtrip() {
+        let mut input = GeneralizedInput::new(b"a(b)c".to_vec());
+        input.generalized_from_options(&[Some(b'a'), None, Some(b'b'), None, Some(b'c')]);
+        let generalized = input.generalized().unwrap().to_vec();
+        assert!(generalized.contains(&GeneralizedItem::Gap));
+
+        let mut testcase = Testcase::new(input.clone());
+        testcase.add_metadata(input.generalized_metadata().unwrap());
+
+        let serialized = postcard::to_allocvec(testcase.metadata()).unwrap();
+        let metadata: SerdeAnyMap = postcard::from_bytes(&serialized).unwrap();
+
+        let mut reloaded = GeneralizedInput::new(input.bytes().to_vec());
+        assert!(reloaded.generalized().is_none());
+        reloaded.generalized_from_metadata(metadata.get::<GeneralizedInputMetadata>().unwrap());
+        assert_eq!(reloaded.generalized().unwrap(), generalized.as_slice());
+    }
+}
@@ -12,6 +12,15 @@ pub use gramatron::*;
 pub mod generalized;
 pub use generalized::*;
 
+pub mod multi;
+pub use multi::MultiInput;
+
+pub mod cmdline;
+pub use cmdline::{CommandLineInput, INPUT_FILE_PLACEHOLDER};
+
+pub mod structured;
+pub use structured::{FieldKind, StructuredBytesInput};
+
 #[cfg(feature = "nautilus")]
 pub mod nautilus;
 #[cfg(feature = "nautilus")]
@@ -101,6 +110,13 @@ impl HasTargetBytes for NopInput {
 pub trait HasTargetBytes {
     /// Target bytes, that can be written to a target
     fn target_bytes(&self) -> OwnedSlice<u8>;
+
+    /// The command line arguments and environment variables to run a command-based target with,
+    /// for inputs that fuzz the command line, see [`CommandLineInput`].
+    /// `None`, the default, runs the target with the command line it was configured with.
+    fn target_command_line(&self) -> Option<(&[String], &[(String, String)])> {
+        None
+    }
 }
 
 /// Contains an internal bytes Vector
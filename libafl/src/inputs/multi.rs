@@ -0,0 +1,129 @@
+//! The `MultiInput` is a sequence of messages, for stateful targets that consume several inputs per execution,
+//! such as the messages of a network protocol.
+
+use ahash::AHasher;
+use alloc::{string::String, vec::Vec};
+use core::hash::Hasher;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{ownedref::OwnedSlice, HasLen},
+    inputs::{BytesInput, HasBytesVec, HasTargetBytes, Input},
+};
+
+/// An input made of a sequence of messages, fed to the target one after the other
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MultiInput {
+    messages: Vec<BytesInput>,
+}
+
+impl Input for MultiInput {
+    /// Generate a name for this input
+    fn generate_name(&self, _idx: usize) -> String {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        for message in &self.messages {
+            hasher.write_usize(message.bytes().len());
+            hasher.write(message.bytes());
+        }
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+impl HasTargetBytes for MultiInput {
+    /// The messages, each prefixed with its length as little endian `u32`.
+    /// Use [`MultiInput::split_target_bytes`] to get the messages back in the target.
+    #[allow(clippy::cast_possible_truncation)]
+    fn target_bytes(&self) -> OwnedSlice<u8> {
+        let mut bytes = Vec::with_capacity(
+            self.messages
+                .iter()
+                .map(|message| 4 + message.bytes().len())
+                .sum(),
+        );
+        for message in &self.messages {
+            bytes.extend_from_slice(&(message.bytes().len() as u32).to_le_bytes());
+            bytes.extend_from_slice(message.bytes());
+        }
+        OwnedSlice::from(bytes)
+    }
+}
+
+impl HasLen for MultiInput {
+    /// The amount of messages
+    #[inline]
+    fn len(&self) -> usize {
+        self.messages.len()
+    }
+}
+
+impl From<Vec<BytesInput>> for MultiInput {
+    fn from(messages: Vec<BytesInput>) -> Self {
+        Self::new(messages)
+    }
+}
+
+impl MultiInput {
+    /// Creates a new multi input from the given messages
+    #[must_use]
+    pub fn new(messages: Vec<BytesInput>) -> Self {
+        Self { messages }
+    }
+
+    /// The messages, in the order they get fed to the target
+    #[must_use]
+    pub fn messages(&self) -> &[BytesInput] {
+        &self.messages
+    }
+
+    /// The messages, in the order they get fed to the target (as mutable borrow)
+    pub fn messages_mut(&mut self) -> &mut Vec<BytesInput> {
+        &mut self.messages
+    }
+
+    /// The bytes of each message, to be passed to a harness taking a slice of slices:
+    /// `let mut harness = |input: &MultiInput| target(&input.message_slices());`
+    #[must_use]
+    pub fn message_slices(&self) -> Vec<&[u8]> {
+        self.messages.iter().map(HasBytesVec::bytes).collect()
+    }
+
+    /// Splits the [`HasTargetBytes::target_bytes`] of a [`MultiInput`] back into its messages.
+    /// A truncated last message is dropped.
+    #[must_use]
+    pub fn split_target_bytes(mut bytes: &[u8]) -> Vec<&[u8]> {
+        let mut messages = Vec::new();
+        while bytes.len() >= 4 {
+            let len = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+            bytes = &bytes[4..];
+            if bytes.len() < len {
+                break;
+            }
+            messages.push(&bytes[..len]);
+            bytes = &bytes[len..];
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::AsSlice,
+        inputs::{BytesInput, HasTargetBytes, MultiInput},
+    };
+
+    #[test]
+    fn test_multi_input_target_bytes() {
+        let input = MultiInput::new(vec![
+            BytesInput::new(b"HELLO".to_vec()),
+            BytesInput::new(vec![]),
+            BytesInput::new(b"LOGIN".to_vec()),
+        ]);
+        let bytes = input.target_bytes();
+        assert_eq!(bytes.as_slice().len(), 3 * 4 + 10);
+        assert_eq!(
+            MultiInput::split_target_bytes(bytes.as_slice()),
+            input.message_slices()
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! The `StructuredBytesInput` splits a length-delimited format into its fields, following a simple schema.
+//! Mutations can then target single fields, while the length prefixes get recomputed on serialization.
+
+use ahash::AHasher;
+use alloc::{string::String, vec::Vec};
+use core::hash::Hasher;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{ownedref::OwnedSlice, AsSlice, HasLen},
+    inputs::{BytesInput, HasBytesVec, HasTargetBytes, Input},
+    Error,
+};
+
+/// A field of the schema of a [`StructuredBytesInput`]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FieldKind {
+    /// A field of exactly this amount of bytes
+    Fixed(usize),
+    /// A field prefixed with its length, as little endian integer of this width (`1`, `2`, `4` or `8` bytes)
+    LengthPrefixed(usize),
+}
+
+impl FieldKind {
+    /// The largest field length the prefix of a [`FieldKind::LengthPrefixed`] field can encode
+    fn max_len(self) -> usize {
+        match self {
+            FieldKind::Fixed(len) => len,
+            FieldKind::LengthPrefixed(width) if width >= core::mem::size_of::<usize>() => {
+                usize::MAX
+            }
+            FieldKind::LengthPrefixed(width) => (1 << (width * 8)) - 1,
+        }
+    }
+}
+
+/// An input made of fields, following a schema of [`FieldKind`]s.
+/// Serializing it writes fixed fields padded (or truncated) to their size,
+/// and the current length in front of length-prefixed fields, so lengths stay valid across mutations.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StructuredBytesInput {
+    schema: Vec<FieldKind>,
+    fields: Vec<BytesInput>,
+}
+
+impl Input for StructuredBytesInput {
+    /// Generate a name for this input
+    fn generate_name(&self, _idx: usize) -> String {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        hasher.write(self.target_bytes().as_slice());
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+impl HasTargetBytes for StructuredBytesInput {
+    /// The fields, serialized following the schema, with recomputed length prefixes
+    fn target_bytes(&self) -> OwnedSlice<u8> {
+        let mut bytes = vec![];
+        for (kind, field) in self.schema.iter().zip(&self.fields) {
+            let field = field.bytes();
+            match *kind {
+                FieldKind::Fixed(len) => {
+                    let copied = field.len().min(len);
+                    bytes.extend_from_slice(&field[..copied]);
+                    bytes.resize(bytes.len() + len - copied, 0);
+                }
+                FieldKind::LengthPrefixed(width) => {
+                    let len = field.len().min(kind.max_len());
+                    bytes.extend_from_slice(&(len as u64).to_le_bytes()[..width]);
+                    bytes.extend_from_slice(&field[..len]);
+                }
+            }
+        }
+        OwnedSlice::from(bytes)
+    }
+}
+
+impl HasLen for StructuredBytesInput {
+    /// The amount of fields
+    #[inline]
+    fn len(&self) -> usize {
+        self.fields.len()
+    }
+}
+
+impl StructuredBytesInput {
+    /// Parses `bytes` into fields, following the `schema`.
+    /// Fails if the bytes are truncated, or if trailing bytes remain.
+    pub fn parse(schema: Vec<FieldKind>, mut bytes: &[u8]) -> Result<Self, Error> {
+        let mut fields = Vec::with_capacity(schema.len());
+        for (idx, kind) in schema.iter().enumerate() {
+            let len = match *kind {
+                FieldKind::Fixed(len) => len,
+                FieldKind::LengthPrefixed(width) => {
+                    if !matches!(width, 1 | 2 | 4 | 8) {
+                        return Err(Error::IllegalArgument(format!(
+                            "Invalid length prefix width {} for field {}",
+                            width, idx
+                        )));
+                    }
+                    if bytes.len() < width {
+                        return Err(Error::IllegalArgument(format!(
+                            "Truncated length prefix of field {}",
+                            idx
+                        )));
+                    }
+                    let mut prefix = [0_u8; 8];
+                    prefix[..width].copy_from_slice(&bytes[..width]);
+                    bytes = &bytes[width..];
+                    usize::try_from(u64::from_le_bytes(prefix)).unwrap_or(usize::MAX)
+                }
+            };
+            if bytes.len() < len {
+                return Err(Error::IllegalArgument(format!(
+                    "Truncated field {}: {} bytes left, {} expected",
+                    idx,
+                    bytes.len(),
+                    len
+                )));
+            }
+            fields.push(BytesInput::new(bytes[..len].to_vec()));
+            bytes = &bytes[len..];
+        }
+        if !bytes.is_empty() {
+            return Err(Error::IllegalArgument(format!(
+                "{} trailing bytes after the last field",
+                bytes.len()
+            )));
+        }
+        Ok(Self { schema, fields })
+    }
+
+    /// Parses the bytes of a [`BytesInput`] into fields, following the `schema`
+    pub fn from_bytes_input(schema: Vec<FieldKind>, input: &BytesInput) -> Result<Self, Error> {
+        Self::parse(schema, input.bytes())
+    }
+
+    /// The schema of this input
+    #[must_use]
+    pub fn schema(&self) -> &[FieldKind] {
+        &self.schema
+    }
+
+    /// The fields, in the order of the schema
+    #[must_use]
+    pub fn fields(&self) -> &[BytesInput] {
+        &self.fields
+    }
+
+    /// The fields, in the order of the schema (as mutable borrow).
+    /// Fixed fields may change their size here, they get padded or truncated on serialization.
+    pub fn fields_mut(&mut self) -> &mut [BytesInput] {
+        &mut self.fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::AsSlice,
+        inputs::{FieldKind, HasBytesVec, HasTargetBytes, StructuredBytesInput},
+    };
+
+    #[test]
+    fn test_structured_input_roundtrip() {
+        let schema = vec![FieldKind::Fixed(2), FieldKind::LengthPrefixed(2)];
+        let bytes = b"HI\x03\x00abc";
+        let mut input = StructuredBytesInput::parse(schema.clone(), bytes).unwrap();
+        assert_eq!(input.fields()[1].bytes(), b"abc");
+        assert_eq!(input.target_bytes().as_slice(), bytes);
+
+        input.fields_mut()[0].bytes_mut().push(b'!');
+        input.fields_mut()[1].bytes_mut().extend_from_slice(b"defg");
+        assert_eq!(input.target_bytes().as_slice(), b"HI\x07\x00abcdefg");
+
+        assert!(StructuredBytesInput::parse(schema.clone(), b"HI\x04\x00abc").is_err());
+        assert!(StructuredBytesInput::parse(schema, b"HI\x03\x00abcd").is_err());
+    }
+}
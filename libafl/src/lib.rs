@@ -8,6 +8,8 @@ Welcome to `LibAFL`
 #![cfg_attr(unstable_feature, feature(specialization))]
 // For `type_id` and owned things
 #![cfg_attr(unstable_feature, feature(intrinsics))]
+// For reporting a failed allocation in forked children
+#![cfg_attr(unstable_feature, feature(alloc_error_hook))]
 #![warn(clippy::cargo)]
 #![deny(clippy::cargo_common_metadata)]
 #![deny(rustdoc::broken_intra_doc_links)]
@@ -97,6 +99,8 @@ pub mod stages;
 pub mod state;
 
 pub mod fuzzer;
+#[cfg(all(test, feature = "std"))]
+pub(crate) mod test_utils;
 use alloc::string::{FromUtf8Error, String};
 use core::{array::TryFromSliceError, fmt, num::ParseIntError, num::TryFromIntError};
 pub use fuzzer::*;
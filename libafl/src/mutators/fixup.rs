@@ -0,0 +1,147 @@
+//! The [`FixupMutator`] repairs inputs after each mutation, e.g. recomputing checksums,
+//! so the target does not reject most mutated inputs early.
+
+use alloc::vec::Vec;
+
+use crate::{
+    bolts::tuples::Named,
+    inputs::{HasBytesVec, Input},
+    mutators::{MutationResult, Mutator},
+    Error,
+};
+
+/// A fixup, applied to the bytes of each mutated input, such as recomputing the CRCs of a PNG
+pub type FixupFn = fn(&mut Vec<u8>);
+
+/// Wraps a mutator, calling an optional [`FixupFn`] on the bytes of each input it mutated,
+/// before the input gets executed. Skipped mutations are not fixed up.
+#[derive(Debug)]
+pub struct FixupMutator<M> {
+    mutator: M,
+    fixup: Option<FixupFn>,
+}
+
+impl<I, M, S> Mutator<I, S> for FixupMutator<M>
+where
+    I: Input + HasBytesVec,
+    M: Mutator<I, S>,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let result = self.mutator.mutate(state, input, stage_idx)?;
+        if result == MutationResult::Mutated {
+            if let Some(fixup) = self.fixup {
+                fixup(input.bytes_mut());
+            }
+        }
+        Ok(result)
+    }
+
+    fn post_exec(
+        &mut self,
+        state: &mut S,
+        stage_idx: i32,
+        corpus_idx: Option<usize>,
+    ) -> Result<(), Error> {
+        self.mutator.post_exec(state, stage_idx, corpus_idx)
+    }
+}
+
+impl<M> Named for FixupMutator<M> {
+    fn name(&self) -> &str {
+        "FixupMutator"
+    }
+}
+
+impl<M> FixupMutator<M> {
+    /// Creates a new [`FixupMutator`], calling `fixup` after each mutation of the wrapped `mutator`
+    #[must_use]
+    pub fn new(mutator: M, fixup: FixupFn) -> Self {
+        Self {
+            mutator,
+            fixup: Some(fixup),
+        }
+    }
+
+    /// Creates a new [`FixupMutator`] without a fixup, behaving like the wrapped `mutator`
+    #[must_use]
+    pub fn without_fixup(mutator: M) -> Self {
+        Self {
+            mutator,
+            fixup: None,
+        }
+    }
+
+    /// Sets (or removes) the fixup
+    pub fn set_fixup(&mut self, fixup: Option<FixupFn>) {
+        self.fixup = fixup;
+    }
+
+    /// The wrapped mutator
+    #[must_use]
+    pub fn inner(&self) -> &M {
+        &self.mutator
+    }
+
+    /// The wrapped mutator (as mutable borrow)
+    pub fn inner_mut(&mut self) -> &mut M {
+        &mut self.mutator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::InMemoryCorpus,
+        inputs::{BytesInput, HasBytesVec},
+        mutators::{fixup::FixupMutator, BitFlipMutator, MutationResult, Mutator},
+        state::StdState,
+    };
+
+    /// The last byte is the sum of all others
+    #[allow(clippy::ptr_arg)]
+    fn fix_checksum(bytes: &mut Vec<u8>) {
+        let (last, data) = bytes.split_last_mut().unwrap();
+        *last = data.iter().fold(0_u8, |sum, &b| sum.wrapping_add(b));
+    }
+
+    #[test]
+    fn test_fixup_mutator() {
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let original = vec![1, 2, 3, 4, 10];
+        let mut input = BytesInput::new(original.clone());
+
+        let mut mutator = FixupMutator::new(BitFlipMutator::new(), fix_checksum);
+        for _ in 0..8 {
+            assert_eq!(
+                mutator.mutate(&mut state, &mut input, 0).unwrap(),
+                MutationResult::Mutated
+            );
+            let sum = input.bytes()[..4]
+                .iter()
+                .fold(0_u8, |sum, &b| sum.wrapping_add(b));
+            assert_eq!(input.bytes()[4], sum);
+        }
+
+        // Without a fixup, the checksum breaks
+        let mut input = BytesInput::new(original);
+        let mut mutator = FixupMutator::without_fixup(BitFlipMutator::new());
+        mutator.mutate(&mut state, &mut input, 0).unwrap();
+        let sum = input.bytes()[..4]
+            .iter()
+            .fold(0_u8, |sum, &b| sum.wrapping_add(b));
+        assert_ne!(input.bytes()[4], sum);
+    }
+}
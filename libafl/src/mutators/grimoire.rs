@@ -8,7 +8,7 @@ use crate::{
     bolts::{rands::Rand, tuples::Named},
     corpus::Corpus,
     inputs::{GeneralizedInput, GeneralizedItem},
-    mutators::{token_mutations::Tokens, MutationResult, Mutator},
+    mutators::{mutations::ARITH_MAX, token_mutations::Tokens, MutationResult, Mutator},
     stages::generalization::GeneralizedIndexesMetadata,
     state::{HasCorpus, HasMetadata, HasRand},
     Error,
@@ -407,3 +407,160 @@ impl GrimoireRandomDeleteMutator {
         }
     }
 }
+
+/// The default amount of bytes next to a gap [`BoundaryHavocMutator`] mutates
+pub const DEFAULT_BOUNDARY_WINDOW: usize = 4;
+
+/// Mutates a byte at the seams of the generalized input, in the first or last bytes of a chunk next to a gap,
+/// where parsers transition state.
+/// Skips inputs that are not generalized.
+#[derive(Debug)]
+pub struct BoundaryHavocMutator {
+    window: usize,
+    /// The candidate positions, as index of the chunk and of the byte in the chunk
+    boundaries: Vec<(usize, usize)>,
+}
+
+impl<S> Mutator<GeneralizedInput, S> for BoundaryHavocMutator
+where
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut GeneralizedInput,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let gen = match input.generalized_mut() {
+            Some(gen) => gen,
+            None => return Ok(MutationResult::Skipped),
+        };
+
+        self.boundaries.clear();
+        for (i, item) in gen.iter().enumerate() {
+            if let GeneralizedItem::Bytes(bytes) = item {
+                let window = min(self.window, bytes.len());
+                let gap_before = i > 0 && gen[i - 1] == GeneralizedItem::Gap;
+                let gap_after = gen.get(i + 1) == Some(&GeneralizedItem::Gap);
+                for j in 0..bytes.len() {
+                    if (gap_before && j < window) || (gap_after && j >= bytes.len() - window) {
+                        self.boundaries.push((i, j));
+                    }
+                }
+            }
+        }
+        if self.boundaries.is_empty() {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let (i, j) = *state.rand_mut().choose(&self.boundaries);
+        let byte = match &mut gen[i] {
+            GeneralizedItem::Bytes(bytes) => &mut bytes[j],
+            GeneralizedItem::Gap => unreachable!(),
+        };
+        // Each mutation changes the byte
+        *byte = match state.rand_mut().below(4) {
+            0 => *byte ^ (1 << state.rand_mut().below(8)),
+            1 => *byte ^ (1 + state.rand_mut().below(255) as u8),
+            2 => byte.wrapping_add(1 + state.rand_mut().below(ARITH_MAX) as u8),
+            _ => byte.wrapping_sub(1 + state.rand_mut().below(ARITH_MAX) as u8),
+        };
+        input.grimoire_mutated = true;
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for BoundaryHavocMutator {
+    fn name(&self) -> &str {
+        "BoundaryHavocMutator"
+    }
+}
+
+impl Default for BoundaryHavocMutator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BoundaryHavocMutator {
+    /// Creates a new [`BoundaryHavocMutator`], mutating the [`DEFAULT_BOUNDARY_WINDOW`] bytes next to each gap
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_window(DEFAULT_BOUNDARY_WINDOW)
+    }
+
+    /// Creates a new [`BoundaryHavocMutator`], mutating the `window` bytes next to each gap
+    #[must_use]
+    pub fn with_window(window: usize) -> Self {
+        Self {
+            window,
+            boundaries: vec![],
+        }
+    }
+
+    /// The amount of bytes next to a gap this mutator mutates
+    #[must_use]
+    pub fn window(&self) -> usize {
+        self.window
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::InMemoryCorpus,
+        inputs::{GeneralizedInput, GeneralizedItem},
+        mutators::{BoundaryHavocMutator, MutationResult, Mutator},
+        state::StdState,
+    };
+
+    #[test]
+    fn test_boundary_havoc_mutator() {
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<GeneralizedInput>::new(),
+            InMemoryCorpus::<GeneralizedInput>::new(),
+            (),
+        );
+        let mut mutator = BoundaryHavocMutator::with_window(4);
+
+        // Not generalized, nothing to do
+        let mut input = GeneralizedInput::new(b"not generalized".to_vec());
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input, 0).unwrap(),
+            MutationResult::Skipped
+        );
+
+        let mut generalized = GeneralizedInput::new(vec![]);
+        generalized.generalized_extend(&[
+            GeneralizedItem::Gap,
+            GeneralizedItem::Bytes(vec![b'a'; 32]),
+            GeneralizedItem::Gap,
+        ]);
+
+        // Count the mutations of each byte of the chunk
+        let mut density = [0_usize; 32];
+        for _ in 0..1000 {
+            let mut input = generalized.clone();
+            assert_eq!(
+                mutator.mutate(&mut state, &mut input, 0).unwrap(),
+                MutationResult::Mutated
+            );
+            assert!(input.grimoire_mutated);
+            if let GeneralizedItem::Bytes(bytes) = &input.generalized().unwrap()[1] {
+                for (count, &byte) in density.iter_mut().zip(bytes.iter()) {
+                    if byte != b'a' {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+
+        // All mutations hit the bytes next to the gaps, none the middle of the chunk
+        assert_eq!(density.iter().sum::<usize>(), 1000);
+        assert!(density[..4].iter().all(|&count| count > 0));
+        assert!(density[28..].iter().all(|&count| count > 0));
+        assert!(density[4..28].iter().all(|&count| count == 0));
+    }
+}
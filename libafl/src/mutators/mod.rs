@@ -14,6 +14,14 @@ pub mod gramatron;
 pub use gramatron::*;
 pub mod grimoire;
 pub use grimoire::*;
+pub mod multi;
+pub use multi::*;
+pub mod structured;
+pub use structured::*;
+pub mod fixup;
+pub use fixup::*;
+pub mod region;
+pub use region::*;
 
 #[cfg(feature = "nautilus")]
 pub mod nautilus;
@@ -103,6 +111,12 @@ where
         stage_idx: i32,
         corpus_idx: Option<usize>,
     ) -> Result<(), Error>;
+
+    /// Gets the name of the [`Mutator`] at the given index.
+    /// Empty, unless implemented.
+    fn get_name(&self, _index: usize) -> Option<&str> {
+        None
+    }
 }
 
 impl<I, S> MutatorsTuple<I, S> for ()
@@ -206,4 +220,12 @@ where
                 .get_and_post_exec(index - 1, state, stage_idx, corpus_idx)
         }
     }
+
+    fn get_name(&self, index: usize) -> Option<&str> {
+        if index == 0 {
+            Some(self.0.name())
+        } else {
+            self.1.get_name(index - 1)
+        }
+    }
 }
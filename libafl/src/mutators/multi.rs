@@ -0,0 +1,236 @@
+//! Mutations for the [`MultiInput`], changing the sequence of messages, or the bytes of a single message.
+
+use crate::{
+    bolts::{rands::Rand, tuples::Named},
+    inputs::{BytesInput, MultiInput},
+    mutators::{MutationResult, Mutator},
+    state::HasRand,
+    Error,
+};
+
+/// The default upper bound for the amount of messages in a [`MultiInput`], enforced by the [`MessageInsertMutator`]
+pub const DEFAULT_MAX_MESSAGES: usize = 32;
+
+/// Inserts a copy of a random message at a random position of the sequence,
+/// or an empty message into an empty sequence
+#[derive(Debug)]
+pub struct MessageInsertMutator {
+    max_messages: usize,
+}
+
+impl<S> Mutator<MultiInput, S> for MessageInsertMutator
+where
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut MultiInput,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let messages = input.messages_mut();
+        if messages.len() >= self.max_messages {
+            return Ok(MutationResult::Skipped);
+        }
+        let message = if messages.is_empty() {
+            BytesInput::new(vec![])
+        } else {
+            state.rand_mut().choose(messages.iter()).clone()
+        };
+        let idx = state.rand_mut().below(messages.len() as u64 + 1) as usize;
+        messages.insert(idx, message);
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for MessageInsertMutator {
+    fn name(&self) -> &str {
+        "MessageInsertMutator"
+    }
+}
+
+impl MessageInsertMutator {
+    /// Creates a new [`MessageInsertMutator`], with the [`DEFAULT_MAX_MESSAGES`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_max_messages(DEFAULT_MAX_MESSAGES)
+    }
+
+    /// Creates a new [`MessageInsertMutator`], never growing the sequence over `max_messages`
+    #[must_use]
+    pub fn with_max_messages(max_messages: usize) -> Self {
+        Self { max_messages }
+    }
+}
+
+impl Default for MessageInsertMutator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Removes a random message from the sequence, keeping at least one
+#[derive(Default, Debug)]
+pub struct MessageRemoveMutator;
+
+impl<S> Mutator<MultiInput, S> for MessageRemoveMutator
+where
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut MultiInput,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let messages = input.messages_mut();
+        if messages.len() <= 1 {
+            return Ok(MutationResult::Skipped);
+        }
+        let idx = state.rand_mut().below(messages.len() as u64) as usize;
+        messages.remove(idx);
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for MessageRemoveMutator {
+    fn name(&self) -> &str {
+        "MessageRemoveMutator"
+    }
+}
+
+impl MessageRemoveMutator {
+    /// Creates a new [`MessageRemoveMutator`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Swaps two random messages of the sequence
+#[derive(Default, Debug)]
+pub struct MessageSwapMutator;
+
+impl<S> Mutator<MultiInput, S> for MessageSwapMutator
+where
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut MultiInput,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let messages = input.messages_mut();
+        if messages.len() < 2 {
+            return Ok(MutationResult::Skipped);
+        }
+        let first = state.rand_mut().below(messages.len() as u64) as usize;
+        let second = state.rand_mut().below(messages.len() as u64) as usize;
+        if first == second || messages[first] == messages[second] {
+            return Ok(MutationResult::Skipped);
+        }
+        messages.swap(first, second);
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for MessageSwapMutator {
+    fn name(&self) -> &str {
+        "MessageSwapMutator"
+    }
+}
+
+impl MessageSwapMutator {
+    /// Creates a new [`MessageSwapMutator`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Applies a [`BytesInput`] mutator to a random message of the sequence.
+/// The state holds a corpus of [`MultiInput`]s, so the mutator must not rely on a corpus of [`BytesInput`]s,
+/// such as the crossover mutators of the havoc mutations do.
+#[derive(Debug)]
+pub struct MessageMutator<M> {
+    mutator: M,
+}
+
+impl<M, S> Mutator<MultiInput, S> for MessageMutator<M>
+where
+    M: Mutator<BytesInput, S>,
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut MultiInput,
+        stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        if input.messages().is_empty() {
+            return Ok(MutationResult::Skipped);
+        }
+        let idx = state.rand_mut().below(input.messages().len() as u64) as usize;
+        self.mutator
+            .mutate(state, &mut input.messages_mut()[idx], stage_idx)
+    }
+}
+
+impl<M> Named for MessageMutator<M> {
+    fn name(&self) -> &str {
+        "MessageMutator"
+    }
+}
+
+impl<M> MessageMutator<M> {
+    /// Creates a new [`MessageMutator`], mutating single messages with the given mutator
+    #[must_use]
+    pub fn new(mutator: M) -> Self {
+        Self { mutator }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::InMemoryCorpus,
+        inputs::{BytesInput, HasBytesVec, MultiInput},
+        mutators::{
+            multi::{MessageInsertMutator, MessageMutator, MessageRemoveMutator},
+            BitFlipMutator, MutationResult, Mutator,
+        },
+        state::StdState,
+    };
+
+    #[test]
+    fn test_message_mutators() {
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<MultiInput>::new(),
+            InMemoryCorpus::<MultiInput>::new(),
+            (),
+        );
+        let mut input = MultiInput::new(vec![BytesInput::new(vec![0; 4])]);
+
+        let mut insert = MessageInsertMutator::with_max_messages(3);
+        for _ in 0..4 {
+            insert.mutate(&mut state, &mut input, 0).unwrap();
+        }
+        assert_eq!(input.messages().len(), 3);
+
+        let mut remove = MessageRemoveMutator::new();
+        for _ in 0..4 {
+            remove.mutate(&mut state, &mut input, 0).unwrap();
+        }
+        assert_eq!(input.messages().len(), 1);
+
+        let mut bitflip = MessageMutator::new(BitFlipMutator::new());
+        assert_eq!(
+            bitflip.mutate(&mut state, &mut input, 0).unwrap(),
+            MutationResult::Mutated
+        );
+        assert_ne!(input.messages()[0].bytes(), &[0; 4]);
+    }
+}
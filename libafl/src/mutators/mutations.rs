@@ -5,7 +5,7 @@ use crate::{
     corpus::Corpus,
     inputs::{HasBytesVec, Input},
     mutators::{MutationResult, Mutator},
-    state::{HasCorpus, HasMaxSize, HasRand},
+    state::{HasCorpus, HasMaxSize, HasRand, HasSolutions},
     Error,
 };
 
@@ -59,6 +59,23 @@ pub fn buffer_set<T: Clone>(data: &mut [T], from: usize, len: usize, val: T) {
 /// The max value that will be added or subtracted during add mutations
 pub const ARITH_MAX: u64 = 35;
 
+/// The byte order the arithmetic and interesting value mutations use for the integers they write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    /// Pick the byte order at random for each mutation
+    Random,
+    /// Big-endian, as used by most network and file formats
+    Big,
+    /// Little-endian
+    Little,
+}
+
+impl Default for Endianness {
+    fn default() -> Self {
+        Endianness::Random
+    }
+}
+
 /// Interesting 8-bit values from AFL
 pub const INTERESTING_8: [i8; 9] = [-128, -1, 0, 1, 16, 32, 64, 100, 127];
 /// Interesting 16-bit values from AFL
@@ -334,9 +351,12 @@ impl ByteRandMutator {
 // within the input are treated as u8, u16, u32, or u64, then mutated in place.
 macro_rules! add_mutator_impl {
     ($name: ident, $size: ty) => {
-        /// Adds or subtracts a random value up to `ARITH_MAX` to a [`<$size>`] at a random place in the [`Vec`], in random byte order.
+        /// Adds or subtracts a random value up to `ARITH_MAX` to a [`<$size>`] at a random place in the [`Vec`],
+        /// in random byte order unless set with [`$name::with_endianness`].
         #[derive(Default, Debug)]
-        pub struct $name;
+        pub struct $name {
+            endianness: Endianness,
+        }
 
         #[allow(trivial_numeric_casts)]
         impl<I, S> Mutator<I, S> for $name
@@ -357,20 +377,42 @@ macro_rules! add_mutator_impl {
                     let (index, bytes) = state
                         .rand_mut()
                         .choose(input.bytes().windows(size_of::<$size>()).enumerate());
-                    let val = <$size>::from_ne_bytes(bytes.try_into().unwrap());
+                    let bytes: [u8; size_of::<$size>()] = bytes.try_into().unwrap();
 
                     // mutate
                     let num = 1 + state.rand_mut().below(ARITH_MAX) as $size;
-                    let new_val = match state.rand_mut().below(4) {
-                        0 => val.wrapping_add(num),
-                        1 => val.wrapping_sub(num),
-                        2 => val.swap_bytes().wrapping_add(num).swap_bytes(),
-                        _ => val.swap_bytes().wrapping_sub(num).swap_bytes(),
+                    let new_bytes = match self.endianness {
+                        Endianness::Random => {
+                            let val = <$size>::from_ne_bytes(bytes);
+                            match state.rand_mut().below(4) {
+                                0 => val.wrapping_add(num),
+                                1 => val.wrapping_sub(num),
+                                2 => val.swap_bytes().wrapping_add(num).swap_bytes(),
+                                _ => val.swap_bytes().wrapping_sub(num).swap_bytes(),
+                            }
+                            .to_ne_bytes()
+                        }
+                        Endianness::Big => {
+                            let val = <$size>::from_be_bytes(bytes);
+                            match state.rand_mut().below(2) {
+                                0 => val.wrapping_add(num),
+                                _ => val.wrapping_sub(num),
+                            }
+                            .to_be_bytes()
+                        }
+                        Endianness::Little => {
+                            let val = <$size>::from_le_bytes(bytes);
+                            match state.rand_mut().below(2) {
+                                0 => val.wrapping_add(num),
+                                _ => val.wrapping_sub(num),
+                            }
+                            .to_le_bytes()
+                        }
                     };
 
                     // set bytes to mutated value
-                    let new_bytes = &mut input.bytes_mut()[index..index + size_of::<$size>()];
-                    new_bytes.copy_from_slice(&new_val.to_ne_bytes());
+                    input.bytes_mut()[index..index + size_of::<$size>()]
+                        .copy_from_slice(&new_bytes);
                     Ok(MutationResult::Mutated)
                 }
             }
@@ -383,10 +425,16 @@ macro_rules! add_mutator_impl {
         }
 
         impl $name {
-            /// Creates a new [`$name`].
+            /// Creates a new [`$name`], using a random byte order.
             #[must_use]
             pub fn new() -> Self {
-                Self
+                Self::default()
+            }
+
+            /// Creates a new [`$name`], using the given byte order.
+            #[must_use]
+            pub fn with_endianness(endianness: Endianness) -> Self {
+                Self { endianness }
             }
         }
     };
@@ -401,9 +449,12 @@ add_mutator_impl!(QwordAddMutator, u64);
 
 macro_rules! interesting_mutator_impl {
     ($name: ident, $size: ty, $interesting: ident) => {
-        /// Inserts an interesting value at a random place in the input vector
+        /// Inserts an interesting value at a random place in the input vector,
+        /// in random byte order unless set with [`$name::with_endianness`].
         #[derive(Default, Debug)]
-        pub struct $name;
+        pub struct $name {
+            endianness: Endianness,
+        }
 
         impl<I, S> Mutator<I, S> for $name
         where
@@ -424,9 +475,13 @@ macro_rules! interesting_mutator_impl {
                     let upper_bound = (bytes.len() + 1 - size_of::<$size>()) as u64;
                     let idx = state.rand_mut().below(upper_bound) as usize;
                     let val = *state.rand_mut().choose(&$interesting) as $size;
-                    let new_bytes = match state.rand_mut().choose(&[0, 1]) {
-                        0 => val.to_be_bytes(),
-                        _ => val.to_le_bytes(),
+                    let new_bytes = match self.endianness {
+                        Endianness::Random => match state.rand_mut().choose(&[0, 1]) {
+                            0 => val.to_be_bytes(),
+                            _ => val.to_le_bytes(),
+                        },
+                        Endianness::Big => val.to_be_bytes(),
+                        Endianness::Little => val.to_le_bytes(),
                     };
                     bytes[idx..idx + size_of::<$size>()].copy_from_slice(&new_bytes);
                     Ok(MutationResult::Mutated)
@@ -441,10 +496,16 @@ macro_rules! interesting_mutator_impl {
         }
 
         impl $name {
-            /// Creates a new [`$name`].
+            /// Creates a new [`$name`], using a random byte order.
             #[must_use]
             pub fn new() -> Self {
-                Self
+                Self::default()
+            }
+
+            /// Creates a new [`$name`], using the given byte order.
+            #[must_use]
+            pub fn with_endianness(endianness: Endianness) -> Self {
+                Self { endianness }
             }
         }
     };
@@ -894,6 +955,102 @@ impl BytesSwapMutator {
     }
 }
 
+/// Picks a random region of at least two bytes in `input`, returning its start and length
+fn rand_region<I, S>(state: &mut S, input: &I) -> Option<(usize, usize)>
+where
+    I: HasBytesVec,
+    S: HasRand,
+{
+    let size = input.bytes().len();
+    if size <= 1 {
+        return None;
+    }
+    let start = state.rand_mut().below((size - 1) as u64) as usize;
+    let len = 2 + state.rand_mut().below((size - start - 1) as u64) as usize;
+    Some((start, len))
+}
+
+/// Reverses a random region of the bytes vector, keeping its length.
+/// Reaches reordered inputs, such as swapped record fields, that bit flips rarely do.
+#[derive(Debug, Default)]
+pub struct ReverseRegionMutator;
+
+impl<I, S> Mutator<I, S> for ReverseRegionMutator
+where
+    I: Input + HasBytesVec,
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        match rand_region(state, input) {
+            Some((start, len)) => {
+                input.bytes_mut()[start..start + len].reverse();
+                Ok(MutationResult::Mutated)
+            }
+            None => Ok(MutationResult::Skipped),
+        }
+    }
+}
+
+impl Named for ReverseRegionMutator {
+    fn name(&self) -> &str {
+        "ReverseRegionMutator"
+    }
+}
+
+impl ReverseRegionMutator {
+    /// Creates a new [`ReverseRegionMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Rotates a random region of the bytes vector to the left, by a random amount, keeping its length.
+#[derive(Debug, Default)]
+pub struct RotateRegionMutator;
+
+impl<I, S> Mutator<I, S> for RotateRegionMutator
+where
+    I: Input + HasBytesVec,
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        match rand_region(state, input) {
+            Some((start, len)) => {
+                // Rotating by the length of the region would keep it as it is
+                let amount = 1 + state.rand_mut().below((len - 1) as u64) as usize;
+                input.bytes_mut()[start..start + len].rotate_left(amount);
+                Ok(MutationResult::Mutated)
+            }
+            None => Ok(MutationResult::Skipped),
+        }
+    }
+}
+
+impl Named for RotateRegionMutator {
+    fn name(&self) -> &str {
+        "RotateRegionMutator"
+    }
+}
+
+impl RotateRegionMutator {
+    /// Creates a new [`RotateRegionMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
 /// Crossover insert mutation for inputs with a bytes vector
 #[derive(Debug, Default)]
 pub struct CrossoverInsertMutator;
@@ -1036,6 +1193,78 @@ impl CrossoverReplaceMutator {
     }
 }
 
+/// Splice mutation inserting a random part of a solution (e.g., a crashing input) into the input.
+/// Solutions often contain magic values, useful to reach related bugs.
+#[derive(Debug, Default)]
+pub struct SolutionSpliceMutator;
+
+impl<I, S> Mutator<I, S> for SolutionSpliceMutator
+where
+    I: Input + HasBytesVec,
+    S: HasRand + HasSolutions<I> + HasMaxSize,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let count = state.solutions().count();
+        if count == 0 {
+            return Ok(MutationResult::Skipped);
+        }
+        let idx = state.rand_mut().below(count as u64) as usize;
+
+        let other_size = state
+            .solutions()
+            .get(idx)?
+            .borrow_mut()
+            .load_input()?
+            .bytes()
+            .len();
+        if other_size == 0 {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let size = input.bytes().len();
+        let max_size = state.max_size();
+        let from = state.rand_mut().below(other_size as u64) as usize;
+        let to = state.rand_mut().below((size + 1) as u64) as usize;
+        let mut len = 1 + state.rand_mut().below((other_size - from) as u64) as usize;
+
+        if size + len > max_size {
+            if max_size > size {
+                len = max_size - size;
+            } else {
+                return Ok(MutationResult::Skipped);
+            }
+        }
+
+        let mut other_testcase = state.solutions().get(idx)?.borrow_mut();
+        let other = other_testcase.load_input()?;
+
+        input.bytes_mut().resize(size + len, 0);
+        buffer_self_copy(input.bytes_mut(), to, to + len, size - to);
+        buffer_copy(input.bytes_mut(), other.bytes(), from, to, len);
+
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for SolutionSpliceMutator {
+    fn name(&self) -> &str {
+        "SolutionSpliceMutator"
+    }
+}
+
+impl SolutionSpliceMutator {
+    /// Creates a new [`SolutionSpliceMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
 /// Returns the first and last diff position between the given vectors, stopping at the min len
 fn locate_diffs(this: &[u8], other: &[u8]) -> (i64, i64) {
     let mut first_diff: i64 = -1;
@@ -1208,6 +1437,8 @@ mod tests {
             BytesRandSetMutator::new(),
             BytesCopyMutator::new(),
             BytesSwapMutator::new(),
+            ReverseRegionMutator::new(),
+            RotateRegionMutator::new(),
         )
     }
 
@@ -1250,4 +1481,119 @@ mod tests {
             inputs.append(&mut new_testcases);
         }
     }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn test_big_endian_mutations() {
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        // A big-endian u32 length field
+        let field = 0x0100_u32.to_be_bytes();
+
+        let mut add = DwordAddMutator::with_endianness(Endianness::Big);
+        let mut interesting = DwordInterestingMutator::with_endianness(Endianness::Big);
+        for _ in 0..100 {
+            let mut input = BytesInput::new(field.to_vec());
+            add.mutate(&mut state, &mut input, 0).unwrap();
+            let val = u32::from_be_bytes(input.bytes().try_into().unwrap());
+            assert_ne!(val, 0x0100);
+            assert!((0x0100 - ARITH_MAX as u32..=0x0100 + ARITH_MAX as u32).contains(&val));
+
+            let mut input = BytesInput::new(field.to_vec());
+            interesting.mutate(&mut state, &mut input, 0).unwrap();
+            let val = u32::from_be_bytes(input.bytes().try_into().unwrap());
+            assert!(INTERESTING_32.contains(&(val as i32)));
+        }
+    }
+
+    #[test]
+    fn test_solution_splice() {
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        let mut mutator = SolutionSpliceMutator::new();
+
+        // Nothing to splice from yet
+        let mut input = BytesInput::new(vec![0; 16]);
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input, 0).unwrap(),
+            MutationResult::Skipped
+        );
+
+        state
+            .solutions_mut()
+            .add(BytesInput::new(vec![0xAA; 8]).into())
+            .unwrap();
+        for _ in 0..10 {
+            let mut input = BytesInput::new(vec![0; 16]);
+            assert_eq!(
+                mutator.mutate(&mut state, &mut input, 0).unwrap(),
+                MutationResult::Mutated
+            );
+            let spliced = input.bytes().iter().filter(|&&b| b == 0xAA).count();
+            assert!(spliced > 0);
+            assert_eq!(input.bytes().len(), 16 + spliced);
+        }
+    }
+
+    #[test]
+    fn test_region_reorder_mutations() {
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+        let original: Vec<u8> = (0..32).collect();
+
+        let mut reverse = ReverseRegionMutator::new();
+        let mut rotate = RotateRegionMutator::new();
+        for _ in 0..100 {
+            // A reversed region is a descending run somewhere in the input, the rest stays in place
+            let mut input = BytesInput::new(original.clone());
+            assert_eq!(
+                reverse.mutate(&mut state, &mut input, 0).unwrap(),
+                MutationResult::Mutated
+            );
+            let bytes = input.bytes();
+            assert_eq!(bytes.len(), original.len());
+            let start = bytes.iter().zip(&original).position(|(a, b)| a != b);
+            let end = bytes.iter().zip(&original).rposition(|(a, b)| a != b);
+            if let (Some(start), Some(end)) = (start, end) {
+                assert!(bytes[start..=end].windows(2).all(|w| w[0] == w[1] + 1));
+                assert_eq!(bytes[start], original[end]);
+            }
+
+            // A rotated region is never left as it was, and holds the same bytes
+            let mut input = BytesInput::new(original.clone());
+            assert_eq!(
+                rotate.mutate(&mut state, &mut input, 0).unwrap(),
+                MutationResult::Mutated
+            );
+            let bytes = input.bytes();
+            assert_eq!(bytes.len(), original.len());
+            assert_ne!(bytes, &original[..]);
+            let mut sorted = bytes.to_vec();
+            sorted.sort_unstable();
+            assert_eq!(sorted, original);
+        }
+
+        for mut input in [BytesInput::new(vec![]), BytesInput::new(vec![42])] {
+            assert_eq!(
+                reverse.mutate(&mut state, &mut input, 0).unwrap(),
+                MutationResult::Skipped
+            );
+            assert_eq!(
+                rotate.mutate(&mut state, &mut input, 0).unwrap(),
+                MutationResult::Skipped
+            );
+        }
+    }
 }
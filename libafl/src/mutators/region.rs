@@ -0,0 +1,214 @@
+//! The [`RegionMutator`] restricts a mutator to a byte region of the input,
+//! keeping fixed frames around the region intact, without a structured input.
+
+use core::ops::Range;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::Named,
+    inputs::{HasBytesVec, Input},
+    mutators::{MutationResult, Mutator},
+    state::{HasMaxSize, HasMetadata},
+    Error,
+};
+
+/// The region a [`RegionMutator`] created with [`RegionMutator::from_metadata`] mutates,
+/// stored in the metadata of the state
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MutationRegionMetadata {
+    /// The byte range of the region
+    pub region: Range<usize>,
+}
+
+crate::impl_serdeany!(MutationRegionMetadata);
+
+impl MutationRegionMetadata {
+    /// Creates a new [`MutationRegionMetadata`] for the given byte range
+    #[must_use]
+    pub fn new(region: Range<usize>) -> Self {
+        Self { region }
+    }
+}
+
+/// Wraps a mutator, so that it only sees, and mutates, a byte region of the input.
+/// The wrapped mutator works on the region as if it was the whole input, so its offsets are relative to the region.
+/// Growing or shrinking mutations change the length of the region, the bytes after it move along unchanged.
+/// A mutation growing the whole input past the max size of the state is skipped.
+/// A region reaching past the end of the input is cut to the input.
+#[derive(Debug)]
+pub struct RegionMutator<M> {
+    mutator: M,
+    region: Option<Range<usize>>,
+}
+
+impl<I, M, S> Mutator<I, S> for RegionMutator<M>
+where
+    I: Input + HasBytesVec,
+    M: Mutator<I, S>,
+    S: HasMetadata + HasMaxSize,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let region = match &self.region {
+            Some(region) => region.clone(),
+            None => match state.metadata().get::<MutationRegionMetadata>() {
+                Some(meta) => meta.region.clone(),
+                // Without a region, there is nothing we may mutate
+                None => return Ok(MutationResult::Skipped),
+            },
+        };
+        let len = input.bytes().len();
+        let start = region.start.min(len);
+        let end = region.end.clamp(start, len);
+
+        // The wrapped mutator sees the region in place of the input, the other bytes are put aside meanwhile
+        let bytes = core::mem::take(input.bytes_mut());
+        input.bytes_mut().extend_from_slice(&bytes[start..end]);
+        let result = self.mutator.mutate(state, input, stage_idx);
+        let part = core::mem::replace(input.bytes_mut(), bytes);
+        match result? {
+            MutationResult::Mutated if len - (end - start) + part.len() <= state.max_size() => {
+                input.bytes_mut().splice(start..end, part);
+                Ok(MutationResult::Mutated)
+            }
+            // The region grew the input past the max size
+            MutationResult::Mutated => Ok(MutationResult::Skipped),
+            MutationResult::Skipped => Ok(MutationResult::Skipped),
+        }
+    }
+
+    fn post_exec(
+        &mut self,
+        state: &mut S,
+        stage_idx: i32,
+        corpus_idx: Option<usize>,
+    ) -> Result<(), Error> {
+        self.mutator.post_exec(state, stage_idx, corpus_idx)
+    }
+}
+
+impl<M> Named for RegionMutator<M> {
+    fn name(&self) -> &str {
+        "RegionMutator"
+    }
+}
+
+impl<M> RegionMutator<M> {
+    /// Creates a new [`RegionMutator`], letting the wrapped `mutator` mutate the bytes in `region` only
+    #[must_use]
+    pub fn new(mutator: M, region: Range<usize>) -> Self {
+        Self {
+            mutator,
+            region: Some(region),
+        }
+    }
+
+    /// Creates a new [`RegionMutator`], taking the region from the [`MutationRegionMetadata`] of the state.
+    /// Without the metadata, each mutation is skipped.
+    #[must_use]
+    pub fn from_metadata(mutator: M) -> Self {
+        Self {
+            mutator,
+            region: None,
+        }
+    }
+
+    /// The fixed region, or `None` if it is taken from the [`MutationRegionMetadata`]
+    #[must_use]
+    pub fn region(&self) -> Option<&Range<usize>> {
+        self.region.as_ref()
+    }
+
+    /// The wrapped mutator
+    #[must_use]
+    pub fn inner(&self) -> &M {
+        &self.mutator
+    }
+
+    /// The wrapped mutator (as mutable borrow)
+    pub fn inner_mut(&mut self) -> &mut M {
+        &mut self.mutator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        inputs::{BytesInput, HasBytesVec},
+        mutators::{
+            region::{MutationRegionMetadata, RegionMutator},
+            BitFlipMutator, ByteRandMutator, BytesDeleteMutator, BytesExpandMutator,
+            BytesInsertMutator, MutationResult, Mutator, StdScheduledMutator,
+        },
+        state::{HasMaxSize, HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_region_mutator() {
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let original = BytesInput::new(b"HEADpayloadTAIL".to_vec());
+
+        let mut mutator = RegionMutator::new(
+            StdScheduledMutator::new(tuple_list!(
+                BitFlipMutator::new(),
+                ByteRandMutator::new(),
+                BytesDeleteMutator::new(),
+                BytesExpandMutator::new(),
+                BytesInsertMutator::new()
+            )),
+            4..11,
+        );
+        let mut mutated = false;
+        for _ in 0..100 {
+            let mut input = original.clone();
+            mutated |=
+                mutator.mutate(&mut state, &mut input, 0).unwrap() == MutationResult::Mutated;
+            // The bytes outside of the region never change, even if the region grew or shrunk
+            let bytes = input.bytes();
+            assert!(bytes.len() >= 8);
+            assert!(bytes.starts_with(b"HEAD"));
+            assert!(bytes.ends_with(b"TAIL"));
+        }
+        assert!(mutated);
+
+        // The region from the metadata
+        let mut mutator = RegionMutator::from_metadata(BitFlipMutator::new());
+        let mut input = BytesInput::new(b"abcdef".to_vec());
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input, 0).unwrap(),
+            MutationResult::Skipped
+        );
+        state.add_metadata(MutationRegionMetadata::new(2..3));
+        for _ in 0..10 {
+            assert_eq!(
+                mutator.mutate(&mut state, &mut input, 0).unwrap(),
+                MutationResult::Mutated
+            );
+            assert_eq!(&input.bytes()[..2], b"ab");
+            assert_eq!(&input.bytes()[3..], b"def");
+        }
+
+        // The whole input stays within the max size, not only the region
+        state.set_max_size(8);
+        let mut mutator = RegionMutator::new(BytesExpandMutator::new(), 0..2);
+        for _ in 0..100 {
+            let mut input = BytesInput::new(b"abcdef".to_vec());
+            if mutator.mutate(&mut state, &mut input, 0).unwrap() == MutationResult::Skipped {
+                assert_eq!(input.bytes(), b"abcdef");
+            }
+            assert!(input.bytes().len() <= 8);
+            assert!(input.bytes().ends_with(b"cdef"));
+        }
+    }
+}
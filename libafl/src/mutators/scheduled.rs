@@ -53,6 +53,136 @@ impl LogMutationMetadata {
     }
 }
 
+/// A single mutation applied to an input, see [`MutationTrailMetadata`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MutationTrailEntry {
+    /// The name of the mutator
+    pub mutation: String,
+    /// If the mutator changed the input, or skipped
+    pub mutated: bool,
+}
+
+/// The mutations a [`MetadataScheduledMutator`] with enabled trail applied to derive the last input from its parent, in order.
+/// It lives in the state from the mutation until the `post_exec` of the mutator, and gets stored in testcases,
+/// including solutions, by the [`crate::feedbacks::MutationTrailFeedback`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MutationTrailMetadata {
+    /// The applied mutations
+    pub trail: Vec<MutationTrailEntry>,
+}
+
+crate::impl_serdeany!(MutationTrailMetadata);
+
+/// Weights for sampling the mutations of a [`StdScheduledMutator`], by mutator name.
+/// Mutations without an explicit weight get the default weight, a weight of `0` disables a mutation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HavocProfile {
+    default_weight: u64,
+    weights: Vec<(String, u64)>,
+}
+
+impl HavocProfile {
+    /// Creates a new [`HavocProfile`], weighting all mutations with `default_weight`
+    #[must_use]
+    pub fn new(default_weight: u64) -> Self {
+        Self {
+            default_weight,
+            weights: vec![],
+        }
+    }
+
+    /// Sets the weight of the mutator called `name`
+    #[must_use]
+    pub fn with_weight(mut self, name: &str, weight: u64) -> Self {
+        match self.weights.iter_mut().find(|(n, _)| n == name) {
+            Some(entry) => entry.1 = weight,
+            None => self.weights.push((name.into(), weight)),
+        }
+        self
+    }
+
+    /// The weight of the mutator called `name`
+    #[must_use]
+    pub fn weight(&self, name: &str) -> u64 {
+        self.weights
+            .iter()
+            .find(|(n, _)| n == name)
+            .map_or(self.default_weight, |(_, weight)| *weight)
+    }
+
+    /// A profile for text inputs, favoring character swaps, copies, and tokens over bit-level and wide arithmetic mutations
+    #[must_use]
+    pub fn text() -> Self {
+        Self::new(2)
+            .with_weight("BitFlipMutator", 1)
+            .with_weight("ByteFlipMutator", 1)
+            .with_weight("ByteNegMutator", 1)
+            .with_weight("WordAddMutator", 1)
+            .with_weight("DwordAddMutator", 1)
+            .with_weight("QwordAddMutator", 1)
+            .with_weight("WordInterestingMutator", 1)
+            .with_weight("DwordInterestingMutator", 1)
+            .with_weight("ByteIncMutator", 3)
+            .with_weight("ByteDecMutator", 3)
+            .with_weight("ByteRandMutator", 3)
+            .with_weight("BytesInsertMutator", 3)
+            .with_weight("BytesCopyMutator", 4)
+            .with_weight("BytesInsertCopyMutator", 4)
+            .with_weight("BytesSwapMutator", 6)
+            .with_weight("TokenInsert", 6)
+            .with_weight("TokenReplace", 6)
+    }
+
+    /// A profile for binary inputs, favoring bit flips, arithmetic, and interesting values
+    #[must_use]
+    pub fn binary() -> Self {
+        Self::new(2)
+            .with_weight("BitFlipMutator", 6)
+            .with_weight("ByteFlipMutator", 4)
+            .with_weight("ByteAddMutator", 4)
+            .with_weight("WordAddMutator", 4)
+            .with_weight("DwordAddMutator", 4)
+            .with_weight("QwordAddMutator", 4)
+            .with_weight("ByteInterestingMutator", 4)
+            .with_weight("WordInterestingMutator", 4)
+            .with_weight("DwordInterestingMutator", 4)
+            .with_weight("BytesSwapMutator", 1)
+    }
+
+    /// The cumulative weights of `mutations`, in order
+    fn cumulative_weights<I, MT, S>(&self, mutations: &MT) -> Vec<u64>
+    where
+        I: Input,
+        MT: MutatorsTuple<I, S>,
+    {
+        let mut total = 0;
+        (0..mutations.len())
+            .map(|idx| {
+                total += self.weight(mutations.get_name(idx).unwrap_or_default());
+                total
+            })
+            .collect()
+    }
+}
+
+impl Default for HavocProfile {
+    /// All mutations are equally likely
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+/// A [`HavocProfile`] in the state metadata, taking precedence over the schedule of a [`MetadataScheduledMutator`]
+/// for as long as it is present, for example to weight the mutations of a single testcase, or of a phase of the campaign.
+/// Replacing or removing it takes effect with the next mutation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HavocProfileMetadata {
+    /// The profile
+    pub profile: HavocProfile,
+}
+
+crate::impl_serdeany!(HavocProfileMetadata);
+
 /// A [`Mutator`] that composes multiple mutations into one.
 pub trait ComposedByMutations<I, MT, S>
 where
@@ -110,6 +240,9 @@ where
 {
     mutations: MT,
     max_iterations: u64,
+    profile: Option<HavocProfile>,
+    /// The cumulative weights of the profile, empty to sample uniformly
+    weights: Vec<u64>,
     phantom: PhantomData<(I, S)>,
 }
 
@@ -176,10 +309,25 @@ where
         1 << (1 + state.rand_mut().below(self.max_iterations))
     }
 
-    /// Get the next mutation to apply
+    /// Get the next mutation to apply, sampled following the [`HavocProfile`], if any
     fn schedule(&self, state: &mut S, _: &I) -> usize {
         debug_assert!(!self.mutations().is_empty());
-        state.rand_mut().below(self.mutations().len() as u64) as usize
+        sample_weighted(state.rand_mut(), &self.weights)
+            .unwrap_or_else(|| state.rand_mut().below(self.mutations().len() as u64) as usize)
+    }
+}
+
+/// Samples an index following the cumulative `weights`, or returns `None` if there are no (non-zero) weights
+fn sample_weighted<R>(rand: &mut R, weights: &[u64]) -> Option<usize>
+where
+    R: Rand,
+{
+    match weights.last() {
+        Some(&total) if total > 0 => {
+            let r = rand.below(total);
+            Some(weights.partition_point(|&weight| weight <= r))
+        }
+        _ => None,
     }
 }
 
@@ -194,6 +342,8 @@ where
         StdScheduledMutator {
             mutations,
             max_iterations: 6,
+            profile: None,
+            weights: vec![],
             phantom: PhantomData,
         }
     }
@@ -203,9 +353,235 @@ where
         StdScheduledMutator {
             mutations,
             max_iterations,
+            profile: None,
+            weights: vec![],
             phantom: PhantomData,
         }
     }
+
+    /// Create a new [`StdScheduledMutator`] instance specifying mutations, sampled following the given [`HavocProfile`].
+    /// As the mutator is specific to an [`Input`] type, this is the profile for all inputs of this type,
+    /// e.g. [`HavocProfile::text`] for text inputs.
+    pub fn with_profile(mutations: MT, profile: HavocProfile) -> Self {
+        let mut mutator = Self::new(mutations);
+        mutator.set_profile(Some(profile));
+        mutator
+    }
+
+    /// The [`HavocProfile`] the mutations get sampled with, if not sampled uniformly
+    #[must_use]
+    pub fn profile(&self) -> Option<&HavocProfile> {
+        self.profile.as_ref()
+    }
+
+    /// Sets the [`HavocProfile`] to sample the mutations with, or `None` to sample uniformly.
+    /// If all weights are `0`, mutations get sampled uniformly as well.
+    pub fn set_profile(&mut self, profile: Option<HavocProfile>) {
+        self.weights = profile.as_ref().map_or_else(Vec::new, |profile| {
+            profile.cumulative_weights::<I, MT, S>(&self.mutations)
+        });
+        self.profile = profile;
+    }
+}
+
+/// A [`ScheduledMutator`] wrapping another one, e.g. a [`StdScheduledMutator`], that uses the metadata of the state:
+/// - The [`HavocProfileMetadata`] of the state, if present, takes precedence over the schedule of the wrapped mutator.
+/// - With [`MetadataScheduledMutator::set_trail`], the applied mutations get recorded in a [`MutationTrailMetadata`].
+pub struct MetadataScheduledMutator<I, MT, S, SM>
+where
+    I: Input,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand + HasMetadata,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    scheduled: SM,
+    trail: bool,
+    /// The [`HavocProfileMetadata`] profile of the state, and its cumulative weights
+    state_weights: Option<(HavocProfile, Vec<u64>)>,
+    phantom: PhantomData<(I, MT, S)>,
+}
+
+impl<I, MT, S, SM> Debug for MetadataScheduledMutator<I, MT, S, SM>
+where
+    I: Input,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand + HasMetadata,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "MetadataScheduledMutator with {} mutations for Input type {}",
+            self.scheduled.mutations().len(),
+            core::any::type_name::<I>()
+        )
+    }
+}
+
+impl<I, MT, S, SM> Mutator<I, S> for MetadataScheduledMutator<I, MT, S, SM>
+where
+    I: Input,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand + HasMetadata,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    #[inline]
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        self.load_state_profile(state);
+        self.scheduled_mutate(state, input, stage_idx)
+    }
+
+    /// Drops the [`MutationTrailMetadata`] of the executed input,
+    /// so inputs evaluated outside of this mutator do not get a stale trail
+    #[inline]
+    fn post_exec(
+        &mut self,
+        state: &mut S,
+        stage_idx: i32,
+        corpus_idx: Option<usize>,
+    ) -> Result<(), Error> {
+        if self.trail {
+            drop(state.metadata_mut().remove::<MutationTrailMetadata>());
+        }
+        self.scheduled.post_exec(state, stage_idx, corpus_idx)
+    }
+}
+
+impl<I, MT, S, SM> ComposedByMutations<I, MT, S> for MetadataScheduledMutator<I, MT, S, SM>
+where
+    I: Input,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand + HasMetadata,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    #[inline]
+    fn mutations(&self) -> &MT {
+        self.scheduled.mutations()
+    }
+
+    #[inline]
+    fn mutations_mut(&mut self) -> &mut MT {
+        self.scheduled.mutations_mut()
+    }
+}
+
+impl<I, MT, S, SM> ScheduledMutator<I, MT, S> for MetadataScheduledMutator<I, MT, S, SM>
+where
+    I: Input,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand + HasMetadata,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    /// Compute the number of iterations used to apply stacked mutations, as the wrapped mutator does
+    fn iterations(&self, state: &mut S, input: &I) -> u64 {
+        self.scheduled.iterations(state, input)
+    }
+
+    /// Get the next mutation to apply, following the [`HavocProfileMetadata`] of the state,
+    /// else as the wrapped mutator does
+    fn schedule(&self, state: &mut S, input: &I) -> usize {
+        let weights = self
+            .state_weights
+            .as_ref()
+            .map_or(&[][..], |(_, weights)| weights.as_slice());
+        sample_weighted(state.rand_mut(), weights)
+            .unwrap_or_else(|| self.scheduled.schedule(state, input))
+    }
+
+    /// Like the default `scheduled_mutate`, recording each mutation in the [`MutationTrailMetadata`] of the state,
+    /// if enabled
+    #[allow(clippy::cast_possible_truncation)]
+    fn scheduled_mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let mut r = MutationResult::Skipped;
+        let num = self.iterations(state, input);
+        let mut trail = Vec::with_capacity(if self.trail { num as usize } else { 0 });
+        for _ in 0..num {
+            let idx = self.schedule(state, input);
+            let outcome = self
+                .mutations_mut()
+                .get_and_mutate(idx, state, input, stage_idx)?;
+            if self.trail {
+                trail.push(MutationTrailEntry {
+                    mutation: self.mutations().get_name(idx).unwrap_or_default().into(),
+                    mutated: outcome == MutationResult::Mutated,
+                });
+            }
+            if outcome == MutationResult::Mutated {
+                r = MutationResult::Mutated;
+            }
+        }
+        if self.trail {
+            state.add_metadata(MutationTrailMetadata { trail });
+        }
+        Ok(r)
+    }
+}
+
+impl<I, MT, S, SM> MetadataScheduledMutator<I, MT, S, SM>
+where
+    I: Input,
+    MT: MutatorsTuple<I, S>,
+    S: HasRand + HasMetadata,
+    SM: ScheduledMutator<I, MT, S>,
+{
+    /// Create a new [`MetadataScheduledMutator`] wrapping the `scheduled` mutator, without trail
+    pub fn new(scheduled: SM) -> Self {
+        Self {
+            scheduled,
+            trail: false,
+            state_weights: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The wrapped mutator
+    #[must_use]
+    pub fn scheduled(&self) -> &SM {
+        &self.scheduled
+    }
+
+    /// The wrapped mutator (mutable)
+    pub fn scheduled_mut(&mut self) -> &mut SM {
+        &mut self.scheduled
+    }
+
+    /// Returns `true` if the applied mutations are recorded in a [`MutationTrailMetadata`]
+    #[must_use]
+    pub fn trail(&self) -> bool {
+        self.trail
+    }
+
+    /// Enables or disables recording the applied mutations in a [`MutationTrailMetadata`] in the state.
+    /// Disabled by default, as it costs an allocation per mutation.
+    pub fn set_trail(&mut self, trail: bool) {
+        self.trail = trail;
+    }
+
+    /// Loads the weights of the [`HavocProfileMetadata`] of the state, if it has one.
+    /// The weights get recomputed whenever the profile changed.
+    fn load_state_profile(&mut self, state: &S) {
+        match state.metadata().get::<HavocProfileMetadata>() {
+            Some(meta) => {
+                if !matches!(&self.state_weights, Some((cached, _)) if *cached == meta.profile) {
+                    let weights = meta
+                        .profile
+                        .cumulative_weights::<I, MT, S>(self.scheduled.mutations());
+                    self.state_weights = Some((meta.profile.clone(), weights));
+                }
+            }
+            None => self.state_weights = None,
+        }
+    }
 }
 
 /// Get the mutations that compose the Havoc mutator
@@ -270,12 +646,84 @@ pub fn havoc_mutations() -> tuple_list_type!(
     )
 }
 
+/// Get the mutations that compose the Havoc mutator, including the [`ReverseRegionMutator`] and the [`RotateRegionMutator`]
+#[must_use]
+pub fn havoc_mutations_with_regions() -> tuple_list_type!(
+    BitFlipMutator,
+    ByteFlipMutator,
+    ByteIncMutator,
+    ByteDecMutator,
+    ByteNegMutator,
+    ByteRandMutator,
+    ByteAddMutator,
+    WordAddMutator,
+    DwordAddMutator,
+    QwordAddMutator,
+    ByteInterestingMutator,
+    WordInterestingMutator,
+    DwordInterestingMutator,
+    BytesDeleteMutator,
+    BytesDeleteMutator,
+    BytesDeleteMutator,
+    BytesDeleteMutator,
+    BytesExpandMutator,
+    BytesInsertMutator,
+    BytesRandInsertMutator,
+    BytesSetMutator,
+    BytesRandSetMutator,
+    BytesCopyMutator,
+    BytesInsertCopyMutator,
+    BytesSwapMutator,
+    ReverseRegionMutator,
+    RotateRegionMutator,
+    CrossoverInsertMutator,
+    CrossoverReplaceMutator,
+) {
+    tuple_list!(
+        BitFlipMutator::new(),
+        ByteFlipMutator::new(),
+        ByteIncMutator::new(),
+        ByteDecMutator::new(),
+        ByteNegMutator::new(),
+        ByteRandMutator::new(),
+        ByteAddMutator::new(),
+        WordAddMutator::new(),
+        DwordAddMutator::new(),
+        QwordAddMutator::new(),
+        ByteInterestingMutator::new(),
+        WordInterestingMutator::new(),
+        DwordInterestingMutator::new(),
+        BytesDeleteMutator::new(),
+        BytesDeleteMutator::new(),
+        BytesDeleteMutator::new(),
+        BytesDeleteMutator::new(),
+        BytesExpandMutator::new(),
+        BytesInsertMutator::new(),
+        BytesRandInsertMutator::new(),
+        BytesSetMutator::new(),
+        BytesRandSetMutator::new(),
+        BytesCopyMutator::new(),
+        BytesInsertCopyMutator::new(),
+        BytesSwapMutator::new(),
+        ReverseRegionMutator::new(),
+        RotateRegionMutator::new(),
+        CrossoverInsertMutator::new(),
+        CrossoverReplaceMutator::new(),
+    )
+}
+
 /// Get the mutations that uses the Tokens metadata
 #[must_use]
 pub fn tokens_mutations() -> tuple_list_type!(TokenInsert, TokenReplace) {
     tuple_list!(TokenInsert::new(), TokenReplace::new(),)
 }
 
+/// Get the mutations that uses the Tokens metadata, including the [`TokenDelete`] mutator
+#[must_use]
+pub fn tokens_mutations_with_delete() -> tuple_list_type!(TokenInsert, TokenReplace, TokenDelete) {
+    tuple_list!(TokenInsert::new(), TokenReplace::new(), TokenDelete::new(),)
+}
+
 /// A logging [`Mutator`] that wraps around a [`StdScheduledMutator`].
 pub struct LoggerScheduledMutator<I, MT, S, SM>
 where
@@ -425,15 +873,21 @@ where
 #[cfg(test)]
 mod tests {
     use crate::{
-        bolts::rands::{Rand, StdRand, XkcdRand},
+        bolts::{
+            rands::{Rand, StdRand, XkcdRand},
+            tuples::tuple_list,
+        },
         corpus::{Corpus, InMemoryCorpus, Testcase},
         inputs::{BytesInput, HasBytesVec},
         mutators::{
-            mutations::SpliceMutator,
-            scheduled::{havoc_mutations, StdScheduledMutator},
+            mutations::{BitFlipMutator, ByteFlipMutator, BytesSwapMutator, SpliceMutator},
+            scheduled::{
+                havoc_mutations, HavocProfile, HavocProfileMetadata, MetadataScheduledMutator,
+                MutationTrailMetadata, ScheduledMutator, StdScheduledMutator,
+            },
             Mutator,
         },
-        state::StdState,
+        state::{HasMetadata, StdState},
     };
 
     #[test]
@@ -494,4 +948,55 @@ mod tests {
             assert_ne!(equal_in_a_row, 5);
         }
     }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn test_havoc_profile() {
+        let mut corpus: InMemoryCorpus<BytesInput> = InMemoryCorpus::new();
+        corpus.add(Testcase::new(vec![b'a', b'b', b'c'])).unwrap();
+        let mut state = StdState::new(
+            StdRand::with_seed(0x1337),
+            corpus,
+            InMemoryCorpus::new(),
+            (),
+        );
+        let mut input = BytesInput::new(vec![b'a', b'b', b'c']);
+
+        let profile = HavocProfile::new(1)
+            .with_weight("BitFlipMutator", 6)
+            .with_weight("BytesSwapMutator", 3);
+        let mut havoc = StdScheduledMutator::with_profile(
+            tuple_list!(
+                BitFlipMutator::new(),
+                ByteFlipMutator::new(),
+                BytesSwapMutator::new()
+            ),
+            profile,
+        );
+
+        let samples = 100_000;
+        let mut counts = [0_usize; 3];
+        for _ in 0..samples {
+            counts[havoc.schedule(&mut state, &input)] += 1;
+        }
+        for (count, expected) in counts.iter().zip([0.6, 0.1, 0.3]) {
+            assert!((*count as f64 / samples as f64 - expected).abs() < 0.01);
+        }
+
+        // The profile in the state takes precedence, and changes to it take effect right away
+        let mut havoc = MetadataScheduledMutator::new(havoc);
+        havoc.set_trail(true);
+        for only in ["ByteFlipMutator", "BytesSwapMutator"] {
+            state.add_metadata(HavocProfileMetadata {
+                profile: HavocProfile::new(0).with_weight(only, 1),
+            });
+            havoc.mutate(&mut state, &mut input, 0).unwrap();
+            let trail = &state
+                .metadata()
+                .get::<MutationTrailMetadata>()
+                .unwrap()
+                .trail;
+            assert!(trail.iter().all(|entry| entry.mutation == only));
+        }
+    }
 }
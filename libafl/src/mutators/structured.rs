@@ -0,0 +1,109 @@
+//! Mutations for the [`StructuredBytesInput`], mutating the bytes of a single field.
+
+use crate::{
+    bolts::{rands::Rand, tuples::Named},
+    inputs::{BytesInput, StructuredBytesInput},
+    mutators::{MutationResult, Mutator},
+    state::HasRand,
+    Error,
+};
+
+/// Applies a [`BytesInput`] mutator, such as a `StdScheduledMutator` with the havoc mutations,
+/// to a single field of a [`StructuredBytesInput`]: a random one, or always the same.
+/// The length prefixes of the input get recomputed on serialization.
+#[derive(Debug)]
+pub struct FieldMutator<M> {
+    mutator: M,
+    field: Option<usize>,
+}
+
+impl<M, S> Mutator<StructuredBytesInput, S> for FieldMutator<M>
+where
+    M: Mutator<BytesInput, S>,
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut StructuredBytesInput,
+        stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let count = input.fields().len();
+        let idx = match self.field {
+            Some(idx) if idx < count => idx,
+            Some(_) => return Ok(MutationResult::Skipped),
+            None if count == 0 => return Ok(MutationResult::Skipped),
+            None => state.rand_mut().below(count as u64) as usize,
+        };
+        self.mutator
+            .mutate(state, &mut input.fields_mut()[idx], stage_idx)
+    }
+}
+
+impl<M> Named for FieldMutator<M> {
+    fn name(&self) -> &str {
+        "FieldMutator"
+    }
+}
+
+impl<M> FieldMutator<M> {
+    /// Creates a new [`FieldMutator`], mutating a random field with the given mutator
+    #[must_use]
+    pub fn new(mutator: M) -> Self {
+        Self {
+            mutator,
+            field: None,
+        }
+    }
+
+    /// Creates a new [`FieldMutator`], only ever mutating the field at index `field`, e.g. the payload
+    #[must_use]
+    pub fn for_field(mutator: M, field: usize) -> Self {
+        Self {
+            mutator,
+            field: Some(field),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, AsSlice},
+        corpus::InMemoryCorpus,
+        inputs::{FieldKind, HasBytesVec, HasTargetBytes, StructuredBytesInput},
+        mutators::{structured::FieldMutator, BytesInsertMutator, MutationResult, Mutator},
+        state::StdState,
+    };
+
+    #[test]
+    fn test_field_mutator_updates_length() {
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<StructuredBytesInput>::new(),
+            InMemoryCorpus::<StructuredBytesInput>::new(),
+            (),
+        );
+        let schema = vec![
+            FieldKind::Fixed(4),
+            FieldKind::LengthPrefixed(1),
+            FieldKind::Fixed(2),
+        ];
+        let mut input = StructuredBytesInput::parse(schema, b"HEAD\x05helloCK").unwrap();
+
+        let mut mutator = FieldMutator::for_field(BytesInsertMutator::new(), 1);
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input, 0).unwrap(),
+            MutationResult::Mutated
+        );
+
+        let payload_len = input.fields()[1].bytes().len();
+        assert!(payload_len > 5);
+        let bytes = input.target_bytes();
+        let bytes = bytes.as_slice();
+        assert_eq!(&bytes[..4], b"HEAD");
+        assert_eq!(bytes[4] as usize, payload_len);
+        assert_eq!(&bytes[5..5 + payload_len], input.fields()[1].bytes());
+        assert_eq!(&bytes[5 + payload_len..], b"CK");
+    }
+}
@@ -150,6 +150,21 @@ impl Tokens {
         true
     }
 
+    /// Adds the given tokens, skipping duplicates, as long as the dictionary holds less than `max_count` tokens.
+    /// Returns the amount of added tokens.
+    pub fn merge_bounded(&mut self, tokens: &[Vec<u8>], max_count: usize) -> usize {
+        let mut added = 0;
+        for token in tokens {
+            if self.len() >= max_count {
+                break;
+            }
+            if self.add_token(token) {
+                added += 1;
+            }
+        }
+        added
+    }
+
     /// Reads a tokens file, returning the count of new entries read
     #[cfg(feature = "std")]
     pub fn add_from_file<P>(&mut self, file: P) -> Result<&mut Self, Error>
@@ -418,6 +433,73 @@ impl TokenReplace {
     }
 }
 
+/// A `TokenDelete` [`Mutator`] removes a random occurrence of one of the tokens from the input.
+/// Unlike a random byte deletion, this keeps the rest of a structured input intact.
+#[derive(Debug, Default)]
+pub struct TokenDelete;
+
+impl<I, S> Mutator<I, S> for TokenDelete
+where
+    I: Input + HasBytesVec,
+    S: HasMetadata + HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let occurrences = {
+            let meta = match state.metadata().get::<Tokens>() {
+                Some(meta) => meta,
+                None => return Ok(MutationResult::Skipped),
+            };
+            // The first bytes of the tokens, so that most offsets get skipped without comparing any token
+            let mut first_bytes = [false; 256];
+            for token in meta.tokens() {
+                if let Some(first) = token.first() {
+                    first_bytes[*first as usize] = true;
+                }
+            }
+            let bytes = input.bytes();
+            let mut occurrences = vec![];
+            for (off, byte) in bytes.iter().enumerate() {
+                if !first_bytes[*byte as usize] {
+                    continue;
+                }
+                for token in meta.tokens() {
+                    if !token.is_empty() && bytes[off..].starts_with(token) {
+                        occurrences.push((off, token.len()));
+                    }
+                }
+            }
+            occurrences
+        };
+        if occurrences.is_empty() {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let (off, len) = *state.rand_mut().choose(&occurrences);
+        input.bytes_mut().drain(off..off + len);
+
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for TokenDelete {
+    fn name(&self) -> &str {
+        "TokenDelete"
+    }
+}
+
+impl TokenDelete {
+    /// Creates a new `TokenDelete` struct.
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
 /// A `I2SRandReplace` [`Mutator`] replaces a random matching input-2-state comparison operand with the other.
 /// It needs a valid [`CmpValuesMetadata`] in the state.
 #[derive(Debug, Default)]
@@ -603,13 +685,343 @@ impl I2SRandReplace {
     }
 }
 
+/// An `I2STokenReplace` [`Mutator`] looks for the position of a random input-2-state comparison operand in the input,
+/// and inserts a random token of the dictionary there, or overwrites the operand with it.
+/// This helps with comparisons against strings that are part of the dictionary but not the observed comparand.
+/// It needs a valid [`CmpValuesMetadata`] and [`Tokens`] in the state.
+#[derive(Debug, Default)]
+pub struct I2STokenReplace;
+
+impl<I, S> Mutator<I, S> for I2STokenReplace
+where
+    I: Input + HasBytesVec,
+    S: HasMetadata + HasRand + HasMaxSize,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let size = input.bytes().len();
+        if size == 0 {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let cmps_len = match state.metadata().get::<CmpValuesMetadata>() {
+            Some(meta) if !meta.list.is_empty() => meta.list.len(),
+            _ => return Ok(MutationResult::Skipped),
+        };
+        let tokens_len = match state.metadata().get::<Tokens>() {
+            Some(meta) if !meta.is_empty() => meta.len(),
+            _ => return Ok(MutationResult::Skipped),
+        };
+        let cmp_idx = state.rand_mut().below(cmps_len as u64) as usize;
+        let token_idx = state.rand_mut().below(tokens_len as u64) as usize;
+        let off = state.rand_mut().below(size as u64) as usize;
+        let insert = state.rand_mut().below(2) == 0;
+        let max_size = state.max_size();
+
+        let cmp_values = &state.metadata().get::<CmpValuesMetadata>().unwrap().list[cmp_idx];
+        let pos = match find_cmp_operand(input.bytes(), cmp_values, off) {
+            Some(pos) => pos,
+            None => return Ok(MutationResult::Skipped),
+        };
+
+        let token = &state.metadata().get::<Tokens>().unwrap().tokens()[token_idx];
+        let mut len = token.len();
+        if insert {
+            if size + len > max_size {
+                if max_size > size {
+                    len = max_size - size;
+                } else {
+                    return Ok(MutationResult::Skipped);
+                }
+            }
+            input.bytes_mut().resize(size + len, 0);
+            buffer_self_copy(input.bytes_mut(), pos, pos + len, size - pos);
+        } else if pos + len > size {
+            len = size - pos;
+        }
+        buffer_copy(input.bytes_mut(), token, 0, pos, len);
+
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for I2STokenReplace {
+    fn name(&self) -> &str {
+        "I2STokenReplace"
+    }
+}
+
+impl I2STokenReplace {
+    /// Creates a new `I2STokenReplace` struct.
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// An `I2SRoutineReplace` [`Mutator`] replaces the operand of a random routine comparison, such as `strcmp` or `memcmp`,
+/// found in the input with the whole other operand, growing or shrinking the input as needed.
+/// Operands are cut at their first nul byte, as the cmplog logs a fixed amount of bytes past the end of C strings.
+/// It needs a valid [`CmpValuesMetadata`] in the state, with [`CmpValues::Bytes`] entries.
+#[derive(Debug, Default)]
+pub struct I2SRoutineReplace;
+
+impl<I, S> Mutator<I, S> for I2SRoutineReplace
+where
+    I: Input + HasBytesVec,
+    S: HasMetadata + HasRand + HasMaxSize,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let size = input.bytes().len();
+        if size == 0 {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let routines: Vec<usize> = match state.metadata().get::<CmpValuesMetadata>() {
+            Some(meta) => meta
+                .list
+                .iter()
+                .enumerate()
+                .filter(|(_, cmp)| matches!(cmp, CmpValues::Bytes(_)))
+                .map(|(idx, _)| idx)
+                .collect(),
+            None => return Ok(MutationResult::Skipped),
+        };
+        if routines.is_empty() {
+            return Ok(MutationResult::Skipped);
+        }
+        let cmp_idx = routines[state.rand_mut().below(routines.len() as u64) as usize];
+        let off = state.rand_mut().below(size as u64) as usize;
+        let max_size = state.max_size();
+
+        let (v0, v1) = match &state.metadata().get::<CmpValuesMetadata>().unwrap().list[cmp_idx] {
+            CmpValues::Bytes(v) => (c_str(&v.0), c_str(&v.1)),
+            _ => unreachable!(),
+        };
+        for (from, to) in [(v0, v1), (v1, v0)] {
+            if from.is_empty() || from == to {
+                continue;
+            }
+            let bytes = input.bytes();
+            // The operand may also continue past the end of the input, if the harness compares it as is
+            let found = (off..size)
+                .chain(0..off)
+                .find(|&pos| bytes[pos..].starts_with(from))
+                .map(|pos| (pos, from.len()))
+                .or_else(|| {
+                    (0..size)
+                        .find(|&pos| from.starts_with(&bytes[pos..]))
+                        .map(|pos| (pos, size - pos))
+                });
+            if let Some((pos, len)) = found {
+                if size - len + to.len() > max_size {
+                    return Ok(MutationResult::Skipped);
+                }
+                input.bytes_mut().splice(pos..pos + len, to.iter().copied());
+                return Ok(MutationResult::Mutated);
+            }
+        }
+        Ok(MutationResult::Skipped)
+    }
+}
+
+impl Named for I2SRoutineReplace {
+    fn name(&self) -> &str {
+        "I2SRoutineReplace"
+    }
+}
+
+impl I2SRoutineReplace {
+    /// Creates a new `I2SRoutineReplace` struct.
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// The bytes of `bytes` up to the first nul byte
+fn c_str(bytes: &[u8]) -> &[u8] {
+    bytes
+        .iter()
+        .position(|&b| b == 0)
+        .map_or(bytes, |len| &bytes[..len])
+}
+
+/// Finds the first position of one of the operands of `cmp_values` in `bytes`, in either byte order,
+/// searching from `off` and wrapping around to the start of the input.
+fn find_cmp_operand(bytes: &[u8], cmp_values: &CmpValues, off: usize) -> Option<usize> {
+    let operands: Vec<Vec<u8>> = match cmp_values {
+        CmpValues::U8(v) => vec![vec![v.0], vec![v.1]],
+        CmpValues::U16(v) => vec![
+            v.0.to_ne_bytes().to_vec(),
+            v.0.swap_bytes().to_ne_bytes().to_vec(),
+            v.1.to_ne_bytes().to_vec(),
+            v.1.swap_bytes().to_ne_bytes().to_vec(),
+        ],
+        CmpValues::U32(v) => vec![
+            v.0.to_ne_bytes().to_vec(),
+            v.0.swap_bytes().to_ne_bytes().to_vec(),
+            v.1.to_ne_bytes().to_vec(),
+            v.1.swap_bytes().to_ne_bytes().to_vec(),
+        ],
+        CmpValues::U64(v) => vec![
+            v.0.to_ne_bytes().to_vec(),
+            v.0.swap_bytes().to_ne_bytes().to_vec(),
+            v.1.to_ne_bytes().to_vec(),
+            v.1.swap_bytes().to_ne_bytes().to_vec(),
+        ],
+        CmpValues::Bytes(v) => vec![v.0.clone(), v.1.clone()],
+    };
+    (off..bytes.len()).chain(0..off).find(|&i| {
+        operands
+            .iter()
+            .any(|operand| !operand.is_empty() && bytes[i..].starts_with(operand))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     #[cfg(feature = "std")]
     use std::fs;
 
-    #[cfg(feature = "std")]
-    use super::Tokens;
+    use super::{I2SRoutineReplace, I2STokenReplace, TokenDelete, Tokens};
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::InMemoryCorpus,
+        inputs::{BytesInput, HasBytesVec},
+        mutators::{MutationResult, Mutator},
+        observers::cmp::{CmpValues, CmpValuesMetadata},
+        state::{HasMetadata, StdState},
+    };
+
+    /// A harness guarded by a comparison against a word of the dictionary
+    fn harness_reached(buf: &[u8]) -> bool {
+        buf.len() >= 9 && &buf[4..9] == b"MAGIC"
+    }
+
+    #[test]
+    fn test_i2s_token_replace() {
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        // The cmplog only saw a comparison of the input against an unrelated string
+        state.add_metadata(CmpValuesMetadata {
+            list: vec![CmpValues::Bytes((b"zzzz".to_vec(), b"QQQQ".to_vec()))],
+        });
+        let mut tokens = Tokens::new();
+        tokens.add_token(&b"MAGIC".to_vec());
+        state.add_metadata(tokens);
+
+        let mut mutator = I2STokenReplace::new();
+        for i in 0..16 {
+            let mut input = BytesInput::new(b"....zzzz....".to_vec());
+            assert!(!harness_reached(input.bytes()));
+            assert_eq!(
+                mutator.mutate(&mut state, &mut input, i).unwrap(),
+                MutationResult::Mutated
+            );
+            assert!(harness_reached(input.bytes()));
+        }
+    }
+
+    /// A harness comparing the input as C string against a fixed string, as `strcmp` would
+    fn strcmp_harness(buf: &[u8]) -> bool {
+        buf.split(|&b| b == 0).next().unwrap() == b"SECRET_PASSWORD"
+    }
+
+    #[test]
+    fn test_i2s_routine_replace() {
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mutator = I2SRoutineReplace::new();
+        let mut input = BytesInput::new(b"AAAA".to_vec());
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input, 0).unwrap(),
+            MutationResult::Skipped
+        );
+
+        // The cmplog logs a fixed length of each argument, including whatever follows the strings
+        let mut v0 = b"AAAA\0".to_vec();
+        v0.resize(32, b'x');
+        let mut v1 = b"SECRET_PASSWORD\0".to_vec();
+        v1.resize(32, b'y');
+        state.add_metadata(CmpValuesMetadata {
+            list: vec![CmpValues::U32((1, 2)), CmpValues::Bytes((v0, v1))],
+        });
+
+        for i in 0..16 {
+            let mut input = BytesInput::new(b"AAAA".to_vec());
+            assert!(!strcmp_harness(input.bytes()));
+            assert_eq!(
+                mutator.mutate(&mut state, &mut input, i).unwrap(),
+                MutationResult::Mutated
+            );
+            assert!(strcmp_harness(input.bytes()));
+        }
+
+        // An input without a nul byte, the logged argument continues past its end
+        state.add_metadata(CmpValuesMetadata {
+            list: vec![CmpValues::Bytes((
+                b"AAAAzzzz".to_vec(),
+                b"SECRET_PASSWORD\0".to_vec(),
+            ))],
+        });
+        let mut input = BytesInput::new(b"AAAA".to_vec());
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input, 0).unwrap(),
+            MutationResult::Mutated
+        );
+        assert!(strcmp_harness(input.bytes()));
+    }
+
+    #[test]
+    fn test_token_delete() {
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mutator = TokenDelete::new();
+        let mut input = BytesInput::new(b"IHDR....IDAT....IEND".to_vec());
+
+        // Without tokens, there is nothing to delete
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input, 0).unwrap(),
+            MutationResult::Skipped
+        );
+
+        let mut tokens = Tokens::new();
+        tokens.add_token(&b"IEND".to_vec());
+        tokens.add_token(&b"tEXt".to_vec());
+        state.add_metadata(tokens);
+
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input, 0).unwrap(),
+            MutationResult::Mutated
+        );
+        assert_eq!(input.bytes(), b"IHDR....IDAT....");
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input, 0).unwrap(),
+            MutationResult::Skipped
+        );
+    }
 
     #[cfg(feature = "std")]
     #[test]
@@ -0,0 +1,201 @@
+//! A bounded, in-process recorder for symbolic constraints, reported by the target through a [`ConstraintCallback`].
+//! Unlike the [`super::ConcolicObserver`], which reads a full trace written by an external runtime,
+//! this is meant for lightweight instrumentation that reports comparisons (address, operation, operands) directly.
+//! The [`crate::stages::ConstraintTracingStage`] attaches the recorded [`ConstraintTraceMetadata`] to the traced testcase,
+//! for a later solver stage, e.g. from an external solver crate.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{bolts::tuples::Named, executors::ExitKind, observers::Observer, Error};
+
+/// The default bound for the amount of [`Constraint`]s recorded in a single run
+pub const DEFAULT_MAX_CONSTRAINTS: usize = 4096;
+
+/// A single symbolic constraint, as reported by the target
+#[repr(C)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Constraint {
+    /// The address of the instruction emitting the constraint
+    pub address: usize,
+    /// The operation, opaque to `LibAFL` and interpreted by the solver
+    pub operation: u32,
+    /// The operands of the operation
+    pub operands: [u64; 2],
+}
+
+/// The signature of the runtime callback the target calls for each constraint.
+/// Pass [`record_constraint`] to the instrumentation runtime.
+pub type ConstraintCallback =
+    unsafe extern "C" fn(address: usize, operation: u32, lhs: u64, rhs: u64);
+
+/// The constraints of the current run, filled by [`record_constraint`]
+#[derive(Debug)]
+struct ConstraintTrace {
+    constraints: Vec<Constraint>,
+    max_constraints: usize,
+    dropped: usize,
+}
+
+static mut CONSTRAINT_TRACE: ConstraintTrace = ConstraintTrace {
+    constraints: Vec::new(),
+    max_constraints: 0,
+    dropped: 0,
+};
+
+/// Records a constraint for the current run, or counts it as dropped once the bound is reached.
+/// This is the [`ConstraintCallback`] to hand to the target.
+///
+/// # Safety
+/// Must not be called concurrently, i.e. only from the single thread executing the target.
+pub unsafe extern "C" fn record_constraint(address: usize, operation: u32, lhs: u64, rhs: u64) {
+    let trace = &mut CONSTRAINT_TRACE;
+    if trace.constraints.len() < trace.max_constraints {
+        trace.constraints.push(Constraint {
+            address,
+            operation,
+            operands: [lhs, rhs],
+        });
+    } else {
+        trace.dropped += 1;
+    }
+}
+
+/// A metadata holding the bounded constraint trace of a run, for a later solver stage
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ConstraintTraceMetadata {
+    /// The recorded constraints, in the order the target reported them
+    pub constraints: Vec<Constraint>,
+    /// The amount of constraints dropped after the bound was reached
+    pub dropped: usize,
+}
+
+crate::impl_serdeany!(ConstraintTraceMetadata);
+
+/// An observer recording at most `max_constraints` [`Constraint`]s per run, reported through [`record_constraint`]
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConstraintObserver {
+    name: String,
+    max_constraints: usize,
+    constraints: Vec<Constraint>,
+    dropped: usize,
+}
+
+impl ConstraintObserver {
+    /// Creates a new [`ConstraintObserver`], recording up to [`DEFAULT_MAX_CONSTRAINTS`] per run
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self::with_max_constraints(name, DEFAULT_MAX_CONSTRAINTS)
+    }
+
+    /// Creates a new [`ConstraintObserver`], recording up to `max_constraints` per run
+    #[must_use]
+    pub fn with_max_constraints(name: &str, max_constraints: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            max_constraints,
+            constraints: Vec::new(),
+            dropped: 0,
+        }
+    }
+
+    /// The constraints recorded in the last run
+    #[must_use]
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    /// The amount of constraints dropped in the last run, after the bound was reached
+    #[must_use]
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Create the constraint trace metadata for the last run
+    #[must_use]
+    pub fn create_metadata(&self) -> ConstraintTraceMetadata {
+        ConstraintTraceMetadata {
+            constraints: self.constraints.clone(),
+            dropped: self.dropped,
+        }
+    }
+}
+
+impl<I, S> Observer<I, S> for ConstraintObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        unsafe {
+            let trace = &mut CONSTRAINT_TRACE;
+            trace.constraints.clear();
+            trace.max_constraints = self.max_constraints;
+            trace.dropped = 0;
+        }
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        unsafe {
+            let trace = &mut CONSTRAINT_TRACE;
+            self.constraints.clear();
+            self.constraints.append(&mut trace.constraints);
+            self.dropped = trace.dropped;
+            trace.max_constraints = 0;
+        }
+        Ok(())
+    }
+}
+
+impl Named for ConstraintObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use crate::{
+        executors::ExitKind,
+        inputs::BytesInput,
+        observers::{
+            concolic::{record_constraint, ConstraintCallback, ConstraintObserver},
+            Observer,
+        },
+    };
+
+    #[test]
+    #[serial]
+    fn test_constraints_truncated() {
+        let callback: ConstraintCallback = record_constraint;
+        let input = BytesInput::new(vec![]);
+        let mut observer = ConstraintObserver::with_max_constraints("constraints", 4);
+
+        Observer::<BytesInput, ()>::pre_exec(&mut observer, &mut (), &input).unwrap();
+        for i in 0..6 {
+            unsafe { callback(0x1000 + i, 1, i as u64, 0x41) };
+        }
+        Observer::<BytesInput, ()>::post_exec(&mut observer, &mut (), &input, &ExitKind::Ok)
+            .unwrap();
+
+        assert_eq!(observer.constraints().len(), 4);
+        assert_eq!(observer.constraints()[3].address, 0x1003);
+        assert_eq!(observer.constraints()[3].operands, [3, 0x41]);
+        assert_eq!(observer.dropped(), 2);
+
+        let metadata = observer.create_metadata();
+        assert_eq!(metadata.constraints.len(), 4);
+        assert_eq!(metadata.dropped, 2);
+
+        // Nothing gets recorded outside of a run
+        unsafe { callback(0x2000, 1, 0, 0) };
+        Observer::<BytesInput, ()>::pre_exec(&mut observer, &mut (), &input).unwrap();
+        Observer::<BytesInput, ()>::post_exec(&mut observer, &mut (), &input, &ExitKind::Ok)
+            .unwrap();
+        assert!(observer.constraints().is_empty());
+        assert_eq!(observer.dropped(), 0);
+    }
+}
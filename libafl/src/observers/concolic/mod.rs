@@ -380,3 +380,11 @@ pub use metadata::ConcolicMetadata;
 mod observer;
 #[cfg(feature = "std")]
 pub use observer::ConcolicObserver;
+
+#[cfg(feature = "std")]
+mod constraints;
+#[cfg(feature = "std")]
+pub use constraints::{
+    record_constraint, Constraint, ConstraintCallback, ConstraintObserver, ConstraintTraceMetadata,
+    DEFAULT_MAX_CONSTRAINTS,
+};
@@ -8,7 +8,7 @@ use alloc::{
 use core::{
     fmt::Debug,
     hash::Hasher,
-    iter::Flatten,
+    iter::{Chain, Flatten, Skip, Take},
     marker::PhantomData,
     slice::{from_raw_parts, Iter, IterMut},
 };
@@ -118,6 +118,13 @@ pub trait MapObserver: HasLen + Named + Serialize + serde::de::DeserializeOwned
         }
         res
     }
+
+    /// The entries iterated by `as_ref_iter`, as a single slice, if the map is stored contiguously.
+    /// Feedbacks use this for vectorized fast paths, `None` by default.
+    #[inline]
+    fn as_contiguous_slice(&self) -> Option<&[Self::Entry]> {
+        None
+    }
 }
 
 /// A Simple iterator calling `MapObserver::get`
@@ -313,6 +320,12 @@ where
     fn to_vec(&self) -> Vec<T> {
         self.as_slice().to_vec()
     }
+
+    #[inline]
+    fn as_contiguous_slice(&self) -> Option<&[T]> {
+        let cnt = self.usable_count();
+        Some(&self.as_slice()[..cnt])
+    }
 }
 
 impl<'a, T> AsSlice<T> for StdMapObserver<'a, T>
@@ -340,7 +353,9 @@ impl<'a, T> StdMapObserver<'a, T>
 where
     T: PrimInt + Default + Copy + 'static + Serialize + serde::de::DeserializeOwned,
 {
-    /// Creates a new [`MapObserver`]
+    /// Creates a new [`MapObserver`] borrowing the given slice.
+    /// The observer cannot outlive the map, so prefer this over [`StdMapObserver::new_from_ptr`]
+    /// whenever the map is available as a Rust slice.
     #[must_use]
     pub fn new(name: &'static str, map: &'a mut [T]) -> Self {
         Self {
@@ -373,10 +388,13 @@ where
         }
     }
 
-    /// Creates a new [`MapObserver`] from a raw pointer
+    /// Creates a new [`MapObserver`] from a raw pointer, for maps living outside of Rust, such as the coverage map of a target.
+    /// If the map is available as a slice, use the safe [`StdMapObserver::new`] instead.
     ///
     /// # Safety
     /// Will dereference the `map_ptr` with up to len elements.
+    /// The caller must make sure that `map_ptr` points to at least `len` initialized elements,
+    /// and that the map stays valid (not moved nor freed) for as long as the observer is in use.
     pub unsafe fn new_from_ptr(name: &'static str, map_ptr: *mut T, len: usize) -> Self {
         StdMapObserver {
             map: OwnedSliceMut::from_raw_parts_mut(map_ptr, len),
@@ -520,6 +538,12 @@ where
     fn to_vec(&self) -> Vec<T> {
         self.as_slice().to_vec()
     }
+
+    #[inline]
+    fn as_contiguous_slice(&self) -> Option<&[T]> {
+        let cnt = self.usable_count();
+        Some(&self.as_slice()[..cnt])
+    }
 }
 
 impl<'a, T, const N: usize> AsSlice<T> for ConstMapObserver<'a, T, N>
@@ -713,6 +737,12 @@ where
     fn to_vec(&self) -> Vec<T> {
         self.as_slice().to_vec()
     }
+
+    #[inline]
+    fn as_contiguous_slice(&self) -> Option<&[T]> {
+        let cnt = self.usable_count();
+        Some(&self.as_slice()[..cnt])
+    }
 }
 
 impl<'a, T> AsSlice<T> for VariableMapObserver<'a, T>
@@ -768,6 +798,146 @@ where
     }
 }
 
+/// Implements the traits of a map observer wrapper, holding the wrapped map observer of `$entry`s as `base`,
+/// by delegating everything to the `base`. The wrapper implements [`Observer`] and its constructor itself.
+macro_rules! impl_map_observer_wrapper {
+    ($wrapper:ident, $entry:ty) => {
+        impl<M> Named for $wrapper<M>
+        where
+            M: Named + Serialize + serde::de::DeserializeOwned,
+        {
+            #[inline]
+            fn name(&self) -> &str {
+                self.base.name()
+            }
+        }
+
+        impl<M> HasLen for $wrapper<M>
+        where
+            M: MapObserver,
+        {
+            #[inline]
+            fn len(&self) -> usize {
+                self.base.len()
+            }
+        }
+
+        impl<M> MapObserver for $wrapper<M>
+        where
+            M: MapObserver<Entry = $entry>,
+            for<'it> M: AsMutIterator<'it, Item = $entry>,
+        {
+            type Entry = $entry;
+
+            #[inline]
+            fn initial(&self) -> $entry {
+                self.base.initial()
+            }
+
+            #[inline]
+            fn initial_mut(&mut self) -> &mut $entry {
+                self.base.initial_mut()
+            }
+
+            #[inline]
+            fn usable_count(&self) -> usize {
+                self.base.usable_count()
+            }
+
+            #[inline]
+            fn get(&self, idx: usize) -> &$entry {
+                self.base.get(idx)
+            }
+
+            #[inline]
+            fn get_mut(&mut self, idx: usize) -> &mut $entry {
+                self.base.get_mut(idx)
+            }
+
+            fn hash(&self) -> u64 {
+                self.base.hash()
+            }
+            fn to_vec(&self) -> Vec<$entry> {
+                self.base.to_vec()
+            }
+
+            #[inline]
+            fn as_contiguous_slice(&self) -> Option<&[$entry]> {
+                self.base.as_contiguous_slice()
+            }
+        }
+
+        impl<M> AsSlice<$entry> for $wrapper<M>
+        where
+            M: MapObserver + AsSlice<$entry>,
+        {
+            #[inline]
+            fn as_slice(&self) -> &[$entry] {
+                self.base.as_slice()
+            }
+        }
+        impl<M> AsMutSlice<$entry> for $wrapper<M>
+        where
+            M: MapObserver + AsMutSlice<$entry>,
+        {
+            #[inline]
+            fn as_mut_slice(&mut self) -> &mut [$entry] {
+                self.base.as_mut_slice()
+            }
+        }
+
+        impl<'it, M> AsRefIterator<'it> for $wrapper<M>
+        where
+            M: Named + Serialize + serde::de::DeserializeOwned + AsRefIterator<'it, Item = $entry>,
+        {
+            type Item = $entry;
+            type IntoIter = <M as AsRefIterator<'it>>::IntoIter;
+
+            fn as_ref_iter(&'it self) -> Self::IntoIter {
+                self.base.as_ref_iter()
+            }
+        }
+
+        impl<'it, M> AsMutIterator<'it> for $wrapper<M>
+        where
+            M: Named + Serialize + serde::de::DeserializeOwned + AsMutIterator<'it, Item = $entry>,
+        {
+            type Item = $entry;
+            type IntoIter = <M as AsMutIterator<'it>>::IntoIter;
+
+            fn as_mut_iter(&'it mut self) -> Self::IntoIter {
+                self.base.as_mut_iter()
+            }
+        }
+
+        impl<'it, M> IntoIterator for &'it $wrapper<M>
+        where
+            M: Named + Serialize + serde::de::DeserializeOwned,
+            &'it M: IntoIterator<Item = &'it $entry>,
+        {
+            type Item = &'it $entry;
+            type IntoIter = <&'it M as IntoIterator>::IntoIter;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.base.into_iter()
+            }
+        }
+
+        impl<'it, M> IntoIterator for &'it mut $wrapper<M>
+        where
+            M: Named + Serialize + serde::de::DeserializeOwned,
+            &'it mut M: IntoIterator<Item = &'it mut $entry>,
+        {
+            type Item = &'it mut $entry;
+            type IntoIter = <&'it mut M as IntoIterator>::IntoIter;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.base.into_iter()
+            }
+        }
+    };
+}
+
 /// Map observer with hitcounts postprocessing
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(bound = "M: serde::de::DeserializeOwned")]
@@ -812,7 +982,172 @@ where
     }
 }
 
-impl<M> Named for HitcountsMapObserver<M>
+impl<M> HitcountsMapObserver<M>
+where
+    M: Serialize + serde::de::DeserializeOwned,
+{
+    /// Creates a new [`MapObserver`]
+    pub fn new(base: M) -> Self {
+        Self { base }
+    }
+}
+
+impl_map_observer_wrapper!(HitcountsMapObserver, u8);
+
+/// Classify a hitcount into the AFL-style log2 buckets, like [`HitcountsMapObserver`] does.
+/// Use it as classifier of a [`ClassifyMapObserver`].
+#[must_use]
+#[inline]
+pub fn count_class(value: u8) -> u8 {
+    COUNT_CLASS_LOOKUP[value as usize]
+}
+
+/// Map observer applying a classifier closure to each entry in `post_exec`,
+/// before any feedback reads the map.
+/// This allows experimenting with alternative classifications of the counts.
+/// The classifier gets stored as the class of each of the 256 values, so a deserialized observer,
+/// e.g. after a restart, classifies the same way.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound = "M: serde::de::DeserializeOwned")]
+pub struct ClassifyMapObserver<M>
+where
+    M: Serialize + serde::de::DeserializeOwned,
+{
+    base: M,
+    /// The class of each value, indexed by the value
+    classes: Vec<u8>,
+}
+
+impl<I, S, M> Observer<I, S> for ClassifyMapObserver<M>
+where
+    M: MapObserver<Entry = u8> + Observer<I, S>,
+    for<'it> M: AsMutIterator<'it, Item = u8>,
+{
+    #[inline]
+    fn pre_exec(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.base.pre_exec(state, input)
+    }
+
+    #[inline]
+    fn post_exec(&mut self, state: &mut S, input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        for elem in self.base.as_mut_iter() {
+            *elem = self.classes[*elem as usize];
+        }
+        self.base.post_exec(state, input, exit_kind)
+    }
+}
+
+impl<M> ClassifyMapObserver<M>
+where
+    M: Serialize + serde::de::DeserializeOwned,
+{
+    /// Creates a new [`MapObserver`], applying the `classifier` to each entry of the `base` map,
+    /// e.g., `ClassifyMapObserver::new(base, |value| u8::from(value != 0))`
+    pub fn new<F>(base: M, classifier: F) -> Self
+    where
+        F: Fn(u8) -> u8,
+    {
+        Self {
+            base,
+            classes: (0..=u8::MAX).map(classifier).collect(),
+        }
+    }
+}
+
+impl_map_observer_wrapper!(ClassifyMapObserver, u8);
+
+/// Bucket a 16-bit hitcount to its highest set bit, e.g., `10` to `8` and `1000` to `512`.
+/// Unlike the 8-bit [`HitcountsMapObserver`] buckets, this keeps distinguishing counts beyond `128`.
+#[must_use]
+#[inline]
+pub fn extended_count_class(count: u16) -> u16 {
+    if count == 0 {
+        0
+    } else {
+        1 << (15 - count.leading_zeros())
+    }
+}
+
+/// Map observer with hitcounts postprocessing for 16-bit counters, see [`extended_count_class`].
+/// Meant for a small map of extended counters, for example for the loop headers of a target,
+/// where the 8-bit counters of the edges map saturate and lose the amount of loop iterations.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound = "M: serde::de::DeserializeOwned")]
+pub struct ExtendedHitcountsMapObserver<M>
+where
+    M: Serialize + serde::de::DeserializeOwned,
+{
+    base: M,
+}
+
+impl<I, S, M> Observer<I, S> for ExtendedHitcountsMapObserver<M>
+where
+    M: MapObserver<Entry = u16> + Observer<I, S>,
+    for<'it> M: AsMutIterator<'it, Item = u16>,
+{
+    #[inline]
+    fn pre_exec(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.base.pre_exec(state, input)
+    }
+
+    #[inline]
+    fn post_exec(&mut self, state: &mut S, input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        for elem in self.as_mut_iter() {
+            *elem = extended_count_class(*elem);
+        }
+        self.base.post_exec(state, input, exit_kind)
+    }
+}
+
+impl<M> ExtendedHitcountsMapObserver<M>
+where
+    M: Serialize + serde::de::DeserializeOwned,
+{
+    /// Creates a new [`MapObserver`]
+    pub fn new(base: M) -> Self {
+        Self { base }
+    }
+}
+
+impl_map_observer_wrapper!(ExtendedHitcountsMapObserver, u16);
+
+/// Map observer wrapper resetting only the entries touched in the last run, instead of the whole map.
+/// The runtime records the index of each entry it sets in a dirty list, as done in `libafl_targets`
+/// with the `edges_dirty_list` feature. If more entries were touched than the list can hold,
+/// the next reset falls back to the full map, as does the first reset, since the map may hold anything
+/// written before the observer was created or restored.
+/// The reset happens in `pre_exec`, the `pre_exec` of the wrapped observer is not called.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "M: serde::de::DeserializeOwned")]
+#[allow(clippy::unsafe_derive_deserialize)]
+pub struct DirtyMapObserver<'a, M>
+where
+    M: Serialize + serde::de::DeserializeOwned,
+{
+    base: M,
+    dirty_list: OwnedSliceMut<'a, usize>,
+    dirty_len: OwnedRefMut<'a, usize>,
+    /// If the map got fully reset once, so that only the entries in the dirty list may be set
+    #[serde(skip)]
+    clean: bool,
+}
+
+impl<'a, I, S, M> Observer<I, S> for DirtyMapObserver<'a, M>
+where
+    M: MapObserver + Observer<I, S>,
+{
+    #[inline]
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.reset_map()
+    }
+
+    #[inline]
+    fn post_exec(&mut self, state: &mut S, input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        self.base.post_exec(state, input, exit_kind)
+    }
+}
+
+impl<'a, M> Named for DirtyMapObserver<'a, M>
 where
     M: Named + Serialize + serde::de::DeserializeOwned,
 {
@@ -822,7 +1157,7 @@ where
     }
 }
 
-impl<M> HasLen for HitcountsMapObserver<M>
+impl<'a, M> HasLen for DirtyMapObserver<'a, M>
 where
     M: MapObserver,
 {
@@ -832,20 +1167,19 @@ where
     }
 }
 
-impl<M> MapObserver for HitcountsMapObserver<M>
+impl<'a, M> MapObserver for DirtyMapObserver<'a, M>
 where
-    M: MapObserver<Entry = u8>,
-    for<'it> M: AsMutIterator<'it, Item = u8>,
+    M: MapObserver,
 {
-    type Entry = u8;
+    type Entry = M::Entry;
 
     #[inline]
-    fn initial(&self) -> u8 {
+    fn initial(&self) -> M::Entry {
         self.base.initial()
     }
 
     #[inline]
-    fn initial_mut(&mut self) -> &mut u8 {
+    fn initial_mut(&mut self) -> &mut M::Entry {
         self.base.initial_mut()
     }
 
@@ -855,57 +1189,74 @@ where
     }
 
     #[inline]
-    fn get(&self, idx: usize) -> &u8 {
+    fn get(&self, idx: usize) -> &M::Entry {
         self.base.get(idx)
     }
 
     #[inline]
-    fn get_mut(&mut self, idx: usize) -> &mut u8 {
+    fn get_mut(&mut self, idx: usize) -> &mut M::Entry {
         self.base.get_mut(idx)
     }
 
     fn hash(&self) -> u64 {
         self.base.hash()
     }
-    fn to_vec(&self) -> Vec<u8> {
+
+    fn to_vec(&self) -> Vec<M::Entry> {
         self.base.to_vec()
     }
-}
 
-impl<M> AsSlice<u8> for HitcountsMapObserver<M>
-where
-    M: MapObserver + AsSlice<u8>,
-{
     #[inline]
-    fn as_slice(&self) -> &[u8] {
-        self.base.as_slice()
+    fn as_contiguous_slice(&self) -> Option<&[M::Entry]> {
+        self.base.as_contiguous_slice()
+    }
+
+    /// Reset the entries in the dirty list, or the whole map on the first reset or if the list overflowed
+    fn reset_map(&mut self) -> Result<(), Error> {
+        let dirty_len = *self.dirty_len.as_ref();
+        if !self.clean || dirty_len > self.dirty_list.as_slice().len() {
+            self.base.reset_map()?;
+            self.clean = true;
+        } else {
+            let initial = self.base.initial();
+            let cnt = self.base.usable_count();
+            for i in 0..dirty_len {
+                let idx = self.dirty_list.as_slice()[i];
+                if idx < cnt {
+                    *self.base.get_mut(idx) = initial;
+                }
+            }
+        }
+        *self.dirty_len.as_mut() = 0;
+        Ok(())
     }
 }
-impl<M> AsMutSlice<u8> for HitcountsMapObserver<M>
+
+impl<'a, M> AsSlice<M::Entry> for DirtyMapObserver<'a, M>
 where
-    M: MapObserver + AsMutSlice<u8>,
+    M: MapObserver + AsSlice<M::Entry>,
 {
     #[inline]
-    fn as_mut_slice(&mut self) -> &mut [u8] {
-        self.base.as_mut_slice()
+    fn as_slice(&self) -> &[M::Entry] {
+        self.base.as_slice()
     }
 }
 
-impl<M> HitcountsMapObserver<M>
+impl<'a, M> AsMutSlice<M::Entry> for DirtyMapObserver<'a, M>
 where
-    M: Serialize + serde::de::DeserializeOwned,
+    M: MapObserver + AsMutSlice<M::Entry>,
 {
-    /// Creates a new [`MapObserver`]
-    pub fn new(base: M) -> Self {
-        Self { base }
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [M::Entry] {
+        self.base.as_mut_slice()
     }
 }
 
-impl<'it, M> AsRefIterator<'it> for HitcountsMapObserver<M>
+impl<'a, 'it, M> AsRefIterator<'it> for DirtyMapObserver<'a, M>
 where
-    M: Named + Serialize + serde::de::DeserializeOwned + AsRefIterator<'it, Item = u8>,
+    M: Named + Serialize + serde::de::DeserializeOwned + AsRefIterator<'it>,
 {
-    type Item = u8;
+    type Item = <M as AsRefIterator<'it>>::Item;
     type IntoIter = <M as AsRefIterator<'it>>::IntoIter;
 
     fn as_ref_iter(&'it self) -> Self::IntoIter {
@@ -913,11 +1264,11 @@ where
     }
 }
 
-impl<'it, M> AsMutIterator<'it> for HitcountsMapObserver<M>
+impl<'a, 'it, M> AsMutIterator<'it> for DirtyMapObserver<'a, M>
 where
-    M: Named + Serialize + serde::de::DeserializeOwned + AsMutIterator<'it, Item = u8>,
+    M: Named + Serialize + serde::de::DeserializeOwned + AsMutIterator<'it>,
 {
-    type Item = u8;
+    type Item = <M as AsMutIterator<'it>>::Item;
     type IntoIter = <M as AsMutIterator<'it>>::IntoIter;
 
     fn as_mut_iter(&'it mut self) -> Self::IntoIter {
@@ -925,29 +1276,228 @@ where
     }
 }
 
-impl<'it, M> IntoIterator for &'it HitcountsMapObserver<M>
+impl<'a, M> DirtyMapObserver<'a, M>
 where
-    M: Named + Serialize + serde::de::DeserializeOwned,
-    &'it M: IntoIterator<Item = &'it u8>,
+    M: Serialize + serde::de::DeserializeOwned,
 {
-    type Item = &'it u8;
-    type IntoIter = <&'it M as IntoIterator>::IntoIter;
+    /// Creates a new [`DirtyMapObserver`], wrapping `base`.
+    /// The runtime appends the indexes it touches to `dirty_list`, incrementing `dirty_len` for each of them,
+    /// also past the capacity of the list.
+    pub fn new(base: M, dirty_list: &'a mut [usize], dirty_len: &'a mut usize) -> Self {
+        Self {
+            base,
+            dirty_list: OwnedSliceMut::from(dirty_list),
+            dirty_len: OwnedRefMut::Ref(dirty_len),
+            clean: false,
+        }
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.base.into_iter()
+    /// Creates a new [`DirtyMapObserver`] from a raw dirty list of `dirty_list_cap` entries and a raw length.
+    ///
+    /// # Safety
+    /// The pointers must be valid for the whole lifetime of the observer.
+    pub unsafe fn from_raw_parts(
+        base: M,
+        dirty_list: *mut usize,
+        dirty_list_cap: usize,
+        dirty_len: *mut usize,
+    ) -> Self {
+        Self {
+            base,
+            dirty_list: OwnedSliceMut::from_raw_parts_mut(dirty_list, dirty_list_cap),
+            dirty_len: OwnedRefMut::Ref(&mut *dirty_len),
+            clean: false,
+        }
+    }
+}
+
+/// Map observer wrapper salting the coverage indexes with the size class of the input,
+/// the log2 of its length, bucketed into `size_classes` classes.
+/// The same edges hit by inputs of different size classes then land in different map entries,
+/// rewarding inputs of new sizes, as some bugs only trigger at specific sizes.
+/// The observer shows the map shifted by `class * len / size_classes` entries, without touching the map itself,
+/// so each class needs free room in the map: only use this if the map is far from saturated.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound = "M: serde::de::DeserializeOwned")]
+pub struct SizeClassMapObserver<M>
+where
+    M: Serialize + serde::de::DeserializeOwned,
+{
+    base: M,
+    size_classes: usize,
+    class: usize,
+}
+
+impl<I, S, M> Observer<I, S> for SizeClassMapObserver<M>
+where
+    I: HasLen,
+    M: MapObserver + Observer<I, S>,
+{
+    #[inline]
+    fn pre_exec(&mut self, state: &mut S, input: &I) -> Result<(), Error> {
+        self.class = self.size_class(input.len());
+        self.base.pre_exec(state, input)
+    }
+
+    #[inline]
+    fn post_exec(&mut self, state: &mut S, input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        self.base.post_exec(state, input, exit_kind)
     }
 }
 
-impl<'it, M> IntoIterator for &'it mut HitcountsMapObserver<M>
+impl<M> Named for SizeClassMapObserver<M>
 where
     M: Named + Serialize + serde::de::DeserializeOwned,
-    &'it mut M: IntoIterator<Item = &'it mut u8>,
 {
-    type Item = &'it mut u8;
-    type IntoIter = <&'it mut M as IntoIterator>::IntoIter;
+    #[inline]
+    fn name(&self) -> &str {
+        self.base.name()
+    }
+}
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.base.into_iter()
+impl<M> HasLen for SizeClassMapObserver<M>
+where
+    M: MapObserver,
+{
+    #[inline]
+    fn len(&self) -> usize {
+        self.base.len()
+    }
+}
+
+impl<M> MapObserver for SizeClassMapObserver<M>
+where
+    M: MapObserver,
+{
+    type Entry = M::Entry;
+
+    #[inline]
+    fn initial(&self) -> M::Entry {
+        self.base.initial()
+    }
+
+    #[inline]
+    fn initial_mut(&mut self) -> &mut M::Entry {
+        self.base.initial_mut()
+    }
+
+    #[inline]
+    fn usable_count(&self) -> usize {
+        self.base.usable_count()
+    }
+
+    #[inline]
+    fn get(&self, idx: usize) -> &M::Entry {
+        self.base.get(self.salted(idx))
+    }
+
+    #[inline]
+    fn get_mut(&mut self, idx: usize) -> &mut M::Entry {
+        let idx = self.salted(idx);
+        self.base.get_mut(idx)
+    }
+
+    #[inline]
+    fn count_bytes(&self) -> u64 {
+        self.base.count_bytes()
+    }
+
+    fn hash(&self) -> u64 {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        hasher.write_u64(self.base.hash());
+        hasher.write_usize(self.offset());
+        hasher.finish()
+    }
+
+    #[inline]
+    fn reset_map(&mut self) -> Result<(), Error> {
+        self.base.reset_map()
+    }
+
+    fn to_vec(&self) -> Vec<M::Entry> {
+        (0..self.usable_count()).map(|i| *self.get(i)).collect()
+    }
+
+    #[inline]
+    fn as_contiguous_slice(&self) -> Option<&[M::Entry]> {
+        if self.offset() == 0 {
+            self.base.as_contiguous_slice()
+        } else {
+            None
+        }
+    }
+}
+
+impl<'it, M> AsRefIterator<'it> for SizeClassMapObserver<M>
+where
+    M: MapObserver + AsRefIterator<'it>,
+{
+    type Item = <M as AsRefIterator<'it>>::Item;
+    type IntoIter = Chain<
+        Skip<Take<<M as AsRefIterator<'it>>::IntoIter>>,
+        Take<<M as AsRefIterator<'it>>::IntoIter>,
+    >;
+
+    fn as_ref_iter(&'it self) -> Self::IntoIter {
+        let cnt = self.base.usable_count();
+        let offset = self.offset();
+        self.base
+            .as_ref_iter()
+            .take(cnt)
+            .skip(cnt - offset)
+            .chain(self.base.as_ref_iter().take(cnt - offset))
+    }
+}
+
+impl<M> SizeClassMapObserver<M>
+where
+    M: Serialize + serde::de::DeserializeOwned,
+{
+    /// Creates a new [`SizeClassMapObserver`], wrapping `base`, with `size_classes` size classes.
+    /// An input of length `len` is in class `log2(len) + 1` (`0` for empty inputs),
+    /// all inputs of larger classes share the last one. With a single class, the indexes are not salted.
+    #[must_use]
+    pub fn new(base: M, size_classes: usize) -> Self {
+        Self {
+            base,
+            size_classes: size_classes.max(1),
+            class: 0,
+        }
+    }
+
+    /// The amount of size classes
+    #[must_use]
+    pub fn size_classes(&self) -> usize {
+        self.size_classes
+    }
+
+    /// The size class of an input of length `len`
+    #[must_use]
+    pub fn size_class(&self, len: usize) -> usize {
+        let class = (usize::BITS - len.leading_zeros()) as usize;
+        class.min(self.size_classes - 1)
+    }
+
+    /// The amount of entries the map is shifted by for the size class of the last input
+    fn offset(&self) -> usize
+    where
+        M: MapObserver,
+    {
+        self.class * self.base.usable_count() / self.size_classes
+    }
+
+    /// The index in the `base` map shown at `idx`
+    fn salted(&self, idx: usize) -> usize
+    where
+        M: MapObserver,
+    {
+        let offset = self.offset();
+        if offset == 0 {
+            idx
+        } else {
+            let cnt = self.base.usable_count();
+            (idx + cnt - offset) % cnt
+        }
     }
 }
 
@@ -1316,6 +1866,11 @@ where
     fn to_vec(&self) -> Vec<T> {
         self.as_slice().to_vec()
     }
+
+    #[inline]
+    fn as_contiguous_slice(&self) -> Option<&[T]> {
+        Some(self.as_slice())
+    }
 }
 
 impl<T> AsSlice<T> for OwnedMapObserver<T>
@@ -1354,6 +1909,204 @@ where
         }
     }
 }
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{
+            rands::{Rand, StdRand},
+            AsMutSlice, AsRefIterator,
+        },
+        executors::ExitKind,
+        inputs::{BytesInput, NopInput},
+        observers::{
+            count_class, ClassifyMapObserver, DirtyMapObserver, ExtendedHitcountsMapObserver,
+            HitcountsMapObserver, MapObserver, Observer, SizeClassMapObserver, StdMapObserver,
+        },
+    };
+
+    #[test]
+    fn test_classify_map_observer() {
+        let mut map = [0_u8, 1, 5, 0, 255];
+        // Only distinguishes between hit and not hit
+        let mut observer =
+            ClassifyMapObserver::new(StdMapObserver::new("map", &mut map), |value| {
+                u8::from(value != 0)
+            });
+        observer
+            .post_exec(&mut (), &NopInput {}, &ExitKind::Ok)
+            .unwrap();
+        assert_eq!(observer.to_vec(), vec![0, 1, 1, 0, 1]);
+
+        let mut map = [0_u8, 1, 5, 0, 255];
+        let mut observer =
+            ClassifyMapObserver::new(StdMapObserver::new("map", &mut map), count_class);
+        observer
+            .post_exec(&mut (), &NopInput {}, &ExitKind::Ok)
+            .unwrap();
+        assert_eq!(observer.to_vec(), vec![0, 1, 8, 0, 128]);
+
+        // A deserialized observer, e.g. after a restart, keeps classifying the same way
+        let mut map = [0_u8, 1, 5, 0, 255];
+        let observer = ClassifyMapObserver::new(StdMapObserver::new("map", &mut map), count_class);
+        let serialized = postcard::to_allocvec(&observer).unwrap();
+        let mut observer: ClassifyMapObserver<StdMapObserver<u8>> =
+            postcard::from_bytes(&serialized).unwrap();
+        observer
+            .post_exec(&mut (), &NopInput {}, &ExitKind::Ok)
+            .unwrap();
+        assert_eq!(observer.to_vec(), vec![0, 1, 8, 0, 128]);
+    }
+
+    #[test]
+    fn test_extended_hitcounts_loop_iterations() {
+        // A loop header hit 10 times, 1000 times, and 2000 times
+        let counts = [10_u16, 1000, 2000];
+
+        // The 8-bit counters wrap around and lose the amount of iterations
+        let mut map = [10_u8, (1000 % 256) as u8, (2000 % 256) as u8];
+        let mut observer = HitcountsMapObserver::new(StdMapObserver::new("map", &mut map));
+        observer
+            .post_exec(&mut (), &NopInput {}, &ExitKind::Ok)
+            .unwrap();
+        assert_eq!(observer.to_vec(), vec![16, 128, 128]);
+
+        let mut map = counts;
+        let mut observer =
+            ExtendedHitcountsMapObserver::new(StdMapObserver::new("loops", &mut map));
+        observer
+            .post_exec(&mut (), &NopInput {}, &ExitKind::Ok)
+            .unwrap();
+        assert_eq!(observer.to_vec(), vec![8, 512, 1024]);
+    }
+
+    #[test]
+    fn test_size_class_map_observer() {
+        let mut map = [0_u8; 64];
+        let map_ptr = map.as_mut_ptr();
+        let mut observer = SizeClassMapObserver::new(
+            unsafe { StdMapObserver::new_from_ptr("map", map_ptr, 64) },
+            4,
+        );
+        assert_eq!(observer.size_class(0), 0);
+        assert_eq!(observer.size_class(1), 1);
+        assert_eq!(observer.size_class(3), 2);
+        assert_eq!(observer.size_class(1 << 20), 3);
+
+        // The target hits the same two edges of the map for each input
+        let mut run = |input: &BytesInput| {
+            observer.pre_exec(&mut (), input).unwrap();
+            observer.reset_map().unwrap();
+            unsafe {
+                *map_ptr.add(5) = 1;
+                *map_ptr.add(60) = 1;
+            }
+            observer.post_exec(&mut (), input, &ExitKind::Ok).unwrap();
+            let view = observer.to_vec();
+            assert_eq!(observer.as_ref_iter().copied().collect::<Vec<_>>(), view);
+            (view, observer.hash())
+        };
+        let (small, small_hash) = run(&BytesInput::new(vec![0; 1]));
+        let (medium, medium_hash) = run(&BytesInput::new(vec![0; 2]));
+        let (large, large_hash) = run(&BytesInput::new(vec![0; 4]));
+        let (larger, larger_hash) = run(&BytesInput::new(vec![0; 4096]));
+
+        assert_eq!(small.iter().filter(|&&e| e != 0).count(), 2);
+        // The map itself is left alone
+        assert_eq!(map[5], 1);
+        assert_eq!(map[60], 1);
+        assert_ne!(small, medium);
+        assert_ne!(medium, large);
+        assert_ne!(small, large);
+        assert_ne!(small_hash, medium_hash);
+        // Large inputs share the last size class
+        assert_eq!(large, larger);
+        assert_eq!(large_hash, larger_hash);
+
+        // A single size class does not salt the indexes
+        let mut map = [0_u8; 64];
+        let map_ptr = map.as_mut_ptr();
+        let mut observer = SizeClassMapObserver::new(
+            unsafe { StdMapObserver::new_from_ptr("map", map_ptr, 64) },
+            1,
+        );
+        let input = BytesInput::new(vec![0; 100]);
+        observer.pre_exec(&mut (), &input).unwrap();
+        unsafe {
+            *map_ptr.add(5) = 1;
+        }
+        observer.post_exec(&mut (), &input, &ExitKind::Ok).unwrap();
+        assert_eq!(*observer.get(5), 1);
+        assert!(observer.as_contiguous_slice().is_some());
+    }
+
+    #[test]
+    fn test_std_map_observer_borrowed_slice() {
+        let mut map = [0_u8; 16];
+        {
+            let mut observer = StdMapObserver::new("map", &mut map);
+            observer.as_mut_slice()[3] = 1;
+            observer.as_mut_slice()[7] = 2;
+            assert_eq!(observer.count_bytes(), 2);
+            observer.reset_map().unwrap();
+            observer.as_mut_slice()[5] = 1;
+        }
+        // The observer wrote through to the borrowed map
+        assert_eq!(map[5], 1);
+        assert_eq!(map.iter().filter(|&&e| e != 0).count(), 1);
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation)]
+    fn test_dirty_map_observer_reset() {
+        const MAP_SIZE: usize = 1 << 16;
+        let mut full_map = vec![0_u8; MAP_SIZE];
+        let mut dirty_map = vec![0_u8; MAP_SIZE];
+        let mut dirty_list = vec![0_usize; 64];
+        let mut dirty_len = 0;
+        let list_ptr = dirty_list.as_mut_ptr();
+        let len_ptr: *mut usize = &mut dirty_len;
+
+        // Leftovers from before the observer existed, not in the dirty list
+        dirty_map[42] = 1;
+        dirty_map[MAP_SIZE - 1] = 3;
+
+        let mut full = StdMapObserver::new("full", &mut full_map);
+        let mut dirty = unsafe {
+            DirtyMapObserver::from_raw_parts(
+                StdMapObserver::new("dirty", &mut dirty_map),
+                list_ptr,
+                64,
+                len_ptr,
+            )
+        };
+
+        let mut rand = StdRand::with_seed(1337);
+        // Runs touching less and more entries than the dirty list holds
+        for hits in [0, 1, 10, 64, 65, 500, 3] {
+            full.pre_exec(&mut (), &NopInput {}).unwrap();
+            dirty.pre_exec(&mut (), &NopInput {}).unwrap();
+            assert_eq!(full.to_vec(), dirty.to_vec());
+            assert_eq!(dirty.count_bytes(), 0);
+
+            // What the runtime does: record each entry on its first hit
+            for _ in 0..hits {
+                let idx = rand.below(MAP_SIZE as u64) as usize;
+                *full.get_mut(idx) = full.get(idx).wrapping_add(1);
+                if *dirty.get(idx) == 0 {
+                    unsafe {
+                        if *len_ptr < 64 {
+                            *list_ptr.add(*len_ptr) = idx;
+                        }
+                        *len_ptr += 1;
+                    }
+                }
+                *dirty.get_mut(idx) = dirty.get(idx).wrapping_add(1);
+            }
+            assert_eq!(full.to_vec(), dirty.to_vec());
+        }
+    }
+}
+
 /// `MapObserver` Python bindings
 #[cfg(feature = "python")]
 pub mod pybind {
@@ -16,14 +16,33 @@ pub mod stacktrace;
 #[cfg(feature = "std")]
 pub use stacktrace::*;
 
+#[cfg(feature = "std")]
+pub mod msan;
+#[cfg(feature = "std")]
+pub use msan::{MsanErrorsObserver, MsanReport};
+
 pub mod concolic;
 
+pub mod profile;
+pub use profile::{EdgeTimingCursor, EdgeTimingMetadata, EdgeTimingObserver};
+
+pub mod value;
+pub use value::MaxValueObserver;
+
+pub mod portable;
+pub use portable::{
+    PortableData, PortableObservation, PortableObserver, PORTABLE_OBSERVATION_VERSION,
+};
+
 #[cfg(unstable_feature)]
 pub mod owned;
 #[cfg(unstable_feature)]
 pub use owned::*;
 
-use alloc::string::{String, ToString};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
 use core::{fmt::Debug, time::Duration};
 use serde::{Deserialize, Serialize};
 
@@ -104,6 +123,12 @@ pub trait ObserversTuple<I, S>: MatchName + Debug {
         input: &I,
         exit_kind: &ExitKind,
     ) -> Result<(), Error>;
+
+    /// The names of all observers in this tuple, in order, for diagnostics.
+    /// Empty, unless implemented.
+    fn names(&self) -> Vec<&str> {
+        Vec::new()
+    }
 }
 
 impl<I, S> ObserversTuple<I, S> for () {
@@ -168,6 +193,12 @@ where
         self.0.post_exec_child(state, input, exit_kind)?;
         self.1.post_exec_child_all(state, input, exit_kind)
     }
+
+    fn names(&self) -> Vec<&str> {
+        let mut names = vec![self.0.name()];
+        names.append(&mut self.1.names());
+        names
+    }
 }
 
 /// A trait for obervers with a hash field
@@ -235,7 +266,8 @@ mod tests {
 
     use crate::{
         bolts::tuples::{tuple_list, tuple_list_type, Named},
-        observers::{StdMapObserver, TimeObserver},
+        inputs::NopInput,
+        observers::{ObserversTuple, StdMapObserver, StdOutObserver, TimeObserver},
     };
 
     static mut MAP: [u32; 4] = [0; 4];
@@ -252,4 +284,18 @@ mod tests {
             postcard::from_bytes(&vec).unwrap();
         assert_eq!(obv.0.name(), obv2.0.name());
     }
+
+    #[test]
+    fn test_observer_names() {
+        let obv = tuple_list!(
+            TimeObserver::new("time"),
+            StdMapObserver::new("map", unsafe { &mut MAP }),
+            StdOutObserver::new("stdout".into())
+        );
+        assert_eq!(
+            <_ as ObserversTuple<NopInput, ()>>::names(&obv),
+            vec!["time", "map", "stdout"]
+        );
+        assert!(<() as ObserversTuple<NopInput, ()>>::names(&()).is_empty());
+    }
 }
@@ -0,0 +1,142 @@
+//! The [`MsanErrorsObserver`] parses the reports of the `MemorySanitizer` (`MSan`) in the output of a target,
+//! such as reads of uninitialized memory. The executor must explicitly support it,
+//! for example, it is supported on the [`crate::executors::CommandExecutor`].
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::{bolts::tuples::Named, observers::Observer, Error};
+
+/// The prefix of the first line of a `MSan` report, followed by the kind of the error
+const MSAN_WARNING: &str = "WARNING: MemorySanitizer: ";
+/// The prefix of the last line of a `MSan` report, followed by the kind and the location of the error
+const MSAN_SUMMARY: &str = "SUMMARY: MemorySanitizer: ";
+/// A stack frame of a `MSan` report, with its program counter and, if printed, its module and offset
+const MSAN_FRAME: &str = r"^\s*#[0-9]+\s+0x([0-9a-f]+)(?:.*\(([^()\s]+\+0x[0-9a-f]+)\))?";
+
+/// A report of the `MemorySanitizer`, parsed from the output of the target
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MsanReport {
+    /// The kind of the error, e.g. `use-of-uninitialized-value`
+    pub kind: String,
+    /// The program counters of the stack frames of the report, innermost first
+    pub frames: Vec<u64>,
+    /// The summary of the report, with the location of the error, if the target printed it
+    pub summary: Option<String>,
+    /// The module and offset of the innermost frame, like `libfoo.so+0x1234`, if the target printed it
+    pub module_offset: Option<String>,
+}
+
+crate::impl_serdeany!(MsanReport);
+
+impl MsanReport {
+    /// The site of the report, identifying the error for deduplication.
+    /// It is the hash of the summary, else of the module and offset of the innermost frame,
+    /// which, unlike the program counters, stay the same across runs of position independent targets.
+    /// Without either of them, it falls back to the program counter of the innermost frame.
+    #[must_use]
+    pub fn site(&self) -> u64 {
+        match self.summary.as_ref().or(self.module_offset.as_ref()) {
+            Some(location) => xxh3_64(location.as_bytes()),
+            None => self.frames.first().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Parses the first `MSan` report in `output`, if there is one, matching the stack frames with `frame`
+fn parse_msan_report(frame: &Regex, output: &str) -> Option<MsanReport> {
+    let mut lines = output.lines();
+    let kind = lines.find_map(|line| {
+        line.find(MSAN_WARNING)
+            .map(|pos| line[pos + MSAN_WARNING.len()..].trim().to_string())
+    })?;
+
+    let mut frames = vec![];
+    let mut module_offset = None;
+    let mut in_stack = true;
+    let mut summary = None;
+    for line in lines {
+        if let Some(pos) = line.find(MSAN_SUMMARY) {
+            summary = Some(line[pos + MSAN_SUMMARY.len()..].trim().to_string());
+            break;
+        }
+        if !in_stack {
+            continue;
+        }
+        match frame.captures(line) {
+            Some(captures) => {
+                if frames.is_empty() {
+                    module_offset = captures.get(2).map(|m| m.as_str().to_string());
+                }
+                frames.push(u64::from_str_radix(&captures[1], 16).unwrap());
+            }
+            // Only the first stack is the one of the error, the next ones show where the memory came from
+            None if !frames.is_empty() => in_stack = false,
+            None => (),
+        }
+    }
+    Some(MsanReport {
+        kind,
+        frames,
+        summary,
+        module_offset,
+    })
+}
+
+fn msan_frame_regex() -> Regex {
+    Regex::new(MSAN_FRAME).unwrap()
+}
+
+/// An observer holding the `MSan` report of the last run, if any
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MsanErrorsObserver {
+    observer_name: String,
+    report: Option<MsanReport>,
+    /// Compiled once, instead of on each parsed output
+    #[serde(skip, default = "msan_frame_regex")]
+    frame: Regex,
+}
+
+impl MsanErrorsObserver {
+    /// Creates a new [`MsanErrorsObserver`] with the given name.
+    /// Executors look for it by its default name, so prefer [`MsanErrorsObserver::default`].
+    #[must_use]
+    pub fn new(observer_name: &str) -> Self {
+        Self {
+            observer_name: observer_name.to_string(),
+            report: None,
+            frame: msan_frame_regex(),
+        }
+    }
+
+    /// Parses the output of the target, usually its `stderr`, keeping the first `MSan` report in it
+    pub fn parse_msan_output(&mut self, output: &str) {
+        self.report = parse_msan_report(&self.frame, output);
+    }
+
+    /// The `MSan` report of the last run, if any
+    #[must_use]
+    pub fn report(&self) -> Option<&MsanReport> {
+        self.report.as_ref()
+    }
+}
+
+impl Default for MsanErrorsObserver {
+    fn default() -> Self {
+        Self::new("MsanErrorsObserver")
+    }
+}
+
+impl<I, S> Observer<I, S> for MsanErrorsObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.report = None;
+        Ok(())
+    }
+}
+
+impl Named for MsanErrorsObserver {
+    fn name(&self) -> &str {
+        &self.observer_name
+    }
+}
@@ -1,12 +1,13 @@
 //! A dynamic collection of owned observers, working only with unstable rust
 
+use alloc::vec::Vec;
 use core::{any::Any, fmt::Debug};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{
     bolts::{
         anymap::{pack_type_id, AsAny},
-        tuples::MatchName,
+        tuples::{MatchName, Named},
     },
     executors::ExitKind,
     observers::{Observer, ObserversTuple},
@@ -96,6 +97,16 @@ impl<I: 'static + Debug, S: 'static + Debug> ObserversTuple<I, S> for ObserversO
         self.map
             .for_each_mut(&mut |_, ob| ob.post_exec_child(state, input, exit_kind))
     }
+
+    /// The names of all observers, in no particular order
+    fn names(&self) -> Vec<&str> {
+        self.map
+            .all_typeids()
+            .filter_map(|id| self.map.all_by_typeid(&id))
+            .flatten()
+            .map(|ob| ob.name())
+            .collect()
+    }
 }
 
 impl<I: 'static + Debug, S: 'static + Debug> MatchName for ObserversOwnedMap<I, S> {
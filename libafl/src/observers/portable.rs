@@ -0,0 +1,177 @@
+//! A portable, versioned representation of the observations of a run, for tools written in other languages.
+//! Unlike the serde representation of the observers themselves, which follows their internals,
+//! the schema of a [`PortableObservation`] only changes together with [`PORTABLE_OBSERVATION_VERSION`].
+//!
+//! As JSON, see [`PortableObservation::to_json`], an observation looks like this:
+//!
+//! ```json
+//! {"version":1,"name":"edges","data":{"type":"map","entries":[0,1,0,3]}}
+//! {"version":1,"name":"time","data":{"type":"time","runtime_ns":1200}}
+//! ```
+//!
+//! Map entries are unsigned integers, `runtime_ns` is `null` if the run did not finish.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Debug;
+use num_traits::PrimInt;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use crate::Error;
+use crate::{
+    bolts::{tuples::Named, AsSlice},
+    observers::{StdMapObserver, TimeObserver},
+};
+
+/// The version of the schema of the [`PortableObservation`], increased on each change of it
+pub const PORTABLE_OBSERVATION_VERSION: u32 = 1;
+
+/// The observation of a run, in the portable schema
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PortableObservation {
+    /// The version of the schema, [`PORTABLE_OBSERVATION_VERSION`] for observations made by this version of `LibAFL`
+    pub version: u32,
+    /// The name of the observer
+    pub name: String,
+    /// The observed data
+    pub data: PortableData,
+}
+
+/// The data of a [`PortableObservation`], tagged with its `type`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PortableData {
+    /// The entries of a map, such as a coverage map. Negative entries, of signed maps, are stored as `0`.
+    Map {
+        /// The entries
+        entries: Vec<u64>,
+    },
+    /// The runtime of the target
+    Time {
+        /// The runtime in nanoseconds, `None` if the run did not finish
+        runtime_ns: Option<u64>,
+    },
+}
+
+impl PortableObservation {
+    /// Creates a new [`PortableObservation`] of the current version
+    #[must_use]
+    pub fn new(name: &str, data: PortableData) -> Self {
+        Self {
+            version: PORTABLE_OBSERVATION_VERSION,
+            name: name.to_string(),
+            data,
+        }
+    }
+
+    /// Serializes the observation to JSON
+    #[cfg(feature = "std")]
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Deserializes an observation from JSON.
+    /// Fails for observations of a newer version of the schema, unknown to this version of `LibAFL`.
+    #[cfg(feature = "std")]
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let observation: Self = serde_json::from_str(json)?;
+        if observation.version > PORTABLE_OBSERVATION_VERSION {
+            return Err(Error::IllegalArgument(format!(
+                "Unsupported portable observation version {}, the latest known is {}",
+                observation.version, PORTABLE_OBSERVATION_VERSION
+            )));
+        }
+        Ok(observation)
+    }
+}
+
+/// An observer that can hand out its last observation in the portable schema
+pub trait PortableObserver: Named {
+    /// The last observation, as [`PortableObservation`]
+    fn to_portable(&self) -> PortableObservation;
+}
+
+impl<'a, T> PortableObserver for StdMapObserver<'a, T>
+where
+    T: PrimInt + Default + Copy + 'static + Serialize + serde::de::DeserializeOwned + Debug,
+{
+    fn to_portable(&self) -> PortableObservation {
+        let entries = self
+            .as_slice()
+            .iter()
+            .map(|entry| entry.to_u64().unwrap_or_default())
+            .collect();
+        PortableObservation::new(self.name(), PortableData::Map { entries })
+    }
+}
+
+impl PortableObserver for TimeObserver {
+    fn to_portable(&self) -> PortableObservation {
+        let runtime_ns = self
+            .last_runtime()
+            .map(|runtime| u64::try_from(runtime.as_nanos()).unwrap_or(u64::MAX));
+        PortableObservation::new(self.name(), PortableData::Time { runtime_ns })
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg(test)]
+mod tests {
+    use crate::{
+        executors::ExitKind,
+        observers::{
+            portable::{
+                PortableData, PortableObservation, PortableObserver, PORTABLE_OBSERVATION_VERSION,
+            },
+            Observer, StdMapObserver, TimeObserver,
+        },
+    };
+
+    #[test]
+    fn test_portable_observation() {
+        let map = StdMapObserver::new_owned("edges", vec![0_u16, 1, 0, 300]);
+        let portable = map.to_portable();
+        assert_eq!(portable.version, PORTABLE_OBSERVATION_VERSION);
+        assert_eq!(
+            portable.data,
+            PortableData::Map {
+                entries: vec![0, 1, 0, 300]
+            }
+        );
+
+        // The documented schema
+        let json = portable.to_json().unwrap();
+        assert_eq!(
+            json,
+            r#"{"version":1,"name":"edges","data":{"type":"map","entries":[0,1,0,300]}}"#
+        );
+        assert_eq!(PortableObservation::from_json(&json).unwrap(), portable);
+
+        let mut time = TimeObserver::new("time");
+        let json = time.to_portable().to_json().unwrap();
+        assert_eq!(
+            json,
+            r#"{"version":1,"name":"time","data":{"type":"time","runtime_ns":null}}"#
+        );
+        Observer::<(), ()>::pre_exec(&mut time, &mut (), &()).unwrap();
+        Observer::<(), ()>::post_exec(&mut time, &mut (), &(), &ExitKind::Ok).unwrap();
+        let portable = time.to_portable();
+        assert!(matches!(
+            portable.data,
+            PortableData::Time {
+                runtime_ns: Some(_)
+            }
+        ));
+        assert_eq!(
+            PortableObservation::from_json(&portable.to_json().unwrap()).unwrap(),
+            portable
+        );
+
+        // Observations of a future schema are rejected
+        let newer = r#"{"version":2,"name":"time","data":{"type":"time","runtime_ns":null}}"#;
+        assert!(PortableObservation::from_json(newer).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! Hot path profiling: the instrumentation timestamps each edge transition,
+//! and the [`EdgeTimingObserver`] sums up the time spent after each edge over all runs.
+//! Reading the time counter on each edge is expensive, so this is meant for profiling sessions only.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{
+        cpu::read_time_counter,
+        ownedref::{OwnedRefMut, OwnedSliceMut},
+        tuples::Named,
+        AsMutSlice, AsSlice,
+    },
+    executors::ExitKind,
+    observers::Observer,
+    state::HasMetadata,
+    Error,
+};
+
+/// The last edge transition of the current run, written by the instrumentation
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+#[repr(C)]
+pub struct EdgeTimingCursor {
+    /// The edge taken last
+    pub last_edge: usize,
+    /// The time counter at the last edge, or 0 if no edge was taken yet in this run
+    pub last_time: u64,
+}
+
+impl EdgeTimingCursor {
+    /// Records a transition to `edge`, adding the time since the previous transition to the previous edge in `times`.
+    /// This is what the instrumentation calls on each edge.
+    #[inline]
+    pub fn transition(&mut self, times: &mut [u64], edge: usize) {
+        let now = read_time_counter();
+        self.close(times, now);
+        self.last_edge = edge;
+        self.last_time = now;
+    }
+
+    /// Adds the time up to `now` to the last edge, if any
+    #[inline]
+    fn close(&mut self, times: &mut [u64], now: u64) {
+        if self.last_time != 0 {
+            if let Some(time) = times.get_mut(self.last_edge) {
+                *time = time.wrapping_add(now.wrapping_sub(self.last_time));
+            }
+        }
+    }
+}
+
+/// The time spent after each edge, summed up over all runs by the [`EdgeTimingObserver`]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EdgeTimingMetadata {
+    /// The summed up time counter deltas, for each edge
+    pub times: Vec<u64>,
+    /// The amount of profiled runs
+    pub runs: u64,
+}
+
+crate::impl_serdeany!(EdgeTimingMetadata);
+
+impl EdgeTimingMetadata {
+    /// The edge the most time was spent after, with its time
+    #[must_use]
+    pub fn hottest(&self) -> Option<(usize, u64)> {
+        self.hottest_n(1).pop()
+    }
+
+    /// The `n` edges the most time was spent after, hottest first
+    #[must_use]
+    pub fn hottest_n(&self, n: usize) -> Vec<(usize, u64)> {
+        let mut edges: Vec<(usize, u64)> = self
+            .times
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, time)| *time > 0)
+            .collect();
+        edges.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        edges.truncate(n);
+        edges
+    }
+}
+
+/// An observer for the edge timing map of a timing-enabled instrumentation,
+/// such as the `sancov_pcguard_timing` feature of `libafl_targets`.
+/// After each run, it adds the times of the run to the [`EdgeTimingMetadata`] of the state.
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(clippy::unsafe_derive_deserialize)]
+pub struct EdgeTimingObserver<'a> {
+    name: String,
+    times: OwnedSliceMut<'a, u64>,
+    cursor: OwnedRefMut<'a, EdgeTimingCursor>,
+}
+
+impl<'a, I, S> Observer<I, S> for EdgeTimingObserver<'a>
+where
+    S: HasMetadata,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.times.as_mut_slice().fill(0);
+        *self.cursor.as_mut() = EdgeTimingCursor::default();
+        Ok(())
+    }
+
+    fn post_exec(&mut self, state: &mut S, _input: &I, _exit_kind: &ExitKind) -> Result<(), Error> {
+        // The time after the last edge, up to the end of the run
+        let now = read_time_counter();
+        self.cursor.as_mut().close(self.times.as_mut_slice(), now);
+        *self.cursor.as_mut() = EdgeTimingCursor::default();
+
+        let times = self.times.as_slice();
+        if !state.has_metadata::<EdgeTimingMetadata>() {
+            state.add_metadata(EdgeTimingMetadata {
+                times: vec![0; times.len()],
+                runs: 0,
+            });
+        }
+        let meta = state
+            .metadata_mut()
+            .get_mut::<EdgeTimingMetadata>()
+            .unwrap();
+        if meta.times.len() < times.len() {
+            meta.times.resize(times.len(), 0);
+        }
+        for (total, time) in meta.times.iter_mut().zip(times) {
+            *total = total.wrapping_add(*time);
+        }
+        meta.runs += 1;
+        Ok(())
+    }
+}
+
+impl<'a> Named for EdgeTimingObserver<'a> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<'a> EdgeTimingObserver<'a> {
+    /// Creates a new [`EdgeTimingObserver`] for the given times map and cursor, both written by the instrumentation
+    #[must_use]
+    pub fn new(name: &str, times: &'a mut [u64], cursor: &'a mut EdgeTimingCursor) -> Self {
+        Self {
+            name: name.to_string(),
+            times: OwnedSliceMut::from(times),
+            cursor: OwnedRefMut::Ref(cursor),
+        }
+    }
+
+    /// Creates a new [`EdgeTimingObserver`] from a raw times map of `len` entries and a raw cursor.
+    ///
+    /// # Safety
+    /// The pointers must be valid for the whole lifetime of the observer.
+    #[must_use]
+    pub unsafe fn from_raw_parts(
+        name: &str,
+        times: *mut u64,
+        len: usize,
+        cursor: *mut EdgeTimingCursor,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            times: OwnedSliceMut::from_raw_parts_mut(times, len),
+            cursor: OwnedRefMut::Ref(&mut *cursor),
+        }
+    }
+
+    /// The times of the last run, for each edge
+    #[must_use]
+    pub fn times(&self) -> &[u64] {
+        self.times.as_slice()
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::InMemoryCorpus,
+        executors::ExitKind,
+        inputs::{BytesInput, HasBytesVec},
+        observers::{EdgeTimingCursor, EdgeTimingMetadata, EdgeTimingObserver, Observer},
+        state::{HasMetadata, StdState},
+    };
+
+    static mut TIMES: [u64; 8] = [0; 8];
+    static mut CURSOR: EdgeTimingCursor = EdgeTimingCursor {
+        last_edge: 0,
+        last_time: 0,
+    };
+
+    /// Calls the timing callback, as the instrumentation would
+    fn edge(pos: usize) {
+        unsafe { CURSOR.transition(&mut TIMES, pos) };
+    }
+
+    fn spin(duration: Duration) {
+        let start = Instant::now();
+        while start.elapsed() < duration {}
+    }
+
+    /// A harness spending most of its time in the loop after edge 3
+    fn harness(input: &BytesInput) -> ExitKind {
+        edge(1);
+        spin(Duration::from_micros(100));
+        edge(2);
+        if !input.bytes().is_empty() {
+            edge(3);
+            spin(Duration::from_millis(5));
+        }
+        edge(4);
+        ExitKind::Ok
+    }
+
+    #[test]
+    fn test_edge_timing_hottest() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut observer = unsafe {
+            EdgeTimingObserver::from_raw_parts("timing", TIMES.as_mut_ptr(), 8, &mut CURSOR)
+        };
+
+        for input in [BytesInput::new(vec![]), BytesInput::new(vec![1])] {
+            observer.pre_exec(&mut state, &input).unwrap();
+            let exit_kind = harness(&input);
+            observer.post_exec(&mut state, &input, &exit_kind).unwrap();
+        }
+
+        let meta = state.metadata().get::<EdgeTimingMetadata>().unwrap();
+        assert_eq!(meta.runs, 2);
+        assert_eq!(meta.hottest().unwrap().0, 3);
+        assert_eq!(meta.hottest_n(2)[1].0, 1);
+        // Edges never taken do not show up
+        assert!(meta
+            .hottest_n(8)
+            .iter()
+            .all(|(edge, _)| (1..=4).contains(edge)));
+        assert_eq!(observer.times()[0], 0);
+    }
+}
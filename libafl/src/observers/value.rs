@@ -0,0 +1,73 @@
+//! The [`MaxValueObserver`] watches a single scalar written by the harness, such as the deepest recursion level reached.
+
+use alloc::string::{String, ToString};
+use core::fmt::Debug;
+use num_traits::PrimInt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{ownedref::OwnedRefMut, tuples::Named},
+    observers::Observer,
+    Error,
+};
+
+/// An observer for a scalar value written by the harness during a run, reset to `0` before each run.
+/// Pair it with a [`crate::feedbacks::MaxValueFeedback`] to reward inputs reaching a new maximum.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "T: serde::de::DeserializeOwned")]
+#[allow(clippy::unsafe_derive_deserialize)]
+pub struct MaxValueObserver<'a, T>
+where
+    T: PrimInt + Serialize + Debug + 'static,
+{
+    name: String,
+    value: OwnedRefMut<'a, T>,
+}
+
+impl<'a, T> MaxValueObserver<'a, T>
+where
+    T: PrimInt + Serialize + Debug + 'static,
+{
+    /// Creates a new [`MaxValueObserver`] for the given value, written by the harness
+    #[must_use]
+    pub fn new(name: &str, value: &'a mut T) -> Self {
+        Self {
+            name: name.to_string(),
+            value: OwnedRefMut::Ref(value),
+        }
+    }
+
+    /// Creates a new [`MaxValueObserver`] from a raw pointer to the value, e.g. a global of the target.
+    ///
+    /// # Safety
+    /// The pointer must be valid for the whole lifetime of the observer.
+    #[must_use]
+    pub unsafe fn from_ptr(name: &str, value: *mut T) -> Self {
+        Self::new(name, &mut *value)
+    }
+
+    /// The value of the last run
+    #[must_use]
+    pub fn value(&self) -> T {
+        *self.value.as_ref()
+    }
+}
+
+impl<'a, I, S, T> Observer<I, S> for MaxValueObserver<'a, T>
+where
+    T: PrimInt + Serialize + Debug + 'static,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        *self.value.as_mut() = T::zero();
+        Ok(())
+    }
+}
+
+impl<'a, T> Named for MaxValueObserver<'a, T>
+where
+    T: PrimInt + Serialize + Debug + 'static,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
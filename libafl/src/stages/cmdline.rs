@@ -0,0 +1,201 @@
+//! The [`ArgvEnvMutationStage`] fuzzes the command line of command-based targets,
+//! for bugs in the parsing of arguments and environment variables rather than of the file content.
+
+use alloc::string::String;
+use core::marker::PhantomData;
+
+use crate::{
+    bolts::rands::Rand,
+    corpus::Corpus,
+    fuzzer::Evaluator,
+    inputs::{BytesInput, CommandLineInput, HasBytesVec},
+    mark_feature_time,
+    mutators::{MutationResult, Mutator},
+    stages::{Stage, StageDeadline},
+    start_timer,
+    state::{HasClientPerfMonitor, HasCorpus, HasRand},
+    Error,
+};
+
+#[cfg(feature = "introspection")]
+use crate::monitors::PerfFeature;
+
+/// How many iterations the [`ArgvEnvMutationStage`] gets, as an upper bound
+pub static ARGV_ENV_MUTATIONAL_MAX_ITERATIONS: u64 = 16;
+
+/// A stage mutating the arguments and environment variables of a [`CommandLineInput`], leaving its bytes alone.
+/// Each iteration, the mutator gets one argument or variable value as [`BytesInput`].
+/// The [`crate::inputs::INPUT_FILE_PLACEHOLDER`] argument is never mutated, so the target keeps getting its input.
+#[derive(Clone, Debug)]
+pub struct ArgvEnvMutationStage<E, EM, M, S, Z>
+where
+    M: Mutator<BytesInput, S>,
+    S: HasClientPerfMonitor + HasCorpus<CommandLineInput> + HasRand,
+    Z: Evaluator<E, EM, CommandLineInput, S>,
+{
+    mutator: M,
+    deadline: Option<StageDeadline>,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(E, EM, S, Z)>,
+}
+
+impl<E, EM, M, S, Z> Stage<E, EM, S, Z> for ArgvEnvMutationStage<E, EM, M, S, Z>
+where
+    M: Mutator<BytesInput, S>,
+    S: HasClientPerfMonitor + HasCorpus<CommandLineInput> + HasRand,
+    Z: Evaluator<E, EM, CommandLineInput, S>,
+{
+    #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let num = 1 + state.rand_mut().below(ARGV_ENV_MUTATIONAL_MAX_ITERATIONS) as usize;
+
+        for i in 0..num {
+            if i > 0 && self.deadline.map_or(false, |d| d.passed()) {
+                break;
+            }
+            start_timer!(state);
+            let mut input = state
+                .corpus()
+                .get(corpus_idx)?
+                .borrow_mut()
+                .load_input()?
+                .clone();
+            mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
+
+            start_timer!(state);
+            let strings = input.mutable_strings();
+            if strings.is_empty() {
+                // Nothing to mutate, without arguments besides the input
+                mark_feature_time!(state, PerfFeature::Mutate);
+                break;
+            }
+            let string = state.rand_mut().choose(strings);
+            let mut part = BytesInput::new(string.as_bytes().to_vec());
+            let result = self.mutator.mutate(state, &mut part, i as i32)?;
+            mark_feature_time!(state, PerfFeature::Mutate);
+            if result == MutationResult::Skipped {
+                continue;
+            }
+            // A process cannot get NUL bytes in its arguments or environment
+            *string = String::from_utf8_lossy(part.bytes()).replace('\0', "");
+
+            let (_, corpus_idx) = fuzzer.evaluate_input(state, executor, manager, input)?;
+
+            start_timer!(state);
+            self.mutator.post_exec(state, i as i32, corpus_idx)?;
+            mark_feature_time!(state, PerfFeature::MutatePostExec);
+        }
+
+        #[cfg(feature = "introspection")]
+        state.introspection_monitor_mut().finish_stage();
+
+        Ok(())
+    }
+
+    #[inline]
+    fn set_deadline(&mut self, deadline: Option<StageDeadline>) {
+        self.deadline = deadline;
+    }
+}
+
+impl<E, EM, M, S, Z> ArgvEnvMutationStage<E, EM, M, S, Z>
+where
+    M: Mutator<BytesInput, S>,
+    S: HasClientPerfMonitor + HasCorpus<CommandLineInput> + HasRand,
+    Z: Evaluator<E, EM, CommandLineInput, S>,
+{
+    /// Creates a new [`ArgvEnvMutationStage`].
+    /// The mutator must not rely on a corpus of [`BytesInput`]s, such as the crossover mutators do.
+    pub fn new(mutator: M) -> Self {
+        Self {
+            mutator,
+            deadline: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, RandCorpusScheduler, Testcase},
+        events::NopEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        inputs::{CommandLineInput, HasBytesVec, INPUT_FILE_PLACEHOLDER},
+        mutators::BitFlipMutator,
+        stages::{ArgvEnvMutationStage, Stage},
+        state::StdState,
+        StdFuzzer,
+    };
+
+    #[test]
+    fn test_argv_env_mutation_stage() {
+        let original = CommandLineInput::new(
+            b"content".to_vec(),
+            vec![
+                "--verbose".to_string(),
+                INPUT_FILE_PLACEHOLDER.to_string(),
+                "-o".to_string(),
+            ],
+            vec![("LANG".to_string(), "C".to_string())],
+        );
+        let mut corpus = InMemoryCorpus::<CommandLineInput>::new();
+        corpus.add(Testcase::new(original.clone())).unwrap();
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<CommandLineInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), ());
+
+        let mut executed = vec![];
+        let mut harness = |input: &CommandLineInput| {
+            executed.push(input.clone());
+            ExitKind::Ok
+        };
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        let mut stage = ArgvEnvMutationStage::new(BitFlipMutator::new());
+        for _ in 0..8 {
+            stage
+                .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, 0)
+                .unwrap();
+        }
+        drop(executor);
+
+        assert!(!executed.is_empty());
+        for input in &executed {
+            // A bit flip changes exactly one string, neither the bytes nor the placeholder
+            assert_eq!(input.bytes(), original.bytes());
+            assert_eq!(input.args().len(), 3);
+            assert_eq!(input.args()[1], INPUT_FILE_PLACEHOLDER);
+            assert_eq!(input.envs()[0].0, "LANG");
+            let changed = input
+                .args()
+                .iter()
+                .zip(original.args())
+                .filter(|(arg, orig)| arg != orig)
+                .count()
+                + usize::from(input.envs()[0].1 != original.envs()[0].1);
+            assert_eq!(changed, 1);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! The [`CminCoverageStage`] records the coverage of each entry scheduled in a corpus minimization run,
+//! for the [`crate::corpus::CminCorpusScheduler`].
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::marker::PhantomData;
+
+use crate::{
+    bolts::tuples::{MatchName, Named},
+    corpus::Corpus,
+    executors::{Executor, HasObservers},
+    feedbacks::MapIndexesMetadata,
+    inputs::Input,
+    observers::{MapObserver, ObserversTuple},
+    stages::Stage,
+    state::{HasCorpus, HasExecutions},
+    Error,
+};
+
+/// A stage executing the scheduled entry once, and storing the set entries of a map
+/// as the [`MapIndexesMetadata`] of the entry, for the [`crate::corpus::CminCorpusScheduler`] to score.
+#[derive(Clone, Debug)]
+pub struct CminCoverageStage<I, O, OT, S>
+where
+    I: Input,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasCorpus<I> + HasExecutions,
+{
+    map_observer_name: String,
+    phantom: PhantomData<(I, O, OT, S)>,
+}
+
+impl<E, EM, I, O, OT, S, Z> Stage<E, EM, S, Z> for CminCoverageStage<I, O, OT, S>
+where
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasCorpus<I> + HasExecutions,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let input = state
+            .corpus()
+            .get(corpus_idx)?
+            .borrow_mut()
+            .load_input()?
+            .clone();
+
+        executor.observers_mut().pre_exec_all(state, &input)?;
+        let exit_kind = executor.run_target(fuzzer, state, manager, &input)?;
+        *state.executions_mut() += 1;
+        executor
+            .observers_mut()
+            .post_exec_all(state, &input, &exit_kind)?;
+
+        let observer = executor
+            .observers()
+            .match_name::<O>(&self.map_observer_name)
+            .ok_or_else(|| Error::KeyNotFound("MapObserver not found".into()))?;
+        let initial = observer.initial();
+        let list: Vec<usize> = (0..observer.usable_count())
+            .filter(|&i| *observer.get(i) != initial)
+            .collect();
+        state
+            .corpus()
+            .get(corpus_idx)?
+            .borrow_mut()
+            .add_metadata(MapIndexesMetadata::new(list));
+        Ok(())
+    }
+}
+
+impl<I, O, OT, S> CminCoverageStage<I, O, OT, S>
+where
+    I: Input,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasCorpus<I> + HasExecutions,
+{
+    /// Creates a new [`CminCoverageStage`], recording the coverage of the given `map_observer`
+    #[must_use]
+    pub fn new(map_observer: &O) -> Self {
+        Self {
+            map_observer_name: map_observer.name().to_string(),
+            phantom: PhantomData,
+        }
+    }
+}
@@ -8,7 +8,10 @@ use crate::{
     corpus::Corpus,
     executors::{Executor, HasObservers},
     inputs::Input,
-    observers::{concolic::ConcolicObserver, ObserversTuple},
+    observers::{
+        concolic::{ConcolicObserver, ConstraintObserver},
+        ObserversTuple,
+    },
     state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata},
     Error,
 };
@@ -81,6 +84,72 @@ where
     }
 }
 
+/// Wraps a [`TracingStage`] to attach the constraints recorded by a [`ConstraintObserver`] to the traced testcase,
+/// as [`crate::observers::concolic::ConstraintTraceMetadata`].
+#[derive(Clone, Debug)]
+pub struct ConstraintTracingStage<EM, I, OT, S, TE, Z>
+where
+    I: Input,
+    TE: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasCorpus<I>,
+{
+    inner: TracingStage<EM, I, OT, S, TE, Z>,
+    observer_name: String,
+}
+
+impl<E, EM, I, OT, S, TE, Z> Stage<E, EM, S, Z> for ConstraintTracingStage<EM, I, OT, S, TE, Z>
+where
+    I: Input,
+    TE: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasCorpus<I>,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        self.inner
+            .perform(fuzzer, executor, state, manager, corpus_idx)?;
+        if let Some(observer) = self
+            .inner
+            .executor()
+            .observers()
+            .match_name::<ConstraintObserver>(&self.observer_name)
+        {
+            let metadata = observer.create_metadata();
+            state
+                .corpus()
+                .get(corpus_idx)?
+                .borrow_mut()
+                .metadata_mut()
+                .insert(metadata);
+        }
+        Ok(())
+    }
+}
+
+impl<EM, I, OT, S, TE, Z> ConstraintTracingStage<EM, I, OT, S, TE, Z>
+where
+    I: Input,
+    TE: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasExecutions + HasCorpus<I>,
+{
+    /// Creates a new tracing stage using the given [`Executor`], observing constraints from a [`ConstraintObserver`] with the given name.
+    pub fn new(inner: TracingStage<EM, I, OT, S, TE, Z>, observer_name: String) -> Self {
+        Self {
+            inner,
+            observer_name,
+        }
+    }
+}
+
 #[cfg(feature = "concolic_mutation")]
 use crate::{
     inputs::HasBytesVec,
@@ -402,3 +471,71 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, RandCorpusScheduler, Testcase},
+        events::NopEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        inputs::{BytesInput, HasBytesVec},
+        observers::concolic::{record_constraint, ConstraintObserver, ConstraintTraceMetadata},
+        stages::{ConstraintTracingStage, Stage, TracingStage},
+        state::{HasCorpus, HasMetadata, StdState},
+        StdFuzzer,
+    };
+
+    #[test]
+    #[serial]
+    fn test_constraint_tracing_stage() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        corpus
+            .add(Testcase::new(BytesInput::new(vec![b'a', b'b'])))
+            .unwrap();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), ());
+
+        // Compares each byte against 'b'
+        let mut harness = |input: &BytesInput| {
+            for (i, byte) in input.bytes().iter().enumerate() {
+                unsafe { record_constraint(0x1000 + i, 1, u64::from(*byte), u64::from(b'b')) };
+            }
+            ExitKind::Ok
+        };
+        let tracer = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(ConstraintObserver::new("constraints")),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+        let mut stage =
+            ConstraintTracingStage::new(TracingStage::new(tracer), "constraints".to_string());
+        stage
+            .perform(&mut fuzzer, &mut (), &mut state, &mut mgr, 0)
+            .unwrap();
+
+        let testcase = state.corpus().get(0).unwrap().borrow();
+        let metadata = testcase
+            .metadata()
+            .get::<ConstraintTraceMetadata>()
+            .unwrap();
+        assert_eq!(metadata.constraints.len(), 2);
+        assert_eq!(metadata.constraints[1].address, 0x1001);
+        assert_eq!(
+            metadata.constraints[0].operands,
+            [u64::from(b'a'), u64::from(b'b')]
+        );
+        assert_eq!(metadata.dropped, 0);
+    }
+}
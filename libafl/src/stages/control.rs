@@ -0,0 +1,74 @@
+//! The [`ControlStage`] checks a [`ControlSocket`] between executions, to pause and resume the fuzzer.
+
+use core::marker::PhantomData;
+
+use crate::{
+    bolts::os::control_socket::ControlSocket,
+    corpus::Corpus,
+    inputs::Input,
+    stages::Stage,
+    state::{HasCorpus, HasExecutions, HasSolutions},
+    Error,
+};
+
+/// A stage serving the commands of a [`ControlSocket`].
+/// Add it to the stages of the fuzzer, a `pause` command then blocks the fuzz loop until `resume`.
+/// A `status` command replies with the executions, corpus and objectives counts.
+#[derive(Debug)]
+pub struct ControlStage<I>
+where
+    I: Input,
+{
+    socket: ControlSocket,
+    phantom: PhantomData<I>,
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for ControlStage<I>
+where
+    I: Input,
+    S: HasExecutions + HasCorpus<I> + HasSolutions<I>,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        _manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        self.socket.handle_commands(|| {
+            format!(
+                "executions: {}, corpus: {}, objectives: {}",
+                state.executions(),
+                state.corpus().count(),
+                state.solutions().count()
+            )
+        })
+    }
+}
+
+impl<I> ControlStage<I>
+where
+    I: Input,
+{
+    /// Creates a new [`ControlStage`], listening on the filename (or abstract name, starting with `@`) `name`
+    pub fn new(name: &str) -> Result<Self, Error> {
+        Ok(Self::with_socket(ControlSocket::new(name)?))
+    }
+
+    /// Creates a new [`ControlStage`] serving the given [`ControlSocket`]
+    #[must_use]
+    pub fn with_socket(socket: ControlSocket) -> Self {
+        Self {
+            socket,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The served [`ControlSocket`]
+    #[must_use]
+    pub fn socket(&self) -> &ControlSocket {
+        &self.socket
+    }
+}
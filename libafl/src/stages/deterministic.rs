@@ -0,0 +1,329 @@
+//! The deterministic stage sweeps each corpus entry once with the deterministic mutations of AFL:
+//! walking bit flips, byte flips, and small arithmetic additions at each position.
+
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Corpus,
+    fuzzer::Evaluator,
+    inputs::{HasBytesVec, Input},
+    stages::Stage,
+    state::{HasCorpus, HasMetadata},
+    Error,
+};
+
+/// The default maximum delta of the arithmetic sweep, as in AFL
+pub const DEFAULT_DETERMINISTIC_MAX_ARITH: u8 = 35;
+
+/// A testcase metadata marking an entry as swept by the [`DeterministicStage`] ("det-done")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeterministicDoneMetadata {
+    /// The amount of mutated inputs in the sweep
+    pub executions: usize,
+}
+
+crate::impl_serdeany!(DeterministicDoneMetadata);
+
+/// A testcase metadata recording the step of the [`DeterministicStage`] sweep of an entry about to be executed.
+/// If the step crashes or hangs the target, the sweep resumes after it once the state gets restored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeterministicProgressMetadata {
+    /// The step of the sweep being executed
+    pub step: usize,
+}
+
+crate::impl_serdeany!(DeterministicProgressMetadata);
+
+/// A stage running the deterministic mutations of AFL on each corpus entry, the first time it gets scheduled.
+/// In order, it flips 1, 2, and 4 consecutive bits starting at each bit, flips each byte,
+/// and adds and subtracts `1..=max_arith` to each byte, executing each result once.
+/// Before each execution, the entry gets marked with a [`DeterministicProgressMetadata`],
+/// so a sweep interrupted by a crash resumes after the crashing step instead of starting over.
+/// Afterwards, the entry gets marked with a [`DeterministicDoneMetadata`], so the sweep is not repeated.
+#[derive(Clone, Debug)]
+pub struct DeterministicStage<I>
+where
+    I: Input + HasBytesVec,
+{
+    max_arith: u8,
+    skip: bool,
+    phantom: PhantomData<I>,
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for DeterministicStage<I>
+where
+    I: Input + HasBytesVec,
+    S: HasCorpus<I>,
+    Z: Evaluator<E, EM, I, S>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        if self.skip {
+            return Ok(());
+        }
+        let (original, resume) = {
+            let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
+            if entry.has_metadata::<DeterministicDoneMetadata>() {
+                return Ok(());
+            }
+            // Skip the step that was executing when the sweep got interrupted
+            let resume = entry
+                .metadata()
+                .get::<DeterministicProgressMetadata>()
+                .map_or(0, |progress| progress.step + 1);
+            (entry.load_input()?.clone(), resume)
+        };
+        let executions = self.sweep_len(original.bytes().len());
+
+        for step in resume..executions {
+            let mut input = original.clone();
+            self.mutate_step(&mut input, step);
+            state
+                .corpus()
+                .get(corpus_idx)?
+                .borrow_mut()
+                .add_metadata(DeterministicProgressMetadata { step });
+            fuzzer.evaluate_input(state, executor, manager, input)?;
+        }
+
+        let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
+        drop(
+            entry
+                .metadata_mut()
+                .remove::<DeterministicProgressMetadata>(),
+        );
+        entry.add_metadata(DeterministicDoneMetadata { executions });
+        Ok(())
+    }
+}
+
+impl<I> DeterministicStage<I>
+where
+    I: Input + HasBytesVec,
+{
+    /// Creates a new [`DeterministicStage`], with the [`DEFAULT_DETERMINISTIC_MAX_ARITH`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_max_arith(DEFAULT_DETERMINISTIC_MAX_ARITH)
+    }
+
+    /// Creates a new [`DeterministicStage`], adding and subtracting up to `max_arith` to each byte
+    #[must_use]
+    pub fn with_max_arith(max_arith: u8) -> Self {
+        Self {
+            max_arith,
+            skip: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the stage skips all entries
+    #[must_use]
+    pub fn skip(&self) -> bool {
+        self.skip
+    }
+
+    /// Skips the deterministic sweep for all entries, for campaigns relying on havoc only
+    pub fn set_skip(&mut self, skip: bool) {
+        self.skip = skip;
+    }
+
+    /// The amount of steps in the sweep of an input of `len` bytes
+    fn sweep_len(&self, len: usize) -> usize {
+        let bit_flips: usize = [1, 2, 4]
+            .iter()
+            .map(|width| (len * 8).saturating_sub(width - 1))
+            .sum();
+        bit_flips + len + len * 2 * self.max_arith as usize
+    }
+
+    /// Applies the mutation of the given `step` of the sweep to `input`
+    fn mutate_step(&self, input: &mut I, mut step: usize) {
+        let len = input.bytes().len();
+
+        // Walking bit flips
+        for width in [1, 2, 4] {
+            let flips = (len * 8).saturating_sub(width - 1);
+            if step < flips {
+                for b in step..step + width {
+                    input.bytes_mut()[b >> 3] ^= 128 >> (b & 7);
+                }
+                return;
+            }
+            step -= flips;
+        }
+
+        // Walking byte flips
+        if step < len {
+            input.bytes_mut()[step] ^= 0xff;
+            return;
+        }
+        step -= len;
+
+        // Arithmetic sweep, adding and subtracting each delta in turn
+        let per_byte = 2 * self.max_arith as usize;
+        let pos = step / per_byte;
+        let delta = (step % per_byte / 2) as u8 + 1;
+        let byte = &mut input.bytes_mut()[pos];
+        *byte = if step % 2 == 0 {
+            byte.wrapping_add(delta)
+        } else {
+            byte.wrapping_sub(delta)
+        };
+    }
+}
+
+impl<I> Default for DeterministicStage<I>
+where
+    I: Input + HasBytesVec,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, RandCorpusScheduler, Testcase},
+        events::NopEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        fuzzer::StdFuzzer,
+        inputs::{BytesInput, HasBytesVec},
+        stages::{
+            deterministic::{DeterministicDoneMetadata, DeterministicProgressMetadata},
+            DeterministicStage, Stage,
+        },
+        state::{HasCorpus, HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_deterministic_sweep() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        corpus.add(Testcase::new(vec![0_u8, 0])).unwrap();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), ());
+
+        let mut seen: Vec<Vec<u8>> = vec![];
+        let mut harness = |input: &BytesInput| {
+            seen.push(input.bytes().to_vec());
+            ExitKind::Ok
+        };
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        let mut stage = DeterministicStage::with_max_arith(1);
+        stage.set_skip(true);
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, 0)
+            .unwrap();
+        stage.set_skip(false);
+        for _ in 0..2 {
+            stage
+                .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, 0)
+                .unwrap();
+        }
+        let executions = state
+            .corpus()
+            .get(0)
+            .unwrap()
+            .borrow()
+            .metadata()
+            .get::<DeterministicDoneMetadata>()
+            .unwrap()
+            .executions;
+        drop(executor);
+
+        // 16 + 15 + 13 bit flips, 2 byte flips, and 2 * 2 additions, only in the first run
+        assert_eq!(executions, 50);
+        assert_eq!(seen.len(), 50);
+
+        // Each bit gets flipped exactly once by the single bit flips
+        let mut flipped = [0_usize; 16];
+        for input in &seen[..16] {
+            let value = u16::from_be_bytes([input[0], input[1]]);
+            assert_eq!(value.count_ones(), 1);
+            flipped[value.leading_zeros() as usize] += 1;
+        }
+        assert!(flipped.iter().all(|&count| count == 1));
+
+        assert_eq!(&seen[44..46], &[vec![0xff, 0], vec![0, 0xff]]);
+        assert_eq!(
+            &seen[46..],
+            &[vec![1, 0], vec![0xff, 0], vec![0, 1], vec![0, 0xff]]
+        );
+    }
+
+    #[test]
+    fn test_deterministic_resume() {
+        // The sweep got interrupted by a crash at step 3
+        let mut testcase = Testcase::new(vec![0_u8, 0]);
+        testcase.add_metadata(DeterministicProgressMetadata { step: 3 });
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        corpus.add(testcase).unwrap();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), ());
+
+        let mut seen: Vec<Vec<u8>> = vec![];
+        let mut harness = |input: &BytesInput| {
+            seen.push(input.bytes().to_vec());
+            ExitKind::Ok
+        };
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        DeterministicStage::with_max_arith(1)
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, 0)
+            .unwrap();
+        let entry = state.corpus().get(0).unwrap().borrow();
+        assert_eq!(
+            entry
+                .metadata()
+                .get::<DeterministicDoneMetadata>()
+                .unwrap()
+                .executions,
+            50
+        );
+        assert!(!entry.has_metadata::<DeterministicProgressMetadata>());
+        drop(entry);
+        drop(executor);
+
+        // The sweep resumes after the crashing step, flipping bit 4
+        assert_eq!(seen.len(), 46);
+        assert_eq!(seen[0], vec![0b0000_1000, 0]);
+    }
+}
@@ -0,0 +1,187 @@
+//! The corpus diversity stage periodically reports how different the corpus entries are from each other,
+//! to notice when the corpus collapsed onto near-duplicates.
+
+use alloc::{string::ToString, vec::Vec};
+use core::marker::PhantomData;
+
+use crate::{
+    bolts::rands::Rand,
+    corpus::Corpus,
+    events::{Event, EventFirer},
+    inputs::{HasBytesVec, Input},
+    monitors::UserStats,
+    stages::Stage,
+    state::{HasCorpus, HasExecutions, HasRand},
+    Error,
+};
+
+/// The default amount of corpus entries sampled for each measurement
+pub const DEFAULT_DIVERSITY_SAMPLE_SIZE: usize = 16;
+
+/// The default amount of executions between two measurements
+pub const DEFAULT_DIVERSITY_INTERVAL: usize = 100_000;
+
+/// Only the first bytes of each entry get compared, to keep the measurement cheap
+const DIVERSITY_MAX_LEN: usize = 512;
+
+/// The edit distance between `a` and `b`, divided by the length of the longer one.
+/// `0.0` means equal, `1.0` means completely different.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn normalized_edit_distance(a: &[u8], b: &[u8]) -> f64 {
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 0.0;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, &x) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, &y) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(x != y);
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        core::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()] as f64 / longest as f64
+}
+
+/// The average [`normalized_edit_distance`] over all pairs of `entries`, or `0.0` for less than two entries
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn corpus_diversity<B>(entries: &[B]) -> f64
+where
+    B: AsRef<[u8]>,
+{
+    let mut sum = 0.0;
+    let mut pairs = 0_usize;
+    for (i, a) in entries.iter().enumerate() {
+        for b in &entries[i + 1..] {
+            sum += normalized_edit_distance(a.as_ref(), b.as_ref());
+            pairs += 1;
+        }
+    }
+    if pairs == 0 {
+        0.0
+    } else {
+        sum / pairs as f64
+    }
+}
+
+/// A stage sampling `sample_size` corpus entries every `interval` executions,
+/// and reporting their [`corpus_diversity`] as the `corpus_diversity` user stat.
+#[derive(Clone, Debug)]
+pub struct CorpusDiversityStage<I, S>
+where
+    I: Input + HasBytesVec,
+    S: HasCorpus<I> + HasExecutions + HasRand,
+{
+    sample_size: usize,
+    interval: usize,
+    last_executions: usize,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for CorpusDiversityStage<I, S>
+where
+    EM: EventFirer<I>,
+    I: Input + HasBytesVec,
+    S: HasCorpus<I> + HasExecutions + HasRand,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let executions = *state.executions();
+        if executions < self.last_executions + self.interval {
+            return Ok(());
+        }
+        self.last_executions = executions;
+
+        let count = state.corpus().count();
+        if count < 2 {
+            return Ok(());
+        }
+
+        let mut samples = Vec::with_capacity(self.sample_size.min(count));
+        for i in 0..self.sample_size.min(count) {
+            let idx = if count <= self.sample_size {
+                i
+            } else {
+                state.rand_mut().below(count as u64) as usize
+            };
+            let mut testcase = state.corpus().get(idx)?.borrow_mut();
+            let bytes = testcase.load_input()?.bytes();
+            samples.push(bytes[..bytes.len().min(DIVERSITY_MAX_LEN)].to_vec());
+        }
+
+        manager.fire(
+            state,
+            Event::UpdateUserStats {
+                name: "corpus_diversity".to_string(),
+                value: UserStats::Float(corpus_diversity(&samples)),
+                phantom: PhantomData,
+            },
+        )
+    }
+}
+
+impl<I, S> CorpusDiversityStage<I, S>
+where
+    I: Input + HasBytesVec,
+    S: HasCorpus<I> + HasExecutions + HasRand,
+{
+    /// Creates a new [`CorpusDiversityStage`], with the [`DEFAULT_DIVERSITY_SAMPLE_SIZE`] and [`DEFAULT_DIVERSITY_INTERVAL`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_sample_size(DEFAULT_DIVERSITY_SAMPLE_SIZE, DEFAULT_DIVERSITY_INTERVAL)
+    }
+
+    /// Creates a new [`CorpusDiversityStage`], sampling `sample_size` entries every `interval` executions
+    #[must_use]
+    pub fn with_sample_size(sample_size: usize, interval: usize) -> Self {
+        Self {
+            sample_size,
+            interval,
+            last_executions: 0,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I, S> Default for CorpusDiversityStage<I, S>
+where
+    I: Input + HasBytesVec,
+    S: HasCorpus<I> + HasExecutions + HasRand,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stages::diversity::{corpus_diversity, normalized_edit_distance};
+
+    #[test]
+    fn test_corpus_diversity() {
+        assert!((normalized_edit_distance(b"kitten", b"sitting") - 3.0 / 7.0).abs() < f64::EPSILON);
+        assert!(normalized_edit_distance(b"", b"") < f64::EPSILON);
+
+        // Near-duplicates are barely diverse
+        let duplicates: [&[u8]; 3] = [b"AAAAAAAAAA", b"AAAAAAAAAB", b"AAAAAAAAAA"];
+        assert!((corpus_diversity(&duplicates) - 0.2 / 3.0).abs() < f64::EPSILON);
+
+        // Entirely different entries are fully diverse
+        let distinct: [&[u8]; 3] = [b"AAAA", b"BBBB", b"CCCC"];
+        assert!((corpus_diversity(&distinct) - 1.0).abs() < f64::EPSILON);
+
+        assert!(corpus_diversity(&[b"single"]) < f64::EPSILON);
+    }
+}
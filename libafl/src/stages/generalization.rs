@@ -13,7 +13,7 @@ use crate::{
     corpus::Corpus,
     executors::{Executor, HasObservers},
     feedbacks::map::MapNoveltiesMetadata,
-    inputs::{GeneralizedInput, GeneralizedItem, HasBytesVec},
+    inputs::{GeneralizedInput, GeneralizedInputMetadata, GeneralizedItem, HasBytesVec},
     mark_feature_time,
     observers::{MapObserver, ObserversTuple},
     stages::Stage,
@@ -101,6 +101,18 @@ where
             state.corpus().get(corpus_idx)?.borrow_mut().load_input()?;
             mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
             let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
+
+            // Restore the generalization learned before a restart, if any
+            if entry.input().as_ref().unwrap().generalized().is_none() {
+                if let Some(meta) = entry.metadata().get::<GeneralizedInputMetadata>().cloned() {
+                    entry
+                        .input_mut()
+                        .as_mut()
+                        .unwrap()
+                        .generalized_from_metadata(&meta);
+                }
+            }
+
             let input = entry.input_mut().as_mut().unwrap();
 
             if input.generalized().is_some() {
@@ -318,11 +330,10 @@ where
             {
                 let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
                 entry.load_input()?;
-                entry
-                    .input_mut()
-                    .as_mut()
-                    .unwrap()
-                    .generalized_from_options(&payload);
+                let input = entry.input_mut().as_mut().unwrap();
+                input.generalized_from_options(&payload);
+                let meta = input.generalized_metadata().unwrap();
+                entry.add_metadata(meta);
                 entry.store_input()?;
 
                 debug_assert!(
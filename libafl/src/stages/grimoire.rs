@@ -0,0 +1,207 @@
+//! The Grimoire recursive replacement stage recombines generalized corpus entries,
+//! replacing the content at a gap with a chunk of another generalized entry.
+
+use core::marker::PhantomData;
+
+use crate::{
+    bolts::rands::Rand,
+    corpus::Corpus,
+    fuzzer::Evaluator,
+    inputs::GeneralizedInput,
+    mutators::GrimoireRecursiveReplacementMutator,
+    stages::{
+        generalization::GeneralizedIndexesMetadata, mutational::DEFAULT_MUTATIONAL_MAX_ITERATIONS,
+        MutationalStage, Stage,
+    },
+    state::{HasClientPerfMonitor, HasCorpus, HasMetadata, HasRand},
+    Error,
+};
+
+/// A stage applying the [`GrimoireRecursiveReplacementMutator`] to generalized inputs.
+/// Entries without a generalization (see [`crate::stages::GeneralizationStage`]) are skipped,
+/// as well as all entries while no other generalized entry is there to recombine with.
+#[derive(Clone, Debug)]
+pub struct GrimoireRecursiveReplacementStage<E, EM, S, Z>
+where
+    S: HasClientPerfMonitor + HasCorpus<GeneralizedInput> + HasMetadata + HasRand,
+    Z: Evaluator<E, EM, GeneralizedInput, S>,
+{
+    mutator: GrimoireRecursiveReplacementMutator,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(E, EM, S, Z)>,
+}
+
+impl<E, EM, S, Z>
+    MutationalStage<E, EM, GeneralizedInput, GrimoireRecursiveReplacementMutator, S, Z>
+    for GrimoireRecursiveReplacementStage<E, EM, S, Z>
+where
+    S: HasClientPerfMonitor + HasCorpus<GeneralizedInput> + HasMetadata + HasRand,
+    Z: Evaluator<E, EM, GeneralizedInput, S>,
+{
+    /// The mutator, added to this stage
+    #[inline]
+    fn mutator(&self) -> &GrimoireRecursiveReplacementMutator {
+        &self.mutator
+    }
+
+    /// The mutator, added to this stage (as mutable ref)
+    #[inline]
+    fn mutator_mut(&mut self) -> &mut GrimoireRecursiveReplacementMutator {
+        &mut self.mutator
+    }
+
+    /// Gets the number of iterations as a random number, or 0 if the entry cannot be recombined
+    fn iterations(&self, state: &mut S, corpus_idx: usize) -> Result<usize, Error> {
+        let has_others = state
+            .metadata()
+            .get::<GeneralizedIndexesMetadata>()
+            .map_or(false, |meta| !meta.indexes.is_empty());
+        if !has_others
+            || state
+                .corpus()
+                .get(corpus_idx)?
+                .borrow_mut()
+                .load_input()?
+                .generalized()
+                .is_none()
+        {
+            return Ok(0);
+        }
+        Ok(1 + state.rand_mut().below(DEFAULT_MUTATIONAL_MAX_ITERATIONS) as usize)
+    }
+}
+
+impl<E, EM, S, Z> Stage<E, EM, S, Z> for GrimoireRecursiveReplacementStage<E, EM, S, Z>
+where
+    S: HasClientPerfMonitor + HasCorpus<GeneralizedInput> + HasMetadata + HasRand,
+    Z: Evaluator<E, EM, GeneralizedInput, S>,
+{
+    #[inline]
+    #[allow(clippy::let_and_return)]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let ret = self.perform_mutational(fuzzer, executor, state, manager, corpus_idx);
+
+        #[cfg(feature = "introspection")]
+        state.introspection_monitor_mut().finish_stage();
+
+        ret
+    }
+}
+
+impl<E, EM, S, Z> GrimoireRecursiveReplacementStage<E, EM, S, Z>
+where
+    S: HasClientPerfMonitor + HasCorpus<GeneralizedInput> + HasMetadata + HasRand,
+    Z: Evaluator<E, EM, GeneralizedInput, S>,
+{
+    /// Creates a new [`GrimoireRecursiveReplacementStage`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            mutator: GrimoireRecursiveReplacementMutator::new(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<E, EM, S, Z> Default for GrimoireRecursiveReplacementStage<E, EM, S, Z>
+where
+    S: HasClientPerfMonitor + HasCorpus<GeneralizedInput> + HasMetadata + HasRand,
+    Z: Evaluator<E, EM, GeneralizedInput, S>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list, AsSlice},
+        corpus::{Corpus, InMemoryCorpus, RandCorpusScheduler, Testcase},
+        events::SimpleEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        inputs::{GeneralizedInput, HasTargetBytes},
+        monitors::SimpleMonitor,
+        stages::{generalization::GeneralizedIndexesMetadata, Stage},
+        state::{HasCorpus, HasMetadata, StdState},
+        StdFuzzer,
+    };
+
+    use super::GrimoireRecursiveReplacementStage;
+
+    #[test]
+    fn test_grimoire_recursive_replacement_stage() {
+        let mut corpus = InMemoryCorpus::<GeneralizedInput>::new();
+        corpus
+            .add(Testcase::new(GeneralizedInput::new(b"abc".to_vec())))
+            .unwrap();
+        corpus
+            .add(Testcase::new(GeneralizedInput::new(b"XYZ".to_vec())))
+            .unwrap();
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<GeneralizedInput>::new(),
+            (),
+        );
+        for (idx, bytes) in [b"abc", b"XYZ"].iter().enumerate() {
+            let options = bytes.iter().map(|b| Some(*b)).collect::<Vec<_>>();
+            state
+                .corpus()
+                .get(idx)
+                .unwrap()
+                .borrow_mut()
+                .input_mut()
+                .as_mut()
+                .unwrap()
+                .generalized_from_options(&options);
+        }
+        let mut meta = GeneralizedIndexesMetadata::new();
+        meta.indexes.insert(1);
+        state.add_metadata(meta);
+
+        let monitor = SimpleMonitor::new(|s| println!("{}", s));
+        let mut event_manager = SimpleEventManager::new(monitor);
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), ());
+
+        let mut executed = vec![];
+        let mut harness = |input: &GeneralizedInput| {
+            executed.push(input.target_bytes().as_slice().to_vec());
+            ExitKind::Ok
+        };
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut event_manager,
+        )
+        .unwrap();
+
+        let mut stage = GrimoireRecursiveReplacementStage::new();
+        stage
+            .perform(
+                &mut fuzzer,
+                &mut executor,
+                &mut state,
+                &mut event_manager,
+                0,
+            )
+            .unwrap();
+        drop(executor);
+
+        assert!(executed
+            .iter()
+            .any(|bytes| bytes.windows(3).any(|w| w == b"abc")
+                && bytes.windows(3).any(|w| w == b"XYZ")));
+    }
+}
@@ -0,0 +1,478 @@
+//! The [`HttpCorpusSync`] stage shares corpus entries through a central HTTP endpoint,
+//! for clusters where the clients cannot reach each other's LLMP brokers.
+//!
+//! The protocol is plain `HTTP/1.1`, without TLS:
+//! - `POST <path>` with a single `postcard`-serialized input as body, for each new corpus entry.
+//! - `GET <path>?since=<cursor>`, answered with a `postcard`-serialized [`HttpSyncBatch`],
+//!   holding all entries the endpoint received after `cursor`, and the cursor for the next pull.
+
+use ahash::AHasher;
+use core::{hash::Hasher, marker::PhantomData, time::Duration};
+use hashbrown::HashSet;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Instant,
+};
+
+use crate::{
+    bolts::current_time, corpus::Corpus, fuzzer::Evaluator, inputs::Input, libafl_log,
+    stages::Stage, state::HasCorpus, Error,
+};
+
+/// The default amount of retries of a failed request
+pub const DEFAULT_HTTP_SYNC_RETRIES: usize = 3;
+
+/// The timeout for a whole request to the endpoint, from connecting to reading the last byte of the response
+const HTTP_SYNC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The longest delay between two retries of a failed request
+const HTTP_SYNC_MAX_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// The answer of the endpoint to a pull
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct HttpSyncBatch {
+    /// The cursor to pass with the next pull
+    pub next: u64,
+    /// The serialized inputs received by the endpoint since the last pull
+    pub entries: Vec<Vec<u8>>,
+}
+
+/// A stage pushing new corpus entries to a central HTTP endpoint, and evaluating the entries other clients pushed,
+/// every `interval`. Entries are deduplicated by the hash of their content, and entries failing to deserialize are skipped.
+/// The requests run on a background thread, so a slow endpoint does not stall the fuzzer:
+/// the entries pulled by a sync get evaluated by a later run of the stage.
+/// Each request times out after a few seconds, and failed requests are retried, with a growing delay.
+/// If all retries fail, the sync is attempted again later.
+#[derive(Debug)]
+pub struct HttpCorpusSync<I>
+where
+    I: Input,
+{
+    interval: Duration,
+    last_sync: Option<Duration>,
+    /// The first corpus index not pushed yet
+    next_push: usize,
+    /// If the background thread still works on the last sync
+    in_flight: bool,
+    /// The serialized entries to push, sent to the background thread
+    jobs: Sender<Vec<Vec<u8>>>,
+    /// The entries pulled by the background thread
+    pulled: Receiver<Vec<Vec<u8>>>,
+    phantom: PhantomData<I>,
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for HttpCorpusSync<I>
+where
+    I: Input,
+    S: HasCorpus<I>,
+    Z: Evaluator<E, EM, I, S>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        if let Ok(entries) = self.pulled.try_recv() {
+            self.in_flight = false;
+            for entry in entries {
+                // Another client may run a different input type, or the endpoint may be broken
+                match postcard::from_bytes::<I>(&entry) {
+                    Ok(input) => {
+                        fuzzer.evaluate_input(state, executor, manager, input)?;
+                    }
+                    Err(err) => {
+                        libafl_log!(
+                            Warn,
+                            "Skipping a malformed entry from the corpus endpoint: {}",
+                            err
+                        );
+                    }
+                }
+            }
+        }
+
+        let now = current_time();
+        if let Some(last_sync) = self.last_sync {
+            if now < last_sync + self.interval {
+                return Ok(());
+            }
+        }
+        if self.in_flight {
+            return Ok(());
+        }
+        self.last_sync = Some(now);
+
+        // Entries added by the evaluation are known to the background thread, it skips them
+        let count = state.corpus().count();
+        let mut entries = Vec::with_capacity(count.saturating_sub(self.next_push));
+        while self.next_push < count {
+            let mut testcase = state.corpus().get(self.next_push)?.borrow_mut();
+            entries.push(postcard::to_allocvec(testcase.load_input()?)?);
+            self.next_push += 1;
+        }
+        self.jobs.send(entries).map_err(|_| {
+            Error::IllegalState("The corpus sync thread stopped unexpectedly".to_string())
+        })?;
+        self.in_flight = true;
+        Ok(())
+    }
+}
+
+impl<I> HttpCorpusSync<I>
+where
+    I: Input,
+{
+    /// Creates a new [`HttpCorpusSync`] for an `endpoint` such as `http://sync.local:8080/corpus`,
+    /// syncing every `interval`, with [`DEFAULT_HTTP_SYNC_RETRIES`]
+    pub fn new(endpoint: &str, interval: Duration) -> Result<Self, Error> {
+        Self::with_retries(endpoint, interval, DEFAULT_HTTP_SYNC_RETRIES)
+    }
+
+    /// Creates a new [`HttpCorpusSync`], retrying each failed request up to `retries` times.
+    /// This starts the background thread, which stops once the stage gets dropped.
+    pub fn with_retries(endpoint: &str, interval: Duration, retries: usize) -> Result<Self, Error> {
+        let rest = endpoint.strip_prefix("http://").ok_or_else(|| {
+            Error::IllegalArgument(format!(
+                "Unsupported corpus endpoint {}, expected http://host:port/path",
+                endpoint
+            ))
+        })?;
+        let (host, path) = match rest.find('/') {
+            Some(idx) => (&rest[..idx], &rest[idx..]),
+            None => (rest, "/"),
+        };
+        let host = if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{}:80", host)
+        };
+        let mut worker = HttpSyncWorker {
+            host,
+            path: path.to_string(),
+            retries,
+            cursor: 0,
+            known: HashSet::new(),
+        };
+
+        let (jobs, job_receiver) = mpsc::channel();
+        let (pulled_sender, pulled) = mpsc::channel();
+        thread::Builder::new()
+            .name("http_corpus_sync".into())
+            .spawn(move || {
+                // Stops once the stage, and with it the sender, got dropped
+                while let Ok(entries) = job_receiver.recv() {
+                    if pulled_sender.send(worker.sync(&entries)).is_err() {
+                        break;
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            interval,
+            last_sync: None,
+            next_push: 0,
+            in_flight: false,
+            jobs,
+            pulled,
+            phantom: PhantomData,
+        })
+    }
+}
+
+/// The background thread of a [`HttpCorpusSync`], talking to the endpoint
+#[derive(Debug)]
+struct HttpSyncWorker {
+    host: String,
+    path: String,
+    retries: usize,
+    /// The cursor of the next pull
+    cursor: u64,
+    /// The hashes of all entries pushed or pulled so far
+    known: HashSet<u64>,
+}
+
+impl HttpSyncWorker {
+    /// Pushes the `entries`, and returns the pulled ones. Failed requests get logged.
+    fn sync(&mut self, entries: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        if let Err(err) = self.push(entries) {
+            libafl_log!(Warn, "Pushing to the corpus endpoint failed: {}", err);
+        }
+        match self.pull() {
+            Ok(entries) => entries,
+            Err(err) => {
+                libafl_log!(Warn, "Pulling from the corpus endpoint failed: {}", err);
+                vec![]
+            }
+        }
+    }
+
+    /// Pushes the `entries`, skipping the ones known to the endpoint
+    fn push(&mut self, entries: &[Vec<u8>]) -> Result<(), Error> {
+        for entry in entries {
+            let hash = content_hash(entry);
+            if !self.known.contains(&hash) {
+                self.request("POST", &self.path, entry)?;
+                self.known.insert(hash);
+            }
+        }
+        Ok(())
+    }
+
+    /// Pulls the entries pushed since the last pull, skipping the known ones
+    fn pull(&mut self) -> Result<Vec<Vec<u8>>, Error> {
+        let path = format!("{}?since={}", self.path, self.cursor);
+        let body = self.request("GET", &path, &[])?;
+        let batch: HttpSyncBatch = postcard::from_bytes(&body)?;
+        self.cursor = batch.next;
+        Ok(batch
+            .entries
+            .into_iter()
+            .filter(|entry| self.known.insert(content_hash(entry)))
+            .collect())
+    }
+
+    /// Sends a request, retrying with a growing delay, and returns the body of the response
+    fn request(&self, method: &str, path: &str, body: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut attempt = 0;
+        loop {
+            match http_request(&self.host, method, path, body) {
+                Ok(response) => return Ok(response),
+                Err(err) if attempt >= self.retries => return Err(err),
+                Err(_) => {
+                    attempt += 1;
+                    thread::sleep(
+                        Duration::from_millis(100 * attempt as u64).min(HTTP_SYNC_MAX_RETRY_DELAY),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Connects to `host`, trying each of its addresses for at most [`HTTP_SYNC_TIMEOUT`]
+fn connect(host: &str) -> Result<TcpStream, Error> {
+    let mut last_err = None;
+    for addr in host.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, HTTP_SYNC_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err.map_or_else(
+        || Error::IllegalArgument(format!("Could not resolve the corpus endpoint {}", host)),
+        Error::from,
+    ))
+}
+
+/// The hash used to deduplicate serialized entries
+fn content_hash(bytes: &[u8]) -> u64 {
+    let mut hasher = AHasher::new_with_keys(0, 0);
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// Sends a single `HTTP/1.1` request to `host`, and returns the body of a successful (`2xx`) response.
+/// Fails if the whole request takes longer than [`HTTP_SYNC_TIMEOUT`].
+fn http_request(host: &str, method: &str, path: &str, body: &[u8]) -> Result<Vec<u8>, Error> {
+    let deadline = Instant::now() + HTTP_SYNC_TIMEOUT;
+    let mut stream = connect(host)?;
+    stream.set_write_timeout(Some(HTTP_SYNC_TIMEOUT))?;
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        method,
+        path,
+        host,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()?;
+
+    // A slow endpoint may trickle in the response, so bound the time of all reads together
+    let mut response = vec![];
+    let mut buf = [0; 4096];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(Error::IllegalState(format!(
+                "{} {} timed out after {:?}",
+                method, path, HTTP_SYNC_TIMEOUT
+            )));
+        }
+        stream.set_read_timeout(Some(remaining))?;
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => response.extend_from_slice(&buf[..len]),
+            Err(err) if err.kind() == ErrorKind::Interrupted => (),
+            Err(err) => return Err(err.into()),
+        }
+    }
+    let header_end = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| Error::IllegalState("Malformed HTTP response".to_string()))?;
+    let header = String::from_utf8_lossy(&response[..header_end]);
+    let status = header
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse::<u16>().ok())
+        .ok_or_else(|| Error::IllegalState("Malformed HTTP status line".to_string()))?;
+    if !(200..300).contains(&status) {
+        return Err(Error::IllegalState(format!(
+            "{} {} failed with HTTP status {}",
+            method, path, status
+        )));
+    }
+
+    let mut body = response.split_off(header_end + 4);
+    let content_length = header.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim().eq_ignore_ascii_case("content-length") {
+            value.trim().parse::<usize>().ok()
+        } else {
+            None
+        }
+    });
+    if let Some(content_length) = content_length {
+        body.truncate(content_length);
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread,
+        time::Instant,
+    };
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, RandCorpusScheduler, Testcase},
+        events::NopEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        inputs::{BytesInput, HasBytesVec},
+        stages::{http_sync::HttpSyncBatch, HttpCorpusSync, Stage},
+        state::StdState,
+        StdFuzzer,
+    };
+
+    /// Answers `connections` requests: the first one fails, then stores pushes and answers pulls
+    fn mock_server(listener: &TcpListener, connections: usize, store: &mut Vec<Vec<u8>>) {
+        for i in 0..connections {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(value) = line.strip_prefix("Content-Length:") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let response = if i == 0 {
+                b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n".to_vec()
+            } else if request_line.starts_with("POST /corpus ") {
+                store.push(body);
+                b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".to_vec()
+            } else {
+                assert!(request_line.starts_with("GET /corpus?since=0 "));
+                let batch = postcard::to_allocvec(&HttpSyncBatch {
+                    next: store.len() as u64,
+                    entries: store.clone(),
+                })
+                .unwrap();
+                let mut response =
+                    format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", batch.len())
+                        .into_bytes();
+                response.extend_from_slice(&batch);
+                response
+            };
+            reader.get_mut().write_all(&response).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_http_corpus_sync() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/corpus", listener.local_addr().unwrap());
+
+        // Another client already pushed an entry, and something pushed garbage
+        let remote = postcard::to_allocvec(&BytesInput::new(b"remote".to_vec())).unwrap();
+        let garbage = vec![0xff; 3];
+        let server = thread::spawn(move || {
+            let mut store = vec![remote, garbage];
+            // The failing attempt, the retried push, and the pull
+            mock_server(&listener, 3, &mut store);
+            store
+        });
+
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        corpus
+            .add(Testcase::new(BytesInput::new(b"local".to_vec())))
+            .unwrap();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), ());
+
+        let mut executed = vec![];
+        let mut harness = |input: &BytesInput| {
+            executed.push(input.bytes().to_vec());
+            ExitKind::Ok
+        };
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        let mut sync = HttpCorpusSync::new(&endpoint, Duration::from_secs(60)).unwrap();
+        sync.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, 0)
+            .unwrap();
+        // The sync runs in the background, a later run evaluates the pulled entries.
+        // Within the interval, nothing gets sent.
+        let start = Instant::now();
+        while sync.in_flight {
+            assert!(start.elapsed() < Duration::from_secs(30));
+            thread::sleep(Duration::from_millis(10));
+            sync.perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, 0)
+                .unwrap();
+        }
+        drop(executor);
+
+        let store = server.join().unwrap();
+        assert_eq!(store.len(), 3);
+        assert_eq!(
+            postcard::from_bytes::<BytesInput>(&store[2]).unwrap(),
+            BytesInput::new(b"local".to_vec())
+        );
+        // The remote entry got evaluated, the garbage got skipped, and our own entry did not come back
+        assert_eq!(executed, vec![b"remote".to_vec()]);
+    }
+}
@@ -0,0 +1,366 @@
+//! Adapts the mutation intensity of a campaign to its find rate.
+//! The [`IntensityControlStage`] watches the executions since the last find and, following an [`IntensityPolicy`],
+//! sets the amount of iterations the following [`AdaptiveMutationalStage`]s run for each entry.
+
+use core::{fmt::Debug, marker::PhantomData};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::rands::Rand,
+    corpus::Corpus,
+    fuzzer::Evaluator,
+    inputs::Input,
+    mutators::Mutator,
+    stages::{
+        mutational::DEFAULT_MUTATIONAL_MAX_ITERATIONS, MutationalStage, Stage, StallDetector,
+    },
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata, HasRand},
+    Error,
+};
+
+/// The default amount of executions without a find after which the [`StallIntensityPolicy`] increases the intensity
+pub const DEFAULT_STALL_EXECS: usize = 100_000;
+
+/// The default upper bound for the max iterations set by the [`StallIntensityPolicy`]
+pub const DEFAULT_MAX_INTENSITY: u64 = 1024;
+
+/// The current mutation intensity, set by the [`IntensityControlStage`]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct MutationIntensityMetadata {
+    /// The upper bound of the iterations an [`AdaptiveMutationalStage`] runs for each entry
+    pub max_iterations: u64,
+}
+
+crate::impl_serdeany!(MutationIntensityMetadata);
+
+/// Decides how the mutation intensity changes with the find rate of the campaign
+pub trait IntensityPolicy: Debug {
+    /// Returns the new max iterations, given the current ones and the executions since the last find
+    /// (or since the last change of the intensity, whichever came later)
+    fn adjust(&mut self, execs_since_last_find: usize, max_iterations: u64) -> u64;
+}
+
+/// The default [`IntensityPolicy`]: as long as the campaign finds new entries, the intensity stays the same.
+/// After `stall_execs` executions without a find, the max iterations double, up to `max_iterations`.
+#[derive(Clone, Copy, Debug)]
+pub struct StallIntensityPolicy {
+    stall_execs: usize,
+    max_iterations: u64,
+}
+
+impl IntensityPolicy for StallIntensityPolicy {
+    fn adjust(&mut self, execs_since_last_find: usize, max_iterations: u64) -> u64 {
+        if execs_since_last_find >= self.stall_execs {
+            max_iterations.saturating_mul(2).min(self.max_iterations)
+        } else {
+            max_iterations
+        }
+    }
+}
+
+impl StallIntensityPolicy {
+    /// Creates a new [`StallIntensityPolicy`], doubling the intensity every `stall_execs` executions without a find
+    #[must_use]
+    pub fn new(stall_execs: usize, max_iterations: u64) -> Self {
+        Self {
+            stall_execs: stall_execs.max(1),
+            max_iterations,
+        }
+    }
+}
+
+impl Default for StallIntensityPolicy {
+    fn default() -> Self {
+        Self::new(DEFAULT_STALL_EXECS, DEFAULT_MAX_INTENSITY)
+    }
+}
+
+/// Tracks the finds of a campaign and applies an [`IntensityPolicy`] to them
+#[derive(Clone, Debug)]
+pub struct IntensityController<P>
+where
+    P: IntensityPolicy,
+{
+    policy: P,
+    max_iterations: u64,
+    stall: StallDetector,
+}
+
+impl<P> IntensityController<P>
+where
+    P: IntensityPolicy,
+{
+    /// Creates a new [`IntensityController`], starting at the given max iterations
+    #[must_use]
+    pub fn new(policy: P, max_iterations: u64) -> Self {
+        Self {
+            policy,
+            max_iterations: max_iterations.max(1),
+            stall: StallDetector::new(),
+        }
+    }
+
+    /// The current max iterations
+    #[must_use]
+    pub fn max_iterations(&self) -> u64 {
+        self.max_iterations
+    }
+
+    /// The policy of this controller
+    pub fn policy_mut(&mut self) -> &mut P {
+        &mut self.policy
+    }
+
+    /// Feeds the current executions and corpus size to the controller, returning the new max iterations.
+    /// A grown corpus counts as a find.
+    pub fn update(&mut self, executions: usize, corpus_count: usize) -> u64 {
+        let execs_since_last_find = self.stall.update(executions, corpus_count);
+        let max_iterations = self
+            .policy
+            .adjust(execs_since_last_find, self.max_iterations)
+            .max(1);
+        if max_iterations != self.max_iterations {
+            self.max_iterations = max_iterations;
+            self.stall.reset(executions, corpus_count);
+        }
+        max_iterations
+    }
+}
+
+/// A stage updating the [`MutationIntensityMetadata`] from the find rate of the campaign.
+/// It should come before the [`AdaptiveMutationalStage`]s in the stages tuple.
+#[derive(Clone, Debug)]
+pub struct IntensityControlStage<I, P>
+where
+    I: Input,
+    P: IntensityPolicy,
+{
+    controller: IntensityController<P>,
+    phantom: PhantomData<I>,
+}
+
+impl<E, EM, I, P, S, Z> Stage<E, EM, S, Z> for IntensityControlStage<I, P>
+where
+    I: Input,
+    P: IntensityPolicy,
+    S: HasCorpus<I> + HasExecutions + HasMetadata,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        _manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let max_iterations = self
+            .controller
+            .update(*state.executions(), state.corpus().count());
+        match state.metadata_mut().get_mut::<MutationIntensityMetadata>() {
+            Some(meta) => meta.max_iterations = max_iterations,
+            None => state.add_metadata(MutationIntensityMetadata { max_iterations }),
+        }
+        Ok(())
+    }
+}
+
+impl<I> IntensityControlStage<I, StallIntensityPolicy>
+where
+    I: Input,
+{
+    /// Creates a new [`IntensityControlStage`] with the default [`StallIntensityPolicy`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_policy(StallIntensityPolicy::default())
+    }
+}
+
+impl<I> Default for IntensityControlStage<I, StallIntensityPolicy>
+where
+    I: Input,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, P> IntensityControlStage<I, P>
+where
+    I: Input,
+    P: IntensityPolicy,
+{
+    /// Creates a new [`IntensityControlStage`] with the given policy, starting at the default intensity
+    #[must_use]
+    pub fn with_policy(policy: P) -> Self {
+        Self {
+            controller: IntensityController::new(policy, DEFAULT_MUTATIONAL_MAX_ITERATIONS),
+            phantom: PhantomData,
+        }
+    }
+
+    /// The controller of this stage
+    #[must_use]
+    pub fn controller(&self) -> &IntensityController<P> {
+        &self.controller
+    }
+}
+
+/// A mutational stage like the [`crate::stages::StdMutationalStage`],
+/// taking the max iterations from the [`MutationIntensityMetadata`], if present.
+#[derive(Clone, Debug)]
+pub struct AdaptiveMutationalStage<E, EM, I, M, S, Z>
+where
+    M: Mutator<I, S>,
+    I: Input,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasMetadata + HasRand,
+    Z: Evaluator<E, EM, I, S>,
+{
+    mutator: M,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(E, EM, I, S, Z)>,
+}
+
+impl<E, EM, I, M, S, Z> MutationalStage<E, EM, I, M, S, Z>
+    for AdaptiveMutationalStage<E, EM, I, M, S, Z>
+where
+    M: Mutator<I, S>,
+    I: Input,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasMetadata + HasRand,
+    Z: Evaluator<E, EM, I, S>,
+{
+    /// The mutator, added to this stage
+    #[inline]
+    fn mutator(&self) -> &M {
+        &self.mutator
+    }
+
+    /// The list of mutators, added to this stage (as mutable ref)
+    #[inline]
+    fn mutator_mut(&mut self) -> &mut M {
+        &mut self.mutator
+    }
+
+    /// Gets the number of iterations as a random number, bounded by the current intensity
+    fn iterations(&self, state: &mut S, _corpus_idx: usize) -> Result<usize, Error> {
+        let max_iterations = state
+            .metadata()
+            .get::<MutationIntensityMetadata>()
+            .map_or(DEFAULT_MUTATIONAL_MAX_ITERATIONS, |meta| {
+                meta.max_iterations
+            });
+        Ok(1 + state.rand_mut().below(max_iterations) as usize)
+    }
+}
+
+impl<E, EM, I, M, S, Z> Stage<E, EM, S, Z> for AdaptiveMutationalStage<E, EM, I, M, S, Z>
+where
+    M: Mutator<I, S>,
+    I: Input,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasMetadata + HasRand,
+    Z: Evaluator<E, EM, I, S>,
+{
+    #[inline]
+    #[allow(clippy::let_and_return)]
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let ret = self.perform_mutational(fuzzer, executor, state, manager, corpus_idx);
+
+        #[cfg(feature = "introspection")]
+        state.introspection_monitor_mut().finish_stage();
+
+        ret
+    }
+}
+
+impl<E, EM, I, M, S, Z> AdaptiveMutationalStage<E, EM, I, M, S, Z>
+where
+    M: Mutator<I, S>,
+    I: Input,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasMetadata + HasRand,
+    Z: Evaluator<E, EM, I, S>,
+{
+    /// Creates a new [`AdaptiveMutationalStage`]
+    pub fn new(mutator: M) -> Self {
+        Self {
+            mutator,
+            phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        inputs::BytesInput,
+        stages::{
+            intensity::{
+                IntensityControlStage, IntensityController, MutationIntensityMetadata,
+                StallIntensityPolicy,
+            },
+            Stage,
+        },
+        state::{HasCorpus, HasExecutions, HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_intensity_controller() {
+        let mut controller = IntensityController::new(StallIntensityPolicy::new(1000, 64), 8);
+
+        // Regular finds keep the intensity
+        for i in 1..10 {
+            assert_eq!(controller.update(i * 500, i), 8);
+        }
+        // A stall doubles it, once every `stall_execs`
+        assert_eq!(controller.update(5400, 9), 8);
+        assert_eq!(controller.update(5500, 9), 16);
+        assert_eq!(controller.update(6000, 9), 16);
+        assert_eq!(controller.update(6500, 9), 32);
+        assert_eq!(controller.update(7500, 9), 64);
+        // Up to the max
+        assert_eq!(controller.update(100_000, 9), 64);
+        // A find keeps the reached intensity
+        assert_eq!(controller.update(100_500, 10), 64);
+    }
+
+    #[test]
+    fn test_intensity_control_stage() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut stage = IntensityControlStage::with_policy(StallIntensityPolicy::new(10, 256));
+
+        state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(vec![0])))
+            .unwrap();
+        stage
+            .perform(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+        let max_iterations = |state: &StdState<_, _, _, _, _>| {
+            state
+                .metadata()
+                .get::<MutationIntensityMetadata>()
+                .unwrap()
+                .max_iterations
+        };
+        assert_eq!(max_iterations(&state), 128);
+
+        *state.executions_mut() += 10;
+        stage
+            .perform(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+        assert_eq!(max_iterations(&state), 256);
+        assert_eq!(stage.controller().max_iterations(), 256);
+    }
+}
@@ -17,26 +17,79 @@ pub mod calibrate;
 pub use calibrate::{CalibrationStage, PowerScheduleMetadata};
 
 pub mod power;
-pub use power::PowerMutationalStage;
+pub use power::{PowerMutationalStage, DEFAULT_ENERGY_DECAY};
 
 pub mod generalization;
 pub use generalization::GeneralizationStage;
 
+pub mod stall;
+pub use stall::StallDetector;
+
+pub mod intensity;
+pub use intensity::{AdaptiveMutationalStage, IntensityControlStage, StallIntensityPolicy};
+
+pub mod phase;
+pub use phase::{FuzzPhase, PhaseControlStage, PhaseCorpusScheduler, PhaseStage};
+
+pub mod grimoire;
+pub use grimoire::GrimoireRecursiveReplacementStage;
+
+pub mod tokens;
+pub use tokens::{AutoTokensScannedMetadata, AutoTokensStage};
+
+pub mod diversity;
+pub use diversity::CorpusDiversityStage;
+
+pub mod trim;
+pub use trim::{TrimStage, TrimmedMetadata};
+
+pub mod deterministic;
+pub use deterministic::{DeterministicDoneMetadata, DeterministicStage};
+
+pub mod cmin;
+pub use cmin::CminCoverageStage;
+
 pub mod owned;
 pub use owned::StagesOwnedList;
 
+pub mod cmdline;
+pub use cmdline::ArgvEnvMutationStage;
+
+pub mod weighted;
+pub use weighted::{StageDeadline, WeightedStages, WeightedStagesTuple};
+
 #[cfg(feature = "std")]
 pub mod concolic;
 #[cfg(feature = "std")]
-pub use concolic::ConcolicTracingStage;
+pub use concolic::{ConcolicTracingStage, ConstraintTracingStage};
 #[cfg(feature = "std")]
 pub use concolic::SimpleConcolicMutationalStage;
 
+#[cfg(all(feature = "std", unix))]
+pub mod control;
+#[cfg(all(feature = "std", unix))]
+pub use control::ControlStage;
+
+#[cfg(feature = "std")]
+pub mod seedlog;
+#[cfg(feature = "std")]
+pub use seedlog::SeedLogStage;
+
 #[cfg(feature = "std")]
 pub mod sync;
 #[cfg(feature = "std")]
 pub use sync::*;
 
+#[cfg(feature = "std")]
+pub mod http_sync;
+#[cfg(feature = "std")]
+pub use http_sync::HttpCorpusSync;
+
+#[cfg(feature = "std")]
+pub mod reseed;
+#[cfg(feature = "std")]
+pub use reseed::ReseedStage;
+
 use crate::{
     corpus::CorpusScheduler,
     events::{EventFirer, EventRestarter, HasEventManagerId, ProgressReporter},
@@ -64,6 +117,10 @@ pub trait Stage<E, EM, S, Z> {
         manager: &mut EM,
         corpus_idx: usize,
     ) -> Result<(), Error>;
+
+    /// Sets the deadline of the following runs of the stage, or clears it with `None`.
+    /// Used by the [`WeightedStages`], stages doing open-ended work should stop once it passed.
+    fn set_deadline(&mut self, _deadline: Option<StageDeadline>) {}
 }
 
 /// A tuple holding all `Stages` used for fuzzing.
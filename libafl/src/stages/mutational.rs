@@ -10,7 +10,7 @@ use crate::{
     inputs::Input,
     mark_feature_time,
     mutators::Mutator,
-    stages::Stage,
+    stages::{Stage, StageDeadline},
     start_timer,
     state::{HasClientPerfMonitor, HasCorpus, HasRand},
     Error,
@@ -40,6 +40,12 @@ where
     /// Gets the number of iterations this mutator should run for.
     fn iterations(&self, state: &mut S, corpus_idx: usize) -> Result<usize, Error>;
 
+    /// The deadline set by the [`crate::stages::WeightedStages`], if any.
+    /// The stage stops mutating once it passed.
+    fn deadline(&self) -> Option<StageDeadline> {
+        None
+    }
+
     /// Runs this (mutational) stage for the given testcase
     #[allow(clippy::cast_possible_wrap)] // more than i32 stages on 32 bit system - highly unlikely...
     fn perform_mutational(
@@ -50,9 +56,25 @@ where
         manager: &mut EM,
         corpus_idx: usize,
     ) -> Result<(), Error> {
+        if state.corpus().get(corpus_idx)?.borrow().no_mutate() {
+            // Pinned testcases run once, as they are
+            let input = state
+                .corpus()
+                .get(corpus_idx)?
+                .borrow_mut()
+                .load_input()?
+                .clone();
+            fuzzer.evaluate_input(state, executor, manager, input)?;
+            return Ok(());
+        }
+
         let num = self.iterations(state, corpus_idx)?;
 
         for i in 0..num {
+            // Leave the rest of the time to the next stages, see `WeightedStages`
+            if i > 0 && self.deadline().map_or(false, |d| d.passed()) {
+                break;
+            }
             start_timer!(state);
             let mut input = state
                 .corpus()
@@ -91,6 +113,7 @@ where
     Z: Evaluator<E, EM, I, S>,
 {
     mutator: M,
+    deadline: Option<StageDeadline>,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(E, EM, I, S, Z)>,
 }
@@ -118,6 +141,11 @@ where
     fn iterations(&self, state: &mut S, _corpus_idx: usize) -> Result<usize, Error> {
         Ok(1 + state.rand_mut().below(DEFAULT_MUTATIONAL_MAX_ITERATIONS) as usize)
     }
+
+    #[inline]
+    fn deadline(&self) -> Option<StageDeadline> {
+        self.deadline
+    }
 }
 
 impl<E, EM, I, M, S, Z> Stage<E, EM, S, Z> for StdMutationalStage<E, EM, I, M, S, Z>
@@ -144,6 +172,11 @@ where
 
         ret
     }
+
+    #[inline]
+    fn set_deadline(&mut self, deadline: Option<StageDeadline>) {
+        self.deadline = deadline;
+    }
 }
 
 impl<E, EM, I, M, S, Z> StdMutationalStage<E, EM, I, M, S, Z>
@@ -157,11 +190,56 @@ where
     pub fn new(mutator: M) -> Self {
         Self {
             mutator,
+            deadline: None,
             phantom: PhantomData,
         }
     }
 }
 
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::{
+        corpus::{Corpus, InMemoryCorpus, RandCorpusScheduler, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        inputs::{BytesInput, HasBytesVec},
+        mutators::BitFlipMutator,
+        stages::{Stage, StdMutationalStage},
+        state::HasCorpus,
+        test_utils::{test_executor, test_state},
+        StdFuzzer,
+    };
+
+    #[test]
+    fn test_no_mutate_testcase() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        let mut testcase = Testcase::new(BytesInput::new(b"regression".to_vec()));
+        testcase.set_no_mutate(true);
+        corpus.add(testcase).unwrap();
+
+        let mut state = test_state(corpus);
+        let mut mgr = NopEventManager {};
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), ());
+
+        let mut executed = vec![];
+        let mut harness = |input: &BytesInput| {
+            executed.push(input.bytes().to_vec());
+            ExitKind::Ok
+        };
+        let mut executor = test_executor(&mut harness, &mut fuzzer, &mut state, &mut mgr);
+
+        let mut stage = StdMutationalStage::new(BitFlipMutator::new());
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, 0)
+            .unwrap();
+        drop(executor);
+
+        assert_eq!(executed, vec![b"regression".to_vec()]);
+        let mut testcase = state.corpus().get(0).unwrap().borrow_mut();
+        assert_eq!(testcase.load_input().unwrap().bytes(), b"regression");
+    }
+}
+
 #[cfg(feature = "python")]
 /// `StdMutationalStage` Python bindings
 pub mod pybind {
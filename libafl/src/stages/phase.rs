@@ -0,0 +1,481 @@
+//! Alternates the campaign between an exploration and an exploitation phase.
+//! The [`PhaseControlStage`] switches the [`FuzzPhase`] following a [`PhasePolicy`],
+//! the [`PhaseStage`]s only run their inner stages in their phase,
+//! and the [`PhaseCorpusScheduler`] favors recent finds while exploiting.
+//! The finds are tracked with a [`StallDetector`].
+
+use core::{fmt::Debug, marker::PhantomData};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::rands::Rand,
+    corpus::{Corpus, CorpusScheduler, Testcase},
+    inputs::Input,
+    stages::{Stage, StagesTuple, StallDetector},
+    state::{HasCorpus, HasExecutions, HasMetadata, HasRand},
+    Error,
+};
+
+/// The default amount of executions of each phase of the [`CadencePhasePolicy`]
+pub const DEFAULT_PHASE_EXECS: usize = 500_000;
+
+/// The default amount of executions without a find ending a phase early
+pub const DEFAULT_PHASE_STALL_EXECS: usize = 100_000;
+
+/// The default amount of most recent entries the [`PhaseCorpusScheduler`] favors while exploiting
+pub const DEFAULT_RECENT_ENTRIES: usize = 16;
+
+/// The default probability, in percent, the [`PhaseCorpusScheduler`] skips older entries with while exploiting
+pub const DEFAULT_SKIP_OLD_PROB: u64 = 95;
+
+/// The maximum amount of entries the [`PhaseCorpusScheduler`] skips for a single pick
+const MAX_SKIPPED_ENTRIES: usize = 64;
+
+/// A phase of the campaign
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FuzzPhase {
+    /// Look for new coverage, e.g. with havoc on the whole corpus
+    Exploration,
+    /// Dig deeper into the recent finds, e.g. with cmplog and input-to-state mutations
+    Exploitation,
+}
+
+impl FuzzPhase {
+    /// The other phase
+    #[must_use]
+    pub fn toggled(self) -> Self {
+        match self {
+            FuzzPhase::Exploration => FuzzPhase::Exploitation,
+            FuzzPhase::Exploitation => FuzzPhase::Exploration,
+        }
+    }
+}
+
+/// The current phase, set by the [`PhaseControlStage`]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct FuzzPhaseMetadata {
+    /// The current phase
+    pub phase: FuzzPhase,
+    /// How many times the phase changed so far
+    pub switches: u64,
+}
+
+crate::impl_serdeany!(FuzzPhaseMetadata);
+
+/// Returns the current [`FuzzPhase`] of the state, [`FuzzPhase::Exploration`] before the first [`PhaseControlStage`] ran
+#[must_use]
+pub fn current_phase<S>(state: &S) -> FuzzPhase
+where
+    S: HasMetadata,
+{
+    state
+        .metadata()
+        .get::<FuzzPhaseMetadata>()
+        .map_or(FuzzPhase::Exploration, |meta| meta.phase)
+}
+
+/// Decides when the campaign switches to the other phase
+pub trait PhasePolicy: Debug {
+    /// Returns `true` if the current `phase` should end,
+    /// given the executions since it started and since the last find (or the start of the phase, whichever came later)
+    fn should_switch(
+        &mut self,
+        phase: FuzzPhase,
+        execs_in_phase: usize,
+        execs_since_last_find: usize,
+    ) -> bool;
+}
+
+/// The default [`PhasePolicy`]: each phase lasts a fixed amount of executions,
+/// and ends early after `stall_execs` executions without a find.
+#[derive(Clone, Copy, Debug)]
+pub struct CadencePhasePolicy {
+    exploration_execs: usize,
+    exploitation_execs: usize,
+    stall_execs: Option<usize>,
+}
+
+impl PhasePolicy for CadencePhasePolicy {
+    fn should_switch(
+        &mut self,
+        phase: FuzzPhase,
+        execs_in_phase: usize,
+        execs_since_last_find: usize,
+    ) -> bool {
+        let phase_execs = match phase {
+            FuzzPhase::Exploration => self.exploration_execs,
+            FuzzPhase::Exploitation => self.exploitation_execs,
+        };
+        execs_in_phase >= phase_execs
+            || self
+                .stall_execs
+                .map_or(false, |stall_execs| execs_since_last_find >= stall_execs)
+    }
+}
+
+impl CadencePhasePolicy {
+    /// Creates a new [`CadencePhasePolicy`], switching phases after the given amounts of executions
+    #[must_use]
+    pub fn new(exploration_execs: usize, exploitation_execs: usize) -> Self {
+        Self {
+            exploration_execs: exploration_execs.max(1),
+            exploitation_execs: exploitation_execs.max(1),
+            stall_execs: None,
+        }
+    }
+
+    /// Also ends a phase after `stall_execs` executions without a find
+    #[must_use]
+    pub fn with_stall_execs(mut self, stall_execs: usize) -> Self {
+        self.stall_execs = Some(stall_execs.max(1));
+        self
+    }
+}
+
+impl Default for CadencePhasePolicy {
+    fn default() -> Self {
+        Self::new(DEFAULT_PHASE_EXECS, DEFAULT_PHASE_EXECS)
+            .with_stall_execs(DEFAULT_PHASE_STALL_EXECS)
+    }
+}
+
+/// A stage switching the [`FuzzPhaseMetadata`] following a [`PhasePolicy`].
+/// It should come first in the stages tuple.
+#[derive(Clone, Debug)]
+pub struct PhaseControlStage<I, P>
+where
+    I: Input,
+    P: PhasePolicy,
+{
+    policy: P,
+    phase_start_execs: usize,
+    stall: StallDetector,
+    phantom: PhantomData<I>,
+}
+
+impl<E, EM, I, P, S, Z> Stage<E, EM, S, Z> for PhaseControlStage<I, P>
+where
+    I: Input,
+    P: PhasePolicy,
+    S: HasCorpus<I> + HasExecutions + HasMetadata,
+{
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        _manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let executions = *state.executions();
+        let corpus_count = state.corpus().count();
+        if !state.has_metadata::<FuzzPhaseMetadata>() {
+            state.add_metadata(FuzzPhaseMetadata {
+                phase: FuzzPhase::Exploration,
+                switches: 0,
+            });
+            self.phase_start_execs = executions;
+            self.stall.reset(executions, corpus_count);
+        }
+        let meta = state.metadata_mut().get_mut::<FuzzPhaseMetadata>().unwrap();
+
+        let execs_in_phase = executions.saturating_sub(self.phase_start_execs);
+        // The stall counter starts over with each phase
+        let execs_since_last_find = self.stall.update(executions, corpus_count);
+        if self
+            .policy
+            .should_switch(meta.phase, execs_in_phase, execs_since_last_find)
+        {
+            meta.phase = meta.phase.toggled();
+            meta.switches += 1;
+            self.phase_start_execs = executions;
+            self.stall.reset(executions, corpus_count);
+        }
+        Ok(())
+    }
+}
+
+impl<I> PhaseControlStage<I, CadencePhasePolicy>
+where
+    I: Input,
+{
+    /// Creates a new [`PhaseControlStage`] with the default [`CadencePhasePolicy`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_policy(CadencePhasePolicy::default())
+    }
+}
+
+impl<I> Default for PhaseControlStage<I, CadencePhasePolicy>
+where
+    I: Input,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, P> PhaseControlStage<I, P>
+where
+    I: Input,
+    P: PhasePolicy,
+{
+    /// Creates a new [`PhaseControlStage`] with the given policy, starting with the exploration
+    #[must_use]
+    pub fn with_policy(policy: P) -> Self {
+        Self {
+            policy,
+            phase_start_execs: 0,
+            stall: StallDetector::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// The policy of this stage
+    pub fn policy_mut(&mut self) -> &mut P {
+        &mut self.policy
+    }
+}
+
+/// Runs the wrapped stages only during the given [`FuzzPhase`]
+#[derive(Debug)]
+pub struct PhaseStage<ST> {
+    phase: FuzzPhase,
+    stages: ST,
+}
+
+impl<E, EM, S, ST, Z> Stage<E, EM, S, Z> for PhaseStage<ST>
+where
+    S: HasMetadata,
+    ST: StagesTuple<E, EM, S, Z>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        if current_phase(state) == self.phase {
+            self.stages
+                .perform_all(fuzzer, executor, state, manager, corpus_idx)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<ST> PhaseStage<ST> {
+    /// Creates a new [`PhaseStage`], running the `stages` tuple only during `phase`
+    #[must_use]
+    pub fn new(phase: FuzzPhase, stages: ST) -> Self {
+        Self { phase, stages }
+    }
+
+    /// Runs the `stages` tuple only during the exploration
+    #[must_use]
+    pub fn exploration(stages: ST) -> Self {
+        Self::new(FuzzPhase::Exploration, stages)
+    }
+
+    /// Runs the `stages` tuple only during the exploitation
+    #[must_use]
+    pub fn exploitation(stages: ST) -> Self {
+        Self::new(FuzzPhase::Exploitation, stages)
+    }
+}
+
+/// A [`CorpusScheduler`] delegating to the base scheduler.
+/// While exploiting, it skips the entries the base scheduler picks with a given probability,
+/// unless they are among the most recent entries.
+/// After a bounded amount of skipped picks in a row, the pick of the base scheduler is used.
+#[derive(Debug, Clone)]
+pub struct PhaseCorpusScheduler<CS, I, S>
+where
+    CS: CorpusScheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata + HasRand,
+{
+    base: CS,
+    recent_entries: usize,
+    skip_old_prob: u64,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<CS, I, S> CorpusScheduler<I, S> for PhaseCorpusScheduler<CS, I, S>
+where
+    CS: CorpusScheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata + HasRand,
+{
+    fn on_add(&self, state: &mut S, idx: usize) -> Result<(), Error> {
+        self.base.on_add(state, idx)
+    }
+
+    fn on_replace(&self, state: &mut S, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        self.base.on_replace(state, idx, testcase)
+    }
+
+    fn on_remove(
+        &self,
+        state: &mut S,
+        idx: usize,
+        testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        self.base.on_remove(state, idx, testcase)
+    }
+
+    fn on_schedule(&self, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        self.base.on_schedule(idx, testcase)
+    }
+
+    fn next(&self, state: &mut S) -> Result<usize, Error> {
+        let mut idx = self.base.next(state)?;
+        if current_phase(state) == FuzzPhase::Exploration {
+            return Ok(idx);
+        }
+        let mut skipped = 0;
+        while skipped < MAX_SKIPPED_ENTRIES
+            && idx + self.recent_entries < state.corpus().count()
+            && state.rand_mut().below(100) < self.skip_old_prob
+        {
+            idx = self.base.next(state)?;
+            skipped += 1;
+        }
+        Ok(idx)
+    }
+}
+
+impl<CS, I, S> PhaseCorpusScheduler<CS, I, S>
+where
+    CS: CorpusScheduler<I, S>,
+    I: Input,
+    S: HasCorpus<I> + HasMetadata + HasRand,
+{
+    /// Creates a new [`PhaseCorpusScheduler`], favoring the [`DEFAULT_RECENT_ENTRIES`] most recent entries
+    /// while exploiting, skipping older ones with the [`DEFAULT_SKIP_OLD_PROB`]
+    #[must_use]
+    pub fn new(base: CS) -> Self {
+        Self::with_recent_entries(base, DEFAULT_RECENT_ENTRIES, DEFAULT_SKIP_OLD_PROB)
+    }
+
+    /// Creates a new [`PhaseCorpusScheduler`], favoring the `recent_entries` most recent entries while exploiting,
+    /// skipping older ones with a probability of `skip_old_prob` percent, at most 99
+    #[must_use]
+    pub fn with_recent_entries(base: CS, recent_entries: usize, skip_old_prob: u64) -> Self {
+        Self {
+            base,
+            recent_entries: recent_entries.max(1),
+            skip_old_prob: skip_old_prob.min(99),
+            phantom: PhantomData,
+        }
+    }
+
+    /// The base scheduler
+    #[must_use]
+    pub fn base(&self) -> &CS {
+        &self.base
+    }
+
+    /// The base scheduler (as mutable borrow)
+    pub fn base_mut(&mut self) -> &mut CS {
+        &mut self.base
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, CorpusScheduler, InMemoryCorpus, QueueCorpusScheduler, Testcase},
+        inputs::BytesInput,
+        stages::{
+            phase::{
+                current_phase, CadencePhasePolicy, FuzzPhase, PhaseControlStage,
+                PhaseCorpusScheduler, PhaseStage,
+            },
+            Stage, StagesTuple,
+        },
+        state::{HasCorpus, HasExecutions, StdState},
+        Error,
+    };
+
+    /// Counts its runs
+    #[derive(Debug, Default)]
+    struct CountingStage {
+        runs: usize,
+    }
+
+    impl<E, EM, S, Z> Stage<E, EM, S, Z> for CountingStage {
+        fn perform(
+            &mut self,
+            _fuzzer: &mut Z,
+            _executor: &mut E,
+            _state: &mut S,
+            _manager: &mut EM,
+            _corpus_idx: usize,
+        ) -> Result<(), Error> {
+            self.runs += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_phase_cadence() {
+        let mut corpus = InMemoryCorpus::new();
+        for i in 0..4 {
+            corpus.add(Testcase::new(BytesInput::new(vec![i]))).unwrap();
+        }
+        let mut state = StdState::new(StdRand::with_seed(0), corpus, InMemoryCorpus::new(), ());
+        let mut stages = tuple_list!(
+            PhaseControlStage::with_policy(CadencePhasePolicy::new(30, 20)),
+            PhaseStage::exploration(tuple_list!(CountingStage::default())),
+            PhaseStage::exploitation(tuple_list!(CountingStage::default()))
+        );
+
+        let mut phases = vec![];
+        for _ in 0..10 {
+            stages
+                .perform_all(&mut (), &mut (), &mut state, &mut (), 0)
+                .unwrap();
+            phases.push(current_phase(&state));
+            *state.executions_mut() += 10;
+        }
+
+        let (explore, exploit) = (FuzzPhase::Exploration, FuzzPhase::Exploitation);
+        assert_eq!(
+            phases,
+            vec![
+                explore, explore, explore, exploit, exploit, explore, explore, explore, exploit,
+                exploit
+            ]
+        );
+        // Each loop ran the stages of exactly one phase
+        assert_eq!((stages.1).0.stages.0.runs, 6);
+        assert_eq!((stages.1).1 .0.stages.0.runs, 4);
+
+        // While exploiting, the scheduler skips the older entries the queue picks, with a probability of 99%
+        let scheduler =
+            PhaseCorpusScheduler::with_recent_entries(QueueCorpusScheduler::new(), 2, 100);
+        assert_eq!(scheduler.skip_old_prob, 99);
+        assert_eq!(current_phase(&state), exploit);
+        let picked: Vec<usize> = (0..8)
+            .map(|_| scheduler.next(&mut state).unwrap())
+            .collect();
+        assert!(picked.iter().filter(|idx| **idx >= 2).count() >= 7);
+        assert_eq!(state.corpus().count(), 4);
+
+        // and follows it while exploring
+        stages
+            .perform_all(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+        assert_eq!(current_phase(&state), explore);
+        let picked: Vec<usize> = (0..4)
+            .map(|_| scheduler.next(&mut state).unwrap())
+            .collect();
+        assert_eq!(picked, vec![0, 1, 2, 3]);
+    }
+}
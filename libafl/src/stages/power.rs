@@ -4,13 +4,16 @@ use alloc::string::{String, ToString};
 use core::{fmt::Debug, marker::PhantomData};
 
 use crate::{
-    corpus::{Corpus, IsFavoredMetadata, PowerScheduleTestcaseMetaData, Testcase},
+    corpus::{
+        Corpus, InitialEnergyMetadata, IsFavoredMetadata, PowerScheduleTestcaseMetaData, Testcase,
+        MAX_ENERGY,
+    },
     executors::{Executor, HasObservers},
     fuzzer::Evaluator,
     inputs::Input,
     mutators::Mutator,
     observers::{MapObserver, ObserversTuple},
-    stages::{MutationalStage, PowerScheduleMetadata, Stage},
+    stages::{MutationalStage, PowerScheduleMetadata, Stage, StageDeadline},
     state::{HasClientPerfMonitor, HasCorpus, HasMetadata},
     Error,
 };
@@ -31,6 +34,33 @@ const POWER_BETA: f64 = 1.0;
 const MAX_FACTOR: f64 = POWER_BETA * 32.0;
 const HAVOC_MAX_MULT: f64 = 64.0;
 
+/// A suggested share of its energy a testcase loses after each fruitless round, see [`PowerMutationalStage::with_energy_decay`]
+pub const DEFAULT_ENERGY_DECAY: f64 = 0.1;
+
+/// Updates the energy of a testcase after a round of the [`PowerMutationalStage`] on it:
+/// a round that `found` a new entry restores it to [`MAX_ENERGY`], a fruitless round takes the share `decay` of it.
+pub fn update_energy<I>(testcase: &mut Testcase<I>, found: bool, decay: f64)
+where
+    I: Input,
+{
+    let energy = if found {
+        MAX_ENERGY
+    } else {
+        testcase.energy() * (1.0 - decay)
+    };
+    testcase.set_energy(energy);
+}
+
+/// Scales the `perf_score` by the `energy` of a testcase, down to at least one iteration,
+/// so that exhausted testcases still get fuzzed
+fn scale_by_energy(perf_score: f64, energy: f64) -> f64 {
+    if energy < MAX_ENERGY {
+        (perf_score * energy).max(1.0)
+    } else {
+        perf_score
+    }
+}
+
 /// The mutational stage using power schedules
 #[derive(Clone, Debug)]
 pub struct PowerMutationalStage<E, EM, I, M, O, OT, S, Z>
@@ -47,6 +77,9 @@ where
     mutator: M,
     /// The employed power schedule strategy
     strat: PowerSchedule,
+    /// The share of its energy a testcase loses after each fruitless round, if the energy decays
+    energy_decay: Option<f64>,
+    deadline: Option<StageDeadline>,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(E, EM, I, O, OT, S, Z)>,
 }
@@ -91,6 +124,11 @@ where
         self.calculate_score(&mut testcase, psmeta, fuzz_mu)
     }
 
+    #[inline]
+    fn deadline(&self) -> Option<StageDeadline> {
+        self.deadline
+    }
+
     #[allow(clippy::cast_possible_wrap)]
     fn perform_mutational(
         &mut self,
@@ -101,8 +139,12 @@ where
         corpus_idx: usize,
     ) -> Result<(), Error> {
         let num = self.iterations(state, corpus_idx)?;
+        let mut found = false;
 
         for i in 0..num {
+            if i > 0 && self.deadline().map_or(false, |d| d.passed()) {
+                break;
+            }
             let mut input = state
                 .corpus()
                 .get(corpus_idx)?
@@ -131,6 +173,7 @@ where
             psmeta.n_fuzz_mut()[hash] = psmeta.n_fuzz()[hash].saturating_add(1);
 
             if let Some(idx) = corpus_idx {
+                found = true;
                 state
                     .corpus()
                     .get(idx)?
@@ -146,6 +189,14 @@ where
             self.mutator_mut().post_exec(state, i as i32, corpus_idx)?;
         }
 
+        if let Some(decay) = self.energy_decay {
+            update_energy(
+                &mut state.corpus().get(corpus_idx)?.borrow_mut(),
+                found,
+                decay,
+            );
+        }
+
         Ok(())
     }
 }
@@ -173,6 +224,11 @@ where
         let ret = self.perform_mutational(fuzzer, executor, state, manager, corpus_idx);
         ret
     }
+
+    #[inline]
+    fn set_deadline(&mut self, deadline: Option<StageDeadline>) {
+        self.deadline = deadline;
+    }
 }
 
 impl<E, EM, I, M, O, OT, S, Z> PowerMutationalStage<E, EM, I, M, O, OT, S, Z>
@@ -191,10 +247,28 @@ where
             map_observer_name: map_observer_name.name().to_string(),
             mutator,
             strat,
+            energy_decay: None,
+            deadline: None,
             phantom: PhantomData,
         }
     }
 
+    /// Creates a new [`PowerMutationalStage`] where testcases cool down after fruitless rounds:
+    /// each round without a find takes the share `decay`, between `0.0` and `1.0`, of the energy of the testcase,
+    /// and a round with a find restores it, see [`update_energy`].
+    /// The iterations of a round get scaled by the energy of the testcase.
+    pub fn with_energy_decay(
+        mutator: M,
+        strat: PowerSchedule,
+        map_observer_name: &O,
+        decay: f64,
+    ) -> Self {
+        Self {
+            energy_decay: Some(decay.clamp(0.0, 1.0)),
+            ..Self::new(mutator, strat, map_observer_name)
+        }
+    }
+
     /// Compute the parameter `μ` used in the COE schedule.
     #[inline]
     #[allow(clippy::unused_self)]
@@ -245,6 +319,11 @@ where
         let avg_bitmap_size = psmeta.bitmap_size() / psmeta.bitmap_entries();
 
         let favored = testcase.has_metadata::<IsFavoredMetadata>();
+        let energy = testcase.energy();
+        let initial_factor = testcase
+            .metadata()
+            .get::<InitialEnergyMetadata>()
+            .map(|meta| meta.factor);
         let tcmeta = testcase
             .metadata_mut()
             .get_mut::<PowerScheduleTestcaseMetaData>()
@@ -299,6 +378,13 @@ where
             perf_score *= 5.0;
         }
 
+        // Seeds weighted on import, see `StdState::weight_initial_inputs_by_size`
+        if tcmeta.fuzz_level() == 0 {
+            if let Some(factor) = initial_factor {
+                perf_score *= factor;
+            }
+        }
+
         let mut factor: f64 = 1.0;
 
         // COE and Fast schedule are fairly different from what are described in the original thesis,
@@ -375,6 +461,9 @@ where
             perf_score *= factor / POWER_BETA;
         }
 
+        // Seeds exhausted by many fruitless rounds cool down, see `Self::with_energy_decay`
+        perf_score = scale_by_energy(perf_score, energy);
+
         // Lower bound if the strat is not COE.
         if self.strat == PowerSchedule::COE && perf_score < 1.0 {
             perf_score = 1.0;
@@ -388,3 +477,41 @@ where
         Ok(perf_score as usize)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        corpus::{Testcase, MAX_ENERGY, MIN_ENERGY},
+        inputs::BytesInput,
+        stages::power::{scale_by_energy, update_energy},
+    };
+
+    #[test]
+    fn test_energy_decay() {
+        let mut testcase = Testcase::new(BytesInput::new(vec![b'a']));
+        assert!((testcase.energy() - MAX_ENERGY).abs() < f64::EPSILON);
+
+        // Many fruitless rounds cool the seed down
+        let mut last = testcase.energy();
+        for _ in 0..10 {
+            update_energy(&mut testcase, false, 0.2);
+            assert!(testcase.energy() < last);
+            last = testcase.energy();
+        }
+        assert!(last < 0.2);
+        for _ in 0..100 {
+            update_energy(&mut testcase, false, 0.2);
+        }
+        assert!((testcase.energy() - MIN_ENERGY).abs() < f64::EPSILON);
+
+        // An exhausted seed still gets fuzzed
+        assert!((scale_by_energy(100.0, testcase.energy()) - 1.0).abs() < f64::EPSILON);
+        assert!((scale_by_energy(100.0, 0.5) - 50.0).abs() < f64::EPSILON);
+        // Without decay, the score is left as is
+        assert!((scale_by_energy(0.5, MAX_ENERGY) - 0.5).abs() < f64::EPSILON);
+
+        // A find boosts it again
+        update_energy(&mut testcase, true, 0.2);
+        assert!((testcase.energy() - MAX_ENERGY).abs() < f64::EPSILON);
+    }
+}
@@ -101,7 +101,11 @@ where
         self.current_corpus_idx = Some(if let Some(corpus_idx) = self.current_corpus_idx {
             corpus_idx
         } else {
-            fuzzer.scheduler().next(state)?
+            let corpus_idx = fuzzer.scheduler().next(state)?;
+            fuzzer
+                .scheduler()
+                .on_schedule(corpus_idx, &state.corpus().get(corpus_idx)?.borrow())?;
+            corpus_idx
         });
 
         self.testcases_to_do = self.iterations(state, self.current_corpus_idx.unwrap())?;
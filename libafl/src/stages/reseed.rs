@@ -0,0 +1,290 @@
+//! The reseed stage re-imports the initial seeds once the campaign stalls,
+//! to help it escape a local optimum.
+
+use ahash::AHasher;
+use alloc::vec::Vec;
+use core::{hash::Hasher, marker::PhantomData};
+use hashbrown::HashSet;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    corpus::{Corpus, PowerScheduleTestcaseMetaData},
+    fuzzer::Evaluator,
+    inputs::Input,
+    libafl_log,
+    stages::{Stage, StallDetector},
+    state::{HasCorpus, HasExecutions, HasMetadata},
+    Error,
+};
+
+/// The default amount of executions without a find after which the [`ReseedStage`] reseeds
+pub const DEFAULT_RESEED_STALL_EXECS: usize = 1_000_000;
+
+/// The default maximum amount of seeds the [`ReseedStage`] re-imports per reseed
+pub const DEFAULT_RESEED_MAX_INPUTS: usize = 1024;
+
+/// A stage watching the executions since the last find.
+/// After `stall_execs` executions without a find, it adds the seeds from the seed directories to the corpus again,
+/// even if they are not interesting, and resets the scheduling energy
+/// (the fuzz level and handicap of the [`PowerScheduleTestcaseMetaData`]) of all entries.
+/// Seeds still in the corpus are skipped, and at most `max_inputs` seeds get re-imported per reseed.
+#[derive(Clone, Debug)]
+pub struct ReseedStage<I>
+where
+    I: Input,
+{
+    seed_dirs: Vec<PathBuf>,
+    stall_execs: usize,
+    max_inputs: usize,
+    stall: StallDetector,
+    reseeds: usize,
+    phantom: PhantomData<I>,
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for ReseedStage<I>
+where
+    I: Input,
+    S: HasCorpus<I> + HasExecutions,
+    Z: Evaluator<E, EM, I, S>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let stalled = self
+            .stall
+            .update(*state.executions(), state.corpus().count());
+        if stalled < self.stall_execs {
+            return Ok(());
+        }
+
+        libafl_log!(
+            Info,
+            "No finds for {} executions, reseeding from {:?}",
+            stalled,
+            self.seed_dirs
+        );
+        let mut known = HashSet::new();
+        for idx in 0..state.corpus().count() {
+            let mut testcase = state.corpus().get(idx)?.borrow_mut();
+            if let Some(meta) = testcase
+                .metadata_mut()
+                .get_mut::<PowerScheduleTestcaseMetaData>()
+            {
+                meta.set_fuzz_level(0);
+                meta.set_handicap(0);
+            }
+            known.insert(input_hash(testcase.load_input()?)?);
+        }
+
+        let mut budget = self.max_inputs;
+        for dir in &self.seed_dirs {
+            Self::reseed_from_directory(
+                dir,
+                &mut known,
+                &mut budget,
+                fuzzer,
+                executor,
+                state,
+                manager,
+            )?;
+        }
+
+        // The reseeded entries are no finds, give them a chance first
+        self.reseeds += 1;
+        self.stall
+            .reset(*state.executions(), state.corpus().count());
+        Ok(())
+    }
+}
+
+impl<I> ReseedStage<I>
+where
+    I: Input,
+{
+    /// Creates a new [`ReseedStage`], reseeding from `seed_dirs` after [`DEFAULT_RESEED_STALL_EXECS`] executions without a find
+    #[must_use]
+    pub fn new(seed_dirs: &[PathBuf]) -> Self {
+        Self::with_stall_execs(seed_dirs, DEFAULT_RESEED_STALL_EXECS)
+    }
+
+    /// Creates a new [`ReseedStage`], reseeding from `seed_dirs` after `stall_execs` executions without a find
+    #[must_use]
+    pub fn with_stall_execs(seed_dirs: &[PathBuf], stall_execs: usize) -> Self {
+        Self {
+            seed_dirs: seed_dirs.to_vec(),
+            stall_execs: stall_execs.max(1),
+            max_inputs: DEFAULT_RESEED_MAX_INPUTS,
+            stall: StallDetector::new(),
+            reseeds: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Re-imports at most `max_inputs` seeds per reseed, instead of [`DEFAULT_RESEED_MAX_INPUTS`]
+    #[must_use]
+    pub fn with_max_inputs(mut self, max_inputs: usize) -> Self {
+        self.max_inputs = max_inputs;
+        self
+    }
+
+    /// How often this stage reseeded the corpus
+    #[must_use]
+    pub fn reseeds(&self) -> usize {
+        self.reseeds
+    }
+
+    /// Adds the files in `in_dir` (and its subdirectories) not `known` yet to the corpus,
+    /// until the `budget` is used up
+    fn reseed_from_directory<E, EM, S, Z>(
+        in_dir: &Path,
+        known: &mut HashSet<u64>,
+        budget: &mut usize,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+    ) -> Result<(), Error>
+    where
+        Z: Evaluator<E, EM, I, S>,
+    {
+        for entry in fs::read_dir(in_dir)? {
+            if *budget == 0 {
+                break;
+            }
+            let path = entry?.path();
+            let attr = match fs::metadata(&path) {
+                Ok(attr) => attr,
+                Err(_) => continue,
+            };
+
+            if attr.is_file() && attr.len() > 0 {
+                let input = I::from_file(&path)?;
+                if known.insert(input_hash(&input)?) {
+                    fuzzer.add_input(state, executor, manager, input)?;
+                    *budget -= 1;
+                }
+            } else if attr.is_dir() {
+                Self::reseed_from_directory(
+                    &path, known, budget, fuzzer, executor, state, manager,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The hash of the serialized `input`, to recognize seeds already in the corpus
+fn input_hash<I>(input: &I) -> Result<u64, Error>
+where
+    I: Input,
+{
+    let mut hasher = AHasher::new_with_keys(0, 0);
+    hasher.write(&postcard::to_allocvec(input)?);
+    Ok(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        corpus::{
+            Corpus, InMemoryCorpus, PowerScheduleTestcaseMetaData, RandCorpusScheduler, Testcase,
+        },
+        events::NopEventManager,
+        executors::ExitKind,
+        fuzzer::StdFuzzer,
+        inputs::BytesInput,
+        stages::{reseed::ReseedStage, Stage},
+        state::{HasCorpus, HasExecutions, HasMetadata},
+        test_utils::{test_executor, test_state, TestDir},
+    };
+
+    #[test]
+    fn test_reseed_on_stall() {
+        let seed_dir = TestDir::new("reseed");
+        fs::write(seed_dir.join("seed1"), b"seed1").unwrap();
+        fs::write(seed_dir.join("seed2"), b"seed2").unwrap();
+
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        let mut testcase = Testcase::new(vec![b'a']);
+        let mut meta = PowerScheduleTestcaseMetaData::new(0);
+        meta.set_fuzz_level(42);
+        testcase.add_metadata(meta);
+        corpus.add(testcase).unwrap();
+        let mut state = test_state(corpus);
+
+        let mut mgr = NopEventManager {};
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), ());
+        let mut harness = |_buf: &BytesInput| ExitKind::Ok;
+        let mut executor = test_executor(&mut harness, &mut fuzzer, &mut state, &mut mgr);
+
+        let mut stage = ReseedStage::with_stall_execs(&[seed_dir.to_path_buf()], 1000);
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, 0)
+            .unwrap();
+
+        // Not stalled yet
+        *state.executions_mut() = 999;
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, 0)
+            .unwrap();
+        assert_eq!(stage.reseeds(), 0);
+        assert_eq!(state.corpus().count(), 1);
+
+        // Stalled, the seeds get added again and the energy is reset
+        *state.executions_mut() = 1000;
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, 0)
+            .unwrap();
+        assert_eq!(stage.reseeds(), 1);
+        assert_eq!(state.corpus().count(), 3);
+        let fuzz_level = state
+            .corpus()
+            .get(0)
+            .unwrap()
+            .borrow()
+            .metadata()
+            .get::<PowerScheduleTestcaseMetaData>()
+            .unwrap()
+            .fuzz_level();
+        assert_eq!(fuzz_level, 0);
+
+        // The stall counter starts over
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, 0)
+            .unwrap();
+        assert_eq!(stage.reseeds(), 1);
+
+        // Stalled again, the seeds are still in the corpus, so nothing gets added
+        *state.executions_mut() = 2000;
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, 0)
+            .unwrap();
+        assert_eq!(stage.reseeds(), 2);
+        assert_eq!(state.corpus().count(), 3);
+
+        // A bounded stage re-imports one new seed per reseed
+        fs::write(seed_dir.join("seed3"), b"seed3").unwrap();
+        fs::write(seed_dir.join("seed4"), b"seed4").unwrap();
+        let mut stage =
+            ReseedStage::with_stall_execs(&[seed_dir.to_path_buf()], 1000).with_max_inputs(1);
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, 0)
+            .unwrap();
+        *state.executions_mut() = 3000;
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, 0)
+            .unwrap();
+        assert_eq!(stage.reseeds(), 1);
+        assert_eq!(state.corpus().count(), 4);
+    }
+}
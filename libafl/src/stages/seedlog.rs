@@ -0,0 +1,215 @@
+//! The [`SeedLogStage`] periodically logs the state of the random number generator to disk,
+//! so that a campaign can be replayed from any of the logged checkpoints.
+
+use alloc::{collections::VecDeque, vec::Vec};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    bolts::rands::Rand,
+    stages::Stage,
+    state::{HasExecutions, HasRand},
+    Error,
+};
+
+/// The default amount of inputs between two logged checkpoints
+pub const DEFAULT_SEED_LOG_INTERVAL: usize = 1024;
+
+/// The default amount of checkpoint files kept on disk
+pub const DEFAULT_SEED_LOG_RETENTION: usize = 16;
+
+/// A checkpoint written by the [`SeedLogStage`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "R: serde::de::DeserializeOwned")]
+pub struct SeedLogEntry<R>
+where
+    R: Rand,
+{
+    /// The amount of executions at the time of the checkpoint
+    pub executions: usize,
+    /// The state of the random number generator
+    pub rand: R,
+}
+
+/// A stage writing a [`SeedLogEntry`] with the state of the random number generator every `interval` inputs.
+/// Only the last `retention` checkpoint files are kept in the log directory.
+/// Restoring a logged rand in the state replays the following mutations.
+/// The checkpoints already in the log directory are picked up at creation, so a restarted stage continues the log.
+#[derive(Debug, Clone)]
+pub struct SeedLogStage {
+    log_dir: PathBuf,
+    interval: usize,
+    retention: usize,
+    performed: usize,
+    checkpoints: usize,
+    files: VecDeque<PathBuf>,
+}
+
+impl<E, EM, S, Z> Stage<E, EM, S, Z> for SeedLogStage
+where
+    S: HasRand + HasExecutions,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        _manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        self.performed += 1;
+        if self.performed % self.interval != 0 {
+            return Ok(());
+        }
+
+        // Serialized the same as a `SeedLogEntry`, without cloning the rand
+        let entry = (*state.executions(), state.rand());
+        let path = self
+            .log_dir
+            .join(format!("seed_{:08}.log", self.checkpoints));
+        fs::write(&path, postcard::to_allocvec(&entry)?)?;
+        self.checkpoints += 1;
+
+        self.files.push_back(path);
+        while self.files.len() > self.retention {
+            let old = self.files.pop_front().unwrap();
+            // The file may have been removed by the user
+            let _ = fs::remove_file(old);
+        }
+        Ok(())
+    }
+}
+
+impl SeedLogStage {
+    /// Creates a new [`SeedLogStage`] writing to `log_dir`, with the default interval and retention
+    pub fn new(log_dir: PathBuf) -> Result<Self, Error> {
+        Self::with_params(
+            log_dir,
+            DEFAULT_SEED_LOG_INTERVAL,
+            DEFAULT_SEED_LOG_RETENTION,
+        )
+    }
+
+    /// Creates a new [`SeedLogStage`] writing to `log_dir` every `interval` inputs,
+    /// and keeping the last `retention` checkpoint files.
+    /// Checkpoint files left in `log_dir` by an earlier run count towards the `retention`.
+    pub fn with_params(log_dir: PathBuf, interval: usize, retention: usize) -> Result<Self, Error> {
+        if interval == 0 || retention == 0 {
+            return Err(Error::IllegalArgument(
+                "The seed log interval and retention cannot be 0".into(),
+            ));
+        }
+        fs::create_dir_all(&log_dir)?;
+        let mut logged = Self::logged_checkpoints(&log_dir)?;
+        logged.sort_unstable();
+        let checkpoints = logged.last().map_or(0, |(nr, _)| nr + 1);
+        Ok(Self {
+            log_dir,
+            interval,
+            retention,
+            performed: 0,
+            checkpoints,
+            files: logged.into_iter().map(|(_, path)| path).collect(),
+        })
+    }
+
+    /// The checkpoint files in `log_dir`, with their number
+    fn logged_checkpoints(log_dir: &Path) -> Result<Vec<(usize, PathBuf)>, Error> {
+        let mut logged = vec![];
+        for entry in fs::read_dir(log_dir)? {
+            let path = entry?.path();
+            let nr = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("seed_"))
+                .and_then(|name| name.strip_suffix(".log"))
+                .and_then(|nr| nr.parse().ok());
+            if let Some(nr) = nr {
+                logged.push((nr, path));
+            }
+        }
+        Ok(logged)
+    }
+
+    /// The checkpoint files currently kept on disk, oldest first
+    #[must_use]
+    pub fn files(&self) -> &VecDeque<PathBuf> {
+        &self.files
+    }
+
+    /// Loads a [`SeedLogEntry`] from a checkpoint file
+    pub fn load_entry<P, R>(path: P) -> Result<SeedLogEntry<R>, Error>
+    where
+        P: AsRef<Path>,
+        R: Rand,
+    {
+        let (executions, rand) = postcard::from_bytes(&fs::read(path)?)?;
+        Ok(SeedLogEntry { executions, rand })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::Rand,
+        corpus::InMemoryCorpus,
+        inputs::{BytesInput, HasBytesVec},
+        mutators::{havoc_mutations, Mutator, StdScheduledMutator},
+        stages::{SeedLogStage, Stage},
+        state::{HasRand, StdState},
+        test_utils::{test_state, TestDir},
+    };
+
+    #[test]
+    fn test_seed_log_replay() {
+        let log_dir = TestDir::new("seedlog");
+
+        let mut state = test_state(InMemoryCorpus::<BytesInput>::new());
+        let mut stage = SeedLogStage::with_params(log_dir.to_path_buf(), 2, 2).unwrap();
+        let mut mutator = StdScheduledMutator::new(havoc_mutations());
+
+        let mut mutate = |state: &mut StdState<_, _, _, _, _>| {
+            let mut input = BytesInput::new(b"replay me".to_vec());
+            for i in 0..8 {
+                mutator.mutate(state, &mut input, i).unwrap();
+            }
+            input.bytes().to_vec()
+        };
+
+        for _ in 0..3 {
+            stage
+                .perform(&mut (), &mut (), &mut state, &mut (), 0)
+                .unwrap();
+            mutate(&mut state);
+        }
+        // Only the checkpoints after the 2nd and the 4th input get logged
+        stage
+            .perform(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+        assert_eq!(stage.files().len(), 2);
+        let expected = mutate(&mut state);
+
+        // Restart from the last checkpoint with a differently seeded rand
+        state.rand_mut().set_seed(0);
+        let entry = SeedLogStage::load_entry(stage.files().back().unwrap()).unwrap();
+        *state.rand_mut() = entry.rand;
+        assert_eq!(mutate(&mut state), expected);
+
+        // A restarted stage continues the log
+        let mut stage = SeedLogStage::with_params(log_dir.to_path_buf(), 1, 2).unwrap();
+        assert_eq!(stage.files().len(), 2);
+        stage
+            .perform(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+        assert_eq!(stage.files().len(), 2);
+        assert_eq!(
+            stage.files().back().unwrap(),
+            &log_dir.join("seed_00000002.log")
+        );
+        assert!(!log_dir.join("seed_00000000.log").exists());
+    }
+}
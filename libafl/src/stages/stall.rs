@@ -0,0 +1,62 @@
+//! The [`StallDetector`] tracks the executions since the campaign last found a new entry,
+//! for the stages reacting to a stalled campaign.
+
+use serde::{Deserialize, Serialize};
+
+/// Tracks the executions since the last find, given the executions and corpus size of the campaign over time.
+/// A grown corpus counts as a find.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct StallDetector {
+    last_find_execs: usize,
+    last_corpus_count: usize,
+}
+
+impl StallDetector {
+    /// Creates a new [`StallDetector`], counting from the first execution
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds the current executions and corpus size to the detector,
+    /// returning the executions since the last find (or since the last [`StallDetector::reset`], whichever came later)
+    pub fn update(&mut self, executions: usize, corpus_count: usize) -> usize {
+        if corpus_count > self.last_corpus_count {
+            self.last_find_execs = executions;
+        }
+        self.last_corpus_count = corpus_count;
+        executions.saturating_sub(self.last_find_execs)
+    }
+
+    /// Starts counting over from `executions`, e.g. after reacting to a stall.
+    /// Entries added up to `corpus_count` do not count as finds.
+    pub fn reset(&mut self, executions: usize, corpus_count: usize) {
+        self.last_find_execs = executions;
+        self.last_corpus_count = corpus_count;
+    }
+
+    /// The executions at the last find, or at the last [`StallDetector::reset`]
+    #[must_use]
+    pub fn last_find_execs(&self) -> usize {
+        self.last_find_execs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stages::stall::StallDetector;
+
+    #[test]
+    fn test_stall_detector() {
+        let mut detector = StallDetector::new();
+        assert_eq!(detector.update(100, 0), 100);
+        // A find
+        assert_eq!(detector.update(150, 1), 0);
+        assert_eq!(detector.update(400, 1), 250);
+        // A shrinking corpus is no find
+        assert_eq!(detector.update(500, 0), 350);
+        detector.reset(600, 3);
+        assert_eq!(detector.update(700, 3), 100);
+        assert_eq!(detector.last_find_execs(), 600);
+    }
+}
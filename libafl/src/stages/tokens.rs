@@ -0,0 +1,248 @@
+//! The auto tokens stage bootstraps a dictionary, extracting printable strings from the corpus entries.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Corpus,
+    events::{Event, EventFirer},
+    inputs::{HasBytesVec, Input},
+    mutators::Tokens,
+    stages::Stage,
+    state::{HasCorpus, HasMetadata},
+    Error,
+};
+
+/// The default minimum length of a printable run to be considered a token
+pub const DEFAULT_AUTO_TOKENS_MIN_LEN: usize = 4;
+
+/// The default maximum amount of tokens in the dictionary
+pub const DEFAULT_AUTO_TOKENS_MAX_COUNT: usize = 256;
+
+/// A testcase metadata marking an entry as scanned by the [`AutoTokensStage`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoTokensScannedMetadata {}
+
+crate::impl_serdeany!(AutoTokensScannedMetadata);
+
+/// A stage that scans the corpus entries for runs of printable ASCII characters
+/// and adds them, deduplicated, to the [`Tokens`] metadata of the state.
+/// Each entry gets scanned once and marked with an [`AutoTokensScannedMetadata`],
+/// so the stage can run in every iteration to pick up new entries.
+/// As new entries get added at the end of the corpus, only the unscanned entries at the end get visited.
+/// Once the dictionary is full, the stage does nothing but merge the shared tokens.
+/// New tokens get shared with the other clients in an [`Event::NewTokens`],
+/// and the tokens shared by the other clients get merged into the dictionary, see [`EventFirer::take_shared_tokens`].
+#[derive(Clone, Debug)]
+pub struct AutoTokensStage<I, S>
+where
+    I: Input + HasBytesVec,
+    S: HasCorpus<I> + HasMetadata,
+{
+    min_len: usize,
+    max_count: usize,
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<E, EM, I, S, Z> Stage<E, EM, S, Z> for AutoTokensStage<I, S>
+where
+    EM: EventFirer<I>,
+    I: Input + HasBytesVec,
+    S: HasCorpus<I> + HasMetadata,
+{
+    #[inline]
+    fn perform(
+        &mut self,
+        _fuzzer: &mut Z,
+        _executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        _corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let shared = manager.take_shared_tokens();
+        if !shared.is_empty() {
+            match state.metadata_mut().get_mut::<Tokens>() {
+                Some(tokens) => {
+                    tokens.merge_bounded(&shared, self.max_count);
+                }
+                None => {
+                    let mut tokens = Tokens::new();
+                    tokens.merge_bounded(&shared, self.max_count);
+                    state.add_metadata(tokens);
+                }
+            }
+        }
+
+        if state
+            .metadata()
+            .get::<Tokens>()
+            .map_or(false, |tokens| tokens.len() >= self.max_count)
+        {
+            return Ok(());
+        }
+
+        // The entries from the last scanned one on are new
+        let count = state.corpus().count();
+        let mut first_unscanned = count;
+        while first_unscanned > 0
+            && !state
+                .corpus()
+                .get(first_unscanned - 1)?
+                .borrow()
+                .has_metadata::<AutoTokensScannedMetadata>()
+        {
+            first_unscanned -= 1;
+        }
+        if first_unscanned == count {
+            return Ok(());
+        }
+
+        let mut tokens = state
+            .metadata_mut()
+            .remove::<Tokens>()
+            .map_or_else(Tokens::new, |meta| *meta);
+
+        let mut new_tokens = vec![];
+        for idx in first_unscanned..count {
+            if tokens.len() >= self.max_count {
+                break;
+            }
+            let mut testcase = state.corpus().get(idx)?.borrow_mut();
+            let bytes = testcase.load_input()?.bytes();
+            for token in printable_runs(bytes, self.min_len) {
+                if tokens.len() >= self.max_count {
+                    break;
+                }
+                let token = token.to_vec();
+                if tokens.add_token(&token) {
+                    new_tokens.push(token);
+                }
+            }
+            testcase.add_metadata(AutoTokensScannedMetadata {});
+        }
+
+        state.add_metadata(tokens);
+        if !new_tokens.is_empty() {
+            manager.fire(
+                state,
+                Event::NewTokens {
+                    tokens: new_tokens,
+                    phantom: PhantomData,
+                },
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<I, S> AutoTokensStage<I, S>
+where
+    I: Input + HasBytesVec,
+    S: HasCorpus<I> + HasMetadata,
+{
+    /// Creates a new [`AutoTokensStage`] with the default minimum token length and dictionary size
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_limits(DEFAULT_AUTO_TOKENS_MIN_LEN, DEFAULT_AUTO_TOKENS_MAX_COUNT)
+    }
+
+    /// Creates a new [`AutoTokensStage`] extracting runs of at least `min_len` printable characters,
+    /// until the dictionary holds `max_count` tokens
+    #[must_use]
+    pub fn with_limits(min_len: usize, max_count: usize) -> Self {
+        Self {
+            min_len: min_len.max(1),
+            max_count,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<I, S> Default for AutoTokensStage<I, S>
+where
+    I: Input + HasBytesVec,
+    S: HasCorpus<I> + HasMetadata,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the runs of printable ASCII characters in `bytes` at least `min_len` long
+fn printable_runs(bytes: &[u8], min_len: usize) -> Vec<&[u8]> {
+    bytes
+        .split(|b| !(b.is_ascii_graphic() || *b == b' '))
+        .filter(|run| run.len() >= min_len)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        inputs::BytesInput,
+        mutators::Tokens,
+        stages::{AutoTokensScannedMetadata, AutoTokensStage, Stage},
+        state::{HasCorpus, HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_auto_tokens() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        corpus
+            .add(Testcase::new(
+                b"\x89PNG\x00\x00\x00\x0dIHDR\x00\x01\xffab\x00\x00IEND\xaeB`\x82".to_vec(),
+            ))
+            .unwrap();
+        corpus.add(Testcase::new(b"\x00IHDR\x00".to_vec())).unwrap();
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            corpus,
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+
+        let mut stage = AutoTokensStage::with_limits(4, 16);
+        stage
+            .perform(&mut (), &mut (), &mut state, &mut NopEventManager {}, 0)
+            .unwrap();
+
+        let tokens = state.metadata().get::<Tokens>().unwrap();
+        assert_eq!(tokens.tokens(), &[b"IHDR".to_vec(), b"IEND".to_vec()]);
+
+        // Entries added after removing scanned ones still get scanned
+        state.corpus_mut().remove(0).unwrap();
+        state
+            .corpus_mut()
+            .add(Testcase::new(b"\x00tEXt\x00".to_vec()))
+            .unwrap();
+        stage
+            .perform(&mut (), &mut (), &mut state, &mut NopEventManager {}, 0)
+            .unwrap();
+        let tokens = state.metadata().get::<Tokens>().unwrap();
+        assert_eq!(
+            tokens.tokens(),
+            &[b"IHDR".to_vec(), b"IEND".to_vec(), b"tEXt".to_vec()]
+        );
+
+        // Once the dictionary is full, new entries are left alone
+        let mut stage = AutoTokensStage::with_limits(4, 3);
+        let idx = state
+            .corpus_mut()
+            .add(Testcase::new(b"\x00zTXt\x00".to_vec()))
+            .unwrap();
+        stage
+            .perform(&mut (), &mut (), &mut state, &mut NopEventManager {}, 0)
+            .unwrap();
+        assert_eq!(state.metadata().get::<Tokens>().unwrap().len(), 3);
+        assert!(!state
+            .corpus()
+            .get(idx)
+            .unwrap()
+            .borrow()
+            .has_metadata::<AutoTokensScannedMetadata>());
+    }
+}
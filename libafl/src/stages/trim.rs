@@ -0,0 +1,297 @@
+//! The trim stage shortens each corpus entry once, on its first scheduling, as done by AFL for imported seeds.
+//! Trailing bytes get removed as long as the map of the observer stays the same.
+
+use alloc::string::{String, ToString};
+use core::marker::PhantomData;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::HasLen,
+    corpus::{Corpus, CorpusScheduler},
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::{HasBytesVec, Input},
+    mark_feature_time,
+    observers::{MapObserver, ObserversTuple},
+    stages::Stage,
+    start_timer,
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata},
+    Error, HasCorpusScheduler,
+};
+
+#[cfg(feature = "introspection")]
+use crate::monitors::PerfFeature;
+
+/// A testcase metadata marking an entry as trimmed by the [`TrimStage`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrimmedMetadata {
+    /// The length of the input before trimming
+    pub original_len: usize,
+}
+
+crate::impl_serdeany!(TrimmedMetadata);
+
+/// A stage trimming each corpus entry at most once, replacing the stored input with the trimmed one.
+/// Starting from half of the input, it removes chunks of trailing bytes while the hash of the map stays the same,
+/// halving the chunk size each time a removal changes the map.
+/// Only runs exiting with [`ExitKind::Ok`] count, an entry that does not is kept as is.
+/// The scheduler gets notified of the replaced entries.
+#[derive(Clone, Debug)]
+pub struct TrimStage<CS, EM, I, O, OT, S, Z>
+where
+    CS: CorpusScheduler<I, S>,
+    I: Input + HasBytesVec + HasLen,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasExecutions,
+{
+    map_observer_name: String,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(CS, EM, I, O, OT, S, Z)>,
+}
+
+impl<CS, E, EM, I, O, OT, S, Z> Stage<E, EM, S, Z> for TrimStage<CS, EM, I, O, OT, S, Z>
+where
+    CS: CorpusScheduler<I, S>,
+    E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    I: Input + HasBytesVec + HasLen,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasExecutions,
+    Z: HasCorpusScheduler<CS, I, S>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        start_timer!(state);
+        let mut input = {
+            let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
+            if entry.has_metadata::<TrimmedMetadata>() {
+                return Ok(());
+            }
+            entry.load_input()?.clone()
+        };
+        mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
+        let original_len = input.bytes().len();
+
+        let original_hash = self.run_and_hash(fuzzer, executor, state, manager, &input)?;
+        // Without a normal run, there is nothing to preserve
+        let mut step = if original_hash.is_some() {
+            original_len / 2
+        } else {
+            0
+        };
+        while step > 0 {
+            let len = input.bytes().len();
+            if len <= step {
+                step /= 2;
+                continue;
+            }
+            let mut candidate = input.clone();
+            candidate.bytes_mut().truncate(len - step);
+            if self.run_and_hash(fuzzer, executor, state, manager, &candidate)? == original_hash {
+                input = candidate;
+            } else {
+                step /= 2;
+            }
+        }
+
+        let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
+        entry.add_metadata(TrimmedMetadata { original_len });
+        if input.bytes().len() < original_len {
+            let old = entry.clone();
+            entry.set_input(input);
+            // Update the cached len before the input may get dropped from memory
+            entry.cached_len()?;
+            entry.store_input()?;
+            drop(entry);
+            fuzzer.scheduler().on_replace(state, corpus_idx, &old)?;
+        }
+        Ok(())
+    }
+}
+
+impl<CS, EM, I, O, OT, S, Z> TrimStage<CS, EM, I, O, OT, S, Z>
+where
+    CS: CorpusScheduler<I, S>,
+    I: Input + HasBytesVec + HasLen,
+    O: MapObserver,
+    OT: ObserversTuple<I, S>,
+    S: HasClientPerfMonitor + HasCorpus<I> + HasExecutions,
+{
+    /// Create a new [`TrimStage`], preserving the map of the given observer.
+    #[must_use]
+    pub fn new(map_observer: &O) -> Self {
+        Self::from_name(map_observer.name())
+    }
+
+    /// Create a new [`TrimStage`] from the name of the map observer
+    #[must_use]
+    pub fn from_name(map_observer_name: &str) -> Self {
+        Self {
+            map_observer_name: map_observer_name.to_string(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Runs the input and returns the hash of the map, or `None` if the run did not exit with [`ExitKind::Ok`]
+    fn run_and_hash<E>(
+        &self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        input: &I,
+    ) -> Result<Option<u64>, Error>
+    where
+        E: Executor<EM, I, S, Z> + HasObservers<I, OT, S>,
+    {
+        start_timer!(state);
+        executor.observers_mut().pre_exec_all(state, input)?;
+        mark_feature_time!(state, PerfFeature::PreExecObservers);
+
+        start_timer!(state);
+        let exit_kind = executor.run_target(fuzzer, state, manager, input)?;
+        mark_feature_time!(state, PerfFeature::TargetExecution);
+
+        *state.executions_mut() += 1;
+
+        start_timer!(state);
+        executor
+            .observers_mut()
+            .post_exec_all(state, input, &exit_kind)?;
+        mark_feature_time!(state, PerfFeature::PostExecObservers);
+
+        if exit_kind != ExitKind::Ok {
+            return Ok(None);
+        }
+        Ok(Some(
+            executor
+                .observers()
+                .match_name::<O>(&self.map_observer_name)
+                .ok_or_else(|| Error::KeyNotFound("MapObserver not found".to_string()))?
+                .hash(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{
+            rands::StdRand,
+            tuples::{tuple_list, tuple_list_type},
+            AsMutSlice,
+        },
+        corpus::{Corpus, InMemoryCorpus, QueueCorpusScheduler, Testcase},
+        executors::{Executor, ExitKind, HasObservers},
+        inputs::{BytesInput, HasBytesVec},
+        observers::StdMapObserver,
+        stages::{trim::TrimmedMetadata, Stage, TrimStage},
+        state::{HasCorpus, HasExecutions, HasMetadata, StdState},
+        Error, StdFuzzer,
+    };
+
+    type Observers = tuple_list_type!(StdMapObserver<'static, u8>);
+
+    /// Sets a map entry for each byte of the `abc` prefix found in the input,
+    /// and crashes on inputs shorter than `min_len`
+    #[derive(Debug)]
+    struct PrefixExecutor {
+        observers: Observers,
+        min_len: usize,
+    }
+
+    impl<S, Z> Executor<(), BytesInput, S, Z> for PrefixExecutor {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut S,
+            _mgr: &mut (),
+            input: &BytesInput,
+        ) -> Result<ExitKind, Error> {
+            if input.bytes().len() < self.min_len {
+                return Ok(ExitKind::Crash);
+            }
+            let map = self.observers.0.as_mut_slice();
+            for (i, (a, b)) in input.bytes().iter().zip(b"abc").enumerate() {
+                if a != b {
+                    break;
+                }
+                map[i] = 1;
+            }
+            Ok(ExitKind::Ok)
+        }
+    }
+
+    impl<S> HasObservers<BytesInput, Observers, S> for PrefixExecutor {
+        fn observers(&self) -> &Observers {
+            &self.observers
+        }
+
+        fn observers_mut(&mut self) -> &mut Observers {
+            &mut self.observers
+        }
+    }
+
+    #[test]
+    fn test_trim_stage() {
+        let observer = StdMapObserver::new_owned("map", vec![0_u8; 4]);
+        let mut stage = TrimStage::new(&observer);
+        let mut executor = PrefixExecutor {
+            observers: tuple_list!(observer),
+            min_len: 0,
+        };
+        let mut fuzzer: StdFuzzer<_, _, BytesInput, _, Observers, _> =
+            StdFuzzer::new(QueueCorpusScheduler::new(), (), ());
+
+        let mut seed = b"abc".to_vec();
+        seed.resize(1000, b'x');
+        let mut corpus = InMemoryCorpus::new();
+        corpus.add(Testcase::new(BytesInput::new(seed))).unwrap();
+        let mut state = StdState::new(StdRand::with_seed(0), corpus, InMemoryCorpus::new(), ());
+
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut (), 0)
+            .unwrap();
+        {
+            let mut entry = state.corpus().get(0).unwrap().borrow_mut();
+            assert_eq!(entry.load_input().unwrap().bytes(), b"abc");
+            assert_eq!(
+                entry
+                    .metadata()
+                    .get::<TrimmedMetadata>()
+                    .unwrap()
+                    .original_len,
+                1000
+            );
+        }
+
+        // The next scheduling of the entry does not trim it again
+        let executions = *state.executions();
+        assert!(executions > 0);
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut (), 0)
+            .unwrap();
+        assert_eq!(*state.executions(), executions);
+
+        // Candidates that crash are not accepted
+        executor.min_len = 10;
+        let mut seed = b"abc".to_vec();
+        seed.resize(1000, b'x');
+        state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(seed)))
+            .unwrap();
+        stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut (), 1)
+            .unwrap();
+        let mut entry = state.corpus().get(1).unwrap().borrow_mut();
+        let len = entry.load_input().unwrap().bytes().len();
+        assert!((10..1000).contains(&len));
+    }
+}
@@ -0,0 +1,318 @@
+//! The [`WeightedStages`] split the time budget of each input across a tuple of stages, by weight,
+//! so that expensive stages, like tracing, do not starve the mutational stages.
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::{
+    bolts::{current_time, tuples::HasConstLen},
+    stages::Stage,
+    Error,
+};
+
+/// The deadline of a stage run by the [`WeightedStages`], handed to the stage with [`Stage::set_deadline`].
+/// Stages honouring it, like the mutational stages, stop their work once it passed.
+#[derive(Clone, Copy, Debug)]
+pub struct StageDeadline {
+    deadline: Duration,
+    clock: fn() -> Duration,
+}
+
+impl StageDeadline {
+    /// Creates a new [`StageDeadline`] at `deadline`, as time read from `clock`, usually [`current_time`]
+    #[must_use]
+    pub fn new(deadline: Duration, clock: fn() -> Duration) -> Self {
+        Self { deadline, clock }
+    }
+
+    /// The deadline, as time read from the clock
+    #[must_use]
+    pub fn deadline(&self) -> Duration {
+        self.deadline
+    }
+
+    /// Returns `true` once the deadline passed
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        (self.clock)() >= self.deadline
+    }
+}
+
+/// A tuple of stages, each of which gets a share of the remaining time budget, by weight
+pub trait WeightedStagesTuple<E, EM, S, Z> {
+    /// Performs all stages in this tuple, giving each one its share of the time left until `deadline`.
+    /// The `weights` are the weights of this stage and all following ones.
+    #[allow(clippy::too_many_arguments)]
+    fn perform_weighted(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+        weights: &[f64],
+        deadline: Duration,
+        clock: fn() -> Duration,
+    ) -> Result<(), Error>;
+}
+
+impl<E, EM, S, Z> WeightedStagesTuple<E, EM, S, Z> for () {
+    fn perform_weighted(
+        &mut self,
+        _: &mut Z,
+        _: &mut E,
+        _: &mut S,
+        _: &mut EM,
+        _: usize,
+        _: &[f64],
+        _: Duration,
+        _: fn() -> Duration,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<Head, Tail, E, EM, S, Z> WeightedStagesTuple<E, EM, S, Z> for (Head, Tail)
+where
+    Head: Stage<E, EM, S, Z>,
+    Tail: WeightedStagesTuple<E, EM, S, Z>,
+{
+    fn perform_weighted(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+        weights: &[f64],
+        deadline: Duration,
+        clock: fn() -> Duration,
+    ) -> Result<(), Error> {
+        // The share is taken from the time left, so the slack of stages finishing early goes to the next ones
+        let total: f64 = weights.iter().sum();
+        let weight = weights.first().copied().unwrap_or(0.0);
+        let share = if total > 0.0 { weight / total } else { 0.0 };
+        let now = clock();
+        let slice = deadline.saturating_sub(now).mul_f64(share);
+
+        self.0
+            .set_deadline(Some(StageDeadline::new(now + slice, clock)));
+        let ret = self.0.perform(fuzzer, executor, state, manager, corpus_idx);
+        // The stage may run on its own as well, without a deadline
+        self.0.set_deadline(None);
+        ret?;
+
+        self.1.perform_weighted(
+            fuzzer,
+            executor,
+            state,
+            manager,
+            corpus_idx,
+            weights.get(1..).unwrap_or(&[]),
+            deadline,
+            clock,
+        )
+    }
+}
+
+/// Runs a tuple of stages, splitting a time budget for each input across them, by weight.
+/// A stage honouring its [`StageDeadline`] stops once its share is used up,
+/// a stage finishing early leaves the rest of its share to the following stages.
+/// Each stage runs at least once per input, even if the budget is used up.
+/// The deadlines are held by the stages, not the state, so none of them outlives a restart.
+#[derive(Debug)]
+pub struct WeightedStages<ST> {
+    stages: ST,
+    weights: Vec<f64>,
+    budget: Duration,
+    clock: fn() -> Duration,
+}
+
+impl<E, EM, S, ST, Z> Stage<E, EM, S, Z> for WeightedStages<ST>
+where
+    ST: WeightedStagesTuple<E, EM, S, Z>,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        let deadline = (self.clock)() + self.budget;
+        self.stages.perform_weighted(
+            fuzzer,
+            executor,
+            state,
+            manager,
+            corpus_idx,
+            &self.weights,
+            deadline,
+            self.clock,
+        )
+    }
+}
+
+impl<ST> WeightedStages<ST>
+where
+    ST: HasConstLen,
+{
+    /// Creates new [`WeightedStages`], splitting the time `budget` of each input across the `stages`,
+    /// by their `weights`, one weight per stage.
+    pub fn new(stages: ST, weights: Vec<f64>, budget: Duration) -> Result<Self, Error> {
+        Self::check_weights(&weights)?;
+        Ok(Self {
+            stages,
+            weights,
+            budget,
+            clock: current_time,
+        })
+    }
+
+    /// The weights of the stages
+    #[must_use]
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    /// Sets the weights of the stages, one weight per stage
+    pub fn set_weights(&mut self, weights: Vec<f64>) -> Result<(), Error> {
+        Self::check_weights(&weights)?;
+        self.weights = weights;
+        Ok(())
+    }
+
+    /// The time budget of each input
+    #[must_use]
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    /// Sets the time budget of each input
+    pub fn set_budget(&mut self, budget: Duration) {
+        self.budget = budget;
+    }
+
+    /// Sets the clock the budget is measured with, [`current_time`] by default
+    pub fn set_clock(&mut self, clock: fn() -> Duration) {
+        self.clock = clock;
+    }
+
+    /// The wrapped stages
+    #[must_use]
+    pub fn inner(&self) -> &ST {
+        &self.stages
+    }
+
+    /// The wrapped stages (as mutable borrow)
+    pub fn inner_mut(&mut self) -> &mut ST {
+        &mut self.stages
+    }
+
+    fn check_weights(weights: &[f64]) -> Result<(), Error> {
+        if weights.len() != ST::LEN {
+            return Err(Error::IllegalArgument(format!(
+                "Got {} weights for {} stages",
+                weights.len(),
+                ST::LEN
+            )));
+        }
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err(Error::IllegalArgument(
+                "The weights of the stages must not be negative".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        sync::atomic::{AtomicU64, Ordering},
+        time::Duration,
+    };
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        inputs::BytesInput,
+        stages::{
+            weighted::{StageDeadline, WeightedStages},
+            Stage,
+        },
+        state::StdState,
+        Error,
+    };
+
+    type TestState =
+        StdState<InMemoryCorpus<BytesInput>, (), BytesInput, StdRand, InMemoryCorpus<BytesInput>>;
+
+    /// The time in millis, only moved by the [`BusyStage`]s
+    static FAKE_TIME: AtomicU64 = AtomicU64::new(0);
+
+    fn fake_clock() -> Duration {
+        Duration::from_millis(FAKE_TIME.load(Ordering::SeqCst))
+    }
+
+    /// Spends a millisecond of fake time per iteration until its deadline passed
+    #[derive(Debug, Default)]
+    struct BusyStage {
+        deadline: Option<StageDeadline>,
+        iterations: u64,
+    }
+
+    impl Stage<(), (), TestState, ()> for BusyStage {
+        fn perform(
+            &mut self,
+            _: &mut (),
+            _: &mut (),
+            _: &mut TestState,
+            _: &mut (),
+            _: usize,
+        ) -> Result<(), Error> {
+            let deadline = self.deadline.unwrap();
+            while !deadline.passed() {
+                FAKE_TIME.fetch_add(1, Ordering::SeqCst);
+                self.iterations += 1;
+            }
+            Ok(())
+        }
+
+        fn set_deadline(&mut self, deadline: Option<StageDeadline>) {
+            self.deadline = deadline;
+        }
+    }
+
+    #[test]
+    fn test_weighted_stages() {
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            (),
+        );
+
+        let mut stages = WeightedStages::new(
+            tuple_list!(BusyStage::default(), BusyStage::default()),
+            vec![1.0, 3.0],
+            Duration::from_millis(200),
+        )
+        .unwrap();
+        stages.set_clock(fake_clock);
+        stages
+            .perform(&mut (), &mut (), &mut state, &mut (), 0)
+            .unwrap();
+
+        // A quarter of the budget for the first stage, the rest for the second
+        let (first, (second, ())) = &stages.stages;
+        assert_eq!(first.iterations, 50);
+        assert_eq!(second.iterations, 150);
+        assert!(first.deadline.is_none());
+        assert!(second.deadline.is_none());
+
+        assert!(stages.set_weights(vec![1.0]).is_err());
+        assert!(stages.set_weights(vec![1.0, -1.0]).is_err());
+    }
+}
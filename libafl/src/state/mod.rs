@@ -1,5 +1,6 @@
 //! The fuzzer, and state are the core pieces of every good fuzzer
 
+use alloc::vec::Vec;
 use core::{fmt::Debug, marker::PhantomData, time::Duration};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 #[cfg(feature = "std")]
@@ -12,8 +13,9 @@ use crate::{
     bolts::{
         rands::Rand,
         serdeany::{SerdeAny, SerdeAnyMap},
+        HasLen,
     },
-    corpus::Corpus,
+    corpus::{Corpus, InitialEnergyMetadata},
     events::{Event, EventFirer, LogSeverity},
     feedbacks::FeedbackStatesTuple,
     fuzzer::{Evaluator, ExecuteInputResult},
@@ -23,9 +25,23 @@ use crate::{
     Error,
 };
 
+#[cfg(feature = "std")]
+use crate::libafl_log;
+
 /// The maximum size of a testcase
 pub const DEFAULT_MAX_SIZE: usize = 1_048_576;
 
+/// Computes the initial energy factor of a seed of `len` bytes, given the average length `avg_len` of all seeds
+pub type SeedEnergyWeight = fn(len: usize, avg_len: f64) -> f64;
+
+/// The default [`SeedEnergyWeight`], linear inverse to the seed size:
+/// a seed half as long as the average gets twice the energy, a seed twice as long gets half of it.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn inverse_size_weight(len: usize, avg_len: f64) -> f64 {
+    avg_len / len.max(1) as f64
+}
+
 /// The [`State`] of the fuzzer.
 /// Contains all important information about the current run.
 /// Will be used to restart the fuzzing process at any timme.
@@ -358,6 +374,53 @@ where
     }
 }
 
+/// Walks the non-empty files in a directory and its subdirectories, depth-first.
+/// Entries without readable metadata are skipped.
+#[cfg(feature = "std")]
+struct SeedFiles {
+    dirs: Vec<fs::ReadDir>,
+}
+
+#[cfg(feature = "std")]
+impl SeedFiles {
+    fn new(in_dir: &Path) -> Result<Self, Error> {
+        Ok(Self {
+            dirs: vec![fs::read_dir(in_dir)?],
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl Iterator for SeedFiles {
+    type Item = Result<PathBuf, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.dirs.last_mut()?.next() {
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => return Some(Err(err.into())),
+                None => {
+                    self.dirs.pop();
+                    continue;
+                }
+            };
+            let path = entry.path();
+            let attr = match fs::metadata(&path) {
+                Ok(attr) => attr,
+                Err(_) => continue,
+            };
+            if attr.is_file() && attr.len() > 0 {
+                return Some(Ok(path));
+            } else if attr.is_dir() {
+                match fs::read_dir(&path) {
+                    Ok(dir) => self.dirs.push(dir),
+                    Err(err) => return Some(Err(err.into())),
+                }
+            }
+        }
+    }
+}
+
 #[cfg(feature = "std")]
 impl<C, FT, I, R, SC> StdState<C, FT, I, R, SC>
 where
@@ -382,30 +445,17 @@ where
     where
         Z: Evaluator<E, EM, I, Self>,
     {
-        for entry in fs::read_dir(in_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            let attributes = fs::metadata(&path);
-
-            if attributes.is_err() {
-                continue;
-            }
-
-            let attr = attributes?;
-
-            if attr.is_file() && attr.len() > 0 {
-                println!("Loading file {:?} ...", &path);
-                let input = loader(fuzzer, self, &path)?;
-                if forced {
-                    let _ = fuzzer.add_input(self, executor, manager, input)?;
-                } else {
-                    let (res, _) = fuzzer.evaluate_input(self, executor, manager, input)?;
-                    if res == ExecuteInputResult::None {
-                        println!("File {:?} was not interesting, skipped.", &path);
-                    }
+        for path in SeedFiles::new(in_dir)? {
+            let path = path?;
+            println!("Loading file {:?} ...", &path);
+            let input = loader(fuzzer, self, &path)?;
+            if forced {
+                let _ = fuzzer.add_input(self, executor, manager, input)?;
+            } else {
+                let (res, _) = fuzzer.evaluate_input(self, executor, manager, input)?;
+                if res == ExecuteInputResult::None {
+                    println!("File {:?} was not interesting, skipped.", &path);
                 }
-            } else if attr.is_dir() {
-                self.load_from_directory(fuzzer, executor, manager, &path, forced, loader)?;
             }
         }
 
@@ -414,6 +464,8 @@ where
 
     /// Loads initial inputs from the passed-in `in_dirs`.
     /// If `forced` is true, will add all testcases, no matter what.
+    /// If a `parse` function is passed, it builds the inputs from the file contents,
+    /// else [`Input::from_file`] is used.
     fn load_initial_inputs_internal<E, EM, Z>(
         &mut self,
         fuzzer: &mut Z,
@@ -421,6 +473,7 @@ where
         manager: &mut EM,
         in_dirs: &[PathBuf],
         forced: bool,
+        parse: Option<&dyn Fn(&[u8]) -> Result<I, Error>>,
     ) -> Result<(), Error>
     where
         Z: Evaluator<E, EM, I, Self>,
@@ -433,7 +486,10 @@ where
                 manager,
                 in_dir,
                 forced,
-                &mut |_, _, path| I::from_file(&path),
+                &mut |_, _, path| match parse {
+                    Some(parse) => parse(&fs::read(path)?),
+                    None => I::from_file(&path),
+                },
             )?;
         }
         manager.fire(
@@ -461,7 +517,7 @@ where
         Z: Evaluator<E, EM, I, Self>,
         EM: EventFirer<I>,
     {
-        self.load_initial_inputs_internal(fuzzer, executor, manager, in_dirs, true)
+        self.load_initial_inputs_internal(fuzzer, executor, manager, in_dirs, true, None)
     }
 
     /// Loads initial inputs from the passed-in `in_dirs`.
@@ -476,7 +532,95 @@ where
         Z: Evaluator<E, EM, I, Self>,
         EM: EventFirer<I>,
     {
-        self.load_initial_inputs_internal(fuzzer, executor, manager, in_dirs, false)
+        self.load_initial_inputs_internal(fuzzer, executor, manager, in_dirs, false, None)
+    }
+
+    /// Loads initial inputs from the passed-in `in_dirs` in batches of `batch_size` files,
+    /// for corpora too large to be held in memory at once.
+    /// Only the inputs of the current batch are kept in memory, together with the cached entries
+    /// of the corpus, so use it with a [`crate::corpus::CachedOnDiskCorpus`] to bound the memory usage.
+    /// The files are evaluated in the same order as with [`StdState::load_initial_inputs`].
+    pub fn load_initial_inputs_batched<E, EM, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        manager: &mut EM,
+        in_dirs: &[PathBuf],
+        batch_size: usize,
+    ) -> Result<(), Error>
+    where
+        Z: Evaluator<E, EM, I, Self>,
+        EM: EventFirer<I>,
+    {
+        let batch_size = batch_size.max(1);
+        let mut loaded = 0;
+        for in_dir in in_dirs {
+            let mut files = SeedFiles::new(in_dir)?;
+            let mut batch = Vec::with_capacity(batch_size);
+            loop {
+                for path in files.by_ref().take(batch_size) {
+                    let path = path?;
+                    batch.push((I::from_file(&path)?, path));
+                }
+                if batch.is_empty() {
+                    break;
+                }
+
+                loaded += batch.len();
+                for (input, path) in batch.drain(..) {
+                    let (res, _) = fuzzer.evaluate_input(self, executor, manager, input)?;
+                    if res == ExecuteInputResult::None {
+                        libafl_log!(Info, "File {:?} was not interesting, skipped.", &path);
+                    }
+                }
+                libafl_log!(Info, "Loaded {} files ...", loaded);
+            }
+        }
+        manager.fire(
+            self,
+            Event::Log {
+                severity_level: LogSeverity::Debug,
+                message: format!("Loaded {} initial testcases.", self.corpus().count()), // get corpus count
+                phantom: PhantomData,
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Loads initial inputs from the passed-in `in_dirs`, building each input from the file contents with `parse`.
+    /// Use this to import seeds of a structured format into custom [`Input`] types.
+    pub fn load_initial_inputs_with_parser<E, EM, P, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        manager: &mut EM,
+        in_dirs: &[PathBuf],
+        parse: P,
+    ) -> Result<(), Error>
+    where
+        Z: Evaluator<E, EM, I, Self>,
+        EM: EventFirer<I>,
+        P: Fn(&[u8]) -> Result<I, Error>,
+    {
+        self.load_initial_inputs_internal(fuzzer, executor, manager, in_dirs, false, Some(&parse))
+    }
+
+    /// Loads all intial inputs, even if they are not considered `interesting`,
+    /// building each input from the file contents with `parse`.
+    pub fn load_initial_inputs_forced_with_parser<E, EM, P, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        manager: &mut EM,
+        in_dirs: &[PathBuf],
+        parse: P,
+    ) -> Result<(), Error>
+    where
+        Z: Evaluator<E, EM, I, Self>,
+        EM: EventFirer<I>,
+        P: Fn(&[u8]) -> Result<I, Error>,
+    {
+        self.load_initial_inputs_internal(fuzzer, executor, manager, in_dirs, true, Some(&parse))
     }
 }
 
@@ -579,6 +723,50 @@ where
     }
 }
 
+impl<C, FT, I, R, SC> StdState<C, FT, I, R, SC>
+where
+    C: Corpus<I>,
+    I: Input + HasLen,
+    R: Rand,
+    FT: FeedbackStatesTuple,
+    SC: Corpus<I>,
+{
+    /// Sets the initial energy of the corpus entries inversely proportional to their size,
+    /// so that small seeds get fuzzed more at first. Call it after loading the initial inputs.
+    /// The energy gets consumed by the [`crate::stages::PowerMutationalStage`].
+    pub fn weight_initial_inputs_by_size(&mut self) -> Result<(), Error> {
+        self.weight_initial_inputs_by_size_with(inverse_size_weight)
+    }
+
+    /// Sets the initial energy of the corpus entries using the `weight` function of their size.
+    /// Entries with an initial energy already set are left untouched.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn weight_initial_inputs_by_size_with(
+        &mut self,
+        weight: SeedEnergyWeight,
+    ) -> Result<(), Error> {
+        let count = self.corpus().count();
+        if count == 0 {
+            return Ok(());
+        }
+        let mut lens = Vec::with_capacity(count);
+        for idx in 0..count {
+            lens.push(self.corpus().get(idx)?.borrow_mut().cached_len()?);
+        }
+        let avg_len = lens.iter().sum::<usize>() as f64 / count as f64;
+
+        for (idx, len) in lens.into_iter().enumerate() {
+            let mut testcase = self.corpus().get(idx)?.borrow_mut();
+            if !testcase.has_metadata::<InitialEnergyMetadata>() {
+                testcase.add_metadata(InitialEnergyMetadata {
+                    factor: weight(len, avg_len),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(feature = "introspection")]
 impl<C, FT, I, R, SC> HasClientPerfMonitor for StdState<C, FT, I, R, SC>
 where
@@ -823,3 +1011,158 @@ pub mod pybind {
         Ok(())
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use std::fs;
+
+    use crate::{
+        corpus::{
+            CachedOnDiskCorpus, Corpus, InMemoryCorpus, InitialEnergyMetadata, RandCorpusScheduler,
+            Testcase,
+        },
+        events::NopEventManager,
+        executors::ExitKind,
+        feedback_not,
+        inputs::{BytesInput, HasBytesVec, Input},
+        state::{HasCorpus, HasMetadata},
+        test_utils::{test_executor, test_state, TestDir},
+        Error, StdFuzzer,
+    };
+
+    /// A structured input, stored as `key=value` text in the seed files
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+    struct KeyValueInput {
+        key: String,
+        value: u32,
+    }
+
+    impl Input for KeyValueInput {
+        fn generate_name(&self, _idx: usize) -> String {
+            format!("{}={}", self.key, self.value)
+        }
+    }
+
+    fn parse_key_value(bytes: &[u8]) -> Result<KeyValueInput, Error> {
+        let text = std::str::from_utf8(bytes).map_err(|e| Error::IllegalArgument(e.to_string()))?;
+        let (key, value) = text
+            .trim()
+            .split_once('=')
+            .ok_or_else(|| Error::IllegalArgument(format!("Not a key=value pair: {}", text)))?;
+        Ok(KeyValueInput {
+            key: key.to_string(),
+            value: value
+                .parse()
+                .map_err(|_| Error::IllegalArgument(format!("Not a number: {}", value)))?,
+        })
+    }
+
+    #[test]
+    fn test_load_initial_inputs_with_parser() {
+        let in_dir = TestDir::new("load_with_parser");
+        fs::write(in_dir.join("seed"), "answer=42\n").unwrap();
+
+        let mut state = test_state(InMemoryCorpus::<KeyValueInput>::new());
+        let mut mgr = NopEventManager {};
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), (), ());
+        let mut harness = |_input: &KeyValueInput| ExitKind::Ok;
+        let mut executor = test_executor(&mut harness, &mut fuzzer, &mut state, &mut mgr);
+
+        state
+            .load_initial_inputs_forced_with_parser(
+                &mut fuzzer,
+                &mut executor,
+                &mut mgr,
+                &[in_dir.to_path_buf()],
+                parse_key_value,
+            )
+            .unwrap();
+
+        assert_eq!(state.corpus().count(), 1);
+        let mut testcase = state.corpus().get(0).unwrap().borrow_mut();
+        assert_eq!(
+            testcase.load_input().unwrap(),
+            &KeyValueInput {
+                key: "answer".to_string(),
+                value: 42
+            }
+        );
+    }
+
+    #[test]
+    fn test_weight_initial_inputs_by_size() {
+        let mut corpus = InMemoryCorpus::new();
+        corpus
+            .add(Testcase::new(BytesInput::new(vec![0; 16])))
+            .unwrap();
+        corpus
+            .add(Testcase::new(BytesInput::new(vec![0; 4096])))
+            .unwrap();
+        let mut state = test_state(corpus);
+
+        state.weight_initial_inputs_by_size().unwrap();
+
+        let factor = |idx| {
+            state
+                .corpus()
+                .get(idx)
+                .unwrap()
+                .borrow()
+                .metadata()
+                .get::<InitialEnergyMetadata>()
+                .unwrap()
+                .factor
+        };
+        assert!(factor(0) > 1.0);
+        assert!(factor(1) < 1.0);
+        assert!(factor(0) > factor(1));
+    }
+
+    #[test]
+    fn test_load_initial_inputs_batched() {
+        let in_dir = TestDir::new("batched_seeds");
+        let corpus_dir = TestDir::new("batched_corpus");
+        fs::create_dir_all(in_dir.join("nested")).unwrap();
+        for i in 0..500_u32 {
+            let dir = if i % 5 == 0 {
+                in_dir.join("nested")
+            } else {
+                in_dir.to_path_buf()
+            };
+            fs::write(dir.join(format!("seed{}", i)), i.to_le_bytes()).unwrap();
+        }
+
+        let mut state =
+            test_state(CachedOnDiskCorpus::<BytesInput>::new(corpus_dir.to_path_buf(), 8).unwrap());
+        let mut mgr = NopEventManager {};
+        // Every seed is interesting
+        let mut fuzzer = StdFuzzer::new(RandCorpusScheduler::new(), feedback_not!(()), ());
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+        let mut executor = test_executor(&mut harness, &mut fuzzer, &mut state, &mut mgr);
+
+        state
+            .load_initial_inputs_batched(
+                &mut fuzzer,
+                &mut executor,
+                &mut mgr,
+                &[in_dir.to_path_buf()],
+                16,
+            )
+            .unwrap();
+
+        assert_eq!(state.corpus().count(), 500);
+        // The added inputs got written to disk, and loading them back keeps at most the cache in memory
+        assert_eq!(state.corpus().cached_count(), 0);
+        let mut seeds: Vec<u32> = (0..500)
+            .map(|idx| {
+                let mut testcase = state.corpus().get(idx).unwrap().borrow_mut();
+                let bytes = testcase.load_input().unwrap().bytes();
+                u32::from_le_bytes(bytes.try_into().unwrap())
+            })
+            .collect();
+        seeds.sort_unstable();
+        assert!(seeds.into_iter().eq(0..500));
+        assert_eq!(state.corpus().cached_count(), 8);
+    }
+}
@@ -0,0 +1,88 @@
+//! Fixtures shared by the unit tests
+
+use core::{
+    ops::Deref,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{
+    bolts::{rands::StdRand, tuples::tuple_list},
+    corpus::{Corpus, InMemoryCorpus},
+    events::{EventFirer, EventRestarter},
+    executors::{ExitKind, InProcessExecutor},
+    feedbacks::Feedback,
+    fuzzer::HasObjective,
+    inputs::Input,
+    state::{HasClientPerfMonitor, HasSolutions, StdState},
+};
+
+/// A [`StdState`] without feedback states, keeping the solutions in memory
+pub(crate) type TestState<C, I> = StdState<C, (), I, StdRand, InMemoryCorpus<I>>;
+
+/// Creates a [`TestState`] on `corpus`, with a fixed seed
+pub(crate) fn test_state<C, I>(corpus: C) -> TestState<C, I>
+where
+    C: Corpus<I>,
+    I: Input,
+{
+    StdState::new(StdRand::with_seed(0), corpus, InMemoryCorpus::new(), ())
+}
+
+/// Creates an [`InProcessExecutor`] running `harness`, without observers
+pub(crate) fn test_executor<'a, EM, H, I, OF, S, Z>(
+    harness: &'a mut H,
+    fuzzer: &mut Z,
+    state: &mut S,
+    mgr: &mut EM,
+) -> InProcessExecutor<'a, H, I, (), S>
+where
+    EM: EventFirer<I> + EventRestarter<S>,
+    H: FnMut(&I) -> ExitKind,
+    I: Input,
+    OF: Feedback<I, S>,
+    S: HasSolutions<I> + HasClientPerfMonitor,
+    Z: HasObjective<I, OF, S>,
+{
+    InProcessExecutor::new(harness, tuple_list!(), fuzzer, state, mgr).unwrap()
+}
+
+/// An empty directory, unique to this process and test, removed again on drop
+#[derive(Debug)]
+pub(crate) struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    /// Creates a new, empty [`TestDir`], named after `name`
+    pub(crate) fn new(name: &str) -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = env::temp_dir().join(format!(
+            "libafl_{}_{}_{}",
+            name,
+            process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
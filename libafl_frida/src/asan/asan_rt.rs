@@ -117,6 +117,7 @@ pub const ASAN_SAVE_REGISTER_COUNT: usize = 32;
 /// even if the target would not have crashed under normal conditions.
 /// this helps finding mem errors early.
 pub struct AsanRuntime {
+    enabled: bool,
     check_for_leaks_enabled: bool,
     current_report_impl: u64,
     allocator: Allocator,
@@ -144,6 +145,7 @@ pub struct AsanRuntime {
 impl Debug for AsanRuntime {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("AsanRuntime")
+            .field("enabled", &self.enabled)
             .field("stalked_addresses", &self.stalked_addresses)
             .field("options", &self.options)
             .field("module_map", &"<ModuleMap>")
@@ -261,6 +263,10 @@ impl FridaRuntime for AsanRuntime {
         &mut self,
         input: &I,
     ) -> Result<(), libafl::Error> {
+        if !self.enabled {
+            return Ok(());
+        }
+
         let target_bytes = input.target_bytes();
         let slice = target_bytes.as_slice();
 
@@ -272,13 +278,15 @@ impl FridaRuntime for AsanRuntime {
         &mut self,
         input: &I,
     ) -> Result<(), libafl::Error> {
-        if self.check_for_leaks_enabled {
-            self.check_for_leaks();
-        }
+        if self.enabled {
+            if self.check_for_leaks_enabled {
+                self.check_for_leaks();
+            }
 
-        let target_bytes = input.target_bytes();
-        let slice = target_bytes.as_slice();
-        self.poison(slice.as_ptr() as usize, slice.len());
+            let target_bytes = input.target_bytes();
+            let slice = target_bytes.as_slice();
+            self.poison(slice.as_ptr() as usize, slice.len());
+        }
         self.reset_allocations();
 
         Ok(())
@@ -290,6 +298,7 @@ impl AsanRuntime {
     #[must_use]
     pub fn new(options: FridaOptions) -> AsanRuntime {
         Self {
+            enabled: true,
             check_for_leaks_enabled: options.asan_detect_leaks(),
             current_report_impl: 0,
             allocator: Allocator::new(options.clone()),
@@ -315,6 +324,40 @@ impl AsanRuntime {
         }
     }
 
+    /// Whether the shadow checks are currently emitted for instrumented code
+    #[must_use]
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enable or disable the shadow checks and input (un)poisoning.
+    /// While disabled, the hooks of libc functions accessing memory fall through to the real functions,
+    /// only the allocator stays hooked, so that allocations made while enabled can still be freed.
+    /// Blocks that have already been instrumented keep their checks until they get re-compiled,
+    /// see [`crate::executor::FridaInProcessExecutor::set_asan_enabled`].
+    #[inline]
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Whether calls from the given address go to the hooks, i.e., come from an instrumented module
+    fn hooks_caller(&self, real_address: usize) -> bool {
+        !self.suppressed_addresses.contains(&real_address)
+            && self
+                .module_map
+                .as_ref()
+                .unwrap()
+                .find(real_address as u64)
+                .is_some()
+    }
+
+    /// Whether calls from the given address go to the hooks checking memory accesses,
+    /// which get skipped while the checks are disabled, see [`Self::set_enabled`]
+    fn checks_caller(&self, real_address: usize) -> bool {
+        self.enabled && self.hooks_caller(real_address)
+    }
+
     /// Reset all allocations so that they can be reused for new allocation requests.
     #[allow(clippy::unused_self)]
     pub fn reset_allocations(&mut self) {
@@ -513,7 +556,35 @@ impl AsanRuntime {
                         let mut invocation = Interceptor::current_invocation();
                         let this = &mut *(invocation.replacement_data().unwrap().0 as *mut AsanRuntime);
                         let real_address = this.real_address_for_stalked(invocation.return_addr());
-                        if !this.suppressed_addresses.contains(&real_address) && this.module_map.as_ref().unwrap().find(real_address as u64).is_some() {
+                        if this.hooks_caller(real_address) {
+                            this.[<hook_ $name>]($($param),*)
+                        } else {
+                            $name($($param),*)
+                        }
+                    }
+                    interceptor.replace(
+                        frida_gum::Module::find_export_by_name($lib, stringify!($name)).expect("Failed to find function"),
+                        NativePointer([<replacement_ $name>] as *mut c_void),
+                        NativePointer(self as *mut _ as *mut c_void)
+                    ).ok();
+                }
+            }
+        }
+
+        // Like `hook_func`, for functions that only get hooked to check memory accesses.
+        // Unlike the allocator, they fall through to the real function while the checks are disabled.
+        macro_rules! hook_access_func {
+            ($lib:expr, $name:ident, ($($param:ident : $param_type:ty),*), $return_type:ty) => {
+                paste::paste! {
+                    extern "C" {
+                        fn $name($($param: $param_type),*) -> $return_type;
+                    }
+                    #[allow(non_snake_case)]
+                    unsafe extern "C" fn [<replacement_ $name>]($($param: $param_type),*) -> $return_type {
+                        let mut invocation = Interceptor::current_invocation();
+                        let this = &mut *(invocation.replacement_data().unwrap().0 as *mut AsanRuntime);
+                        let real_address = this.real_address_for_stalked(invocation.return_addr());
+                        if this.checks_caller(real_address) {
                             this.[<hook_ $name>]($($param),*)
                         } else {
                             $name($($param),*)
@@ -723,64 +794,64 @@ impl AsanRuntime {
         hook_func!(None, munmap, (addr: *const c_void, length: usize), i32);
 
         // Hook libc functions which may access allocated memory
-        hook_func!(
+        hook_access_func!(
             None,
             write,
             (fd: i32, buf: *const c_void, count: usize),
             usize
         );
-        hook_func!(None, read, (fd: i32, buf: *mut c_void, count: usize), usize);
-        hook_func!(
+        hook_access_func!(None, read, (fd: i32, buf: *mut c_void, count: usize), usize);
+        hook_access_func!(
             None,
             fgets,
             (s: *mut c_void, size: u32, stream: *mut c_void),
             *mut c_void
         );
-        hook_func!(
+        hook_access_func!(
             None,
             memcmp,
             (s1: *const c_void, s2: *const c_void, n: usize),
             i32
         );
-        hook_func!(
+        hook_access_func!(
             None,
             memcpy,
             (dest: *mut c_void, src: *const c_void, n: usize),
             *mut c_void
         );
         #[cfg(not(target_vendor = "apple"))]
-        hook_func!(
+        hook_access_func!(
             None,
             mempcpy,
             (dest: *mut c_void, src: *const c_void, n: usize),
             *mut c_void
         );
-        hook_func!(
+        hook_access_func!(
             None,
             memmove,
             (dest: *mut c_void, src: *const c_void, n: usize),
             *mut c_void
         );
-        hook_func!(
+        hook_access_func!(
             None,
             memset,
             (s: *mut c_void, c: i32, n: usize),
             *mut c_void
         );
-        hook_func!(
+        hook_access_func!(
             None,
             memchr,
             (s: *mut c_void, c: i32, n: usize),
             *mut c_void
         );
         #[cfg(not(target_vendor = "apple"))]
-        hook_func!(
+        hook_access_func!(
             None,
             memrchr,
             (s: *mut c_void, c: i32, n: usize),
             *mut c_void
         );
-        hook_func!(
+        hook_access_func!(
             None,
             memmem,
             (
@@ -792,87 +863,87 @@ impl AsanRuntime {
             *mut c_void
         );
         #[cfg(not(target_os = "android"))]
-        hook_func!(None, bzero, (s: *mut c_void, n: usize), ());
+        hook_access_func!(None, bzero, (s: *mut c_void, n: usize), ());
         #[cfg(not(any(target_os = "android", target_vendor = "apple")))]
-        hook_func!(None, explicit_bzero, (s: *mut c_void, n: usize), ());
+        hook_access_func!(None, explicit_bzero, (s: *mut c_void, n: usize), ());
         #[cfg(not(target_os = "android"))]
-        hook_func!(
+        hook_access_func!(
             None,
             bcmp,
             (s1: *const c_void, s2: *const c_void, n: usize),
             i32
         );
-        hook_func!(None, strchr, (s: *mut c_char, c: i32), *mut c_char);
-        hook_func!(None, strrchr, (s: *mut c_char, c: i32), *mut c_char);
-        hook_func!(
+        hook_access_func!(None, strchr, (s: *mut c_char, c: i32), *mut c_char);
+        hook_access_func!(None, strrchr, (s: *mut c_char, c: i32), *mut c_char);
+        hook_access_func!(
             None,
             strcasecmp,
             (s1: *const c_char, s2: *const c_char),
             i32
         );
-        hook_func!(
+        hook_access_func!(
             None,
             strncasecmp,
             (s1: *const c_char, s2: *const c_char, n: usize),
             i32
         );
-        hook_func!(
+        hook_access_func!(
             None,
             strcat,
             (dest: *mut c_char, src: *const c_char),
             *mut c_char
         );
-        hook_func!(None, strcmp, (s1: *const c_char, s2: *const c_char), i32);
-        hook_func!(
+        hook_access_func!(None, strcmp, (s1: *const c_char, s2: *const c_char), i32);
+        hook_access_func!(
             None,
             strncmp,
             (s1: *const c_char, s2: *const c_char, n: usize),
             i32
         );
-        hook_func!(
+        hook_access_func!(
             None,
             strcpy,
             (dest: *mut c_char, src: *const c_char),
             *mut c_char
         );
-        hook_func!(
+        hook_access_func!(
             None,
             strncpy,
             (dest: *mut c_char, src: *const c_char, n: usize),
             *mut c_char
         );
-        hook_func!(
+        hook_access_func!(
             None,
             stpcpy,
             (dest: *mut c_char, src: *const c_char),
             *mut c_char
         );
-        hook_func!(None, strdup, (s: *const c_char), *mut c_char);
-        hook_func!(None, strlen, (s: *const c_char), usize);
-        hook_func!(None, strnlen, (s: *const c_char, n: usize), usize);
-        hook_func!(
+        hook_access_func!(None, strdup, (s: *const c_char), *mut c_char);
+        hook_access_func!(None, strlen, (s: *const c_char), usize);
+        hook_access_func!(None, strnlen, (s: *const c_char, n: usize), usize);
+        hook_access_func!(
             None,
             strstr,
             (haystack: *const c_char, needle: *const c_char),
             *mut c_char
         );
-        hook_func!(
+        hook_access_func!(
             None,
             strcasestr,
             (haystack: *const c_char, needle: *const c_char),
             *mut c_char
         );
-        hook_func!(None, atoi, (nptr: *const c_char), i32);
-        hook_func!(None, atol, (nptr: *const c_char), i32);
-        hook_func!(None, atoll, (nptr: *const c_char), i64);
-        hook_func!(None, wcslen, (s: *const wchar_t), usize);
-        hook_func!(
+        hook_access_func!(None, atoi, (nptr: *const c_char), i32);
+        hook_access_func!(None, atol, (nptr: *const c_char), i32);
+        hook_access_func!(None, atoll, (nptr: *const c_char), i64);
+        hook_access_func!(None, wcslen, (s: *const wchar_t), usize);
+        hook_access_func!(
             None,
             wcscpy,
             (dest: *mut wchar_t, src: *const wchar_t),
             *mut wchar_t
         );
-        hook_func!(None, wcscmp, (s1: *const wchar_t, s2: *const wchar_t), i32);
+        hook_access_func!(None, wcscmp, (s1: *const wchar_t, s2: *const wchar_t), i32);
     }
 
     #[cfg(target_arch = "x86_64")]
@@ -2636,3 +2707,31 @@ impl AsanRuntime {
         ));
     }
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use frida_gum::{Gum, Module, ModuleMap};
+
+    use crate::{asan::asan_rt::AsanRuntime, FridaOptions};
+
+    #[test]
+    fn test_disabled_asan_skips_access_hooks() {
+        let _gum = Gum::obtain();
+        let mut runtime = AsanRuntime::new(FridaOptions::default());
+        runtime.module_map = Some(ModuleMap::new_from_names(&["libc.so.6"]));
+        let caller = Module::find_export_by_name(Some("libc.so.6"), "memcpy")
+            .unwrap()
+            .0 as usize;
+
+        assert!(runtime.hooks_caller(caller));
+        assert!(runtime.checks_caller(caller));
+
+        // The allocator stays hooked, the accesses go to the real functions
+        runtime.set_enabled(false);
+        assert!(runtime.hooks_caller(caller));
+        assert!(!runtime.checks_caller(caller));
+
+        runtime.set_enabled(true);
+        assert!(runtime.checks_caller(caller));
+    }
+}
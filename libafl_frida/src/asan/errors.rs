@@ -9,7 +9,7 @@ use frida_gum::interceptor::Interceptor;
 use frida_gum::ModuleDetails;
 use libafl::{
     bolts::{ownedref::OwnedPtr, tuples::Named},
-    corpus::Testcase,
+    corpus::{SolutionReport, Testcase},
     events::EventFirer,
     executors::ExitKind,
     feedbacks::Feedback,
@@ -88,6 +88,23 @@ impl AsanError {
             AsanError::BadFuncArgWrite(_) => "function arg resulting in bad write",
         }
     }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        match self {
+            AsanError::OobRead(error)
+            | AsanError::OobWrite(error)
+            | AsanError::ReadAfterFree(error)
+            | AsanError::WriteAfterFree(error) => Some(&error.backtrace),
+            AsanError::DoubleFree((_, _, backtrace))
+            | AsanError::UnallocatedFree((_, backtrace))
+            | AsanError::Unknown((_, _, _, backtrace))
+            | AsanError::StackOobRead((_, _, _, backtrace))
+            | AsanError::StackOobWrite((_, _, _, backtrace))
+            | AsanError::BadFuncArgRead((_, _, _, _, backtrace))
+            | AsanError::BadFuncArgWrite((_, _, _, _, backtrace)) => Some(backtrace),
+            AsanError::Leak(_) => None,
+        }
+    }
 }
 
 /// A struct holding errors that occurred during frida address sanitizer runs
@@ -125,6 +142,11 @@ impl AsanErrors {
         self.errors.is_empty()
     }
 
+    /// Gets the descriptions of the errors, e.g. `heap out-of-bounds read`
+    pub fn descriptions(&self) -> impl Iterator<Item = &str> {
+        self.errors.iter().map(AsanError::description)
+    }
+
     /// Get a mutable reference to the global [`struct@AsanErrors`] object
     #[must_use]
     pub fn get_mut<'a>() -> &'a mut Self {
@@ -543,6 +565,24 @@ impl AsanErrors {
 /// static field for `AsanErrors` for a run
 pub static mut ASAN_ERRORS: Option<AsanErrors> = None;
 
+impl SolutionReport for AsanErrors {
+    /// The descriptions of all errors, in the order they occurred
+    fn description(&self) -> String {
+        self.descriptions().collect::<Vec<_>>().join(", ")
+    }
+
+    /// The hash of the backtrace of the first error, computed as the `BacktraceObserver` does
+    fn backtrace_hash(&self) -> Option<u64> {
+        let backtrace = self.errors.iter().find_map(AsanError::backtrace)?;
+        Some(
+            backtrace
+                .frames()
+                .iter()
+                .fold(0, |hash, frame| hash ^ frame.ip() as u64),
+        )
+    }
+}
+
 /// An observer for frida address sanitizer `AsanError`s for a frida executor run
 #[derive(Debug, Serialize, Deserialize)]
 #[allow(clippy::unsafe_derive_deserialize)]
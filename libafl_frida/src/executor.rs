@@ -15,7 +15,7 @@ use libafl::{
 };
 
 #[cfg(unix)]
-use crate::asan::errors::ASAN_ERRORS;
+use crate::asan::{asan_rt::AsanRuntime, errors::ASAN_ERRORS};
 
 #[cfg(windows)]
 use libafl::executors::inprocess::{HasInProcessHandlers, InProcessHandlers};
@@ -84,9 +84,16 @@ where
         }
         #[cfg(unix)]
         if unsafe { ASAN_ERRORS.is_some() && !ASAN_ERRORS.as_ref().unwrap().is_empty() } {
-            println!("Crashing target as it had ASAN errors");
-            unsafe {
-                libc::raise(libc::SIGABRT);
+            if self.asan_enabled() {
+                println!("Crashing target as it had ASAN errors");
+                unsafe {
+                    libc::raise(libc::SIGABRT);
+                }
+            } else {
+                // Errors reported by the hooks while ASAN is disabled are dropped
+                unsafe {
+                    ASAN_ERRORS.as_mut().unwrap().clear();
+                }
             }
         }
         self.helper.post_exec(input)?;
@@ -156,6 +163,34 @@ where
             _phantom: PhantomData,
         }
     }
+
+    /// Returns `true` if the [`AsanRuntime`] is present and currently checking memory accesses.
+    #[cfg(unix)]
+    #[must_use]
+    pub fn asan_enabled(&self) -> bool {
+        self.helper
+            .runtime::<AsanRuntime>()
+            .map_or(false, AsanRuntime::enabled)
+    }
+
+    /// Enable or disable the ASAN shadow checks for the following runs.
+    /// Disabling trades memory error detection for speed, e.g., during a fast initial coverage sweep.
+    /// Stalker caches the instrumented blocks, so toggling drops them and the target gets re-instrumented
+    /// (with or without checks) on the next run.
+    /// Does nothing if no [`AsanRuntime`] is in use.
+    #[cfg(unix)]
+    pub fn set_asan_enabled(&mut self, enabled: bool) {
+        if let Some(rt) = self.helper.runtime_mut::<AsanRuntime>() {
+            if rt.enabled() == enabled {
+                return;
+            }
+            rt.set_enabled(enabled);
+            if self.followed {
+                self.stalker.unfollow_me();
+                self.followed = false;
+            }
+        }
+    }
 }
 
 #[cfg(windows)]
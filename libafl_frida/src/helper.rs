@@ -283,10 +283,13 @@ where
                         }
 
                         #[cfg(unix)]
-                        let res = if let Some(rt) = helper.runtime::<AsanRuntime>() {
-                            rt.asan_is_interesting_instruction(&helper.capstone, address, instr)
-                        } else {
-                            None
+                        let res = match helper.runtime::<AsanRuntime>() {
+                            Some(rt) if rt.enabled() => rt.asan_is_interesting_instruction(
+                                &helper.capstone,
+                                address,
+                                instr,
+                            ),
+                            _ => None,
                         };
 
                         #[cfg(all(target_arch = "x86_64", unix))]
@@ -1,12 +1,22 @@
 use hashbrown::HashMap;
 use libafl::{executors::ExitKind, inputs::Input, observers::ObserversTuple, state::HasMetadata};
+use libafl_targets::CMPLOG_RTN_LEN;
 pub use libafl_targets::{
-    cmplog::__libafl_targets_cmplog_instructions, CmpLogObserver, CMPLOG_MAP, CMPLOG_MAP_W,
+    cmplog::{__libafl_targets_cmplog_instructions, __libafl_targets_cmplog_routines},
+    CmpLogObserver, CMPLOG_MAP, CMPLOG_MAP_W,
 };
 use serde::{Deserialize, Serialize};
 
+#[cfg(cpu_target = "aarch64")]
+use crate::aarch64::Regs;
+#[cfg(cpu_target = "arm")]
+use crate::arm::Regs;
+#[cfg(cpu_target = "i386")]
+use crate::i386::Regs;
+#[cfg(cpu_target = "x86_64")]
+use crate::x86_64::Regs;
 use crate::{
-    emu::Emulator,
+    emu::{check_guest_range, Emulator, GuestAddr, MmapPerms},
     executor::QemuExecutor,
     helper::{hash_me, QemuHelper, QemuHelperTuple, QemuInstrumentationFilter},
 };
@@ -129,6 +139,71 @@ where
     }
 }
 
+/// Logs the buffers compared by routines such as `memcmp`, `strcmp` or `strncmp` into the routine part of the [`CMPLOG_MAP`],
+/// so that input-to-state mutations can solve string comparisons the integer cmplog misses.
+#[derive(Debug)]
+pub struct QemuCmpLogRoutinesHelper {
+    routines: Vec<GuestAddr>,
+}
+
+impl QemuCmpLogRoutinesHelper {
+    /// Creates a new [`QemuCmpLogRoutinesHelper`], hooking the entry of the routines at the given guest addresses
+    #[must_use]
+    pub fn new(routines: &[GuestAddr]) -> Self {
+        Self {
+            routines: routines.to_vec(),
+        }
+    }
+
+    /// The guest addresses of the hooked routines
+    #[must_use]
+    pub fn routines(&self) -> &[GuestAddr] {
+        &self.routines
+    }
+}
+
+impl<I, S> QemuHelper<I, S> for QemuCmpLogRoutinesHelper
+where
+    I: Input,
+    S: HasMetadata,
+{
+    fn init<'a, H, OT, QT>(&self, executor: &QemuExecutor<'a, H, I, OT, QT, S>)
+    where
+        H: FnMut(&I) -> ExitKind,
+        OT: ObserversTuple<I, S>,
+        QT: QemuHelperTuple<I, S>,
+    {
+        for &addr in &self.routines {
+            let id = hash_me(addr.into()) & (CMPLOG_MAP_W as u64 - 1);
+            executor.emulator().set_hook(addr, trace_rtn_cmplog, id);
+        }
+    }
+
+    fn pre_exec(&mut self, emulator: &Emulator, _input: &I) {
+        unsafe { refresh_routine_maps(emulator) };
+    }
+}
+
+/// The guest mappings, as `(start, end, perms)`, the routine hooks check their reads against.
+/// Refreshed before each run, and whenever a read is not covered, as the guest may have mapped memory since.
+static mut ROUTINE_MAPS: Vec<(GuestAddr, GuestAddr, MmapPerms)> = Vec::new();
+
+unsafe fn refresh_routine_maps(emu: &Emulator) {
+    ROUTINE_MAPS.clear();
+    ROUTINE_MAPS.extend(emu.mappings().map(|m| (m.start(), m.end(), m.flags())));
+    ROUTINE_MAPS.sort_unstable_by_key(|(start, _, _)| *start);
+}
+
+/// Reads guest memory into `buf`, if the [`ROUTINE_MAPS`] cover it as readable
+unsafe fn read_routine_mem(emu: &Emulator, addr: GuestAddr, buf: &mut [u8]) -> Option<()> {
+    if check_guest_range(ROUTINE_MAPS.iter().copied(), addr, buf.len(), false).is_err() {
+        refresh_routine_maps(emu);
+        check_guest_range(ROUTINE_MAPS.iter().copied(), addr, buf.len(), false).ok()?;
+    }
+    emu.read_mem(addr, buf);
+    Some(())
+}
+
 pub fn gen_unique_cmp_ids<I, QT, S>(
     _emulator: &Emulator,
     helpers: &mut QT,
@@ -204,3 +279,46 @@ pub extern "C" fn trace_cmp8_cmplog(id: u64, v0: u64, v1: u64) {
         __libafl_targets_cmplog_instructions(id as usize, 8, v0, v1);
     }
 }
+
+/// The first two arguments of the routine about to run, following the calling convention of the target
+#[cfg(any(cpu_target = "x86_64", cpu_target = "aarch64", cpu_target = "arm"))]
+fn routine_args(emu: &Emulator) -> Option<(GuestAddr, GuestAddr)> {
+    #[cfg(cpu_target = "x86_64")]
+    let regs = (Regs::Rdi, Regs::Rsi);
+    #[cfg(cpu_target = "aarch64")]
+    let regs = (Regs::X0, Regs::X1);
+    #[cfg(cpu_target = "arm")]
+    let regs = (Regs::R0, Regs::R1);
+    Some((emu.read_reg(regs.0).ok()?, emu.read_reg(regs.1).ok()?))
+}
+
+/// The first two arguments of the routine about to run, on the stack above the return address
+#[cfg(cpu_target = "i386")]
+fn routine_args(emu: &Emulator) -> Option<(GuestAddr, GuestAddr)> {
+    let sp: GuestAddr = emu.read_reg(Regs::Esp).ok()?;
+    let mut args = [0; 8];
+    unsafe { read_routine_mem(emu, sp + 4, &mut args)? };
+    Some((
+        GuestAddr::from_le_bytes(args[..4].try_into().unwrap()),
+        GuestAddr::from_le_bytes(args[4..].try_into().unwrap()),
+    ))
+}
+
+/// Hooks the entry of a comparison routine, logging the compared buffers, see [`QemuCmpLogRoutinesHelper`].
+/// Skips the call if either buffer is not readable for [`CMPLOG_RTN_LEN`] bytes.
+pub extern "C" fn trace_rtn_cmplog(id: u64) {
+    let emu = Emulator::new_empty();
+    let (a0, a1) = match routine_args(&emu) {
+        Some(args) => args,
+        None => return,
+    };
+    let mut v0 = [0_u8; CMPLOG_RTN_LEN];
+    let mut v1 = [0_u8; CMPLOG_RTN_LEN];
+    unsafe {
+        if read_routine_mem(&emu, a0, &mut v0).is_some()
+            && read_routine_mem(&emu, a1, &mut v1).is_some()
+        {
+            __libafl_targets_cmplog_routines(id as usize, v0.as_ptr(), v1.as_ptr());
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use libafl::{
+    bolts::tuples::Named,
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::Input,
+    observers::{Observer, ObserversTuple},
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    emu::{Emulator, GuestUsize},
+    GuestAddr, Regs,
+};
+
+/// The default amount of stack bytes dumped on crash
+pub const DEFAULT_STACK_DUMP_SIZE: usize = 256;
+
+/// The guest context at the time of a crash, a mini core dump attached to the crashing testcase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QemuCrashContextMetadata {
+    /// All the registers, indexed by their number in [`Regs`]
+    pub regs: Vec<GuestUsize>,
+    /// The program counter
+    pub pc: GuestAddr,
+    /// The stack pointer
+    pub sp: GuestAddr,
+    /// The bytes on the stack, starting from the stack pointer
+    pub stack: Vec<u8>,
+}
+
+libafl::impl_serdeany!(QemuCrashContextMetadata);
+
+impl QemuCrashContextMetadata {
+    /// Capture the current guest context, dumping up to `stack_dump_size` bytes from the stack
+    #[must_use]
+    pub fn capture(emulator: &Emulator, stack_dump_size: usize) -> Self {
+        let regs = (0..emulator.num_regs())
+            .map(|reg| emulator.read_reg(reg).unwrap_or_default())
+            .collect();
+        let pc: GuestAddr = emulator.read_reg(Regs::Pc).unwrap_or_default();
+        let sp: GuestAddr = emulator.read_reg(Regs::Sp).unwrap_or_default();
+
+        // Only dump the readable part of the stack, the crash may be caused by a bogus stack pointer
+        let mut stack = vec![];
+        if let Some(map) = emulator
+            .mappings()
+            .find(|map| map.start() <= sp && sp < map.end() && map.flags().is_r())
+        {
+            let len = stack_dump_size.min((map.end() - sp) as usize);
+            stack.resize(len, 0);
+            unsafe {
+                emulator.read_mem(sp, &mut stack);
+            }
+        }
+
+        Self {
+            regs,
+            pc,
+            sp,
+            stack,
+        }
+    }
+}
+
+/// An observer capturing the guest registers and stack when the target crashes.
+/// The capture happens in the crash handler of the executor, while the guest is still in the faulting state.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QemuCrashContextObserver {
+    name: String,
+    stack_dump_size: usize,
+    context: Option<QemuCrashContextMetadata>,
+}
+
+impl<I, S> Observer<I, S> for QemuCrashContextObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.context = None;
+        Ok(())
+    }
+
+    fn post_exec(&mut self, _state: &mut S, _input: &I, exit_kind: &ExitKind) -> Result<(), Error> {
+        if *exit_kind == ExitKind::Crash {
+            let emulator = Emulator::new_empty();
+            self.context = Some(QemuCrashContextMetadata::capture(
+                &emulator,
+                self.stack_dump_size,
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Named for QemuCrashContextObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl QemuCrashContextObserver {
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self::with_stack_dump_size(name, DEFAULT_STACK_DUMP_SIZE)
+    }
+
+    #[must_use]
+    pub fn with_stack_dump_size(name: &str, stack_dump_size: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            stack_dump_size,
+            context: None,
+        }
+    }
+
+    /// The guest context captured in the last run, if it crashed
+    #[must_use]
+    pub fn context(&self) -> Option<&QemuCrashContextMetadata> {
+        self.context.as_ref()
+    }
+}
+
+/// A feedback attaching the [`QemuCrashContextMetadata`] captured by a [`QemuCrashContextObserver`]
+/// to the crashing testcase. Combine it with a `CrashFeedback` in the objective, e.g. using `feedback_or!`,
+/// and store the solutions in an `OnDiskCorpus` saving the metadata to get the context in the JSON file.
+#[derive(Debug)]
+pub struct QemuCrashContextFeedback {
+    observer_name: String,
+    context: Option<QemuCrashContextMetadata>,
+}
+
+impl<I, S> Feedback<I, S> for QemuCrashContextFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let observer = observers
+            .match_name::<QemuCrashContextObserver>(&self.observer_name)
+            .expect("A QemuCrashContextFeedback needs a QemuCrashContextObserver");
+        self.context = observer.context().cloned();
+        Ok(self.context.is_some())
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if let Some(context) = self.context.take() {
+            testcase.add_metadata(context);
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.context = None;
+        Ok(())
+    }
+}
+
+impl Named for QemuCrashContextFeedback {
+    fn name(&self) -> &str {
+        "QemuCrashContextFeedback"
+    }
+}
+
+impl QemuCrashContextFeedback {
+    #[must_use]
+    pub fn new(observer: &QemuCrashContextObserver) -> Self {
+        Self {
+            observer_name: observer.name().to_string(),
+            context: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::Feedback,
+        inputs::BytesInput,
+        observers::Observer,
+        state::{HasMetadata, StdState},
+    };
+
+    use crate::crash::{
+        QemuCrashContextFeedback, QemuCrashContextMetadata, QemuCrashContextObserver,
+    };
+
+    #[test]
+    fn test_crash_context_feedback() {
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            (),
+        );
+        let mut mgr = NopEventManager {};
+        let input = BytesInput::new(vec![]);
+
+        let mut observer = QemuCrashContextObserver::with_stack_dump_size("crash_context", 4);
+        let mut feedback = QemuCrashContextFeedback::new(&observer);
+
+        // A run that did not crash has no context
+        Observer::<BytesInput, _>::pre_exec(&mut observer, &mut state, &input).unwrap();
+        Observer::<BytesInput, _>::post_exec(&mut observer, &mut state, &input, &ExitKind::Ok)
+            .unwrap();
+        assert!(observer.context().is_none());
+        let mut observers = tuple_list!(observer);
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+
+        // The context captured on a crash gets attached to the testcase
+        let context = QemuCrashContextMetadata {
+            regs: vec![1, 2, 3],
+            pc: 0x1000,
+            sp: 0x2000,
+            stack: vec![0xde, 0xad, 0xbe, 0xef],
+        };
+        observers.0.context = Some(context.clone());
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Crash)
+            .unwrap());
+        let mut testcase = Testcase::new(input.clone());
+        feedback.append_metadata(&mut state, &mut testcase).unwrap();
+        let attached = testcase
+            .metadata()
+            .get::<QemuCrashContextMetadata>()
+            .unwrap();
+        assert_eq!(attached.pc, context.pc);
+        assert_eq!(attached.stack, context.stack);
+
+        // The next run resets the context
+        Observer::<BytesInput, _>::pre_exec(&mut observers.0, &mut state, &input).unwrap();
+        assert!(observers.0.context().is_none());
+    }
+}
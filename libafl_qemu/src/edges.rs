@@ -1,7 +1,8 @@
 use hashbrown::{hash_map::Entry, HashMap};
 use libafl::{executors::ExitKind, inputs::Input, observers::ObserversTuple, state::HasMetadata};
 pub use libafl_targets::{
-    edges_max_num, EDGES_MAP, EDGES_MAP_PTR, EDGES_MAP_PTR_SIZE, EDGES_MAP_SIZE, MAX_EDGES_NUM,
+    edges_map_index, edges_max_num, EDGES_MAP, EDGES_MAP_PTR, EDGES_MAP_PTR_SIZE, EDGES_MAP_SIZE,
+    MAX_EDGES_NUM,
 };
 use serde::{Deserialize, Serialize};
 use std::{cell::UnsafeCell, cmp::max};
@@ -126,6 +127,54 @@ where
     }
 }
 
+/// Block coverage: sets a single entry of the edges map per executed block, hashed by its `pc`.
+/// Cheaper than edge coverage, at the cost of ignoring which block a block was reached from.
+#[derive(Debug)]
+pub struct QemuBlockCoverageHelper {
+    filter: QemuInstrumentationFilter,
+}
+
+impl QemuBlockCoverageHelper {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            filter: QemuInstrumentationFilter::None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_instrumentation_filter(filter: QemuInstrumentationFilter) -> Self {
+        Self { filter }
+    }
+
+    #[must_use]
+    pub fn must_instrument(&self, addr: u64) -> bool {
+        self.filter.allowed(addr)
+    }
+}
+
+impl Default for QemuBlockCoverageHelper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, S> QemuHelper<I, S> for QemuBlockCoverageHelper
+where
+    I: Input,
+    S: HasMetadata,
+{
+    fn init<'a, H, OT, QT>(&self, executor: &QemuExecutor<'a, H, I, OT, QT, S>)
+    where
+        H: FnMut(&I) -> ExitKind,
+        OT: ObserversTuple<I, S>,
+        QT: QemuHelperTuple<I, S>,
+    {
+        executor.hook_block_generation(gen_filtered_block_ids::<I, QT, S>);
+        executor.emulator().set_exec_block_hook(trace_block_single);
+    }
+}
+
 thread_local!(static PREV_LOC : UnsafeCell<u64> = UnsafeCell::new(0));
 
 pub fn gen_unique_edge_ids<I, QT, S>(
@@ -156,7 +205,7 @@ where
     match meta.map.entry((src, dest)) {
         Entry::Occupied(e) => {
             let id = *e.get();
-            let nxt = (id as usize + 1) & (EDGES_MAP_SIZE - 1);
+            let nxt = edges_map_index(id + 1, EDGES_MAP_SIZE);
             unsafe {
                 MAX_EDGES_NUM = max(MAX_EDGES_NUM, nxt);
             }
@@ -165,7 +214,7 @@ where
         Entry::Vacant(e) => {
             let id = meta.current_id;
             e.insert(id);
-            meta.current_id = (id + 1) & (EDGES_MAP_SIZE as u64 - 1);
+            meta.current_id = edges_map_index(id + 1, EDGES_MAP_SIZE) as u64;
             unsafe {
                 MAX_EDGES_NUM = meta.current_id as usize;
             }
@@ -223,10 +272,40 @@ pub fn gen_hashed_block_ids<I, QT, S>(
     Some(hash_me(pc))
 }
 
+/// The index in the edges map for the block at `pc`
+#[must_use]
+pub fn block_map_id(pc: u64) -> u64 {
+    edges_map_index(hash_me(pc), EDGES_MAP_SIZE) as u64
+}
+
+pub fn gen_filtered_block_ids<I, QT, S>(
+    _emulator: &Emulator,
+    helpers: &mut QT,
+    _state: &mut S,
+    pc: u64,
+) -> Option<u64>
+where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    if let Some(h) = helpers.match_first_type::<QemuBlockCoverageHelper>() {
+        if !h.must_instrument(pc) {
+            return None;
+        }
+    }
+    Some(block_map_id(pc))
+}
+
+pub extern "C" fn trace_block_single(id: u64) {
+    unsafe {
+        EDGES_MAP[id as usize] = 1;
+    }
+}
+
 pub extern "C" fn trace_block_transition_hitcount(id: u64) {
     unsafe {
         PREV_LOC.with(|prev_loc| {
-            let x = ((*prev_loc.get() ^ id) as usize) & (EDGES_MAP_PTR_SIZE - 1);
+            let x = edges_map_index(*prev_loc.get() ^ id, EDGES_MAP_PTR_SIZE);
             let entry = EDGES_MAP_PTR.add(x);
             *entry = (*entry).wrapping_add(1);
             *prev_loc.get() = id.overflowing_shr(1).0;
@@ -237,10 +316,36 @@ pub extern "C" fn trace_block_transition_hitcount(id: u64) {
 pub extern "C" fn trace_block_transition_single(id: u64) {
     unsafe {
         PREV_LOC.with(|prev_loc| {
-            let x = ((*prev_loc.get() ^ id) as usize) & (EDGES_MAP_PTR_SIZE - 1);
+            let x = edges_map_index(*prev_loc.get() ^ id, EDGES_MAP_PTR_SIZE);
             let entry = EDGES_MAP_PTR.add(x);
             *entry = 1;
             *prev_loc.get() = id.overflowing_shr(1).0;
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use crate::edges::{block_map_id, trace_block_single, EDGES_MAP};
+
+    #[test]
+    #[serial]
+    fn test_block_coverage_single() {
+        unsafe {
+            EDGES_MAP.iter_mut().for_each(|entry| *entry = 0);
+        }
+
+        // Blocks executed in a loop still only set one entry each
+        for pc in [0x1000, 0x1010, 0x1000, 0x2040, 0x1010, 0x1000] {
+            trace_block_single(block_map_id(pc));
+        }
+
+        let set = unsafe { EDGES_MAP.iter().filter(|&&entry| entry != 0).count() };
+        assert_eq!(set, 3);
+        unsafe {
+            assert_eq!(EDGES_MAP[block_map_id(0x1000) as usize], 1);
+        }
+    }
+}
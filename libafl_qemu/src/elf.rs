@@ -1,7 +1,7 @@
 //! Utilities to parse and process ELFs
 
 use goblin::elf::{header::ET_DYN, Elf};
-use std::{convert::AsRef, fs::File, io::Read, path::Path, str};
+use std::{convert::AsRef, fs::File, io::Read, ops::Range, path::Path, str};
 
 use libafl::Error;
 
@@ -55,6 +55,28 @@ impl<'a> EasyElf<'a> {
         None
     }
 
+    /// Resolve the address range covered by the symbol `name`, using its size in the symbol table
+    #[must_use]
+    pub fn resolve_symbol_range(&self, name: &str, load_addr: u64) -> Option<Range<u64>> {
+        for sym in self.elf.syms.iter() {
+            if let Some(sym_name) = self.elf.strtab.get_at(sym.st_name) {
+                if sym_name == name {
+                    return if sym.st_value == 0 || sym.st_size == 0 {
+                        None
+                    } else {
+                        let start = if self.is_pic() {
+                            sym.st_value + load_addr
+                        } else {
+                            sym.st_value
+                        };
+                        Some(start..start + sym.st_size)
+                    };
+                }
+            }
+        }
+        None
+    }
+
     fn is_pic(&self) -> bool {
         self.elf.header.e_type == ET_DYN
     }
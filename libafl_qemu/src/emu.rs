@@ -305,6 +305,43 @@ impl Drop for GuestMaps {
     }
 }
 
+/// Checks that the guest range `[addr, addr + len)` is covered by the `maps`, given as `(start, end, perms)`, without gaps,
+/// and that all of it is readable, or writable for a `write`.
+pub fn check_guest_range<M>(maps: M, addr: GuestAddr, len: usize, write: bool) -> Result<(), String>
+where
+    M: IntoIterator<Item = (GuestAddr, GuestAddr, MmapPerms)>,
+{
+    if len == 0 {
+        return Ok(());
+    }
+    let end = GuestAddr::try_from(len)
+        .ok()
+        .and_then(|len| addr.checked_add(len))
+        .ok_or_else(|| format!("Guest range at {:#x} of {} bytes overflows", addr, len))?;
+
+    let mut maps: Vec<_> = maps.into_iter().collect();
+    maps.sort_unstable_by_key(|(start, _, _)| *start);
+    let mut cursor = addr;
+    for (start, map_end, perms) in maps {
+        if start > cursor || map_end <= cursor {
+            continue;
+        }
+        if (write && !perms.is_w()) || (!write && !perms.is_r()) {
+            return Err(format!(
+                "Guest address {:#x} is mapped {:?}, not {}",
+                cursor,
+                perms,
+                if write { "writable" } else { "readable" }
+            ));
+        }
+        cursor = map_end;
+        if cursor >= end {
+            return Ok(());
+        }
+    }
+    Err(format!("Guest address {:#x} is not mapped", cursor))
+}
+
 static mut EMULATOR_IS_INITIALIZED: bool = false;
 
 #[derive(Debug)]
@@ -375,6 +412,32 @@ impl Emulator {
         copy_nonoverlapping(host_addr, buf.as_mut_ptr(), buf.len());
     }
 
+    /// Write a value to a guest address, after checking that the whole range is mapped and writable.
+    /// Walks the guest mappings on each call, prefer [`Emulator::write_mem`] in hot paths with known good addresses.
+    pub fn write_mem_checked(&self, addr: GuestAddr, buf: &[u8]) -> Result<(), String> {
+        check_guest_range(
+            self.mappings().map(|m| (m.start(), m.end(), m.flags())),
+            addr,
+            buf.len(),
+            true,
+        )?;
+        unsafe { self.write_mem(addr, buf) };
+        Ok(())
+    }
+
+    /// Read a value from a guest address, after checking that the whole range is mapped and readable.
+    /// Walks the guest mappings on each call, prefer [`Emulator::read_mem`] in hot paths with known good addresses.
+    pub fn read_mem_checked(&self, addr: GuestAddr, buf: &mut [u8]) -> Result<(), String> {
+        check_guest_range(
+            self.mappings().map(|m| (m.start(), m.end(), m.flags())),
+            addr,
+            buf.len(),
+            false,
+        )?;
+        unsafe { self.read_mem(addr, buf) };
+        Ok(())
+    }
+
     #[must_use]
     pub fn num_regs(&self) -> i32 {
         unsafe { libafl_qemu_num_regs() }
@@ -860,3 +923,27 @@ pub mod pybind {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::emu::{check_guest_range, MmapPerms};
+
+    #[test]
+    fn test_check_guest_range() {
+        let maps = [
+            (0x2000, 0x3000, MmapPerms::ReadWrite),
+            (0x1000, 0x2000, MmapPerms::Read),
+            (0x4000, 0x5000, MmapPerms::ReadWrite),
+        ];
+
+        // Ranges spanning adjacent maps
+        assert!(check_guest_range(maps, 0x1ff0, 0x20, false).is_ok());
+        assert!(check_guest_range(maps, 0x2ff0, 0x10, true).is_ok());
+        // Read-only memory
+        assert!(check_guest_range(maps, 0x1ff0, 0x20, true).is_err());
+        // Gaps and unmapped addresses
+        assert!(check_guest_range(maps, 0x2ff0, 0x20, false).is_err());
+        assert!(check_guest_range(maps, 0x8000, 1, false).is_err());
+        assert!(check_guest_range(maps, 0x8000, 0, false).is_ok());
+    }
+}
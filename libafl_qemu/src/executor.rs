@@ -14,10 +14,11 @@ use libafl::{
     feedbacks::Feedback,
     fuzzer::HasObjective,
     inputs::Input,
-    observers::ObserversTuple,
+    observers::{CmpMap, ObserversTuple},
     state::{HasClientPerfMonitor, HasSolutions},
     Error,
 };
+use libafl_targets::{CMPLOG_MAP, EDGES_MAP, EDGES_MAP_PTR, EDGES_MAP_PTR_SIZE};
 
 pub use crate::emu::SyscallHookResult;
 use crate::{
@@ -28,6 +29,18 @@ use crate::{
 
 static mut QEMU_HELPERS_PTR: *const c_void = ptr::null();
 
+/// Clears the edges map, the map behind `EDGES_MAP_PTR` if set, and the headers of the cmplog map,
+/// so that a run only reports its own coverage
+pub fn reset_coverage_maps() -> Result<(), Error> {
+    unsafe {
+        EDGES_MAP.fill(0);
+        if !EDGES_MAP_PTR.is_null() && EDGES_MAP_PTR != EDGES_MAP.as_mut_ptr() {
+            ptr::write_bytes(EDGES_MAP_PTR, 0, EDGES_MAP_PTR_SIZE);
+        }
+        CMPLOG_MAP.reset()
+    }
+}
+
 static mut GEN_EDGE_HOOK_PTR: *const c_void = ptr::null();
 extern "C" fn gen_edge_hook_wrapper<I, QT, S>(src: u64, dst: u64) -> u64
 where
@@ -469,6 +482,7 @@ where
     helpers: QT,
     emulator: &'a Emulator,
     inner: InProcessExecutor<'a, H, I, OT, S>,
+    reset_maps: bool,
 }
 
 impl<'a, H, I, OT, QT, S> Debug for QemuExecutor<'a, H, I, OT, QT, S>
@@ -483,6 +497,7 @@ where
             .field("helpers", &self.helpers)
             .field("emulator", &self.emulator)
             .field("inner", &self.inner)
+            .field("reset_maps", &self.reset_maps)
             .finish()
     }
 }
@@ -513,6 +528,7 @@ where
             helpers,
             emulator,
             inner: InProcessExecutor::new(harness_fn, observers, fuzzer, state, event_mgr)?,
+            reset_maps: false,
         };
         slf.helpers.init_all(&slf);
         Ok(slf)
@@ -530,6 +546,17 @@ where
         self.emulator
     }
 
+    /// Whether the coverage and cmplog maps are cleared before each run
+    pub fn reset_maps(&self) -> bool {
+        self.reset_maps
+    }
+
+    /// Sets whether the coverage and cmplog maps are cleared before each run, `false` by default.
+    /// The map observers already reset their maps before each run, enable it only for maps without an observer.
+    pub fn set_reset_maps(&mut self, reset_maps: bool) {
+        self.reset_maps = reset_maps;
+    }
+
     #[allow(clippy::unused_self)]
     pub fn hook_edge_generation(
         &self,
@@ -815,6 +842,9 @@ where
         input: &I,
     ) -> Result<ExitKind, Error> {
         unsafe { QEMU_HELPERS_PTR = addr_of!(self.helpers) as *const c_void };
+        if self.reset_maps {
+            reset_coverage_maps()?;
+        }
         self.helpers.pre_exec_all(self.emulator, input);
         let r = self.inner.run_target(fuzzer, state, mgr, input);
         self.helpers.post_exec_all(self.emulator, input);
@@ -840,3 +870,35 @@ where
         self.inner.observers_mut()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use crate::{
+        edges::{block_map_id, trace_block_single, EDGES_MAP},
+        executor::reset_coverage_maps,
+    };
+
+    #[test]
+    #[serial]
+    fn test_reset_coverage_maps() {
+        reset_coverage_maps().unwrap();
+        for pc in [0x1000, 0x1010] {
+            trace_block_single(block_map_id(pc));
+        }
+
+        // The second run must not see the edges of the first one
+        reset_coverage_maps().unwrap();
+        for pc in [0x2040, 0x2080] {
+            trace_block_single(block_map_id(pc));
+        }
+
+        unsafe {
+            assert_eq!(EDGES_MAP.iter().filter(|&&entry| entry != 0).count(), 2);
+            assert_eq!(EDGES_MAP[block_map_id(0x1000) as usize], 0);
+            assert_eq!(EDGES_MAP[block_map_id(0x1010) as usize], 0);
+            assert_eq!(EDGES_MAP[block_map_id(0x2040) as usize], 1);
+        }
+    }
+}
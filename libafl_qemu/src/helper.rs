@@ -1,9 +1,11 @@
 use core::{fmt::Debug, ops::Range};
 use libafl::{
-    bolts::tuples::MatchFirstType, executors::ExitKind, inputs::Input, observers::ObserversTuple,
+    bolts::tuples::MatchFirstType, executors::ExitKind, inputs::Input, libafl_log,
+    observers::ObserversTuple,
 };
+use std::path::Path;
 
-use crate::{emu::Emulator, executor::QemuExecutor};
+use crate::{elf::EasyElf, emu::Emulator, executor::QemuExecutor};
 
 /// A helper for `libafl_qemu`.
 // TODO remove 'static when specialization will be stable
@@ -115,6 +117,94 @@ impl QemuInstrumentationFilter {
             QemuInstrumentationFilter::None => true,
         }
     }
+
+    /// Build an allow list from symbol and module names.
+    /// Symbols are looked up in the symbol table of the emulated binary, covering the whole function.
+    /// Other names are matched against the file names of the executable mappings, e.g. `libfoo` allows `libfoo.so.1`.
+    /// Names that cannot be resolved are reported with a warning and skipped.
+    #[must_use]
+    pub fn from_symbols(emulator: &Emulator, names: &[&str]) -> Self {
+        let mut elf_buffer = Vec::new();
+        let elf = match EasyElf::from_file(emulator.binary_path(), &mut elf_buffer) {
+            Ok(elf) => Some(elf),
+            Err(e) => {
+                libafl_log!(
+                    Warn,
+                    "Cannot parse {} to resolve symbols: {:?}",
+                    emulator.binary_path(),
+                    e
+                );
+                None
+            }
+        };
+        let modules: Vec<(String, Range<u64>)> = emulator
+            .mappings()
+            .filter(|map| map.flags().is_x())
+            .filter_map(|map| {
+                map.path().map(|path| {
+                    (
+                        path.to_string(),
+                        u64::from(map.start())..u64::from(map.end()),
+                    )
+                })
+            })
+            .collect();
+
+        let (ranges, unresolved) = resolve_ranges(
+            elf.as_ref(),
+            u64::from(emulator.load_addr()),
+            &modules,
+            names,
+        );
+        for name in unresolved {
+            libafl_log!(
+                Warn,
+                "Cannot resolve {} for the instrumentation filter, skipping it",
+                name
+            );
+        }
+        QemuInstrumentationFilter::AllowList(ranges)
+    }
+}
+
+/// Resolve each name to the range of a symbol in `elf`, or else to the ranges of the `modules` with a matching file name.
+/// A file name matches if it is the name, or the name followed by a `.` or `-`, so `libfoo` matches `libfoo.so.1`, but not `libfoobar.so`.
+/// Returns the ranges and the names that could not be resolved.
+fn resolve_ranges<'a>(
+    elf: Option<&EasyElf>,
+    load_addr: u64,
+    modules: &[(String, Range<u64>)],
+    names: &[&'a str],
+) -> (Vec<Range<u64>>, Vec<&'a str>) {
+    let mut ranges = vec![];
+    let mut unresolved = vec![];
+    for name in names {
+        if let Some(range) = elf.and_then(|elf| elf.resolve_symbol_range(name, load_addr)) {
+            ranges.push(range);
+            continue;
+        }
+        let len = ranges.len();
+        for (path, range) in modules {
+            let file_name = Path::new(path)
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .unwrap_or_default();
+            if module_name_matches(file_name, name) {
+                ranges.push(range.clone());
+            }
+        }
+        if ranges.len() == len {
+            unresolved.push(*name);
+        }
+    }
+    (ranges, unresolved)
+}
+
+/// Whether the module `file_name` is `name`, or `name` followed by a version or extension
+fn module_name_matches(file_name: &str, name: &str) -> bool {
+    file_name.strip_prefix(name).map_or(false, |rest| {
+        rest.is_empty() || rest.starts_with('.') || rest.starts_with('-')
+    })
 }
 
 #[must_use]
@@ -124,3 +214,35 @@ pub fn hash_me(mut x: u64) -> u64 {
     x = (x.overflowing_shr(16).0 ^ x) ^ x;
     x
 }
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crate::{elf::EasyElf, helper::resolve_ranges};
+
+    #[test]
+    fn test_resolve_ranges() {
+        // The test binary itself has known symbols
+        let mut buffer = vec![];
+        let elf = EasyElf::from_file(env::current_exe().unwrap(), &mut buffer).unwrap();
+        let modules = vec![
+            ("/usr/lib/libfoo.so.1".to_string(), 0x1000..0x2000),
+            ("/usr/lib/libfoobar.so".to_string(), 0x3000..0x4000),
+            ("/usr/lib/libbar-2.3.so".to_string(), 0x5000..0x6000),
+        ];
+
+        let (ranges, unresolved) = resolve_ranges(
+            Some(&elf),
+            0,
+            &modules,
+            &["main", "libfoo", "libbar", "no_such_symbol"],
+        );
+        assert_eq!(unresolved, vec!["no_such_symbol"]);
+        assert_eq!(ranges.len(), 3);
+        assert_eq!(ranges[0], elf.resolve_symbol_range("main", 0).unwrap());
+        assert!(!ranges[0].is_empty());
+        assert_eq!(ranges[1], 0x1000..0x2000);
+        assert_eq!(ranges[2], 0x5000..0x6000);
+    }
+}
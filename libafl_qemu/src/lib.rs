@@ -37,11 +37,11 @@ pub use helper::*;
 #[cfg(target_os = "linux")]
 pub mod edges;
 #[cfg(target_os = "linux")]
-pub use edges::QemuEdgeCoverageHelper;
+pub use edges::{QemuBlockCoverageHelper, QemuEdgeCoverageHelper};
 #[cfg(target_os = "linux")]
 pub mod cmplog;
 #[cfg(target_os = "linux")]
-pub use cmplog::QemuCmpLogHelper;
+pub use cmplog::{QemuCmpLogHelper, QemuCmpLogRoutinesHelper};
 #[cfg(target_os = "linux")]
 pub mod snapshot;
 #[cfg(target_os = "linux")]
@@ -50,6 +50,16 @@ pub use snapshot::QemuSnapshotHelper;
 pub mod asan;
 #[cfg(target_os = "linux")]
 pub use asan::{init_with_asan, QemuAsanHelper};
+#[cfg(target_os = "linux")]
+pub mod crash;
+#[cfg(target_os = "linux")]
+pub use crash::{QemuCrashContextFeedback, QemuCrashContextMetadata, QemuCrashContextObserver};
+#[cfg(target_os = "linux")]
+pub mod syscalls;
+#[cfg(target_os = "linux")]
+pub use syscalls::{
+    QemuSyscallHelper, QemuSyscallNgramFeedback, QemuSyscallNgramFeedbackState, QemuSyscallObserver,
+};
 
 #[cfg(target_os = "linux")]
 pub mod executor;
@@ -0,0 +1,376 @@
+use hashbrown::HashSet;
+use libafl::{
+    bolts::tuples::{MatchName, Named},
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, FeedbackState},
+    inputs::Input,
+    observers::{Observer, ObserversTuple},
+    state::{HasClientPerfMonitor, HasFeedbackStates},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    emu::{Emulator, SyscallHookResult},
+    executor::QemuExecutor,
+    helper::{hash_me, QemuHelper, QemuHelperTuple},
+};
+
+/// The default maximum amount of syscalls recorded in a run
+pub const DEFAULT_MAX_SYSCALL_SEQUENCE_LEN: usize = 4096;
+
+/// The default length of the syscall n-grams rewarded by the [`QemuSyscallNgramFeedback`]
+pub const DEFAULT_SYSCALL_NGRAM_LEN: usize = 3;
+
+/// The syscall numbers of the current run, in order, recorded by the [`QemuSyscallHelper`]
+pub static mut SYSCALL_SEQUENCE: Vec<i32> = Vec::new();
+
+/// Records `sys_num` in the [`SYSCALL_SEQUENCE`], unless it already holds `max_len` syscalls
+pub fn record_syscall(sys_num: i32, max_len: usize) {
+    unsafe {
+        if SYSCALL_SEQUENCE.len() < max_len {
+            SYSCALL_SEQUENCE.push(sys_num);
+        }
+    }
+}
+
+/// A helper hooking the syscall entry, recording the sequence of syscall numbers of each run
+/// in the [`SYSCALL_SEQUENCE`], for a [`QemuSyscallObserver`].
+#[derive(Debug)]
+pub struct QemuSyscallHelper {
+    max_len: usize,
+}
+
+impl QemuSyscallHelper {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_max_len(DEFAULT_MAX_SYSCALL_SEQUENCE_LEN)
+    }
+
+    /// Records up to `max_len` syscalls in each run, the later ones are dropped
+    #[must_use]
+    pub fn with_max_len(max_len: usize) -> Self {
+        Self { max_len }
+    }
+
+    #[must_use]
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+}
+
+impl Default for QemuSyscallHelper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, S> QemuHelper<I, S> for QemuSyscallHelper
+where
+    I: Input,
+{
+    fn init<'a, H, OT, QT>(&self, executor: &QemuExecutor<'a, H, I, OT, QT, S>)
+    where
+        H: FnMut(&I) -> ExitKind,
+        OT: ObserversTuple<I, S>,
+        QT: QemuHelperTuple<I, S>,
+    {
+        executor.hook_syscalls(trace_syscall::<I, QT, S>);
+    }
+
+    fn pre_exec(&mut self, _emulator: &Emulator, _input: &I) {
+        unsafe {
+            SYSCALL_SEQUENCE.clear();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn trace_syscall<I, QT, S>(
+    _emulator: &Emulator,
+    helpers: &mut QT,
+    _state: &mut S,
+    sys_num: i32,
+    _a0: u64,
+    _a1: u64,
+    _a2: u64,
+    _a3: u64,
+    _a4: u64,
+    _a5: u64,
+    _a6: u64,
+    _a7: u64,
+) -> SyscallHookResult
+where
+    I: Input,
+    QT: QemuHelperTuple<I, S>,
+{
+    let h = helpers.match_first_type::<QemuSyscallHelper>().unwrap();
+    record_syscall(sys_num, h.max_len());
+    SyscallHookResult::new(None)
+}
+
+/// An observer holding the sequence of syscall numbers of the last run, recorded by the [`QemuSyscallHelper`]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QemuSyscallObserver {
+    name: String,
+    sequence: Vec<i32>,
+}
+
+impl<I, S> Observer<I, S> for QemuSyscallObserver {
+    fn pre_exec(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.sequence.clear();
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &I,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.sequence
+            .extend_from_slice(unsafe { &SYSCALL_SEQUENCE });
+        Ok(())
+    }
+}
+
+impl Named for QemuSyscallObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl QemuSyscallObserver {
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            sequence: vec![],
+        }
+    }
+
+    /// The syscall numbers of the last run, in order
+    #[must_use]
+    pub fn sequence(&self) -> &[i32] {
+        &self.sequence
+    }
+}
+
+/// The state of the [`QemuSyscallNgramFeedback`], holding the hashes of the syscall n-grams seen so far
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QemuSyscallNgramFeedbackState {
+    /// The hashes of the n-grams seen so far
+    pub seen: HashSet<u64>,
+    /// Name identifier of this instance, the name of the observed [`QemuSyscallObserver`]
+    pub name: String,
+}
+
+impl FeedbackState for QemuSyscallNgramFeedbackState {
+    fn reset(&mut self) -> Result<(), Error> {
+        self.seen.clear();
+        Ok(())
+    }
+}
+
+impl Named for QemuSyscallNgramFeedbackState {
+    #[inline]
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl QemuSyscallNgramFeedbackState {
+    /// Creates a new [`QemuSyscallNgramFeedbackState`] for the given [`QemuSyscallObserver`]
+    #[must_use]
+    pub fn with_observer(observer: &QemuSyscallObserver) -> Self {
+        Self {
+            seen: HashSet::new(),
+            name: observer.name().to_string(),
+        }
+    }
+
+    /// The amount of distinct n-grams seen so far
+    #[must_use]
+    pub fn seen_ngrams(&self) -> usize {
+        self.seen.len()
+    }
+}
+
+/// A feedback rewarding runs with syscall n-grams (n consecutive syscalls) never seen before,
+/// observed by a [`QemuSyscallObserver`]. Runs with less than n syscalls count as a single n-gram.
+/// The seen n-grams are kept in a [`QemuSyscallNgramFeedbackState`] in the state.
+#[derive(Debug)]
+pub struct QemuSyscallNgramFeedback {
+    observer_name: String,
+    n: usize,
+    novel: HashSet<u64>,
+}
+
+impl<I, S> Feedback<I, S> for QemuSyscallNgramFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor + HasFeedbackStates,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let observer = observers
+            .match_name::<QemuSyscallObserver>(&self.observer_name)
+            .expect("A QemuSyscallNgramFeedback needs a QemuSyscallObserver");
+        let sequence = observer.sequence();
+        let ngram_state = state
+            .feedback_states()
+            .match_name::<QemuSyscallNgramFeedbackState>(&self.observer_name)
+            .expect("A QemuSyscallNgramFeedback needs a QemuSyscallNgramFeedbackState");
+
+        self.novel.clear();
+        if !sequence.is_empty() {
+            for ngram in sequence.windows(self.n.min(sequence.len())) {
+                let hash = ngram
+                    .iter()
+                    .fold(0, |hash, &sys_num| hash_me(hash ^ sys_num as u64));
+                if !ngram_state.seen.contains(&hash) {
+                    self.novel.insert(hash);
+                }
+            }
+        }
+        Ok(!self.novel.is_empty())
+    }
+
+    fn append_metadata(&mut self, state: &mut S, _testcase: &mut Testcase<I>) -> Result<(), Error> {
+        let ngram_state = state
+            .feedback_states_mut()
+            .match_name_mut::<QemuSyscallNgramFeedbackState>(&self.observer_name)
+            .expect("A QemuSyscallNgramFeedback needs a QemuSyscallNgramFeedbackState");
+        ngram_state.seen.extend(self.novel.drain(..));
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.novel.clear();
+        Ok(())
+    }
+}
+
+impl Named for QemuSyscallNgramFeedback {
+    fn name(&self) -> &str {
+        "QemuSyscallNgramFeedback"
+    }
+}
+
+impl QemuSyscallNgramFeedback {
+    #[must_use]
+    pub fn new(feedback_state: &QemuSyscallNgramFeedbackState) -> Self {
+        Self::with_ngram_len(feedback_state, DEFAULT_SYSCALL_NGRAM_LEN)
+    }
+
+    /// Rewards novel sequences of `n` consecutive syscalls
+    #[must_use]
+    pub fn with_ngram_len(feedback_state: &QemuSyscallNgramFeedbackState, n: usize) -> Self {
+        Self {
+            observer_name: feedback_state.name().to_string(),
+            n: n.max(1),
+            novel: HashSet::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::Feedback,
+        inputs::BytesInput,
+        observers::Observer,
+        state::{HasFeedbackStates, StdState},
+    };
+
+    use crate::syscalls::{
+        record_syscall, QemuSyscallNgramFeedback, QemuSyscallNgramFeedbackState,
+        QemuSyscallObserver, SYSCALL_SEQUENCE,
+    };
+
+    // x86_64 syscall numbers
+    const SYS_READ: i32 = 0;
+    const SYS_WRITE: i32 = 1;
+    const SYS_OPEN: i32 = 2;
+    const SYS_CLOSE: i32 = 3;
+
+    /// Simulates a run of the target, doing the given syscalls
+    fn run<S>(state: &mut S, observer: &mut QemuSyscallObserver, sequence: &[i32]) {
+        let input = BytesInput::new(vec![]);
+        unsafe {
+            SYSCALL_SEQUENCE.clear();
+        }
+        Observer::<BytesInput, S>::pre_exec(observer, state, &input).unwrap();
+        for &sys_num in sequence {
+            record_syscall(sys_num, 5);
+        }
+        Observer::<BytesInput, S>::post_exec(observer, state, &input, &ExitKind::Ok).unwrap();
+    }
+
+    #[test]
+    fn test_syscall_sequence() {
+        let mut observer = QemuSyscallObserver::new("syscalls");
+        let feedback_state = QemuSyscallNgramFeedbackState::with_observer(&observer);
+        let mut feedback = QemuSyscallNgramFeedback::with_ngram_len(&feedback_state, 2);
+
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::<BytesInput>::new(),
+            tuple_list!(feedback_state),
+        );
+        let mut mgr = NopEventManager {};
+        let input = BytesInput::new(vec![]);
+
+        // The known sequence gets recorded, bounded to 5 syscalls
+        let known = [
+            SYS_OPEN, SYS_READ, SYS_READ, SYS_WRITE, SYS_CLOSE, SYS_CLOSE,
+        ];
+        run(&mut state, &mut observer, &known);
+        assert_eq!(observer.sequence(), &known[..5]);
+
+        let mut observers = tuple_list!(observer);
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+        feedback
+            .append_metadata(&mut state, &mut Testcase::new(input.clone()))
+            .unwrap();
+        assert_eq!(state.feedback_states().0.seen_ngrams(), 4);
+
+        // The seen n-grams live in the state, a new feedback (as after a restart) knows them
+        let mut feedback = QemuSyscallNgramFeedback::with_ngram_len(&state.feedback_states().0, 2);
+
+        // Same syscalls in the same order, nothing new
+        run(
+            &mut state,
+            &mut observers.0,
+            &[SYS_OPEN, SYS_READ, SYS_WRITE],
+        );
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+
+        // A novel pair of syscalls
+        run(&mut state, &mut observers.0, &[SYS_OPEN, SYS_WRITE]);
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+    }
+}
@@ -7,7 +7,6 @@ use typed_builder::TypedBuilder;
 
 use libafl::{
     bolts::{
-        current_nanos,
         launcher::Launcher,
         os::Cores,
         rands::StdRand,
@@ -142,7 +141,8 @@ where
 
         let mut run_client = |state: Option<StdState<_, _, _, _, _>>,
                               mut mgr: LlmpRestartingEventManager<_, _, _, _>,
-                              _core_id| {
+                              _core_id,
+                              seed| {
             // Create an observation channel using the coverage map
             let edges = unsafe { &mut EDGES_MAP[0..MAX_EDGES_NUM] };
             let edges_observer = HitcountsMapObserver::new(StdMapObserver::new("edges", edges));
@@ -172,7 +172,7 @@ where
             let mut state = state.unwrap_or_else(|| {
                 StdState::new(
                     // RNG
-                    StdRand::with_seed(current_nanos()),
+                    StdRand::with_seed(seed),
                     // Corpus that will be evolved, we keep a part in memory for performance
                     CachedOnDiskCorpus::new(out_dir.clone(), CORPUS_CACHE_SIZE).unwrap(),
                     // Corpus in which we store solutions (crashes in this example),
@@ -30,9 +30,14 @@ pub const CMPLOG_KIND_INS: u8 = 0;
 pub const CMPLOG_KIND_RTN: u8 = 1;
 
 // void __libafl_targets_cmplog_instructions(uintptr_t k, uint8_t shape, uint64_t arg1, uint64_t arg2)
+// void __libafl_targets_cmplog_routines(uintptr_t k, uint8_t *ptr1, uint8_t *ptr2)
 extern "C" {
     /// Logs an instruction for feedback during fuzzing
     pub fn __libafl_targets_cmplog_instructions(k: usize, shape: u8, arg1: u64, arg2: u64);
+
+    /// Logs the first [`CMPLOG_RTN_LEN`] bytes of the buffers compared by a routine, such as `strcmp` or `memcmp`,
+    /// skipping buffers that are not readable
+    pub fn __libafl_targets_cmplog_routines(k: usize, ptr1: *const u8, ptr2: *const u8);
 }
 
 /// The header for `CmpLog` hits.
@@ -2,7 +2,13 @@
 
 use crate::{ACCOUNTING_MAP_SIZE, EDGES_MAP_SIZE};
 #[cfg(target_os = "linux")]
-use libafl::{mutators::Tokens, Error};
+use libafl::mutators::Tokens;
+use libafl::Error;
+#[cfg(all(feature = "std", unix, feature = "pointer_maps"))]
+use libafl::{
+    bolts::shmem::{ShMem, ShMemProvider, StdShMemProvider},
+    executors::command::COVERAGE_MAP_SHM_ENV,
+};
 
 /// The map for edges.
 #[no_mangle]
@@ -62,6 +68,43 @@ pub fn autotokens() -> Result<Tokens, Error> {
 pub static mut __afl_map_size: usize = EDGES_MAP_SIZE;
 pub use __afl_map_size as EDGES_MAP_PTR_SIZE;
 use libafl::bolts::ownedref::OwnedSliceMut;
+#[cfg(feature = "edges_dirty_list")]
+use libafl::observers::DirtyMapObserver;
+#[cfg(feature = "sancov_pcguard_timing")]
+use libafl::observers::{EdgeTimingCursor, EdgeTimingObserver};
+#[cfg(feature = "sancov_pcguard_loop_counters")]
+use libafl::observers::{ExtendedHitcountsMapObserver, StdMapObserver};
+#[cfg(feature = "edges_dirty_list")]
+use serde::Serialize;
+
+/// Maps an id, such as a hash or a running edge number, to an index in a map of `map_size` entries.
+/// Masks the id for maps with a power-of-two size, and takes the modulo for all other sizes,
+/// so that maps of any size are indexed in bounds.
+///
+/// # Panics
+/// Panics if `map_size` is `0`.
+#[inline]
+#[must_use]
+pub fn edges_map_index(id: u64, map_size: usize) -> usize {
+    if map_size.is_power_of_two() {
+        (id & (map_size as u64 - 1)) as usize
+    } else {
+        (id % map_size as u64) as usize
+    }
+}
+
+/// Checks that the `edges_num` edges registered by the target fit into an edges map of `map_size` entries,
+/// so that each edge has its own entry, in bounds.
+pub fn edges_map_fits(edges_num: usize, map_size: usize) -> Result<(), Error> {
+    if edges_num > map_size {
+        Err(Error::IllegalState(format!(
+            "The target registered {} edges, more than the {} entries of the edges map. Use the LIBAFL_EDGES_MAP_SIZE env to increase it at compile time.",
+            edges_num, map_size
+        )))
+    } else {
+        Ok(())
+    }
+}
 
 /// Gets the edges map from the `EDGES_MAP_PTR` raw pointer.
 ///
@@ -75,6 +118,35 @@ pub unsafe fn edges_map_from_ptr<'a>() -> OwnedSliceMut<'a, u8> {
     OwnedSliceMut::from_raw_parts_mut(EDGES_MAP_PTR, EDGES_MAP_PTR_SIZE)
 }
 
+/// Points the [`EDGES_MAP_PTR`] to the shared coverage map passed to this process by a `CommandExecutor`,
+/// in the `COVERAGE_MAP_SHM_ENV` env var, so that the fuzzer observes the coverage of each run.
+/// Needs the `pointer_maps` feature, the runtime only writes to the [`EDGES_MAP_PTR`] with it.
+/// Returns `false`, keeping the local map, if no shared map was passed.
+/// Fails if the edges registered by the target so far do not fit into the shared map.
+#[cfg(all(feature = "std", unix, feature = "pointer_maps"))]
+pub fn edges_map_from_env() -> Result<bool, Error> {
+    if std::env::var_os(COVERAGE_MAP_SHM_ENV).is_none() {
+        return Ok(false);
+    }
+    let mut shmem = StdShMemProvider::new()?.existing_from_env(COVERAGE_MAP_SHM_ENV)?;
+    // The edges got their indexes for the local map, at init, wrapping around at its size.
+    // Edges registered from now on get their indexes for the shared map.
+    unsafe {
+        if !EDGES_MAP_PTR.is_null() && EDGES_MAP_PTR_SIZE > shmem.len() {
+            return Err(Error::IllegalState(format!(
+                "The edges of the target are indexed for a map of {} entries, more than the {} entries of the shared map",
+                EDGES_MAP_PTR_SIZE,
+                shmem.len()
+            )));
+        }
+        EDGES_MAP_PTR = shmem.as_mut_slice().as_mut_ptr();
+        EDGES_MAP_PTR_SIZE = shmem.len();
+    }
+    // The map stays mapped for the lifetime of the process
+    core::mem::forget(shmem);
+    Ok(true)
+}
+
 /// Gets the current maximum number of edges tracked.
 #[must_use]
 pub fn edges_max_num() -> usize {
@@ -93,3 +165,164 @@ pub fn edges_max_num() -> usize {
         }
     }
 }
+
+/// The size of the list of edges touched in the current run, see [`EDGES_DIRTY_LIST`].
+#[cfg(feature = "edges_dirty_list")]
+pub const EDGES_DIRTY_LIST_SIZE: usize = 4096;
+
+/// The indexes of the edges touched in the current run, to only reset those in the edges map.
+#[cfg(feature = "edges_dirty_list")]
+pub static mut EDGES_DIRTY_LIST: [usize; EDGES_DIRTY_LIST_SIZE] = [0; EDGES_DIRTY_LIST_SIZE];
+
+/// The number of edges touched in the current run, can be bigger than [`EDGES_DIRTY_LIST_SIZE`] on overflow.
+#[cfg(feature = "edges_dirty_list")]
+pub static mut EDGES_DIRTY_NUM: usize = 0;
+
+/// Records the edge at `pos` as touched in the current run.
+/// Call it only when the edge gets hit for the first time, i.e. its entry in the map is still 0.
+///
+/// # Safety
+/// Writes to the global dirty list, not thread safe.
+#[cfg(feature = "edges_dirty_list")]
+#[inline]
+pub unsafe fn edges_mark_dirty(pos: usize) {
+    if EDGES_DIRTY_NUM < EDGES_DIRTY_LIST_SIZE {
+        *EDGES_DIRTY_LIST.get_unchecked_mut(EDGES_DIRTY_NUM) = pos;
+    }
+    EDGES_DIRTY_NUM = EDGES_DIRTY_NUM.saturating_add(1);
+}
+
+/// Wraps the observer of the edges map in a [`DirtyMapObserver`], resetting only the edges touched in the last run.
+///
+/// # Safety
+/// Accesses the global dirty list, which is only filled if the target was instrumented with `sancov_pcguard`.
+#[cfg(feature = "edges_dirty_list")]
+pub unsafe fn edges_dirty_observer<M>(base: M) -> DirtyMapObserver<'static, M>
+where
+    M: Serialize + serde::de::DeserializeOwned,
+{
+    DirtyMapObserver::from_raw_parts(
+        base,
+        EDGES_DIRTY_LIST.as_mut_ptr(),
+        EDGES_DIRTY_LIST_SIZE,
+        &mut EDGES_DIRTY_NUM,
+    )
+}
+
+/// The time spent after each edge in the current run, filled with the `sancov_pcguard_timing` feature.
+#[cfg(feature = "sancov_pcguard_timing")]
+pub static mut EDGES_TIME_MAP: [u64; EDGES_MAP_SIZE] = [0; EDGES_MAP_SIZE];
+
+/// The last edge transition in the current run, see [`EDGES_TIME_MAP`].
+#[cfg(feature = "sancov_pcguard_timing")]
+pub static mut EDGES_TIMING_CURSOR: EdgeTimingCursor = EdgeTimingCursor {
+    last_edge: 0,
+    last_time: 0,
+};
+
+/// Creates an [`EdgeTimingObserver`] for the [`EDGES_TIME_MAP`], to find the edges dominating the runtime.
+///
+/// # Safety
+/// Accesses the global timing map, which is only filled if the target was instrumented with `sancov_pcguard`.
+#[cfg(feature = "sancov_pcguard_timing")]
+pub unsafe fn edges_timing_observer(name: &str) -> EdgeTimingObserver<'static> {
+    EdgeTimingObserver::from_raw_parts(
+        name,
+        EDGES_TIME_MAP.as_mut_ptr(),
+        edges_max_num().min(EDGES_MAP_SIZE),
+        &mut EDGES_TIMING_CURSOR,
+    )
+}
+
+/// The amount of edges that can get extended 16-bit counters, see [`edges_flag_loop_headers`].
+#[cfg(feature = "sancov_pcguard_loop_counters")]
+pub const EDGES_LOOP_COUNTERS_SIZE: usize = 64;
+
+/// The extended 16-bit counters of the edges flagged as loop headers, filled with the `sancov_pcguard_loop_counters` feature.
+#[cfg(feature = "sancov_pcguard_loop_counters")]
+pub static mut EDGES_LOOP_COUNTERS: [u16; EDGES_LOOP_COUNTERS_SIZE] = [0; EDGES_LOOP_COUNTERS_SIZE];
+
+/// For each edge, the index of its extended counter plus one, or `0` if it has none.
+#[cfg(feature = "sancov_pcguard_loop_counters")]
+static mut EDGES_LOOP_SLOTS: [u8; EDGES_MAP_SIZE] = [0; EDGES_MAP_SIZE];
+
+/// Flags the edges at the given map indexes as loop headers, counting their hits in [`EDGES_LOOP_COUNTERS`],
+/// in this order, in addition to the edges map.
+/// Replaces the previously flagged edges.
+///
+/// # Safety
+/// Writes to the global slot table, not thread safe. Do not call while the target runs.
+#[cfg(feature = "sancov_pcguard_loop_counters")]
+pub unsafe fn edges_flag_loop_headers(edges: &[usize]) -> Result<(), Error> {
+    if edges.len() > EDGES_LOOP_COUNTERS_SIZE {
+        return Err(Error::IllegalArgument(format!(
+            "Only {} edges can have extended counters, got {}",
+            EDGES_LOOP_COUNTERS_SIZE,
+            edges.len()
+        )));
+    }
+    if let Some(edge) = edges.iter().find(|&&edge| edge >= EDGES_MAP_SIZE) {
+        return Err(Error::IllegalArgument(format!(
+            "Edge {} is out of the edges map ({})",
+            edge, EDGES_MAP_SIZE
+        )));
+    }
+    EDGES_LOOP_SLOTS.fill(0);
+    for (slot, &edge) in edges.iter().enumerate() {
+        EDGES_LOOP_SLOTS[edge] = (slot + 1) as u8;
+    }
+    Ok(())
+}
+
+/// Counts a hit of the edge at `pos` in its extended counter, if it was flagged as loop header.
+///
+/// # Safety
+/// Writes to the global counters, not thread safe.
+#[cfg(feature = "sancov_pcguard_loop_counters")]
+#[inline]
+pub unsafe fn edges_count_loop(pos: usize) {
+    if let Some(&slot) = EDGES_LOOP_SLOTS.get(pos) {
+        if slot != 0 {
+            let counter = EDGES_LOOP_COUNTERS.get_unchecked_mut(slot as usize - 1);
+            *counter = counter.saturating_add(1);
+        }
+    }
+}
+
+/// Creates an [`ExtendedHitcountsMapObserver`] for the [`EDGES_LOOP_COUNTERS`],
+/// to use with a `MaxMapFeedback` next to the one for the edges map.
+///
+/// # Safety
+/// Accesses the global counters, which are only filled if the target was instrumented with `sancov_pcguard`.
+#[cfg(feature = "sancov_pcguard_loop_counters")]
+pub unsafe fn edges_loop_counters_observer(
+    name: &'static str,
+) -> ExtendedHitcountsMapObserver<StdMapObserver<'static, u16>> {
+    ExtendedHitcountsMapObserver::new(StdMapObserver::new(name, &mut EDGES_LOOP_COUNTERS))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coverage::{edges_map_fits, edges_map_index};
+
+    #[test]
+    fn test_edges_map_index() {
+        // Not a power of two, masking with `size - 1` would leave out entries, or reach past the end
+        let size = 48000;
+        let mut map = vec![0_u8; size];
+        for id in (0..4 * size as u64).chain([u64::MAX - 1, u64::MAX]) {
+            map[edges_map_index(id, size)] += 1;
+        }
+        assert!(map.iter().all(|&hits| hits >= 4));
+        assert_eq!(edges_map_index(47999, size), 47999);
+        assert_eq!(edges_map_index(48000, size), 0);
+        assert_eq!(edges_map_index(48001, size), 1);
+
+        // Power-of-two sizes still mask
+        assert_eq!(edges_map_index(0x1_0001, 0x1_0000), 1);
+        assert_eq!(edges_map_index(u64::MAX, 0x1_0000), 0xffff);
+
+        assert!(edges_map_fits(48000, size).is_ok());
+        assert!(edges_map_fits(48001, size).is_err());
+    }
+}
@@ -1,8 +1,16 @@
 //! [`LLVM` `PcGuard`](https://clang.llvm.org/docs/SanitizerCoverage.html#tracing-pcs-with-guards) runtime for `LibAFL`.
 
-use crate::coverage::{EDGES_MAP, MAX_EDGES_NUM};
+#[cfg(feature = "sancov_pcguard_loop_counters")]
+use crate::coverage::edges_count_loop;
+#[cfg(not(feature = "pointer_maps"))]
+use crate::coverage::edges_map_fits;
+#[cfg(feature = "edges_dirty_list")]
+use crate::coverage::edges_mark_dirty;
 #[cfg(feature = "pointer_maps")]
-use crate::coverage::{EDGES_MAP_PTR, EDGES_MAP_PTR_SIZE};
+use crate::coverage::{edges_map_index, EDGES_MAP_PTR, EDGES_MAP_PTR_SIZE};
+use crate::coverage::{EDGES_MAP, MAX_EDGES_NUM};
+#[cfg(feature = "sancov_pcguard_timing")]
+use crate::coverage::{EDGES_TIME_MAP, EDGES_TIMING_CURSOR};
 
 #[cfg(all(feature = "sancov_pcguard_edges", feature = "sancov_pcguard_hitcounts"))]
 #[cfg(not(any(doc, feature = "clippy")))]
@@ -18,6 +26,18 @@ compile_error!(
 #[no_mangle]
 pub unsafe extern "C" fn __sanitizer_cov_trace_pc_guard(guard: *mut u32) {
     let pos = *guard as usize;
+    #[cfg(feature = "sancov_pcguard_timing")]
+    EDGES_TIMING_CURSOR.transition(&mut EDGES_TIME_MAP, pos);
+    #[cfg(feature = "sancov_pcguard_loop_counters")]
+    edges_count_loop(pos);
+    #[cfg(all(feature = "edges_dirty_list", feature = "pointer_maps"))]
+    if (EDGES_MAP_PTR as *mut u8).add(pos).read() == 0 {
+        edges_mark_dirty(pos);
+    }
+    #[cfg(all(feature = "edges_dirty_list", not(feature = "pointer_maps")))]
+    if *EDGES_MAP.get_unchecked(pos) == 0 {
+        edges_mark_dirty(pos);
+    }
     #[cfg(feature = "pointer_maps")]
     {
         #[cfg(feature = "sancov_pcguard_edges")]
@@ -67,12 +87,14 @@ pub unsafe extern "C" fn __sanitizer_cov_trace_pc_guard_init(mut start: *mut u32
 
         #[cfg(feature = "pointer_maps")]
         {
-            MAX_EDGES_NUM = MAX_EDGES_NUM.wrapping_add(1) % EDGES_MAP_PTR_SIZE;
+            MAX_EDGES_NUM = edges_map_index(MAX_EDGES_NUM as u64 + 1, EDGES_MAP_PTR_SIZE);
         }
         #[cfg(not(feature = "pointer_maps"))]
         {
             MAX_EDGES_NUM = MAX_EDGES_NUM.wrapping_add(1);
-            assert!((MAX_EDGES_NUM <= EDGES_MAP.len()), "The number of edges reported by SanitizerCoverage exceed the size of the edges map ({}). Use the LIBAFL_EDGES_MAP_SIZE env to increase it at compile time.", EDGES_MAP.len());
+            if let Err(err) = edges_map_fits(MAX_EDGES_NUM, EDGES_MAP.len()) {
+                panic!("{}", err);
+            }
         }
     }
 }